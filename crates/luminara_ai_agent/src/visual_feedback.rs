@@ -3,7 +3,8 @@
 
 use luminara_core::world::World;
 use luminara_math::Transform;
-use image::{ImageBuffer, Rgb, RgbImage, ImageFormat as ImgFormat};
+use image::{DynamicImage, ImageBuffer, Rgb, RgbImage, ImageFormat as ImgFormat};
+use luminara_render::GpuContext;
 use std::io::Cursor;
 use std::time::Instant;
 
@@ -131,8 +132,20 @@ impl VisualFeedbackSystem {
         }
     }
 
-    /// Render scene to RGB buffer (mock implementation for headless environment)
+    /// Render scene to RGB buffer through the world's `GpuContext`, which
+    /// may be headless
     fn render_scene(&self, world: &World, width: u32, height: u32) -> Vec<u8> {
+        if world.get_resource::<GpuContext>().is_some() {
+            if let Ok(image) = luminara_render::capture_world(world, width, height) {
+                return DynamicImage::ImageRgba8(image).into_rgb8().into_raw();
+            }
+        }
+        self.render_top_down(world, width, height)
+    }
+
+    /// Top-down dot preview of entity positions, for worlds without a GPU or
+    /// when the capture fails
+    fn render_top_down(&self, world: &World, width: u32, height: u32) -> Vec<u8> {
         let mut buffer = vec![0u8; (width * height * 3) as usize];
         
         // Clear to dark blue background
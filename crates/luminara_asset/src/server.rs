@@ -93,10 +93,13 @@ struct AssetEntry {
     generation: u32,
 }
 
+/// Loaders by extension; several asset types may share one extension
+type LoaderMap = HashMap<String, Vec<Arc<dyn ErasedAssetLoader>>>;
+
 pub struct AssetServer {
     asset_dir: PathBuf,
-    handle_allocator: HandleAllocator,
-    loaders: Arc<RwLock<HashMap<String, Arc<dyn ErasedAssetLoader>>>>,
+    handle_allocator: Arc<HandleAllocator>,
+    loaders: Arc<RwLock<LoaderMap>>,
     load_states: Arc<RwLock<HashMap<AssetId, LoadState>>>,
     assets: Arc<RwLock<HashMap<AssetId, AssetEntry>>>,
     fallbacks: Arc<RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>>,
@@ -117,6 +120,29 @@ pub struct AssetServer {
     retry_config: RetryConfig,
 }
 
+/// Clones share assets, loaders and the loading threads, e.g. to read assets
+/// from a render world on another thread. Only one of them should call
+/// [`AssetServer::update`], which consumes finished loads.
+impl Clone for AssetServer {
+    fn clone(&self) -> Self {
+        Self {
+            asset_dir: self.asset_dir.clone(),
+            handle_allocator: Arc::clone(&self.handle_allocator),
+            loaders: Arc::clone(&self.loaders),
+            load_states: Arc::clone(&self.load_states),
+            assets: Arc::clone(&self.assets),
+            fallbacks: Arc::clone(&self.fallbacks),
+            placeholders: Arc::clone(&self.placeholders),
+            load_request_tx: self.load_request_tx.clone(),
+            load_result_rx: self.load_result_rx.clone(),
+            runtime: Arc::clone(&self.runtime),
+            thread_count: self.thread_count,
+            sequence_counter: Arc::clone(&self.sequence_counter),
+            retry_config: self.retry_config.clone(),
+        }
+    }
+}
+
 struct LoadRequest {
     path: PathBuf,
     id: AssetId,
@@ -334,7 +360,7 @@ impl AssetServer {
 
         Self {
             asset_dir: asset_dir.into(),
-            handle_allocator: HandleAllocator::new(),
+            handle_allocator: Arc::new(HandleAllocator::new()),
             loaders: Arc::new(RwLock::new(HashMap::new())),
             load_states: Arc::new(RwLock::new(HashMap::new())),
             assets: Arc::new(RwLock::new(HashMap::new())),
@@ -424,15 +450,9 @@ impl AssetServer {
         let full_path = self.asset_dir.join(path);
 
         // Find loader
-        let extension = path_obj
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| s.to_string())
-            .unwrap_or_default();
-
-        let loader = {
+        let (extension, loader) = {
             let loaders = self.loaders.read().unwrap();
-            loaders.get(&extension).cloned()
+            find_loader(&loaders, path_obj, Some(TypeId::of::<T>()))
         };
 
         if let Some(loader) = loader {
@@ -535,16 +555,21 @@ impl AssetServer {
         &self,
         path: &Path,
     ) -> Result<Arc<dyn Any + Send + Sync>, AssetLoadError> {
-        let extension = path
-            .extension()
-            .and_then(|s| s.to_str())
-            .ok_or_else(|| AssetLoadError::UnsupportedFormat("No extension".to_string()))?;
+        if path.extension().is_none() {
+            return Err(AssetLoadError::UnsupportedFormat("No extension".to_string()));
+        }
 
         let loader = {
             let loaders = self.loaders.read().unwrap();
-            loaders.get(extension).cloned().ok_or_else(|| {
-                AssetLoadError::UnsupportedFormat(format!("No loader for extension {}", extension))
-            })?
+            match find_loader(&loaders, path, None) {
+                (_, Some(loader)) => loader,
+                (extension, None) => {
+                    return Err(AssetLoadError::UnsupportedFormat(format!(
+                        "No loader for extension {}",
+                        extension
+                    )))
+                }
+            }
         };
 
         let bytes = std::fs::read(path)?;
@@ -575,16 +600,15 @@ impl AssetServer {
                         }
                     };
 
-                    // Find loader
-                    let extension = path
-                        .extension()
-                        .and_then(|s| s.to_str())
-                        .map(|s| s.to_string())
-                        .unwrap_or_default();
-
-                    let loader = {
+                    // Find loader, keeping the type of the asset being replaced
+                    let asset_type = assets
+                        .read()
+                        .unwrap()
+                        .get(&id)
+                        .map(|entry| Any::type_id(&*entry.asset));
+                    let (_, loader) = {
                         let loaders = loaders.read().unwrap();
-                        loaders.get(&extension).cloned()
+                        find_loader(&loaders, &path, asset_type)
                     };
 
                     if let Some(loader) = loader {
@@ -648,7 +672,11 @@ impl AssetServer {
         let erased = Arc::new(LoaderWrapper { loader });
         let mut loaders = self.loaders.write().unwrap();
         for ext in erased.extensions() {
-            loaders.insert(ext.to_string(), erased.clone());
+            // Several asset types may share an extension; a later loader
+            // replaces an earlier one only for the same asset type
+            let registered = loaders.entry(ext.to_string()).or_default();
+            registered.retain(|loader| loader.asset_type() != erased.asset_type());
+            registered.push(erased.clone());
         }
     }
 
@@ -685,14 +713,20 @@ impl AssetServer {
     }
 
     pub fn add<T: Asset>(&self, asset: T) -> Handle<T> {
-        let id = AssetId::new();
+        self.insert(AssetId::new(), asset)
+    }
+
+    /// Store an asset under a known id, e.g. a built-in shader referenced by
+    /// a fixed handle. Replacing an existing asset bumps its generation.
+    pub fn insert<T: Asset>(&self, id: AssetId, asset: T) -> Handle<T> {
         let mut assets = self.assets.write().unwrap();
+        let generation = assets.get(&id).map_or(0, |entry| entry.generation + 1);
 
         assets.insert(
             id,
             AssetEntry {
                 asset: Arc::new(asset),
-                generation: 0,
+                generation,
             },
         );
 
@@ -701,7 +735,7 @@ impl AssetServer {
             .unwrap()
             .insert(id, LoadState::Loaded);
 
-        Handle::new(id, 0)
+        Handle::new(id, generation)
     }
 
     /// Get the tokio runtime for spawning async tasks
@@ -764,8 +798,46 @@ fn is_transient_parse_error(error: &AssetLoadError) -> bool {
 
 impl Resource for AssetServer {}
 
+/// Pick the loader for a path, preferring the longest registered compound
+/// extension so `stone.material.ron` can load differently from `level.ron`.
+/// When several loaders share that extension, the one producing
+/// `asset_type` wins. Returns the matched extension, or the plain one when
+/// nothing matches.
+fn find_loader(
+    loaders: &LoaderMap,
+    path: &Path,
+    asset_type: Option<TypeId>,
+) -> (String, Option<Arc<dyn ErasedAssetLoader>>) {
+    let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+    let suffixes = file_name
+        .match_indices('.')
+        .map(|(index, _)| &file_name[index + 1..])
+        .filter(|suffix| !suffix.is_empty());
+
+    for extension in suffixes {
+        let Some(registered) = loaders.get(extension) else {
+            continue;
+        };
+        let loader = registered
+            .iter()
+            .find(|loader| Some(loader.asset_type()) == asset_type)
+            .or_else(|| registered.first());
+        if let Some(loader) = loader {
+            return (extension.to_string(), Some(loader.clone()));
+        }
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    (extension, None)
+}
+
 trait ErasedAssetLoader: Send + Sync {
     fn extensions(&self) -> &[&str];
+    fn asset_type(&self) -> TypeId;
     fn load(&self, bytes: &[u8], path: &Path)
         -> Result<Arc<dyn Any + Send + Sync>, AssetLoadError>;
 }
@@ -778,6 +850,9 @@ impl<L: AssetLoader> ErasedAssetLoader for LoaderWrapper<L> {
     fn extensions(&self) -> &[&str] {
         self.loader.extensions()
     }
+    fn asset_type(&self) -> TypeId {
+        TypeId::of::<L::Asset>()
+    }
     fn load(
         &self,
         bytes: &[u8],
@@ -141,3 +141,97 @@ fn test_multiple_extensions() {
         _ => {}
     }
 }
+
+#[test]
+fn test_compound_extension_prefers_longest_match() {
+    struct NoteLoader;
+    impl AssetLoader for NoteLoader {
+        type Asset = MyAsset;
+        fn extensions(&self) -> &[&str] {
+            &["note.txt"]
+        }
+        fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+            Ok(MyAsset {
+                data: format!("note: {}", String::from_utf8_lossy(bytes)),
+            })
+        }
+    }
+
+    let test_dir = std::env::temp_dir().join("luminara_test_compound_ext");
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("todo.note.txt"), "milk").unwrap();
+    fs::write(test_dir.join("plain.txt"), "text").unwrap();
+
+    let mut server = AssetServer::new(&test_dir);
+    server.register_loader(MyLoader);
+    server.register_loader(NoteLoader);
+
+    let note: Handle<MyAsset> = server.load("todo.note.txt");
+    let plain: Handle<MyAsset> = server.load("plain.txt");
+
+    let start = std::time::Instant::now();
+    while server.load_state(note.id()) == LoadState::Loading
+        || server.load_state(plain.id()) == LoadState::Loading
+    {
+        server.update();
+        if start.elapsed() > std::time::Duration::from_secs(2) {
+            panic!("Timeout waiting for asset load");
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    assert_eq!(server.get(&note).unwrap().data, "note: milk");
+    assert_eq!(server.get(&plain).unwrap().data, "text");
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
+
+#[test]
+fn test_shared_extension_picks_loader_by_type() {
+    #[derive(Debug)]
+    struct LengthAsset(usize);
+    impl Asset for LengthAsset {
+        fn type_name() -> &'static str {
+            "LengthAsset"
+        }
+    }
+
+    struct LengthLoader;
+    impl AssetLoader for LengthLoader {
+        type Asset = LengthAsset;
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+        fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+            Ok(LengthAsset(bytes.len()))
+        }
+    }
+
+    let test_dir = std::env::temp_dir().join("luminara_test_shared_ext");
+    fs::create_dir_all(&test_dir).unwrap();
+    fs::write(test_dir.join("a.txt"), "four").unwrap();
+    fs::write(test_dir.join("b.txt"), "seven!!").unwrap();
+
+    let mut server = AssetServer::new(&test_dir);
+    server.register_loader(MyLoader);
+    server.register_loader(LengthLoader);
+
+    let text: Handle<MyAsset> = server.load("a.txt");
+    let length: Handle<LengthAsset> = server.load("b.txt");
+
+    let start = std::time::Instant::now();
+    while server.load_state(text.id()) == LoadState::Loading
+        || server.load_state(length.id()) == LoadState::Loading
+    {
+        server.update();
+        if start.elapsed() > std::time::Duration::from_secs(2) {
+            panic!("Timeout waiting for asset load");
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    assert_eq!(server.get(&text).unwrap().data, "four");
+    assert_eq!(server.get(&length).unwrap().0, 7);
+
+    fs::remove_dir_all(&test_dir).unwrap();
+}
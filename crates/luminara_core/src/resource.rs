@@ -4,11 +4,13 @@ use parking_lot::{
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 pub trait Resource: Send + Sync + 'static {}
 
 pub struct ResourceMap {
-    pub(crate) resources: HashMap<TypeId, RwLock<Box<dyn Any + Send + Sync>>>,
+    /// Values are reference counted so several maps can share a resource
+    pub(crate) resources: HashMap<TypeId, Arc<RwLock<Box<dyn Any + Send + Sync>>>>,
 }
 
 impl Default for ResourceMap {
@@ -26,7 +28,17 @@ impl ResourceMap {
 
     pub fn insert<R: Resource>(&mut self, resource: R) {
         self.resources
-            .insert(TypeId::of::<R>(), RwLock::new(Box::new(resource)));
+            .insert(TypeId::of::<R>(), Arc::new(RwLock::new(Box::new(resource))));
+    }
+
+    /// Make `other` hold the same `R` as this map. Returns false when this map
+    /// has no `R`.
+    pub fn share<R: Resource>(&self, other: &mut ResourceMap) -> bool {
+        let Some(lock) = self.resources.get(&TypeId::of::<R>()) else {
+            return false;
+        };
+        other.resources.insert(TypeId::of::<R>(), Arc::clone(lock));
+        true
     }
 
     pub fn get<R: Resource>(&self) -> Option<MappedRwLockReadGuard<'_, R>> {
@@ -41,10 +53,41 @@ impl ResourceMap {
         })
     }
 
-    pub fn remove<R: Resource>(&mut self) -> Option<R> {
+    /// Whether another map holds this map's `R` too
+    pub fn is_shared<R: Resource>(&self) -> bool {
         self.resources
-            .remove(&TypeId::of::<R>())
-            .map(|lock| *lock.into_inner().downcast::<R>().unwrap())
+            .get(&TypeId::of::<R>())
+            .is_some_and(|lock| Arc::strong_count(lock) > 1)
+    }
+
+    /// Take `R` out of the map, or `None` when the map has no `R`
+    ///
+    /// # Panics
+    /// When another map still holds `R`; [`ResourceMap::unshare`] lets go of
+    /// this map's handle instead.
+    pub fn remove<R: Resource>(&mut self) -> Option<R> {
+        let type_id = TypeId::of::<R>();
+        let lock = self.resources.remove(&type_id)?;
+        match Arc::try_unwrap(lock) {
+            Ok(lock) => Some(*lock.into_inner().downcast::<R>().unwrap()),
+            Err(shared) => {
+                self.resources.insert(type_id, shared);
+                panic!(
+                    "can't remove {}: it is shared with another map",
+                    std::any::type_name::<R>()
+                );
+            }
+        }
+    }
+
+    /// Drop this map's handle to a shared `R`, leaving the value with the
+    /// other maps. Returns false, keeping `R`, when it isn't shared.
+    pub fn unshare<R: Resource>(&mut self) -> bool {
+        if !self.is_shared::<R>() {
+            return false;
+        }
+        self.resources.remove(&TypeId::of::<R>());
+        true
     }
 }
 
@@ -298,10 +298,12 @@ impl World {
     pub fn despawn(&mut self, entity: Entity) -> bool {
         if let Some((archetype_id, index)) = self.archetypes.get_entity_location(entity) {
             let archetype = self.archetypes.get_archetype_mut(archetype_id).unwrap();
-            let swapped_entity = archetype.swap_remove(index);
-            if swapped_entity != entity {
+            archetype.swap_remove(index);
+            // The last entity of the archetype moved into the freed slot
+            let swapped_entity = archetype.entities().get(index).copied();
+            if let Some(swapped) = swapped_entity {
                 self.archetypes
-                    .set_entity_location(swapped_entity, archetype_id, index);
+                    .set_entity_location(swapped, archetype_id, index);
             }
             self.archetypes.remove_entity_location(entity);
             self.entities.despawn(entity);
@@ -323,17 +325,50 @@ impl World {
         self.resources.get_mut::<R>()
     }
 
+    /// Take `R` out of the world
+    ///
+    /// # Panics
+    /// When `R` is shared with another world; see [`World::unshare_resource`].
     pub fn remove_resource<R: Resource>(&mut self) -> Option<R> {
         self.resources.remove::<R>()
     }
 
+    /// Make `other` hold this world's `R` too. Both worlds then lock the same
+    /// value, so it can be used from systems of either one, even on another
+    /// thread. Returns false when this world has no `R`.
+    pub fn share_resource<R: Resource>(&self, other: &mut World) -> bool {
+        self.resources.share::<R>(&mut other.resources)
+    }
+
+    /// Whether another world holds this world's `R` too, in which case it
+    /// can't be removed, only unshared
+    pub fn is_resource_shared<R: Resource>(&self) -> bool {
+        self.resources.is_shared::<R>()
+    }
+
+    /// Let go of a shared `R`; the other worlds keep it. Returns false when
+    /// `R` isn't shared.
+    pub fn unshare_resource<R: Resource>(&mut self) -> bool {
+        self.resources.unshare::<R>()
+    }
+
+    /// Run `f` with `R` taken out of the world. A shared resource stays locked
+    /// for the duration, so the other worlds wait instead of losing it.
     pub fn resource_scope<R: Resource, F, U>(&mut self, f: F) -> U
     where
         F: FnOnce(&mut World, &mut R) -> U,
     {
-        let mut resource = self.remove_resource::<R>().expect("Resource not found");
-        let result = f(self, &mut resource);
-        self.insert_resource(resource);
+        let type_id = TypeId::of::<R>();
+        let lock = self
+            .resources
+            .resources
+            .remove(&type_id)
+            .expect("Resource not found");
+        let result = {
+            let mut resource = lock.write();
+            f(self, resource.downcast_mut::<R>().unwrap())
+        };
+        self.resources.resources.insert(type_id, lock);
         result
     }
 
@@ -52,6 +52,25 @@ fn test_world_spawn_and_components() {
     assert!(world.get_component::<Position>(e1).is_none());
 }
 
+#[test]
+fn test_despawn_keeps_moved_entities_reachable() {
+    let mut world = World::new();
+    let entities: Vec<_> = (0..4)
+        .map(|x| {
+            let e = world.spawn();
+            world.add_component(e, Position { x, y: 0 }).unwrap();
+            e
+        })
+        .collect();
+
+    // Despawning the first moves the last entity into its slot
+    assert!(world.despawn(entities[0]));
+    assert_eq!(world.get_component::<Position>(entities[3]).unwrap().x, 3);
+    assert!(world.despawn(entities[3]));
+    assert_eq!(world.get_component::<Position>(entities[1]).unwrap().x, 1);
+    assert_eq!(world.get_component::<Position>(entities[2]).unwrap().x, 2);
+}
+
 #[test]
 fn test_world_resources() {
     let mut world = World::new();
@@ -63,6 +82,46 @@ fn test_world_resources() {
     assert_eq!(world.get_resource::<TestResource>().unwrap().0, 200);
 }
 
+#[test]
+fn test_shared_resources() {
+    let mut world = World::new();
+    world.insert_resource(TestResource(1));
+    let mut other = World::new();
+    assert!(world.share_resource::<TestResource>(&mut other));
+
+    other.get_resource_mut::<TestResource>().unwrap().0 = 2;
+    assert_eq!(world.get_resource::<TestResource>().unwrap().0, 2);
+
+    // Shared resources can't be taken out until the other world lets go
+    assert!(world.is_resource_shared::<TestResource>());
+
+    // ...but can still be borrowed in place
+    world.resource_scope::<TestResource, _, _>(|world, resource| {
+        assert!(world.get_resource::<TestResource>().is_none());
+        resource.0 += 1;
+    });
+    assert_eq!(other.get_resource::<TestResource>().unwrap().0, 3);
+    assert!(world.is_resource_shared::<TestResource>());
+
+    assert!(other.unshare_resource::<TestResource>());
+    assert!(other.get_resource::<TestResource>().is_none());
+    assert!(!world.is_resource_shared::<TestResource>());
+    assert!(!world.unshare_resource::<TestResource>());
+    assert_eq!(world.remove_resource::<TestResource>().unwrap().0, 3);
+    assert!(!world.share_resource::<TestResource>(&mut World::new()));
+}
+
+#[test]
+#[should_panic(expected = "shared with another map")]
+fn test_removing_a_shared_resource_panics() {
+    let mut world = World::new();
+    world.insert_resource(TestResource(1));
+    let mut other = World::new();
+    world.share_resource::<TestResource>(&mut other);
+
+    world.remove_resource::<TestResource>();
+}
+
 #[test]
 fn test_world_events() {
     let mut world = World::new();
@@ -138,18 +138,11 @@ fn render_3d_scene(
 ) {
     let gpu = gpu.read();
 
-    let frame = match gpu.surface.get_current_texture() {
-        Ok(f) => f,
-        Err(e) => {
-            log::warn!("Failed to get frame: {:?}", e);
-            return;
-        }
+    let Some((frame, view)) = gpu.begin_frame() else {
+        log::warn!("Failed to get frame");
+        return;
     };
 
-    let view = frame
-        .texture
-        .create_view(&wgpu::TextureViewDescriptor::default());
-
     let mut encoder = gpu
         .device
         .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -181,7 +174,7 @@ fn render_3d_scene(
     }
 
     gpu.queue.submit(std::iter::once(encoder.finish()));
-    frame.present();
+    gpu.end_frame(frame);
     window.request_redraw();
 }
 
@@ -242,6 +235,7 @@ fn setup_scene(app: &mut App) {
             },
             clear_color: Color::rgb(0.1, 0.12, 0.15),
             is_active: true,
+            ..Default::default()
         },
     );
 
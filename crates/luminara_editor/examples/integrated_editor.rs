@@ -122,6 +122,7 @@ fn setup_test_scene(app: &mut App) {
             },
             clear_color: Color::rgb(0.1, 0.1, 0.15),
             is_active: true,
+            ..Default::default()
         },
     );
 
@@ -7,7 +7,7 @@ use std::sync::Arc;
 
 use luminara_asset::Handle;
 use luminara_math::Mat4;
-use luminara_render::{Material, Mesh};
+use luminara_render::{Mesh, ShaderMaterial};
 
 use super::RenderDevice;
 
@@ -240,7 +240,7 @@ impl ViewportRenderPipeline {
         render_pass: &mut wgpu::RenderPass<'_>,
         mesh: &Mesh,
         transform: Mat4,
-        _material: &Material,
+        _material: &ShaderMaterial,
     ) {
         // Set pipeline
         render_pass.set_pipeline(self.base_pipeline.pipeline());
@@ -0,0 +1,463 @@
+use luminara_core::{Component, Entity, Query};
+use luminara_math::Vec3;
+use luminara_reflect_derive::Reflect;
+use rapier3d::control::{
+    CharacterAutostep as RapierAutostep, CharacterCollision as RapierCollision, CharacterLength,
+    KinematicCharacterController,
+};
+use rapier3d::na::SVector;
+use rapier3d::parry::query::ShapeCastOptions;
+use rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{RigidBody, RigidBodyType};
+use crate::physics3d::PhysicsWorld3D;
+
+/// Stair-stepping settings for a [`CharacterController`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect)]
+pub struct CharacterAutostep {
+    /// Maximum height of a step the character can climb automatically
+    pub max_height: f32,
+    /// Minimum free width required on top of a step before climbing it
+    pub min_width: f32,
+    /// Whether dynamic bodies can be used as steps
+    pub include_dynamic_bodies: bool,
+}
+
+impl Default for CharacterAutostep {
+    fn default() -> Self {
+        Self {
+            max_height: 0.3,
+            min_width: 0.2,
+            include_dynamic_bodies: false,
+        }
+    }
+}
+
+/// Kinematic character controller built on Rapier's shape-casting
+///
+/// Attach to an entity that also has a `Kinematic` [`RigidBody`] and a [`Collider`].
+/// Gameplay code writes `desired_translation` each frame; the controller resolves it
+/// against the world and reports the corrected movement in [`CharacterControllerOutput`].
+/// The same component drives both the 3D and 2D physics worlds (2D uses `x`/`y` only).
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub struct CharacterController {
+    /// Translation requested for this frame, consumed (reset to zero) by the controller
+    pub desired_translation: Vec3,
+    /// Up direction used for slope, step and ground detection
+    pub up: Vec3,
+    /// Skin width kept between the character shape and obstacles
+    pub offset: f32,
+    /// Maximum slope angle (radians) the character can walk up
+    pub max_slope_climb_angle: f32,
+    /// Minimum slope angle (radians) at which the character starts sliding down
+    pub min_slope_slide_angle: f32,
+    /// Slide along walls and slopes instead of stopping at the first hit
+    pub slide: bool,
+    /// Automatic stair climbing, disabled when `None`
+    pub autostep: Option<CharacterAutostep>,
+    /// Maximum distance to snap down to the ground, disabled when `None`
+    pub snap_to_ground: Option<f32>,
+    /// Carry the character along with the kinematic or dynamic body it stands on
+    pub carry_with_platforms: bool,
+    /// Apply impulses to dynamic bodies the character runs into
+    pub push_dynamic_bodies: bool,
+    /// Mass used when pushing dynamic bodies
+    pub mass: f32,
+}
+
+impl Component for CharacterController {
+    fn type_name() -> &'static str {
+        "CharacterController"
+    }
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            desired_translation: Vec3::ZERO,
+            up: Vec3::Y,
+            offset: 0.01,
+            max_slope_climb_angle: 45.0_f32.to_radians(),
+            min_slope_slide_angle: 30.0_f32.to_radians(),
+            slide: true,
+            autostep: Some(CharacterAutostep::default()),
+            snap_to_ground: Some(0.2),
+            carry_with_platforms: true,
+            push_dynamic_bodies: true,
+            mass: 80.0,
+        }
+    }
+}
+
+/// A single obstacle hit while resolving a character's movement
+#[derive(Debug, Clone, Copy)]
+pub struct CharacterCollisionInfo {
+    /// Entity owning the collider that was hit, if it is tracked by the physics world
+    pub entity: Option<Entity>,
+    /// Outward surface normal of the obstacle at the hit
+    pub normal: Vec3,
+    /// Translation already applied when the hit occurred
+    pub translation_applied: Vec3,
+    /// Translation still left to resolve when the hit occurred
+    pub translation_remaining: Vec3,
+}
+
+/// Result of the last [`CharacterController`] update
+#[derive(Debug, Clone, Default)]
+pub struct CharacterControllerOutput {
+    /// Movement actually applied this frame, including platform carry
+    pub effective_translation: Vec3,
+    /// Whether the character ended the frame standing on the ground
+    pub grounded: bool,
+    /// Whether the character is sliding down a slope that is too steep
+    pub is_sliding_down_slope: bool,
+    /// Entity the character is standing on
+    pub ground_entity: Option<Entity>,
+    /// Velocity of the ground at the character's position (zero on static ground)
+    pub platform_velocity: Vec3,
+    /// Obstacles hit while moving
+    pub collisions: Vec<CharacterCollisionInfo>,
+}
+
+impl Component for CharacterControllerOutput {
+    fn type_name() -> &'static str {
+        "CharacterControllerOutput"
+    }
+}
+
+impl CharacterController {
+    /// Create a controller with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request a translation for the next update
+    pub fn move_by(&mut self, translation: Vec3) {
+        self.desired_translation += translation;
+    }
+
+    /// Whether a surface whose normal has the given dot product with `up` can be stood on
+    pub(crate) fn is_walkable(&self, normal_dot_up: f32) -> bool {
+        normal_dot_up >= self.max_slope_climb_angle.cos()
+    }
+
+    /// Split a resolved move at the first hit on a slope too steep to climb
+    ///
+    /// Rapier 0.22 treats walking into any slope as intent to climb it, so the
+    /// controller climbs slopes steeper than `max_slope_climb_angle` unless the
+    /// motion into them is removed. `hits` are the collision normals with the
+    /// translation applied and remaining at each hit. Returns the index of the hit,
+    /// the translation applied before it and the remaining translation without its
+    /// horizontal part into the slope, or `None` when no steep slope was climbed.
+    pub(crate) fn split_at_steep_slope<const D: usize>(
+        &self,
+        up: &SVector<f32, D>,
+        desired: &SVector<f32, D>,
+        resolved: &SVector<f32, D>,
+        hits: impl IntoIterator<Item = (SVector<f32, D>, SVector<f32, D>, SVector<f32, D>)>,
+    ) -> Option<(usize, SVector<f32, D>, SVector<f32, D>)> {
+        // Moving up on purpose, or not climbing at all
+        if desired.dot(up) > 0.0 || resolved.dot(up) <= 1.0e-5 {
+            return None;
+        }
+
+        hits.into_iter()
+            .enumerate()
+            .find_map(|(index, (normal, applied, remaining))| {
+                let slope = normal.dot(up);
+                // Walls and ceilings are already handled by Rapier
+                if slope <= 1.0e-3 || self.is_walkable(slope) {
+                    return None;
+                }
+                let away = (normal - up * slope).try_normalize(1.0e-6)?;
+                let into = remaining.dot(&away).min(0.0);
+                Some((index, applied, remaining - away * into))
+            })
+    }
+
+    pub(crate) fn to_rapier_3d(&self) -> KinematicCharacterController {
+        let up = UnitVector::try_new(vector![self.up.x, self.up.y, self.up.z], 1.0e-6)
+            .unwrap_or_else(Vector::y_axis);
+
+        KinematicCharacterController {
+            up,
+            offset: CharacterLength::Absolute(self.offset),
+            slide: self.slide,
+            autostep: self.autostep.map(|step| RapierAutostep {
+                max_height: CharacterLength::Absolute(step.max_height),
+                min_width: CharacterLength::Absolute(step.min_width),
+                include_dynamic_bodies: step.include_dynamic_bodies,
+            }),
+            max_slope_climb_angle: self.max_slope_climb_angle,
+            min_slope_slide_angle: self.min_slope_slide_angle,
+            snap_to_ground: self.snap_to_ground.map(CharacterLength::Absolute),
+            ..Default::default()
+        }
+    }
+}
+
+/// Cast a character shape from `position` down along `up` by at most `distance`
+///
+/// A macro rather than a function because the 3D and 2D controllers share Rapier's
+/// query API through different crates; the caller's `ShapeCastOptions` import picks
+/// the dimension.
+macro_rules! ground_probe {
+    ($physics_world:expr, $position:expr, $up:expr, $shape:expr, $distance:expr, $filter:expr) => {
+        $physics_world.query_pipeline.cast_shape(
+            &$physics_world.rigid_body_set,
+            &$physics_world.collider_set,
+            $position,
+            &-*$up,
+            $shape,
+            ShapeCastOptions {
+                max_time_of_impact: $distance,
+                target_distance: 0.0,
+                stop_at_penetration: false,
+                compute_impact_geometry_on_penetration: true,
+            },
+            $filter,
+        )
+    };
+}
+pub(crate) use ground_probe;
+
+fn to_vec3(v: &Vector<f32>) -> Vec3 {
+    Vec3::new(v.x, v.y, v.z)
+}
+
+/// System that resolves [`CharacterController`] movement against the 3D physics world
+/// (Exclusive system — needs mutable World access to write controller outputs)
+///
+/// Runs after body/collider creation and before the physics step: the corrected movement
+/// is applied as the kinematic body's next position, so the step and sync systems move
+/// the entity's `Transform`.
+pub fn character_controller_system(world: &mut luminara_core::world::World) {
+    // Phase 1: collect controllers on kinematic bodies
+    let controllers: Vec<(Entity, CharacterController)> = {
+        let query = Query::<(Entity, &CharacterController, &RigidBody)>::new(world);
+        query
+            .iter()
+            .filter(|(_, _, rb)| rb.body_type == RigidBodyType::Kinematic)
+            .map(|(entity, controller, _)| (entity, controller.clone()))
+            .collect()
+    };
+
+    if controllers.is_empty() {
+        return;
+    }
+
+    let dt = world
+        .get_resource::<luminara_core::Time>()
+        .map(|time| time.delta_seconds())
+        .unwrap_or(1.0 / 60.0);
+
+    // Phase 2: resolve movement
+    let mut outputs = Vec::with_capacity(controllers.len());
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld3D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+
+        // Colliders created this frame must be visible to the shape casts
+        physics_world
+            .query_pipeline
+            .update(&physics_world.collider_set);
+
+        for (entity, controller) in &controllers {
+            let (Some(&body_handle), Some(&collider_handle)) = (
+                physics_world.entity_to_body.get(entity),
+                physics_world.entity_to_collider.get(entity),
+            ) else {
+                continue;
+            };
+            let Some(collider) = physics_world.collider_set.get(collider_handle) else {
+                continue;
+            };
+            let Some(body) = physics_world.rigid_body_set.get(body_handle) else {
+                continue;
+            };
+            let shape = collider.shared_shape().clone();
+            // Start from the pending kinematic target so frames without a physics step
+            // still accumulate movement
+            let body_pos = *body.next_position();
+            let character_pos = match collider.position_wrt_parent() {
+                Some(local) => body_pos * local,
+                None => *collider.position(),
+            };
+
+            let rapier_controller = controller.to_rapier_3d();
+            let filter = QueryFilter::default()
+                .exclude_rigid_body(body_handle)
+                .exclude_sensors();
+
+            let mut output = CharacterControllerOutput::default();
+
+            // Ground probe: find what the character stands on before moving
+            let probe_distance =
+                controller.offset + controller.snap_to_ground.unwrap_or(0.0) + 0.05;
+            let ground = ground_probe!(
+                physics_world,
+                &character_pos,
+                rapier_controller.up,
+                &*shape,
+                probe_distance,
+                filter
+            );
+
+            let mut carry = Vector::zeros();
+            if let Some((ground_handle, hit)) = ground {
+                if controller.is_walkable(hit.normal1.dot(&rapier_controller.up)) {
+                    output.ground_entity = physics_world
+                        .collider_to_entity
+                        .get(&ground_handle)
+                        .copied();
+
+                    if controller.carry_with_platforms {
+                        let ground_body = physics_world
+                            .collider_set
+                            .get(ground_handle)
+                            .and_then(|c| c.parent())
+                            .and_then(|h| physics_world.rigid_body_set.get(h));
+                        if let Some(ground_body) = ground_body {
+                            if !ground_body.is_fixed() {
+                                let velocity = ground_body.velocity_at_point(&Point::from(
+                                    character_pos.translation.vector,
+                                ));
+                                output.platform_velocity = to_vec3(&velocity);
+                                carry = velocity * dt;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let desired = vector![
+                controller.desired_translation.x,
+                controller.desired_translation.y,
+                controller.desired_translation.z
+            ] + carry;
+
+            let mut collisions: Vec<RapierCollision> = Vec::new();
+            let mut movement = rapier_controller.move_shape(
+                dt,
+                &physics_world.rigid_body_set,
+                &physics_world.collider_set,
+                &physics_world.query_pipeline,
+                &*shape,
+                &character_pos,
+                desired,
+                filter,
+                |collision| collisions.push(collision),
+            );
+
+            // Whether walkable ground lies within `distance` below the character after
+            // moving by `translation`
+            let ground_distance = controller.offset * 1.1;
+            let standing_after = |translation: &Vector<f32>, distance: f32| {
+                ground_probe!(
+                    physics_world,
+                    &(Translation::from(*translation) * character_pos),
+                    rapier_controller.up,
+                    &*shape,
+                    distance,
+                    filter
+                )
+                .is_some_and(|(_, hit)| {
+                    controller.is_walkable(hit.normal1.dot(&rapier_controller.up))
+                })
+            };
+
+            // A climb that ends on a slope too steep to stand on is resolved again
+            // without the motion into the slope; autosteps end at most a step height
+            // above walkable ground
+            let step_height = controller.autostep.map_or(0.0, |step| step.max_height);
+            let steep = if standing_after(&movement.translation, ground_distance + step_height) {
+                None
+            } else {
+                controller.split_at_steep_slope(
+                    &rapier_controller.up,
+                    &desired,
+                    &movement.translation,
+                    collisions.iter().map(|collision| {
+                        (
+                            *collision.hit.normal1,
+                            collision.translation_applied,
+                            collision.translation_remaining,
+                        )
+                    }),
+                )
+            };
+            if let Some((index, applied, remaining)) = steep {
+                collisions.truncate(index + 1);
+                let retry = rapier_controller.move_shape(
+                    dt,
+                    &physics_world.rigid_body_set,
+                    &physics_world.collider_set,
+                    &physics_world.query_pipeline,
+                    &*shape,
+                    &(Translation::from(applied) * character_pos),
+                    remaining,
+                    filter,
+                    |mut collision| {
+                        collision.translation_applied += applied;
+                        collisions.push(collision);
+                    },
+                );
+                movement.translation = applied + retry.translation;
+                movement.grounded = retry.grounded;
+                movement.is_sliding_down_slope = retry.is_sliding_down_slope;
+            }
+
+            output.effective_translation = to_vec3(&movement.translation);
+            // Rapier only reports ground it collided with or snapped onto, so an
+            // unobstructed walk along the floor is checked at the final position
+            output.grounded = movement.grounded
+                || (movement.translation.dot(&rapier_controller.up) <= 1.0e-5
+                    && standing_after(&movement.translation, ground_distance));
+            output.is_sliding_down_slope = movement.is_sliding_down_slope;
+            output.collisions = collisions
+                .iter()
+                .map(|collision| CharacterCollisionInfo {
+                    entity: physics_world
+                        .collider_to_entity
+                        .get(&collision.handle)
+                        .copied(),
+                    normal: to_vec3(&collision.hit.normal1),
+                    translation_applied: to_vec3(&collision.translation_applied),
+                    translation_remaining: to_vec3(&collision.translation_remaining),
+                })
+                .collect();
+
+            if controller.push_dynamic_bodies && !collisions.is_empty() {
+                rapier_controller.solve_character_collision_impulses(
+                    dt,
+                    &mut physics_world.rigid_body_set,
+                    &physics_world.collider_set,
+                    &physics_world.query_pipeline,
+                    &*shape,
+                    controller.mass,
+                    &collisions,
+                    filter,
+                );
+            }
+
+            if let Some(body) = physics_world.rigid_body_set.get_mut(body_handle) {
+                body.set_next_kinematic_translation(
+                    body_pos.translation.vector + movement.translation,
+                );
+            }
+
+            outputs.push((*entity, output));
+        }
+    }
+
+    // Phase 3: write outputs and consume the requested translation
+    for (entity, output) in outputs {
+        if let Some(controller) = world.get_component_mut::<CharacterController>(entity) {
+            controller.desired_translation = Vec3::ZERO;
+        }
+        let _ = world.add_component(entity, output);
+    }
+}
@@ -0,0 +1,271 @@
+use luminara_core::{Entity, Query};
+use luminara_math::Vec3;
+use rapier2d::control::{
+    CharacterAutostep as RapierAutostep, CharacterCollision as RapierCollision, CharacterLength,
+    KinematicCharacterController,
+};
+use rapier2d::parry::query::ShapeCastOptions;
+use rapier2d::prelude::*;
+
+use crate::character_controller::{
+    ground_probe, CharacterCollisionInfo, CharacterController, CharacterControllerOutput,
+};
+use crate::components::{RigidBody, RigidBodyType};
+use crate::physics2d::PhysicsWorld2D;
+
+impl CharacterController {
+    pub(crate) fn to_rapier_2d(&self) -> KinematicCharacterController {
+        let up = UnitVector::try_new(vector![self.up.x, self.up.y], 1.0e-6)
+            .unwrap_or_else(Vector::y_axis);
+
+        KinematicCharacterController {
+            up,
+            offset: CharacterLength::Absolute(self.offset),
+            slide: self.slide,
+            autostep: self.autostep.map(|step| RapierAutostep {
+                max_height: CharacterLength::Absolute(step.max_height),
+                min_width: CharacterLength::Absolute(step.min_width),
+                include_dynamic_bodies: step.include_dynamic_bodies,
+            }),
+            max_slope_climb_angle: self.max_slope_climb_angle,
+            min_slope_slide_angle: self.min_slope_slide_angle,
+            snap_to_ground: self.snap_to_ground.map(CharacterLength::Absolute),
+            ..Default::default()
+        }
+    }
+}
+
+fn to_vec3(v: &Vector<f32>) -> Vec3 {
+    Vec3::new(v.x, v.y, 0.0)
+}
+
+/// System that resolves [`CharacterController`] movement against the 2D physics world
+/// (Exclusive system — needs mutable World access to write controller outputs)
+///
+/// Mirrors [`crate::character_controller::character_controller_system`] for entities
+/// tracked by [`PhysicsWorld2D`]; the `z` component of the desired translation is ignored.
+pub fn character_controller_system_2d(world: &mut luminara_core::world::World) {
+    // Phase 1: collect controllers on kinematic bodies
+    let controllers: Vec<(Entity, CharacterController)> = {
+        let query = Query::<(Entity, &CharacterController, &RigidBody)>::new(world);
+        query
+            .iter()
+            .filter(|(_, _, rb)| rb.body_type == RigidBodyType::Kinematic)
+            .map(|(entity, controller, _)| (entity, controller.clone()))
+            .collect()
+    };
+
+    if controllers.is_empty() {
+        return;
+    }
+
+    let dt = world
+        .get_resource::<luminara_core::Time>()
+        .map(|time| time.delta_seconds())
+        .unwrap_or(1.0 / 60.0);
+
+    // Phase 2: resolve movement
+    let mut outputs = Vec::with_capacity(controllers.len());
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld2D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+
+        // Colliders created this frame must be visible to the shape casts
+        physics_world
+            .query_pipeline
+            .update(&physics_world.collider_set);
+
+        for (entity, controller) in &controllers {
+            let (Some(&body_handle), Some(&collider_handle)) = (
+                physics_world.entity_to_body.get(entity),
+                physics_world.entity_to_collider.get(entity),
+            ) else {
+                continue;
+            };
+            let Some(collider) = physics_world.collider_set.get(collider_handle) else {
+                continue;
+            };
+            let Some(body) = physics_world.rigid_body_set.get(body_handle) else {
+                continue;
+            };
+            let shape = collider.shared_shape().clone();
+            let body_pos = *body.next_position();
+            let character_pos = match collider.position_wrt_parent() {
+                Some(local) => body_pos * local,
+                None => *collider.position(),
+            };
+
+            let rapier_controller = controller.to_rapier_2d();
+            let filter = QueryFilter::default()
+                .exclude_rigid_body(body_handle)
+                .exclude_sensors();
+
+            let mut output = CharacterControllerOutput::default();
+
+            // Ground probe: find what the character stands on before moving
+            let probe_distance =
+                controller.offset + controller.snap_to_ground.unwrap_or(0.0) + 0.05;
+            let ground = ground_probe!(
+                physics_world,
+                &character_pos,
+                rapier_controller.up,
+                &*shape,
+                probe_distance,
+                filter
+            );
+
+            let mut carry = Vector::zeros();
+            if let Some((ground_handle, hit)) = ground {
+                if controller.is_walkable(hit.normal1.dot(&rapier_controller.up)) {
+                    output.ground_entity = physics_world
+                        .collider_to_entity
+                        .get(&ground_handle)
+                        .copied();
+
+                    if controller.carry_with_platforms {
+                        let ground_body = physics_world
+                            .collider_set
+                            .get(ground_handle)
+                            .and_then(|c| c.parent())
+                            .and_then(|h| physics_world.rigid_body_set.get(h));
+                        if let Some(ground_body) = ground_body {
+                            if !ground_body.is_fixed() {
+                                let velocity = ground_body.velocity_at_point(&Point::from(
+                                    character_pos.translation.vector,
+                                ));
+                                output.platform_velocity = to_vec3(&velocity);
+                                carry = velocity * dt;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let desired = vector![
+                controller.desired_translation.x,
+                controller.desired_translation.y
+            ] + carry;
+
+            let mut collisions: Vec<RapierCollision> = Vec::new();
+            let mut movement = rapier_controller.move_shape(
+                dt,
+                &physics_world.rigid_body_set,
+                &physics_world.collider_set,
+                &physics_world.query_pipeline,
+                &*shape,
+                &character_pos,
+                desired,
+                filter,
+                |collision| collisions.push(collision),
+            );
+
+            // Whether walkable ground lies within `distance` below the character after
+            // moving by `translation`
+            let ground_distance = controller.offset * 1.1;
+            let standing_after = |translation: &Vector<f32>, distance: f32| {
+                ground_probe!(
+                    physics_world,
+                    &(Translation::from(*translation) * character_pos),
+                    rapier_controller.up,
+                    &*shape,
+                    distance,
+                    filter
+                )
+                .is_some_and(|(_, hit)| {
+                    controller.is_walkable(hit.normal1.dot(&rapier_controller.up))
+                })
+            };
+
+            // A climb that ends on a slope too steep to stand on is resolved again
+            // without the motion into the slope; autosteps end at most a step height
+            // above walkable ground
+            let step_height = controller.autostep.map_or(0.0, |step| step.max_height);
+            let steep = if standing_after(&movement.translation, ground_distance + step_height) {
+                None
+            } else {
+                controller.split_at_steep_slope(
+                    &rapier_controller.up,
+                    &desired,
+                    &movement.translation,
+                    collisions.iter().map(|collision| {
+                        (
+                            *collision.hit.normal1,
+                            collision.translation_applied,
+                            collision.translation_remaining,
+                        )
+                    }),
+                )
+            };
+            if let Some((index, applied, remaining)) = steep {
+                collisions.truncate(index + 1);
+                let retry = rapier_controller.move_shape(
+                    dt,
+                    &physics_world.rigid_body_set,
+                    &physics_world.collider_set,
+                    &physics_world.query_pipeline,
+                    &*shape,
+                    &(Translation::from(applied) * character_pos),
+                    remaining,
+                    filter,
+                    |mut collision| {
+                        collision.translation_applied += applied;
+                        collisions.push(collision);
+                    },
+                );
+                movement.translation = applied + retry.translation;
+                movement.grounded = retry.grounded;
+                movement.is_sliding_down_slope = retry.is_sliding_down_slope;
+            }
+
+            output.effective_translation = to_vec3(&movement.translation);
+            // Same fallback for unobstructed moves as the 3D controller
+            output.grounded = movement.grounded
+                || (movement.translation.dot(&rapier_controller.up) <= 1.0e-5
+                    && standing_after(&movement.translation, ground_distance));
+            output.is_sliding_down_slope = movement.is_sliding_down_slope;
+            output.collisions = collisions
+                .iter()
+                .map(|collision| CharacterCollisionInfo {
+                    entity: physics_world
+                        .collider_to_entity
+                        .get(&collision.handle)
+                        .copied(),
+                    normal: to_vec3(&collision.hit.normal1),
+                    translation_applied: to_vec3(&collision.translation_applied),
+                    translation_remaining: to_vec3(&collision.translation_remaining),
+                })
+                .collect();
+
+            if controller.push_dynamic_bodies && !collisions.is_empty() {
+                rapier_controller.solve_character_collision_impulses(
+                    dt,
+                    &mut physics_world.rigid_body_set,
+                    &physics_world.collider_set,
+                    &physics_world.query_pipeline,
+                    &*shape,
+                    controller.mass,
+                    &collisions,
+                    filter,
+                );
+            }
+
+            if let Some(body) = physics_world.rigid_body_set.get_mut(body_handle) {
+                body.set_next_kinematic_translation(
+                    body_pos.translation.vector + movement.translation,
+                );
+            }
+
+            outputs.push((*entity, output));
+        }
+    }
+
+    // Phase 3: write outputs and consume the requested translation
+    for (entity, output) in outputs {
+        if let Some(controller) = world.get_component_mut::<CharacterController>(entity) {
+            controller.desired_translation = Vec3::ZERO;
+        }
+        let _ = world.add_component(entity, output);
+    }
+}
@@ -1,5 +1,6 @@
 use luminara_core::{Component, Entity};
-use luminara_math::{Transform, Vec3};
+use luminara_math::geometry::reeb_graph::HeightMap;
+use luminara_math::{Quat, Transform, Vec3};
 use luminara_reflect_derive::Reflect;
 use serde::{Deserialize, Serialize};
 
@@ -103,6 +104,148 @@ pub enum ColliderShape {
         vertices: Vec<Vec3>,
         indices: Vec<[u32; 3]>,
     },
+    /// Cylinder aligned with the local Y axis
+    Cylinder {
+        half_height: f32,
+        radius: f32,
+    },
+    /// Cone aligned with the local Y axis, apex pointing up
+    Cone {
+        half_height: f32,
+        radius: f32,
+    },
+    /// Convex hull of a point cloud
+    ConvexHull {
+        points: Vec<Vec3>,
+    },
+    /// Grid of heights on the local XZ plane, centered on the entity
+    ///
+    /// `heights` is row-major with `rows` samples along Z and `cols` samples along X.
+    /// `scale` is the total size of the field; heights are multiplied by `scale.y`.
+    Heightfield {
+        heights: Vec<f32>,
+        rows: usize,
+        cols: usize,
+        scale: Vec3,
+    },
+    /// Several shapes rigidly attached to the same collider
+    Compound {
+        children: Vec<CompoundChild>,
+    },
+    /// Triangle mesh decomposed into convex parts with V-HACD
+    ///
+    /// Unlike [`ColliderShape::Mesh`], the result has volume and can be used on dynamic bodies.
+    ConvexDecomposition {
+        vertices: Vec<Vec3>,
+        indices: Vec<[u32; 3]>,
+        settings: ConvexDecompositionSettings,
+    },
+}
+
+impl ColliderShape {
+    /// Build a heightfield shape covering the same area as a [`HeightMap`]
+    ///
+    /// The collider is centered on its entity, so place the entity at the center of the
+    /// map (half of `(width - 1) * scale.x`, `(height - 1) * scale.z`) to line it up with
+    /// [`HeightMap::world_pos`].
+    pub fn heightfield_from_map(map: &HeightMap) -> Self {
+        ColliderShape::Heightfield {
+            heights: map.heights.clone(),
+            rows: map.height,
+            cols: map.width,
+            scale: Vec3::new(
+                (map.width.saturating_sub(1)) as f32 * map.scale.x,
+                map.scale.y,
+                (map.height.saturating_sub(1)) as f32 * map.scale.z,
+            ),
+        }
+    }
+
+    /// Build a static triangle-mesh shape from a render mesh
+    pub fn trimesh_from_mesh(mesh: &luminara_render::Mesh) -> Self {
+        let (vertices, indices) = mesh_geometry(mesh);
+        ColliderShape::Mesh { vertices, indices }
+    }
+
+    /// Build a convex decomposition of a render mesh, suitable for dynamic bodies
+    pub fn convex_decomposition_from_mesh(
+        mesh: &luminara_render::Mesh,
+        settings: ConvexDecompositionSettings,
+    ) -> Self {
+        let (vertices, indices) = mesh_geometry(mesh);
+        ColliderShape::ConvexDecomposition {
+            vertices,
+            indices,
+            settings,
+        }
+    }
+
+    /// Build a convex hull enclosing all vertices of a render mesh
+    pub fn convex_hull_from_mesh(mesh: &luminara_render::Mesh) -> Self {
+        ColliderShape::ConvexHull {
+            points: mesh
+                .vertices
+                .iter()
+                .map(|v| Vec3::from_array(v.position))
+                .collect(),
+        }
+    }
+}
+
+fn mesh_geometry(mesh: &luminara_render::Mesh) -> (Vec<Vec3>, Vec<[u32; 3]>) {
+    let vertices = mesh
+        .vertices
+        .iter()
+        .map(|v| Vec3::from_array(v.position))
+        .collect();
+    let indices = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|tri| [tri[0], tri[1], tri[2]])
+        .collect();
+    (vertices, indices)
+}
+
+/// A shape placed relative to its [`ColliderShape::Compound`] parent
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub struct CompoundChild {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub shape: ColliderShape,
+}
+
+impl CompoundChild {
+    pub fn new(translation: Vec3, rotation: Quat, shape: ColliderShape) -> Self {
+        Self {
+            translation,
+            rotation,
+            shape,
+        }
+    }
+}
+
+/// V-HACD parameters for [`ColliderShape::ConvexDecomposition`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect)]
+pub struct ConvexDecompositionSettings {
+    /// Voxel resolution used to approximate the mesh volume
+    pub resolution: u32,
+    /// Maximum concavity allowed in each convex part
+    pub concavity: f32,
+    /// Downsampling factor used when searching for clipping planes
+    pub plane_downsampling: u32,
+    /// Downsampling factor used when computing the convex hull of each part
+    pub convex_hull_downsampling: u32,
+}
+
+impl Default for ConvexDecompositionSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 64,
+            concavity: 0.01,
+            plane_downsampling: 4,
+            convex_hull_downsampling: 4,
+        }
+    }
 }
 
 /// Collision event emitted when two entities collide
@@ -1,4 +1,4 @@
-use luminara_core::Component;
+use luminara_core::{Component, Resource};
 use luminara_reflect_derive::Reflect;
 use serde::{Deserialize, Serialize};
 
@@ -27,12 +27,30 @@ impl Default for IntegrationMethod {
 }
 
 /// Global physics integration configuration
+///
+/// Inserted as a resource by the physics plugin. Bodies resolved to
+/// [`IntegrationMethod::Rk4`] keep Rapier's contact, joint and gravity response but
+/// have their rotation re-integrated with the Lie group integrator each step.
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
 pub struct PhysicsIntegrationConfig {
     /// Default integration method for all bodies
     pub default_method: IntegrationMethod,
 }
 
+impl Resource for PhysicsIntegrationConfig {}
+
+impl PhysicsIntegrationConfig {
+    /// Resolve the method for a body, honouring its [`IntegrationMethodOverride`]
+    pub fn method_for(
+        &self,
+        method_override: Option<&IntegrationMethodOverride>,
+    ) -> IntegrationMethod {
+        method_override
+            .map(|o| o.method)
+            .unwrap_or(self.default_method)
+    }
+}
+
 impl Default for PhysicsIntegrationConfig {
     fn default() -> Self {
         Self {
@@ -88,6 +106,18 @@ mod tests {
         assert_eq!(config.default_method, IntegrationMethod::Euler);
     }
 
+    #[test]
+    fn test_override_takes_precedence() {
+        let config = PhysicsIntegrationConfig {
+            default_method: IntegrationMethod::Rk4,
+        };
+        assert_eq!(config.method_for(None), IntegrationMethod::Rk4);
+        assert_eq!(
+            config.method_for(Some(&IntegrationMethodOverride::euler())),
+            IntegrationMethod::Euler
+        );
+    }
+
     #[test]
     fn test_override_creation() {
         let euler_override = IntegrationMethodOverride::euler();
@@ -1,4 +1,6 @@
 pub mod camera_shake;
+pub mod character_controller;
+pub mod character_controller2d;
 pub mod components;
 pub mod debug;
 pub mod explosion;
@@ -7,22 +9,42 @@ pub mod interaction;
 pub mod lie_integrator;
 pub mod physics2d;
 pub mod physics3d;
+pub mod ragdoll;
 pub mod spatial_acceleration;
 pub mod target_game;
+pub mod terrain;
+pub mod tilemap2d;
 
+pub use character_controller::{
+    CharacterAutostep, CharacterCollisionInfo, CharacterController, CharacterControllerOutput,
+};
 pub use components::*;
 pub use debug::PhysicsDebugConfig;
 pub use integration_config::{IntegrationMethod, IntegrationMethodOverride, PhysicsIntegrationConfig};
 pub use lie_integrator::LiePhysicsIntegrator;
-pub use physics2d::{CollisionEvents2D, Physics2dPlugin, PhysicsWorld2D};
-pub use physics3d::{CollisionEvents, PhysicsPlugin, PhysicsWorld3D};
+pub use physics2d::{CollisionEvents2D, Physics2dPlugin, PhysicsSnapshot2D, PhysicsWorld2D};
+pub use physics3d::{CollisionEvents, PhysicsPlugin, PhysicsSnapshot3D, PhysicsWorld3D};
+pub use ragdoll::{
+    Ragdoll, RagdollBoneRule, RagdollJoint, RagdollMode, RagdollPart, RagdollProfile,
+    RagdollProfileLoader,
+};
 pub use target_game::{Target, TargetGameState};
+pub use terrain::TerrainCollider;
+pub use tilemap2d::TilemapColliders2D;
 
 // Re-export physics systems for manual scheduling if needed
 pub use physics3d::{
-    collision_detection_system, physics_step_system, physics_sync_system,
+    collision_detection_system, particle_collider, particle_collider_sync_system,
+    physics_integration_method_system, physics_step_system, physics_sync_system,
+    restore_physics_snapshot,
 };
+pub use character_controller::character_controller_system;
+pub use ragdoll::{ragdoll_drive_system, ragdoll_pose_system};
+pub use terrain::terrain_collider_system;
 
 pub use physics2d::{
     collision_detection_system_2d, physics_step_system_2d, physics_sync_system_2d,
+    restore_physics_snapshot_2d,
 };
+pub use character_controller2d::character_controller_system_2d;
+pub use tilemap2d::tilemap_collider_system_2d;
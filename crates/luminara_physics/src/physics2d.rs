@@ -1,7 +1,8 @@
-use luminara_core::{Component, Entity, Plugin, Query, Res, ResMut, Resource};
+use luminara_core::{AppInterface, Component, Entity, Plugin, Query, Res, ResMut, Resource};
 use luminara_math::{Quat, Transform, Vec3};
 use rapier2d::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 use crate::components::{CollisionEvent, RigidBody};
 
@@ -50,6 +51,213 @@ impl Default for PhysicsWorld2D {
     }
 }
 
+impl PhysicsWorld2D {
+    /// Advance the simulation by one step of `integration_parameters.dt`
+    pub fn step(&mut self) {
+        let PhysicsWorld2D {
+            ref gravity,
+            ref integration_parameters,
+            ref mut physics_pipeline,
+            ref mut island_manager,
+            ref mut broad_phase,
+            ref mut narrow_phase,
+            ref mut rigid_body_set,
+            ref mut collider_set,
+            ref mut impulse_joint_set,
+            ref mut multibody_joint_set,
+            ref mut ccd_solver,
+            ref mut query_pipeline,
+            ..
+        } = *self;
+
+        physics_pipeline.step(
+            gravity,
+            integration_parameters,
+            island_manager,
+            broad_phase,
+            narrow_phase,
+            rigid_body_set,
+            collider_set,
+            impulse_joint_set,
+            multibody_joint_set,
+            ccd_solver,
+            Some(query_pipeline),
+            &(),
+            &(),
+        );
+    }
+
+    /// Capture the complete simulation state (see [`crate::PhysicsWorld3D::snapshot`])
+    pub fn snapshot(&self) -> PhysicsSnapshot2D {
+        PhysicsSnapshot2D {
+            gravity: self.gravity,
+            integration_parameters: self.integration_parameters,
+            island_manager: self.island_manager.clone(),
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            rigid_body_set: self.rigid_body_set.clone(),
+            collider_set: self.collider_set.clone(),
+            impulse_joint_set: self.impulse_joint_set.clone(),
+            multibody_joint_set: self.multibody_joint_set.clone(),
+            ccd_solver: self.ccd_solver.clone(),
+            query_pipeline: self.query_pipeline.clone(),
+            entity_to_body: self.entity_to_body.iter().map(|(e, h)| (*e, *h)).collect(),
+            entity_to_collider: self
+                .entity_to_collider
+                .iter()
+                .map(|(e, h)| (*e, *h))
+                .collect(),
+        }
+    }
+
+    /// Replace the simulation state with a previously captured snapshot
+    pub fn restore(&mut self, snapshot: &PhysicsSnapshot2D) {
+        self.gravity = snapshot.gravity;
+        self.integration_parameters = snapshot.integration_parameters;
+        self.island_manager = snapshot.island_manager.clone();
+        self.broad_phase = snapshot.broad_phase.clone();
+        self.narrow_phase = snapshot.narrow_phase.clone();
+        self.rigid_body_set = snapshot.rigid_body_set.clone();
+        self.collider_set = snapshot.collider_set.clone();
+        self.impulse_joint_set = snapshot.impulse_joint_set.clone();
+        self.multibody_joint_set = snapshot.multibody_joint_set.clone();
+        self.ccd_solver = snapshot.ccd_solver.clone();
+        self.query_pipeline = snapshot.query_pipeline.clone();
+
+        self.entity_to_body = snapshot.entity_to_body.iter().copied().collect();
+        self.body_to_entity = snapshot
+            .entity_to_body
+            .iter()
+            .map(|(e, h)| (*h, *e))
+            .collect();
+        self.entity_to_collider = snapshot.entity_to_collider.iter().copied().collect();
+        self.collider_to_entity = snapshot
+            .entity_to_collider
+            .iter()
+            .map(|(e, h)| (*h, *e))
+            .collect();
+    }
+}
+
+/// Serializable copy of a [`PhysicsWorld2D`]'s simulation state
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PhysicsSnapshot2D {
+    pub gravity: Vector<f32>,
+    pub integration_parameters: IntegrationParameters,
+    pub island_manager: IslandManager,
+    pub broad_phase: DefaultBroadPhase,
+    pub narrow_phase: NarrowPhase,
+    pub rigid_body_set: RigidBodySet,
+    pub collider_set: ColliderSet,
+    pub impulse_joint_set: ImpulseJointSet,
+    pub multibody_joint_set: MultibodyJointSet,
+    pub ccd_solver: CCDSolver,
+    pub query_pipeline: QueryPipeline,
+    pub entity_to_body: Vec<(Entity, RigidBodyHandle)>,
+    pub entity_to_collider: Vec<(Entity, ColliderHandle)>,
+}
+
+impl PhysicsSnapshot2D {
+    /// Encode the snapshot into a compact binary form (e.g. for replay files)
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    /// Decode a snapshot produced by [`PhysicsSnapshot2D::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
+/// Restore a [`PhysicsSnapshot2D`] and reconcile the ECS with it (see
+/// [`crate::restore_physics_snapshot`])
+///
+/// Bodies and colliders of entities despawned since the snapshot are removed from the
+/// restored world, and creation markers without a handle in the snapshot are dropped.
+/// Tilemap colliders are reconciled the same way.
+pub fn restore_physics_snapshot_2d(
+    world: &mut luminara_core::world::World,
+    snapshot: &PhysicsSnapshot2D,
+) {
+    let alive: HashSet<Entity> = world.entities().into_iter().collect();
+
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld2D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+        physics_world.restore(snapshot);
+
+        // Drop what belonged to entities that no longer exist
+        let dead_bodies: Vec<RigidBodyHandle> = physics_world
+            .body_to_entity
+            .iter()
+            .filter(|(_, entity)| !alive.contains(entity))
+            .map(|(handle, _)| *handle)
+            .collect();
+        for handle in dead_bodies {
+            physics_world.rigid_body_set.remove(
+                handle,
+                &mut physics_world.island_manager,
+                &mut physics_world.collider_set,
+                &mut physics_world.impulse_joint_set,
+                &mut physics_world.multibody_joint_set,
+                true,
+            );
+        }
+        let dead_colliders: Vec<ColliderHandle> = physics_world
+            .collider_to_entity
+            .iter()
+            .filter(|(_, entity)| !alive.contains(entity))
+            .map(|(handle, _)| *handle)
+            .collect();
+        for handle in dead_colliders {
+            physics_world.collider_set.remove(
+                handle,
+                &mut physics_world.island_manager,
+                &mut physics_world.rigid_body_set,
+                true,
+            );
+        }
+
+        let PhysicsWorld2D {
+            ref rigid_body_set,
+            ref collider_set,
+            ref mut entity_to_body,
+            ref mut body_to_entity,
+            ref mut entity_to_collider,
+            ref mut collider_to_entity,
+            ..
+        } = *physics_world;
+        entity_to_body.retain(|_, handle| rigid_body_set.contains(*handle));
+        body_to_entity.retain(|handle, _| rigid_body_set.contains(*handle));
+        entity_to_collider.retain(|_, handle| collider_set.contains(*handle));
+        collider_to_entity.retain(|handle, _| collider_set.contains(*handle));
+    }
+
+    let (stale_bodies, stale_colliders) = {
+        let physics_world = world.get_resource::<PhysicsWorld2D>().unwrap();
+        let stale_bodies: Vec<Entity> = Query::<(Entity, &PhysicsBodyCreated2D)>::new(world)
+            .iter()
+            .map(|(entity, _)| entity)
+            .filter(|entity| !physics_world.entity_to_body.contains_key(entity))
+            .collect();
+        let stale_colliders: Vec<Entity> = Query::<(Entity, &PhysicsColliderCreated2D)>::new(world)
+            .iter()
+            .map(|(entity, _)| entity)
+            .filter(|entity| !physics_world.entity_to_collider.contains_key(entity))
+            .collect();
+        (stale_bodies, stale_colliders)
+    };
+    for entity in stale_bodies {
+        let _ = world.remove_component::<PhysicsBodyCreated2D>(entity);
+    }
+    for entity in stale_colliders {
+        let _ = world.remove_component::<PhysicsColliderCreated2D>(entity);
+    }
+    crate::tilemap2d::reconcile_tilemap_bodies(world);
+}
+
 /// Collision events resource for 2D
 pub struct CollisionEvents2D(pub Vec<CollisionEvent>);
 
@@ -76,6 +284,18 @@ impl Plugin for Physics2dPlugin {
         // Register collision event
         app.world.insert_resource(CollisionEvents2D::default());
 
+        // Build tilemap colliders before characters are moved against them
+        app.add_system::<luminara_core::system::ExclusiveMarker>(
+            luminara_core::CoreStage::PreUpdate,
+            crate::tilemap2d::tilemap_collider_system_2d,
+        );
+
+        // Resolve character controller movement before the physics step
+        app.add_system::<luminara_core::system::ExclusiveMarker>(
+            luminara_core::CoreStage::PreUpdate,
+            crate::character_controller2d::character_controller_system_2d,
+        );
+
         log::info!("Physics2dPlugin initialized");
     }
 }
@@ -100,40 +320,9 @@ impl Component for PhysicsColliderCreated2D {
     }
 }
 
-
 /// System to step the physics simulation
 pub fn physics_step_system_2d(mut physics_world: ResMut<PhysicsWorld2D>) {
-    let PhysicsWorld2D {
-        ref gravity,
-        ref integration_parameters,
-        ref mut physics_pipeline,
-        ref mut island_manager,
-        ref mut broad_phase,
-        ref mut narrow_phase,
-        ref mut rigid_body_set,
-        ref mut collider_set,
-        ref mut impulse_joint_set,
-        ref mut multibody_joint_set,
-        ref mut ccd_solver,
-        ref mut query_pipeline,
-        ..
-    } = *physics_world;
-
-    physics_pipeline.step(
-        gravity,
-        integration_parameters,
-        island_manager,
-        broad_phase,
-        narrow_phase,
-        rigid_body_set,
-        collider_set,
-        impulse_joint_set,
-        multibody_joint_set,
-        ccd_solver,
-        Some(query_pipeline),
-        &(),
-        &(),
-    );
+    physics_world.step();
 }
 
 /// System to sync physics state back to ECS transforms
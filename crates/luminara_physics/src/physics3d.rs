@@ -2,12 +2,19 @@ use luminara_core::shared_types::{AppInterface, CoreStage};
 use luminara_core::system::ExclusiveMarker;
 use luminara_core::{Component, Entity, Plugin, Query, Res, ResMut, Resource, Without};
 use luminara_math::{Quat, Transform, Vec3};
+use luminara_render::{ParticleCollider, ParticleColliders};
+use luminara_scene::{GlobalTransform, Parent};
 use rapier3d::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 use crate::components::{
     Collider, ColliderShape, CollisionEvent, PreviousTransform, RigidBody, RigidBodyType,
 };
+use crate::integration_config::{
+    IntegrationMethod, IntegrationMethodOverride, PhysicsIntegrationConfig,
+};
+use crate::lie_integrator::LiePhysicsIntegrator;
 
 /// Resource containing the Rapier 3D physics world
 pub struct PhysicsWorld3D {
@@ -29,6 +36,9 @@ pub struct PhysicsWorld3D {
     pub entity_to_collider: HashMap<Entity, ColliderHandle>,
     pub body_to_entity: HashMap<RigidBodyHandle, Entity>,
     pub collider_to_entity: HashMap<ColliderHandle, Entity>,
+    /// Bodies whose rotation is advanced with the Lie group RK4 integrator
+    /// (maintained by [`physics_integration_method_system`])
+    pub lie_integrated_bodies: HashSet<RigidBodyHandle>,
 }
 
 impl Resource for PhysicsWorld3D {}
@@ -54,10 +64,320 @@ impl Default for PhysicsWorld3D {
             entity_to_collider: HashMap::new(),
             body_to_entity: HashMap::new(),
             collider_to_entity: HashMap::new(),
+            lie_integrated_bodies: HashSet::new(),
         }
     }
 }
 
+impl PhysicsWorld3D {
+    /// Advance the simulation by one fixed `timestep`
+    pub fn step(&mut self) {
+        self.integration_parameters.dt = self.timestep;
+
+        // Orientations the RK4 bodies start this step from
+        let lie_start: Vec<(RigidBodyHandle, Rotation<f32>)> = self
+            .lie_integrated_bodies
+            .iter()
+            .filter_map(|&handle| {
+                let body = self.rigid_body_set.get(handle)?;
+                (body.is_dynamic() && !body.is_sleeping()).then(|| (handle, *body.rotation()))
+            })
+            .collect();
+
+        // We need to borrow fields individually to call step
+        let PhysicsWorld3D {
+            ref gravity,
+            ref integration_parameters,
+            ref mut physics_pipeline,
+            ref mut island_manager,
+            ref mut broad_phase,
+            ref mut narrow_phase,
+            ref mut rigid_body_set,
+            ref mut collider_set,
+            ref mut impulse_joint_set,
+            ref mut multibody_joint_set,
+            ref mut ccd_solver,
+            ref mut query_pipeline,
+            ..
+        } = *self;
+
+        physics_pipeline.step(
+            gravity,
+            integration_parameters,
+            island_manager,
+            broad_phase,
+            narrow_phase,
+            rigid_body_set,
+            collider_set,
+            impulse_joint_set,
+            multibody_joint_set,
+            ccd_solver,
+            Some(query_pipeline),
+            &(),
+            &(),
+        );
+
+        self.apply_lie_integration(&lie_start);
+    }
+
+    /// Replace Rapier's orientation update for RK4 bodies
+    ///
+    /// Rapier has already solved the velocities (gravity, contacts, joints, damping) and
+    /// moved the centre of mass, so those stay authoritative. The solved angular velocity
+    /// is re-integrated from the pre-step orientation with the Lie group RK4 integrator,
+    /// which adds the gyroscopic term Rapier's integrator leaves out. The new pose is
+    /// written back before the next step, where Rapier picks it up as a user change.
+    fn apply_lie_integration(&mut self, start: &[(RigidBodyHandle, Rotation<f32>)]) {
+        let dt = self.integration_parameters.dt;
+
+        for (handle, start_rotation) in start {
+            let Some(body) = self.rigid_body_set.get_mut(*handle) else {
+                continue;
+            };
+            // Partially locked rotations are left to Rapier
+            if body.locked_axes().intersects(LockedAxes::ROTATION_LOCKED) {
+                continue;
+            }
+
+            let mprops = &body.mass_properties().local_mprops;
+            let inertia = mprops.principal_inertia();
+            let principal_inertia = Vec3::new(inertia.x, inertia.y, inertia.z);
+            let frame = &mprops.principal_inertia_local_frame;
+            let inertia_frame = Quat::from_xyzw(frame.i, frame.j, frame.k, frame.w);
+            let local_com = mprops.local_com;
+
+            let start_frame = Quat::from_xyzw(
+                start_rotation.i,
+                start_rotation.j,
+                start_rotation.k,
+                start_rotation.w,
+            ) * inertia_frame;
+            let angvel = body.angvel();
+            let principal_angvel = start_frame.inverse() * Vec3::new(angvel.x, angvel.y, angvel.z);
+
+            let (next_frame, next_angvel) = LiePhysicsIntegrator::integrate_rigid_body_rk4(
+                start_frame,
+                principal_angvel,
+                principal_inertia,
+                dt,
+            );
+            let r = next_frame * inertia_frame.inverse();
+            let rotation = rapier3d::na::UnitQuaternion::from_quaternion(
+                rapier3d::na::Quaternion::new(r.w, r.x, r.y, r.z),
+            );
+            let world_angvel = next_frame * next_angvel;
+
+            // Keep Rapier's centre of mass and derive the body origin from the new rotation
+            let world_com = *body.center_of_mass();
+            let translation = world_com.coords - rotation * local_com.coords;
+
+            body.set_position(
+                Isometry::from_parts(Translation::from(translation), rotation),
+                false,
+            );
+            body.set_angvel(
+                vector![world_angvel.x, world_angvel.y, world_angvel.z],
+                false,
+            );
+        }
+    }
+
+    /// Capture the complete simulation state
+    ///
+    /// The snapshot owns copies of every Rapier set plus the entity/handle maps, so the
+    /// world can keep simulating while the snapshot is stored for rollback, replays or
+    /// editor play sessions.
+    pub fn snapshot(&self) -> PhysicsSnapshot3D {
+        PhysicsSnapshot3D {
+            gravity: self.gravity,
+            accumulator: self.accumulator,
+            timestep: self.timestep,
+            integration_parameters: self.integration_parameters,
+            island_manager: self.island_manager.clone(),
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            rigid_body_set: self.rigid_body_set.clone(),
+            collider_set: self.collider_set.clone(),
+            impulse_joint_set: self.impulse_joint_set.clone(),
+            multibody_joint_set: self.multibody_joint_set.clone(),
+            ccd_solver: self.ccd_solver.clone(),
+            query_pipeline: self.query_pipeline.clone(),
+            entity_to_body: self.entity_to_body.iter().map(|(e, h)| (*e, *h)).collect(),
+            entity_to_collider: self
+                .entity_to_collider
+                .iter()
+                .map(|(e, h)| (*e, *h))
+                .collect(),
+            lie_integrated_bodies: self.lie_integrated_bodies.iter().copied().collect(),
+        }
+    }
+
+    /// Replace the simulation state with a previously captured snapshot
+    ///
+    /// Stepping after a restore produces bit-identical results to stepping the world the
+    /// snapshot was taken from. ECS `Transform`s are not touched; the sync system picks
+    /// up the restored body positions on the next frame. When entities may have been
+    /// spawned or despawned since the snapshot, use [`restore_physics_snapshot`] instead.
+    pub fn restore(&mut self, snapshot: &PhysicsSnapshot3D) {
+        self.gravity = snapshot.gravity;
+        self.accumulator = snapshot.accumulator;
+        self.timestep = snapshot.timestep;
+        self.integration_parameters = snapshot.integration_parameters;
+        self.island_manager = snapshot.island_manager.clone();
+        self.broad_phase = snapshot.broad_phase.clone();
+        self.narrow_phase = snapshot.narrow_phase.clone();
+        self.rigid_body_set = snapshot.rigid_body_set.clone();
+        self.collider_set = snapshot.collider_set.clone();
+        self.impulse_joint_set = snapshot.impulse_joint_set.clone();
+        self.multibody_joint_set = snapshot.multibody_joint_set.clone();
+        self.ccd_solver = snapshot.ccd_solver.clone();
+        self.query_pipeline = snapshot.query_pipeline.clone();
+
+        self.entity_to_body = snapshot.entity_to_body.iter().copied().collect();
+        self.body_to_entity = snapshot
+            .entity_to_body
+            .iter()
+            .map(|(e, h)| (*h, *e))
+            .collect();
+        self.entity_to_collider = snapshot.entity_to_collider.iter().copied().collect();
+        self.collider_to_entity = snapshot
+            .entity_to_collider
+            .iter()
+            .map(|(e, h)| (*h, *e))
+            .collect();
+        self.lie_integrated_bodies = snapshot.lie_integrated_bodies.iter().copied().collect();
+    }
+}
+
+/// Serializable copy of a [`PhysicsWorld3D`]'s simulation state
+///
+/// Created with [`PhysicsWorld3D::snapshot`] and applied with [`PhysicsWorld3D::restore`]
+/// or [`restore_physics_snapshot`].
+/// Entity maps are stored as pairs because Rapier handles are not valid map keys in
+/// every serialization format.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PhysicsSnapshot3D {
+    pub gravity: Vector<f32>,
+    pub accumulator: f32,
+    pub timestep: f32,
+    pub integration_parameters: IntegrationParameters,
+    pub island_manager: IslandManager,
+    pub broad_phase: DefaultBroadPhase,
+    pub narrow_phase: NarrowPhase,
+    pub rigid_body_set: RigidBodySet,
+    pub collider_set: ColliderSet,
+    pub impulse_joint_set: ImpulseJointSet,
+    pub multibody_joint_set: MultibodyJointSet,
+    pub ccd_solver: CCDSolver,
+    pub query_pipeline: QueryPipeline,
+    pub entity_to_body: Vec<(Entity, RigidBodyHandle)>,
+    pub entity_to_collider: Vec<(Entity, ColliderHandle)>,
+    pub lie_integrated_bodies: Vec<RigidBodyHandle>,
+}
+
+impl PhysicsSnapshot3D {
+    /// Encode the snapshot into a compact binary form (e.g. for replay files)
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    /// Decode a snapshot produced by [`PhysicsSnapshot3D::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
+/// Restore a [`PhysicsSnapshot3D`] and reconcile the ECS with it
+///
+/// Bodies and colliders of entities despawned since the snapshot are removed from the
+/// restored world. Entities whose [`PhysicsBodyCreated`] or [`PhysicsColliderCreated`]
+/// marker has no handle in the snapshot (e.g. spawned after it) lose the marker, so the
+/// creation systems rebuild them on the next frame. Terrain heightfields are
+/// reconciled the same way.
+pub fn restore_physics_snapshot(
+    world: &mut luminara_core::world::World,
+    snapshot: &PhysicsSnapshot3D,
+) {
+    let alive: HashSet<Entity> = world.entities().into_iter().collect();
+
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld3D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+        physics_world.restore(snapshot);
+
+        // Drop what belonged to entities that no longer exist
+        let dead_bodies: Vec<RigidBodyHandle> = physics_world
+            .body_to_entity
+            .iter()
+            .filter(|(_, entity)| !alive.contains(entity))
+            .map(|(handle, _)| *handle)
+            .collect();
+        for handle in dead_bodies {
+            physics_world.rigid_body_set.remove(
+                handle,
+                &mut physics_world.island_manager,
+                &mut physics_world.collider_set,
+                &mut physics_world.impulse_joint_set,
+                &mut physics_world.multibody_joint_set,
+                true,
+            );
+            physics_world.lie_integrated_bodies.remove(&handle);
+        }
+        let dead_colliders: Vec<ColliderHandle> = physics_world
+            .collider_to_entity
+            .iter()
+            .filter(|(_, entity)| !alive.contains(entity))
+            .map(|(handle, _)| *handle)
+            .collect();
+        for handle in dead_colliders {
+            physics_world.collider_set.remove(
+                handle,
+                &mut physics_world.island_manager,
+                &mut physics_world.rigid_body_set,
+                true,
+            );
+        }
+
+        let PhysicsWorld3D {
+            ref rigid_body_set,
+            ref collider_set,
+            ref mut entity_to_body,
+            ref mut body_to_entity,
+            ref mut entity_to_collider,
+            ref mut collider_to_entity,
+            ..
+        } = *physics_world;
+        entity_to_body.retain(|_, handle| rigid_body_set.contains(*handle));
+        body_to_entity.retain(|handle, _| rigid_body_set.contains(*handle));
+        entity_to_collider.retain(|_, handle| collider_set.contains(*handle));
+        collider_to_entity.retain(|handle, _| collider_set.contains(*handle));
+    }
+
+    let (stale_bodies, stale_colliders) = {
+        let physics_world = world.get_resource::<PhysicsWorld3D>().unwrap();
+        let stale_bodies: Vec<Entity> = Query::<(Entity, &PhysicsBodyCreated)>::new(world)
+            .iter()
+            .map(|(entity, _)| entity)
+            .filter(|entity| !physics_world.entity_to_body.contains_key(entity))
+            .collect();
+        let stale_colliders: Vec<Entity> = Query::<(Entity, &PhysicsColliderCreated)>::new(world)
+            .iter()
+            .map(|(entity, _)| entity)
+            .filter(|entity| !physics_world.entity_to_collider.contains_key(entity))
+            .collect();
+        (stale_bodies, stale_colliders)
+    };
+    for entity in stale_bodies {
+        let _ = world.remove_component::<PhysicsBodyCreated>(entity);
+    }
+    for entity in stale_colliders {
+        let _ = world.remove_component::<PhysicsColliderCreated>(entity);
+    }
+    crate::terrain::reconcile_terrain_bodies(world);
+}
+
 /// Collision events resource
 pub struct CollisionEvents(pub Vec<CollisionEvent>);
 
@@ -88,6 +408,16 @@ impl Plugin for PhysicsPlugin {
         app.world
             .insert_resource(crate::debug::PhysicsDebugConfig::default());
 
+        // Register integration config (Euler unless changed)
+        app.world
+            .insert_resource(PhysicsIntegrationConfig::default());
+
+        // Register ragdoll profile loader (`.ragdoll` RON files) once the asset server exists
+        app.add_system::<ExclusiveMarker>(
+            CoreStage::Startup,
+            crate::ragdoll::register_ragdoll_profile_loader,
+        );
+
         // Register physics body/collider creation as exclusive systems (need world mutation)
         app.add_system::<ExclusiveMarker>(
             CoreStage::PreUpdate,
@@ -98,6 +428,27 @@ impl Plugin for PhysicsPlugin {
             physics_collider_creation_system_exclusive,
         );
 
+        // Select the integrator for each body from the config and per-entity overrides
+        app.add_system::<ExclusiveMarker>(CoreStage::PreUpdate, physics_integration_method_system);
+
+        // Build terrain heightfields before characters are moved against them
+        app.add_system::<ExclusiveMarker>(
+            CoreStage::PreUpdate,
+            crate::terrain::terrain_collider_system,
+        );
+
+        // Resolve character controller movement once bodies and colliders exist
+        app.add_system::<ExclusiveMarker>(
+            CoreStage::PreUpdate,
+            crate::character_controller::character_controller_system,
+        );
+
+        // Drive ragdoll parts from the animated pose (or apply queued hit impulses)
+        app.add_system::<ExclusiveMarker>(
+            CoreStage::PreUpdate,
+            crate::ragdoll::ragdoll_drive_system,
+        );
+
         // Register physics step system
         app.add_system::<(
             luminara_core::system::FunctionMarker,
@@ -113,6 +464,15 @@ impl Plugin for PhysicsPlugin {
             Query<'static, (Entity, &mut Transform, &RigidBody, &PreviousTransform)>,
         )>(CoreStage::PostUpdate, physics_sync_system);
 
+        // Write simulated ragdoll poses back to the bone entities
+        app.add_system::<ExclusiveMarker>(
+            CoreStage::PostUpdate,
+            crate::ragdoll::ragdoll_pose_system,
+        );
+
+        // Let particle effects collide with the settled collider positions
+        app.add_system::<ExclusiveMarker>(CoreStage::PostUpdate, particle_collider_sync_system);
+
         // Register collision detection system
         app.add_system::<(
             luminara_core::system::FunctionMarker,
@@ -217,8 +577,187 @@ pub fn physics_body_creation_system_exclusive(world: &mut luminara_core::world::
     }
 }
 
+/// Convert an ECS transform into a Rapier isometry (scale is ignored)
+pub(crate) fn transform_to_isometry(transform: &Transform) -> Isometry<f32> {
+    let t = transform.translation;
+    let r = transform.rotation;
+    Isometry::from_parts(
+        Translation::new(t.x, t.y, t.z),
+        rapier3d::na::UnitQuaternion::from_quaternion(rapier3d::na::Quaternion::new(
+            r.w, r.x, r.y, r.z,
+        )),
+    )
+}
+
+/// Build the Rapier shape for a [`ColliderShape`]
+///
+/// Returns `None` when the shape data is degenerate (e.g. a convex hull of coplanar
+/// points or a heightfield with fewer than 2x2 samples).
+pub fn build_collider_shape(shape: &ColliderShape) -> Option<SharedShape> {
+    let to_points = |points: &[Vec3]| -> Vec<Point<f32>> {
+        points.iter().map(|v| point![v.x, v.y, v.z]).collect()
+    };
+
+    let shape = match shape {
+        ColliderShape::Box { half_extents } => {
+            SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z)
+        }
+        ColliderShape::Sphere { radius } => SharedShape::ball(*radius),
+        ColliderShape::Capsule {
+            half_height,
+            radius,
+        } => SharedShape::capsule_y(*half_height, *radius),
+        ColliderShape::Mesh { vertices, indices } => {
+            SharedShape::trimesh(to_points(vertices), indices.clone())
+        }
+        ColliderShape::Cylinder {
+            half_height,
+            radius,
+        } => SharedShape::cylinder(*half_height, *radius),
+        ColliderShape::Cone {
+            half_height,
+            radius,
+        } => SharedShape::cone(*half_height, *radius),
+        ColliderShape::ConvexHull { points } => {
+            // Parry accepts flat hulls; reject them so they can't produce zero mass.
+            // The volume is compared to the bounds so small but solid hulls pass
+            let hull = SharedShape::convex_hull(&to_points(points))?;
+            let extents = hull.compute_local_aabb().extents();
+            if hull.mass_properties(1.0).mass() <= extents.x * extents.y * extents.z * 1.0e-4 {
+                return None;
+            }
+            hull
+        }
+        ColliderShape::Heightfield {
+            heights,
+            rows,
+            cols,
+            scale,
+        } => {
+            if *rows < 2 || *cols < 2 || heights.len() != rows * cols {
+                return None;
+            }
+            let matrix = rapier3d::na::DMatrix::from_fn(*rows, *cols, |r, c| heights[r * cols + c]);
+            SharedShape::heightfield(matrix, vector![scale.x, scale.y, scale.z])
+        }
+        ColliderShape::Compound { children } => {
+            let mut parts = Vec::new();
+            collect_compound_parts(children, &Isometry::identity(), &mut parts);
+            if parts.is_empty() {
+                return None;
+            }
+            SharedShape::compound(parts)
+        }
+        ColliderShape::ConvexDecomposition {
+            vertices,
+            indices,
+            settings,
+        } => {
+            let params = rapier3d::parry::transformation::vhacd::VHACDParameters {
+                resolution: settings.resolution,
+                concavity: settings.concavity,
+                plane_downsampling: settings.plane_downsampling,
+                convex_hull_downsampling: settings.convex_hull_downsampling,
+                ..Default::default()
+            };
+            SharedShape::convex_decomposition_with_params(&to_points(vertices), indices, &params)
+        }
+    };
+
+    Some(shape)
+}
+
+/// Flatten compound children into Rapier parts
+///
+/// Rapier compounds cannot contain composite shapes, so nested compounds are flattened
+/// and triangle meshes, heightfields and convex decompositions are skipped.
+fn collect_compound_parts(
+    children: &[crate::components::CompoundChild],
+    parent: &Isometry<f32>,
+    parts: &mut Vec<(Isometry<f32>, SharedShape)>,
+) {
+    for child in children {
+        let local = transform_to_isometry(&Transform {
+            translation: child.translation,
+            rotation: child.rotation,
+            scale: Vec3::ONE,
+        });
+        let position = parent * local;
+
+        match &child.shape {
+            ColliderShape::Compound { children } => {
+                collect_compound_parts(children, &position, parts);
+            }
+            ColliderShape::Mesh { .. }
+            | ColliderShape::Heightfield { .. }
+            | ColliderShape::ConvexDecomposition { .. } => {
+                log::warn!("Composite shapes cannot be nested in a compound collider, skipping");
+            }
+            shape => {
+                if let Some(shape) = build_collider_shape(shape) {
+                    parts.push((position, shape));
+                }
+            }
+        }
+    }
+}
+
+/// Where a new collider should be attached
+enum ColliderAttachment {
+    /// Attach to a rigid body, offset by the given local position
+    Body(RigidBodyHandle, Isometry<f32>),
+    /// No rigid body in the hierarchy: a free collider placed in world space
+    Free(Isometry<f32>),
+}
+
+/// Find the nearest rigid body on the entity or its ancestors
+///
+/// The collider offset is the composition of the local `Transform`s between the
+/// collider entity and the body entity. Returns `None` while a `RigidBody` in the
+/// hierarchy is still waiting for its Rapier body to be created.
+fn resolve_collider_attachment(
+    world: &luminara_core::world::World,
+    physics_world: &PhysicsWorld3D,
+    entity: Entity,
+) -> Option<ColliderAttachment> {
+    if let Some(&handle) = physics_world.entity_to_body.get(&entity) {
+        return Some(ColliderAttachment::Body(handle, Isometry::identity()));
+    }
+    if world.get_component::<RigidBody>(entity).is_some() {
+        return None;
+    }
+
+    let mut offset = world
+        .get_component::<Transform>(entity)
+        .copied()
+        .unwrap_or_default();
+    let mut current = entity;
+
+    while let Some(parent) = world.get_component::<Parent>(current).map(|p| p.0) {
+        if let Some(&handle) = physics_world.entity_to_body.get(&parent) {
+            return Some(ColliderAttachment::Body(
+                handle,
+                transform_to_isometry(&offset),
+            ));
+        }
+        if world.get_component::<RigidBody>(parent).is_some() {
+            return None;
+        }
+        if let Some(parent_transform) = world.get_component::<Transform>(parent) {
+            offset = parent_transform.mul_transform(&offset);
+        }
+        current = parent;
+    }
+
+    Some(ColliderAttachment::Free(transform_to_isometry(&offset)))
+}
+
 /// System to create colliders for entities with Collider components
 /// (Exclusive system — needs mutable World access to add marker components)
+///
+/// Colliders attach to the rigid body on the same entity, or else to the nearest
+/// ancestor (via `Parent`) that has one. Colliders with no body in their hierarchy
+/// become free colliders positioned from their `Transform`.
 pub fn physics_collider_creation_system_exclusive(world: &mut luminara_core::world::World) {
     // Phase 1: collect entities + component data
     let entities_to_create: Vec<(Entity, Collider)> = {
@@ -235,20 +774,19 @@ pub fn physics_collider_creation_system_exclusive(world: &mut luminara_core::wor
 
     // Phase 2: create Rapier colliders
     for (entity, collider) in &entities_to_create {
-        let shape: SharedShape = match &collider.shape {
-            ColliderShape::Box { half_extents } => {
-                SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z)
-            }
-            ColliderShape::Sphere { radius } => SharedShape::ball(*radius),
-            ColliderShape::Capsule {
-                half_height,
-                radius,
-            } => SharedShape::capsule_y(*half_height, *radius),
-            ColliderShape::Mesh { vertices, indices } => {
-                let vertices: Vec<Point<f32>> =
-                    vertices.iter().map(|v| point![v.x, v.y, v.z]).collect();
-                SharedShape::trimesh(vertices, indices.clone())
-            }
+        let attachment = {
+            let physics_world = world.get_resource::<PhysicsWorld3D>().unwrap();
+            resolve_collider_attachment(world, &physics_world, *entity)
+        };
+        let Some(attachment) = attachment else {
+            // Retry next frame once the body exists
+            continue;
+        };
+
+        let Some(shape) = build_collider_shape(&collider.shape) else {
+            log::warn!("Skipping degenerate collider shape on entity {:?}", entity);
+            let _ = world.add_component(*entity, PhysicsColliderCreated);
+            continue;
         };
 
         let rapier_collider = ColliderBuilder::new(shape)
@@ -259,9 +797,11 @@ pub fn physics_collider_creation_system_exclusive(world: &mut luminara_core::wor
 
         {
             let mut physics_world = world.get_resource_mut::<PhysicsWorld3D>().unwrap();
-            // Attach to rigid body if it exists
-            let collider_handle =
-                if let Some(&body_handle) = physics_world.entity_to_body.get(entity) {
+            let collider_handle = match attachment {
+                ColliderAttachment::Body(body_handle, local) => {
+                    let mut rapier_collider = rapier_collider;
+                    // Rapier takes the offset from the collider position on insertion
+                    rapier_collider.set_position(local);
                     let mut rigid_body_set = std::mem::take(&mut physics_world.rigid_body_set);
                     let handle = physics_world.collider_set.insert_with_parent(
                         rapier_collider,
@@ -270,9 +810,13 @@ pub fn physics_collider_creation_system_exclusive(world: &mut luminara_core::wor
                     );
                     physics_world.rigid_body_set = rigid_body_set;
                     handle
-                } else {
+                }
+                ColliderAttachment::Free(position) => {
+                    let mut rapier_collider = rapier_collider;
+                    rapier_collider.set_position(position);
                     physics_world.collider_set.insert(rapier_collider)
-                };
+                }
+            };
 
             physics_world
                 .entity_to_collider
@@ -288,6 +832,38 @@ pub fn physics_collider_creation_system_exclusive(world: &mut luminara_core::wor
     }
 }
 
+/// System to select the integration method of every physics body
+/// (Exclusive system — reads overrides and the config before mutating the physics world)
+///
+/// Bodies resolved to [`IntegrationMethod::Rk4`] through [`IntegrationMethodOverride`] or
+/// [`PhysicsIntegrationConfig::default_method`] are recorded in
+/// [`PhysicsWorld3D::lie_integrated_bodies`] for the next physics step.
+pub fn physics_integration_method_system(world: &mut luminara_core::world::World) {
+    let config = world
+        .get_resource::<PhysicsIntegrationConfig>()
+        .map(|config| config.clone())
+        .unwrap_or_default();
+
+    let overrides: HashMap<Entity, IntegrationMethodOverride> = {
+        let query = Query::<(Entity, &IntegrationMethodOverride)>::new(world);
+        query
+            .iter()
+            .map(|(entity, method_override)| (entity, *method_override))
+            .collect()
+    };
+
+    let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld3D>() else {
+        return;
+    };
+    let physics_world = &mut *physics_world;
+
+    physics_world.lie_integrated_bodies = physics_world
+        .entity_to_body
+        .iter()
+        .filter(|(entity, _)| config.method_for(overrides.get(entity)) == IntegrationMethod::Rk4)
+        .map(|(_, handle)| *handle)
+        .collect();
+}
 
 /// System to step the physics simulation
 pub fn physics_step_system(
@@ -322,42 +898,7 @@ pub fn physics_step_system(
         }
 
         physics_world.accumulator -= timestep;
-
-        // Update integration parameters for fixed step
-        physics_world.integration_parameters.dt = timestep;
-
-        // We need to borrow fields individually to call step
-        let PhysicsWorld3D {
-            ref gravity,
-            ref integration_parameters,
-            ref mut physics_pipeline,
-            ref mut island_manager,
-            ref mut broad_phase,
-            ref mut narrow_phase,
-            ref mut rigid_body_set,
-            ref mut collider_set,
-            ref mut impulse_joint_set,
-            ref mut multibody_joint_set,
-            ref mut ccd_solver,
-            ref mut query_pipeline,
-            ..
-        } = *physics_world;
-
-        physics_pipeline.step(
-            gravity,
-            integration_parameters,
-            island_manager,
-            broad_phase,
-            narrow_phase,
-            rigid_body_set,
-            collider_set,
-            impulse_joint_set,
-            multibody_joint_set,
-            ccd_solver,
-            Some(query_pipeline),
-            &(),
-            &(),
-        );
+        physics_world.step();
     }
 }
 
@@ -425,3 +966,52 @@ pub fn collision_detection_system(
         }
     }
 }
+
+/// Approximate a collider for particle collisions; shapes without a cheap
+/// distance function are skipped
+pub fn particle_collider(shape: &ColliderShape, transform: &Transform) -> Option<ParticleCollider> {
+    let scale = transform.scale.abs();
+    match shape {
+        ColliderShape::Box { half_extents } => Some(ParticleCollider::Box {
+            center: transform.translation,
+            rotation: transform.rotation,
+            half_extents: *half_extents * scale,
+        }),
+        ColliderShape::Sphere { radius } => Some(ParticleCollider::Sphere {
+            center: transform.translation,
+            radius: radius * scale.max_element(),
+        }),
+        ColliderShape::Capsule {
+            half_height,
+            radius,
+        } => {
+            let axis = transform.rotation * Vec3::Y * (half_height * scale.y);
+            Some(ParticleCollider::Capsule {
+                a: transform.translation - axis,
+                b: transform.translation + axis,
+                radius: radius * scale.x.max(scale.z),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Mirror solid colliders into [`ParticleColliders`], placed by their world
+/// transform so colliders on child entities line up with what gets rendered
+pub fn particle_collider_sync_system(world: &mut luminara_core::world::World) {
+    let colliders: Vec<ParticleCollider> = Query::<(Entity, &Collider)>::new(&*world)
+        .iter()
+        .filter(|(_, collider)| !collider.is_sensor)
+        .filter_map(|(entity, collider)| {
+            let transform = world
+                .get_component::<GlobalTransform>(entity)
+                .map(|global| global.0)
+                .or_else(|| world.get_component::<Transform>(entity).copied())?;
+            particle_collider(&collider.shape, &transform)
+        })
+        .collect();
+
+    if let Some(mut particle_colliders) = world.get_resource_mut::<ParticleColliders>() {
+        particle_colliders.0 = colliders;
+    }
+}
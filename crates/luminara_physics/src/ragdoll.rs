@@ -0,0 +1,1001 @@
+use luminara_asset::{Asset, AssetLoadError, AssetLoader, AssetServer};
+use luminara_core::{Component, Entity, Query, Resource};
+use luminara_math::{Quat, Transform, Vec3};
+use luminara_render::{Skeleton, SkinnedMesh};
+use luminara_scene::{GlobalTransform, Parent};
+use rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::physics3d::{transform_to_isometry, PhysicsWorld3D};
+
+/// Joint connecting a ragdoll bone to its parent part
+///
+/// Angles are in radians. The twist axis of a spherical joint is the bone's own axis
+/// (from the bone origin towards its children); the hinge axis of a revolute joint is
+/// given in the bone's local frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RagdollJoint {
+    /// Ball-and-socket joint with a symmetric swing cone and a twist range
+    Spherical {
+        swing_limit: f32,
+        twist_min: f32,
+        twist_max: f32,
+    },
+    /// Hinge joint around `axis` limited to `[min, max]`
+    Revolute { axis: Vec3, min: f32, max: f32 },
+    /// Rigidly welded to the parent part
+    Fixed,
+}
+
+impl RagdollJoint {
+    /// Spherical joint with a swing cone of `swing_deg` and a symmetric twist of `twist_deg`
+    pub fn spherical_deg(swing_deg: f32, twist_deg: f32) -> Self {
+        Self::Spherical {
+            swing_limit: swing_deg.to_radians(),
+            twist_min: -twist_deg.to_radians(),
+            twist_max: twist_deg.to_radians(),
+        }
+    }
+
+    /// Revolute joint around `axis` limited to `[min_deg, max_deg]`
+    pub fn revolute_deg(axis: Vec3, min_deg: f32, max_deg: f32) -> Self {
+        Self::Revolute {
+            axis,
+            min: min_deg.to_radians(),
+            max: max_deg.to_radians(),
+        }
+    }
+}
+
+/// Per-bone settings of a [`RagdollProfile`]
+///
+/// Rules are matched against bone names in order; the first rule whose `pattern` is
+/// contained in the (lowercase) bone name applies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RagdollBoneRule {
+    /// Lowercase substring matched against bone names
+    pub pattern: String,
+    /// Bones matching this rule get no body and follow their parent part
+    #[serde(default)]
+    pub skip: bool,
+    /// Joint to the parent part, `None` uses the profile's default joint
+    #[serde(default)]
+    pub joint: Option<RagdollJoint>,
+    /// Capsule radius, `None` derives it from the bone length
+    #[serde(default)]
+    pub radius: Option<f32>,
+    /// Multiplier applied to the bone's volume-based share of the total mass
+    #[serde(default = "default_mass_scale")]
+    pub mass_scale: f32,
+}
+
+fn default_mass_scale() -> f32 {
+    1.0
+}
+
+impl RagdollBoneRule {
+    fn new(pattern: &str, joint: RagdollJoint) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            skip: false,
+            joint: Some(joint),
+            radius: None,
+            mass_scale: 1.0,
+        }
+    }
+
+    fn skip(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            skip: true,
+            joint: None,
+            radius: None,
+            mass_scale: 1.0,
+        }
+    }
+}
+
+/// Ragdoll profile asset (RON, `.ragdoll` extension)
+///
+/// Describes how a skeleton is turned into a ragdoll: capsule sizing, mass, damping,
+/// joint limits per bone and the blend time back to animation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RagdollProfile {
+    /// Total mass distributed over all parts by capsule volume
+    pub total_mass: f32,
+    /// Capsule radius as a fraction of the bone length
+    pub radius_ratio: f32,
+    /// Lower bound for derived capsule radii
+    pub min_radius: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    /// Joint used for bones without a matching rule (or a rule without a joint)
+    pub default_joint: RagdollJoint,
+    /// Seconds to blend from the physics pose back to the animated pose
+    pub blend_duration: f32,
+    pub rules: Vec<RagdollBoneRule>,
+}
+
+impl Asset for RagdollProfile {
+    fn type_name() -> &'static str {
+        "RagdollProfile"
+    }
+}
+
+impl Default for RagdollProfile {
+    fn default() -> Self {
+        Self::humanoid()
+    }
+}
+
+impl RagdollProfile {
+    /// Anatomical limits for common humanoid rig naming (Mixamo, Unreal, Blender Rigify)
+    ///
+    /// Hinge axes assume the bone's local X axis is the flexion axis; rigs with other
+    /// conventions should override the elbow and knee rules.
+    pub fn humanoid() -> Self {
+        let elbow = RagdollJoint::revolute_deg(Vec3::X, 0.0, 145.0);
+        let knee = RagdollJoint::revolute_deg(Vec3::X, -145.0, 0.0);
+        let hip = RagdollJoint::spherical_deg(70.0, 30.0);
+        let clavicle = RagdollJoint::spherical_deg(15.0, 10.0);
+        let spine = RagdollJoint::spherical_deg(20.0, 15.0);
+
+        let mut rules: Vec<RagdollBoneRule> = [
+            "finger", "thumb", "index", "middle", "ring", "pinky", "toe", "eye", "jaw", "twist",
+            "_end",
+        ]
+        .into_iter()
+        .map(RagdollBoneRule::skip)
+        .collect();
+        rules.extend(
+            [
+                ("forearm", elbow),
+                ("lowerarm", elbow),
+                ("elbow", elbow),
+                ("upleg", hip),
+                ("thigh", hip),
+                ("upperleg", hip),
+                ("shin", knee),
+                ("calf", knee),
+                ("knee", knee),
+                ("lowerleg", knee),
+                ("leg", knee),
+                ("clavicle", clavicle),
+                ("shoulder", clavicle),
+                ("arm", RagdollJoint::spherical_deg(80.0, 45.0)),
+                ("hand", RagdollJoint::spherical_deg(40.0, 30.0)),
+                ("foot", RagdollJoint::spherical_deg(30.0, 15.0)),
+                ("spine", spine),
+                ("chest", spine),
+                ("neck", RagdollJoint::spherical_deg(30.0, 30.0)),
+                ("head", RagdollJoint::spherical_deg(30.0, 40.0)),
+            ]
+            .into_iter()
+            .map(|(pattern, joint)| RagdollBoneRule::new(pattern, joint)),
+        );
+
+        Self {
+            total_mass: 70.0,
+            radius_ratio: 0.25,
+            min_radius: 0.03,
+            linear_damping: 0.1,
+            angular_damping: 0.8,
+            default_joint: RagdollJoint::spherical_deg(30.0, 20.0),
+            blend_duration: 0.5,
+            rules,
+        }
+    }
+
+    /// Rule applying to a bone, if any
+    pub fn rule_for(&self, bone_name: &str) -> Option<&RagdollBoneRule> {
+        let name = bone_name.to_lowercase();
+        self.rules
+            .iter()
+            .find(|rule| name.contains(&rule.pattern.to_lowercase()))
+    }
+
+    /// Parse a profile from RON
+    pub fn from_ron(source: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(source)
+    }
+}
+
+/// Asset loader for [`RagdollProfile`] RON files
+pub struct RagdollProfileLoader;
+
+impl AssetLoader for RagdollProfileLoader {
+    type Asset = RagdollProfile;
+
+    fn extensions(&self) -> &[&str] {
+        &["ragdoll"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let source =
+            std::str::from_utf8(bytes).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+        RagdollProfile::from_ron(source).map_err(|e| AssetLoadError::Parse(e.to_string()))
+    }
+}
+
+pub(crate) fn register_ragdoll_profile_loader(world: &mut luminara_core::world::World) {
+    if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
+        asset_server.register_loader(RagdollProfileLoader);
+    }
+}
+
+/// Whether a ragdoll follows its animation or the physics simulation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RagdollMode {
+    /// Parts are kinematic and follow the animated bones
+    Animated,
+    /// Parts are dynamic and drive the bone entities
+    Simulated,
+    /// Parts are kinematic again while the bones blend from the last physics pose
+    /// back to the animated pose
+    BlendingToAnimation { elapsed: f32, duration: f32 },
+}
+
+/// Physics objects created for one ragdoll bone
+#[derive(Debug, Clone, Copy)]
+pub struct RagdollPart {
+    pub body: RigidBodyHandle,
+    pub collider: ColliderHandle,
+    /// Joint to the parent part (`None` for the root part)
+    pub joint: Option<ImpulseJointHandle>,
+}
+
+/// Ragdoll attached to an animated character
+///
+/// Add to the character's root entity. Bone entities (usually the `SkinnedMesh` joints)
+/// keep their local `Transform`s relative to the skeleton hierarchy. Root bones are
+/// relative to their [`Parent`] entity, or to the character entity when they have none.
+/// Parts are created on the first update and follow the animation until
+/// [`Ragdoll::simulate`] hands the bones over to physics. They are removed with the
+/// character entity or its `Ragdoll`.
+#[derive(Debug, Clone)]
+pub struct Ragdoll {
+    pub profile: RagdollProfile,
+    /// Bone entity for every skeleton bone
+    pub bones: Vec<Entity>,
+    pub bone_names: Vec<String>,
+    /// Parent bone index for every skeleton bone
+    pub parents: Vec<Option<usize>>,
+    mode: RagdollMode,
+    parts: Vec<Option<RagdollPart>>,
+    built: bool,
+    /// Bind-pose local transforms, used when a bone entity has no `Transform`
+    bind_pose: Vec<Transform>,
+    /// Local bone transforms written while simulated, blended from when switching back
+    physics_pose: Vec<Transform>,
+    pending_impulses: Vec<(usize, Vec3)>,
+}
+
+impl Component for Ragdoll {
+    fn type_name() -> &'static str {
+        "Ragdoll"
+    }
+}
+
+impl Ragdoll {
+    /// Create a ragdoll for `skeleton`, with `bones[i]` being the entity of bone `i`
+    pub fn new(skeleton: &Skeleton, bones: Vec<Entity>, profile: RagdollProfile) -> Self {
+        let bind_pose: Vec<Transform> = skeleton
+            .bones
+            .iter()
+            .map(|bone| {
+                let (scale, rotation, translation) =
+                    bone.local_transform.to_scale_rotation_translation();
+                Transform {
+                    translation,
+                    rotation,
+                    scale,
+                }
+            })
+            .collect();
+
+        Self {
+            profile,
+            bone_names: skeleton.bones.iter().map(|b| b.name.clone()).collect(),
+            parents: skeleton.hierarchy.clone(),
+            mode: RagdollMode::Animated,
+            parts: vec![None; bones.len()],
+            built: false,
+            physics_pose: bind_pose.clone(),
+            bind_pose,
+            bones,
+            pending_impulses: Vec::new(),
+        }
+    }
+
+    /// Create a ragdoll from a skinned mesh's joints and embedded skeleton
+    pub fn from_skinned_mesh(mesh: &SkinnedMesh, profile: RagdollProfile) -> Option<Self> {
+        let skeleton = mesh.skeleton.as_ref()?;
+        if skeleton.bones.len() != mesh.joints.len() {
+            return None;
+        }
+        Some(Self::new(skeleton, mesh.joints.clone(), profile))
+    }
+
+    pub fn mode(&self) -> RagdollMode {
+        self.mode
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        self.mode == RagdollMode::Simulated
+    }
+
+    /// Hand the bones over to physics, keeping the current animated motion
+    pub fn simulate(&mut self) {
+        self.mode = RagdollMode::Simulated;
+    }
+
+    /// Blend back to animation over the profile's `blend_duration`
+    pub fn blend_to_animation(&mut self) {
+        self.blend_to_animation_over(self.profile.blend_duration);
+    }
+
+    /// Blend back to animation over `duration` seconds (0 snaps immediately)
+    pub fn blend_to_animation_over(&mut self, duration: f32) {
+        if self.mode == RagdollMode::Animated {
+            return;
+        }
+        self.mode = RagdollMode::BlendingToAnimation {
+            elapsed: 0.0,
+            duration: duration.max(0.0),
+        };
+    }
+
+    /// Queue a world-space impulse on a bone's part, applied once it is simulated
+    pub fn apply_impulse(&mut self, bone: usize, impulse: Vec3) {
+        self.pending_impulses.push((bone, impulse));
+    }
+
+    /// Index of the bone called `name`
+    pub fn bone_index(&self, name: &str) -> Option<usize> {
+        self.bone_names.iter().position(|n| n == name)
+    }
+
+    /// Physics objects of a bone (`None` for skipped bones or before the first update)
+    pub fn part(&self, bone: usize) -> Option<&RagdollPart> {
+        self.parts.get(bone)?.as_ref()
+    }
+
+    /// Remove every body, collider and joint of this ragdoll from the physics world
+    pub fn remove_parts(&mut self, physics_world: &mut PhysicsWorld3D) {
+        for part in self.parts.iter_mut() {
+            if let Some(part) = part.take() {
+                remove_part_body(physics_world, part.body);
+            }
+        }
+        self.built = false;
+    }
+
+    /// Whether `bone` is a root of the skeleton within `bone_count` bones
+    fn is_root(&self, bone: usize, bone_count: usize) -> bool {
+        !matches!(self.parents.get(bone).copied().flatten(), Some(parent) if parent < bone_count)
+    }
+
+    /// World transforms of all bones given their local transforms and the frame of
+    /// every root bone (`roots[i]` is ignored for other bones)
+    fn world_pose(&self, roots: &[Transform], locals: &[Transform]) -> Vec<Transform> {
+        let mut world: Vec<Option<Transform>> = vec![None; locals.len()];
+        for bone in 0..locals.len() {
+            self.resolve_world(bone, roots, locals, &mut world);
+        }
+        world
+            .into_iter()
+            .zip(roots)
+            .map(|(t, root)| t.unwrap_or(*root))
+            .collect()
+    }
+
+    fn resolve_world(
+        &self,
+        bone: usize,
+        roots: &[Transform],
+        locals: &[Transform],
+        world: &mut [Option<Transform>],
+    ) -> Transform {
+        if let Some(resolved) = world[bone] {
+            return resolved;
+        }
+        // Guard against malformed (cyclic) hierarchies
+        world[bone] = Some(roots[bone]);
+        let parent_world = match self.parents.get(bone).copied().flatten() {
+            Some(parent) if parent < locals.len() => {
+                self.resolve_world(parent, roots, locals, world)
+            }
+            _ => roots[bone],
+        };
+        let resolved = parent_world.mul_transform(&locals[bone]);
+        world[bone] = Some(resolved);
+        resolved
+    }
+
+    /// Nearest ancestor of `bone` that has a physics part
+    fn parent_part(&self, bone: usize) -> Option<usize> {
+        let mut current = self.parents.get(bone).copied().flatten();
+        let mut guard = 0;
+        while let Some(index) = current {
+            if self.parts.get(index).is_some_and(|p| p.is_some()) {
+                return Some(index);
+            }
+            current = self.parents.get(index).copied().flatten();
+            guard += 1;
+            if guard > self.parents.len() {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Create bodies, colliders and joints from the current bone pose
+    fn build_parts(&mut self, physics_world: &mut PhysicsWorld3D, world_pose: &[Transform]) {
+        let bone_count = world_pose.len();
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); bone_count];
+        for (bone, parent) in self.parents.iter().enumerate().take(bone_count) {
+            if let Some(parent) = parent.filter(|p| *p < bone_count) {
+                children[parent].push(bone);
+            }
+        }
+
+        // Capsule segment (in the bone's frame) and radius for every simulated bone
+        struct PartShape {
+            end: Vec3,
+            radius: f32,
+            volume: f32,
+            mass_scale: f32,
+        }
+        let mut shapes: Vec<Option<PartShape>> = Vec::with_capacity(bone_count);
+        for (bone, pose) in world_pose.iter().enumerate() {
+            let rule = self.profile.rule_for(&self.bone_names[bone]);
+            if rule.is_some_and(|r| r.skip) {
+                shapes.push(None);
+                continue;
+            }
+
+            let to_local = |p: Vec3| pose.rotation.inverse() * (p - pose.translation);
+            let end = if !children[bone].is_empty() {
+                let sum: Vec3 = children[bone]
+                    .iter()
+                    .map(|&c| to_local(world_pose[c].translation))
+                    .sum();
+                sum / children[bone].len() as f32
+            } else if let Some(parent) = self
+                .parents
+                .get(bone)
+                .copied()
+                .flatten()
+                .filter(|p| *p < bone_count)
+            {
+                // Leaf: continue along the incoming direction for half its length
+                let incoming = pose.translation - world_pose[parent].translation;
+                to_local(pose.translation + incoming * 0.5)
+            } else {
+                Vec3::ZERO
+            };
+
+            let length = end.length();
+            let radius = rule
+                .and_then(|r| r.radius)
+                .unwrap_or(length * self.profile.radius_ratio)
+                .max(self.profile.min_radius);
+            let half_height = (length * 0.5 - radius).max(0.0);
+            let volume =
+                std::f32::consts::PI * radius * radius * (2.0 * half_height + 4.0 / 3.0 * radius);
+            shapes.push(Some(PartShape {
+                end,
+                radius,
+                volume,
+                mass_scale: rule.map(|r| r.mass_scale).unwrap_or(1.0),
+            }));
+        }
+
+        let total_weight: f32 = shapes
+            .iter()
+            .flatten()
+            .map(|s| s.volume * s.mass_scale)
+            .sum();
+
+        let body_type = match self.mode {
+            RagdollMode::Simulated => rapier3d::prelude::RigidBodyType::Dynamic,
+            _ => rapier3d::prelude::RigidBodyType::KinematicPositionBased,
+        };
+
+        for (bone, shape) in shapes.iter().enumerate() {
+            let Some(shape) = shape else {
+                continue;
+            };
+            let pose = &world_pose[bone];
+
+            let body = RigidBodyBuilder::new(body_type)
+                .position(rigid_isometry(pose))
+                .linear_damping(self.profile.linear_damping)
+                .angular_damping(self.profile.angular_damping)
+                .build();
+            let body_handle = physics_world.rigid_body_set.insert(body);
+
+            let length = shape.end.length();
+            let dir = if length > f32::EPSILON {
+                shape.end / length
+            } else {
+                Vec3::Y
+            };
+            let mass = if total_weight > 0.0 {
+                self.profile.total_mass * shape.volume * shape.mass_scale / total_weight
+            } else {
+                0.0
+            };
+            let builder = if length * 0.5 > shape.radius {
+                let a = dir * shape.radius;
+                let b = shape.end - dir * shape.radius;
+                ColliderBuilder::capsule_from_endpoints(
+                    point![a.x, a.y, a.z],
+                    point![b.x, b.y, b.z],
+                    shape.radius,
+                )
+            } else {
+                let center = shape.end * 0.5;
+                ColliderBuilder::ball(shape.radius)
+                    .translation(vector![center.x, center.y, center.z])
+            };
+            let collider_handle = physics_world.collider_set.insert_with_parent(
+                builder.mass(mass).build(),
+                body_handle,
+                &mut physics_world.rigid_body_set,
+            );
+
+            let entity = self.bones[bone];
+            physics_world.entity_to_body.insert(entity, body_handle);
+            physics_world.body_to_entity.insert(body_handle, entity);
+            physics_world
+                .entity_to_collider
+                .insert(entity, collider_handle);
+            physics_world
+                .collider_to_entity
+                .insert(collider_handle, entity);
+
+            self.parts[bone] = Some(RagdollPart {
+                body: body_handle,
+                collider: collider_handle,
+                joint: None,
+            });
+        }
+
+        // Joints in a second pass: a parent's part may come after its child's
+        for (bone, shape) in shapes.iter().enumerate() {
+            let (Some(part), Some(parent)) = (self.parts[bone], self.parent_part(bone)) else {
+                continue;
+            };
+            let Some(parent_part) = self.parts[parent] else {
+                continue;
+            };
+
+            let rule = self.profile.rule_for(&self.bone_names[bone]);
+            let joint = rule
+                .and_then(|r| r.joint)
+                .unwrap_or(self.profile.default_joint);
+            let twist_axis = shape
+                .as_ref()
+                .map(|s| s.end.normalize_or_zero())
+                .filter(|d| *d != Vec3::ZERO)
+                .unwrap_or(Vec3::Y);
+
+            let data = build_joint(joint, twist_axis, &world_pose[parent], &world_pose[bone]);
+            let handle =
+                physics_world
+                    .impulse_joint_set
+                    .insert(parent_part.body, part.body, data, true);
+            if let Some(part) = self.parts[bone].as_mut() {
+                part.joint = Some(handle);
+            }
+        }
+
+        self.built = true;
+    }
+}
+
+/// Isometry of a bone pose, ignoring scale
+fn rigid_isometry(pose: &Transform) -> Isometry<f32> {
+    transform_to_isometry(&Transform {
+        translation: pose.translation,
+        rotation: pose.rotation.normalize(),
+        scale: Vec3::ONE,
+    })
+}
+
+fn quat_to_rapier(q: Quat) -> Rotation<f32> {
+    rapier3d::na::UnitQuaternion::from_quaternion(rapier3d::na::Quaternion::new(q.w, q.x, q.y, q.z))
+}
+
+/// Joint between a parent and child part, at rest in the current pose
+///
+/// The joint's X axis is the twist/hinge axis; the frames coincide at rest, so limits
+/// are measured from the pose the ragdoll was built in.
+fn build_joint(
+    joint: RagdollJoint,
+    twist_axis: Vec3,
+    parent_pose: &Transform,
+    child_pose: &Transform,
+) -> GenericJoint {
+    let (locked_axes, axis) = match joint {
+        RagdollJoint::Spherical { .. } => (JointAxesMask::LOCKED_SPHERICAL_AXES, twist_axis),
+        RagdollJoint::Revolute { axis, .. } => (
+            JointAxesMask::LOCKED_REVOLUTE_AXES,
+            axis.try_normalize().unwrap_or(Vec3::X),
+        ),
+        RagdollJoint::Fixed => (JointAxesMask::LOCKED_FIXED_AXES, Vec3::X),
+    };
+
+    let frame2_rotation = Quat::from_rotation_arc(Vec3::X, axis);
+    let parent_rotation = parent_pose.rotation.normalize();
+    let child_rotation = child_pose.rotation.normalize();
+    let frame1_rotation = parent_rotation.inverse() * child_rotation * frame2_rotation;
+    let anchor1 = parent_rotation.inverse() * (child_pose.translation - parent_pose.translation);
+
+    let mut builder = GenericJointBuilder::new(locked_axes)
+        .local_frame1(Isometry::from_parts(
+            Translation::new(anchor1.x, anchor1.y, anchor1.z),
+            quat_to_rapier(frame1_rotation),
+        ))
+        .local_frame2(Isometry::from_parts(
+            Translation::identity(),
+            quat_to_rapier(frame2_rotation),
+        ))
+        .contacts_enabled(false);
+
+    match joint {
+        RagdollJoint::Spherical {
+            swing_limit,
+            twist_min,
+            twist_max,
+        } => {
+            builder = builder
+                .limits(JointAxis::AngX, [twist_min, twist_max])
+                .limits(JointAxis::AngY, [-swing_limit, swing_limit])
+                .limits(JointAxis::AngZ, [-swing_limit, swing_limit]);
+        }
+        RagdollJoint::Revolute { min, max, .. } => {
+            builder = builder.limits(JointAxis::AngX, [min, max]);
+        }
+        RagdollJoint::Fixed => {}
+    }
+
+    builder.build()
+}
+
+/// World transform of an entity: its `GlobalTransform`, or its `Transform` composed
+/// with those of its [`Parent`]s when the hierarchy hasn't been propagated yet
+fn entity_world(world: &luminara_core::world::World, entity: Entity) -> Transform {
+    let mut chain = Vec::new();
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if let Some(global) = world.get_component::<GlobalTransform>(entity) {
+            chain.push(global.0);
+            break;
+        }
+        chain.push(
+            world
+                .get_component::<Transform>(entity)
+                .copied()
+                .unwrap_or(Transform::IDENTITY),
+        );
+        current = world.get_component::<Parent>(entity).map(|parent| parent.0);
+        // Guard against cyclic hierarchies
+        if chain.len() > 1024 {
+            break;
+        }
+    }
+    chain
+        .into_iter()
+        .rev()
+        .reduce(|parent, child| parent.mul_transform(&child))
+        .unwrap_or(Transform::IDENTITY)
+}
+
+/// Frame every root bone of a ragdoll is relative to: the world transform of the bone
+/// entity's parent, or of the character entity for bones without one
+fn root_frames(
+    world: &luminara_core::world::World,
+    ragdoll: &Ragdoll,
+    owner: Entity,
+) -> Vec<Transform> {
+    let bone_count = ragdoll.bones.len();
+    let owner_world = entity_world(world, owner);
+    ragdoll
+        .bones
+        .iter()
+        .enumerate()
+        .map(|(bone, entity)| {
+            if !ragdoll.is_root(bone, bone_count) {
+                return owner_world;
+            }
+            match world.get_component::<Parent>(*entity) {
+                Some(parent) if parent.0 != owner => entity_world(world, parent.0),
+                _ => owner_world,
+            }
+        })
+        .collect()
+}
+
+/// Current local transforms of a ragdoll's bone entities
+fn bone_locals(world: &luminara_core::world::World, ragdoll: &Ragdoll) -> Vec<Transform> {
+    ragdoll
+        .bones
+        .iter()
+        .enumerate()
+        .map(|(bone, entity)| {
+            world
+                .get_component::<Transform>(*entity)
+                .copied()
+                .unwrap_or_else(|| ragdoll.bind_pose.get(bone).copied().unwrap_or_default())
+        })
+        .collect()
+}
+
+/// Part bodies of each ragdoll by character entity. Despawning the character
+/// takes its `Ragdoll` and the part handles with it, so they are kept here too.
+#[derive(Default)]
+struct RagdollBodies(HashMap<Entity, Vec<RigidBodyHandle>>);
+
+impl Resource for RagdollBodies {}
+
+/// Remove a part's body with its collider and joints and forget its bone entity
+fn remove_part_body(physics_world: &mut PhysicsWorld3D, body: RigidBodyHandle) {
+    let Some(removed) = physics_world.rigid_body_set.remove(
+        body,
+        &mut physics_world.island_manager,
+        &mut physics_world.collider_set,
+        &mut physics_world.impulse_joint_set,
+        &mut physics_world.multibody_joint_set,
+        true,
+    ) else {
+        return;
+    };
+    let bone = physics_world.body_to_entity.remove(&body);
+    if let Some(bone) = bone {
+        if physics_world.entity_to_body.get(&bone) == Some(&body) {
+            physics_world.entity_to_body.remove(&bone);
+        }
+    }
+    for collider in removed.colliders() {
+        physics_world.collider_to_entity.remove(collider);
+        if let Some(bone) = bone {
+            if physics_world.entity_to_collider.get(&bone) == Some(collider) {
+                physics_world.entity_to_collider.remove(&bone);
+            }
+        }
+    }
+}
+
+/// System that builds ragdoll parts and drives them before the physics step
+/// (Exclusive system — needs mutable World access to update the ragdoll components)
+///
+/// Animated and blending ragdolls move their kinematic parts to the bone pose so the
+/// parts carry the animation's velocity when [`Ragdoll::simulate`] switches them to
+/// dynamic bodies. Queued impulses are applied to simulated parts. Parts of ragdolls
+/// whose character entity or `Ragdoll` is gone are removed.
+pub fn ragdoll_drive_system(world: &mut luminara_core::world::World) {
+    let entities: Vec<Entity> = {
+        let query = Query::<(Entity, &Ragdoll)>::new(world);
+        query.iter().map(|(entity, _)| entity).collect()
+    };
+
+    if entities.is_empty() && world.get_resource::<RagdollBodies>().is_none() {
+        return;
+    }
+    if world.get_resource::<RagdollBodies>().is_none() {
+        world.insert_resource(RagdollBodies::default());
+    }
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld3D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+        let Some(mut bodies) = world.get_resource_mut::<RagdollBodies>() else {
+            return;
+        };
+        let live: HashSet<Entity> = entities.iter().copied().collect();
+        bodies.0.retain(|owner, parts| {
+            if live.contains(owner) {
+                return true;
+            }
+            for body in parts.iter() {
+                remove_part_body(physics_world, *body);
+            }
+            false
+        });
+    }
+
+    for entity in entities {
+        let Some(ragdoll) = world.get_component_mut::<Ragdoll>(entity) else {
+            continue;
+        };
+        let roots = root_frames(world, ragdoll, entity);
+        let locals = bone_locals(world, ragdoll);
+        let pose = ragdoll.world_pose(&roots, &locals);
+
+        {
+            let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld3D>() else {
+                return;
+            };
+            let physics_world = &mut *physics_world;
+
+            if !ragdoll.built {
+                ragdoll.build_parts(physics_world, &pose);
+                if let Some(mut bodies) = world.get_resource_mut::<RagdollBodies>() {
+                    let parts = ragdoll.parts.iter().flatten().map(|part| part.body);
+                    bodies.0.insert(entity, parts.collect());
+                }
+            }
+
+            let simulated = ragdoll.mode == RagdollMode::Simulated;
+            for (bone, part) in ragdoll.parts.iter().enumerate() {
+                let Some(part) = part else {
+                    continue;
+                };
+                let Some(body) = physics_world.rigid_body_set.get_mut(part.body) else {
+                    continue;
+                };
+
+                if simulated {
+                    if !body.is_dynamic() {
+                        body.set_body_type(rapier3d::prelude::RigidBodyType::Dynamic, true);
+                    }
+                } else {
+                    if !body.is_kinematic() {
+                        body.set_body_type(
+                            rapier3d::prelude::RigidBodyType::KinematicPositionBased,
+                            true,
+                        );
+                    }
+                    body.set_next_kinematic_position(rigid_isometry(&pose[bone]));
+                }
+            }
+
+            if simulated {
+                for (bone, impulse) in ragdoll.pending_impulses.drain(..) {
+                    let Some(part) = ragdoll.parts.get(bone).copied().flatten() else {
+                        continue;
+                    };
+                    if let Some(body) = physics_world.rigid_body_set.get_mut(part.body) {
+                        body.apply_impulse(vector![impulse.x, impulse.y, impulse.z], true);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// System that writes ragdoll poses back to the bone entities after the physics step
+/// (Exclusive system — needs mutable World access to write bone transforms)
+///
+/// Simulated ragdolls set every bone's local `Transform` from its part. While blending
+/// back, bones are interpolated from the last physics pose towards their current
+/// (animated) local transform, then the ragdoll returns to [`RagdollMode::Animated`].
+pub fn ragdoll_pose_system(world: &mut luminara_core::world::World) {
+    let entities: Vec<Entity> = {
+        let query = Query::<(Entity, &Ragdoll)>::new(world);
+        query.iter().map(|(entity, _)| entity).collect()
+    };
+
+    let dt = world
+        .get_resource::<luminara_core::Time>()
+        .map(|time| time.delta_seconds())
+        .unwrap_or(1.0 / 60.0);
+
+    for entity in entities {
+        let Some(ragdoll) = world.get_component_mut::<Ragdoll>(entity) else {
+            continue;
+        };
+        if !ragdoll.built {
+            continue;
+        }
+
+        let locals = bone_locals(world, ragdoll);
+        let new_locals = match ragdoll.mode {
+            RagdollMode::Animated => continue,
+            RagdollMode::Simulated => {
+                let roots = root_frames(world, ragdoll, entity);
+                let Some(physics_world) = world.get_resource::<PhysicsWorld3D>() else {
+                    return;
+                };
+
+                // Bones with a part take its pose, the others follow their parent
+                let mut pose = ragdoll.world_pose(&roots, &locals);
+                let mut new_locals = locals.clone();
+                let order = hierarchy_order(&ragdoll.parents, locals.len());
+                for bone in order {
+                    let parent_world = match ragdoll.parents[bone].filter(|p| *p < locals.len()) {
+                        Some(parent) => pose[parent],
+                        None => roots[bone],
+                    };
+                    let body = ragdoll.parts[bone]
+                        .and_then(|part| physics_world.rigid_body_set.get(part.body));
+                    match body {
+                        Some(body) => {
+                            let t = body.translation();
+                            let r = body.rotation();
+                            let body_world = Transform {
+                                translation: Vec3::new(t.x, t.y, t.z),
+                                rotation: Quat::from_xyzw(r.i, r.j, r.k, r.w),
+                                scale: parent_world.scale * locals[bone].scale,
+                            };
+                            let mut local = relative_transform(&parent_world, &body_world);
+                            local.scale = locals[bone].scale;
+                            new_locals[bone] = local;
+                            pose[bone] = parent_world.mul_transform(&local);
+                        }
+                        None => pose[bone] = parent_world.mul_transform(&locals[bone]),
+                    }
+                }
+                ragdoll.physics_pose = new_locals.clone();
+                new_locals
+            }
+            RagdollMode::BlendingToAnimation { elapsed, duration } => {
+                let elapsed = elapsed + dt;
+                let t = if duration > 0.0 {
+                    (elapsed / duration).min(1.0)
+                } else {
+                    1.0
+                };
+                ragdoll.mode = if t >= 1.0 {
+                    RagdollMode::Animated
+                } else {
+                    RagdollMode::BlendingToAnimation { elapsed, duration }
+                };
+
+                locals
+                    .iter()
+                    .zip(&ragdoll.physics_pose)
+                    .map(|(animated, physics)| Transform {
+                        translation: physics.translation.lerp(animated.translation, t),
+                        rotation: physics.rotation.slerp(animated.rotation, t),
+                        scale: physics.scale.lerp(animated.scale, t),
+                    })
+                    .collect()
+            }
+        };
+
+        let bones = ragdoll.bones.clone();
+        for (bone_entity, local) in bones.into_iter().zip(new_locals) {
+            match world.get_component_mut::<Transform>(bone_entity) {
+                Some(transform) => *transform = local,
+                None => {
+                    let _ = world.add_component(bone_entity, local);
+                }
+            }
+        }
+    }
+}
+
+/// `parent^-1 * child`
+fn relative_transform(parent: &Transform, child: &Transform) -> Transform {
+    let matrix = parent.compute_matrix().inverse() * child.compute_matrix();
+    let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+    Transform {
+        translation,
+        rotation,
+        scale,
+    }
+}
+
+/// Bone indices ordered so that parents come before their children
+fn hierarchy_order(parents: &[Option<usize>], bone_count: usize) -> Vec<usize> {
+    let mut depth = vec![0usize; bone_count];
+    for (bone, d) in depth.iter_mut().enumerate() {
+        let mut current = parents.get(bone).copied().flatten();
+        while let Some(parent) = current.filter(|p| *p < bone_count) {
+            *d += 1;
+            if *d > bone_count {
+                break;
+            }
+            current = parents.get(parent).copied().flatten();
+        }
+    }
+    let mut order: Vec<usize> = (0..bone_count).collect();
+    order.sort_by_key(|&bone| depth[bone]);
+    order
+}
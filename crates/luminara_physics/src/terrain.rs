@@ -0,0 +1,251 @@
+use luminara_core::{Component, Entity, Query, Resource};
+use luminara_math::{Transform, Vec3};
+use luminara_render::Terrain;
+use luminara_scene::GlobalTransform;
+use rapier3d::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::components::ColliderShape;
+use crate::physics3d::{build_collider_shape, transform_to_isometry, PhysicsWorld3D};
+
+/// Fixed rigid body holding the heightfield collider of a [`Terrain`], added
+/// by [`terrain_collider_system`]
+#[derive(Debug, Clone)]
+pub struct TerrainCollider {
+    pub body: RigidBodyHandle,
+    pub collider: ColliderHandle,
+    revision: u64,
+    scale: Vec3,
+}
+
+impl Component for TerrainCollider {
+    fn type_name() -> &'static str {
+        "TerrainCollider"
+    }
+}
+
+/// Bodies built for terrains, kept apart from the entities so they can still
+/// be removed once the entity is despawned
+#[derive(Default)]
+struct TerrainBodies(HashMap<Entity, RigidBodyHandle>);
+
+impl Resource for TerrainBodies {}
+
+/// Heightfield of `terrain` scaled by the non-negative `scale`, and its offset
+/// from the terrain's origin (heightfields are centered, terrains start at a
+/// corner)
+fn terrain_shape(terrain: &Terrain, scale: Vec3) -> Option<(SharedShape, Vector<f32>)> {
+    let mut shape = ColliderShape::heightfield_from_map(&terrain.data().to_height_map());
+    if let ColliderShape::Heightfield { scale: extent, .. } = &mut shape {
+        *extent *= scale;
+    }
+    let half = terrain.data().extent() * 0.5;
+    let offset = vector![half.x * scale.x, 0.0, half.y * scale.z];
+    build_collider_shape(&shape).map(|shape| (shape, offset))
+}
+
+/// System that keeps a static heightfield collider in sync with every
+/// [`Terrain`]
+/// (Exclusive system — needs mutable World access to attach [`TerrainCollider`])
+///
+/// The heightfield is rebuilt when the terrain's heights are sculpted or its
+/// scale changes; otherwise only the body follows the world transform. A body
+/// can't mirror, so the sign of the scale is ignored. Collision events on the
+/// heightfield report the terrain entity, and the body is removed with the
+/// entity or its [`Terrain`].
+pub fn terrain_collider_system(world: &mut luminara_core::world::World) {
+    let terrains: Vec<(Entity, u64, Transform)> = {
+        let query = Query::<(Entity, &Terrain)>::new(world);
+        query
+            .iter()
+            .filter_map(|(entity, terrain)| {
+                let transform = world
+                    .get_component::<GlobalTransform>(entity)
+                    .map(|global| global.0)
+                    .or_else(|| world.get_component::<Transform>(entity).copied())?;
+                Some((entity, terrain.height_revision(), transform))
+            })
+            .collect()
+    };
+
+    if terrains.is_empty() && world.get_resource::<TerrainBodies>().is_none() {
+        return;
+    }
+    if world.get_resource::<TerrainBodies>().is_none() {
+        world.insert_resource(TerrainBodies::default());
+    }
+
+    let mut built = Vec::new();
+    let mut removed = Vec::new();
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld3D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+        let Some(mut bodies) = world.get_resource_mut::<TerrainBodies>() else {
+            return;
+        };
+
+        let live: HashSet<Entity> = terrains.iter().map(|(entity, ..)| *entity).collect();
+        bodies.0.retain(|entity, body| {
+            if live.contains(entity) {
+                return true;
+            }
+            remove_terrain_body(physics_world, *entity, *body);
+            removed.push(*entity);
+            false
+        });
+
+        for (entity, revision, transform) in &terrains {
+            let isometry = transform_to_isometry(transform);
+            let scale = transform.scale.abs();
+            let existing = world.get_component::<TerrainCollider>(*entity);
+            if let Some(existing) = existing.as_ref() {
+                if let Some(body) = physics_world.rigid_body_set.get_mut(existing.body) {
+                    if *body.position() != isometry {
+                        body.set_position(isometry, true);
+                    }
+                }
+                if existing.revision == *revision && existing.scale == scale {
+                    continue;
+                }
+            }
+
+            let Some((shape, offset)) = world
+                .get_component::<Terrain>(*entity)
+                .and_then(|terrain| terrain_shape(terrain, scale))
+            else {
+                continue;
+            };
+
+            // Sculpting swaps the shape in place so the handles stay valid
+            if let Some(existing) = existing {
+                if let Some(collider) = physics_world.collider_set.get_mut(existing.collider) {
+                    collider.set_shape(shape);
+                    collider.set_translation_wrt_parent(offset);
+                    collider.user_data = *revision as u128;
+                    built.push((
+                        *entity,
+                        TerrainCollider {
+                            revision: *revision,
+                            scale,
+                            ..existing.clone()
+                        },
+                    ));
+                    continue;
+                }
+            }
+
+            let body = physics_world
+                .rigid_body_set
+                .insert(RigidBodyBuilder::fixed().position(isometry).build());
+            let collider = physics_world.collider_set.insert_with_parent(
+                ColliderBuilder::new(shape)
+                    .translation(offset)
+                    .user_data(*revision as u128)
+                    .build(),
+                body,
+                &mut physics_world.rigid_body_set,
+            );
+
+            physics_world.entity_to_body.insert(*entity, body);
+            physics_world.body_to_entity.insert(body, *entity);
+            physics_world.entity_to_collider.insert(*entity, collider);
+            physics_world.collider_to_entity.insert(collider, *entity);
+            bodies.0.insert(*entity, body);
+            built.push((
+                *entity,
+                TerrainCollider {
+                    body,
+                    collider,
+                    revision: *revision,
+                    scale,
+                },
+            ));
+        }
+    }
+
+    for entity in removed {
+        let _ = world.remove_component::<TerrainCollider>(entity);
+    }
+    for (entity, collider) in built {
+        let _ = world.add_component(entity, collider);
+    }
+}
+
+/// Bring terrain bodies in line with a physics world just restored from a
+/// snapshot
+///
+/// A terrain whose body the snapshot doesn't hold (built or rebuilt since)
+/// drops its [`TerrainCollider`] and any older body of the snapshot, so
+/// [`terrain_collider_system`] builds it again. A restored heightfield older
+/// than the terrain's heights is reshaped. Its collider carries the height
+/// revision it was built from.
+pub(crate) fn reconcile_terrain_bodies(world: &mut luminara_core::world::World) {
+    let mut stale = Vec::new();
+    let mut kept = Vec::new();
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld3D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+        let Some(mut bodies) = world.get_resource_mut::<TerrainBodies>() else {
+            return;
+        };
+        bodies.0.retain(|entity, body| {
+            if physics_world.rigid_body_set.contains(*body) {
+                kept.push(*entity);
+                return true;
+            }
+            if let Some(restored) = physics_world.entity_to_body.get(entity).copied() {
+                remove_terrain_body(physics_world, *entity, restored);
+            }
+            stale.push(*entity);
+            false
+        });
+    }
+
+    for entity in stale {
+        let _ = world.remove_component::<TerrainCollider>(entity);
+    }
+    for entity in kept {
+        let restored = {
+            let Some(physics_world) = world.get_resource::<PhysicsWorld3D>() else {
+                return;
+            };
+            world
+                .get_component::<TerrainCollider>(entity)
+                .and_then(|terrain| physics_world.collider_set.get(terrain.collider))
+                .map(|collider| collider.user_data as u64)
+        };
+        if let (Some(revision), Some(terrain)) =
+            (restored, world.get_component_mut::<TerrainCollider>(entity))
+        {
+            terrain.revision = revision;
+        }
+    }
+}
+
+/// Remove a terrain's body with its heightfield and forget their entity
+fn remove_terrain_body(physics_world: &mut PhysicsWorld3D, entity: Entity, body: RigidBodyHandle) {
+    let Some(removed) = physics_world.rigid_body_set.remove(
+        body,
+        &mut physics_world.island_manager,
+        &mut physics_world.collider_set,
+        &mut physics_world.impulse_joint_set,
+        &mut physics_world.multibody_joint_set,
+        true,
+    ) else {
+        return;
+    };
+    physics_world.body_to_entity.remove(&body);
+    if physics_world.entity_to_body.get(&entity) == Some(&body) {
+        physics_world.entity_to_body.remove(&entity);
+    }
+    for collider in removed.colliders() {
+        physics_world.collider_to_entity.remove(collider);
+        if physics_world.entity_to_collider.get(&entity) == Some(collider) {
+            physics_world.entity_to_collider.remove(&entity);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use luminara_core::{Component, Entity, Query, Resource};
+use luminara_math::{Transform, Vec3};
+use luminara_render::Tilemap;
+use luminara_scene::GlobalTransform;
+use rapier2d::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::physics2d::PhysicsWorld2D;
+
+/// Fixed rigid body holding the colliders of a [`Tilemap`], added by
+/// [`tilemap_collider_system_2d`]
+#[derive(Debug, Clone)]
+pub struct TilemapColliders2D {
+    pub body: RigidBodyHandle,
+    pub colliders: Vec<ColliderHandle>,
+    revision: u64,
+}
+
+impl Component for TilemapColliders2D {
+    fn type_name() -> &'static str {
+        "TilemapColliders2D"
+    }
+}
+
+/// Tilemap body of each entity. Unloading a level despawns its tilemaps along
+/// with their [`TilemapColliders2D`], so the walls are found here instead.
+#[derive(Default)]
+struct TilemapBodies(HashMap<Entity, RigidBodyHandle>);
+
+impl Resource for TilemapBodies {}
+
+fn tilemap_isometry(transform: &Transform) -> Isometry<f32> {
+    let x_axis = transform.rotation * Vec3::X;
+    Isometry::new(
+        vector![transform.translation.x, transform.translation.y],
+        x_axis.y.atan2(x_axis.x),
+    )
+}
+
+/// System that builds static colliders for the solid tiles of every [`Tilemap`]
+/// (Exclusive system — needs mutable World access to attach [`TilemapColliders2D`])
+///
+/// Colliders are rebuilt from [`Tilemap::collision_rects`] when the map's
+/// collision revision changes; otherwise only the body follows the world
+/// transform.
+/// Collision events on these colliders report the tilemap entity, and the
+/// body is removed with the entity or its [`Tilemap`].
+pub fn tilemap_collider_system_2d(world: &mut luminara_core::world::World) {
+    let tilemaps: Vec<(Entity, u64, Transform)> = {
+        let query = Query::<(Entity, &Tilemap)>::new(world);
+        query
+            .iter()
+            .filter_map(|(entity, tilemap)| {
+                let transform = world
+                    .get_component::<GlobalTransform>(entity)
+                    .map(|global| global.0)
+                    .or_else(|| world.get_component::<Transform>(entity).copied())?;
+                Some((entity, tilemap.collision_revision(), transform))
+            })
+            .collect()
+    };
+
+    if tilemaps.is_empty() && world.get_resource::<TilemapBodies>().is_none() {
+        return;
+    }
+    if world.get_resource::<TilemapBodies>().is_none() {
+        world.insert_resource(TilemapBodies::default());
+    }
+
+    let mut built = Vec::new();
+    let mut removed = Vec::new();
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld2D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+        let Some(mut bodies) = world.get_resource_mut::<TilemapBodies>() else {
+            return;
+        };
+
+        let live: HashSet<Entity> = tilemaps.iter().map(|(entity, ..)| *entity).collect();
+        bodies.0.retain(|entity, body| {
+            if live.contains(entity) {
+                return true;
+            }
+            remove_tilemap_body(physics_world, *entity, *body);
+            removed.push(*entity);
+            false
+        });
+
+        for (entity, revision, transform) in &tilemaps {
+            let isometry = tilemap_isometry(transform);
+            let existing = world.get_component::<TilemapColliders2D>(*entity);
+            if let Some(existing) = existing.as_ref().filter(|c| c.revision == *revision) {
+                if let Some(body) = physics_world.rigid_body_set.get_mut(existing.body) {
+                    if *body.position() != isometry {
+                        body.set_position(isometry, true);
+                    }
+                }
+                continue;
+            }
+
+            if let Some(existing) = existing {
+                remove_tilemap_body(physics_world, *entity, existing.body);
+            }
+
+            let Some(rects) = world
+                .get_component::<Tilemap>(*entity)
+                .map(|tilemap| tilemap.collision_rects())
+            else {
+                continue;
+            };
+
+            let body = physics_world
+                .rigid_body_set
+                .insert(RigidBodyBuilder::fixed().position(isometry).build());
+            let scale = transform.scale;
+            let colliders: Vec<ColliderHandle> = rects
+                .iter()
+                .map(|rect| {
+                    let center = (rect.min + rect.max) * 0.5;
+                    let half = (rect.max - rect.min) * 0.5;
+                    let collider =
+                        ColliderBuilder::cuboid((half.x * scale.x).abs(), (half.y * scale.y).abs())
+                            .translation(vector![center.x * scale.x, center.y * scale.y])
+                            .build();
+                    physics_world.collider_set.insert_with_parent(
+                        collider,
+                        body,
+                        &mut physics_world.rigid_body_set,
+                    )
+                })
+                .collect();
+
+            physics_world.entity_to_body.insert(*entity, body);
+            physics_world.body_to_entity.insert(body, *entity);
+            for collider in &colliders {
+                physics_world.collider_to_entity.insert(*collider, *entity);
+            }
+            bodies.0.insert(*entity, body);
+            built.push((
+                *entity,
+                TilemapColliders2D {
+                    body,
+                    colliders,
+                    revision: *revision,
+                },
+            ));
+        }
+    }
+
+    for entity in removed {
+        let _ = world.remove_component::<TilemapColliders2D>(entity);
+    }
+    for (entity, colliders) in built {
+        let _ = world.add_component(entity, colliders);
+    }
+}
+
+/// Bring tilemap bodies in line with a physics world just restored from a
+/// snapshot
+///
+/// A tilemap whose body the snapshot doesn't hold (built or rebuilt since)
+/// drops its [`TilemapColliders2D`] and any older body of the snapshot, so
+/// [`tilemap_collider_system_2d`] builds it again. The snapshot only maps an
+/// entity to one collider, so the tile colliders of a kept body are mapped
+/// back to the tilemap here.
+pub(crate) fn reconcile_tilemap_bodies(world: &mut luminara_core::world::World) {
+    let mut stale = Vec::new();
+    {
+        let Some(mut physics_world) = world.get_resource_mut::<PhysicsWorld2D>() else {
+            return;
+        };
+        let physics_world = &mut *physics_world;
+        let Some(mut bodies) = world.get_resource_mut::<TilemapBodies>() else {
+            return;
+        };
+        bodies.0.retain(|entity, body| {
+            if let Some(restored) = physics_world.rigid_body_set.get(*body) {
+                for collider in restored.colliders() {
+                    physics_world.collider_to_entity.insert(*collider, *entity);
+                }
+                return true;
+            }
+            if let Some(restored) = physics_world.entity_to_body.get(entity).copied() {
+                remove_tilemap_body(physics_world, *entity, restored);
+            }
+            stale.push(*entity);
+            false
+        });
+    }
+
+    for entity in stale {
+        let _ = world.remove_component::<TilemapColliders2D>(entity);
+    }
+}
+
+/// Remove a tilemap's body with its colliders and forget their entity
+fn remove_tilemap_body(physics_world: &mut PhysicsWorld2D, entity: Entity, body: RigidBodyHandle) {
+    let Some(removed) = physics_world.rigid_body_set.remove(
+        body,
+        &mut physics_world.island_manager,
+        &mut physics_world.collider_set,
+        &mut physics_world.impulse_joint_set,
+        &mut physics_world.multibody_joint_set,
+        true,
+    ) else {
+        return;
+    };
+    physics_world.body_to_entity.remove(&body);
+    if physics_world.entity_to_body.get(&entity) == Some(&body) {
+        physics_world.entity_to_body.remove(&entity);
+    }
+    for collider in removed.colliders() {
+        physics_world.collider_to_entity.remove(collider);
+    }
+}
@@ -0,0 +1,493 @@
+use luminara_core::time::Time;
+use luminara_core::{Entity, World};
+use luminara_math::{Transform, Vec3};
+use luminara_physics::physics3d::{
+    physics_body_creation_system_exclusive, physics_collider_creation_system_exclusive,
+    PhysicsWorld3D,
+};
+use luminara_physics::{
+    character_controller_system, character_controller_system_2d, CharacterController,
+    CharacterControllerOutput, Collider, ColliderShape, PhysicsWorld2D, RigidBody, RigidBodyType,
+};
+
+const FLOOR_TOP: f32 = 0.5;
+const CAPSULE_HALF_HEIGHT: f32 = 0.5;
+const CAPSULE_RADIUS: f32 = 0.3;
+
+fn spawn_static_box(world: &mut World, center: Vec3, half_extents: Vec3) -> Entity {
+    let entity = world.spawn();
+    world
+        .add_component(entity, Transform::from_xyz(center.x, center.y, center.z))
+        .unwrap();
+    world
+        .add_component(
+            entity,
+            RigidBody {
+                body_type: RigidBodyType::Static,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    world
+        .add_component(
+            entity,
+            Collider {
+                shape: ColliderShape::Box { half_extents },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    entity
+}
+
+/// Spawn a static wedge rising along +X at `angle`, whose slope starts at
+/// (`start_x`, `FLOOR_TOP`)
+fn spawn_ramp(world: &mut World, start_x: f32, angle: f32) -> Entity {
+    let length = 5.0;
+    let top = FLOOR_TOP + length * angle.tan();
+    let points = [-5.0, 5.0]
+        .into_iter()
+        .flat_map(|z| {
+            [
+                Vec3::new(start_x, FLOOR_TOP, z),
+                Vec3::new(start_x + length, FLOOR_TOP, z),
+                Vec3::new(start_x + length, top, z),
+            ]
+        })
+        .collect();
+
+    let entity = world.spawn();
+    world.add_component(entity, Transform::IDENTITY).unwrap();
+    world
+        .add_component(
+            entity,
+            RigidBody {
+                body_type: RigidBodyType::Static,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    world
+        .add_component(
+            entity,
+            Collider {
+                shape: ColliderShape::ConvexHull { points },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    entity
+}
+
+fn spawn_character(world: &mut World, position: Vec3) -> Entity {
+    let entity = world.spawn();
+    world
+        .add_component(
+            entity,
+            Transform::from_xyz(position.x, position.y, position.z),
+        )
+        .unwrap();
+    world
+        .add_component(
+            entity,
+            RigidBody {
+                body_type: RigidBodyType::Kinematic,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    world
+        .add_component(
+            entity,
+            Collider {
+                shape: ColliderShape::Capsule {
+                    half_height: CAPSULE_HALF_HEIGHT,
+                    radius: CAPSULE_RADIUS,
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    world
+        .add_component(entity, CharacterController::default())
+        .unwrap();
+    entity
+}
+
+/// Build a world with a large floor whose top face sits at `FLOOR_TOP`
+fn setup_world() -> (World, Entity) {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+    let mut time = Time::new();
+    time.update_manual(1.0 / 60.0);
+    world.insert_resource(time);
+
+    let floor = spawn_static_box(
+        &mut world,
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(50.0, FLOOR_TOP, 50.0),
+    );
+    (world, floor)
+}
+
+fn standing_height() -> f32 {
+    FLOOR_TOP + CAPSULE_HALF_HEIGHT + CAPSULE_RADIUS + 0.01
+}
+
+fn create_physics_objects(world: &mut World) {
+    physics_body_creation_system_exclusive(world);
+    physics_collider_creation_system_exclusive(world);
+}
+
+fn move_character(
+    world: &mut World,
+    character: Entity,
+    translation: Vec3,
+) -> CharacterControllerOutput {
+    world
+        .get_component_mut::<CharacterController>(character)
+        .unwrap()
+        .move_by(translation);
+    character_controller_system(world);
+    world
+        .get_component::<CharacterControllerOutput>(character)
+        .cloned()
+        .expect("controller should write an output component")
+}
+
+#[test]
+fn test_character_walks_on_flat_ground() {
+    let (mut world, floor) = setup_world();
+    let character = spawn_character(&mut world, Vec3::new(0.0, standing_height(), 0.0));
+    create_physics_objects(&mut world);
+
+    let output = move_character(&mut world, character, Vec3::new(0.5, 0.0, 0.0));
+
+    assert!(
+        output.grounded,
+        "character standing on the floor should be grounded"
+    );
+    assert_eq!(output.ground_entity, Some(floor));
+    assert!(
+        (output.effective_translation.x - 0.5).abs() < 1e-3,
+        "unobstructed movement should be applied in full, got {:?}",
+        output.effective_translation
+    );
+    assert!(output.effective_translation.y.abs() < 0.05);
+}
+
+#[test]
+fn test_desired_translation_is_consumed() {
+    let (mut world, _) = setup_world();
+    let character = spawn_character(&mut world, Vec3::new(0.0, standing_height(), 0.0));
+    create_physics_objects(&mut world);
+
+    move_character(&mut world, character, Vec3::new(0.2, 0.0, 0.0));
+
+    let controller = world
+        .get_component::<CharacterController>(character)
+        .unwrap();
+    assert_eq!(controller.desired_translation, Vec3::ZERO);
+}
+
+#[test]
+fn test_wall_blocks_and_slides_character() {
+    let (mut world, _) = setup_world();
+    // Wall whose near face is at x = 1.0
+    let wall = spawn_static_box(
+        &mut world,
+        Vec3::new(1.5, 2.0, 0.0),
+        Vec3::new(0.5, 2.0, 10.0),
+    );
+    let character = spawn_character(&mut world, Vec3::new(0.0, standing_height(), 0.0));
+    create_physics_objects(&mut world);
+
+    // Move diagonally into the wall: the x part is blocked, the z part slides along it
+    let output = move_character(&mut world, character, Vec3::new(2.0, 0.0, 1.0));
+
+    let max_x = 1.0 - CAPSULE_RADIUS;
+    assert!(
+        output.effective_translation.x <= max_x + 1e-3,
+        "character penetrated the wall: {:?}",
+        output.effective_translation
+    );
+    assert!(
+        output.effective_translation.z > 0.5,
+        "character should slide along the wall: {:?}",
+        output.effective_translation
+    );
+    assert!(output.collisions.iter().any(|c| c.entity == Some(wall)));
+}
+
+#[test]
+fn test_character_climbs_small_step() {
+    let (mut world, _) = setup_world();
+    // 0.2 high step starting at x = 0.6
+    spawn_static_box(
+        &mut world,
+        Vec3::new(2.6, FLOOR_TOP + 0.1, 0.0),
+        Vec3::new(2.0, 0.1, 2.0),
+    );
+    let character = spawn_character(&mut world, Vec3::new(0.0, standing_height(), 0.0));
+    create_physics_objects(&mut world);
+
+    let output = move_character(&mut world, character, Vec3::new(1.0, 0.0, 0.0));
+
+    assert!(
+        output.effective_translation.x > 0.6,
+        "character should step onto the ledge: {:?}",
+        output.effective_translation
+    );
+    assert!(output.effective_translation.y > 0.1);
+}
+
+#[test]
+fn test_non_kinematic_bodies_are_ignored() {
+    let (mut world, _) = setup_world();
+    let character = spawn_character(&mut world, Vec3::new(0.0, standing_height(), 0.0));
+    world
+        .add_component(character, RigidBody::default())
+        .unwrap();
+    create_physics_objects(&mut world);
+
+    world
+        .get_component_mut::<CharacterController>(character)
+        .unwrap()
+        .move_by(Vec3::X);
+    character_controller_system(&mut world);
+
+    assert!(world
+        .get_component::<CharacterControllerOutput>(character)
+        .is_none());
+}
+
+#[test]
+fn test_default_controller_settings() {
+    let controller = CharacterController::default();
+    assert_eq!(controller.up, Vec3::Y);
+    assert!(controller.max_slope_climb_angle > controller.min_slope_slide_angle);
+    assert!(controller.autostep.is_some());
+    assert!(controller.snap_to_ground.is_some());
+    assert_eq!(controller.desired_translation, Vec3::ZERO);
+}
+
+#[test]
+fn test_slope_limit_decides_what_can_be_climbed() {
+    let climb = |angle_degrees: f32| {
+        let (mut world, _) = setup_world();
+        let ramp = spawn_ramp(&mut world, 0.5, angle_degrees.to_radians());
+        let character = spawn_character(&mut world, Vec3::new(0.0, standing_height(), 0.0));
+        world
+            .get_component_mut::<CharacterController>(character)
+            .unwrap()
+            .autostep = None;
+        create_physics_objects(&mut world);
+        let output = move_character(&mut world, character, Vec3::new(1.5, 0.0, 0.0));
+        (output, ramp)
+    };
+
+    // 20 degrees is below the default 45 degree climb limit
+    let (gentle, _) = climb(20.0);
+    assert!(
+        gentle.effective_translation.y > 0.2 && gentle.effective_translation.x > 1.0,
+        "character should walk up a gentle ramp: {:?}",
+        gentle.effective_translation
+    );
+
+    let (steep, ramp) = climb(60.0);
+    assert!(
+        steep.effective_translation.y < 0.05,
+        "character must not climb a steep ramp: {:?}",
+        steep.effective_translation
+    );
+    assert!(steep.effective_translation.x < 0.5);
+    assert!(steep.collisions.iter().any(|c| c.entity == Some(ramp)));
+}
+
+#[test]
+fn test_snap_to_ground_follows_downhill_slope() {
+    let slope = 20.0_f32.to_radians();
+    let walk_downhill = |snap_to_ground: Option<f32>| {
+        let (mut world, _) = setup_world();
+        let ramp = spawn_ramp(&mut world, 0.5, slope);
+        // Resting on the ramp at x = 3
+        let surface = FLOOR_TOP + 2.5 * slope.tan();
+        let height = surface + (CAPSULE_RADIUS + 0.005) / slope.cos() + CAPSULE_HALF_HEIGHT;
+        let character = spawn_character(&mut world, Vec3::new(3.0, height, 0.0));
+        world
+            .get_component_mut::<CharacterController>(character)
+            .unwrap()
+            .snap_to_ground = snap_to_ground;
+        create_physics_objects(&mut world);
+        // Snapping only applies to downward moves, as gravity would give
+        let output = move_character(&mut world, character, Vec3::new(-1.0, -0.01, 0.0));
+        (output, ramp)
+    };
+
+    let (snapped, ramp) = walk_downhill(Some(0.5));
+    assert!(snapped.grounded);
+    assert_eq!(snapped.ground_entity, Some(ramp));
+    assert!(
+        (snapped.effective_translation.y + slope.tan()).abs() < 0.02,
+        "character should follow the slope down: {:?}",
+        snapped.effective_translation
+    );
+
+    let (launched, _) = walk_downhill(None);
+    assert!(!launched.grounded);
+    assert!((launched.effective_translation.y + 0.01).abs() < 1e-3);
+}
+
+#[test]
+fn test_moving_platform_carries_character() {
+    let carried = |carry_with_platforms: bool| {
+        let mut world = World::new();
+        world.insert_resource(PhysicsWorld3D::default());
+        let mut time = Time::new();
+        time.update_manual(1.0 / 60.0);
+        world.insert_resource(time);
+
+        let platform = spawn_static_box(
+            &mut world,
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(5.0, FLOOR_TOP, 5.0),
+        );
+        world
+            .get_component_mut::<RigidBody>(platform)
+            .unwrap()
+            .body_type = RigidBodyType::Kinematic;
+        let character = spawn_character(&mut world, Vec3::new(0.0, standing_height(), 0.0));
+        world
+            .get_component_mut::<CharacterController>(character)
+            .unwrap()
+            .carry_with_platforms = carry_with_platforms;
+        create_physics_objects(&mut world);
+
+        // Move the platform at 3 m/s along X; the step derives its velocity
+        {
+            let mut physics = world.get_resource_mut::<PhysicsWorld3D>().unwrap();
+            let handle = physics.entity_to_body[&platform];
+            let dt = physics.timestep;
+            physics.rigid_body_set[handle]
+                .set_next_kinematic_translation(rapier3d::na::Vector3::new(3.0 * dt, 0.0, 0.0));
+            physics.step();
+        }
+
+        let output = move_character(&mut world, character, Vec3::ZERO);
+        (output, platform)
+    };
+
+    let (output, platform) = carried(true);
+    assert_eq!(output.ground_entity, Some(platform));
+    assert!(
+        (output.platform_velocity.x - 3.0).abs() < 1e-2,
+        "platform velocity should be reported: {:?}",
+        output.platform_velocity
+    );
+    assert!(
+        (output.effective_translation.x - 3.0 / 60.0).abs() < 1e-3,
+        "character should ride along with the platform: {:?}",
+        output.effective_translation
+    );
+
+    let (output, _) = carried(false);
+    assert_eq!(output.platform_velocity, Vec3::ZERO);
+    assert!(output.effective_translation.x.abs() < 1e-3);
+}
+
+#[test]
+fn test_2d_controller_walks_and_is_blocked() {
+    use rapier2d::na::Vector2;
+    use rapier2d::prelude::{ColliderBuilder, RigidBodyBuilder};
+
+    fn insert(
+        physics: &mut PhysicsWorld2D,
+        entity: Entity,
+        body: RigidBodyBuilder,
+        collider: ColliderBuilder,
+    ) {
+        let body = physics.rigid_body_set.insert(body.build());
+        let collider = physics.collider_set.insert_with_parent(
+            collider.build(),
+            body,
+            &mut physics.rigid_body_set,
+        );
+        physics.entity_to_body.insert(entity, body);
+        physics.entity_to_collider.insert(entity, collider);
+        physics.collider_to_entity.insert(collider, entity);
+    }
+
+    let mut world = World::new();
+    let mut time = Time::new();
+    time.update_manual(1.0 / 60.0);
+    world.insert_resource(time);
+    let mut physics = PhysicsWorld2D::default();
+
+    // Floor with its top at y = 0 and a wall whose near face is at x = 1
+    let floor = world.spawn();
+    insert(
+        &mut physics,
+        floor,
+        RigidBodyBuilder::fixed().translation(Vector2::new(0.0, -0.5)),
+        ColliderBuilder::cuboid(50.0, 0.5),
+    );
+    let wall = world.spawn();
+    insert(
+        &mut physics,
+        wall,
+        RigidBodyBuilder::fixed().translation(Vector2::new(1.5, 2.0)),
+        ColliderBuilder::cuboid(0.5, 2.0),
+    );
+
+    let character = world.spawn();
+    world
+        .add_component(
+            character,
+            RigidBody {
+                body_type: RigidBodyType::Kinematic,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    world
+        .add_component(character, CharacterController::default())
+        .unwrap();
+    insert(
+        &mut physics,
+        character,
+        RigidBodyBuilder::kinematic_position_based().translation(Vector2::new(
+            -2.0,
+            CAPSULE_HALF_HEIGHT + CAPSULE_RADIUS + 0.01,
+        )),
+        ColliderBuilder::capsule_y(CAPSULE_HALF_HEIGHT, CAPSULE_RADIUS),
+    );
+    world.insert_resource(physics);
+
+    let mut move_2d = |translation: Vec3| {
+        world
+            .get_component_mut::<CharacterController>(character)
+            .unwrap()
+            .move_by(translation);
+        character_controller_system_2d(&mut world);
+        world
+            .get_component::<CharacterControllerOutput>(character)
+            .cloned()
+            .unwrap()
+    };
+
+    // The z component is ignored in 2D
+    let output = move_2d(Vec3::new(1.0, 0.0, 5.0));
+    assert!(output.grounded);
+    assert_eq!(output.ground_entity, Some(floor));
+    assert!((output.effective_translation.x - 1.0).abs() < 1e-3);
+    assert_eq!(output.effective_translation.z, 0.0);
+
+    // Blocked by the wall 2 - radius units further on
+    let output = move_2d(Vec3::new(3.0, 0.0, 0.0));
+    assert!(
+        output.effective_translation.x <= 2.0 - CAPSULE_RADIUS + 1e-3,
+        "character penetrated the wall: {:?}",
+        output.effective_translation
+    );
+    assert!(output.collisions.iter().any(|c| c.entity == Some(wall)));
+}
@@ -0,0 +1,253 @@
+use luminara_core::World;
+use luminara_math::geometry::reeb_graph::HeightMap;
+use luminara_math::{Quat, Transform, Vec3};
+use luminara_physics::physics3d::{
+    build_collider_shape, physics_body_creation_system_exclusive,
+    physics_collider_creation_system_exclusive, PhysicsWorld3D,
+};
+use luminara_physics::{
+    Collider, ColliderShape, CompoundChild, ConvexDecompositionSettings, RigidBody,
+};
+use luminara_render::Mesh;
+use luminara_scene::Parent;
+use rapier3d::prelude::*;
+
+fn cube_points() -> Vec<Vec3> {
+    let mut points = Vec::new();
+    for x in [-1.0, 1.0] {
+        for y in [-1.0, 1.0] {
+            for z in [-1.0, 1.0] {
+                points.push(Vec3::new(x, y, z));
+            }
+        }
+    }
+    points
+}
+
+#[test]
+fn test_primitive_shapes_build() {
+    let cylinder = build_collider_shape(&ColliderShape::Cylinder {
+        half_height: 1.0,
+        radius: 0.5,
+    })
+    .unwrap();
+    assert_eq!(cylinder.shape_type(), ShapeType::Cylinder);
+
+    let cone = build_collider_shape(&ColliderShape::Cone {
+        half_height: 1.0,
+        radius: 0.5,
+    })
+    .unwrap();
+    assert_eq!(cone.shape_type(), ShapeType::Cone);
+
+    let hull = build_collider_shape(&ColliderShape::ConvexHull {
+        points: cube_points(),
+    })
+    .unwrap();
+    assert_eq!(hull.shape_type(), ShapeType::ConvexPolyhedron);
+}
+
+#[test]
+fn test_degenerate_convex_hull_is_rejected() {
+    let coplanar = vec![
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    ];
+    assert!(build_collider_shape(&ColliderShape::ConvexHull { points: coplanar }).is_none());
+
+    // Flat but tilted, so the bounds are not flat
+    let tilted = vec![
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.0),
+        Vec3::new(0.0, 1.0, 1.0),
+        Vec3::new(1.0, 2.0, 1.0),
+    ];
+    assert!(build_collider_shape(&ColliderShape::ConvexHull { points: tilted }).is_none());
+
+    // A 4 mm cube is small, not degenerate
+    let tiny = cube_points().into_iter().map(|p| p * 0.002).collect();
+    assert!(build_collider_shape(&ColliderShape::ConvexHull { points: tiny }).is_some());
+}
+
+#[test]
+fn test_heightfield_from_height_map() {
+    let width = 4;
+    let height = 3;
+    let heights: Vec<f32> = (0..width * height).map(|i| i as f32).collect();
+    let map = HeightMap::new(heights, width, height, Vec3::new(2.0, 0.5, 3.0));
+
+    let shape = ColliderShape::heightfield_from_map(&map);
+    match &shape {
+        ColliderShape::Heightfield {
+            rows, cols, scale, ..
+        } => {
+            assert_eq!(*rows, height);
+            assert_eq!(*cols, width);
+            assert_eq!(*scale, Vec3::new(6.0, 0.5, 6.0));
+        }
+        _ => panic!("Wrong shape"),
+    }
+
+    let built = build_collider_shape(&shape).unwrap();
+    let field = built.as_heightfield().unwrap();
+    assert_eq!(field.nrows(), height - 1);
+    assert_eq!(field.ncols(), width - 1);
+
+    // Highest sample is the last one, scaled by scale.y
+    let aabb = built.compute_local_aabb();
+    assert!((aabb.maxs.y - 11.0 * 0.5).abs() < 1e-5);
+}
+
+#[test]
+fn test_heightfield_rejects_mismatched_data() {
+    let shape = ColliderShape::Heightfield {
+        heights: vec![0.0; 5],
+        rows: 2,
+        cols: 3,
+        scale: Vec3::ONE,
+    };
+    assert!(build_collider_shape(&shape).is_none());
+}
+
+#[test]
+fn test_nested_compound_is_flattened() {
+    let inner = ColliderShape::Compound {
+        children: vec![
+            CompoundChild::new(
+                Vec3::new(1.0, 0.0, 0.0),
+                Quat::IDENTITY,
+                ColliderShape::Sphere { radius: 0.5 },
+            ),
+            // Composite shapes are not allowed inside a compound
+            CompoundChild::new(
+                Vec3::ZERO,
+                Quat::IDENTITY,
+                ColliderShape::Mesh {
+                    vertices: cube_points(),
+                    indices: vec![[0, 1, 2]],
+                },
+            ),
+        ],
+    };
+    let outer = ColliderShape::Compound {
+        children: vec![
+            CompoundChild::new(
+                Vec3::ZERO,
+                Quat::IDENTITY,
+                ColliderShape::Box {
+                    half_extents: Vec3::splat(0.5),
+                },
+            ),
+            CompoundChild::new(Vec3::new(0.0, 2.0, 0.0), Quat::IDENTITY, inner),
+        ],
+    };
+
+    let built = build_collider_shape(&outer).unwrap();
+    let compound = built.as_compound().unwrap();
+    assert_eq!(compound.shapes().len(), 2);
+
+    let (sphere_pos, _) = &compound.shapes()[1];
+    let t = sphere_pos.translation.vector;
+    assert!((t.x - 1.0).abs() < 1e-6 && (t.y - 2.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_convex_decomposition_from_render_mesh() {
+    let mesh = Mesh::cube(1.0);
+    let shape = ColliderShape::convex_decomposition_from_mesh(
+        &mesh,
+        ConvexDecompositionSettings::default(),
+    );
+
+    let built = build_collider_shape(&shape).unwrap();
+    let compound = built.as_compound().unwrap();
+    assert!(!compound.shapes().is_empty());
+}
+
+#[test]
+fn test_child_collider_attaches_to_ancestor_body() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+
+    let body_entity = world.spawn();
+    world
+        .add_component(body_entity, Transform::from_xyz(0.0, 5.0, 0.0))
+        .unwrap();
+    world
+        .add_component(body_entity, RigidBody::default())
+        .unwrap();
+
+    // Intermediate node without a body
+    let pivot = world.spawn();
+    world
+        .add_component(pivot, Transform::from_xyz(1.0, 0.0, 0.0))
+        .unwrap();
+    world.add_component(pivot, Parent(body_entity)).unwrap();
+
+    let child = world.spawn();
+    world
+        .add_component(child, Transform::from_xyz(0.0, 0.5, 0.0))
+        .unwrap();
+    world.add_component(child, Parent(pivot)).unwrap();
+    world
+        .add_component(
+            child,
+            Collider {
+                shape: ColliderShape::Sphere { radius: 0.25 },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    physics_body_creation_system_exclusive(&mut world);
+    physics_collider_creation_system_exclusive(&mut world);
+
+    let physics_world = world.get_resource::<PhysicsWorld3D>().unwrap();
+    let body_handle = physics_world.entity_to_body[&body_entity];
+    let collider_handle = physics_world.entity_to_collider[&child];
+    let collider = physics_world.collider_set.get(collider_handle).unwrap();
+
+    assert_eq!(collider.parent(), Some(body_handle));
+    let offset = collider.position_wrt_parent().unwrap().translation.vector;
+    assert!((offset.x - 1.0).abs() < 1e-6);
+    assert!((offset.y - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn test_child_collider_waits_for_ancestor_body() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+
+    let body_entity = world.spawn();
+    world
+        .add_component(body_entity, Transform::IDENTITY)
+        .unwrap();
+    world
+        .add_component(body_entity, RigidBody::default())
+        .unwrap();
+
+    let child = world.spawn();
+    world.add_component(child, Transform::IDENTITY).unwrap();
+    world.add_component(child, Parent(body_entity)).unwrap();
+    world.add_component(child, Collider::default()).unwrap();
+
+    // Colliders created before bodies must not become free colliders
+    physics_collider_creation_system_exclusive(&mut world);
+    assert!(!world
+        .get_resource::<PhysicsWorld3D>()
+        .unwrap()
+        .entity_to_collider
+        .contains_key(&child));
+
+    physics_body_creation_system_exclusive(&mut world);
+    physics_collider_creation_system_exclusive(&mut world);
+
+    let physics_world = world.get_resource::<PhysicsWorld3D>().unwrap();
+    let collider_handle = physics_world.entity_to_collider[&child];
+    let collider = physics_world.collider_set.get(collider_handle).unwrap();
+    assert_eq!(
+        collider.parent(),
+        Some(physics_world.entity_to_body[&body_entity])
+    );
+}
@@ -0,0 +1,208 @@
+use luminara_core::World;
+use luminara_math::{Quat, Transform, Vec3};
+use luminara_physics::physics3d::{
+    physics_body_creation_system_exclusive, physics_integration_method_system, PhysicsWorld3D,
+};
+use luminara_physics::{
+    IntegrationMethod, IntegrationMethodOverride, LiePhysicsIntegrator, PhysicsIntegrationConfig,
+    RigidBody,
+};
+use rapier3d::prelude::*;
+
+const DT: f32 = 1.0 / 120.0;
+const STEPS: usize = 600;
+
+/// Principal moments of a box with half extents (0.5, 1.0, 1.5): y is the intermediate axis
+const BOX_HALF_EXTENTS: [f32; 3] = [0.5, 1.0, 1.5];
+
+/// Spin mostly around the intermediate axis so the body tumbles
+fn tumbling_angvel() -> Vec3 {
+    Vec3::new(0.1, 8.0, 0.2)
+}
+
+type RigidBody3D = rapier3d::dynamics::RigidBody;
+
+/// Kinetic energy and world-space angular momentum of a body
+fn rotational_state(body: &RigidBody3D) -> (f32, Vec3) {
+    let mprops = &body.mass_properties().local_mprops;
+    let inertia = mprops.principal_inertia();
+    let inertia = Vec3::new(inertia.x, inertia.y, inertia.z);
+    let f = &mprops.principal_inertia_local_frame;
+    let r = body.rotation();
+    let frame = Quat::from_xyzw(r.i, r.j, r.k, r.w) * Quat::from_xyzw(f.i, f.j, f.k, f.w);
+
+    let w = body.angvel();
+    let principal = frame.inverse() * Vec3::new(w.x, w.y, w.z);
+    (
+        LiePhysicsIntegrator::rotational_energy(principal, inertia),
+        frame * (inertia * principal),
+    )
+}
+
+fn spawn_spinning_box(physics: &mut PhysicsWorld3D, x: f32) -> RigidBodyHandle {
+    let w = tumbling_angvel();
+    let body = physics.rigid_body_set.insert(
+        RigidBodyBuilder::dynamic()
+            .translation(vector![x, 0.0, 0.0])
+            .angvel(vector![w.x, w.y, w.z])
+            .can_sleep(false)
+            .build(),
+    );
+    let [hx, hy, hz] = BOX_HALF_EXTENTS;
+    physics.collider_set.insert_with_parent(
+        ColliderBuilder::cuboid(hx, hy, hz).build(),
+        body,
+        &mut physics.rigid_body_set,
+    );
+    body
+}
+
+#[test]
+fn test_rigid_body_energy_drift_euler_vs_rk4() {
+    let inertia = Vec3::new(1.0, 2.0, 3.0);
+    let initial = tumbling_angvel();
+    let initial_energy = LiePhysicsIntegrator::rotational_energy(initial, inertia);
+
+    let (mut euler_rot, mut euler_vel) = (Quat::IDENTITY, initial);
+    let (mut rk4_rot, mut rk4_vel) = (Quat::IDENTITY, initial);
+    for _ in 0..STEPS {
+        (euler_rot, euler_vel) =
+            LiePhysicsIntegrator::integrate_rigid_body_euler(euler_rot, euler_vel, inertia, DT);
+        (rk4_rot, rk4_vel) =
+            LiePhysicsIntegrator::integrate_rigid_body_rk4(rk4_rot, rk4_vel, inertia, DT);
+    }
+
+    let euler_drift =
+        (LiePhysicsIntegrator::rotational_energy(euler_vel, inertia) - initial_energy).abs()
+            / initial_energy;
+    let rk4_drift = (LiePhysicsIntegrator::rotational_energy(rk4_vel, inertia) - initial_energy)
+        .abs()
+        / initial_energy;
+
+    assert!(rk4_drift < 1e-4, "RK4 drifted by {rk4_drift}");
+    assert!(euler_drift > 100.0 * rk4_drift);
+    assert!((euler_rot.length() - 1.0).abs() < 1e-4);
+    assert!((rk4_rot.length() - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_rk4_bodies_conserve_energy_and_momentum_in_world() {
+    let mut physics = PhysicsWorld3D {
+        gravity: vector![0.0, 0.0, 0.0],
+        timestep: DT,
+        ..Default::default()
+    };
+
+    let euler_body = spawn_spinning_box(&mut physics, -5.0);
+    let rk4_body = spawn_spinning_box(&mut physics, 5.0);
+    physics.lie_integrated_bodies.insert(rk4_body);
+
+    let (initial_energy, initial_momentum) = rotational_state(&physics.rigid_body_set[euler_body]);
+    let initial_com_rk4 = *physics.rigid_body_set[rk4_body].center_of_mass();
+
+    for _ in 0..STEPS {
+        physics.step();
+    }
+
+    let (_, euler_momentum) = rotational_state(&physics.rigid_body_set[euler_body]);
+    let (rk4_energy, rk4_momentum) = rotational_state(&physics.rigid_body_set[rk4_body]);
+
+    let euler_momentum_drift =
+        (euler_momentum - initial_momentum).length() / initial_momentum.length();
+    let rk4_momentum_drift = (rk4_momentum - initial_momentum).length() / initial_momentum.length();
+    let rk4_energy_drift = (rk4_energy - initial_energy).abs() / initial_energy;
+
+    assert!(
+        rk4_energy_drift < 1e-3,
+        "RK4 energy drift {rk4_energy_drift}"
+    );
+    assert!(
+        rk4_momentum_drift < 5e-3,
+        "RK4 momentum drift {rk4_momentum_drift}"
+    );
+    // Rapier's integrator has no gyroscopic term, so the tumbling body's momentum wanders
+    assert!(euler_momentum_drift > 5.0 * rk4_momentum_drift);
+
+    // Spinning in place: the centre of mass must not move
+    let com = physics.rigid_body_set[rk4_body].center_of_mass();
+    assert!((com - initial_com_rk4).norm() < 1e-4);
+}
+
+#[test]
+fn test_rk4_bodies_still_collide() {
+    let mut physics = PhysicsWorld3D::default();
+    let ground = physics
+        .rigid_body_set
+        .insert(RigidBodyBuilder::fixed().build());
+    physics.collider_set.insert_with_parent(
+        ColliderBuilder::cuboid(10.0, 0.5, 10.0).build(),
+        ground,
+        &mut physics.rigid_body_set,
+    );
+
+    let ball = physics.rigid_body_set.insert(
+        RigidBodyBuilder::dynamic()
+            .translation(vector![0.0, 3.0, 0.0])
+            .angvel(vector![0.0, 4.0, 0.0])
+            .build(),
+    );
+    physics.collider_set.insert_with_parent(
+        ColliderBuilder::ball(0.5).build(),
+        ball,
+        &mut physics.rigid_body_set,
+    );
+    physics.lie_integrated_bodies.insert(ball);
+
+    for _ in 0..240 {
+        physics.step();
+    }
+
+    let y = physics.rigid_body_set[ball].translation().y;
+    assert!(
+        (y - 1.0).abs() < 0.05,
+        "RK4 ball should rest on the ground, got y = {y}"
+    );
+}
+
+#[test]
+fn test_integration_method_system_selects_bodies() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+    world.insert_resource(PhysicsIntegrationConfig::default());
+
+    let spawn_body = |world: &mut World| {
+        let entity = world.spawn();
+        world.add_component(entity, Transform::IDENTITY).unwrap();
+        world.add_component(entity, RigidBody::default()).unwrap();
+        entity
+    };
+    let precise = spawn_body(&mut world);
+    world
+        .add_component(precise, IntegrationMethodOverride::rk4())
+        .unwrap();
+    let plain = spawn_body(&mut world);
+    let debris = spawn_body(&mut world);
+    world
+        .add_component(debris, IntegrationMethodOverride::euler())
+        .unwrap();
+
+    physics_body_creation_system_exclusive(&mut world);
+    physics_integration_method_system(&mut world);
+
+    let selected = |world: &World| {
+        let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+        [precise, plain, debris].map(|e| {
+            physics
+                .lie_integrated_bodies
+                .contains(&physics.entity_to_body[&e])
+        })
+    };
+    assert_eq!(selected(&world), [true, false, false]);
+
+    world
+        .get_resource_mut::<PhysicsIntegrationConfig>()
+        .unwrap()
+        .default_method = IntegrationMethod::Rk4;
+    physics_integration_method_system(&mut world);
+    assert_eq!(selected(&world), [true, true, false]);
+}
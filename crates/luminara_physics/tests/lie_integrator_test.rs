@@ -119,6 +119,25 @@ fn test_integrate_transform() {
     assert!((transform.scale - Vec3::ONE).length() < 1e-5);
 }
 
+#[test]
+fn test_integrate_transform_rotates_about_the_angular_velocity_axis() {
+    // Each axis of the angular velocity must turn about that same axis, not
+    // the one its bivector plane is mistaken for
+    let dt = 0.1;
+    for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+        let mut transform = Transform::IDENTITY;
+        LiePhysicsIntegrator::integrate_transform(&mut transform, Vec3::ZERO, axis, dt);
+
+        let expected = Quat::from_axis_angle(axis, dt);
+        assert!(
+            transform.rotation.angle_between(expected) < 1e-4,
+            "angular velocity {:?} gave rotation {:?}",
+            axis,
+            transform.rotation
+        );
+    }
+}
+
 #[test]
 fn test_compute_energy() {
     // Test energy computation
@@ -0,0 +1,296 @@
+use luminara_core::World;
+use luminara_physics::{PhysicsSnapshot3D, PhysicsWorld2D, PhysicsWorld3D};
+
+/// Build a scene with contacts, stacking and a joint so that the snapshot has to carry
+/// warm-started solver state, islands and broad-phase pairs
+fn build_scene_3d(world: &mut World) -> PhysicsWorld3D {
+    use rapier3d::prelude::*;
+
+    let mut physics = PhysicsWorld3D::default();
+
+    let ground = physics
+        .rigid_body_set
+        .insert(RigidBodyBuilder::fixed().build());
+    let ground_collider = physics.collider_set.insert_with_parent(
+        ColliderBuilder::cuboid(20.0, 0.5, 20.0).build(),
+        ground,
+        &mut physics.rigid_body_set,
+    );
+    let ground_entity = world.spawn();
+    physics.entity_to_body.insert(ground_entity, ground);
+    physics.body_to_entity.insert(ground, ground_entity);
+    physics
+        .entity_to_collider
+        .insert(ground_entity, ground_collider);
+    physics
+        .collider_to_entity
+        .insert(ground_collider, ground_entity);
+
+    let mut previous = None;
+    for i in 0..6 {
+        let body = physics.rigid_body_set.insert(
+            RigidBodyBuilder::dynamic()
+                .translation(vector![0.1 * i as f32, 1.0 + i as f32 * 1.05, 0.0])
+                .angvel(vector![0.3, 0.1 * i as f32, 0.0])
+                .build(),
+        );
+        let collider = physics.collider_set.insert_with_parent(
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5).build(),
+            body,
+            &mut physics.rigid_body_set,
+        );
+        let entity = world.spawn();
+        physics.entity_to_body.insert(entity, body);
+        physics.body_to_entity.insert(body, entity);
+        physics.entity_to_collider.insert(entity, collider);
+        physics.collider_to_entity.insert(collider, entity);
+
+        if let Some(previous) = previous {
+            let joint = SphericalJointBuilder::new()
+                .local_anchor1(point![0.0, 0.6, 0.0])
+                .local_anchor2(point![0.0, -0.6, 0.0]);
+            physics
+                .impulse_joint_set
+                .insert(previous, body, joint, true);
+        }
+        previous = Some(body);
+    }
+
+    physics
+}
+
+/// Bit patterns of every body's position and velocity
+fn state_bits_3d(physics: &PhysicsWorld3D) -> Vec<u32> {
+    let mut handles: Vec<_> = physics.rigid_body_set.iter().map(|(h, _)| h).collect();
+    handles.sort_by_key(|h| h.into_raw_parts());
+
+    let mut bits = Vec::new();
+    for handle in handles {
+        let body = &physics.rigid_body_set[handle];
+        let t = body.translation();
+        let r = body.rotation();
+        let v = body.linvel();
+        let w = body.angvel();
+        for value in [
+            t.x, t.y, t.z, r.i, r.j, r.k, r.w, v.x, v.y, v.z, w.x, w.y, w.z,
+        ] {
+            bits.push(value.to_bits());
+        }
+    }
+    bits
+}
+
+fn run_steps_3d(physics: &mut PhysicsWorld3D, steps: usize) -> Vec<Vec<u32>> {
+    (0..steps)
+        .map(|_| {
+            physics.step();
+            state_bits_3d(physics)
+        })
+        .collect()
+}
+
+#[test]
+fn test_restore_in_place_is_bit_identical() {
+    let mut world = World::new();
+    let mut physics = build_scene_3d(&mut world);
+
+    run_steps_3d(&mut physics, 60);
+    let snapshot = physics.snapshot();
+
+    let expected = run_steps_3d(&mut physics, 120);
+
+    physics.restore(&snapshot);
+    let replayed = run_steps_3d(&mut physics, 120);
+
+    assert_eq!(expected, replayed);
+}
+
+#[test]
+fn test_restore_into_fresh_world_from_bytes_is_bit_identical() {
+    let mut world = World::new();
+    let mut physics = build_scene_3d(&mut world);
+
+    run_steps_3d(&mut physics, 45);
+    let bytes = physics
+        .snapshot()
+        .to_bytes()
+        .expect("snapshot should serialize");
+    let expected = run_steps_3d(&mut physics, 90);
+
+    let snapshot = PhysicsSnapshot3D::from_bytes(&bytes).expect("snapshot should deserialize");
+    let mut fresh = PhysicsWorld3D::default();
+    fresh.restore(&snapshot);
+    let replayed = run_steps_3d(&mut fresh, 90);
+
+    assert_eq!(expected, replayed);
+}
+
+#[test]
+fn test_restore_rebuilds_entity_maps() {
+    let mut world = World::new();
+    let physics = build_scene_3d(&mut world);
+    let snapshot = physics.snapshot();
+
+    let mut fresh = PhysicsWorld3D::default();
+    fresh.restore(&snapshot);
+
+    assert_eq!(fresh.entity_to_body, physics.entity_to_body);
+    assert_eq!(fresh.body_to_entity, physics.body_to_entity);
+    assert_eq!(fresh.entity_to_collider, physics.entity_to_collider);
+    assert_eq!(fresh.collider_to_entity, physics.collider_to_entity);
+}
+
+#[test]
+fn test_snapshot_is_isolated_from_later_edits() {
+    let mut world = World::new();
+    let mut physics = build_scene_3d(&mut world);
+    let snapshot = physics.snapshot();
+    let before = state_bits_3d(&physics);
+
+    // Simulate an editor play session that mutates the world
+    run_steps_3d(&mut physics, 30);
+    let extra = physics
+        .rigid_body_set
+        .insert(rapier3d::prelude::RigidBodyBuilder::dynamic().build());
+    assert!(physics.rigid_body_set.contains(extra));
+
+    physics.restore(&snapshot);
+    assert!(!physics.rigid_body_set.contains(extra));
+    assert_eq!(state_bits_3d(&physics), before);
+}
+
+#[test]
+fn test_restore_2d_is_bit_identical() {
+    use rapier2d::prelude::*;
+
+    let mut physics = PhysicsWorld2D::default();
+    let ground = physics
+        .rigid_body_set
+        .insert(RigidBodyBuilder::fixed().build());
+    physics.collider_set.insert_with_parent(
+        ColliderBuilder::cuboid(20.0, 0.5).build(),
+        ground,
+        &mut physics.rigid_body_set,
+    );
+    for i in 0..5 {
+        let body = physics.rigid_body_set.insert(
+            RigidBodyBuilder::dynamic()
+                .translation(vector![0.2 * i as f32, 1.0 + i as f32 * 1.1])
+                .build(),
+        );
+        physics.collider_set.insert_with_parent(
+            ColliderBuilder::ball(0.5).build(),
+            body,
+            &mut physics.rigid_body_set,
+        );
+    }
+
+    let state = |physics: &PhysicsWorld2D| -> Vec<u32> {
+        physics
+            .rigid_body_set
+            .iter()
+            .flat_map(|(_, body)| {
+                let t = body.translation();
+                [
+                    t.x.to_bits(),
+                    t.y.to_bits(),
+                    body.rotation().angle().to_bits(),
+                ]
+            })
+            .collect()
+    };
+
+    for _ in 0..30 {
+        physics.step();
+    }
+    let bytes = physics.snapshot().to_bytes().unwrap();
+
+    let mut expected = Vec::new();
+    for _ in 0..60 {
+        physics.step();
+        expected.push(state(&physics));
+    }
+
+    let mut fresh = PhysicsWorld2D::default();
+    fresh.restore(&luminara_physics::PhysicsSnapshot2D::from_bytes(&bytes).unwrap());
+    let mut replayed = Vec::new();
+    for _ in 0..60 {
+        fresh.step();
+        replayed.push(state(&fresh));
+    }
+
+    assert_eq!(expected, replayed);
+}
+
+#[test]
+fn test_world_restore_reconciles_spawned_and_despawned_entities() {
+    use luminara_core::Entity;
+    use luminara_math::{Transform, Vec3};
+    use luminara_physics::physics3d::{
+        physics_body_creation_system_exclusive, physics_collider_creation_system_exclusive,
+        PhysicsBodyCreated, PhysicsColliderCreated,
+    };
+    use luminara_physics::{restore_physics_snapshot, Collider, ColliderShape, RigidBody};
+
+    fn spawn_box(world: &mut World, y: f32) -> Entity {
+        let entity = world.spawn();
+        world
+            .add_component(entity, Transform::from_xyz(0.0, y, 0.0))
+            .unwrap();
+        world.add_component(entity, RigidBody::default()).unwrap();
+        world
+            .add_component(
+                entity,
+                Collider {
+                    shape: ColliderShape::Box {
+                        half_extents: Vec3::splat(0.5),
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        entity
+    }
+    fn create(world: &mut World) {
+        physics_body_creation_system_exclusive(world);
+        physics_collider_creation_system_exclusive(world);
+    }
+
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+    let kept = spawn_box(&mut world, 1.0);
+    let removed = spawn_box(&mut world, 3.0);
+    create(&mut world);
+    let snapshot = world.get_resource::<PhysicsWorld3D>().unwrap().snapshot();
+
+    let added = spawn_box(&mut world, 5.0);
+    create(&mut world);
+    assert!(world.despawn(removed));
+
+    restore_physics_snapshot(&mut world, &snapshot);
+    {
+        let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+        assert_eq!(physics.rigid_body_set.len(), 1);
+        assert_eq!(physics.collider_set.len(), 1);
+        assert!(physics.entity_to_body.contains_key(&kept));
+        assert!(!physics.entity_to_body.contains_key(&removed));
+        assert!(!physics.entity_to_collider.contains_key(&removed));
+        assert!(!physics.body_to_entity.values().any(|e| *e == removed));
+    }
+    assert!(world.get_component::<PhysicsBodyCreated>(kept).is_some());
+    assert!(world.get_component::<PhysicsBodyCreated>(added).is_none());
+    assert!(world
+        .get_component::<PhysicsColliderCreated>(added)
+        .is_none());
+
+    // The creation systems rebuild what the snapshot did not know about
+    create(&mut world);
+    let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+    assert_eq!(physics.rigid_body_set.len(), 2);
+    assert_eq!(physics.collider_set.len(), 2);
+    let body = physics.entity_to_body[&added];
+    assert_eq!(
+        physics.collider_set[physics.entity_to_collider[&added]].parent(),
+        Some(body)
+    );
+}
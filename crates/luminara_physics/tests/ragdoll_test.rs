@@ -0,0 +1,448 @@
+use luminara_asset::AssetLoader;
+use luminara_core::time::Time;
+use luminara_core::{Entity, World};
+use luminara_math::{Mat4, Transform, Vec3};
+use luminara_physics::physics3d::PhysicsWorld3D;
+use luminara_physics::{
+    ragdoll_drive_system, ragdoll_pose_system, Ragdoll, RagdollJoint, RagdollMode, RagdollProfile,
+    RagdollProfileLoader,
+};
+use luminara_render::{Bone, Skeleton};
+use luminara_scene::{GlobalTransform, Parent};
+use rapier3d::prelude::*;
+use std::path::Path;
+
+const HIPS: usize = 0;
+const SPINE: usize = 1;
+const HEAD: usize = 2;
+const UP_LEG: usize = 3;
+const LEG: usize = 4;
+const FOOT: usize = 5;
+const TOE: usize = 6;
+
+/// Minimal biped: hips -> spine -> head, hips -> left leg chain
+fn test_skeleton() -> Skeleton {
+    let bone = |name: &str, offset: Vec3| Bone {
+        name: name.to_string(),
+        local_transform: Mat4::from_translation(offset),
+        inverse_bind_matrix: Mat4::IDENTITY,
+    };
+    Skeleton {
+        bones: vec![
+            bone("Hips", Vec3::new(0.0, 1.0, 0.0)),
+            bone("Spine", Vec3::new(0.0, 0.3, 0.0)),
+            bone("Head", Vec3::new(0.0, 0.3, 0.0)),
+            bone("LeftUpLeg", Vec3::new(0.1, 0.0, 0.0)),
+            bone("LeftLeg", Vec3::new(0.0, -0.45, 0.0)),
+            bone("LeftFoot", Vec3::new(0.0, -0.45, 0.0)),
+            bone("LeftToeBase", Vec3::new(0.0, -0.05, 0.1)),
+        ],
+        hierarchy: vec![
+            None,
+            Some(HIPS),
+            Some(SPINE),
+            Some(HIPS),
+            Some(UP_LEG),
+            Some(LEG),
+            Some(FOOT),
+        ],
+    }
+}
+
+fn bind_locals(skeleton: &Skeleton) -> Vec<Transform> {
+    skeleton
+        .bones
+        .iter()
+        .map(|bone| Transform::from_translation(bone.local_transform.w_axis.truncate()))
+        .collect()
+}
+
+struct Character {
+    world: World,
+    root: Entity,
+    bones: Vec<Entity>,
+    bind: Vec<Transform>,
+}
+
+fn setup() -> Character {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+    let mut time = Time::new();
+    time.update_manual(1.0 / 60.0);
+    world.insert_resource(time);
+
+    // Ground plane with its top face at y = 0
+    {
+        let mut physics = world.get_resource_mut::<PhysicsWorld3D>().unwrap();
+        let physics = &mut *physics;
+        let ground = physics
+            .rigid_body_set
+            .insert(RigidBodyBuilder::fixed().translation(vector![0.0, -0.5, 0.0]));
+        physics.collider_set.insert_with_parent(
+            ColliderBuilder::cuboid(20.0, 0.5, 20.0),
+            ground,
+            &mut physics.rigid_body_set,
+        );
+    }
+
+    let skeleton = test_skeleton();
+    let bind = bind_locals(&skeleton);
+    let bones: Vec<Entity> = bind
+        .iter()
+        .map(|local| {
+            let entity = world.spawn();
+            world.add_component(entity, *local).unwrap();
+            entity
+        })
+        .collect();
+
+    let root = world.spawn();
+    world.add_component(root, Transform::IDENTITY).unwrap();
+    world
+        .add_component(
+            root,
+            Ragdoll::new(&skeleton, bones.clone(), RagdollProfile::humanoid()),
+        )
+        .unwrap();
+
+    Character {
+        world,
+        root,
+        bones,
+        bind,
+    }
+}
+
+impl Character {
+    fn ragdoll(&self) -> Ragdoll {
+        self.world
+            .get_component::<Ragdoll>(self.root)
+            .unwrap()
+            .clone()
+    }
+
+    fn ragdoll_mut(&mut self) -> &mut Ragdoll {
+        self.world.get_component_mut::<Ragdoll>(self.root).unwrap()
+    }
+
+    fn local(&self, bone: usize) -> Transform {
+        *self
+            .world
+            .get_component::<Transform>(self.bones[bone])
+            .unwrap()
+    }
+
+    fn head_height(&self) -> f32 {
+        let world = self
+            .local(HIPS)
+            .mul_transform(&self.local(SPINE))
+            .mul_transform(&self.local(HEAD));
+        world.translation.y
+    }
+
+    /// Play the bind pose as the "animation" for this frame
+    fn animate_bind_pose(&mut self) {
+        for (entity, local) in self.bones.iter().zip(&self.bind) {
+            self.world.add_component(*entity, *local).unwrap();
+        }
+    }
+
+    fn frame(&mut self) {
+        ragdoll_drive_system(&mut self.world);
+        self.world
+            .get_resource_mut::<PhysicsWorld3D>()
+            .unwrap()
+            .step();
+        ragdoll_pose_system(&mut self.world);
+    }
+}
+
+#[test]
+fn test_profile_loads_from_ron() {
+    let source = r#"(
+        total_mass: 80.0,
+        radius_ratio: 0.2,
+        min_radius: 0.05,
+        linear_damping: 0.0,
+        angular_damping: 1.0,
+        default_joint: Spherical(swing_limit: 0.5, twist_min: -0.2, twist_max: 0.2),
+        blend_duration: 0.25,
+        rules: [
+            (pattern: "finger", skip: true),
+            (pattern: "knee", joint: Some(Revolute(axis: (1.0, 0.0, 0.0), min: -2.0, max: 0.0))),
+            (pattern: "spine", radius: Some(0.15), mass_scale: 2.0),
+        ],
+    )"#;
+
+    let profile = RagdollProfileLoader
+        .load(source.as_bytes(), Path::new("hero.ragdoll"))
+        .expect("profile should parse");
+
+    assert_eq!(profile.total_mass, 80.0);
+    assert_eq!(profile.rules.len(), 3);
+    assert!(profile.rule_for("RightFinger2").unwrap().skip);
+    assert_eq!(
+        profile.rule_for("LeftKnee").unwrap().joint,
+        Some(RagdollJoint::Revolute {
+            axis: Vec3::X,
+            min: -2.0,
+            max: 0.0
+        })
+    );
+    let spine = profile.rule_for("Spine1").unwrap();
+    assert_eq!(spine.radius, Some(0.15));
+    assert_eq!(spine.mass_scale, 2.0);
+    assert_eq!(profile.rules[0].mass_scale, 1.0);
+
+    assert!(RagdollProfileLoader
+        .load(b"(total_mass: ", Path::new("broken.ragdoll"))
+        .is_err());
+}
+
+#[test]
+fn test_humanoid_profile_matches_common_bone_names() {
+    let profile = RagdollProfile::humanoid();
+
+    assert!(matches!(
+        profile.rule_for("mixamorig:LeftForeArm").unwrap().joint,
+        Some(RagdollJoint::Revolute { .. })
+    ));
+    assert!(matches!(
+        profile.rule_for("mixamorig:RightLeg").unwrap().joint,
+        Some(RagdollJoint::Revolute { .. })
+    ));
+    assert!(matches!(
+        profile.rule_for("mixamorig:RightUpLeg").unwrap().joint,
+        Some(RagdollJoint::Spherical { .. })
+    ));
+    assert!(profile.rule_for("mixamorig:LeftHandIndex1").unwrap().skip);
+    assert!(profile.rule_for("mixamorig:HeadTop_End").unwrap().skip);
+    assert!(profile.rule_for("Hips").is_none());
+}
+
+#[test]
+fn test_ragdoll_builds_parts_and_joints() {
+    let mut character = setup();
+    character.frame();
+
+    let ragdoll = character.ragdoll();
+    assert_eq!(ragdoll.mode(), RagdollMode::Animated);
+    for bone in [HIPS, SPINE, HEAD, UP_LEG, LEG, FOOT] {
+        assert!(
+            ragdoll.part(bone).is_some(),
+            "bone {bone} should have a part"
+        );
+    }
+    assert!(ragdoll.part(TOE).is_none(), "toes are skipped");
+    assert!(ragdoll.part(HIPS).unwrap().joint.is_none());
+
+    let physics = character.world.get_resource::<PhysicsWorld3D>().unwrap();
+
+    // Knee is a hinge, hip a ball joint
+    let knee = ragdoll.part(LEG).unwrap().joint.unwrap();
+    assert_eq!(
+        physics
+            .impulse_joint_set
+            .get(knee)
+            .unwrap()
+            .data
+            .locked_axes,
+        JointAxesMask::LOCKED_REVOLUTE_AXES
+    );
+    let hip = ragdoll.part(UP_LEG).unwrap().joint.unwrap();
+    assert_eq!(
+        physics.impulse_joint_set.get(hip).unwrap().data.locked_axes,
+        JointAxesMask::LOCKED_SPHERICAL_AXES
+    );
+
+    // Mass is distributed over the parts and bones map back to their entities
+    let total_mass: f32 = [HIPS, SPINE, HEAD, UP_LEG, LEG, FOOT]
+        .iter()
+        .map(|&bone| {
+            let part = ragdoll.part(bone).unwrap();
+            assert_eq!(
+                physics.collider_to_entity.get(&part.collider),
+                Some(&character.bones[bone])
+            );
+            assert!(physics.rigid_body_set[part.body].is_kinematic());
+            physics.collider_set[part.collider].mass()
+        })
+        .sum();
+    assert!((total_mass - 70.0).abs() < 1e-2, "total mass {total_mass}");
+}
+
+#[test]
+fn test_animated_ragdoll_follows_bones() {
+    let mut character = setup();
+    character.frame();
+
+    // Move the whole character: kinematic parts follow on the next step
+    character
+        .world
+        .add_component(character.root, Transform::from_xyz(2.0, 0.0, 0.0))
+        .unwrap();
+    character.frame();
+
+    let ragdoll = character.ragdoll();
+    let physics = character.world.get_resource::<PhysicsWorld3D>().unwrap();
+    let hips = physics.rigid_body_set[ragdoll.part(HIPS).unwrap().body].translation();
+    assert!((hips.x - 2.0).abs() < 1e-4);
+    assert!((hips.y - 1.0).abs() < 1e-4);
+
+    // Bone transforms are left to the animation
+    assert_eq!(character.local(HEAD), character.bind[HEAD]);
+}
+
+#[test]
+fn test_root_bones_follow_their_parent_entity() {
+    let mut character = setup();
+
+    // The hips hang under an armature node offset from the character
+    let armature = character.world.spawn();
+    character
+        .world
+        .add_component(
+            armature,
+            GlobalTransform(Transform::from_xyz(0.0, 0.0, 3.0)),
+        )
+        .unwrap();
+    character
+        .world
+        .add_component(character.bones[HIPS], Parent(armature))
+        .unwrap();
+    character.frame();
+
+    let ragdoll = character.ragdoll();
+    {
+        let physics = character.world.get_resource::<PhysicsWorld3D>().unwrap();
+        let hips = physics.rigid_body_set[ragdoll.part(HIPS).unwrap().body].translation();
+        assert!((hips.z - 3.0).abs() < 1e-4);
+        assert!((hips.y - 1.0).abs() < 1e-4);
+    }
+
+    // Simulated bones are written back relative to the same parent
+    character.ragdoll_mut().simulate();
+    character.frame();
+    let hips = character.local(HIPS).translation;
+    assert!(hips.z.abs() < 1e-2, "hips local {hips:?}");
+}
+
+#[test]
+fn test_parts_are_removed_with_the_ragdoll() {
+    let mut character = setup();
+    character.frame();
+    {
+        let physics = character.world.get_resource::<PhysicsWorld3D>().unwrap();
+        assert!(physics.rigid_body_set.len() > 1);
+        assert!(!physics.impulse_joint_set.is_empty());
+    }
+
+    assert!(character.world.despawn(character.root));
+    character.frame();
+
+    // Only the ground is left
+    let physics = character.world.get_resource::<PhysicsWorld3D>().unwrap();
+    assert_eq!(physics.rigid_body_set.len(), 1);
+    assert_eq!(physics.collider_set.len(), 1);
+    assert_eq!(physics.impulse_joint_set.len(), 0);
+    assert!(physics.entity_to_body.is_empty());
+    assert!(physics.body_to_entity.is_empty());
+    assert!(physics.entity_to_collider.is_empty());
+    assert!(physics.collider_to_entity.is_empty());
+}
+
+#[test]
+fn test_simulated_ragdoll_collapses_and_drives_bones() {
+    let mut character = setup();
+    character.frame();
+    let standing_head = character.head_height();
+
+    character.ragdoll_mut().simulate();
+    for _ in 0..180 {
+        character.frame();
+    }
+
+    let ragdoll = character.ragdoll();
+    assert!(ragdoll.is_simulated());
+    {
+        let physics = character.world.get_resource::<PhysicsWorld3D>().unwrap();
+        assert!(physics.rigid_body_set[ragdoll.part(HIPS).unwrap().body].is_dynamic());
+    }
+
+    let head = character.head_height();
+    assert!(head.is_finite());
+    assert!(
+        head < standing_head - 0.5,
+        "head should fall from {standing_head}, got {head}"
+    );
+    assert!(head > -0.1, "ragdoll should rest on the ground, got {head}");
+
+    // Skipped bones keep their local offset to the parent part
+    assert_eq!(
+        character.local(TOE).translation,
+        character.bind[TOE].translation
+    );
+}
+
+#[test]
+fn test_impulse_is_applied_when_simulated() {
+    let mut character = setup();
+    character.frame();
+
+    character.ragdoll_mut().simulate();
+    character
+        .ragdoll_mut()
+        .apply_impulse(SPINE, Vec3::new(0.0, 0.0, 200.0));
+    character.frame();
+
+    let ragdoll = character.ragdoll();
+    let physics = character.world.get_resource::<PhysicsWorld3D>().unwrap();
+    let velocity = physics.rigid_body_set[ragdoll.part(SPINE).unwrap().body].linvel();
+    assert!(
+        velocity.z > 0.5,
+        "hit should push the spine, got {velocity:?}"
+    );
+}
+
+#[test]
+fn test_blend_back_to_animation() {
+    let mut character = setup();
+    character.frame();
+
+    character.ragdoll_mut().simulate();
+    for _ in 0..60 {
+        character.frame();
+    }
+    let fallen_hips = character.local(HIPS).translation;
+    assert!(fallen_hips.distance(character.bind[HIPS].translation) > 0.1);
+
+    character.ragdoll_mut().blend_to_animation_over(0.5);
+
+    // Halfway through the blend the hips are between the two poses
+    for _ in 0..15 {
+        character.animate_bind_pose();
+        character.frame();
+    }
+    let halfway = character.local(HIPS).translation;
+    let target = character.bind[HIPS].translation;
+    assert!(halfway.distance(target) < fallen_hips.distance(target));
+    assert!(halfway.distance(target) > 1e-3);
+    assert!(matches!(
+        character.ragdoll().mode(),
+        RagdollMode::BlendingToAnimation { .. }
+    ));
+
+    for _ in 0..20 {
+        character.animate_bind_pose();
+        character.frame();
+    }
+    assert_eq!(character.ragdoll().mode(), RagdollMode::Animated);
+    assert!(character.local(HIPS).translation.distance(target) < 1e-4);
+
+    // Parts are kinematic again and follow the animation
+    let ragdoll = character.ragdoll();
+    let physics = character.world.get_resource::<PhysicsWorld3D>().unwrap();
+    let hips = &physics.rigid_body_set[ragdoll.part(HIPS).unwrap().body];
+    assert!(hips.is_kinematic());
+    assert!((hips.translation().y - 1.0).abs() < 1e-3);
+}
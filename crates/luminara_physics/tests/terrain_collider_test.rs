@@ -0,0 +1,219 @@
+use luminara_core::World;
+use luminara_math::{Transform, UVec2, Vec2, Vec3};
+use luminara_physics::{
+    restore_physics_snapshot, terrain_collider_system, PhysicsWorld3D, TerrainCollider,
+};
+use luminara_render::{SculptMode, Terrain, TerrainData};
+use luminara_scene::GlobalTransform;
+use rapier3d::prelude::*;
+
+/// Height of the terrain collider under world position (`x`, `z`)
+fn ground_height(world: &World, x: f32, z: f32) -> Option<f32> {
+    let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+    let ray = Ray::new(point![x, 100.0, z], vector![0.0, -1.0, 0.0]);
+    physics
+        .collider_set
+        .iter()
+        .filter_map(|(_, collider)| {
+            collider
+                .shape()
+                .cast_ray(collider.position(), &ray, 1000.0, true)
+        })
+        .reduce(f32::min)
+        .map(|toi| 100.0 - toi)
+}
+
+#[test]
+fn test_terrain_collider_matches_heights_and_edits() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+
+    // A slope rising by 0.5 per sample along X
+    let heights = (0..17 * 17).map(|i| ((i % 17) * 4000) as u16).collect();
+    let data = TerrainData::from_heights(UVec2::splat(17), heights, 2.0, 65535.0 / 8000.0);
+    let entity = world.spawn();
+    world.add_component(entity, Terrain::new(data)).unwrap();
+    world
+        .add_component(entity, Transform::from_xyz(10.0, 1.0, 0.0))
+        .unwrap();
+
+    terrain_collider_system(&mut world);
+
+    let collider = world
+        .get_component::<TerrainCollider>(entity)
+        .unwrap()
+        .clone();
+    {
+        let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+        assert!(physics.rigid_body_set[collider.body].is_fixed());
+        assert_eq!(
+            physics.collider_to_entity.get(&collider.collider),
+            Some(&entity)
+        );
+        assert_eq!(
+            physics.entity_to_collider.get(&entity),
+            Some(&collider.collider)
+        );
+    }
+    // Terrain-local (x, z) sits at world (x + 10, z); heights are offset by 1
+    let expected = |x: f32| 1.0 + x / 4.0;
+    for x in [2.0, 13.0, 30.0] {
+        let height = ground_height(&world, 10.0 + x, 7.0).unwrap();
+        assert!(
+            (height - expected(x)).abs() < 1e-3,
+            "{} vs {}",
+            height,
+            expected(x)
+        );
+    }
+    assert!(
+        ground_height(&world, 5.0, 7.0).is_none(),
+        "nothing before the map"
+    );
+
+    // Sculpting swaps the heightfield without replacing the collider
+    world.get_component_mut::<Terrain>(entity).unwrap().sculpt(
+        Vec2::new(16.0, 16.0),
+        4.0,
+        3.0,
+        SculptMode::Raise,
+    );
+    terrain_collider_system(&mut world);
+    assert_eq!(
+        world
+            .get_component::<TerrainCollider>(entity)
+            .unwrap()
+            .collider,
+        collider.collider
+    );
+    let raised = ground_height(&world, 26.0, 16.0).unwrap();
+    assert!((raised - expected(16.0) - 3.0).abs() < 1e-3);
+
+    // Moving the terrain moves the body
+    world
+        .get_component_mut::<Transform>(entity)
+        .unwrap()
+        .translation = Vec3::new(0.0, 1.0, 0.0);
+    terrain_collider_system(&mut world);
+    let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+    assert_eq!(physics.rigid_body_set[collider.body].translation().x, 0.0);
+    assert_eq!(physics.collider_set.len(), 1);
+}
+
+/// A flat 16 x 16 terrain at height 2
+fn spawn_flat_terrain(world: &mut World) -> luminara_core::Entity {
+    let data = TerrainData::from_heights(UVec2::splat(17), vec![16384; 17 * 17], 1.0, 8.0);
+    let entity = world.spawn();
+    world.add_component(entity, Terrain::new(data)).unwrap();
+    entity
+}
+
+#[test]
+fn test_terrain_collider_follows_global_transform() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+
+    // Parented terrain: the local transform is identity, the world one is not.
+    // The mirrored X scale can't be represented and is dropped
+    let entity = spawn_flat_terrain(&mut world);
+    world.add_component(entity, Transform::IDENTITY).unwrap();
+    world
+        .add_component(
+            entity,
+            GlobalTransform(Transform {
+                translation: Vec3::new(10.0, 1.0, 0.0),
+                scale: Vec3::new(-1.0, 1.0, 1.0),
+                ..Transform::IDENTITY
+            }),
+        )
+        .unwrap();
+
+    terrain_collider_system(&mut world);
+    assert!((ground_height(&world, 18.0, 8.0).unwrap() - 3.0).abs() < 1e-3);
+    assert!(ground_height(&world, 2.0, 8.0).is_none());
+}
+
+#[test]
+fn test_terrain_collider_is_removed_with_its_terrain() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+    let removed = spawn_flat_terrain(&mut world);
+    world.add_component(removed, Transform::IDENTITY).unwrap();
+    let despawned = spawn_flat_terrain(&mut world);
+    world
+        .add_component(despawned, Transform::from_xyz(100.0, 0.0, 0.0))
+        .unwrap();
+    terrain_collider_system(&mut world);
+    assert_eq!(
+        world
+            .get_resource::<PhysicsWorld3D>()
+            .unwrap()
+            .collider_set
+            .len(),
+        2
+    );
+
+    world.remove_component::<Terrain>(removed).unwrap();
+    assert!(world.despawn(despawned));
+    terrain_collider_system(&mut world);
+
+    assert!(world.get_component::<TerrainCollider>(removed).is_none());
+    let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+    assert_eq!(physics.rigid_body_set.len(), 0);
+    assert_eq!(physics.collider_set.len(), 0);
+    assert!(physics.entity_to_body.is_empty());
+    assert!(physics.body_to_entity.is_empty());
+    assert!(physics.entity_to_collider.is_empty());
+    assert!(physics.collider_to_entity.is_empty());
+}
+
+#[test]
+fn test_restore_reconciles_terrain_colliders() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld3D::default());
+    let sculpted = spawn_flat_terrain(&mut world);
+    world.add_component(sculpted, Transform::IDENTITY).unwrap();
+    terrain_collider_system(&mut world);
+    let body = world
+        .get_component::<TerrainCollider>(sculpted)
+        .unwrap()
+        .body;
+    let snapshot = world.get_resource::<PhysicsWorld3D>().unwrap().snapshot();
+
+    // Sculpted and spawned after the snapshot
+    world
+        .get_component_mut::<Terrain>(sculpted)
+        .unwrap()
+        .sculpt(Vec2::splat(8.0), 4.0, 1.0, SculptMode::Raise);
+    let added = spawn_flat_terrain(&mut world);
+    world
+        .add_component(added, Transform::from_xyz(100.0, 0.0, 0.0))
+        .unwrap();
+    terrain_collider_system(&mut world);
+    assert!((ground_height(&world, 8.0, 8.0).unwrap() - 3.0).abs() < 1e-3);
+
+    restore_physics_snapshot(&mut world, &snapshot);
+    assert!(world.get_component::<TerrainCollider>(added).is_none());
+    assert!((ground_height(&world, 8.0, 8.0).unwrap() - 2.0).abs() < 1e-3);
+    {
+        let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+        assert_eq!(physics.rigid_body_set.len(), 1);
+        assert!(physics.rigid_body_set.contains(body));
+    }
+
+    // The restored heightfield catches up with the heights, the new terrain
+    // gets its body back
+    terrain_collider_system(&mut world);
+    assert!((ground_height(&world, 8.0, 8.0).unwrap() - 3.0).abs() < 1e-3);
+    assert!((ground_height(&world, 108.0, 8.0).unwrap() - 2.0).abs() < 1e-3);
+    assert_eq!(
+        world
+            .get_component::<TerrainCollider>(sculpted)
+            .unwrap()
+            .body,
+        body
+    );
+    let physics = world.get_resource::<PhysicsWorld3D>().unwrap();
+    assert_eq!(physics.rigid_body_set.len(), 2);
+    assert_eq!(physics.body_to_entity.get(&body), Some(&sculpted));
+}
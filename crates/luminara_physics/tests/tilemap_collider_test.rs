@@ -0,0 +1,240 @@
+use luminara_core::{Entity, World};
+use luminara_math::{IVec2, Transform, Vec2, Vec3};
+use luminara_physics::{
+    restore_physics_snapshot_2d, tilemap_collider_system_2d, PhysicsWorld2D, TilemapColliders2D,
+};
+use luminara_render::{Rect, Tile, TileCollider, TileLayer, Tilemap};
+use luminara_scene::GlobalTransform;
+use rapier2d::prelude::*;
+
+fn spawn_tilemap(world: &mut World, transform: Transform) -> Entity {
+    let mut tilemap = Tilemap::new(Vec2::splat(1.0));
+    let ground = tilemap.add_layer(TileLayer::new("ground"));
+    tilemap.set_tile_collider(0, 0, Some(TileCollider::Full));
+    tilemap.set_tile_collider(
+        0,
+        1,
+        Some(TileCollider::Rect(Rect::new(
+            Vec2::ZERO,
+            Vec2::new(1.0, 0.5),
+        ))),
+    );
+    for x in 0..3 {
+        tilemap.set_tile(ground, IVec2::new(x, 0), Some(Tile::new(0, 0)));
+    }
+    tilemap.set_tile(ground, IVec2::new(4, 1), Some(Tile::new(0, 1)));
+
+    let entity = world.spawn();
+    world.add_component(entity, tilemap).unwrap();
+    world.add_component(entity, transform).unwrap();
+    entity
+}
+
+fn collider_cuboids(world: &World, entity: Entity) -> Vec<(Vector<f32>, Vector<f32>)> {
+    let colliders = world.get_component::<TilemapColliders2D>(entity).unwrap();
+    let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+    let mut cuboids: Vec<_> = colliders
+        .colliders
+        .iter()
+        .map(|handle| {
+            let collider = &physics.collider_set[*handle];
+            let cuboid = collider.shape().as_cuboid().unwrap();
+            (*collider.translation(), cuboid.half_extents)
+        })
+        .collect();
+    cuboids.sort_by(|a, b| a.0.x.total_cmp(&b.0.x));
+    cuboids
+}
+
+#[test]
+fn test_tilemap_colliders_follow_merged_tiles() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld2D::default());
+    let mut transform = Transform::from_xyz(10.0, 5.0, 0.0);
+    transform.scale = Vec3::new(2.0, 2.0, 1.0);
+    let entity = spawn_tilemap(&mut world, transform);
+
+    tilemap_collider_system_2d(&mut world);
+
+    // The solid row becomes one box; the half tile adds a slab
+    let cuboids = collider_cuboids(&world, entity);
+    assert_eq!(cuboids.len(), 2);
+    assert_eq!(cuboids[0].0, vector![13.0, 6.0]);
+    assert_eq!(cuboids[0].1, vector![3.0, 1.0]);
+    assert_eq!(cuboids[1].0, vector![19.0, 7.5]);
+    assert_eq!(cuboids[1].1, vector![1.0, 0.5]);
+
+    let body = world
+        .get_component::<TilemapColliders2D>(entity)
+        .unwrap()
+        .body;
+    {
+        let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+        assert!(physics.rigid_body_set[body].is_fixed());
+        assert_eq!(physics.body_to_entity.get(&body), Some(&entity));
+        assert!(physics
+            .collider_set
+            .iter()
+            .all(|(handle, _)| physics.collider_to_entity.get(&handle) == Some(&entity)));
+    }
+
+    // Moving the map moves the body without rebuilding it
+    world
+        .get_component_mut::<Transform>(entity)
+        .unwrap()
+        .translation
+        .x = 20.0;
+    tilemap_collider_system_2d(&mut world);
+    assert_eq!(
+        world
+            .get_component::<TilemapColliders2D>(entity)
+            .unwrap()
+            .body,
+        body
+    );
+    {
+        let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+        assert_eq!(physics.rigid_body_set[body].translation().x, 20.0);
+    }
+
+    // Editing the tiles rebuilds the colliders
+    world
+        .get_component_mut::<Tilemap>(entity)
+        .unwrap()
+        .set_tile(0, IVec2::new(4, 1), None);
+    tilemap_collider_system_2d(&mut world);
+    let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+    assert_eq!(physics.rigid_body_set.len(), 1);
+    assert_eq!(physics.collider_set.len(), 1);
+    assert!(!physics.body_to_entity.contains_key(&body));
+}
+
+#[test]
+fn test_tilemap_colliders_follow_global_transform() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld2D::default());
+
+    // Parented tilemap: the local transform is identity, the world one is not
+    let entity = spawn_tilemap(&mut world, Transform::IDENTITY);
+    let mut global = Transform::from_xyz(10.0, 5.0, 0.0);
+    global.scale = Vec3::new(2.0, 2.0, 1.0);
+    world
+        .add_component(entity, GlobalTransform(global))
+        .unwrap();
+
+    tilemap_collider_system_2d(&mut world);
+    let body = world
+        .get_component::<TilemapColliders2D>(entity)
+        .unwrap()
+        .body;
+    {
+        let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+        assert_eq!(
+            *physics.rigid_body_set[body].translation(),
+            vector![10.0, 5.0]
+        );
+    }
+    let cuboids = collider_cuboids(&world, entity);
+    assert_eq!(cuboids[0].0, vector![13.0, 6.0]);
+    assert_eq!(cuboids[0].1, vector![3.0, 1.0]);
+
+    // The parent moving shows up in the world transform only
+    world
+        .get_component_mut::<GlobalTransform>(entity)
+        .unwrap()
+        .0
+        .translation
+        .x = 30.0;
+    tilemap_collider_system_2d(&mut world);
+    let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+    assert_eq!(physics.rigid_body_set[body].translation().x, 30.0);
+}
+
+#[test]
+fn test_tilemap_colliders_are_removed_with_the_tilemap() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld2D::default());
+    let removed = spawn_tilemap(&mut world, Transform::IDENTITY);
+    let despawned = spawn_tilemap(&mut world, Transform::from_xyz(100.0, 0.0, 0.0));
+    tilemap_collider_system_2d(&mut world);
+    {
+        let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+        assert_eq!(physics.rigid_body_set.len(), 2);
+        assert_eq!(physics.collider_set.len(), 4);
+    }
+
+    world.remove_component::<Tilemap>(removed).unwrap();
+    tilemap_collider_system_2d(&mut world);
+    assert!(world.get_component::<TilemapColliders2D>(removed).is_none());
+    assert_eq!(
+        world
+            .get_resource::<PhysicsWorld2D>()
+            .unwrap()
+            .rigid_body_set
+            .len(),
+        1
+    );
+
+    // The last tilemap going away still clears its body
+    assert!(world.despawn(despawned));
+    tilemap_collider_system_2d(&mut world);
+    let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+    assert_eq!(physics.rigid_body_set.len(), 0);
+    assert_eq!(physics.collider_set.len(), 0);
+    assert!(physics.entity_to_body.is_empty());
+    assert!(physics.body_to_entity.is_empty());
+    assert!(physics.collider_to_entity.is_empty());
+}
+
+#[test]
+fn test_restore_reconciles_tilemap_colliders() {
+    let mut world = World::new();
+    world.insert_resource(PhysicsWorld2D::default());
+    let kept = spawn_tilemap(&mut world, Transform::IDENTITY);
+    let edited = spawn_tilemap(&mut world, Transform::from_xyz(100.0, 0.0, 0.0));
+    let despawned = spawn_tilemap(&mut world, Transform::from_xyz(200.0, 0.0, 0.0));
+    tilemap_collider_system_2d(&mut world);
+    let kept_body = world
+        .get_component::<TilemapColliders2D>(kept)
+        .unwrap()
+        .body;
+    let snapshot = world.get_resource::<PhysicsWorld2D>().unwrap().snapshot();
+
+    // Rebuilt, spawned and despawned after the snapshot
+    world
+        .get_component_mut::<Tilemap>(edited)
+        .unwrap()
+        .set_tile(0, IVec2::new(4, 1), None);
+    let added = spawn_tilemap(&mut world, Transform::from_xyz(300.0, 0.0, 0.0));
+    assert!(world.despawn(despawned));
+    tilemap_collider_system_2d(&mut world);
+
+    restore_physics_snapshot_2d(&mut world, &snapshot);
+    assert!(world.get_component::<TilemapColliders2D>(edited).is_none());
+    assert!(world.get_component::<TilemapColliders2D>(added).is_none());
+    {
+        let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+        assert_eq!(physics.rigid_body_set.len(), 1);
+        assert_eq!(physics.collider_set.len(), 2);
+        assert_eq!(physics.body_to_entity.get(&kept_body), Some(&kept));
+        // Every tile collider reports its tilemap again
+        assert!(physics
+            .collider_set
+            .iter()
+            .all(|(handle, _)| physics.collider_to_entity.get(&handle) == Some(&kept)));
+    }
+
+    tilemap_collider_system_2d(&mut world);
+    assert_eq!(
+        world
+            .get_component::<TilemapColliders2D>(kept)
+            .unwrap()
+            .body,
+        kept_body
+    );
+    let physics = world.get_resource::<PhysicsWorld2D>().unwrap();
+    assert_eq!(physics.rigid_body_set.len(), 3);
+    assert_eq!(physics.collider_set.len(), 5);
+    assert_eq!(physics.collider_to_entity.len(), 5);
+    assert!(!physics.body_to_entity.values().any(|e| *e == despawned));
+}
@@ -0,0 +1,81 @@
+// Built-in PbrMaterial. Bindings, `material` and `VertexInput` come from the
+// generated material prelude.
+//
+// Permutations:
+// - NORMAL_MAP: perturb the normal with `normal_texture`
+// - SKINNED: deform by the joint palette, see `luminara::skinning`
+
+#import luminara::lighting
+#ifdef SKINNED
+#import luminara::skinning
+#endif
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+#ifdef NORMAL_MAP
+    @location(3) tangent: vec4<f32>,
+#endif
+};
+
+@vertex
+#ifdef SKINNED
+fn vs_main(in: VertexInput, skin: SkinInput) -> VertexOutput {
+    let position = skin_position(skin.joints, skin.weights, in.position);
+    let normal = skin_normal(skin.joints, skin.weights, in.normal);
+    let tangent = skin_direction(skin.joints, skin.weights, in.tangent.xyz);
+#else
+fn vs_main(in: VertexInput) -> VertexOutput {
+    let position = in.position;
+    let normal = in.normal;
+    let tangent = in.tangent.xyz;
+#endif
+    var out: VertexOutput;
+    let world_pos = model.model * vec4<f32>(position, 1.0);
+    out.position = camera.view_proj * world_pos;
+    out.world_pos = world_pos.xyz;
+    out.normal = normalize((model.model * vec4<f32>(normal, 0.0)).xyz);
+    out.uv = in.uv;
+#ifdef NORMAL_MAP
+    out.tangent = vec4<f32>(normalize((model.model * vec4<f32>(tangent, 0.0)).xyz), in.tangent.w);
+#endif
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Unset texture slots are bound to white, so sampling is always valid
+    let base_color = material.albedo * textureSample(albedo_texture, albedo_texture_sampler, in.uv);
+    let metallic_roughness = textureSample(
+        metallic_roughness_texture,
+        metallic_roughness_texture_sampler,
+        in.uv,
+    );
+#ifdef NORMAL_MAP
+    let tangent_normal = textureSample(normal_texture, normal_texture_sampler, in.uv).xyz * 2.0 - 1.0;
+    let n = normalize(in.normal);
+    let t = normalize(in.tangent.xyz - n * dot(n, in.tangent.xyz));
+    let b = cross(n, t) * in.tangent.w;
+    let normal = normalize(mat3x3<f32>(t, b, n) * tangent_normal);
+#else
+    let normal = normalize(in.normal);
+#endif
+    if (!material_alpha_test(base_color.a)) {
+        discard;
+    }
+
+    let metallic = material.metallic * metallic_roughness.b;
+    let roughness = material.roughness * metallic_roughness.g;
+    let view_dir = normalize(camera.camera_pos - in.world_pos);
+
+    // Image-based light from the camera's environment and reflection
+    // probes, or the constant ambient without one
+    let direct = shade_direct(default_light(), base_color.rgb, normal, view_dir, metallic, roughness);
+    let lit = direct + environment_lighting(base_color.rgb, normal, view_dir, metallic, roughness);
+    let final_color = lit + material.emissive.rgb;
+
+    // Linear HDR output; tone mapping happens in the post-process pass
+    return vec4<f32>(final_color, base_color.a);
+}
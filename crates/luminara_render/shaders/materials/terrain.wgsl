@@ -0,0 +1,64 @@
+// Built-in TerrainMaterial. Bindings, `material` and `VertexInput` come from
+// the generated material prelude.
+//
+// `splat_0` and `splat_1` hold the weights of layers 0-3 and 4-7, one texel
+// per heightmap sample. Layer textures repeat `material.tiling` times across
+// the terrain.
+
+#import luminara::lighting
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let world_pos = model.model * vec4<f32>(in.position, 1.0);
+    out.position = camera.view_proj * world_pos;
+    out.world_pos = world_pos.xyz;
+    out.normal = normalize((model.model * vec4<f32>(in.normal, 0.0)).xyz);
+    out.uv = in.uv;
+    return out;
+}
+
+// Material samplers clamp, so wrap by hand; gradients of the unwrapped
+// coordinates keep the mip level stable across the seams
+fn sample_layer(layer: texture_2d<f32>, layer_sampler: sampler, uv: vec2<f32>, ddx: vec2<f32>, ddy: vec2<f32>) -> vec3<f32> {
+    return textureSampleGrad(layer, layer_sampler, fract(uv), ddx, ddy).rgb;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Mesh UVs run from the first to the last sample; move them onto texel
+    // centers of the splat maps
+    let splat_size = vec2<f32>(textureDimensions(splat_0));
+    let splat_uv = (in.uv * (splat_size - 1.0) + 0.5) / splat_size;
+    let weights_0 = textureSample(splat_0, splat_0_sampler, splat_uv);
+    let weights_1 = textureSample(splat_1, splat_1_sampler, splat_uv);
+
+    let uv = in.uv * material.tiling;
+    let ddx = dpdx(uv);
+    let ddy = dpdy(uv);
+    var albedo = sample_layer(layer_0, layer_0_sampler, uv, ddx, ddy) * weights_0.r;
+    albedo += sample_layer(layer_1, layer_1_sampler, uv, ddx, ddy) * weights_0.g;
+    albedo += sample_layer(layer_2, layer_2_sampler, uv, ddx, ddy) * weights_0.b;
+    albedo += sample_layer(layer_3, layer_3_sampler, uv, ddx, ddy) * weights_0.a;
+    albedo += sample_layer(layer_4, layer_4_sampler, uv, ddx, ddy) * weights_1.r;
+    albedo += sample_layer(layer_5, layer_5_sampler, uv, ddx, ddy) * weights_1.g;
+    albedo += sample_layer(layer_6, layer_6_sampler, uv, ddx, ddy) * weights_1.b;
+    albedo += sample_layer(layer_7, layer_7_sampler, uv, ddx, ddy) * weights_1.a;
+    let total = dot(weights_0, vec4<f32>(1.0)) + dot(weights_1, vec4<f32>(1.0));
+    albedo = albedo / max(total, 0.0001);
+
+    let normal = normalize(in.normal);
+    let view_dir = normalize(camera.camera_pos - in.world_pos);
+    let direct = shade_direct(default_light(), albedo, normal, view_dir, 0.0, material.roughness);
+    let lit = direct + environment_lighting(albedo, normal, view_dir, 0.0, material.roughness);
+
+    // Linear HDR output; tone mapping happens in the post-process pass
+    return vec4<f32>(lit, 1.0);
+}
@@ -0,0 +1,37 @@
+// Built-in UnlitMaterial. Bindings, `material` and `VertexInput` come from
+// the generated material prelude.
+//
+// Permutations:
+// - SKINNED: deform by the joint palette, see `luminara::skinning`
+
+#ifdef SKINNED
+#import luminara::skinning
+#endif
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+#ifdef SKINNED
+fn vs_main(in: VertexInput, skin: SkinInput) -> VertexOutput {
+    let position = skin_position(skin.joints, skin.weights, in.position);
+#else
+fn vs_main(in: VertexInput) -> VertexOutput {
+    let position = in.position;
+#endif
+    var out: VertexOutput;
+    out.position = camera.view_proj * model.model * vec4<f32>(position, 1.0);
+    out.uv = in.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = material.color * textureSample(color_texture, color_texture_sampler, in.uv);
+    if (!material_alpha_test(color.a)) {
+        discard;
+    }
+    return color;
+}
@@ -0,0 +1,47 @@
+#define_import_path luminara::lighting
+
+// Shading shared by the built-in lit materials
+
+struct SurfaceLight {
+    direction: vec3<f32>,
+    color: vec3<f32>,
+};
+
+// Fixed sun-like light until materials read the scene's lights
+fn default_light() -> SurfaceLight {
+    return SurfaceLight(normalize(vec3<f32>(0.3, 0.7, 0.5)), vec3<f32>(1.0, 0.95, 0.9));
+}
+
+// Lambert diffuse plus a Blinn-Phong approximation of PBR specular
+fn shade_direct(
+    light: SurfaceLight,
+    albedo: vec3<f32>,
+    normal: vec3<f32>,
+    view_dir: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+) -> vec3<f32> {
+    let n_dot_l = max(dot(normal, light.direction), 0.0);
+    let diffuse = albedo * light.color * n_dot_l;
+
+    let half_dir = normalize(light.direction + view_dir);
+    let spec_power = mix(16.0, 256.0, 1.0 - roughness);
+    let spec = pow(max(dot(normal, half_dir), 0.0), spec_power);
+    let fresnel = metallic + (1.0 - metallic) * pow(1.0 - max(dot(view_dir, half_dir), 0.0), 5.0);
+    let specular = light.color * spec * fresnel;
+
+    return diffuse + specular;
+}
+
+// Direct light plus a constant ambient, for shaders without an environment
+fn shade_surface(
+    light: SurfaceLight,
+    albedo: vec3<f32>,
+    normal: vec3<f32>,
+    view_dir: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+) -> vec3<f32> {
+    let ambient = albedo * vec3<f32>(0.15, 0.15, 0.2);
+    return ambient + shade_direct(light, albedo, normal, view_dir, metallic, roughness);
+}
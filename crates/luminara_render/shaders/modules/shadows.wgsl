@@ -0,0 +1,175 @@
+#define_import_path luminara::shadows
+
+// Filtering of the point and spot light shadow atlas packed by `ShadowAtlas`.
+// The importing shader binds the atlas, its comparison sampler and the light
+// and view buffers of `ShadowAtlasResources`, picks a light's view with
+// `shadow_view_index` and passes both to `sample_shadow`.
+
+struct ShadowLight {
+    // xyz: position, w: near plane
+    position_near: vec4<f32>,
+    // x: far plane, y: depth bias, z: normal bias, w: PCSS light size
+    params: vec4<f32>,
+    first_view: u32,
+    view_count: u32,
+    // 0: hard, 1: PCF, 2: PCSS
+    filter_kind: u32,
+    filter_radius: u32,
+    search_radius: u32,
+    // Width of the shadow frustum one unit in front of the light
+    frustum_width: f32,
+};
+
+struct ShadowView {
+    view_proj: mat4x4<f32>,
+    // min uv, max uv of the view's tile
+    uv_rect: vec4<f32>,
+};
+
+// Index into the view buffer of the view covering `world_position`: the
+// cube face it lies behind for point lights
+fn shadow_view_index(light: ShadowLight, world_position: vec3<f32>) -> u32 {
+    if light.view_count < 6u {
+        return light.first_view;
+    }
+    let d = world_position - light.position_near.xyz;
+    let a = abs(d);
+    var face: u32;
+    if a.x >= a.y && a.x >= a.z {
+        face = select(1u, 0u, d.x >= 0.0);
+    } else if a.y >= a.z {
+        face = select(3u, 2u, d.y >= 0.0);
+    } else {
+        face = select(5u, 4u, d.z >= 0.0);
+    }
+    return light.first_view + face;
+}
+
+// Atlas uv of `tile_uv` inside the tile, kept half a texel away from its
+// neighbours
+fn shadow_atlas_uv(uv_rect: vec4<f32>, tile_uv: vec2<f32>, atlas_size: vec2<f32>) -> vec2<f32> {
+    let half_texel = 0.5 / atlas_size;
+    return clamp(
+        mix(uv_rect.xy, uv_rect.zw, tile_uv),
+        uv_rect.xy + half_texel,
+        uv_rect.zw - half_texel
+    );
+}
+
+// Distance along the view axis of a perspective depth
+fn shadow_linear_depth(depth: f32, near: f32, far: f32) -> f32 {
+    return near * far / (far - depth * (far - near));
+}
+
+// Average of a (2 * radius + 1)² grid of comparisons `spacing` texels apart
+fn shadow_pcf(
+    atlas: texture_depth_2d,
+    atlas_sampler: sampler_comparison,
+    uv_rect: vec4<f32>,
+    tile_uv: vec2<f32>,
+    depth: f32,
+    radius: i32,
+    spacing: f32,
+) -> f32 {
+    let atlas_size = vec2<f32>(textureDimensions(atlas));
+    let step = spacing / ((uv_rect.z - uv_rect.x) * atlas_size.x);
+    var lit = 0.0;
+    for (var y = -radius; y <= radius; y = y + 1) {
+        for (var x = -radius; x <= radius; x = x + 1) {
+            let offset = vec2<f32>(f32(x), f32(y)) * step;
+            let uv = shadow_atlas_uv(uv_rect, tile_uv + offset, atlas_size);
+            lit += textureSampleCompareLevel(atlas, atlas_sampler, uv, depth);
+        }
+    }
+    let taps = f32((2 * radius + 1) * (2 * radius + 1));
+    return lit / taps;
+}
+
+// Percentage-closer soft shadows: the average blocker depth sets the
+// penumbra width, which spreads out the PCF grid
+fn shadow_pcss(
+    atlas: texture_depth_2d,
+    atlas_sampler: sampler_comparison,
+    light: ShadowLight,
+    uv_rect: vec4<f32>,
+    tile_uv: vec2<f32>,
+    depth: f32,
+) -> f32 {
+    let atlas_size = vec2<f32>(textureDimensions(atlas));
+    let tile_texels = (uv_rect.z - uv_rect.x) * atlas_size.x;
+    let search = i32(light.search_radius);
+    var blocker_sum = 0.0;
+    var blockers = 0.0;
+    for (var y = -search; y <= search; y = y + 1) {
+        for (var x = -search; x <= search; x = x + 1) {
+            let offset = vec2<f32>(f32(x), f32(y)) / tile_texels;
+            let uv = shadow_atlas_uv(uv_rect, tile_uv + offset, atlas_size);
+            let occluder = textureLoad(atlas, vec2<i32>(uv * atlas_size), 0);
+            if occluder < depth {
+                blocker_sum += occluder;
+                blockers += 1.0;
+            }
+        }
+    }
+    if blockers == 0.0 {
+        return 1.0;
+    }
+
+    let near = light.position_near.w;
+    let far = light.params.x;
+    let receiver = shadow_linear_depth(depth, near, far);
+    let blocker = shadow_linear_depth(blocker_sum / blockers, near, far);
+    // Penumbra width over the width of the shadow frustum at the receiver
+    let penumbra = light.params.w / light.frustum_width * (receiver - blocker)
+        / (blocker * receiver);
+    let radius = i32(light.filter_radius);
+    let spacing = max(0.5 * penumbra * tile_texels / f32(max(radius, 1)), 1.0);
+    return shadow_pcf(atlas, atlas_sampler, uv_rect, tile_uv, depth, radius, spacing);
+}
+
+// Fraction of `light` reaching `world_position`, 1 when lit. Receivers move
+// towards the light by its depth bias and along `normal` by its normal bias
+// in texels, less as the surface faces the light.
+fn sample_shadow(
+    atlas: texture_depth_2d,
+    atlas_sampler: sampler_comparison,
+    light: ShadowLight,
+    view: ShadowView,
+    world_position: vec3<f32>,
+    normal: vec3<f32>,
+) -> f32 {
+    let atlas_size = vec2<f32>(textureDimensions(atlas));
+    let tile_texels = (view.uv_rect.z - view.uv_rect.x) * atlas_size.x;
+    let to_light = light.position_near.xyz - world_position;
+    let distance = max(length(to_light), 1e-4);
+    let l = to_light / distance;
+    let texel_world = light.frustum_width * distance / tile_texels;
+    let n_dot_l = clamp(dot(normal, l), 0.0, 1.0);
+    let biased = world_position
+        + l * light.params.y
+        + normal * light.params.z * texel_world * (1.0 - n_dot_l);
+
+    let clip = view.view_proj * vec4<f32>(biased, 1.0);
+    if clip.w <= 0.0 {
+        return 1.0;
+    }
+    let ndc = clip.xyz / clip.w;
+    if any(abs(ndc.xy) > vec2<f32>(1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+    let tile_uv = ndc.xy * vec2<f32>(0.5, -0.5) + 0.5;
+
+    switch light.filter_kind {
+        case 1u: {
+            let radius = i32(light.filter_radius);
+            return shadow_pcf(atlas, atlas_sampler, view.uv_rect, tile_uv, ndc.z, radius, 1.0);
+        }
+        case 2u: {
+            return shadow_pcss(atlas, atlas_sampler, light, view.uv_rect, tile_uv, ndc.z);
+        }
+        default: {
+            let uv = shadow_atlas_uv(view.uv_rect, tile_uv, atlas_size);
+            return textureSampleCompareLevel(atlas, atlas_sampler, uv, ndc.z);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+#define_import_path luminara::skinning
+
+// Vertex skinning against a joint palette uploaded from `JointPalette`.
+// Define DUAL_QUAT_SKINNING to blend dual quaternions instead of matrices.
+// `SkinInput` reads the second vertex buffer laid out by `SkinWeights::desc`.
+
+struct SkinJoint {
+    matrix: mat4x4<f32>,
+    real: vec4<f32>,
+    dual: vec4<f32>,
+};
+
+struct SkinInput {
+    @location(10) joints: vec4<u32>,
+    @location(11) weights: vec4<f32>,
+};
+
+// Next to the model transform in the per-object group
+@group(1) @binding(1)
+var<storage, read> joint_palette: array<SkinJoint>;
+
+fn skin_matrix(joints: vec4<u32>, weights: vec4<f32>) -> mat4x4<f32> {
+    return joint_palette[joints.x].matrix * weights.x
+        + joint_palette[joints.y].matrix * weights.y
+        + joint_palette[joints.z].matrix * weights.z
+        + joint_palette[joints.w].matrix * weights.w;
+}
+
+struct BlendedDualQuat {
+    real: vec4<f32>,
+    dual: vec4<f32>,
+};
+
+fn skin_dual_quat(joints: vec4<u32>, weights: vec4<f32>) -> BlendedDualQuat {
+    let pivot = joint_palette[joints.x].real;
+    var real = vec4<f32>(0.0);
+    var dual = vec4<f32>(0.0);
+    for (var i = 0u; i < 4u; i++) {
+        let joint = joint_palette[joints[i]];
+        // Stay in the pivot's hemisphere so rotations blend the short way
+        let w = select(weights[i], -weights[i], dot(joint.real, pivot) < 0.0);
+        real += joint.real * w;
+        dual += joint.dual * w;
+    }
+    let len = length(real);
+    return BlendedDualQuat(real / len, dual / len);
+}
+
+fn quat_rotate(q: vec4<f32>, v: vec3<f32>) -> vec3<f32> {
+    let t = 2.0 * cross(q.xyz, v);
+    return v + q.w * t + cross(q.xyz, t);
+}
+
+fn dual_quat_translation(dq: BlendedDualQuat) -> vec3<f32> {
+    return 2.0 * (dq.real.w * dq.dual.xyz - dq.dual.w * dq.real.xyz + cross(dq.real.xyz, dq.dual.xyz));
+}
+
+fn skin_position(joints: vec4<u32>, weights: vec4<f32>, position: vec3<f32>) -> vec3<f32> {
+#ifdef DUAL_QUAT_SKINNING
+    let dq = skin_dual_quat(joints, weights);
+    return quat_rotate(dq.real, position) + dual_quat_translation(dq);
+#else
+    return (skin_matrix(joints, weights) * vec4<f32>(position, 1.0)).xyz;
+#endif
+}
+
+// Tangents and other directions lying on the surface
+fn skin_direction(joints: vec4<u32>, weights: vec4<f32>, direction: vec3<f32>) -> vec3<f32> {
+#ifdef DUAL_QUAT_SKINNING
+    return normalize(quat_rotate(skin_dual_quat(joints, weights).real, direction));
+#else
+    return normalize((skin_matrix(joints, weights) * vec4<f32>(direction, 0.0)).xyz);
+#endif
+}
+
+// Normals follow the inverse-transpose, which stays perpendicular to the
+// surface under non-uniform joint scale. The cofactor matrix is that up to
+// the determinant, whose sign is kept for mirrored joints.
+fn skin_normal(joints: vec4<u32>, weights: vec4<f32>, normal: vec3<f32>) -> vec3<f32> {
+#ifdef DUAL_QUAT_SKINNING
+    return skin_direction(joints, weights, normal);
+#else
+    let m = skin_matrix(joints, weights);
+    let x = m[0].xyz;
+    let y = m[1].xyz;
+    let z = m[2].xyz;
+    let cofactor = mat3x3<f32>(cross(y, z), cross(z, x), cross(x, y));
+    return normalize(cofactor * normal) * sign(dot(x, cross(y, z)));
+#endif
+}
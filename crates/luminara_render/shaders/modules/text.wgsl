@@ -0,0 +1,32 @@
+#define_import_path luminara::text
+
+// Glyph atlas sampling for text laid out against a `GlyphAtlas`.
+// Define TEXT_SDF or TEXT_MSDF to match the atlas raster mode.
+
+@group(1) @binding(0)
+var glyph_atlas: texture_2d<f32>;
+@group(1) @binding(1)
+var glyph_sampler: sampler;
+
+fn median3(v: vec3<f32>) -> f32 {
+    return max(min(v.r, v.g), min(max(v.r, v.g), v.b));
+}
+
+// `distance` is the decoded field value (0.5 on the edge); `screen_px_range`
+// is the field range in screen pixels: 2 * sdf_range * drawn size / raster size
+fn distance_alpha(distance: f32, screen_px_range: f32) -> f32 {
+    return clamp((distance - 0.5) * screen_px_range + 0.5, 0.0, 1.0);
+}
+
+fn glyph_alpha(uv: vec2<f32>, screen_px_range: f32) -> f32 {
+    let texel = textureSample(glyph_atlas, glyph_sampler, uv);
+#ifdef TEXT_MSDF
+    return distance_alpha(median3(texel.rgb), screen_px_range);
+#else
+#ifdef TEXT_SDF
+    return distance_alpha(texel.r, screen_px_range);
+#else
+    return texel.r;
+#endif
+#endif
+}
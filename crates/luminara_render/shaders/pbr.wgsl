@@ -1,5 +1,5 @@
 // PBR Forward+ Shader for Luminara Engine
-// Implements physically-based rendering with tile-based light culling
+// Implements physically-based rendering with clustered light culling
 
 // Camera uniform
 struct CameraUniform {
@@ -50,24 +50,65 @@ struct DirectionalLightData {
     intensity: f32,
 }
 
-struct PointLightData {
+// Point or spot light; point lights carry cone cosines accepting every direction
+struct ClusteredLightData {
     position: vec3<f32>,
     range: f32,
     color: vec3<f32>,
     intensity: f32,
+    direction: vec3<f32>,
+    spot_outer_cos: f32,
+    spot_inner_cos: f32,
 }
 
 struct LightBuffer {
     directional_count: u32,
-    point_count: u32,
-    _padding: vec2<u32>,
     directional_lights: array<DirectionalLightData, 4>,
-    point_lights: array<PointLightData, 256>,
+}
+
+struct ClusterParams {
+    view: mat4x4<f32>,
+    // Tiles in X and Y, depth slices
+    dimensions: vec4<u32>,
+    screen_size: vec2<f32>,
+    near: f32,
+    far: f32,
+}
+
+struct ClusterRange {
+    offset: u32,
+    count: u32,
 }
 
 @group(2) @binding(0)
 var<storage, read> lights: LightBuffer;
 
+@group(2) @binding(1)
+var<storage, read> clustered_lights: array<ClusteredLightData>;
+
+@group(2) @binding(2)
+var<uniform> clusters: ClusterParams;
+
+@group(2) @binding(3)
+var<storage, read> cluster_ranges: array<ClusterRange>;
+
+@group(2) @binding(4)
+var<storage, read> cluster_indices: array<u32>;
+
+// Cluster of a fragment: screen tile and exponential depth slice
+fn cluster_index(frag_coord: vec2<f32>, world_position: vec3<f32>) -> u32 {
+    let dims = clusters.dimensions;
+    let depth = -(clusters.view * vec4<f32>(world_position, 1.0)).z;
+    let slice_f = log(max(depth, clusters.near) / clusters.near) / log(clusters.far / clusters.near);
+    let slice = min(u32(max(slice_f * f32(dims.z), 0.0)), dims.z - 1u);
+    let tile = vec2<u32>(clamp(
+        frag_coord / clusters.screen_size * vec2<f32>(dims.xy),
+        vec2<f32>(0.0),
+        vec2<f32>(dims.xy) - 1.0,
+    ));
+    return tile.x + tile.y * dims.x + slice * dims.x * dims.y;
+}
+
 // Vertex input
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -209,16 +250,23 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
         Lo = Lo + (kD * albedo_color.rgb / PI + specular) * radiance * NdotL;
     }
     
-    // Point lights
-    for (var i = 0u; i < lights.point_count; i = i + 1u) {
-        let light = lights.point_lights[i];
-        let L = normalize(light.position - input.world_position);
+    // Point and spot lights reaching this fragment's cluster
+    let range = cluster_ranges[cluster_index(input.clip_position.xy, input.world_position)];
+    for (var i = 0u; i < range.count; i = i + 1u) {
+        let light = clustered_lights[cluster_indices[range.offset + i]];
+        let to_light = light.position - input.world_position;
+        let distance = length(to_light);
+        if (distance > light.range) {
+            continue;
+        }
+        let L = to_light / max(distance, 0.0001);
         let H = normalize(V + L);
-        let distance = length(light.position - input.world_position);
         
-        // Attenuation
-        let attenuation = 1.0 / (distance * distance);
-        let radiance = light.color * light.intensity * attenuation;
+        // Attenuation with a smooth cutoff at the range, and the spot cone
+        let falloff = saturate(1.0 - pow(distance / light.range, 4.0));
+        let attenuation = falloff * falloff / max(distance * distance, 0.0001);
+        let cone = smoothstep(light.spot_outer_cos, light.spot_inner_cos, dot(-L, light.direction));
+        let radiance = light.color * light.intensity * attenuation * cone;
         
         // Cook-Torrance BRDF
         let NDF = distribution_ggx(N, H, roughness);
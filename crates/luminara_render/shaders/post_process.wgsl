@@ -1,4 +1,26 @@
-// Post-processing shader with tone mapping and gamma correction
+// Post-processing effects, one fragment entry point per pass.
+//
+// Every pass draws a fullscreen triangle clipped to a viewport of its output
+// and samples the matching region of its input, so several cameras can share
+// one target. Passes read `input_texture`, and some an `aux_texture`, the
+// color grading LUT or the scene depth.
+
+struct Params {
+    // Output viewport in pixels: x, y, width, height
+    out_rect: vec4<f32>,
+    // Input region in UV: min.xy, max.xy
+    in_rect: vec4<f32>,
+    // Auxiliary texture region in UV: min.xy, max.xy
+    aux_rect: vec4<f32>,
+    // Effect parameters
+    settings: vec4<f32>,
+    extra: vec4<f32>,
+    // Input range of the color grading LUT
+    lut_min: vec4<f32>,
+    lut_max: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+}
 
 @group(0) @binding(0)
 var input_texture: texture_2d<f32>;
@@ -6,6 +28,22 @@ var input_texture: texture_2d<f32>;
 @group(0) @binding(1)
 var input_sampler: sampler;
 
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+@group(0) @binding(3)
+var aux_texture: texture_2d<f32>;
+
+@group(0) @binding(4)
+var lut_texture: texture_3d<f32>;
+
+@group(0) @binding(5)
+var depth_texture: texture_depth_2d;
+
+// Nearest, non-filtering: GLSL can't `textureLoad` depth textures
+@group(0) @binding(6)
+var depth_sampler: sampler;
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
@@ -15,17 +53,156 @@ struct VertexOutput {
 @vertex
 fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     var output: VertexOutput;
-    
+
     // Generate fullscreen triangle
     let x = f32((vertex_index << 1u) & 2u);
     let y = f32(vertex_index & 2u);
-    
+
     output.position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
     output.uv = vec2<f32>(x, y);
-    
+
     return output;
 }
 
+// Position inside the output viewport, 0 to 1
+fn local_uv(position: vec4<f32>) -> vec2<f32> {
+    return (position.xy - params.out_rect.xy) / params.out_rect.zw;
+}
+
+// Input UV of a fragment
+fn input_uv(position: vec4<f32>) -> vec2<f32> {
+    return mix(params.in_rect.xy, params.in_rect.zw, local_uv(position));
+}
+
+// Sample the input without bleeding outside its region
+fn sample_input(uv: vec2<f32>) -> vec4<f32> {
+    let half_texel = 0.5 / vec2<f32>(textureDimensions(input_texture));
+    let clamped = clamp(uv, params.in_rect.xy + half_texel, params.in_rect.zw - half_texel);
+    return textureSampleLevel(input_texture, input_sampler, clamped, 0.0);
+}
+
+// Sample the auxiliary texture at the point of `uv` in the input
+fn sample_aux(uv: vec2<f32>) -> vec4<f32> {
+    let local = (uv - params.in_rect.xy) / (params.in_rect.zw - params.in_rect.xy);
+    let half_texel = 0.5 / vec2<f32>(textureDimensions(aux_texture));
+    let aux_uv = clamp(
+        mix(params.aux_rect.xy, params.aux_rect.zw, local),
+        params.aux_rect.xy + half_texel,
+        params.aux_rect.zw - half_texel,
+    );
+    return textureSampleLevel(aux_texture, input_sampler, aux_uv, 0.0);
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// ---------------------------------------------------------------------------
+// Bloom
+
+// Downsample to the bloom texture, keeping what is above the threshold with
+// a soft knee. settings.x: threshold
+@fragment
+fn fs_bloom_prefilter(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = input_uv(input.position);
+    let texel = 1.0 / vec2<f32>(textureDimensions(input_texture));
+    // Four bilinear taps average a 4x4 block
+    let color = 0.25 * (
+        sample_input(uv + vec2<f32>(-1.0, -1.0) * texel).rgb
+        + sample_input(uv + vec2<f32>(1.0, -1.0) * texel).rgb
+        + sample_input(uv + vec2<f32>(-1.0, 1.0) * texel).rgb
+        + sample_input(uv + vec2<f32>(1.0, 1.0) * texel).rgb
+    );
+
+    let threshold = params.settings.x;
+    let knee = threshold * 0.5;
+    let brightness = max(color.r, max(color.g, color.b));
+    var soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-5);
+    let contribution = max(soft, brightness - threshold) / max(brightness, 1e-5);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+// Nine-tap gaussian through five bilinear fetches along `direction`
+fn gaussian_blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let texel = direction * params.settings.x / vec2<f32>(textureDimensions(input_texture));
+    var color = sample_input(uv) * 0.2270270270;
+    color += sample_input(uv + texel * 1.3846153846) * 0.3162162162;
+    color += sample_input(uv - texel * 1.3846153846) * 0.3162162162;
+    color += sample_input(uv + texel * 3.2307692308) * 0.0702702703;
+    color += sample_input(uv - texel * 3.2307692308) * 0.0702702703;
+    return color;
+}
+
+// settings.x: tap spacing in texels
+@fragment
+fn fs_blur_h(input: VertexOutput) -> @location(0) vec4<f32> {
+    return gaussian_blur(input_uv(input.position), vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_v(input: VertexOutput) -> @location(0) vec4<f32> {
+    return gaussian_blur(input_uv(input.position), vec2<f32>(0.0, 1.0));
+}
+
+// Add the blurred bloom texture from aux. settings.x: intensity
+@fragment
+fn fs_bloom_composite(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = input_uv(input.position);
+    let color = sample_input(uv);
+    let bloom = sample_aux(uv).rgb * params.settings.x;
+    return vec4<f32>(color.rgb + bloom, color.a);
+}
+
+// ---------------------------------------------------------------------------
+// Exposure
+
+// Log2 luminance of the input, to be averaged by fs_downsample
+@fragment
+fn fs_luminance(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position)).rgb;
+    let log_luminance = log2(max(luminance(color), 1e-4));
+    return vec4<f32>(log_luminance, 0.0, 0.0, 1.0);
+}
+
+// Average 2x2 input texels with one bilinear tap
+@fragment
+fn fs_downsample(input: VertexOutput) -> @location(0) vec4<f32> {
+    return sample_input(input_uv(input.position));
+}
+
+// Adapt the exposure value in aux towards the average log luminance in the
+// 1x1 input. settings: min_ev, max_ev, speed_up, speed_down.
+// extra.x: seconds since the last frame, extra.y: 1 to skip adaptation
+@fragment
+fn fs_adapt_exposure(input: VertexOutput) -> @location(0) vec4<f32> {
+    let average = textureLoad(input_texture, vec2<i32>(0, 0), 0).r;
+    // Stops from mid gray (0.18)
+    let target_ev = clamp(average - log2(0.18), params.settings.x, params.settings.y);
+    let current = textureLoad(aux_texture, vec2<i32>(0, 0), 0).r;
+    if params.extra.y > 0.5 {
+        return vec4<f32>(target_ev, 0.0, 0.0, 1.0);
+    }
+    let speed = select(params.settings.w, params.settings.z, target_ev > current);
+    let adapted = current + (target_ev - current) * (1.0 - exp(-params.extra.x * speed));
+    return vec4<f32>(adapted, 0.0, 0.0, 1.0);
+}
+
+// Scale by 2^compensation, and by the adapted exposure in aux when
+// settings.y is 1. settings.x: compensation
+@fragment
+fn fs_exposure(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position));
+    var ev = -params.settings.x;
+    if params.settings.y > 0.5 {
+        ev += textureLoad(aux_texture, vec2<i32>(0, 0), 0).r;
+    }
+    return vec4<f32>(color.rgb * exp2(-ev), color.a);
+}
+
+// ---------------------------------------------------------------------------
+// Tone mapping
+
 // ACES tone mapping
 fn aces_tonemap(color: vec3<f32>) -> vec3<f32> {
     let a = 2.51;
@@ -41,20 +218,395 @@ fn reinhard_tonemap(color: vec3<f32>) -> vec3<f32> {
     return color / (color + vec3<f32>(1.0));
 }
 
+// Minimal AgX with the default look, after Wrensch's polynomial fit
+fn agx_tonemap(color: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3<f32>(
+        0.842479062, 0.0423282423, 0.0423756549,
+        0.0784336, 0.878468636, 0.0784336,
+        0.0792237451, 0.0791661275, 0.879142974,
+    );
+    let outset = mat3x3<f32>(
+        1.196879, -0.0528968518, -0.0529716355,
+        -0.0980208811, 1.15190313, -0.0980434501,
+        -0.0990297441, -0.0989611768, 1.15107367,
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    let encoded = log2(max(inset * color, vec3<f32>(1e-10)));
+    let x = clamp((encoded - min_ev) / (max_ev - min_ev), vec3<f32>(0.0), vec3<f32>(1.0));
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    let curve = 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x
+        + 0.4298 * x2 + 0.1191 * x - 0.00232;
+    // The curve's output is display encoded with a 2.2 gamma
+    let display = clamp(outset * curve, vec3<f32>(0.0), vec3<f32>(1.0));
+    return pow(display, vec3<f32>(2.2));
+}
+
+@fragment
+fn fs_tonemap_aces(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position));
+    return vec4<f32>(aces_tonemap(max(color.rgb, vec3<f32>(0.0))), color.a);
+}
+
+@fragment
+fn fs_tonemap_agx(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position));
+    return vec4<f32>(agx_tonemap(max(color.rgb, vec3<f32>(0.0))), color.a);
+}
+
+@fragment
+fn fs_tonemap_reinhard(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position));
+    return vec4<f32>(reinhard_tonemap(max(color.rgb, vec3<f32>(0.0))), color.a);
+}
+
+// ---------------------------------------------------------------------------
+// Color grading and lens effects
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+// Look the sRGB-encoded color up in the LUT. settings.x: strength,
+// settings.y: LUT size
+@fragment
+fn fs_color_grading(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position));
+    let encoded = linear_to_srgb(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0)));
+    let normalized = clamp(
+        (encoded - params.lut_min.xyz) / (params.lut_max.xyz - params.lut_min.xyz),
+        vec3<f32>(0.0),
+        vec3<f32>(1.0),
+    );
+    // Entries sit at texel centers
+    let size = params.settings.y;
+    let coords = (normalized * (size - 1.0) + 0.5) / size;
+    let graded = textureSampleLevel(lut_texture, input_sampler, coords, 0.0).rgb;
+    let linear = srgb_to_linear(clamp(graded, vec3<f32>(0.0), vec3<f32>(1.0)));
+    return vec4<f32>(mix(color.rgb, linear, params.settings.x), color.a);
+}
+
+// settings: intensity, radius, smoothness
+@fragment
+fn fs_vignette(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position));
+    // 1 at the corners
+    let from_center = length(local_uv(input.position) - 0.5) * sqrt(2.0);
+    let radius = params.settings.y;
+    let falloff = smoothstep(radius, radius + max(params.settings.z, 1e-4), from_center);
+    return vec4<f32>(color.rgb * (1.0 - params.settings.x * falloff), color.a);
+}
+
+// settings.x: largest offset as a fraction of the view
+@fragment
+fn fs_chromatic_aberration(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = input_uv(input.position);
+    let from_center = local_uv(input.position) - 0.5;
+    let offset = from_center * 2.0 * params.settings.x * (params.in_rect.zw - params.in_rect.xy);
+    let color = sample_input(uv);
+    let red = sample_input(uv - offset).r;
+    let blue = sample_input(uv + offset).b;
+    return vec4<f32>(red, color.g, blue, color.a);
+}
+
+// ---------------------------------------------------------------------------
+// Antialiasing
+
+// Perceptual luma of display color
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(max(luminance(color), 0.0));
+}
+
+@fragment
+fn fs_fxaa(input: VertexOutput) -> @location(0) vec4<f32> {
+    let span_max = 8.0;
+    let reduce_mul = 1.0 / 8.0;
+    let reduce_min = 1.0 / 128.0;
+
+    let uv = input_uv(input.position);
+    let texel = 1.0 / vec2<f32>(textureDimensions(input_texture));
+    let center = sample_input(uv);
+    let luma_nw = luma(sample_input(uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne = luma(sample_input(uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw = luma(sample_input(uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se = luma(sample_input(uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let luma_m = luma(center.rgb);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Blur along the edge, perpendicular to the luma gradient
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * reduce_mul, reduce_min);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2<f32>(-span_max), vec2<f32>(span_max)) * texel;
+
+    let near = 0.5 * (
+        sample_input(uv + direction * (1.0 / 3.0 - 0.5)).rgb
+        + sample_input(uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    let far = near * 0.5 + 0.25 * (
+        sample_input(uv - direction * 0.5).rgb + sample_input(uv + direction * 0.5).rgb
+    );
+    let luma_far = luma(far);
+    if luma_far < luma_min || luma_far > luma_max {
+        return vec4<f32>(near, center.a);
+    }
+    return vec4<f32>(far, center.a);
+}
+
+const SMAA_THRESHOLD: f32 = 0.1;
+const SMAA_MAX_SEARCH: i32 = 16;
+
+fn input_texel(pixel: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(input_texture));
+    return textureLoad(input_texture, clamp(pixel, vec2<i32>(0), size - 1), 0);
+}
+
+fn region_pixels() -> vec4<i32> {
+    let size = vec2<f32>(textureDimensions(input_texture));
+    return vec4<i32>(vec4<f32>(params.in_rect.xy * size, params.in_rect.zw * size - 1.0));
+}
+
+// Input texel clamped to the input region
+fn region_texel(pixel: vec2<i32>) -> vec4<f32> {
+    let region = region_pixels();
+    return input_texel(clamp(pixel, region.xy, region.zw));
+}
+
+// Luma edges on the left (r) and top (g) side of each pixel
+@fragment
+fn fs_smaa_edges(input: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(input_uv(input.position) * vec2<f32>(textureDimensions(input_texture)));
+    let region = region_pixels();
+    let center = luma(region_texel(pixel).rgb);
+    let left = luma(region_texel(pixel - vec2<i32>(1, 0)).rgb);
+    let top = luma(region_texel(pixel - vec2<i32>(0, 1)).rgb);
+    var edges = step(vec2<f32>(SMAA_THRESHOLD), abs(vec2<f32>(center - left, center - top)));
+    // Nothing lies beyond the region
+    edges *= vec2<f32>(f32(pixel.x > region.x), f32(pixel.y > region.y));
+    return vec4<f32>(edges, 0.0, 1.0);
+}
+
+// Whether the edge of `axis` (0 left, 1 top) is set at `pixel`
+fn has_edge(pixel: vec2<i32>, axis: i32) -> bool {
+    return region_texel(pixel)[axis] > 0.5;
+}
+
+// Share of a pixel covered by the other side of an edge, from the shape of
+// the edge line: it runs `before` pixels back and `after` pixels on, ending
+// in crossing edges or not
+fn edge_area(before: i32, after: i32, cross_before: bool, cross_after: bool) -> f32 {
+    let span = f32(before + after + 1);
+    let t = (f32(before) + 0.5) / span;
+    if cross_before && cross_after {
+        // U shapes bulge in the middle of the line
+        return 0.5 * abs(1.0 - 2.0 * t);
+    }
+    if cross_before {
+        return 0.5 * (1.0 - t);
+    }
+    if cross_after {
+        return 0.5 * t;
+    }
+    return 0.0;
+}
+
+// Blend weights across the top (r) and left (g) edge of each pixel, from
+// the edges texture in the input
+@fragment
+fn fs_smaa_weights(input: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(input_uv(input.position) * vec2<f32>(textureDimensions(input_texture)));
+    var weights = vec2<f32>(0.0);
+
+    // Horizontal line above the pixel
+    if has_edge(pixel, 1) {
+        var left = 0;
+        while left < SMAA_MAX_SEARCH && has_edge(pixel - vec2<i32>(left + 1, 0), 1) {
+            left += 1;
+        }
+        var right = 0;
+        while right < SMAA_MAX_SEARCH && has_edge(pixel + vec2<i32>(right + 1, 0), 1) {
+            right += 1;
+        }
+        let start = pixel - vec2<i32>(left, 0);
+        let end = pixel + vec2<i32>(right + 1, 0);
+        let cross_start = has_edge(start, 0) || has_edge(start - vec2<i32>(0, 1), 0);
+        let cross_end = has_edge(end, 0) || has_edge(end - vec2<i32>(0, 1), 0);
+        weights.r = edge_area(left, right, cross_start, cross_end);
+    }
+
+    // Vertical line left of the pixel
+    if has_edge(pixel, 0) {
+        var up = 0;
+        while up < SMAA_MAX_SEARCH && has_edge(pixel - vec2<i32>(0, up + 1), 0) {
+            up += 1;
+        }
+        var down = 0;
+        while down < SMAA_MAX_SEARCH && has_edge(pixel + vec2<i32>(0, down + 1), 0) {
+            down += 1;
+        }
+        let start = pixel - vec2<i32>(0, up);
+        let end = pixel + vec2<i32>(0, down + 1);
+        let cross_start = has_edge(start, 1) || has_edge(start - vec2<i32>(1, 0), 1);
+        let cross_end = has_edge(end, 1) || has_edge(end - vec2<i32>(1, 0), 1);
+        weights.g = edge_area(up, down, cross_start, cross_end);
+    }
+    return vec4<f32>(weights, 0.0, 1.0);
+}
+
+fn weights_texel(pixel: vec2<i32>) -> vec2<f32> {
+    let size = vec2<i32>(textureDimensions(aux_texture));
+    return textureLoad(aux_texture, clamp(pixel, vec2<i32>(0), size - 1), 0).rg;
+}
+
+// Mix each pixel with its neighbors across weighted edges, weights in aux
+@fragment
+fn fs_smaa_blend(input: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(input_uv(input.position) * vec2<f32>(textureDimensions(input_texture)));
+    let own = weights_texel(pixel);
+    let up = own.r;
+    let left = own.g;
+    let down = weights_texel(pixel + vec2<i32>(0, 1)).r;
+    let right = weights_texel(pixel + vec2<i32>(1, 0)).g;
+    let total = up + left + down + right;
+    let center = region_texel(pixel);
+    if total <= 0.0 {
+        return center;
+    }
+
+    var color = region_texel(pixel - vec2<i32>(0, 1)).rgb * up
+        + region_texel(pixel - vec2<i32>(1, 0)).rgb * left
+        + region_texel(pixel + vec2<i32>(0, 1)).rgb * down
+        + region_texel(pixel + vec2<i32>(1, 0)).rgb * right;
+    let blend = min(total, 1.0);
+    color = mix(center.rgb, color / total, blend);
+    return vec4<f32>(color, center.a);
+}
+
+// ---------------------------------------------------------------------------
+// Ambient occlusion
+
+// World position under a point of the view, local UV and depth
+fn world_position(local: vec2<f32>, depth: f32) -> vec3<f32> {
+    let ndc = vec4<f32>(local.x * 2.0 - 1.0, 1.0 - local.y * 2.0, depth, 1.0);
+    let world = params.inv_view_proj * ndc;
+    return world.xyz / world.w;
+}
+
+fn scene_depth(uv: vec2<f32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    let pixel = clamp(vec2<i32>(uv * vec2<f32>(size)), vec2<i32>(0), size - 1);
+    let center = (vec2<f32>(pixel) + 0.5) / vec2<f32>(size);
+    return textureSampleLevel(depth_texture, depth_sampler, center, 0.0);
+}
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+// Occlusion of the scene at each pixel, 1 for unoccluded.
+// settings: radius, intensity, sample count
+@fragment
+fn fs_ssao(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = input_uv(input.position);
+    let local = local_uv(input.position);
+    let depth = scene_depth(uv);
+    let position = world_position(local, depth);
+    // Derivatives are taken before any branch
+    let normal = normalize(cross(dpdy(position), dpdx(position)));
+    if depth >= 1.0 {
+        return vec4<f32>(1.0);
+    }
+
+    let radius = params.settings.x;
+    let samples = clamp(i32(params.settings.z), 1, 64);
+    var occlusion = 0.0;
+    for (var i = 0; i < samples; i += 1) {
+        let seed = input.position.xy + vec2<f32>(f32(i) * 7.31, f32(i) * 3.17);
+        // Random direction in the hemisphere around the normal
+        let z = hash(seed) * 2.0 - 1.0;
+        let angle = hash(seed.yx + 1.7) * 6.2831853;
+        let planar = sqrt(max(1.0 - z * z, 0.0));
+        var direction = vec3<f32>(planar * cos(angle), planar * sin(angle), z);
+        direction *= sign(dot(direction, normal));
+        // More samples close to the point
+        let scale = mix(0.1, 1.0, pow(f32(i + 1) / f32(samples), 2.0));
+        let sample_position = position + direction * radius * scale;
+
+        let clip = params.view_proj * vec4<f32>(sample_position, 1.0);
+        if clip.w <= 0.0 {
+            continue;
+        }
+        let ndc = clip.xyz / clip.w;
+        let sample_local = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+        let sample_uv = mix(params.in_rect.xy, params.in_rect.zw, sample_local);
+        let occluder_depth = scene_depth(sample_uv);
+        if occluder_depth < ndc.z - 1e-5 {
+            // Ignore occluders far outside the radius
+            let occluder = world_position(sample_local, occluder_depth);
+            let range = smoothstep(0.0, 1.0, radius / max(distance(occluder, position), 1e-4));
+            occlusion += range;
+        }
+    }
+    let ao = 1.0 - occlusion / f32(samples);
+    return vec4<f32>(ao, ao, ao, 1.0);
+}
+
+// 4x4 box blur hiding the sampling noise
+@fragment
+fn fs_ssao_blur(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = input_uv(input.position);
+    let texel = 1.0 / vec2<f32>(textureDimensions(input_texture));
+    var sum = 0.0;
+    for (var y = -1; y <= 2; y += 1) {
+        for (var x = -1; x <= 2; x += 1) {
+            sum += sample_input(uv + (vec2<f32>(f32(x), f32(y)) - 0.5) * texel).r;
+        }
+    }
+    let ao = sum / 16.0;
+    return vec4<f32>(ao, ao, ao, 1.0);
+}
+
+// Darken the input by the occlusion in aux. settings.y: intensity
+@fragment
+fn fs_ssao_apply(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = input_uv(input.position);
+    let color = sample_input(uv);
+    let ao = sample_aux(uv).r;
+    return vec4<f32>(color.rgb * mix(1.0, ao, params.settings.y), color.a);
+}
+
+// ---------------------------------------------------------------------------
+// Output
+
 // Gamma correction
 fn gamma_correct(color: vec3<f32>, gamma: f32) -> vec3<f32> {
     return pow(color, vec3<f32>(1.0 / gamma));
 }
 
 @fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let hdr_color = textureSample(input_texture, input_sampler, input.uv);
-    
-    // Apply ACES tone mapping
-    var color = aces_tonemap(hdr_color.rgb);
-    
-    // Apply gamma correction (2.2)
-    color = gamma_correct(color, 2.2);
-    
-    return vec4<f32>(color, hdr_color.a);
+fn fs_output(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position));
+    let clamped = clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    return vec4<f32>(gamma_correct(clamped, 2.2), color.a);
+}
+
+// sRGB render targets encode gamma in hardware
+@fragment
+fn fs_output_srgb(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input_uv(input.position));
+    return vec4<f32>(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0)), color.a);
 }
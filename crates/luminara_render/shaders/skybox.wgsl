@@ -0,0 +1,42 @@
+// Skybox: a fullscreen triangle on the far plane sampling an environment
+// cubemap along the view ray of each pixel. Drawn after opaque geometry
+// with a less-equal depth test, so it only fills uncovered pixels.
+
+struct SkyboxUniform {
+    inv_view_proj: mat4x4<f32>,
+    // x: brightness
+    brightness: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> skybox: SkyboxUniform;
+
+@group(0) @binding(1)
+var skybox_texture: texture_cube<f32>;
+
+@group(0) @binding(2)
+var skybox_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    let ndc = uv * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let near = skybox.inv_view_proj * vec4<f32>(in.ndc, 0.0, 1.0);
+    let far = skybox.inv_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = normalize(far.xyz / far.w - near.xyz / near.w);
+    let color = textureSample(skybox_texture, skybox_sampler, direction).rgb;
+    return vec4<f32>(color * skybox.brightness.x, 1.0);
+}
@@ -0,0 +1,49 @@
+// World-space text: `Text3dMesh` vertices, already in world space, sampled
+// from the shared glyph atlas. TEXT_SDF or TEXT_MSDF match its raster mode.
+
+#import luminara::text
+
+struct Text3dUniform {
+    view_proj: mat4x4<f32>,
+    // Atlas size in texels
+    atlas_size: vec2<f32>,
+    // Distance covered by the field across an edge, in atlas texels
+    field_range: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> text: Text3dUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = text.view_proj * vec4<f32>(in.position, 1.0);
+    out.uv = in.uv;
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Atlas texels covered by one screen pixel
+    let texels = fwidth(in.uv) * text.atlas_size;
+    let screen_px_range = text.field_range / max(0.5 * (texels.x + texels.y), 1e-4);
+    let alpha = glyph_alpha(in.uv, screen_px_range) * in.color.a;
+    if (alpha <= 0.0) {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb, alpha);
+}
@@ -10,7 +10,7 @@
 //! SymExpr to enable AI-driven shader generation from mathematical expressions"
 
 use crate::error::RenderError;
-use crate::material::Material;
+use crate::material::ShaderMaterial;
 use crate::shader::Shader;
 use crate::shader_generator::ShaderGenerator;
 use luminara_math::symbolic::SymExpr;
@@ -301,12 +301,12 @@ impl AiShaderPipeline {
         description: &str,
         material_name: &str,
         device: &wgpu::Device,
-    ) -> AiShaderResult<Material> {
+    ) -> AiShaderResult<ShaderMaterial> {
         // Generate and compile shader
         let shader = self.generate_and_compile_shader(description, device)?;
 
         // Create material with the shader
-        let material = Material::new(material_name, Arc::new(shader));
+        let material = ShaderMaterial::new(material_name, Arc::new(shader));
 
         Ok(material)
     }
@@ -1,3 +1,4 @@
+use crate::animation_graph::{BonePose, Pose, RootMotion};
 use luminara_asset::{Asset, AssetLoadError, AssetLoader, Handle};
 use luminara_core::{Component, Entity};
 use luminara_math::{Mat4, Quat, Vec3};
@@ -16,6 +17,111 @@ impl Asset for AnimationClip {
     }
 }
 
+impl AnimationClip {
+    /// Sample every channel at `time` seconds, clamped to the keyframes
+    pub fn sample(&self, time: f32) -> Pose {
+        let mut pose = Pose::default();
+        for channel in &self.channels {
+            let Some((k, t)) = keyframe(&channel.inputs, time) else {
+                continue;
+            };
+            let bone = pose.bones.entry(channel.target_node_index).or_default();
+            match (&channel.target_path, &channel.outputs) {
+                (AnimationPath::Translation, AnimationOutput::Vector3(values)) => {
+                    bone.translation = interpolate(values, k, t, |a, b, t| a.lerp(b, t));
+                }
+                (AnimationPath::Rotation, AnimationOutput::Rotation(values)) => {
+                    bone.rotation = interpolate(values, k, t, |a, b, t| a.slerp(b, t));
+                }
+                (AnimationPath::Scale, AnimationOutput::Vector3(values)) => {
+                    bone.scale = interpolate(values, k, t, |a, b, t| a.lerp(b, t));
+                }
+                (AnimationPath::Weights, AnimationOutput::Scalar(values)) => {
+                    // One weight per morph target for each keyframe
+                    let targets = values.len() / channel.inputs.len();
+                    let weight = |frame: usize, target: usize| values.get(frame * targets + target);
+                    let weights = (0..targets)
+                        .filter_map(|i| {
+                            let a = *weight(k, i)?;
+                            Some(weight(k + 1, i).map_or(a, |&b| a + (b - a) * t))
+                        })
+                        .collect();
+                    pose.weights.insert(channel.target_node_index, weights);
+                }
+                _ => {}
+            }
+        }
+        pose.bones.retain(|_, bone| *bone != BonePose::default());
+        pose
+    }
+
+    /// Motion of `node` while playback moves from `from` to `to` seconds.
+    /// With `wrapped`, playback looped past the end of the clip in between.
+    pub fn root_motion(&self, node: usize, from: f32, to: f32, wrapped: bool) -> RootMotion {
+        let sample = |time: f32| {
+            let bone = self.sample(time).bones.remove(&node).unwrap_or_default();
+            (
+                bone.translation.unwrap_or(Vec3::ZERO),
+                bone.rotation.unwrap_or(Quat::IDENTITY),
+            )
+        };
+        let delta = |(t0, r0): (Vec3, Quat), (t1, r1): (Vec3, Quat)| RootMotion {
+            translation: t1 - t0,
+            rotation: r0.inverse() * r1,
+        };
+
+        if wrapped {
+            let to_end = delta(sample(from), sample(self.duration));
+            let from_start = delta(sample(0.0), sample(to));
+            to_end.then(&from_start)
+        } else {
+            delta(sample(from), sample(to))
+        }
+    }
+}
+
+/// Index of the keyframe at or before `time` and the blend factor towards the
+/// next one
+fn keyframe(inputs: &[f32], time: f32) -> Option<(usize, f32)> {
+    if inputs.is_empty() {
+        return None;
+    }
+
+    // Binary search for the keyframe bracket
+    let mut lo = 0usize;
+    let mut hi = inputs.len() - 1;
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if inputs[mid] < time {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    // lo is now the index of the first keyframe >= time
+    let k = lo.saturating_sub(1);
+
+    let t = if k + 1 < inputs.len() {
+        let duration = inputs[k + 1] - inputs[k];
+        if duration > 0.0001 {
+            ((time - inputs[k]) / duration).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    };
+    Some((k, t))
+}
+
+fn interpolate<T: Copy>(values: &[T], k: usize, t: f32, mix: impl Fn(T, T, f32) -> T) -> Option<T> {
+    let value = *values.get(k)?;
+    Some(match values.get(k + 1) {
+        Some(&next) => mix(value, next, t),
+        None => value,
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct AnimationChannel {
     pub target_node_index: usize,
@@ -73,6 +179,7 @@ pub struct SkinnedMesh {
     pub joints: Vec<Entity>, // Entity IDs of bones
     pub inverse_bind_matrices: Vec<Mat4>,
     pub skeleton: Option<Skeleton>,
+    pub method: crate::skinning::SkinningMethod,
 }
 
 impl Component for SkinnedMesh {
@@ -117,173 +224,188 @@ impl AssetLoader for GltfLoader {
             .map(|n| n.name().unwrap_or("unnamed").to_string())
             .collect();
 
+        let get_buffer = |buf: gltf::Buffer| match buf.source() {
+            gltf::buffer::Source::Bin => blob,
+            gltf::buffer::Source::Uri(_) => None,
+        };
+
         // ── Extract skeleton from first skin ─────────────────
-        let skeleton = if let Some(skin) = gltf.skins().next() {
-            let joints: Vec<_> = skin.joints().collect();
-            let reader = skin.reader(|buf| match buf.source() {
-                gltf::buffer::Source::Bin => blob,
-                gltf::buffer::Source::Uri(_) => None,
-            });
+        let skeleton = gltf
+            .skins()
+            .next()
+            .map(|skin| read_skeleton(&skin, get_buffer));
 
-            let inverse_bind_matrices: Vec<Mat4> = reader
-                .read_inverse_bind_matrices()
-                .map(|ibm| ibm.map(|m| Mat4::from_cols_array_2d(&m)).collect())
-                .unwrap_or_else(|| vec![Mat4::IDENTITY; joints.len()]);
+        // ── Extract all animations ───────────────────────────
+        let animation_clips = gltf
+            .animations()
+            .map(|anim| read_animation_clip(&anim, get_buffer))
+            .collect();
 
-            // Build bone list
-            let mut bones = Vec::with_capacity(joints.len());
-            let mut hierarchy = Vec::with_capacity(joints.len());
+        Ok(GltfScene {
+            name: _path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "unnamed".to_string()),
+            skeleton,
+            animation_clips,
+            node_names,
+        })
+    }
+}
 
-            // Create a map from node index -> joint index
-            let mut node_to_joint: std::collections::HashMap<usize, usize> =
-                std::collections::HashMap::new();
-            for (ji, joint) in joints.iter().enumerate() {
-                node_to_joint.insert(joint.index(), ji);
-            }
+/// Read the bones of a glTF skin, in joint order
+pub(crate) fn read_skeleton<'a, 's, F>(skin: &'a gltf::Skin<'a>, get_buffer: F) -> Skeleton
+where
+    F: Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>,
+{
+    let joints: Vec<_> = skin.joints().collect();
+    let reader = skin.reader(get_buffer);
+
+    let inverse_bind_matrices: Vec<Mat4> = reader
+        .read_inverse_bind_matrices()
+        .map(|ibm| ibm.map(|m| Mat4::from_cols_array_2d(&m)).collect())
+        .unwrap_or_else(|| vec![Mat4::IDENTITY; joints.len()]);
+
+    // Build bone list
+    let mut bones = Vec::with_capacity(joints.len());
+    let mut hierarchy = Vec::with_capacity(joints.len());
+
+    // Create a map from node index -> joint index
+    let mut node_to_joint: std::collections::HashMap<usize, usize> =
+        std::collections::HashMap::new();
+    for (ji, joint) in joints.iter().enumerate() {
+        node_to_joint.insert(joint.index(), ji);
+    }
 
-            for (ji, joint) in joints.iter().enumerate() {
-                let (t, r, s) = joint.transform().decomposed();
-                let translation = Vec3::new(t[0], t[1], t[2]);
-                let rotation = Quat::from_xyzw(r[0], r[1], r[2], r[3]);
-                let scale = Vec3::new(s[0], s[1], s[2]);
-                let local_transform =
-                    Mat4::from_scale_rotation_translation(scale, rotation, translation);
-
-                let ibm = if ji < inverse_bind_matrices.len() {
-                    inverse_bind_matrices[ji]
-                } else {
-                    Mat4::IDENTITY
-                };
-
-                bones.push(Bone {
-                    name: joint.name().unwrap_or("bone").to_string(),
-                    local_transform,
-                    inverse_bind_matrix: ibm,
-                });
-
-                // Find parent joint index
-                let parent_idx = joints
-                    .iter()
-                    .enumerate()
-                    .find(|(_, potential_parent)| {
-                        potential_parent
-                            .children()
-                            .any(|child| child.index() == joint.index())
-                    })
-                    .map(|(pi, _)| pi);
-                hierarchy.push(parent_idx);
-            }
+    for (ji, joint) in joints.iter().enumerate() {
+        let (t, r, s) = joint.transform().decomposed();
+        let translation = Vec3::new(t[0], t[1], t[2]);
+        let rotation = Quat::from_xyzw(r[0], r[1], r[2], r[3]);
+        let scale = Vec3::new(s[0], s[1], s[2]);
+        let local_transform = Mat4::from_scale_rotation_translation(scale, rotation, translation);
 
-            Some(Skeleton { bones, hierarchy })
+        let ibm = if ji < inverse_bind_matrices.len() {
+            inverse_bind_matrices[ji]
         } else {
-            None
+            Mat4::IDENTITY
         };
 
-        // ── Extract all animations ───────────────────────────
-        let mut animation_clips = Vec::new();
-
-        for anim in gltf.animations() {
-            let mut channels = Vec::new();
-            let mut max_time: f32 = 0.0;
-
-            for channel in anim.channels() {
-                let target = channel.target();
-                let node_index = target.node().index();
-                let property = target.property();
-
-                let reader = channel.reader(|buf| match buf.source() {
-                    gltf::buffer::Source::Bin => blob,
-                    gltf::buffer::Source::Uri(_) => None,
-                });
-
-                // Read input timestamps
-                let inputs: Vec<f32> = reader
-                    .read_inputs()
-                    .map(|iter| iter.collect())
-                    .unwrap_or_default();
+        bones.push(Bone {
+            name: joint.name().unwrap_or("bone").to_string(),
+            local_transform,
+            inverse_bind_matrix: ibm,
+        });
+
+        // Find parent joint index
+        let parent_idx = joints
+            .iter()
+            .enumerate()
+            .find(|(_, potential_parent)| {
+                potential_parent
+                    .children()
+                    .any(|child| child.index() == joint.index())
+            })
+            .map(|(pi, _)| pi);
+        hierarchy.push(parent_idx);
+    }
 
-                if let Some(&last) = inputs.last() {
-                    max_time = max_time.max(last);
-                }
+    Skeleton { bones, hierarchy }
+}
 
-                // Read output values
-                let (target_path, outputs) = match property {
-                    gltf::animation::Property::Translation => {
-                        let values: Vec<Vec3> = reader
-                            .read_outputs()
-                            .map(|out| match out {
-                                gltf::animation::util::ReadOutputs::Translations(iter) => {
-                                    iter.map(|t| Vec3::new(t[0], t[1], t[2])).collect()
-                                }
-                                _ => Vec::new(),
-                            })
-                            .unwrap_or_default();
-                        (AnimationPath::Translation, AnimationOutput::Vector3(values))
-                    }
-                    gltf::animation::Property::Rotation => {
-                        let values: Vec<Quat> = reader
-                            .read_outputs()
-                            .map(|out| match out {
-                                gltf::animation::util::ReadOutputs::Rotations(iter) => iter
-                                    .into_f32()
-                                    .map(|r| Quat::from_xyzw(r[0], r[1], r[2], r[3]))
-                                    .collect(),
-                                _ => Vec::new(),
-                            })
-                            .unwrap_or_default();
-                        (AnimationPath::Rotation, AnimationOutput::Rotation(values))
-                    }
-                    gltf::animation::Property::Scale => {
-                        let values: Vec<Vec3> = reader
-                            .read_outputs()
-                            .map(|out| match out {
-                                gltf::animation::util::ReadOutputs::Scales(iter) => {
-                                    iter.map(|s| Vec3::new(s[0], s[1], s[2])).collect()
-                                }
-                                _ => Vec::new(),
-                            })
-                            .unwrap_or_default();
-                        (AnimationPath::Scale, AnimationOutput::Vector3(values))
-                    }
-                    gltf::animation::Property::MorphTargetWeights => {
-                        let values: Vec<f32> = reader
-                            .read_outputs()
-                            .map(|out| match out {
-                                gltf::animation::util::ReadOutputs::MorphTargetWeights(iter) => {
-                                    iter.into_f32().collect()
-                                }
-                                _ => Vec::new(),
-                            })
-                            .unwrap_or_default();
-                        (AnimationPath::Weights, AnimationOutput::Scalar(values))
-                    }
-                };
-
-                if !inputs.is_empty() {
-                    channels.push(AnimationChannel {
-                        target_node_index: node_index,
-                        target_path,
-                        inputs,
-                        outputs,
-                    });
-                }
+/// Read the channels of a glTF animation; channels target glTF node indices
+pub(crate) fn read_animation_clip<'a, 's, F>(
+    anim: &gltf::Animation<'a>,
+    get_buffer: F,
+) -> AnimationClip
+where
+    F: Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>,
+{
+    let mut channels = Vec::new();
+    let mut max_time: f32 = 0.0;
+
+    for channel in anim.channels() {
+        let target = channel.target();
+        let node_index = target.node().index();
+        let property = target.property();
+
+        let reader = channel.reader(get_buffer.clone());
+
+        // Read input timestamps
+        let inputs: Vec<f32> = reader
+            .read_inputs()
+            .map(|iter| iter.collect())
+            .unwrap_or_default();
+
+        if let Some(&last) = inputs.last() {
+            max_time = max_time.max(last);
+        }
+
+        // Read output values
+        let (target_path, outputs) = match property {
+            gltf::animation::Property::Translation => {
+                let values: Vec<Vec3> = reader
+                    .read_outputs()
+                    .map(|out| match out {
+                        gltf::animation::util::ReadOutputs::Translations(iter) => {
+                            iter.map(|t| Vec3::new(t[0], t[1], t[2])).collect()
+                        }
+                        _ => Vec::new(),
+                    })
+                    .unwrap_or_default();
+                (AnimationPath::Translation, AnimationOutput::Vector3(values))
             }
+            gltf::animation::Property::Rotation => {
+                let values: Vec<Quat> = reader
+                    .read_outputs()
+                    .map(|out| match out {
+                        gltf::animation::util::ReadOutputs::Rotations(iter) => iter
+                            .into_f32()
+                            .map(|r| Quat::from_xyzw(r[0], r[1], r[2], r[3]))
+                            .collect(),
+                        _ => Vec::new(),
+                    })
+                    .unwrap_or_default();
+                (AnimationPath::Rotation, AnimationOutput::Rotation(values))
+            }
+            gltf::animation::Property::Scale => {
+                let values: Vec<Vec3> = reader
+                    .read_outputs()
+                    .map(|out| match out {
+                        gltf::animation::util::ReadOutputs::Scales(iter) => {
+                            iter.map(|s| Vec3::new(s[0], s[1], s[2])).collect()
+                        }
+                        _ => Vec::new(),
+                    })
+                    .unwrap_or_default();
+                (AnimationPath::Scale, AnimationOutput::Vector3(values))
+            }
+            gltf::animation::Property::MorphTargetWeights => {
+                let values: Vec<f32> = reader
+                    .read_outputs()
+                    .map(|out| match out {
+                        gltf::animation::util::ReadOutputs::MorphTargetWeights(iter) => {
+                            iter.into_f32().collect()
+                        }
+                        _ => Vec::new(),
+                    })
+                    .unwrap_or_default();
+                (AnimationPath::Weights, AnimationOutput::Scalar(values))
+            }
+        };
 
-            animation_clips.push(AnimationClip {
-                name: anim.name().unwrap_or("default").to_string(),
-                duration: max_time,
-                channels,
+        if !inputs.is_empty() {
+            channels.push(AnimationChannel {
+                target_node_index: node_index,
+                target_path,
+                inputs,
+                outputs,
             });
         }
+    }
 
-        Ok(GltfScene {
-            name: _path
-                .file_stem()
-                .map(|s| s.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unnamed".to_string()),
-            skeleton,
-            animation_clips,
-            node_names,
-        })
+    AnimationClip {
+        name: anim.name().unwrap_or("default").to_string(),
+        duration: max_time,
+        channels,
     }
 }
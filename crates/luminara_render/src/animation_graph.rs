@@ -0,0 +1,1158 @@
+//! Animation graphs: clips combined by blend spaces, crossfades, layers and a
+//! parameter-driven state machine.
+//!
+//! Sampling and blending are plain CPU code working on [`Pose`]s, so a graph
+//! can be evaluated without a world or GPU. [`animation_graph_system`] drives
+//! [`AnimationGraphPlayer`]s and [`apply_animation_pose_system`] writes the
+//! result to the joint entities listed in [`AnimationTargets`].
+
+use crate::animation::AnimationClip;
+use crate::animation_system::AnimationPlayer;
+use crate::components::MorphWeights;
+use luminara_asset::{Asset, AssetId, AssetLoadError, AssetLoader, AssetServer, Handle};
+use luminara_core::{Component, Entity, Query, Resource, World};
+use luminara_math::{Quat, Transform, Vec2, Vec3};
+use luminara_scene::Children;
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+// ── Poses ───────────────────────────────────────────────────────────────
+
+/// Local transform of one node; `None` channels are not animated
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BonePose {
+    pub translation: Option<Vec3>,
+    pub rotation: Option<Quat>,
+    pub scale: Option<Vec3>,
+}
+
+impl BonePose {
+    /// Overwrite the animated channels of `transform`
+    pub fn apply(&self, transform: &mut Transform) {
+        if let Some(translation) = self.translation {
+            transform.translation = translation;
+        }
+        if let Some(rotation) = self.rotation {
+            transform.rotation = rotation;
+        }
+        if let Some(scale) = self.scale {
+            transform.scale = scale;
+        }
+    }
+}
+
+/// Sampled node transforms and morph target weights, keyed by glTF node
+/// index
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pose {
+    pub bones: BTreeMap<usize, BonePose>,
+    pub weights: BTreeMap<usize, Vec<f32>>,
+}
+
+impl Pose {
+    pub fn get(&self, node: usize) -> Option<&BonePose> {
+        self.bones.get(&node)
+    }
+
+    /// Blend from `self` towards `other`. A channel animated by only one of
+    /// the poses keeps that pose's value.
+    pub fn blend(&self, other: &Pose, weight: f32) -> Pose {
+        self.blend_masked(other, weight, &BoneMask::default())
+    }
+
+    /// [`Pose::blend`] restricted to the nodes in `mask`
+    pub fn blend_masked(&self, other: &Pose, weight: f32, mask: &BoneMask) -> Pose {
+        let weight = weight.clamp(0.0, 1.0);
+        let mut result = self.clone();
+        for (&node, theirs) in &other.bones {
+            if !mask.contains(node) {
+                continue;
+            }
+            let bone = result.bones.entry(node).or_default();
+            bone.translation = mix(bone.translation, theirs.translation, |a, b| {
+                a.lerp(b, weight)
+            });
+            bone.rotation = mix(bone.rotation, theirs.rotation, |a, b| a.slerp(b, weight));
+            bone.scale = mix(bone.scale, theirs.scale, |a, b| a.lerp(b, weight));
+        }
+        for (&node, theirs) in &other.weights {
+            if !mask.contains(node) {
+                continue;
+            }
+            let ours = result.weights.entry(node).or_insert_with(|| theirs.clone());
+            for (a, b) in ours.iter_mut().zip(theirs) {
+                *a += (b - *a) * weight;
+            }
+        }
+        result
+    }
+
+    /// Normalized blend of any number of poses
+    pub fn weighted(poses: &[(Pose, f32)]) -> Pose {
+        let mut result = Pose::default();
+        let mut total = 0.0;
+        for (pose, weight) in poses {
+            if *weight <= 0.0 {
+                continue;
+            }
+            total += weight;
+            result = result.blend(pose, weight / total);
+        }
+        result
+    }
+
+    /// Offset of `self` from `reference`, for use as an additive pose
+    pub fn difference(&self, reference: &Pose) -> Pose {
+        let mut result = Pose::default();
+        for (&node, bone) in &self.bones {
+            let base = reference.bones.get(&node).copied().unwrap_or_default();
+            result.bones.insert(
+                node,
+                BonePose {
+                    translation: bone
+                        .translation
+                        .map(|t| t - base.translation.unwrap_or(Vec3::ZERO)),
+                    rotation: bone
+                        .rotation
+                        .map(|r| base.rotation.unwrap_or(Quat::IDENTITY).inverse() * r),
+                    scale: bone.scale.map(|s| s / base.scale.unwrap_or(Vec3::ONE)),
+                },
+            );
+        }
+        for (&node, weights) in &self.weights {
+            let base = reference.weights.get(&node);
+            let offsets = weights
+                .iter()
+                .enumerate()
+                .map(|(i, w)| w - base.and_then(|b| b.get(i)).copied().unwrap_or(0.0))
+                .collect();
+            result.weights.insert(node, offsets);
+        }
+        result
+    }
+
+    /// Layer an additive pose from [`Pose::difference`] on top of `self`.
+    /// Channels that `self` does not animate are offset from identity.
+    pub fn add(&self, additive: &Pose, weight: f32, mask: &BoneMask) -> Pose {
+        let mut result = self.clone();
+        for (&node, delta) in &additive.bones {
+            if !mask.contains(node) {
+                continue;
+            }
+            let bone = result.bones.entry(node).or_default();
+            if let Some(t) = delta.translation {
+                bone.translation = Some(bone.translation.unwrap_or(Vec3::ZERO) + t * weight);
+            }
+            if let Some(r) = delta.rotation {
+                let base = bone.rotation.unwrap_or(Quat::IDENTITY);
+                bone.rotation = Some(base * Quat::IDENTITY.slerp(r, weight));
+            }
+            if let Some(s) = delta.scale {
+                bone.scale = Some(bone.scale.unwrap_or(Vec3::ONE) * Vec3::ONE.lerp(s, weight));
+            }
+        }
+        for (&node, offsets) in &additive.weights {
+            if !mask.contains(node) {
+                continue;
+            }
+            let weights = result.weights.entry(node).or_default();
+            if weights.len() < offsets.len() {
+                weights.resize(offsets.len(), 0.0);
+            }
+            for (w, offset) in weights.iter_mut().zip(offsets) {
+                *w += offset * weight;
+            }
+        }
+        result
+    }
+}
+
+fn mix<T: Copy>(a: Option<T>, b: Option<T>, f: impl Fn(T, T) -> T) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(f(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Nodes affected by a layer. An empty mask affects every node.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct BoneMask(pub BTreeSet<usize>);
+
+impl BoneMask {
+    pub fn new(nodes: impl IntoIterator<Item = usize>) -> Self {
+        Self(nodes.into_iter().collect())
+    }
+
+    pub fn contains(&self, node: usize) -> bool {
+        self.0.is_empty() || self.0.contains(&node)
+    }
+}
+
+/// Movement of the root node, taken out of the pose so gameplay can apply it
+/// to the entity instead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RootMotion {
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+impl Default for RootMotion {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+        }
+    }
+}
+
+impl RootMotion {
+    /// This motion followed by `next`
+    pub fn then(&self, next: &RootMotion) -> RootMotion {
+        RootMotion {
+            translation: self.translation + next.translation,
+            rotation: self.rotation * next.rotation,
+        }
+    }
+
+    pub fn lerp(&self, other: &RootMotion, weight: f32) -> RootMotion {
+        RootMotion {
+            translation: self.translation.lerp(other.translation, weight),
+            rotation: self.rotation.slerp(other.rotation, weight),
+        }
+    }
+
+    /// Normalized blend of any number of motions
+    pub fn weighted(motions: &[(RootMotion, f32)]) -> RootMotion {
+        let mut result = RootMotion::default();
+        let mut total = 0.0;
+        for (motion, weight) in motions {
+            if *weight <= 0.0 {
+                continue;
+            }
+            total += weight;
+            result = result.lerp(motion, weight / total);
+        }
+        result
+    }
+}
+
+// ── Blend spaces ────────────────────────────────────────────────────────
+
+/// Weights of the points of a 1D blend space at `value`. The two points
+/// around `value` share the weight; outside the range the nearest end wins.
+pub fn blend_weights_1d(points: &[f32], value: f32) -> Vec<f32> {
+    let mut weights = vec![0.0; points.len()];
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&a, &b| points[a].total_cmp(&points[b]));
+
+    let (Some(&first), Some(&last)) = (order.first(), order.last()) else {
+        return weights;
+    };
+    if value <= points[first] {
+        weights[first] = 1.0;
+    } else if value >= points[last] {
+        weights[last] = 1.0;
+    } else {
+        for pair in order.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if value <= points[b] {
+                let span = points[b] - points[a];
+                let t = if span > f32::EPSILON {
+                    (value - points[a]) / span
+                } else {
+                    1.0
+                };
+                weights[a] = 1.0 - t;
+                weights[b] = t;
+                break;
+            }
+        }
+    }
+    weights
+}
+
+/// Weights of the points of a 2D blend space at `value`, using gradient band
+/// interpolation. Weights sum to one and a point gets full weight when
+/// `value` sits on it.
+pub fn blend_weights_2d(points: &[Vec2], value: Vec2) -> Vec<f32> {
+    let mut weights: Vec<f32> = points
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| {
+            points
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, &q)| {
+                    let edge = q - p;
+                    let length_squared = edge.length_squared();
+                    if length_squared <= f32::EPSILON {
+                        return 1.0;
+                    }
+                    (1.0 - (value - p).dot(edge) / length_squared).clamp(0.0, 1.0)
+                })
+                .fold(1.0, f32::min)
+        })
+        .collect();
+
+    let total: f32 = weights.iter().sum();
+    if total > f32::EPSILON {
+        for weight in &mut weights {
+            *weight /= total;
+        }
+    }
+    weights
+}
+
+// ── Graph asset ─────────────────────────────────────────────────────────
+
+/// Clips are referenced by asset path in graph files, e.g.
+/// `"models/robot.glb#Animation0"`
+fn clip_from_path<'de, D>(deserializer: D) -> Result<Handle<AnimationClip>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let path = String::deserialize(deserializer)?;
+    Ok(Handle::new(AssetId::from_path(&path), 0))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlendPoint1D {
+    pub position: f32,
+    #[serde(deserialize_with = "clip_from_path")]
+    pub clip: Handle<AnimationClip>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlendPoint2D {
+    pub position: Vec2,
+    #[serde(deserialize_with = "clip_from_path")]
+    pub clip: Handle<AnimationClip>,
+}
+
+/// What a state plays
+#[derive(Debug, Clone, Deserialize)]
+pub enum Motion {
+    Clip(#[serde(deserialize_with = "clip_from_path")] Handle<AnimationClip>),
+    /// Clips placed along one float parameter
+    BlendSpace1D {
+        parameter: String,
+        points: Vec<BlendPoint1D>,
+    },
+    /// Clips placed on the plane of two float parameters
+    BlendSpace2D {
+        x: String,
+        y: String,
+        points: Vec<BlendPoint2D>,
+    },
+}
+
+impl Motion {
+    /// Clips of this motion with their current blend weights
+    pub fn weights(&self, parameters: &AnimationParameters) -> Vec<(Handle<AnimationClip>, f32)> {
+        match self {
+            Motion::Clip(clip) => vec![(clip.clone(), 1.0)],
+            Motion::BlendSpace1D { parameter, points } => {
+                let positions: Vec<f32> = points.iter().map(|p| p.position).collect();
+                let weights = blend_weights_1d(&positions, parameters.float(parameter));
+                points.iter().map(|p| p.clip.clone()).zip(weights).collect()
+            }
+            Motion::BlendSpace2D { x, y, points } => {
+                let positions: Vec<Vec2> = points.iter().map(|p| p.position).collect();
+                let value = Vec2::new(parameters.float(x), parameters.float(y));
+                let weights = blend_weights_2d(&positions, value);
+                points.iter().map(|p| p.clip.clone()).zip(weights).collect()
+            }
+        }
+    }
+}
+
+/// Named event fired when playback of a state crosses `time`, given as a
+/// fraction of the state's duration
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimationEventKey {
+    pub time: f32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimationState {
+    pub name: String,
+    pub motion: Motion,
+    #[serde(default = "one")]
+    pub speed: f32,
+    #[serde(default = "yes")]
+    pub looping: bool,
+    #[serde(default)]
+    pub events: Vec<AnimationEventKey>,
+}
+
+impl AnimationState {
+    pub fn new(name: impl Into<String>, motion: Motion) -> Self {
+        Self {
+            name: name.into(),
+            motion,
+            speed: 1.0,
+            looping: true,
+            events: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum TransitionCondition {
+    Greater(String, f32),
+    Less(String, f32),
+    IsTrue(String),
+    IsFalse(String),
+    /// Consumed when the transition is taken
+    Trigger(String),
+}
+
+impl TransitionCondition {
+    fn holds(&self, parameters: &AnimationParameters) -> bool {
+        match self {
+            TransitionCondition::Greater(name, value) => parameters.float(name) > *value,
+            TransitionCondition::Less(name, value) => parameters.float(name) < *value,
+            TransitionCondition::IsTrue(name) => parameters.bool(name),
+            TransitionCondition::IsFalse(name) => !parameters.bool(name),
+            TransitionCondition::Trigger(name) => parameters.triggers.contains(name),
+        }
+    }
+}
+
+/// Edge of a layer's state machine, taken once every condition holds
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimationTransition {
+    /// Source state; `None` allows the transition from any state
+    #[serde(default)]
+    pub from: Option<String>,
+    pub to: String,
+    #[serde(default)]
+    pub conditions: Vec<TransitionCondition>,
+    /// Crossfade time in seconds
+    #[serde(default)]
+    pub duration: f32,
+    /// Only leave once playback reaches this fraction of the source state
+    #[serde(default)]
+    pub exit_time: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum LayerBlend {
+    /// Replace the layers below
+    #[default]
+    Override,
+    /// Add the offset of each clip from its first frame to the layers below
+    Additive,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimationLayer {
+    pub name: String,
+    pub states: Vec<AnimationState>,
+    #[serde(default)]
+    pub transitions: Vec<AnimationTransition>,
+    /// Defaults to the first state
+    #[serde(default)]
+    pub initial_state: Option<String>,
+    #[serde(default)]
+    pub blend: LayerBlend,
+    /// Ignored on the first layer
+    #[serde(default = "one")]
+    pub weight: f32,
+    #[serde(default)]
+    pub mask: BoneMask,
+}
+
+impl AnimationLayer {
+    pub fn new(name: impl Into<String>, states: Vec<AnimationState>) -> Self {
+        Self {
+            name: name.into(),
+            states,
+            transitions: Vec::new(),
+            initial_state: None,
+            blend: LayerBlend::Override,
+            weight: 1.0,
+            mask: BoneMask::default(),
+        }
+    }
+
+    pub fn state_index(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|s| s.name == name)
+    }
+
+    fn initial_index(&self) -> usize {
+        self.initial_state
+            .as_deref()
+            .and_then(|name| self.state_index(name))
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum AnimationParameter {
+    Float(f32),
+    Bool(bool),
+    Trigger,
+}
+
+/// Layered state machines over animation clips
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AnimationGraph {
+    pub layers: Vec<AnimationLayer>,
+    /// Parameters with their default values
+    #[serde(default)]
+    pub parameters: HashMap<String, AnimationParameter>,
+    /// Node whose motion is extracted into [`AnimationGraphPlayer::root_motion`]
+    /// instead of being posed
+    #[serde(default)]
+    pub root_motion_node: Option<usize>,
+}
+
+impl Asset for AnimationGraph {
+    fn type_name() -> &'static str {
+        "AnimationGraph"
+    }
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn yes() -> bool {
+    true
+}
+
+/// Loads `.animgraph.ron` files
+pub struct AnimationGraphLoader;
+
+impl AssetLoader for AnimationGraphLoader {
+    type Asset = AnimationGraph;
+
+    fn extensions(&self) -> &[&str] {
+        &["animgraph.ron"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let source = std::str::from_utf8(bytes)
+            .map_err(|e| AssetLoadError::Parse(format!("Animation graph is not UTF-8: {}", e)))?;
+        let graph: AnimationGraph =
+            ron::from_str(source).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+
+        for layer in &graph.layers {
+            let names = layer
+                .transitions
+                .iter()
+                .flat_map(|t| t.from.iter().chain(std::iter::once(&t.to)))
+                .chain(layer.initial_state.iter());
+            for name in names {
+                if layer.state_index(name).is_none() {
+                    return Err(AssetLoadError::Parse(format!(
+                        "Layer '{}' references unknown state '{}'",
+                        layer.name, name
+                    )));
+                }
+            }
+        }
+        Ok(graph)
+    }
+}
+
+// ── Playback ────────────────────────────────────────────────────────────
+
+/// Current parameter values of a player
+#[derive(Debug, Clone, Default)]
+pub struct AnimationParameters {
+    pub floats: HashMap<String, f32>,
+    pub bools: HashMap<String, bool>,
+    pub triggers: HashSet<String>,
+}
+
+impl AnimationParameters {
+    pub fn float(&self, name: &str) -> f32 {
+        self.floats.get(name).copied().unwrap_or(0.0)
+    }
+
+    pub fn bool(&self, name: &str) -> bool {
+        self.bools.get(name).copied().unwrap_or(false)
+    }
+}
+
+/// Event reached by an [`AnimationGraphPlayer`] during an update
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationEvent {
+    pub layer: usize,
+    pub state: String,
+    pub name: String,
+}
+
+/// Events fired this frame, with the entity whose player fired them
+#[derive(Debug, Default)]
+pub struct AnimationEvents(pub Vec<(Entity, AnimationEvent)>);
+
+impl Resource for AnimationEvents {}
+
+#[derive(Debug, Clone)]
+struct StatePlayback {
+    state: usize,
+    /// Fraction of the state's duration played
+    phase: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Crossfade {
+    from: StatePlayback,
+    elapsed: f32,
+    duration: f32,
+}
+
+#[derive(Debug, Clone)]
+struct LayerPlayback {
+    current: StatePlayback,
+    fade: Option<Crossfade>,
+}
+
+/// Pose and root motion of one state for one update
+struct StateOutput {
+    pose: Pose,
+    root_motion: RootMotion,
+}
+
+type ClipLookup<'a> = dyn Fn(&Handle<AnimationClip>) -> Option<Arc<AnimationClip>> + 'a;
+
+/// Plays an [`AnimationGraph`]. Set parameters or request crossfades, and
+/// the animation systems evaluate the graph into [`Self::pose`].
+pub struct AnimationGraphPlayer {
+    pub graph: Handle<AnimationGraph>,
+    pub speed: f32,
+    pub playing: bool,
+    pub parameters: AnimationParameters,
+    /// Weights overriding [`AnimationLayer::weight`], by layer index
+    pub layer_weights: HashMap<usize, f32>,
+    /// Pose evaluated by the last update
+    pub pose: Pose,
+    /// Root motion accumulated by the last update
+    pub root_motion: RootMotion,
+    layers: Vec<LayerPlayback>,
+    requests: Vec<(usize, String, f32)>,
+    /// Graph the layer playback was built for
+    bound: Option<Handle<AnimationGraph>>,
+}
+
+impl Component for AnimationGraphPlayer {
+    fn type_name() -> &'static str {
+        "AnimationGraphPlayer"
+    }
+}
+
+impl AnimationGraphPlayer {
+    pub fn new(graph: Handle<AnimationGraph>) -> Self {
+        Self {
+            graph,
+            speed: 1.0,
+            playing: true,
+            parameters: AnimationParameters::default(),
+            layer_weights: HashMap::new(),
+            pose: Pose::default(),
+            root_motion: RootMotion::default(),
+            layers: Vec::new(),
+            requests: Vec::new(),
+            bound: None,
+        }
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32) {
+        self.parameters.floats.insert(name.to_string(), value);
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        self.parameters.bools.insert(name.to_string(), value);
+    }
+
+    /// Set a trigger; it stays set until a transition consumes it
+    pub fn set_trigger(&mut self, name: &str) {
+        self.parameters.triggers.insert(name.to_string());
+    }
+
+    /// Crossfade `layer` to `state` over `duration` seconds on the next
+    /// update, bypassing the state machine
+    pub fn crossfade_to(&mut self, layer: usize, state: &str, duration: f32) {
+        self.requests.push((layer, state.to_string(), duration));
+    }
+
+    pub fn set_layer_weight(&mut self, layer: usize, weight: f32) {
+        self.layer_weights.insert(layer, weight);
+    }
+
+    /// Name of the state `layer` is playing or fading into
+    pub fn current_state<'g>(&self, graph: &'g AnimationGraph, layer: usize) -> Option<&'g str> {
+        let playback = self.layers.get(layer)?;
+        let state = graph
+            .layers
+            .get(layer)?
+            .states
+            .get(playback.current.state)?;
+        Some(&state.name)
+    }
+
+    /// Whether `layer` is crossfading between states
+    pub fn is_fading(&self, layer: usize) -> bool {
+        self.layers
+            .get(layer)
+            .is_some_and(|playback| playback.fade.is_some())
+    }
+
+    /// Advance playback by `dt` seconds and evaluate the graph into
+    /// [`Self::pose`] and [`Self::root_motion`]. Returns the events crossed.
+    pub fn update(
+        &mut self,
+        graph: &AnimationGraph,
+        clips: &ClipLookup,
+        dt: f32,
+    ) -> Vec<AnimationEvent> {
+        if self.bound.as_ref() != Some(&self.graph) || self.layers.len() != graph.layers.len() {
+            self.initialize(graph);
+        } else {
+            self.clamp_states(graph);
+        }
+        let dt = if self.playing { dt * self.speed } else { 0.0 };
+
+        for (layer, state, duration) in std::mem::take(&mut self.requests) {
+            if let Some(index) = graph.layers.get(layer).and_then(|l| l.state_index(&state)) {
+                self.start_transition(layer, index, duration);
+            }
+        }
+
+        let mut events = Vec::new();
+        let mut pose = Pose::default();
+        let mut root_motion = RootMotion::default();
+
+        for (index, layer) in graph.layers.iter().enumerate() {
+            if layer.states.is_empty() {
+                continue;
+            }
+            let additive = layer.blend == LayerBlend::Additive;
+            let root = if index == 0 {
+                graph.root_motion_node
+            } else {
+                None
+            };
+
+            let playback = &mut self.layers[index];
+            let wrapped = advance(
+                &mut playback.current,
+                layer,
+                &self.parameters,
+                clips,
+                dt,
+                index,
+                &mut events,
+            );
+            let mut output = evaluate(
+                &playback.current,
+                layer,
+                &self.parameters,
+                clips,
+                dt,
+                wrapped,
+                root,
+                additive,
+            );
+
+            if let Some(fade) = &mut playback.fade {
+                let from_wrapped = advance(
+                    &mut fade.from,
+                    layer,
+                    &self.parameters,
+                    clips,
+                    dt,
+                    index,
+                    &mut Vec::new(),
+                );
+                let from = evaluate(
+                    &fade.from,
+                    layer,
+                    &self.parameters,
+                    clips,
+                    dt,
+                    from_wrapped,
+                    root,
+                    additive,
+                );
+                fade.elapsed += dt;
+                let weight = if fade.duration > 0.0 {
+                    (fade.elapsed / fade.duration).min(1.0)
+                } else {
+                    1.0
+                };
+                output = StateOutput {
+                    pose: from.pose.blend(&output.pose, weight),
+                    root_motion: from.root_motion.lerp(&output.root_motion, weight),
+                };
+                if weight >= 1.0 {
+                    playback.fade = None;
+                }
+            }
+
+            if index == 0 {
+                pose = output.pose;
+                root_motion = output.root_motion;
+            } else {
+                let weight = self
+                    .layer_weights
+                    .get(&index)
+                    .copied()
+                    .unwrap_or(layer.weight)
+                    .clamp(0.0, 1.0);
+                pose = match layer.blend {
+                    LayerBlend::Override => pose.blend_masked(&output.pose, weight, &layer.mask),
+                    LayerBlend::Additive => pose.add(&output.pose, weight, &layer.mask),
+                };
+            }
+
+            self.take_transition(graph, index, wrapped);
+        }
+
+        self.pose = pose;
+        self.root_motion = root_motion;
+        events
+    }
+
+    fn initialize(&mut self, graph: &AnimationGraph) {
+        for (name, parameter) in &graph.parameters {
+            match *parameter {
+                AnimationParameter::Float(value) => {
+                    self.parameters.floats.entry(name.clone()).or_insert(value);
+                }
+                AnimationParameter::Bool(value) => {
+                    self.parameters.bools.entry(name.clone()).or_insert(value);
+                }
+                AnimationParameter::Trigger => {}
+            }
+        }
+        self.layers = graph
+            .layers
+            .iter()
+            .map(|layer| LayerPlayback {
+                current: StatePlayback {
+                    state: layer.initial_index(),
+                    phase: 0.0,
+                },
+                fade: None,
+            })
+            .collect();
+        self.bound = Some(self.graph.clone());
+    }
+
+    /// Restart layers whose state no longer exists, e.g. after the graph was
+    /// hot-reloaded with fewer states
+    fn clamp_states(&mut self, graph: &AnimationGraph) {
+        for (playback, layer) in self.layers.iter_mut().zip(&graph.layers) {
+            let count = layer.states.len();
+            if playback.current.state >= count {
+                playback.current = StatePlayback {
+                    state: layer.initial_index(),
+                    phase: 0.0,
+                };
+            }
+            if playback
+                .fade
+                .as_ref()
+                .is_some_and(|fade| fade.from.state >= count)
+            {
+                playback.fade = None;
+            }
+        }
+    }
+
+    fn start_transition(&mut self, layer: usize, state: usize, duration: f32) {
+        let Some(playback) = self.layers.get_mut(layer) else {
+            return;
+        };
+        let from = std::mem::replace(&mut playback.current, StatePlayback { state, phase: 0.0 });
+        playback.fade = (duration > 0.0).then_some(Crossfade {
+            from,
+            elapsed: 0.0,
+            duration,
+        });
+    }
+
+    /// Take the first transition out of the current state whose conditions
+    /// hold, consuming the triggers it used
+    fn take_transition(&mut self, graph: &AnimationGraph, layer: usize, wrapped: bool) {
+        let definition = &graph.layers[layer];
+        let current = &self.layers[layer].current;
+        let current_name = &definition.states[current.state].name;
+
+        let transition = definition.transitions.iter().find(|t| {
+            let from_matches = match &t.from {
+                Some(from) => from == current_name,
+                None => &t.to != current_name,
+            };
+            let exit_reached = t
+                .exit_time
+                .is_none_or(|exit| wrapped || current.phase >= exit);
+            from_matches && exit_reached && t.conditions.iter().all(|c| c.holds(&self.parameters))
+        });
+        let Some(transition) = transition else {
+            return;
+        };
+        let Some(target) = definition.state_index(&transition.to) else {
+            return;
+        };
+
+        for condition in &transition.conditions {
+            if let TransitionCondition::Trigger(name) = condition {
+                self.parameters.triggers.remove(name);
+            }
+        }
+        self.start_transition(layer, target, transition.duration);
+    }
+}
+
+/// Weight-averaged clip duration of a state, so the clips of a blend space
+/// stay in step
+fn state_duration(weights: &[(Arc<AnimationClip>, f32)]) -> f32 {
+    let total: f32 = weights.iter().map(|(_, w)| w).sum();
+    if total <= f32::EPSILON {
+        return 0.0;
+    }
+    weights.iter().map(|(c, w)| c.duration * w).sum::<f32>() / total
+}
+
+fn resolve(
+    state: &AnimationState,
+    parameters: &AnimationParameters,
+    clips: &ClipLookup,
+) -> Vec<(Arc<AnimationClip>, f32)> {
+    state
+        .motion
+        .weights(parameters)
+        .into_iter()
+        .filter(|(_, w)| *w > 0.0)
+        .filter_map(|(handle, w)| clips(&handle).map(|clip| (clip, w)))
+        .collect()
+}
+
+/// Move a state's phase forward, collecting the events crossed. Returns
+/// whether playback looped.
+fn advance(
+    playback: &mut StatePlayback,
+    layer: &AnimationLayer,
+    parameters: &AnimationParameters,
+    clips: &ClipLookup,
+    dt: f32,
+    layer_index: usize,
+    events: &mut Vec<AnimationEvent>,
+) -> bool {
+    let state = &layer.states[playback.state];
+    let duration = state_duration(&resolve(state, parameters, clips));
+    if duration <= 0.0 || dt <= 0.0 {
+        return false;
+    }
+
+    let start = playback.phase;
+    let mut end = start + dt * state.speed / duration;
+    let wrapped = state.looping && end >= 1.0;
+    if state.looping {
+        end = end.fract();
+    } else {
+        end = end.min(1.0);
+    }
+
+    let crossed = |time: f32| {
+        if wrapped {
+            time > start || time <= end
+        } else {
+            time > start && time <= end
+        }
+    };
+    for key in &state.events {
+        if crossed(key.time) {
+            events.push(AnimationEvent {
+                layer: layer_index,
+                state: state.name.clone(),
+                name: key.name.clone(),
+            });
+        }
+    }
+
+    playback.phase = end;
+    wrapped
+}
+
+/// Sample a state at its current phase
+#[allow(clippy::too_many_arguments)]
+fn evaluate(
+    playback: &StatePlayback,
+    layer: &AnimationLayer,
+    parameters: &AnimationParameters,
+    clips: &ClipLookup,
+    dt: f32,
+    wrapped: bool,
+    root: Option<usize>,
+    additive: bool,
+) -> StateOutput {
+    let state = &layer.states[playback.state];
+    let weights = resolve(state, parameters, clips);
+    let duration = state_duration(&weights);
+
+    let mut poses = Vec::with_capacity(weights.len());
+    let mut motions = Vec::with_capacity(weights.len());
+    for (clip, weight) in &weights {
+        let time = playback.phase * clip.duration;
+        let mut pose = clip.sample(time);
+
+        if let Some(node) = root {
+            let reference = clip.sample(0.0).bones.remove(&node).unwrap_or_default();
+            if let Some(bone) = pose.bones.get_mut(&node) {
+                // Keep the root at its first-frame placement; the movement
+                // goes to the root motion instead
+                bone.translation = bone.translation.and(reference.translation);
+                bone.rotation = bone.rotation.and(reference.rotation);
+            }
+            if duration > 0.0 && dt > 0.0 {
+                let step = dt * state.speed / duration * clip.duration;
+                let previous = if wrapped {
+                    time - step + clip.duration
+                } else {
+                    (time - step).max(0.0)
+                };
+                motions.push((clip.root_motion(node, previous, time, wrapped), *weight));
+            }
+        }
+
+        if additive {
+            pose = pose.difference(&clip.sample(0.0));
+        }
+        poses.push((pose, *weight));
+    }
+
+    StateOutput {
+        pose: Pose::weighted(&poses),
+        root_motion: RootMotion::weighted(&motions),
+    }
+}
+
+// ── Systems ─────────────────────────────────────────────────────────────
+
+/// Entities posed by an animation player, keyed by glTF node index
+#[derive(Debug, Clone, Default)]
+pub struct AnimationTargets {
+    pub nodes: HashMap<usize, Entity>,
+}
+
+impl Component for AnimationTargets {
+    fn type_name() -> &'static str {
+        "AnimationTargets"
+    }
+}
+
+impl AnimationTargets {
+    /// Targets from the node ids returned by [`crate::spawn_gltf_scene`]
+    pub fn from_scene_ids(ids: &HashMap<u64, Entity>) -> Self {
+        Self {
+            nodes: ids
+                .iter()
+                .map(|(&node, &entity)| (node as usize, entity))
+                .collect(),
+        }
+    }
+}
+
+/// Advance every [`AnimationGraphPlayer`], collect its events and move the
+/// entity by the extracted root motion
+pub fn animation_graph_system(world: &mut World) {
+    let dt = world
+        .get_resource::<luminara_core::Time>()
+        .map(|t| t.delta_seconds())
+        .unwrap_or(0.0);
+
+    let entities: Vec<Entity> = Query::<(Entity, &AnimationGraphPlayer)>::new(world)
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+
+    let mut fired = Vec::new();
+    {
+        let Some(assets) = world.get_resource::<AssetServer>() else {
+            return;
+        };
+        let clips = |handle: &Handle<AnimationClip>| assets.get(handle);
+
+        for &entity in &entities {
+            let Some(player) = world.get_component_mut::<AnimationGraphPlayer>(entity) else {
+                continue;
+            };
+            let Some(graph) = assets.get(&player.graph) else {
+                continue;
+            };
+            let events = player.update(&graph, &clips, dt);
+            fired.extend(events.into_iter().map(|event| (entity, event)));
+
+            let motion = player.root_motion;
+            if graph.root_motion_node.is_some() {
+                if let Some(transform) = world.get_component_mut::<Transform>(entity) {
+                    transform.translation += transform.rotation * motion.translation;
+                    transform.rotation = (transform.rotation * motion.rotation).normalize();
+                }
+            }
+        }
+    }
+
+    if let Some(mut events) = world.get_resource_mut::<AnimationEvents>() {
+        events.0 = fired;
+    }
+}
+
+/// Write the poses of [`AnimationGraphPlayer`]s and [`AnimationPlayer`]s to
+/// the `Transform`s and [`MorphWeights`] of their [`AnimationTargets`]
+pub fn apply_animation_pose_system(world: &mut World) {
+    let entities: Vec<Entity> = Query::<(Entity, &AnimationTargets)>::new(world)
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in entities {
+        let pose = if let Some(player) = world.get_component::<AnimationGraphPlayer>(entity) {
+            player.pose.clone()
+        } else if let Some(player) = world.get_component::<AnimationPlayer>(entity) {
+            player.pose()
+        } else {
+            continue;
+        };
+        let Some(targets) = world.get_component::<AnimationTargets>(entity) else {
+            continue;
+        };
+
+        for (node, bone) in &pose.bones {
+            let Some(&target) = targets.nodes.get(node) else {
+                continue;
+            };
+            if let Some(transform) = world.get_component_mut::<Transform>(target) {
+                bone.apply(transform);
+            }
+        }
+
+        for (node, weights) in &pose.weights {
+            let Some(&target) = targets.nodes.get(node) else {
+                continue;
+            };
+            // Meshes with several primitives keep their weights on the
+            // primitive children
+            let children = world
+                .get_component::<Children>(target)
+                .map(|c| c.0.clone())
+                .unwrap_or_default();
+            for mesh in std::iter::once(target).chain(children) {
+                if let Some(morph) = world.get_component_mut::<MorphWeights>(mesh) {
+                    morph.weights.clone_from(weights);
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,13 @@
-use crate::animation::{AnimationClip, AnimationOutput, AnimationPath};
+use crate::animation::AnimationClip;
+use crate::animation_graph::{
+    animation_graph_system, apply_animation_pose_system, AnimationEvents, AnimationGraphLoader,
+    BonePose, Pose,
+};
 use luminara_asset::{AssetServer, Handle};
-use luminara_core::system::FunctionMarker;
-use luminara_core::{App, AppInterface, Component, CoreStage, Plugin, Query, Res};
+use luminara_core::system::{ExclusiveMarker, FunctionMarker};
+use luminara_core::{App, AppInterface, Component, CoreStage, Plugin, Query, Res, World};
 use luminara_math::{Quat, Vec3};
+use std::collections::BTreeMap;
 
 pub struct AnimationPlayer {
     pub current_clip: Option<Handle<AnimationClip>>,
@@ -12,6 +17,8 @@ pub struct AnimationPlayer {
     pub playing: bool,
     /// Sampled bone transforms updated each frame by the animation system.
     pub sampled_transforms: Vec<SampledBoneTransform>,
+    /// Morph target weights sampled alongside, by node index
+    pub sampled_weights: BTreeMap<usize, Vec<f32>>,
 }
 
 /// Sampled bone transform from animation playback.
@@ -38,6 +45,7 @@ impl Default for AnimationPlayer {
             looping: true,
             playing: true,
             sampled_transforms: Vec::new(),
+            sampled_weights: BTreeMap::new(),
         }
     }
 }
@@ -64,6 +72,25 @@ impl AnimationPlayer {
     pub fn is_finished(&self) -> bool {
         !self.playing && !self.looping
     }
+
+    /// The last sampled transforms as a [`Pose`]
+    pub fn pose(&self) -> Pose {
+        Pose {
+            bones: self
+                .sampled_transforms
+                .iter()
+                .map(|s| {
+                    let bone = BonePose {
+                        translation: s.translation,
+                        rotation: s.rotation,
+                        scale: s.scale,
+                    };
+                    (s.node_index, bone)
+                })
+                .collect(),
+            weights: self.sampled_weights.clone(),
+        }
+    }
 }
 
 pub struct AnimationPlugin;
@@ -80,6 +107,19 @@ impl Plugin for AnimationPlugin {
             Res<'static, AssetServer>,
             Res<'static, luminara_core::Time>,
         )>(CoreStage::Update, animation_system);
+
+        if app.world.get_resource::<AnimationEvents>().is_none() {
+            app.insert_resource(AnimationEvents::default());
+        }
+        app.add_system::<ExclusiveMarker>(CoreStage::Startup, register_animation_graph_loader);
+        app.add_system::<ExclusiveMarker>(CoreStage::Update, animation_graph_system);
+        app.add_system::<ExclusiveMarker>(CoreStage::Update, apply_animation_pose_system);
+    }
+}
+
+fn register_animation_graph_loader(world: &mut World) {
+    if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
+        asset_server.register_loader(AnimationGraphLoader);
     }
 }
 
@@ -110,80 +150,18 @@ pub fn animation_system(
             }
 
             // Sample all channels and store results
-            player.sampled_transforms.clear();
-
-            for channel in &clip.channels {
-                let inputs = &channel.inputs;
-                if inputs.is_empty() {
-                    continue;
-                }
-
-                // Binary search for the keyframe bracket
-                let mut lo = 0usize;
-                let mut hi = inputs.len().saturating_sub(1);
-                while lo < hi {
-                    let mid = (lo + hi) / 2;
-                    if inputs[mid] < player.time {
-                        lo = mid + 1;
-                    } else {
-                        hi = mid;
-                    }
-                }
-                // lo is now the index of the first keyframe >= player.time
-                let k = if lo > 0 { lo - 1 } else { 0 };
-
-                let t = if k + 1 < inputs.len() {
-                    let duration = inputs[k + 1] - inputs[k];
-                    if duration > 0.0001 {
-                        ((player.time - inputs[k]) / duration).clamp(0.0, 1.0)
-                    } else {
-                        0.0
-                    }
-                } else {
-                    0.0
-                };
-
-                let mut sampled = SampledBoneTransform {
-                    node_index: channel.target_node_index,
-                    ..Default::default()
-                };
-
-                match (&channel.target_path, &channel.outputs) {
-                    (AnimationPath::Translation, AnimationOutput::Vector3(values)) => {
-                        if k < values.len() {
-                            let val = if k + 1 < values.len() {
-                                values[k].lerp(values[k + 1], t)
-                            } else {
-                                values[k]
-                            };
-                            sampled.translation = Some(val);
-                        }
-                    }
-                    (AnimationPath::Rotation, AnimationOutput::Rotation(values)) => {
-                        if k < values.len() {
-                            let val = if k + 1 < values.len() {
-                                values[k].slerp(values[k + 1], t)
-                            } else {
-                                values[k]
-                            };
-                            sampled.rotation = Some(val);
-                        }
-                    }
-                    (AnimationPath::Scale, AnimationOutput::Vector3(values)) => {
-                        if k < values.len() {
-                            let val = if k + 1 < values.len() {
-                                values[k].lerp(values[k + 1], t)
-                            } else {
-                                values[k]
-                            };
-                            sampled.scale = Some(val);
-                        }
-                    }
-                    _ => {}
-                }
-
-                player.sampled_transforms.push(sampled);
-            }
+            let pose = clip.sample(player.time);
+            player.sampled_weights = pose.weights;
+            player.sampled_transforms = pose
+                .bones
+                .into_iter()
+                .map(|(node_index, bone)| SampledBoneTransform {
+                    node_index,
+                    translation: bone.translation,
+                    rotation: bone.rotation,
+                    scale: bone.scale,
+                })
+                .collect();
         }
     }
 }
@@ -1,3 +1,5 @@
+use crate::Texture;
+use luminara_asset::Handle;
 use luminara_core::shared_types::Component;
 use luminara_math::{Color, Mat4};
 use luminara_reflect_derive::Reflect;
@@ -8,6 +10,132 @@ pub struct Camera {
     pub projection: Projection,
     pub clear_color: Color,
     pub is_active: bool,
+    /// Cameras render in ascending order; later cameras draw over earlier
+    /// ones sharing the same target
+    #[serde(default)]
+    pub order: i32,
+    #[serde(default)]
+    pub target: RenderTarget,
+    /// Region of the target this camera draws into
+    #[serde(default)]
+    pub viewport: Viewport,
+}
+
+/// Where a camera's image ends up
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Reflect)]
+pub enum RenderTarget {
+    /// The primary window surface
+    #[default]
+    Window,
+    /// An offscreen texture asset, sized from its [`crate::TextureData`].
+    /// The GPU copy lives in [`crate::RenderTargets`].
+    Texture(Handle<Texture>),
+    /// An offscreen image copied back to the CPU every frame, available
+    /// through [`crate::RenderTargets::readback`]
+    Image { width: u32, height: u32 },
+}
+
+/// Normalized rectangle of a render target, origin at the top-left
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub const FULL: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Pixel rectangle `(x, y, width, height)` inside a target of the given
+    /// size. The result is clamped to the target and never empty.
+    pub fn to_pixels(&self, target_size: (u32, u32)) -> (u32, u32, u32, u32) {
+        let (target_width, target_height) = (target_size.0.max(1), target_size.1.max(1));
+        let to_px = |value: f32, extent: u32| {
+            ((value.clamp(0.0, 1.0) * extent as f32).round() as u32).min(extent - 1)
+        };
+        let x = to_px(self.x, target_width);
+        let y = to_px(self.y, target_height);
+        let right = ((self.x + self.width).clamp(0.0, 1.0) * target_width as f32).round() as u32;
+        let bottom = ((self.y + self.height).clamp(0.0, 1.0) * target_height as f32).round() as u32;
+        (
+            x,
+            y,
+            right.saturating_sub(x).max(1),
+            bottom.saturating_sub(y).max(1),
+        )
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+/// Bitmask of the layers an entity belongs to. A camera only draws renderables
+/// sharing at least one layer with it; entities without the component are on
+/// layer 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+pub struct RenderLayers(pub u32);
+
+impl RenderLayers {
+    pub const ALL: Self = Self(u32::MAX);
+    pub const NONE: Self = Self(0);
+    /// Number of layers; layer indices past it panic
+    pub const COUNT: u8 = 32;
+
+    /// Only the given layer (0..32)
+    pub const fn layer(layer: u8) -> Self {
+        Self(Self::bit(layer))
+    }
+
+    pub const fn with(self, layer: u8) -> Self {
+        Self(self.0 | Self::bit(layer))
+    }
+
+    pub const fn without(self, layer: u8) -> Self {
+        Self(self.0 & !Self::bit(layer))
+    }
+
+    pub const fn contains(&self, layer: u8) -> bool {
+        self.0 & Self::bit(layer) != 0
+    }
+
+    pub const fn intersects(&self, other: &Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    const fn bit(layer: u8) -> u32 {
+        assert!(layer < Self::COUNT, "render layer out of range (0..32)");
+        1 << layer
+    }
+}
+
+impl Default for RenderLayers {
+    fn default() -> Self {
+        Self::layer(0)
+    }
+}
+
+impl Component for RenderLayers {
+    fn type_name() -> &'static str {
+        "RenderLayers"
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
@@ -86,6 +214,9 @@ impl Default for Camera {
             },
             clear_color: Color::BLACK,
             is_active: true,
+            order: 0,
+            target: RenderTarget::Window,
+            viewport: Viewport::FULL,
         }
     }
 }
@@ -134,6 +265,7 @@ mod tests {
             },
             clear_color: Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         let mat = camera.projection_matrix(16.0 / 9.0);
@@ -150,6 +282,7 @@ mod tests {
             },
             clear_color: Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         let mat = camera.projection_matrix(16.0 / 9.0);
@@ -0,0 +1,425 @@
+//! Clustered light assignment for the Forward+ path.
+//!
+//! The view frustum is divided into screen tiles in X/Y and exponentially
+//! spaced depth slices in Z. Every point and spot light is binned into the
+//! clusters its bounding sphere or cone touches, so shading only walks the
+//! lights of the fragment's cluster. Binning runs on the CPU; the packed
+//! [`ClusterRange`] and index lists are laid out for the GPU as-is.
+
+use crate::{PointLight, SpotLight};
+use luminara_core::shared_types::Resource;
+use luminara_math::{Color, Mat4, Transform, Vec2, Vec3, Vec4};
+
+/// Cluster grid dimensions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterConfig {
+    pub tiles_x: u32,
+    pub tiles_y: u32,
+    /// Depth slices between the near and far planes
+    pub slices: u32,
+    /// Lights past this count are dropped from a cluster
+    pub max_lights_per_cluster: u32,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            tiles_x: 16,
+            tiles_y: 9,
+            slices: 24,
+            max_lights_per_cluster: 128,
+        }
+    }
+}
+
+impl ClusterConfig {
+    pub fn cluster_count(&self) -> usize {
+        (self.tiles_x * self.tiles_y * self.slices) as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClusterLightShape {
+    Point,
+    /// Cone along `direction`; angles in radians from the axis
+    Spot {
+        direction: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+/// A point or spot light as seen by the clustering
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterLight {
+    pub position: Vec3,
+    pub range: f32,
+    pub color: Color,
+    pub intensity: f32,
+    pub shape: ClusterLightShape,
+}
+
+impl ClusterLight {
+    pub fn from_point(light: &PointLight, transform: &Transform) -> Self {
+        Self {
+            position: transform.translation,
+            range: light.range,
+            color: light.color,
+            intensity: light.intensity,
+            shape: ClusterLightShape::Point,
+        }
+    }
+
+    /// Spot lights shine along the entity's forward axis
+    pub fn from_spot(light: &SpotLight, transform: &Transform) -> Self {
+        Self {
+            position: transform.translation,
+            range: light.range,
+            color: light.color,
+            intensity: light.intensity,
+            shape: ClusterLightShape::Spot {
+                direction: transform.forward(),
+                inner_angle: light.inner_angle,
+                outer_angle: light.outer_angle,
+            },
+        }
+    }
+
+    /// The light moved into another space, e.g. view space
+    pub fn transformed(&self, matrix: &Mat4) -> Self {
+        let shape = match self.shape {
+            ClusterLightShape::Point => ClusterLightShape::Point,
+            ClusterLightShape::Spot {
+                direction,
+                inner_angle,
+                outer_angle,
+            } => ClusterLightShape::Spot {
+                direction: matrix.transform_vector3(direction).normalize_or_zero(),
+                inner_angle,
+                outer_angle,
+            },
+        };
+        Self {
+            position: matrix.transform_point3(self.position),
+            shape,
+            ..*self
+        }
+    }
+
+    /// Whether the light can reach any point of the box `min..max`
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        let closest = self.position.clamp(min, max);
+        if closest.distance_squared(self.position) > self.range * self.range {
+            return false;
+        }
+        let ClusterLightShape::Spot {
+            direction,
+            outer_angle,
+            ..
+        } = self.shape
+        else {
+            return true;
+        };
+        if outer_angle >= std::f32::consts::FRAC_PI_2 {
+            return true;
+        }
+
+        // Cone against the bounding sphere of the box
+        let center = (min + max) * 0.5;
+        let radius = (max - min).length() * 0.5;
+        let v = center - self.position;
+        let along = v.dot(direction);
+        let across = (v.length_squared() - along * along).max(0.0).sqrt();
+        let (sin, cos) = outer_angle.sin_cos();
+        let distance_to_cone = cos * across - sin * along;
+        distance_to_cone <= radius && along <= radius + self.range && along >= -radius
+    }
+}
+
+/// Slice of [`LightClusters::indices`] belonging to one cluster
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ClusterRange {
+    pub offset: u32,
+    pub count: u32,
+}
+
+/// Per-cluster light lists of the camera being shaded.
+///
+/// Clusters are indexed `x + y * tiles_x + z * tiles_x * tiles_y`, with tile
+/// row 0 at the top of the screen and slice 0 at the near plane.
+pub struct LightClusters {
+    config: ClusterConfig,
+    projection: Mat4,
+    near: f32,
+    far: f32,
+    /// View-space bounds of every cluster
+    bounds: Vec<(Vec3, Vec3)>,
+    /// View-space Z range of every slice
+    slice_extents: Vec<(f32, f32)>,
+    /// X range of every column and Y range of every row, per slice
+    column_extents: Vec<(f32, f32)>,
+    row_extents: Vec<(f32, f32)>,
+    view: Mat4,
+    lights: Vec<ClusterLight>,
+    ranges: Vec<ClusterRange>,
+    indices: Vec<u32>,
+    dropped: usize,
+}
+
+impl Resource for LightClusters {}
+
+impl Default for LightClusters {
+    fn default() -> Self {
+        Self::new(ClusterConfig::default())
+    }
+}
+
+impl LightClusters {
+    pub fn new(config: ClusterConfig) -> Self {
+        Self {
+            config,
+            projection: Mat4::ZERO,
+            near: 0.0,
+            far: 0.0,
+            bounds: Vec::new(),
+            slice_extents: Vec::new(),
+            column_extents: Vec::new(),
+            row_extents: Vec::new(),
+            view: Mat4::IDENTITY,
+            lights: Vec::new(),
+            ranges: vec![ClusterRange::default(); config.cluster_count()],
+            indices: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    pub fn config(&self) -> &ClusterConfig {
+        &self.config
+    }
+
+    /// Change the grid; takes effect on the next [`Self::update_frustum`]
+    pub fn set_config(&mut self, config: ClusterConfig) {
+        if config != self.config {
+            self.config = config;
+            self.bounds.clear();
+        }
+    }
+
+    /// Rebuild the cluster bounds if the projection changed. `near` and `far`
+    /// are the view depths the slices span.
+    pub fn update_frustum(&mut self, projection: Mat4, near: f32, far: f32) {
+        let near = near.max(1e-3);
+        let far = far.max(near * 1.001);
+        if !self.bounds.is_empty()
+            && projection == self.projection
+            && (near, far) == (self.near, self.far)
+        {
+            return;
+        }
+        self.projection = projection;
+        self.near = near;
+        self.far = far;
+
+        let config = self.config;
+        let inverse = projection.inverse();
+        let unproject = |x: f32, y: f32, z: f32| {
+            let p = inverse * Vec4::new(x, y, z, 1.0);
+            p.truncate() / p.w
+        };
+
+        self.bounds = Vec::with_capacity(config.cluster_count());
+        let slice_depths: Vec<f32> = (0..=config.slices).map(|z| self.slice_depth(z)).collect();
+        for z in 0..config.slices as usize {
+            for y in 0..config.tiles_y {
+                for x in 0..config.tiles_x {
+                    let x0 = -1.0 + 2.0 * x as f32 / config.tiles_x as f32;
+                    let x1 = -1.0 + 2.0 * (x + 1) as f32 / config.tiles_x as f32;
+                    let y0 = 1.0 - 2.0 * y as f32 / config.tiles_y as f32;
+                    let y1 = 1.0 - 2.0 * (y + 1) as f32 / config.tiles_y as f32;
+
+                    let mut min = Vec3::splat(f32::MAX);
+                    let mut max = Vec3::splat(f32::MIN);
+                    for (cx, cy) in [(x0, y0), (x1, y0), (x0, y1), (x1, y1)] {
+                        // The corner's ray from the near to the far plane
+                        let near_point = unproject(cx, cy, 0.0);
+                        let far_point = unproject(cx, cy, 1.0);
+                        for depth in [slice_depths[z], slice_depths[z + 1]] {
+                            let t = (depth + near_point.z) / (near_point.z - far_point.z);
+                            let point = near_point.lerp(far_point, t);
+                            min = min.min(point);
+                            max = max.max(point);
+                        }
+                    }
+                    self.bounds.push((min, max));
+                }
+            }
+        }
+
+        // Per-slice extents of every column, row and the slice itself, to
+        // narrow down the clusters a light can touch
+        let (tiles_x, tiles_y) = (config.tiles_x as usize, config.tiles_y as usize);
+        let empty = (f32::MAX, f32::MIN);
+        let union = |a: (f32, f32), b: (f32, f32)| (a.0.min(b.0), a.1.max(b.1));
+        self.slice_extents = vec![empty; config.slices as usize];
+        self.column_extents = vec![empty; config.slices as usize * tiles_x];
+        self.row_extents = vec![empty; config.slices as usize * tiles_y];
+        for (cluster, (min, max)) in self.bounds.iter().enumerate() {
+            let (x, y) = (cluster % tiles_x, cluster / tiles_x % tiles_y);
+            let z = cluster / (tiles_x * tiles_y);
+            let slice = &mut self.slice_extents[z];
+            *slice = union(*slice, (min.z, max.z));
+            let column = &mut self.column_extents[z * tiles_x + x];
+            *column = union(*column, (min.x, max.x));
+            let row = &mut self.row_extents[z * tiles_y + y];
+            *row = union(*row, (min.y, max.y));
+        }
+    }
+
+    /// Bin `lights` (world space) into the clusters, seen from `view`
+    pub fn assign(&mut self, view: Mat4, lights: Vec<ClusterLight>) {
+        let config = self.config;
+        self.view = view;
+        self.lights = lights;
+        self.dropped = 0;
+        self.ranges.clear();
+        self.ranges
+            .resize(config.cluster_count(), ClusterRange::default());
+        self.indices.clear();
+        if self.bounds.len() != config.cluster_count() {
+            return;
+        }
+
+        let mut pairs: Vec<(u32, u32)> = Vec::new();
+        for (index, light) in self.lights.iter().enumerate() {
+            let local = light.transformed(&view);
+            let depth = -local.position.z;
+            if depth + local.range < self.near || depth - local.range > self.far {
+                continue;
+            }
+            // Clusters whose bounds overlap the light's box on every axis
+            let min = local.position - Vec3::splat(local.range);
+            let max = local.position + Vec3::splat(local.range);
+            let overlapping = |extents: &[(f32, f32)], low: f32, high: f32| {
+                let first = extents.iter().position(|e| e.1 >= low && e.0 <= high)?;
+                let last = extents.iter().rposition(|e| e.1 >= low && e.0 <= high)?;
+                Some((first as u32, last as u32))
+            };
+            let Some((z0, z1)) = overlapping(&self.slice_extents, min.z, max.z) else {
+                continue;
+            };
+            for z in z0..=z1 {
+                let columns = z as usize * config.tiles_x as usize;
+                let rows = z as usize * config.tiles_y as usize;
+                let columns = &self.column_extents[columns..columns + config.tiles_x as usize];
+                let rows = &self.row_extents[rows..rows + config.tiles_y as usize];
+                let (Some((x0, x1)), Some((y0, y1))) = (
+                    overlapping(columns, min.x, max.x),
+                    overlapping(rows, min.y, max.y),
+                ) else {
+                    continue;
+                };
+                for y in y0..=y1 {
+                    for x in x0..=x1 {
+                        let cluster = self.cluster_index(x, y, z);
+                        let (min, max) = self.bounds[cluster];
+                        if !local.intersects_aabb(min, max) {
+                            continue;
+                        }
+                        let range = &mut self.ranges[cluster];
+                        if range.count >= config.max_lights_per_cluster {
+                            self.dropped += 1;
+                            continue;
+                        }
+                        range.count += 1;
+                        pairs.push((cluster as u32, index as u32));
+                    }
+                }
+            }
+        }
+
+        // Counting sort into contiguous lists, keeping lights in order
+        let mut offset = 0;
+        for range in &mut self.ranges {
+            range.offset = offset;
+            offset += range.count;
+        }
+        self.indices.resize(pairs.len(), 0);
+        let mut cursor: Vec<u32> = self.ranges.iter().map(|range| range.offset).collect();
+        for (cluster, light) in pairs {
+            let slot = &mut cursor[cluster as usize];
+            self.indices[*slot as usize] = light;
+            *slot += 1;
+        }
+    }
+
+    pub fn near(&self) -> f32 {
+        self.near
+    }
+
+    pub fn far(&self) -> f32 {
+        self.far
+    }
+
+    pub fn view(&self) -> &Mat4 {
+        &self.view
+    }
+
+    /// View depth where `slice` begins
+    pub fn slice_depth(&self, slice: u32) -> f32 {
+        self.near * (self.far / self.near).powf(slice as f32 / self.config.slices as f32)
+    }
+
+    /// Slice containing the view depth, clamped to the grid
+    pub fn slice_for_depth(&self, depth: f32) -> u32 {
+        let depth = depth.max(self.near);
+        let slice =
+            (depth / self.near).ln() / (self.far / self.near).ln() * self.config.slices as f32;
+        (slice.floor().max(0.0) as u32).min(self.config.slices - 1)
+    }
+
+    pub fn cluster_index(&self, x: u32, y: u32, z: u32) -> usize {
+        let config = &self.config;
+        (x + y * config.tiles_x + z * config.tiles_x * config.tiles_y) as usize
+    }
+
+    /// Cluster under a normalized device position at a view depth
+    pub fn cluster_at(&self, ndc: Vec2, depth: f32) -> Option<usize> {
+        if !(self.near..=self.far).contains(&depth) || ndc.abs().max_element() > 1.0 {
+            return None;
+        }
+        let config = &self.config;
+        let x = (((ndc.x + 1.0) * 0.5 * config.tiles_x as f32) as u32).min(config.tiles_x - 1);
+        let y = (((1.0 - ndc.y) * 0.5 * config.tiles_y as f32) as u32).min(config.tiles_y - 1);
+        Some(self.cluster_index(x, y, self.slice_for_depth(depth)))
+    }
+
+    /// View-space bounds of a cluster
+    pub fn cluster_bounds(&self, cluster: usize) -> (Vec3, Vec3) {
+        self.bounds[cluster]
+    }
+
+    /// The lights given to the last [`Self::assign`]
+    pub fn lights(&self) -> &[ClusterLight] {
+        &self.lights
+    }
+
+    /// Indices into [`Self::lights`] reaching a cluster, ascending
+    pub fn cluster_lights(&self, cluster: usize) -> &[u32] {
+        let range = self.ranges[cluster];
+        &self.indices[range.offset as usize..(range.offset + range.count) as usize]
+    }
+
+    pub fn ranges(&self) -> &[ClusterRange] {
+        &self.ranges
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Light-cluster pairs left out by [`ClusterConfig::max_lights_per_cluster`]
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
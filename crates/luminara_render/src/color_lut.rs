@@ -0,0 +1,206 @@
+//! 3D color lookup tables for color grading.
+//!
+//! [`ColorLut`] assets are read from `.cube` files as written by Resolve,
+//! Photoshop and most grading tools. The [`PostEffect::ColorGrading`]
+//! effect samples them on the GPU; [`ColorLut::sample`] gives the same
+//! trilinear lookup on the CPU.
+//!
+//! [`PostEffect::ColorGrading`]: crate::PostEffect::ColorGrading
+
+use luminara_asset::{Asset, AssetLoadError, AssetLoader};
+use luminara_math::Vec3;
+use std::path::Path;
+use thiserror::Error;
+
+/// Largest `LUT_3D_SIZE` accepted, as in the `.cube` specification
+pub const MAX_LUT_SIZE: u32 = 256;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LutError {
+    #[error("missing LUT_3D_SIZE")]
+    MissingSize,
+    #[error("LUT_3D_SIZE {0} is outside 2..=256")]
+    InvalidSize(u32),
+    #[error("1D LUTs are not supported")]
+    Unsupported1D,
+    #[error("line {line}: {reason}")]
+    InvalidLine { line: usize, reason: String },
+    #[error("expected {expected} entries, found {found}")]
+    EntryCount { expected: usize, found: usize },
+    #[error("DOMAIN_MIN must be below DOMAIN_MAX on every channel")]
+    InvalidDomain,
+}
+
+/// A cube of output colors indexed by input color
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorLut {
+    /// Entries along each axis
+    pub size: u32,
+    /// Input color mapped to the first entry of each axis
+    pub domain_min: Vec3,
+    /// Input color mapped to the last entry of each axis
+    pub domain_max: Vec3,
+    /// `size³` output colors, red varying fastest, then green, then blue
+    pub data: Vec<Vec3>,
+}
+
+impl ColorLut {
+    /// A LUT that returns its input over the unit domain
+    pub fn identity(size: u32) -> Self {
+        let size = size.clamp(2, MAX_LUT_SIZE);
+        let scale = 1.0 / (size - 1) as f32;
+        let mut data = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.push(Vec3::new(r as f32, g as f32, b as f32) * scale);
+                }
+            }
+        }
+        Self {
+            size,
+            domain_min: Vec3::ZERO,
+            domain_max: Vec3::ONE,
+            data,
+        }
+    }
+
+    /// Parse the text of a `.cube` file
+    pub fn parse_cube(source: &str) -> Result<Self, LutError> {
+        let mut size = None;
+        let mut domain_min = Vec3::ZERO;
+        let mut domain_max = Vec3::ONE;
+        let mut data = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| LutError::InvalidLine {
+                line: line_number,
+                reason: reason.to_string(),
+            };
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                // Keywords are only allowed before the table
+                if !data.is_empty() {
+                    return Err(invalid("keyword after table data"));
+                }
+                let values: Vec<&str> = words.collect();
+                match keyword {
+                    "TITLE" => {}
+                    "LUT_1D_SIZE" => return Err(LutError::Unsupported1D),
+                    "LUT_3D_SIZE" => {
+                        let [value] = values[..] else {
+                            return Err(invalid("LUT_3D_SIZE takes one value"));
+                        };
+                        let value: u32 = value
+                            .parse()
+                            .map_err(|_| invalid("LUT_3D_SIZE is not an integer"))?;
+                        if !(2..=MAX_LUT_SIZE).contains(&value) {
+                            return Err(LutError::InvalidSize(value));
+                        }
+                        size = Some(value);
+                    }
+                    "DOMAIN_MIN" => {
+                        domain_min = parse_color(&values)
+                            .ok_or_else(|| invalid("DOMAIN_MIN takes three numbers"))?
+                    }
+                    "DOMAIN_MAX" => {
+                        domain_max = parse_color(&values)
+                            .ok_or_else(|| invalid("DOMAIN_MAX takes three numbers"))?
+                    }
+                    // Resolve's shorthand for the same range on every channel
+                    "LUT_3D_INPUT_RANGE" => {
+                        let range: Vec<f32> =
+                            values.iter().filter_map(|v| v.parse().ok()).collect();
+                        let [min, max] = range[..] else {
+                            return Err(invalid("LUT_3D_INPUT_RANGE takes two numbers"));
+                        };
+                        domain_min = Vec3::splat(min);
+                        domain_max = Vec3::splat(max);
+                    }
+                    _ => return Err(invalid(&format!("unknown keyword {}", keyword))),
+                }
+                continue;
+            }
+
+            let values: Vec<&str> = line.split_whitespace().collect();
+            let color = parse_color(&values).ok_or_else(|| invalid("expected three numbers"))?;
+            data.push(color);
+        }
+
+        let size = size.ok_or(LutError::MissingSize)?;
+        let expected = (size * size * size) as usize;
+        if data.len() != expected {
+            return Err(LutError::EntryCount {
+                expected,
+                found: data.len(),
+            });
+        }
+        if domain_min.cmpge(domain_max).any() {
+            return Err(LutError::InvalidDomain);
+        }
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            data,
+        })
+    }
+
+    /// Entry at integer coordinates, clamped to the cube
+    pub fn entry(&self, r: u32, g: u32, b: u32) -> Vec3 {
+        let last = self.size - 1;
+        let (r, g, b) = (r.min(last), g.min(last), b.min(last));
+        self.data[(r + (g + b * self.size) * self.size) as usize]
+    }
+
+    /// Trilinear lookup of `color`, clamped to the domain
+    pub fn sample(&self, color: Vec3) -> Vec3 {
+        let normalized = ((color - self.domain_min) / (self.domain_max - self.domain_min))
+            .clamp(Vec3::ZERO, Vec3::ONE);
+        let position = normalized * (self.size - 1) as f32;
+        let base = position.floor().min(Vec3::splat((self.size - 2) as f32));
+        let t = position - base;
+        let (r, g, b) = (base.x as u32, base.y as u32, base.z as u32);
+
+        let lerp_r = |g, b| self.entry(r, g, b).lerp(self.entry(r + 1, g, b), t.x);
+        let lerp_g = |b| lerp_r(g, b).lerp(lerp_r(g + 1, b), t.y);
+        lerp_g(b).lerp(lerp_g(b + 1), t.z)
+    }
+}
+
+fn parse_color(values: &[&str]) -> Option<Vec3> {
+    let [r, g, b] = values[..] else {
+        return None;
+    };
+    Some(Vec3::new(r.parse().ok()?, g.parse().ok()?, b.parse().ok()?))
+}
+
+impl Asset for ColorLut {
+    fn type_name() -> &'static str {
+        "ColorLut"
+    }
+}
+
+/// Loads `.cube` files as [`ColorLut`] assets
+pub struct ColorLutLoader;
+
+impl AssetLoader for ColorLutLoader {
+    type Asset = ColorLut;
+
+    fn extensions(&self) -> &[&str] {
+        &["cube"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let source = std::str::from_utf8(bytes)
+            .map_err(|e| AssetLoadError::Parse(format!("LUT is not UTF-8: {}", e)))?;
+        ColorLut::parse_cube(source).map_err(|e| AssetLoadError::Parse(e.to_string()))
+    }
+}
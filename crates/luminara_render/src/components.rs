@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use crate::{Mesh, Texture};
 
 /// Mesh renderer component
-#[derive(Debug, Clone, Reflect)]
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
 pub struct MeshRenderer {
     pub mesh: Handle<Mesh>,
     pub material: Handle<PbrMaterial>,
@@ -75,6 +75,37 @@ impl Component for PointLight {
     }
 }
 
+/// Spot light component, shining along the entity's forward axis
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub struct SpotLight {
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,
+    /// Angle from the axis, in radians, where the falloff begins
+    pub inner_angle: f32,
+    /// Angle from the axis, in radians, where the light ends
+    pub outer_angle: f32,
+    pub cast_shadows: bool,
+}
+
+impl Component for SpotLight {
+    fn type_name() -> &'static str {
+        "SpotLight"
+    }
+}
+
+/// Weights of the morph targets of the entity's mesh
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Reflect)]
+pub struct MorphWeights {
+    pub weights: Vec<f32>,
+}
+
+impl Component for MorphWeights {
+    fn type_name() -> &'static str {
+        "MorphWeights"
+    }
+}
+
 /// Level of Detail component
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
 pub struct Lod {
@@ -82,6 +113,16 @@ pub struct Lod {
     pub meshes: Vec<Handle<Mesh>>, // Meshes for each LOD level
 }
 
+impl Lod {
+    /// Level used at `distance` from the camera; may be past the last mesh
+    pub fn level_at(&self, distance: f32) -> usize {
+        self.distances
+            .iter()
+            .position(|&d| distance < d)
+            .unwrap_or(self.distances.len())
+    }
+}
+
 impl Component for Lod {
     fn type_name() -> &'static str {
         "Lod"
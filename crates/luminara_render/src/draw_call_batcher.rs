@@ -5,17 +5,19 @@
 //! - Batch identical materials
 //! - Target: <100 draw calls for 1000+ objects (Requirement 19.3)
 
-use crate::{Handle, Mesh, PbrMaterial, Texture};
-use luminara_asset::AssetServer;
+use crate::{Handle, Material, Mesh, PbrMaterial, Texture};
+use luminara_asset::{Asset, AssetServer};
 use luminara_core::Query;
 use luminara_math::Transform;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 
 /// Sort key for draw call batching
 /// Ordered by: shader → texture → material properties
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DrawCallSortKey {
-    /// Shader ID (for now, all use same PBR shader, but extensible)
+    /// Shader ID (material type, shader asset and pipeline state)
     pub shader_id: u64,
     /// Texture ID (None for untextured)
     pub texture_id: Option<u64>,
@@ -55,6 +57,48 @@ impl MaterialKey {
             ],
         }
     }
+
+    /// Create material key from the uniform scalars of any material, laid
+    /// out like `PbrMaterial`: albedo, metallic, roughness, emissive
+    pub fn from_scalars(scalars: &[f32]) -> Self {
+        let quantize = |index: usize| (scalars.get(index).copied().unwrap_or(0.0) * 255.0) as u8;
+        Self {
+            albedo: [quantize(0), quantize(1), quantize(2), quantize(3)],
+            metallic: quantize(4),
+            roughness: quantize(5),
+            emissive: [quantize(6), quantize(7), quantize(8)],
+        }
+    }
+}
+
+impl DrawCallSortKey {
+    /// Create sort key for any material
+    pub fn for_material<M: Material>(material: &M, asset_server: &AssetServer) -> Self {
+        let mut hasher = DefaultHasher::new();
+        <M as Asset>::type_name().hash(&mut hasher);
+        material.shader().id().hash(&mut hasher);
+        material.pipeline_key().hash(&mut hasher);
+        let shader_id = hasher.finish();
+
+        // Only loaded textures count; until then the draw uses the fallback
+        let mut hasher = DefaultHasher::new();
+        let mut textured = false;
+        for binding in material.textures() {
+            if let Some(handle) = binding.texture {
+                if asset_server.get::<Texture>(&handle).is_some() {
+                    handle.id().hash(&mut hasher);
+                    textured = true;
+                }
+            }
+        }
+        let texture_id = textured.then(|| hasher.finish());
+
+        Self {
+            shader_id,
+            texture_id,
+            material_key: material.material_key(),
+        }
+    }
 }
 
 /// Batched draw call containing multiple instances
@@ -195,33 +239,7 @@ impl DrawCallBatcher {
         material: &PbrMaterial,
         asset_server: &AssetServer,
     ) -> DrawCallSortKey {
-        // For now, all use same PBR shader (shader_id = 0)
-        // In future, different shaders would have different IDs
-        let shader_id = 0;
-
-        // Get texture ID if present
-        let texture_id = material.albedo_texture.as_ref().and_then(|handle| {
-            // Check if texture is loaded
-            if asset_server.get::<Texture>(handle).is_some() {
-                // Use handle's asset ID as texture ID
-                use std::collections::hash_map::DefaultHasher;
-                use std::hash::{Hash, Hasher};
-                let mut hasher = DefaultHasher::new();
-                handle.id().hash(&mut hasher);
-                Some(hasher.finish())
-            } else {
-                None
-            }
-        });
-
-        // Create material key
-        let material_key = MaterialKey::from_material(material);
-
-        DrawCallSortKey {
-            shader_id,
-            texture_id,
-            material_key,
-        }
+        DrawCallSortKey::for_material(material, asset_server)
     }
 
     /// Get batched draw calls (sorted by shader → texture → material)
@@ -0,0 +1,644 @@
+//! Environment maps for image-based lighting.
+//!
+//! An [`EnvironmentMap`] is built on the CPU from an equirectangular image,
+//! usually a Radiance `.hdr` file: the image is resampled into a
+//! [`Cubemap`] shown by skyboxes, its diffuse irradiance is projected onto
+//! L2 [`SphericalHarmonics`], and a chain of cubemaps prefiltered with the
+//! GGX distribution of increasing roughness serves glossy reflections. The
+//! split-sum [`BrdfLut`] completes the specular term.
+//!
+//! Cubemap faces follow the GPU convention, ordered +X, -X, +Y, -Y, +Z, -Z,
+//! so a face uploaded as-is is sampled with world space directions.
+
+use crate::texture::{Texture, TextureData, TextureFormat};
+use luminara_asset::{Asset, AssetLoadError, AssetLoader};
+use luminara_math::{Vec2, Vec3};
+use std::f32::consts::PI;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EnvironmentMapError {
+    #[error("environment images must be RGBA8, RGBA16F or RGBA32F, not {0:?}")]
+    UnsupportedFormat(TextureFormat),
+    #[error("environment image is empty")]
+    Empty,
+    #[error("expected {expected} bytes of pixel data, found {found}")]
+    DataSize { expected: usize, found: usize },
+}
+
+/// Six square faces of linear HDR color
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cubemap {
+    /// Texels along each side of a face
+    pub size: u32,
+    /// `6 * size²` colors, face by face, each face row by row from the top
+    pub data: Vec<Vec3>,
+}
+
+impl Cubemap {
+    pub fn new(size: u32) -> Self {
+        let size = size.max(1);
+        Self {
+            size,
+            data: vec![Vec3::ZERO; (6 * size * size) as usize],
+        }
+    }
+
+    /// A cubemap whose texels hold `radiance` of their center direction
+    pub fn from_fn(size: u32, radiance: impl Fn(Vec3) -> Vec3) -> Self {
+        let size = size.max(1);
+        let mut data = Vec::with_capacity((6 * size * size) as usize);
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    data.push(radiance(Self::texel_direction(size, face, x, y)));
+                }
+            }
+        }
+        Self { size, data }
+    }
+
+    /// Resample an equirectangular image, longitude along its width and
+    /// +Y at its top row. The image's -Z direction is at its center.
+    pub fn from_equirectangular(
+        image: &TextureData,
+        size: u32,
+    ) -> Result<Self, EnvironmentMapError> {
+        let pixels = linear_pixels(image)?;
+        let (width, height) = (image.width as usize, image.height as usize);
+        let pixel = |x: isize, y: usize| pixels[y * width + x.rem_euclid(width as isize) as usize];
+
+        Ok(Self::from_fn(size, |direction| {
+            let u = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+            let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+            // Bilinear, wrapping around horizontally
+            let x = u * width as f32 - 0.5;
+            let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+            let (x0, y0) = (x.floor(), y.floor());
+            let (tx, ty) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as isize, y0 as usize);
+            let y1 = (y0 + 1).min(height - 1);
+            let top = pixel(x0, y0).lerp(pixel(x0 + 1, y0), tx);
+            let bottom = pixel(x0, y1).lerp(pixel(x0 + 1, y1), tx);
+            top.lerp(bottom, ty)
+        }))
+    }
+
+    fn index(&self, face: u32, x: u32, y: u32) -> usize {
+        ((face * self.size + y) * self.size + x) as usize
+    }
+
+    /// Texel of a face
+    pub fn texel(&self, face: u32, x: u32, y: u32) -> Vec3 {
+        self.data[self.index(face, x, y)]
+    }
+
+    /// Unit direction through the center of a texel
+    pub fn texel_direction(size: u32, face: u32, x: u32, y: u32) -> Vec3 {
+        let s = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+        let t = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+        face_direction(face, s, t).normalize()
+    }
+
+    /// Solid angle a texel covers, in steradians. The texels of all faces
+    /// add up to 4π.
+    pub fn texel_solid_angle(size: u32, x: u32, y: u32) -> f32 {
+        let area = |s: f32, t: f32| (s * t).atan2((s * s + t * t + 1.0).sqrt());
+        let texel = 2.0 / size as f32;
+        let s0 = x as f32 * texel - 1.0;
+        let t0 = y as f32 * texel - 1.0;
+        let (s1, t1) = (s0 + texel, t0 + texel);
+        area(s0, t0) - area(s0, t1) - area(s1, t0) + area(s1, t1)
+    }
+
+    /// Bilinear lookup in the direction `direction`, clamped at face edges
+    pub fn sample(&self, direction: Vec3) -> Vec3 {
+        let (face, s, t) = direction_face(direction);
+        let last = (self.size - 1) as f32;
+        let x = ((s + 1.0) * 0.5 * self.size as f32 - 0.5).clamp(0.0, last);
+        let y = ((t + 1.0) * 0.5 * self.size as f32 - 0.5).clamp(0.0, last);
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (x1, y1) = ((x0 + 1).min(self.size - 1), (y0 + 1).min(self.size - 1));
+        let (tx, ty) = (x.fract(), y.fract());
+        let top = self.texel(face, x0, y0).lerp(self.texel(face, x1, y0), tx);
+        let bottom = self.texel(face, x0, y1).lerp(self.texel(face, x1, y1), tx);
+        top.lerp(bottom, ty)
+    }
+
+    /// Half the size, averaging 2x2 texels
+    pub fn downsample(&self) -> Self {
+        if self.size == 1 {
+            return self.clone();
+        }
+        let mut half = Self::new(self.size / 2);
+        for face in 0..6 {
+            for y in 0..half.size {
+                for x in 0..half.size {
+                    let sum = self.texel(face, 2 * x, 2 * y)
+                        + self.texel(face, 2 * x + 1, 2 * y)
+                        + self.texel(face, 2 * x, 2 * y + 1)
+                        + self.texel(face, 2 * x + 1, 2 * y + 1);
+                    let index = half.index(face, x, y);
+                    half.data[index] = sum * 0.25;
+                }
+            }
+        }
+        half
+    }
+
+    /// This cubemap followed by its downsampled levels down to 1x1
+    pub fn mip_chain(&self) -> Vec<Self> {
+        let mut chain = vec![self.clone()];
+        while chain.last().unwrap().size > 1 {
+            let next = chain.last().unwrap().downsample();
+            chain.push(next);
+        }
+        chain
+    }
+}
+
+/// Direction of the face coordinates `s`, `t` in `-1..=1`, `t` pointing down
+fn face_direction(face: u32, s: f32, t: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1.0, -t, -s),
+        1 => Vec3::new(-1.0, -t, s),
+        2 => Vec3::new(s, 1.0, t),
+        3 => Vec3::new(s, -1.0, -t),
+        4 => Vec3::new(s, -t, 1.0),
+        _ => Vec3::new(-s, -t, -1.0),
+    }
+}
+
+/// Face and face coordinates hit by `direction`, inverse of [`face_direction`]
+fn direction_face(direction: Vec3) -> (u32, f32, f32) {
+    let abs = direction.abs();
+    let (face, s, t, major) = if abs.x >= abs.y && abs.x >= abs.z {
+        if direction.x > 0.0 {
+            (0, -direction.z, -direction.y, abs.x)
+        } else {
+            (1, direction.z, -direction.y, abs.x)
+        }
+    } else if abs.y >= abs.z {
+        if direction.y > 0.0 {
+            (2, direction.x, direction.z, abs.y)
+        } else {
+            (3, direction.x, -direction.z, abs.y)
+        }
+    } else if direction.z > 0.0 {
+        (4, direction.x, -direction.y, abs.z)
+    } else {
+        (5, -direction.x, -direction.y, abs.z)
+    };
+    let major = major.max(f32::MIN_POSITIVE);
+    (face, s / major, t / major)
+}
+
+/// Linear colors of an image. 8-bit images are sRGB encoded.
+fn linear_pixels(image: &TextureData) -> Result<Vec<Vec3>, EnvironmentMapError> {
+    let count = (image.width * image.height) as usize;
+    if count == 0 {
+        return Err(EnvironmentMapError::Empty);
+    }
+    let bytes_per_pixel = match image.format {
+        TextureFormat::Rgba8 => 4,
+        TextureFormat::Rgba16F => 8,
+        TextureFormat::Rgba32F => 16,
+        format => return Err(EnvironmentMapError::UnsupportedFormat(format)),
+    };
+    if image.data.len() < count * bytes_per_pixel {
+        return Err(EnvironmentMapError::DataSize {
+            expected: count * bytes_per_pixel,
+            found: image.data.len(),
+        });
+    }
+
+    let pixels = image.data.chunks_exact(bytes_per_pixel).take(count);
+    Ok(match image.format {
+        TextureFormat::Rgba8 => pixels
+            .map(|p| {
+                Vec3::new(
+                    srgb_to_linear(p[0]),
+                    srgb_to_linear(p[1]),
+                    srgb_to_linear(p[2]),
+                )
+            })
+            .collect(),
+        TextureFormat::Rgba16F => pixels
+            .map(|p| {
+                let channel =
+                    |i: usize| half::f16::from_le_bytes([p[2 * i], p[2 * i + 1]]).to_f32();
+                Vec3::new(channel(0), channel(1), channel(2))
+            })
+            .collect(),
+        _ => pixels
+            .map(|p| {
+                let channel = |i: usize| {
+                    f32::from_le_bytes([p[4 * i], p[4 * i + 1], p[4 * i + 2], p[4 * i + 3]])
+                };
+                Vec3::new(channel(0), channel(1), channel(2))
+            })
+            .collect(),
+    })
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Band limits of the clamped cosine lobe, divided by π, per SH band
+const COSINE_LOBE: [f32; 3] = [1.0, 2.0 / 3.0, 0.25];
+
+/// Order 2 (nine coefficient) spherical harmonics of RGB radiance. They
+/// capture the low frequencies diffuse lighting depends on within a few
+/// percent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SphericalHarmonics {
+    pub coefficients: [Vec3; 9],
+}
+
+impl Default for SphericalHarmonics {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl SphericalHarmonics {
+    pub const ZERO: Self = Self {
+        coefficients: [Vec3::ZERO; 9],
+    };
+
+    /// An environment of `radiance` in every direction
+    pub fn constant(radiance: Vec3) -> Self {
+        let mut sh = Self::ZERO;
+        sh.coefficients[0] = radiance / Self::basis(Vec3::Z)[0];
+        sh
+    }
+
+    /// The real SH basis functions up to band 2 at a unit direction
+    pub fn basis(direction: Vec3) -> [f32; 9] {
+        let Vec3 { x, y, z } = direction;
+        [
+            0.282_095,
+            0.488_603 * y,
+            0.488_603 * z,
+            0.488_603 * x,
+            1.092_548 * x * y,
+            1.092_548 * y * z,
+            0.315_392 * (3.0 * z * z - 1.0),
+            1.092_548 * x * z,
+            0.546_274 * (x * x - y * y),
+        ]
+    }
+
+    /// Add `radiance` arriving from `direction` over `solid_angle` steradians
+    pub fn add(&mut self, direction: Vec3, radiance: Vec3, solid_angle: f32) {
+        for (coefficient, basis) in self.coefficients.iter_mut().zip(Self::basis(direction)) {
+            *coefficient += radiance * basis * solid_angle;
+        }
+    }
+
+    /// Project every texel of `cubemap`, weighted by its solid angle
+    pub fn project(cubemap: &Cubemap) -> Self {
+        let mut sh = Self::ZERO;
+        let mut total = 0.0;
+        let size = cubemap.size;
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let solid_angle = Cubemap::texel_solid_angle(size, x, y);
+                    let direction = Cubemap::texel_direction(size, face, x, y);
+                    sh.add(direction, cubemap.texel(face, x, y), solid_angle);
+                    total += solid_angle;
+                }
+            }
+        }
+        // Remove the small error of the summed texel areas
+        sh.scale(4.0 * PI / total)
+    }
+
+    /// Reconstructed radiance arriving from `direction`
+    pub fn evaluate(&self, direction: Vec3) -> Vec3 {
+        self.coefficients
+            .iter()
+            .zip(Self::basis(direction))
+            .map(|(coefficient, basis)| *coefficient * basis)
+            .sum()
+    }
+
+    /// Cosine-weighted radiance arriving at a surface facing `normal`,
+    /// divided by π. A Lambertian surface of albedo `a` reflects
+    /// `a * irradiance(normal)`.
+    pub fn irradiance(&self, normal: Vec3) -> Vec3 {
+        self.irradiance_coefficients().evaluate(normal)
+    }
+
+    /// Coefficients convolved with the clamped cosine lobe, so that
+    /// [`Self::evaluate`] on them gives [`Self::irradiance`]. Shaders use
+    /// these.
+    pub fn irradiance_coefficients(&self) -> Self {
+        let mut convolved = *self;
+        for (index, coefficient) in convolved.coefficients.iter_mut().enumerate() {
+            let band = match index {
+                0 => 0,
+                1..=3 => 1,
+                _ => 2,
+            };
+            *coefficient *= COSINE_LOBE[band];
+        }
+        convolved
+    }
+
+    pub fn scale(mut self, factor: f32) -> Self {
+        for coefficient in &mut self.coefficients {
+            *coefficient *= factor;
+        }
+        self
+    }
+
+    /// Linear blend towards `other`, the SH of the blended environments
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut blended = *self;
+        for (a, b) in blended.coefficients.iter_mut().zip(other.coefficients) {
+            *a = a.lerp(b, t);
+        }
+        blended
+    }
+}
+
+/// Point `i` of a Hammersley set of `count` points in the unit square
+fn hammersley(i: u32, count: u32) -> Vec2 {
+    Vec2::new(
+        i as f32 / count as f32,
+        i.reverse_bits() as f32 * 2.328_306_4e-10,
+    )
+}
+
+/// Half vector around `normal` distributed by the GGX normal distribution
+/// of `roughness`, as perceptual roughness squared
+fn importance_sample_ggx(xi: Vec2, normal: Vec3, roughness: f32) -> Vec3 {
+    let alpha = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = ((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let local = Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+
+    let up = if normal.z.abs() < 0.999 {
+        Vec3::Z
+    } else {
+        Vec3::X
+    };
+    let tangent = up.cross(normal).normalize();
+    let bitangent = normal.cross(tangent);
+    (tangent * local.x + bitangent * local.y + normal * local.z).normalize()
+}
+
+/// GGX normal distribution
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha2 = (roughness * roughness).powi(2);
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator).max(1e-8)
+}
+
+/// Smith geometry term with the `k` used for image-based lighting
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let k = roughness * roughness / 2.0;
+    let schlick = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+    schlick(n_dot_v) * schlick(n_dot_l)
+}
+
+/// Radiance of `source` seen in a mirror of `roughness` facing `normal`.
+/// Samples read the mip of `chain` whose texels match their solid angle,
+/// which keeps a low sample count free of fireflies.
+fn prefilter_texel(chain: &[Cubemap], normal: Vec3, roughness: f32, samples: u32) -> Vec3 {
+    let base_size = chain[0].size as f32;
+    let texel_solid_angle = 4.0 * PI / (6.0 * base_size * base_size);
+    let max_level = (chain.len() - 1) as f32;
+
+    let mut color = Vec3::ZERO;
+    let mut weight = 0.0;
+    for i in 0..samples {
+        let half = importance_sample_ggx(hammersley(i, samples), normal, roughness);
+        let n_dot_h = normal.dot(half);
+        let light = 2.0 * n_dot_h * half - normal;
+        let n_dot_l = normal.dot(light);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+        // With the view along the normal, the pdf of `light` is D / 4
+        let pdf = distribution_ggx(n_dot_h.max(0.0), roughness) / 4.0;
+        let sample_solid_angle = 1.0 / (samples as f32 * pdf).max(1e-8);
+        let level =
+            (0.5 * (sample_solid_angle / texel_solid_angle).log2() + 1.0).clamp(0.0, max_level);
+
+        let lower = level.floor();
+        let below = chain[lower as usize].sample(light);
+        let above = chain[(lower as usize + 1).min(chain.len() - 1)].sample(light);
+        color += below.lerp(above, level - lower) * n_dot_l;
+        weight += n_dot_l;
+    }
+    if weight > 0.0 {
+        color / weight
+    } else {
+        chain[0].sample(normal)
+    }
+}
+
+/// Specular reflections of `source` for increasing roughness: level `i` of
+/// `levels` is `size >> i` texels wide and filtered for roughness
+/// `i / (levels - 1)`. Level 0 is a mirror.
+pub fn prefilter_specular(source: &Cubemap, size: u32, levels: u32, samples: u32) -> Vec<Cubemap> {
+    let size = size.max(1);
+    let levels = levels.clamp(1, size.ilog2() + 1);
+    let chain = source.mip_chain();
+    (0..levels)
+        .map(|level| {
+            let level_size = (size >> level).max(1);
+            let roughness = specular_roughness(level, levels);
+            if level == 0 {
+                return Cubemap::from_fn(level_size, |direction| source.sample(direction));
+            }
+            Cubemap::from_fn(level_size, |direction| {
+                prefilter_texel(&chain, direction, roughness, samples.max(1))
+            })
+        })
+        .collect()
+}
+
+/// Roughness a specular level is filtered for. Shaders pick the level
+/// `roughness * (levels - 1)`.
+pub fn specular_roughness(level: u32, levels: u32) -> f32 {
+    if levels <= 1 {
+        0.0
+    } else {
+        level as f32 / (levels - 1) as f32
+    }
+}
+
+/// Split-sum lookup table of the GGX specular BRDF integrated against a
+/// white environment: reflected light is `environment * (f0 * scale + bias)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrdfLut {
+    pub size: u32,
+    /// `(scale, bias)` rows of increasing roughness, columns of increasing
+    /// `n · v`, sampled at texel centers
+    pub data: Vec<Vec2>,
+}
+
+impl BrdfLut {
+    pub fn new(size: u32, samples: u32) -> Self {
+        let size = size.max(1);
+        let mut data = Vec::with_capacity((size * size) as usize);
+        for y in 0..size {
+            let roughness = (y as f32 + 0.5) / size as f32;
+            for x in 0..size {
+                let n_dot_v = (x as f32 + 0.5) / size as f32;
+                data.push(Self::integrate(n_dot_v, roughness, samples.max(1)));
+            }
+        }
+        Self { size, data }
+    }
+
+    /// `(scale, bias)` applied to `f0` for a view at `n_dot_v` of a surface
+    /// of `roughness`
+    pub fn integrate(n_dot_v: f32, roughness: f32, samples: u32) -> Vec2 {
+        let n_dot_v = n_dot_v.max(1e-4);
+        let view = Vec3::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+        let mut result = Vec2::ZERO;
+        for i in 0..samples {
+            let half = importance_sample_ggx(hammersley(i, samples), Vec3::Z, roughness);
+            let v_dot_h = view.dot(half);
+            let light = 2.0 * v_dot_h * half - view;
+            let (n_dot_l, n_dot_h) = (light.z, half.z);
+            if n_dot_l <= 0.0 {
+                continue;
+            }
+            let visibility = geometry_smith(n_dot_v, n_dot_l, roughness) * v_dot_h.max(0.0)
+                / (n_dot_h * n_dot_v).max(1e-8);
+            let fresnel = (1.0 - v_dot_h.max(0.0)).powi(5);
+            result += Vec2::new(1.0 - fresnel, fresnel) * visibility;
+        }
+        result / samples as f32
+    }
+
+    /// Bilinear lookup, as the shaders sample it
+    pub fn sample(&self, n_dot_v: f32, roughness: f32) -> Vec2 {
+        let last = (self.size - 1) as f32;
+        let x = (n_dot_v * self.size as f32 - 0.5).clamp(0.0, last);
+        let y = (roughness * self.size as f32 - 0.5).clamp(0.0, last);
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (x1, y1) = ((x0 + 1).min(self.size - 1), (y0 + 1).min(self.size - 1));
+        let texel = |x: u32, y: u32| self.data[(y * self.size + x) as usize];
+        let top = texel(x0, y0).lerp(texel(x1, y0), x.fract());
+        let bottom = texel(x0, y1).lerp(texel(x1, y1), x.fract());
+        top.lerp(bottom, y.fract())
+    }
+}
+
+/// Resolution and quality of the maps derived from an environment image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvironmentMapSettings {
+    /// Face size of the skybox cubemap
+    pub skybox_size: u32,
+    /// Face size of the sharpest specular level
+    pub specular_size: u32,
+    /// Specular levels from mirror to fully rough
+    pub specular_levels: u32,
+    /// GGX samples per prefiltered texel
+    pub samples: u32,
+}
+
+impl Default for EnvironmentMapSettings {
+    fn default() -> Self {
+        Self {
+            skybox_size: 512,
+            specular_size: 128,
+            specular_levels: 6,
+            samples: 64,
+        }
+    }
+}
+
+/// Lighting of a distant environment: what skyboxes show, what rough and
+/// glossy surfaces reflect, and the diffuse light they receive
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    pub skybox: Cubemap,
+    /// Prefiltered specular levels, see [`prefilter_specular`]
+    pub specular: Vec<Cubemap>,
+    /// Radiance of the environment; see [`SphericalHarmonics::irradiance`]
+    pub irradiance: SphericalHarmonics,
+}
+
+impl EnvironmentMap {
+    pub fn from_equirectangular(
+        image: &TextureData,
+        settings: &EnvironmentMapSettings,
+    ) -> Result<Self, EnvironmentMapError> {
+        let skybox = Cubemap::from_equirectangular(image, settings.skybox_size)?;
+        Ok(Self::from_cubemap(skybox, settings))
+    }
+
+    pub fn from_cubemap(skybox: Cubemap, settings: &EnvironmentMapSettings) -> Self {
+        let specular = prefilter_specular(
+            &skybox,
+            settings.specular_size,
+            settings.specular_levels,
+            settings.samples,
+        );
+        // Diffuse lighting only has low frequencies, a small level is enough
+        let mut low = skybox.clone();
+        while low.size > 32 {
+            low = low.downsample();
+        }
+        let irradiance = SphericalHarmonics::project(&low);
+        Self {
+            skybox,
+            specular,
+            irradiance,
+        }
+    }
+
+    /// A uniform environment of `radiance`, cheap to build
+    pub fn constant(radiance: Vec3) -> Self {
+        let face = Cubemap::from_fn(1, |_| radiance);
+        Self {
+            skybox: face.clone(),
+            specular: vec![face],
+            irradiance: SphericalHarmonics::constant(radiance),
+        }
+    }
+}
+
+impl Asset for EnvironmentMap {
+    fn type_name() -> &'static str {
+        "EnvironmentMap"
+    }
+}
+
+/// Loads equirectangular images, usually `.hdr` files, as
+/// [`EnvironmentMap`] assets
+#[derive(Default)]
+pub struct EnvironmentMapLoader {
+    pub settings: EnvironmentMapSettings,
+}
+
+impl AssetLoader for EnvironmentMapLoader {
+    type Asset = EnvironmentMap;
+
+    fn extensions(&self) -> &[&str] {
+        &["hdr"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let texture =
+            Texture::from_bytes(bytes).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+        EnvironmentMap::from_equirectangular(&texture.data, &self.settings)
+            .map_err(|e| AssetLoadError::Parse(e.to_string()))
+    }
+}
@@ -10,4 +10,12 @@ pub enum RenderError {
     SurfaceCreationFailed(String),
     #[error("Shader compilation error: {0}")]
     ShaderError(String),
+    #[error("Shader processing failed: {0}")]
+    ShaderProcess(#[from] crate::shader_preprocessor::ShaderProcessError),
+    #[error("Render graph error: {0}")]
+    Graph(#[from] crate::render_graph::RenderGraphError),
+    #[error("Render pass '{pass}' failed: {message}")]
+    PassFailed { pass: String, message: String },
+    #[error("Frame capture failed: {0}")]
+    Capture(String),
 }
@@ -0,0 +1,167 @@
+//! TrueType/OpenType font assets.
+//!
+//! Text layout and glyph rasterization only see fonts through [`FontFace`],
+//! so they can be exercised with synthetic fonts as well as loaded ones.
+
+use ab_glyph::{Font as _, FontArc, GlyphId};
+use luminara_asset::{Asset, AssetLoadError, AssetLoader};
+use luminara_math::Vec2;
+use std::path::Path;
+
+/// Segment of a glyph outline in font units, Y up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutlineCurve {
+    Line(Vec2, Vec2),
+    /// Quadratic Bézier from `.0` to `.2` with control point `.1`
+    Quad(Vec2, Vec2, Vec2),
+    /// Cubic Bézier from `.0` to `.3` with control points `.1` and `.2`
+    Cubic(Vec2, Vec2, Vec2, Vec2),
+}
+
+impl OutlineCurve {
+    pub fn start(&self) -> Vec2 {
+        match *self {
+            OutlineCurve::Line(p0, _)
+            | OutlineCurve::Quad(p0, _, _)
+            | OutlineCurve::Cubic(p0, _, _, _) => p0,
+        }
+    }
+
+    pub fn end(&self) -> Vec2 {
+        match *self {
+            OutlineCurve::Line(_, p1) => p1,
+            OutlineCurve::Quad(_, _, p2) => p2,
+            OutlineCurve::Cubic(_, _, _, p3) => p3,
+        }
+    }
+
+    /// Point at parameter `t` in `[0, 1]`
+    pub fn point(&self, t: f32) -> Vec2 {
+        let s = 1.0 - t;
+        match *self {
+            OutlineCurve::Line(p0, p1) => p0.lerp(p1, t),
+            OutlineCurve::Quad(p0, p1, p2) => p0 * (s * s) + p1 * (2.0 * s * t) + p2 * (t * t),
+            OutlineCurve::Cubic(p0, p1, p2, p3) => {
+                p0 * (s * s * s)
+                    + p1 * (3.0 * s * s * t)
+                    + p2 * (3.0 * s * t * t)
+                    + p3 * (t * t * t)
+            }
+        }
+    }
+}
+
+/// Metrics, glyph mapping and outlines of a font, in font units
+pub trait FontFace: Send + Sync {
+    fn units_per_em(&self) -> f32;
+    /// Height above the baseline
+    fn ascent(&self) -> f32;
+    /// Depth below the baseline, as a positive value
+    fn descent(&self) -> f32;
+    fn line_gap(&self) -> f32;
+    /// Glyph for `c`, or `None` if the font does not cover it
+    fn glyph_id(&self, c: char) -> Option<u16>;
+    fn advance(&self, glyph: u16) -> f32;
+    /// Adjustment to the advance of `left` when followed by `right`
+    fn kerning(&self, left: u16, right: u16) -> f32;
+    /// Outline curves; empty for blank glyphs such as spaces
+    fn outline(&self, glyph: u16) -> Vec<OutlineCurve>;
+}
+
+/// A font loaded from TrueType or OpenType data
+#[derive(Clone)]
+pub struct Font {
+    font: FontArc,
+}
+
+impl std::fmt::Debug for Font {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Font")
+            .field("glyphs", &self.font.glyph_count())
+            .finish()
+    }
+}
+
+impl Asset for Font {
+    fn type_name() -> &'static str {
+        "Font"
+    }
+}
+
+impl Font {
+    /// Parse TrueType/OpenType data; collections use their first face
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, AssetLoadError> {
+        let font = FontArc::try_from_vec(bytes)
+            .map_err(|e| AssetLoadError::Parse(format!("Invalid font: {}", e)))?;
+        Ok(Self { font })
+    }
+}
+
+fn point(p: ab_glyph::Point) -> Vec2 {
+    Vec2::new(p.x, p.y)
+}
+
+impl FontFace for Font {
+    fn units_per_em(&self) -> f32 {
+        self.font.units_per_em().unwrap_or(1000.0)
+    }
+
+    fn ascent(&self) -> f32 {
+        self.font.ascent_unscaled()
+    }
+
+    fn descent(&self) -> f32 {
+        -self.font.descent_unscaled()
+    }
+
+    fn line_gap(&self) -> f32 {
+        self.font.line_gap_unscaled()
+    }
+
+    fn glyph_id(&self, c: char) -> Option<u16> {
+        let id = self.font.glyph_id(c);
+        (id.0 != 0).then_some(id.0)
+    }
+
+    fn advance(&self, glyph: u16) -> f32 {
+        self.font.h_advance_unscaled(GlyphId(glyph))
+    }
+
+    fn kerning(&self, left: u16, right: u16) -> f32 {
+        self.font.kern_unscaled(GlyphId(left), GlyphId(right))
+    }
+
+    fn outline(&self, glyph: u16) -> Vec<OutlineCurve> {
+        let Some(outline) = self.font.outline(GlyphId(glyph)) else {
+            return Vec::new();
+        };
+        outline
+            .curves
+            .iter()
+            .map(|curve| match *curve {
+                ab_glyph::OutlineCurve::Line(a, b) => OutlineCurve::Line(point(a), point(b)),
+                ab_glyph::OutlineCurve::Quad(a, b, c) => {
+                    OutlineCurve::Quad(point(a), point(b), point(c))
+                }
+                ab_glyph::OutlineCurve::Cubic(a, b, c, d) => {
+                    OutlineCurve::Cubic(point(a), point(b), point(c), point(d))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Loads `.ttf`, `.otf` and `.ttc` files
+pub struct FontLoader;
+
+impl AssetLoader for FontLoader {
+    type Asset = Font;
+
+    fn extensions(&self) -> &[&str] {
+        &["ttf", "otf", "ttc"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        Font::from_bytes(bytes.to_vec())
+    }
+}
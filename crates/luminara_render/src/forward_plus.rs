@@ -1,8 +1,19 @@
 // Forward+ rendering pipeline implementation
-use crate::{DirectionalLight, GpuContext, PointLight, Shader};
+use crate::clustered_lighting::{ClusterLight, ClusterLightShape, ClusterRange, LightClusters};
+use crate::image_based_lighting::{EnvironmentResources, ProbeBlend, SceneEnvironments};
+use crate::material::{create_material_pipeline, MaterialRegistry, MaterialTextures};
+use crate::render_graph::{slots, PassSlots, RenderContext, RenderNode, ResourceDesc};
+use crate::render_target::{collect_camera_views, RenderTargets, TargetKey};
+use crate::shader_preprocessor::{ShaderComposer, ShaderDependency};
+use crate::{
+    Camera, DirectionalLight, GpuContext, PipelineCache, PointLight, PreparedMaterialDraw,
+    Projection, RenderError, Shader, SpotLight,
+};
+use luminara_asset::AssetServer;
 use luminara_core::shared_types::{Query, Res, ResMut, Resource};
-use luminara_math::Transform;
+use luminara_math::{Color, Mat4, Transform, Vec3};
 use std::mem;
+use wgpu::util::DeviceExt;
 
 /// Light data structures matching shader layout
 #[repr(C)]
@@ -14,47 +25,79 @@ struct DirectionalLightData {
     intensity: f32,
 }
 
+/// Point or spot light; point lights use cone cosines that accept every
+/// direction
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
-struct PointLightData {
+struct ClusteredLightData {
     position: [f32; 3],
     range: f32,
     color: [f32; 3],
     intensity: f32,
+    direction: [f32; 3],
+    spot_outer_cos: f32,
+    spot_inner_cos: f32,
+    _padding: [f32; 3],
+}
+
+impl From<&ClusterLight> for ClusteredLightData {
+    fn from(light: &ClusterLight) -> Self {
+        let (direction, outer_cos, inner_cos) = match light.shape {
+            ClusterLightShape::Point => (Vec3::ZERO, -2.0, -1.0),
+            ClusterLightShape::Spot {
+                direction,
+                inner_angle,
+                outer_angle,
+            } => (direction, outer_angle.cos(), inner_angle.cos()),
+        };
+        Self {
+            position: light.position.to_array(),
+            range: light.range,
+            color: [light.color.r, light.color.g, light.color.b],
+            intensity: light.intensity,
+            direction: direction.to_array(),
+            spot_outer_cos: outer_cos,
+            spot_inner_cos: inner_cos.max(outer_cos + 1e-4),
+            _padding: [0.0; 3],
+        }
+    }
 }
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 struct LightBuffer {
     directional_count: u32,
-    point_count: u32,
-    _padding: [u32; 2],
+    _padding: [u32; 3],
     directional_lights: [DirectionalLightData; 4],
-    point_lights: [PointLightData; 256],
 }
 
 impl Default for LightBuffer {
     fn default() -> Self {
         Self {
             directional_count: 0,
-            point_count: 0,
-            _padding: [0; 2],
+            _padding: [0; 3],
             directional_lights: [DirectionalLightData {
                 direction: [0.0; 3],
                 _padding1: 0.0,
                 color: [0.0; 3],
                 intensity: 0.0,
             }; 4],
-            point_lights: [PointLightData {
-                position: [0.0; 3],
-                range: 0.0,
-                color: [0.0; 3],
-                intensity: 0.0,
-            }; 256],
         }
     }
 }
 
+/// Cluster grid parameters matching shader layout
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ClusterParams {
+    view: [[f32; 4]; 4],
+    /// Tiles in X and Y, depth slices, unused
+    dimensions: [u32; 4],
+    screen_size: [f32; 2],
+    near: f32,
+    far: f32,
+}
+
 /// Camera uniform matching shader layout
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
@@ -91,6 +134,11 @@ pub struct ForwardPlusRenderer {
     pub camera_buffer: Option<wgpu::Buffer>,
     pub camera_bind_group: Option<wgpu::BindGroup>,
     pub light_buffer: Option<wgpu::Buffer>,
+    /// Point and spot lights indexed by the cluster lists
+    pub clustered_light_buffer: Option<wgpu::Buffer>,
+    pub cluster_params_buffer: Option<wgpu::Buffer>,
+    pub cluster_range_buffer: Option<wgpu::Buffer>,
+    pub cluster_index_buffer: Option<wgpu::Buffer>,
     pub light_bind_group: Option<wgpu::BindGroup>,
     pub bind_group_layouts: Option<BindGroupLayouts>,
 }
@@ -117,6 +165,10 @@ impl ForwardPlusRenderer {
             camera_buffer: None,
             camera_bind_group: None,
             light_buffer: None,
+            clustered_light_buffer: None,
+            cluster_params_buffer: None,
+            cluster_range_buffer: None,
+            cluster_index_buffer: None,
             light_bind_group: None,
             bind_group_layouts: None,
         }
@@ -209,18 +261,39 @@ impl ForwardPlusRenderer {
             ],
         });
 
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
         let lights_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Lights Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: true },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+            entries: &[
+                // Directional lights
+                storage_entry(0),
+                // Clustered point and spot lights
+                storage_entry(1),
+                // Cluster grid parameters
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                // Per-cluster ranges into the index list
+                storage_entry(3),
+                // Light indices
+                storage_entry(4),
+            ],
         });
 
         let model_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -320,34 +393,114 @@ impl ForwardPlusRenderer {
         self.camera_buffer = Some(camera_buffer);
         self.camera_bind_group = Some(camera_bind_group);
 
-        // Create light buffer
+        // Create light buffers
         let light_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Light Buffer"),
             size: mem::size_of::<LightBuffer>() as u64,
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
+        let cluster_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cluster Params Buffer"),
+            size: mem::size_of::<ClusterParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.light_buffer = Some(light_buffer);
+        self.cluster_params_buffer = Some(cluster_params_buffer);
+        self.reserve_light_buffers(device, 1, 1, 1);
+    }
+
+    /// Grow the clustered light storage to hold the given element counts,
+    /// rebuilding the bind group when a buffer is replaced
+    fn reserve_light_buffers(
+        &mut self,
+        device: &wgpu::Device,
+        lights: usize,
+        clusters: usize,
+        indices: usize,
+    ) {
+        let mut changed = self.light_bind_group.is_none();
+        for (buffer, size, label) in [
+            (
+                &mut self.clustered_light_buffer,
+                lights.max(1) * mem::size_of::<ClusteredLightData>(),
+                "Clustered Light Buffer",
+            ),
+            (
+                &mut self.cluster_range_buffer,
+                clusters.max(1) * mem::size_of::<ClusterRange>(),
+                "Cluster Range Buffer",
+            ),
+            (
+                &mut self.cluster_index_buffer,
+                indices.max(1) * mem::size_of::<u32>(),
+                "Cluster Index Buffer",
+            ),
+        ] {
+            if buffer.as_ref().is_some_and(|b| b.size() >= size as u64) {
+                continue;
+            }
+            // Leave headroom so a slowly growing light count does not
+            // reallocate every frame
+            *buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: (size as u64).next_power_of_two(),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            changed = true;
+        }
 
-        let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        if !changed {
+            return;
+        }
+        let (
+            Some(layouts),
+            Some(lights),
+            Some(clustered),
+            Some(params),
+            Some(ranges),
+            Some(indices),
+        ) = (
+            self.bind_group_layouts.as_ref(),
+            self.light_buffer.as_ref(),
+            self.clustered_light_buffer.as_ref(),
+            self.cluster_params_buffer.as_ref(),
+            self.cluster_range_buffer.as_ref(),
+            self.cluster_index_buffer.as_ref(),
+        )
+        else {
+            return;
+        };
+        let buffers = [lights, clustered, params, ranges, indices];
+        let entries: Vec<wgpu::BindGroupEntry> = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        self.light_bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Light Bind Group"),
             layout: &layouts.lights,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: light_buffer.as_entire_binding(),
-            }],
-        });
-
-        self.light_buffer = Some(light_buffer);
-        self.light_bind_group = Some(light_bind_group);
+            entries: &entries,
+        }));
     }
 }
 
-/// System to update light buffer
+/// System to update the light buffers: directional lights are shared by every
+/// fragment, point and spot lights are binned into the clusters of the first
+/// active camera
 pub fn update_lights_system(
-    renderer: ResMut<ForwardPlusRenderer>,
+    mut renderer: ResMut<ForwardPlusRenderer>,
     gpu: Res<GpuContext>,
+    mut clusters: ResMut<LightClusters>,
+    cameras: Query<(&Camera, &Transform)>,
     directional_lights: Query<(&DirectionalLight, &Transform)>,
     point_lights: Query<(&PointLight, &Transform)>,
+    spot_lights: Query<(&SpotLight, &Transform)>,
 ) {
     if renderer.light_buffer.is_none() {
         return;
@@ -368,22 +521,489 @@ pub fn update_lights_system(
         light_data.directional_count += 1;
     }
 
-    // Collect point lights
-    for (light, transform) in point_lights.iter().take(256) {
-        let position = transform.translation;
-        light_data.point_lights[light_data.point_count as usize] = PointLightData {
-            position: [position.x, position.y, position.z],
-            range: light.range,
-            color: [light.color.r, light.color.g, light.color.b],
-            intensity: light.intensity,
-        };
-        light_data.point_count += 1;
+    // Bin point and spot lights for the camera being shaded
+    let (width, height) = (gpu.surface_config.width, gpu.surface_config.height);
+    if let Some((camera, transform)) = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .min_by_key(|(camera, _)| camera.order)
+    {
+        let (Projection::Perspective { near, far, .. }
+        | Projection::Orthographic { near, far, .. }) = camera.projection;
+        let aspect = width.max(1) as f32 / height.max(1) as f32;
+        clusters.update_frustum(camera.projection_matrix(aspect), near, far);
+        let lights = point_lights
+            .iter()
+            .map(|(light, transform)| ClusterLight::from_point(light, transform))
+            .chain(
+                spot_lights
+                    .iter()
+                    .map(|(light, transform)| ClusterLight::from_spot(light, transform)),
+            )
+            .collect();
+        clusters.assign(camera.view_matrix(&transform.compute_matrix()), lights);
+    } else {
+        clusters.assign(Mat4::IDENTITY, Vec::new());
     }
 
-    // Update buffer
-    gpu.queue.write_buffer(
-        renderer.light_buffer.as_ref().unwrap(),
-        0,
-        bytemuck::cast_slice(&[light_data]),
+    let config = *clusters.config();
+    let params = ClusterParams {
+        view: clusters.view().to_cols_array_2d(),
+        dimensions: [config.tiles_x, config.tiles_y, config.slices, 0],
+        screen_size: [width as f32, height as f32],
+        near: clusters.near(),
+        far: clusters.far(),
+    };
+    let lights: Vec<ClusteredLightData> = clusters
+        .lights()
+        .iter()
+        .map(ClusteredLightData::from)
+        .collect();
+
+    renderer.reserve_light_buffers(
+        &gpu.device,
+        lights.len(),
+        clusters.ranges().len(),
+        clusters.indices().len(),
     );
+    let (Some(light_buffer), Some(clustered), Some(params_buffer), Some(ranges), Some(indices)) = (
+        renderer.light_buffer.as_ref(),
+        renderer.clustered_light_buffer.as_ref(),
+        renderer.cluster_params_buffer.as_ref(),
+        renderer.cluster_range_buffer.as_ref(),
+        renderer.cluster_index_buffer.as_ref(),
+    ) else {
+        return;
+    };
+    let queue = &gpu.queue;
+    queue.write_buffer(light_buffer, 0, bytemuck::cast_slice(&[light_data]));
+    queue.write_buffer(params_buffer, 0, bytemuck::cast_slice(&[params]));
+    if !lights.is_empty() {
+        queue.write_buffer(clustered, 0, bytemuck::cast_slice(&lights));
+    }
+    queue.write_buffer(ranges, 0, bytemuck::cast_slice(clusters.ranges()));
+    if !clusters.indices().is_empty() {
+        queue.write_buffer(indices, 0, bytemuck::cast_slice(clusters.indices()));
+    }
+}
+
+/// Main geometry pass: draws every registered material once per active
+/// camera, in camera order, into the HDR target or the camera's offscreen target
+pub struct ForwardPlusNode;
+
+impl ForwardPlusNode {
+    pub const NAME: &'static str = "forward";
+
+    /// Create the pipeline of `draw` unless it is cached, failed before, or
+    /// its shader is not loaded yet
+    fn pipeline(
+        cache: &mut PipelineCache,
+        composer: &mut ShaderComposer,
+        device: &wgpu::Device,
+        asset_server: &AssetServer,
+        draw: &PreparedMaterialDraw,
+    ) {
+        let id = draw.pipeline_id();
+        if cache.get_pipeline(&id).is_some() || cache.is_failed(&id) {
+            return;
+        }
+        let Some(shader) = asset_server.get(&draw.shader) else {
+            return;
+        };
+        composer.watch(asset_server, &draw.shader);
+        let source = match shader.source_code() {
+            Ok(source) => source,
+            Err(e) => {
+                log::error!("Failed to read {} shader: {}", draw.material_type, e);
+                return;
+            }
+        };
+
+        let mut dependencies = vec![ShaderDependency::Asset(draw.shader.id())];
+        let composed = draw.compose(composer, &source).and_then(|composed| {
+            dependencies.extend(
+                composed
+                    .imports
+                    .iter()
+                    .cloned()
+                    .map(ShaderDependency::Module),
+            );
+            composed.validate().map(|_| composed)
+        });
+        match composed {
+            Ok(composed) => {
+                let (pipeline, layouts) = create_material_pipeline(device, draw, &composed);
+                cache.insert_pipeline_with_dependencies(id, pipeline, layouts, dependencies);
+            }
+            Err(e) => {
+                log::error!("Invalid {} shader: {}", draw.material_type, e);
+                cache.mark_failed(id, dependencies);
+            }
+        }
+    }
+}
+
+impl RenderNode for ForwardPlusNode {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn declare(&self, slots: &mut PassSlots) {
+        slots
+            .create(
+                slots::HDR,
+                ResourceDesc::viewport_texture(slots::HDR_FORMAT),
+            )
+            .create(
+                slots::DEPTH,
+                ResourceDesc::viewport_texture(slots::DEPTH_FORMAT),
+            );
+    }
+
+    fn run<'a>(&self, context: &mut RenderContext<'a>) -> Result<(), RenderError> {
+        let resources = context.resources;
+        let (Some(world), Some(hdr_view), Some(depth_view)) = (
+            context.world,
+            resources.texture_view(slots::HDR),
+            resources.texture_view(slots::DEPTH),
+        ) else {
+            return Ok(());
+        };
+        let device = context.device;
+
+        let views = collect_camera_views(world, context.target_size);
+        let mut targets = world.get_resource_mut::<RenderTargets>();
+        if let Some(targets) = targets.as_mut() {
+            targets.prepare(device, &views);
+        }
+        let targets = targets.as_deref();
+
+        // Post-processing always reads the HDR target, so clear it even when
+        // no camera draws to the window
+        if !views.iter().any(|view| view.key == TargetKey::Window) {
+            context
+                .encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Main Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: hdr_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(to_wgpu_color(Color::rgba(
+                                0.1, 0.1, 0.15, 1.0,
+                            ))),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+        }
+
+        let (
+            Some(mut cache),
+            Some(mut composer),
+            Some(asset_server),
+            Some(registry),
+            Some(mut textures),
+            Some(mut environments),
+        ) = (
+            world.get_resource_mut::<PipelineCache>(),
+            world.get_resource_mut::<ShaderComposer>(),
+            world.get_resource::<AssetServer>(),
+            world.get_resource::<MaterialRegistry>(),
+            world.get_resource_mut::<MaterialTextures>(),
+            world.get_resource_mut::<EnvironmentResources>(),
+        )
+        else {
+            return Ok(());
+        };
+
+        // Reloaded shaders and modules rebuild only the permutations using them
+        for dependency in composer.sync(&asset_server) {
+            for label in cache.invalidate(&dependency) {
+                log::info!("Shader changed, rebuilding pipeline {}", label);
+            }
+        }
+
+        let mut draws = registry.collect(world, &asset_server);
+        // Opaque draws first, grouped by shader → texture → material
+        draws.sort_by(|a, b| {
+            (a.pipeline_key.is_transparent(), &a.sort_key)
+                .cmp(&(b.pipeline_key.is_transparent(), &b.sort_key))
+        });
+        for draw in &draws {
+            Self::pipeline(&mut cache, &mut composer, device, &asset_server, draw);
+        }
+        textures.prepare(device, context.queue, &asset_server, &draws);
+        let scene_environments = SceneEnvironments::collect(world);
+        environments.prepare(device, context.queue, &asset_server, &scene_environments);
+
+        // Draws inside the same reflection probes share environment bind
+        // groups
+        let mut blends: Vec<ProbeBlend> = Vec::new();
+
+        // Model and material bind groups are shared by every camera
+        let mut prepared = Vec::with_capacity(draws.len());
+        for draw in &draws {
+            let Some(pipeline) = cache.get_pipeline(&draw.pipeline_id()) else {
+                continue;
+            };
+            let Some(mesh) = asset_server.get(&draw.mesh) else {
+                continue;
+            };
+
+            let model_cols = draw.transform.compute_matrix().to_cols_array();
+            let model_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Model Buffer"),
+                contents: bytemuck::cast_slice(&model_cols),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let palette_buffer = draw
+                .joint_palette
+                .as_ref()
+                .map(|palette| palette.create_buffer(device));
+            let mut model_entries = vec![wgpu::BindGroupEntry {
+                binding: 0,
+                resource: model_buffer.as_entire_binding(),
+            }];
+            if let Some(palette_buffer) = &palette_buffer {
+                model_entries.push(wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: palette_buffer.as_entire_binding(),
+                });
+            }
+            let model_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Model Bind Group"),
+                layout: &pipeline.bind_group_layouts[1],
+                entries: &model_entries,
+            });
+
+            let material_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Material Buffer"),
+                contents: &draw.uniform,
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let Some((_, fallback_sampler)) = textures.get(None) else {
+                continue;
+            };
+            let mut entries = vec![wgpu::BindGroupEntry {
+                binding: 0,
+                resource: material_buffer.as_entire_binding(),
+            }];
+            for (index, binding) in draw.textures.iter().enumerate() {
+                // Camera render targets can be sampled like any other texture
+                let target_view = binding
+                    .texture
+                    .as_ref()
+                    .and_then(|handle| targets.and_then(|targets| targets.texture_view(handle)));
+                let (view, sampler) = match target_view {
+                    Some(view) => (view, fallback_sampler),
+                    None => textures.get(binding.texture.as_ref()).unwrap(),
+                };
+                let index = index as u32;
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 1 + index * 2,
+                    resource: wgpu::BindingResource::TextureView(view),
+                });
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 2 + index * 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                });
+            }
+            let material_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Material Bind Group"),
+                layout: &pipeline.bind_group_layouts[2],
+                entries: &entries,
+            });
+
+            let blend = ProbeBlend::new(draw.transform.translation, scene_environments.probes());
+            let blend_index = match blends.iter().position(|known| *known == blend) {
+                Some(index) => index,
+                None => {
+                    blends.push(blend);
+                    blends.len() - 1
+                }
+            };
+
+            prepared.push((
+                draw,
+                pipeline,
+                mesh,
+                model_bind_group,
+                material_bind_group,
+                blend_index,
+            ));
+        }
+
+        let mut cleared = Vec::new();
+        for view in &views {
+            let (color_view, depth_view) = match view.key {
+                TargetKey::Window => (hdr_view, depth_view),
+                key => match targets.and_then(|targets| targets.get(&key)) {
+                    Some(target) => (&target.hdr_view, &target.depth_view),
+                    None => continue,
+                },
+            };
+            // The first camera on a target clears it, later ones draw on top
+            let load = if cleared.contains(&view.key) {
+                wgpu::LoadOp::Load
+            } else {
+                cleared.push(view.key);
+                wgpu::LoadOp::Clear(to_wgpu_color(view.clear_color))
+            };
+
+            // Camera uniform - must match shader CameraUniform: mat4x4 + vec3 + padding = 80 bytes
+            let cam_pos = view.position;
+            let mut camera_data = [0u8; 80];
+            camera_data[0..64]
+                .copy_from_slice(bytemuck::cast_slice(&view.view_proj.to_cols_array()));
+            camera_data[64..76]
+                .copy_from_slice(bytemuck::cast_slice(&[cam_pos.x, cam_pos.y, cam_pos.z]));
+            let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Camera Buffer"),
+                contents: &camera_data,
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            // Every material pipeline uses the same camera layout
+            let camera_bind_group = prepared.first().map(|(_, pipeline, ..)| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Camera Bind Group"),
+                    layout: &pipeline.bind_group_layouts[0],
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: camera_buffer.as_entire_binding(),
+                    }],
+                })
+            });
+
+            let environment_bind_groups: Vec<wgpu::BindGroup> = prepared
+                .first()
+                .and_then(|(_, pipeline, ..)| {
+                    blends
+                        .iter()
+                        .map(|blend| {
+                            environments.bind_group(
+                                device,
+                                &pipeline.bind_group_layouts[3],
+                                scene_environments.light(view.entity),
+                                blend,
+                                scene_environments.probes(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let mut skybox = scene_environments
+                .skybox(view.entity)
+                .and_then(|skybox| environments.skybox(device, view, skybox));
+
+            // Transparent draws are sorted back to front for this camera
+            let mut order: Vec<usize> = (0..prepared.len())
+                .filter(|&index| view.sees(&prepared[index].0.layers))
+                .collect();
+            let distance = |index: usize| {
+                (prepared[index].0.transform.translation - view.position).length_squared()
+            };
+            order.sort_by(|&a, &b| {
+                match (
+                    prepared[a].0.pipeline_key.is_transparent(),
+                    prepared[b].0.pipeline_key.is_transparent(),
+                ) {
+                    (true, true) => distance(b).total_cmp(&distance(a)),
+                    (ta, tb) => ta.cmp(&tb).then(a.cmp(&b)),
+                }
+            });
+
+            let mut render_pass = context
+                .encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Main Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: color_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+
+            let (x, y, width, height) = view.viewport;
+            render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+            render_pass.set_scissor_rect(x, y, width, height);
+
+            if let Some(camera_bind_group) = &camera_bind_group {
+                render_pass.set_bind_group(0, camera_bind_group, &[]);
+            }
+
+            // The skybox fills what opaque draws left uncovered, behind
+            // transparent ones
+            for index in order.into_iter().map(Some).chain([None]) {
+                let transparent =
+                    index.is_none_or(|index| prepared[index].0.pipeline_key.is_transparent());
+                if transparent {
+                    if let Some((pipeline, bind_group)) = skybox.take() {
+                        render_pass.set_pipeline(pipeline);
+                        render_pass.set_bind_group(0, &bind_group, &[]);
+                        render_pass.draw(0..3, 0..1);
+                        if let Some(camera_bind_group) = &camera_bind_group {
+                            render_pass.set_bind_group(0, camera_bind_group, &[]);
+                        }
+                    }
+                }
+                let Some(index) = index else {
+                    break;
+                };
+
+                let (draw, pipeline, mesh, model_bind_group, material_bind_group, blend_index) =
+                    &prepared[index];
+                let Some(environment_bind_group) = environment_bind_groups.get(*blend_index) else {
+                    continue;
+                };
+                let vb_guard = mesh.vertex_buffer.read().unwrap();
+                let ib_guard = mesh.index_buffer.read().unwrap();
+                let sb_guard = mesh.skin_buffer.read().unwrap();
+                let (Some(vb), Some(ib)) = (vb_guard.as_ref(), ib_guard.as_ref()) else {
+                    continue;
+                };
+                if draw.joint_palette.is_some() {
+                    let Some(sb) = sb_guard.as_ref() else {
+                        continue;
+                    };
+                    render_pass.set_vertex_buffer(1, sb.slice(..));
+                }
+
+                render_pass.set_pipeline(&pipeline.pipeline);
+                render_pass.set_bind_group(1, model_bind_group, &[]);
+                render_pass.set_bind_group(2, material_bind_group, &[]);
+                render_pass.set_bind_group(3, environment_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, vb.slice(..));
+                render_pass.set_index_buffer(ib.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh.indices.len() as u32, 0, 0..1);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn to_wgpu_color(color: Color) -> wgpu::Color {
+    wgpu::Color {
+        r: color.r as f64,
+        g: color.g as f64,
+        b: color.b as f64,
+        a: color.a as f64,
+    }
 }
@@ -136,6 +136,13 @@ impl Frustum {
         let world_aabb = transform_aabb(aabb, transform);
         self.intersects_aabb(&world_aabb)
     }
+
+    /// Test if a sphere is visible (intersects or is inside frustum)
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance_to_point(center) >= -radius)
+    }
 }
 
 /// Transform AABB by matrix (conservative bounding box)
@@ -311,6 +318,16 @@ impl FrustumCullingSystem {
         self.needs_rebuild = true;
     }
 
+    /// Replace the entity data with local bounds and world transforms;
+    /// [`cull`](Self::cull) returns indices into this order
+    pub fn set_entities(&mut self, entities: impl IntoIterator<Item = (AABB, Mat4)>) {
+        self.entity_data = entities
+            .into_iter()
+            .map(|(aabb, transform)| EntityCullData { aabb, transform })
+            .collect();
+        self.needs_rebuild = true;
+    }
+
     /// Rebuild BVH if needed
     pub fn rebuild_bvh(&mut self) {
         if !self.needs_rebuild {
@@ -0,0 +1,753 @@
+// glTF import into scenes: nodes become scene entities, while meshes,
+// materials, textures, skins and animations become labeled sub-assets
+use crate::animation::{read_animation_clip, read_skeleton};
+use crate::camera::{Camera, Projection};
+use crate::components::{DirectionalLight, MorphWeights, PointLight, SpotLight};
+use crate::skinning::SkinningMethod;
+use crate::texture::{SamplerSettings, Texture, TextureData, TextureFormat};
+use crate::{AnimationClip, Mesh, PbrMaterial, Skeleton, SkinnedMesh};
+use luminara_asset::{Asset, AssetId, AssetLoadError, AssetLoader, AssetServer, Handle};
+use luminara_core::shared_types::{App, AppInterface, CoreStage, Plugin, Query, Resource, World};
+use luminara_core::system::ExclusiveMarker;
+use luminara_core::{Component, Entity};
+use luminara_math::glam::Affine2;
+use luminara_math::{Color, Quat, Transform, Vec2, Vec3};
+use luminara_scene::{EntityData, Scene, SceneMeta, TypeRegistry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Range given to point and spot lights that do not declare one
+const DEFAULT_LIGHT_RANGE: f32 = 100.0;
+
+/// Label of a sub-asset of a glTF file. The sub-asset is stored under the
+/// path `<file>#<label>`, e.g. `models/robot.glb#Mesh0/Primitive1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GltfAssetLabel {
+    /// A primitive of a glTF mesh, as a [`Mesh`]
+    Primitive { mesh: usize, primitive: usize },
+    /// A glTF material, as a [`PbrMaterial`]
+    Material(usize),
+    /// The [`PbrMaterial`] of primitives without a material
+    DefaultMaterial,
+    /// A glTF texture, as a [`Texture`]
+    Texture(usize),
+    /// A glTF skin, as a [`Skeleton`]
+    Skin(usize),
+    /// A glTF animation, as an [`AnimationClip`]
+    Animation(usize),
+}
+
+impl std::fmt::Display for GltfAssetLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GltfAssetLabel::Primitive { mesh, primitive } => {
+                write!(f, "Mesh{}/Primitive{}", mesh, primitive)
+            }
+            GltfAssetLabel::Material(index) => write!(f, "Material{}", index),
+            GltfAssetLabel::DefaultMaterial => write!(f, "DefaultMaterial"),
+            GltfAssetLabel::Texture(index) => write!(f, "Texture{}", index),
+            GltfAssetLabel::Skin(index) => write!(f, "Skin{}", index),
+            GltfAssetLabel::Animation(index) => write!(f, "Animation{}", index),
+        }
+    }
+}
+
+impl GltfAssetLabel {
+    /// Handle of this sub-asset of the glTF file at `path`, relative to the
+    /// asset directory. It resolves once the file has been loaded as a
+    /// [`Scene`].
+    pub fn handle<T: Asset>(&self, path: &str) -> Handle<T> {
+        Handle::new(self.id(path), 0)
+    }
+
+    /// Id of this sub-asset of the glTF file at `path`
+    pub fn id(&self, path: &str) -> AssetId {
+        AssetId::from_path(&format!("{}#{}", path, self))
+    }
+}
+
+/// A decoded sub-asset of a glTF file
+pub enum GltfSubAsset {
+    Mesh(Mesh),
+    Material(PbrMaterial),
+    Texture(Texture),
+    Skeleton(Skeleton),
+    Animation(AnimationClip),
+}
+
+/// Sub-assets decoded by [`GltfSceneLoader`], waiting to be stored by
+/// [`insert_gltf_sub_assets_system`]
+#[derive(Clone, Default)]
+pub struct GltfSubAssets(Arc<Mutex<Vec<(AssetId, GltfSubAsset)>>>);
+
+impl Resource for GltfSubAssets {}
+
+impl GltfSubAssets {
+    fn extend(&self, assets: Vec<(AssetId, GltfSubAsset)>) {
+        self.0.lock().unwrap().extend(assets);
+    }
+
+    /// Remove and return every queued sub-asset
+    pub fn drain(&self) -> Vec<(AssetId, GltfSubAsset)> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// Skin of an imported mesh entity. [`spawn_gltf_scene`] resolves it into a
+/// [`SkinnedMesh`] once the joint entities exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GltfSkin {
+    pub skeleton: Handle<Skeleton>,
+    /// glTF node index of each joint, which is also its [`EntityData::id`]
+    pub joints: Vec<u64>,
+}
+
+impl Component for GltfSkin {
+    fn type_name() -> &'static str {
+        "GltfSkin"
+    }
+}
+
+/// Loads `.gltf` and `.glb` files as [`Scene`] assets.
+///
+/// Every node of the default scene becomes an entity with its `Transform`,
+/// and with `Handle<Mesh>`/`Handle<PbrMaterial>`, [`Camera`], light,
+/// [`MorphWeights`] and [`GltfSkin`] components as present in the file.
+/// Meshes with several primitives get one child entity per primitive. The
+/// entity ids of the scene are the glTF node indices, so animation channels
+/// can be bound to the entities returned by [`spawn_gltf_scene`].
+///
+/// Cameras are imported inactive so they do not take over the window.
+pub struct GltfSceneLoader {
+    asset_dir: PathBuf,
+    sub_assets: GltfSubAssets,
+}
+
+impl GltfSceneLoader {
+    /// `asset_dir` is stripped from loaded paths to name the sub-assets the
+    /// same way as [`AssetServer::load`] paths
+    pub fn new(asset_dir: impl Into<PathBuf>, sub_assets: GltfSubAssets) -> Self {
+        Self {
+            asset_dir: asset_dir.into(),
+            sub_assets,
+        }
+    }
+}
+
+impl AssetLoader for GltfSceneLoader {
+    type Asset = Scene;
+
+    fn extensions(&self) -> &[&str] {
+        &["glb", "gltf"]
+    }
+
+    fn load(&self, bytes: &[u8], path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let asset_path = path
+            .strip_prefix(&self.asset_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let (scene, sub_assets) = import_gltf_scene(bytes, &asset_path, path.parent())?;
+        self.sub_assets.extend(sub_assets);
+        Ok(scene)
+    }
+}
+
+/// Decode a glTF file into a scene and its labeled sub-assets. `asset_path`
+/// names the sub-assets; external buffers and images are read relative to
+/// `base`.
+pub fn import_gltf_scene(
+    bytes: &[u8],
+    asset_path: &str,
+    base: Option<&Path>,
+) -> Result<(Scene, Vec<(AssetId, GltfSubAsset)>), AssetLoadError> {
+    let gltf::Gltf { document, blob } =
+        gltf::Gltf::from_slice(bytes).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+    let buffers = gltf::import_buffers(&document, base, blob)
+        .map_err(|e| AssetLoadError::Parse(format!("Failed to read glTF buffers: {}", e)))?;
+    let images = gltf::import_images(&document, base, &buffers)
+        .map_err(|e| AssetLoadError::Parse(format!("Failed to read glTF images: {}", e)))?;
+
+    let mut importer = GltfImporter {
+        path: asset_path,
+        sub_assets: Vec::new(),
+    };
+    importer.read_assets(&document, &buffers, &images)?;
+
+    let roots: Vec<gltf::Node> = match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => scene.nodes().collect(),
+        // Without scenes, every node that is nobody's child is a root
+        None => document
+            .nodes()
+            .filter(|node| {
+                !document
+                    .nodes()
+                    .any(|parent| parent.children().any(|child| child.index() == node.index()))
+            })
+            .collect(),
+    };
+    let entities = roots
+        .into_iter()
+        .map(|node| importer.read_node(&node, None))
+        .collect();
+
+    let name = Path::new(asset_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unnamed".to_string());
+    let scene = Scene {
+        meta: SceneMeta {
+            name,
+            description: format!("Imported from {}", asset_path),
+            version: "1.0".to_string(),
+            tags: vec![],
+        },
+        entities,
+    };
+    Ok((scene, importer.sub_assets))
+}
+
+struct GltfImporter<'a> {
+    path: &'a str,
+    sub_assets: Vec<(AssetId, GltfSubAsset)>,
+}
+
+impl GltfImporter<'_> {
+    fn handle<T: Asset>(&self, label: GltfAssetLabel) -> Handle<T> {
+        label.handle(self.path)
+    }
+
+    fn push(&mut self, label: GltfAssetLabel, asset: GltfSubAsset) {
+        self.sub_assets.push((label.id(self.path), asset));
+    }
+
+    fn read_assets(
+        &mut self,
+        document: &gltf::Document,
+        buffers: &[gltf::buffer::Data],
+        images: &[gltf::image::Data],
+    ) -> Result<(), AssetLoadError> {
+        for texture in document.textures() {
+            if let Some(asset) = read_texture(&texture, images) {
+                self.push(
+                    GltfAssetLabel::Texture(texture.index()),
+                    GltfSubAsset::Texture(asset),
+                );
+            }
+        }
+
+        for material in document.materials() {
+            let Some(index) = material.index() else {
+                continue;
+            };
+            let asset = self.read_material(&material);
+            self.push(GltfAssetLabel::Material(index), GltfSubAsset::Material(asset));
+        }
+
+        let mut has_default_material = false;
+        for mesh in document.meshes() {
+            for primitive in mesh.primitives() {
+                // Primitives without a material report glTF's default one
+                if primitive.material().index().is_none() && !has_default_material {
+                    has_default_material = true;
+                    let asset = self.read_material(&primitive.material());
+                    self.push(
+                        GltfAssetLabel::DefaultMaterial,
+                        GltfSubAsset::Material(asset),
+                    );
+                }
+
+                // UVs are baked with the base color texture's transform, the
+                // only one `PbrMaterial` could not express otherwise
+                let (uv_set, uv_transform) = primitive
+                    .material()
+                    .pbr_metallic_roughness()
+                    .base_color_texture()
+                    .map(|info| match info.texture_transform() {
+                        Some(transform) => (
+                            transform.tex_coord().unwrap_or(info.tex_coord()),
+                            Some(Affine2::from_scale_angle_translation(
+                                Vec2::from(transform.scale()),
+                                -transform.rotation(),
+                                Vec2::from(transform.offset()),
+                            )),
+                        ),
+                        None => (info.tex_coord(), None),
+                    })
+                    .unwrap_or((0, None));
+                let asset = Mesh::from_gltf_primitive(&primitive, buffers, uv_set, uv_transform)
+                    .map_err(|e| {
+                        AssetLoadError::Parse(format!(
+                            "Failed to read primitive {} of mesh {}: {}",
+                            primitive.index(),
+                            mesh.index(),
+                            e
+                        ))
+                    })?;
+                self.push(
+                    GltfAssetLabel::Primitive {
+                        mesh: mesh.index(),
+                        primitive: primitive.index(),
+                    },
+                    GltfSubAsset::Mesh(asset),
+                );
+            }
+        }
+
+        let get_buffer =
+            |buffer: gltf::Buffer| buffers.get(buffer.index()).map(|data| &data.0[..]);
+        for skin in document.skins() {
+            let skeleton = read_skeleton(&skin, get_buffer);
+            self.push(GltfAssetLabel::Skin(skin.index()), GltfSubAsset::Skeleton(skeleton));
+        }
+        for animation in document.animations() {
+            let clip = read_animation_clip(&animation, get_buffer);
+            self.push(
+                GltfAssetLabel::Animation(animation.index()),
+                GltfSubAsset::Animation(clip),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn read_material(&self, material: &gltf::Material) -> PbrMaterial {
+        let pbr = material.pbr_metallic_roughness();
+        let texture = |texture: gltf::Texture| self.handle(GltfAssetLabel::Texture(texture.index()));
+        let [r, g, b] = material.emissive_factor();
+        let strength = material.emissive_strength().unwrap_or(1.0);
+
+        PbrMaterial {
+            albedo: Color::from(pbr.base_color_factor()),
+            albedo_texture: pbr.base_color_texture().map(|info| texture(info.texture())),
+            normal_texture: material.normal_texture().map(|info| texture(info.texture())),
+            metallic: pbr.metallic_factor(),
+            roughness: pbr.roughness_factor(),
+            metallic_roughness_texture: pbr
+                .metallic_roughness_texture()
+                .map(|info| texture(info.texture())),
+            emissive: Color::rgb(r * strength, g * strength, b * strength),
+        }
+    }
+
+    fn read_node(&self, node: &gltf::Node, parent: Option<u64>) -> EntityData {
+        let id = node.index() as u64;
+        let name = node
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Node{}", node.index()));
+
+        let (t, r, s) = node.transform().decomposed();
+        let mut components = HashMap::new();
+        insert_component(
+            &mut components,
+            &Transform {
+                translation: Vec3::from(t),
+                rotation: Quat::from_array(r),
+                scale: Vec3::from(s),
+            },
+        );
+
+        if let Some(camera) = node.camera() {
+            insert_component(&mut components, &read_camera(&camera));
+        }
+        if let Some(light) = node.light() {
+            insert_light(&mut components, &light);
+        }
+
+        let mut children: Vec<EntityData> = node
+            .children()
+            .map(|child| self.read_node(&child, Some(id)))
+            .collect();
+
+        if let Some(mesh) = node.mesh() {
+            let mut primitives: Vec<_> = mesh
+                .primitives()
+                .map(|primitive| self.primitive_components(node, &mesh, &primitive))
+                .collect();
+            if primitives.len() == 1 {
+                components.extend(primitives.remove(0));
+            } else {
+                for (index, mut primitive) in primitives.into_iter().enumerate() {
+                    insert_component(&mut primitive, &Transform::IDENTITY);
+                    children.push(EntityData {
+                        name: format!("{}/Primitive{}", name, index),
+                        id: None,
+                        parent: Some(id),
+                        components: primitive,
+                        children: vec![],
+                        tags: vec![],
+                    });
+                }
+            }
+        }
+
+        EntityData {
+            name,
+            id: Some(id),
+            parent,
+            components,
+            children,
+            tags: vec![],
+        }
+    }
+
+    fn primitive_components(
+        &self,
+        node: &gltf::Node,
+        mesh: &gltf::Mesh,
+        primitive: &gltf::Primitive,
+    ) -> HashMap<String, serde_json::Value> {
+        let mut components = HashMap::new();
+
+        let mesh_handle: Handle<Mesh> = self.handle(GltfAssetLabel::Primitive {
+            mesh: mesh.index(),
+            primitive: primitive.index(),
+        });
+        let material_handle: Handle<PbrMaterial> = self.handle(
+            primitive
+                .material()
+                .index()
+                .map_or(GltfAssetLabel::DefaultMaterial, GltfAssetLabel::Material),
+        );
+        insert_component(&mut components, &mesh_handle);
+        insert_component(&mut components, &material_handle);
+
+        let target_count = primitive.morph_targets().count();
+        if target_count > 0 {
+            let weights = node
+                .weights()
+                .or(mesh.weights())
+                .map(<[f32]>::to_vec)
+                .unwrap_or_else(|| vec![0.0; target_count]);
+            insert_component(&mut components, &MorphWeights { weights });
+        }
+
+        if let Some(skin) = node.skin() {
+            insert_component(
+                &mut components,
+                &GltfSkin {
+                    skeleton: self.handle(GltfAssetLabel::Skin(skin.index())),
+                    joints: skin.joints().map(|joint| joint.index() as u64).collect(),
+                },
+            );
+        }
+
+        components
+    }
+}
+
+fn insert_component<T: Component + Serialize>(
+    components: &mut HashMap<String, serde_json::Value>,
+    component: &T,
+) {
+    if let Ok(value) = serde_json::to_value(component) {
+        components.insert(T::type_name().to_string(), value);
+    }
+}
+
+fn read_camera(camera: &gltf::Camera) -> Camera {
+    let projection = match camera.projection() {
+        gltf::camera::Projection::Perspective(perspective) => Projection::Perspective {
+            fov: perspective.yfov().to_degrees(),
+            near: perspective.znear(),
+            far: perspective.zfar().unwrap_or(1000.0),
+        },
+        // `ymag` is half the height of the view
+        gltf::camera::Projection::Orthographic(orthographic) => Projection::Orthographic {
+            size: orthographic.ymag() * 2.0,
+            near: orthographic.znear(),
+            far: orthographic.zfar(),
+        },
+    };
+    Camera {
+        projection,
+        is_active: false,
+        ..Default::default()
+    }
+}
+
+fn insert_light(
+    components: &mut HashMap<String, serde_json::Value>,
+    light: &gltf::khr_lights_punctual::Light,
+) {
+    use gltf::khr_lights_punctual::Kind;
+
+    let [r, g, b] = light.color();
+    let color = Color::rgb(r, g, b);
+    let intensity = light.intensity();
+    let range = light.range().unwrap_or(DEFAULT_LIGHT_RANGE);
+    match light.kind() {
+        Kind::Directional => insert_component(
+            components,
+            &DirectionalLight {
+                color,
+                intensity,
+                cast_shadows: true,
+                shadow_cascade_count: 4,
+            },
+        ),
+        Kind::Point => insert_component(
+            components,
+            &PointLight {
+                color,
+                intensity,
+                range,
+                cast_shadows: false,
+            },
+        ),
+        Kind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => insert_component(
+            components,
+            &SpotLight {
+                color,
+                intensity,
+                range,
+                inner_angle: inner_cone_angle,
+                outer_angle: outer_cone_angle,
+                cast_shadows: false,
+            },
+        ),
+    }
+}
+
+fn read_texture(texture: &gltf::Texture, images: &[gltf::image::Data]) -> Option<Texture> {
+    let image = images.get(texture.source().index())?;
+    let data = rgba8_pixels(image);
+    let mut asset = Texture::new(TextureData {
+        width: image.width,
+        height: image.height,
+        data,
+        format: TextureFormat::Rgba8,
+    });
+    asset.sampler_settings = sampler_settings(&texture.sampler());
+    Some(asset)
+}
+
+/// Convert decoded glTF image pixels to RGBA8
+fn rgba8_pixels(image: &gltf::image::Data) -> Vec<u8> {
+    use gltf::image::Format;
+
+    let unorm16 = |bytes: &[u8]| bytes[1];
+    let unit_float = |bytes: &[u8]| {
+        let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+    let pixels = &image.pixels;
+    match image.format {
+        Format::R8 => pixels.iter().flat_map(|&l| [l, l, l, 255]).collect(),
+        Format::R8G8 => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        Format::R8G8B8 => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        Format::R8G8B8A8 => pixels.clone(),
+        Format::R16 => pixels
+            .chunks_exact(2)
+            .flat_map(|p| {
+                let l = unorm16(p);
+                [l, l, l, 255]
+            })
+            .collect(),
+        Format::R16G16 => pixels
+            .chunks_exact(4)
+            .flat_map(|p| {
+                let l = unorm16(&p[0..2]);
+                [l, l, l, unorm16(&p[2..4])]
+            })
+            .collect(),
+        Format::R16G16B16 => pixels
+            .chunks_exact(6)
+            .flat_map(|p| [unorm16(&p[0..2]), unorm16(&p[2..4]), unorm16(&p[4..6]), 255])
+            .collect(),
+        Format::R16G16B16A16 => pixels.chunks_exact(2).map(unorm16).collect(),
+        Format::R32G32B32FLOAT => pixels
+            .chunks_exact(12)
+            .flat_map(|p| {
+                [
+                    unit_float(&p[0..4]),
+                    unit_float(&p[4..8]),
+                    unit_float(&p[8..12]),
+                    255,
+                ]
+            })
+            .collect(),
+        Format::R32G32B32A32FLOAT => pixels.chunks_exact(4).map(unit_float).collect(),
+    }
+}
+
+fn sampler_settings(sampler: &gltf::texture::Sampler) -> SamplerSettings {
+    use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+    let address_mode = |mode| match mode {
+        WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+        WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+    };
+    let (min_filter, mipmap_filter) = match sampler.min_filter() {
+        Some(MinFilter::Nearest) | Some(MinFilter::NearestMipmapNearest) => {
+            (wgpu::FilterMode::Nearest, wgpu::FilterMode::Nearest)
+        }
+        Some(MinFilter::NearestMipmapLinear) => {
+            (wgpu::FilterMode::Nearest, wgpu::FilterMode::Linear)
+        }
+        Some(MinFilter::LinearMipmapLinear) => (wgpu::FilterMode::Linear, wgpu::FilterMode::Linear),
+        Some(MinFilter::Linear) | Some(MinFilter::LinearMipmapNearest) | None => {
+            (wgpu::FilterMode::Linear, wgpu::FilterMode::Nearest)
+        }
+    };
+
+    SamplerSettings {
+        address_mode_u: address_mode(sampler.wrap_s()),
+        address_mode_v: address_mode(sampler.wrap_t()),
+        mag_filter: match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => wgpu::FilterMode::Nearest,
+            Some(MagFilter::Linear) | None => wgpu::FilterMode::Linear,
+        },
+        min_filter,
+        mipmap_filter,
+    }
+}
+
+/// Store the sub-assets decoded by glTF loads in the [`AssetServer`]
+pub fn insert_gltf_sub_assets_system(world: &mut World) {
+    let Some(queue) = world.get_resource::<GltfSubAssets>().map(|q| q.clone()) else {
+        return;
+    };
+    let sub_assets = queue.drain();
+    if sub_assets.is_empty() {
+        return;
+    }
+    let Some(asset_server) = world.get_resource::<AssetServer>() else {
+        return;
+    };
+    for (id, sub_asset) in sub_assets {
+        match sub_asset {
+            GltfSubAsset::Mesh(mesh) => {
+                asset_server.insert(id, mesh);
+            }
+            GltfSubAsset::Material(material) => {
+                asset_server.insert(id, material);
+            }
+            GltfSubAsset::Texture(texture) => {
+                asset_server.insert(id, texture);
+            }
+            GltfSubAsset::Skeleton(skeleton) => {
+                asset_server.insert(id, skeleton);
+            }
+            GltfSubAsset::Animation(clip) => {
+                asset_server.insert(id, clip);
+            }
+        }
+    }
+}
+
+/// Spawn an imported glTF scene and wire the joints of its skins.
+///
+/// Returns the entity spawned for each glTF node index.
+pub fn spawn_gltf_scene(world: &mut World, scene: &Scene) -> HashMap<u64, Entity> {
+    // Skeletons must be in the asset server before skins can be resolved
+    insert_gltf_sub_assets_system(world);
+    let nodes = scene.spawn_into_with_ids(world);
+
+    let unbound: Vec<(Entity, GltfSkin, Handle<Mesh>)> =
+        Query::<(Entity, &GltfSkin, &Handle<Mesh>)>::new(&*world)
+            .iter()
+            .filter(|(entity, _, _)| world.get_component::<SkinnedMesh>(*entity).is_none())
+            .map(|(entity, skin, mesh)| (entity, skin.clone(), mesh.clone()))
+            .collect();
+
+    for (entity, skin, mesh) in unbound {
+        let Some(joints) = skin
+            .joints
+            .iter()
+            .map(|joint| nodes.get(joint).copied())
+            .collect::<Option<Vec<Entity>>>()
+        else {
+            continue;
+        };
+        let skeleton = world
+            .get_resource::<AssetServer>()
+            .and_then(|asset_server| asset_server.get(&skin.skeleton));
+        let inverse_bind_matrices = skeleton
+            .as_ref()
+            .map(|skeleton| {
+                skeleton
+                    .bones
+                    .iter()
+                    .map(|bone| bone.inverse_bind_matrix)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let _ = world.add_component(
+            entity,
+            SkinnedMesh {
+                mesh,
+                joints,
+                inverse_bind_matrices,
+                skeleton: skeleton.map(|skeleton| (*skeleton).clone()),
+                method: SkinningMethod::default(),
+            },
+        );
+    }
+
+    nodes
+}
+
+/// Loads glTF files as [`Scene`] assets and registers the components they
+/// contain for scene deserialization
+pub struct GltfPlugin;
+
+impl Plugin for GltfPlugin {
+    fn name(&self) -> &str {
+        "GltfPlugin"
+    }
+
+    fn build(&self, app: &mut App) {
+        if app.world.get_resource::<GltfSubAssets>().is_none() {
+            app.insert_resource(GltfSubAssets::default());
+        }
+        if app.world.get_resource::<TypeRegistry>().is_none() {
+            app.insert_resource(TypeRegistry::new());
+        }
+        if let Some(mut registry) = app.world.get_resource_mut::<TypeRegistry>() {
+            register_gltf_components(&mut registry);
+        }
+
+        app.add_system::<ExclusiveMarker>(CoreStage::Startup, register_gltf_loader);
+        app.add_system::<ExclusiveMarker>(CoreStage::PreUpdate, insert_gltf_sub_assets_system);
+    }
+}
+
+/// Register the components of imported glTF scenes for deserialization
+pub fn register_gltf_components(registry: &mut TypeRegistry) {
+    registry.register::<Handle<Mesh>>();
+    registry.register::<Handle<PbrMaterial>>();
+    registry.register::<Camera>();
+    registry.register::<DirectionalLight>();
+    registry.register::<PointLight>();
+    registry.register::<SpotLight>();
+    registry.register::<MorphWeights>();
+    registry.register::<GltfSkin>();
+}
+
+fn register_gltf_loader(world: &mut World) {
+    let Some(queue) = world.get_resource::<GltfSubAssets>().map(|q| q.clone()) else {
+        return;
+    };
+    if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
+        let asset_dir = asset_server.asset_dir().to_path_buf();
+        asset_server.register_loader(GltfSceneLoader::new(asset_dir, queue));
+    }
+}
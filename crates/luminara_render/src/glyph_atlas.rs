@@ -0,0 +1,613 @@
+//! Glyph rasterization and the dynamic glyph atlas.
+//!
+//! Glyphs are rasterized from their outlines into plain coverage masks,
+//! single-channel signed distance fields or multi-channel distance fields
+//! (MSDF), then shelf-packed into an atlas that grows on demand. Everything
+//! here runs on the CPU; [`GlyphAtlas::upload`] mirrors the pixels to a
+//! texture when a GPU is present.
+
+use crate::font::{FontFace, OutlineCurve};
+use luminara_asset::AssetId;
+use luminara_core::Resource;
+use luminara_math::Vec2;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// How glyphs are stored in the atlas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GlyphRasterMode {
+    /// Antialiased coverage at the exact pixel size; sharpest for UI text
+    #[default]
+    Coverage,
+    /// Signed distance field rasterized once and scaled freely
+    Sdf,
+    /// Multi-channel distance field that keeps corners sharp when magnified
+    Msdf,
+}
+
+impl GlyphRasterMode {
+    /// Bytes per atlas pixel
+    pub fn channels(self) -> u32 {
+        match self {
+            GlyphRasterMode::Msdf => 4,
+            _ => 1,
+        }
+    }
+
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            GlyphRasterMode::Msdf => wgpu::TextureFormat::Rgba8Unorm,
+            _ => wgpu::TextureFormat::R8Unorm,
+        }
+    }
+
+    /// Shader def selecting the matching decode in `luminara::text`
+    pub fn shader_def(self) -> Option<&'static str> {
+        match self {
+            GlyphRasterMode::Coverage => None,
+            GlyphRasterMode::Sdf => Some("TEXT_SDF"),
+            GlyphRasterMode::Msdf => Some("TEXT_MSDF"),
+        }
+    }
+}
+
+/// A rasterized glyph, placed relative to the pen position on the baseline
+/// (Y down)
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphBitmap {
+    pub width: u32,
+    pub height: u32,
+    /// Top-left corner relative to the pen, in pixels
+    pub offset: Vec2,
+    pub channels: u32,
+    pub data: Vec<u8>,
+}
+
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const WHITE: u8 = RED | GREEN | BLUE;
+const EDGE_COLORS: [u8; 3] = [GREEN | BLUE, RED | BLUE, RED | GREEN];
+
+/// Flattened outline segment in pixel space
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    a: Vec2,
+    b: Vec2,
+    /// MSDF channels this segment contributes to
+    color: u8,
+}
+
+/// Flatten `curves` into pixel-space segments (Y down) and color the edges
+/// of each contour for MSDF
+fn flatten(curves: &[OutlineCurve], scale: f32) -> Vec<Segment> {
+    // Split into contours where a curve does not continue the previous one
+    let mut contours: Vec<Vec<OutlineCurve>> = Vec::new();
+    for curve in curves {
+        match contours.last_mut() {
+            Some(contour)
+                if contour
+                    .last()
+                    .is_some_and(|last| last.end().distance(curve.start()) < 1e-3) =>
+            {
+                contour.push(*curve)
+            }
+            _ => contours.push(vec![*curve]),
+        }
+    }
+
+    let to_pixels = |p: Vec2| Vec2::new(p.x * scale, -p.y * scale);
+    let mut segments = Vec::new();
+    for contour in &contours {
+        let colors = color_edges(contour);
+        for (curve, color) in contour.iter().zip(colors) {
+            let steps = match curve {
+                OutlineCurve::Line(..) => 1,
+                OutlineCurve::Quad(..) => 8,
+                OutlineCurve::Cubic(..) => 12,
+            };
+            let mut previous = to_pixels(curve.start());
+            for step in 1..=steps {
+                let next = to_pixels(curve.point(step as f32 / steps as f32));
+                segments.push(Segment {
+                    a: previous,
+                    b: next,
+                    color,
+                });
+                previous = next;
+            }
+        }
+    }
+    segments
+}
+
+/// Assign MSDF channels to the curves of a contour so that the curves meeting
+/// at each corner differ in at least one channel
+fn color_edges(contour: &[OutlineCurve]) -> Vec<u8> {
+    let direction = |from: Vec2, to: Vec2| (to - from).normalize_or_zero();
+    let start_direction = |curve: &OutlineCurve| direction(curve.start(), curve.point(0.05));
+    let end_direction = |curve: &OutlineCurve| direction(curve.point(0.95), curve.end());
+
+    // Curve indices that begin at a corner
+    let corners: Vec<usize> = (0..contour.len())
+        .filter(|&i| {
+            let previous = &contour[(i + contour.len() - 1) % contour.len()];
+            let a = end_direction(previous);
+            let b = start_direction(&contour[i]);
+            a.dot(b) <= 0.0 || a.perp_dot(b).abs() > 3f32.sin()
+        })
+        .collect();
+
+    if corners.len() < 2 {
+        return vec![WHITE; contour.len()];
+    }
+
+    // Cycle through the colors per run of curves between corners, keeping
+    // the last run distinct from the first
+    let mut colors = vec![WHITE; contour.len()];
+    let runs = corners.len();
+    for (run, &start) in corners.iter().enumerate() {
+        let mut color = EDGE_COLORS[run % 3];
+        if run == runs - 1 && run % 3 == 0 {
+            color = EDGE_COLORS[1];
+        }
+        let end = corners[(run + 1) % runs];
+        let mut i = start;
+        loop {
+            colors[i] = color;
+            i = (i + 1) % contour.len();
+            if i == end {
+                break;
+            }
+        }
+    }
+    colors
+}
+
+fn closest_point(segment: &Segment, p: Vec2) -> Vec2 {
+    let ab = segment.b - segment.a;
+    let length_squared = ab.length_squared();
+    if length_squared <= f32::EPSILON {
+        return segment.a;
+    }
+    let t = ((p - segment.a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    segment.a + ab * t
+}
+
+/// Nonzero winding test
+fn is_inside(segments: &[Segment], p: Vec2) -> bool {
+    let mut winding = 0;
+    for segment in segments {
+        let (a, b) = (segment.a, segment.b);
+        if (a.y <= p.y) != (b.y <= p.y) {
+            let x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if x > p.x {
+                winding += if b.y > a.y { 1 } else { -1 };
+            }
+        }
+    }
+    winding != 0
+}
+
+/// Rasterize an outline at `scale` pixels per font unit. `range` is the
+/// distance in pixels covered by the field on each side of the edge in the
+/// SDF modes. Returns `None` for blank glyphs.
+pub fn rasterize_outline(
+    curves: &[OutlineCurve],
+    scale: f32,
+    mode: GlyphRasterMode,
+    range: f32,
+) -> Option<GlyphBitmap> {
+    let segments = flatten(curves, scale);
+    if segments.is_empty() {
+        return None;
+    }
+
+    let (mut min, mut max) = (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN));
+    for segment in &segments {
+        min = min.min(segment.a).min(segment.b);
+        max = max.max(segment.a).max(segment.b);
+    }
+    let padding = match mode {
+        GlyphRasterMode::Coverage => 1.0,
+        _ => range.ceil().max(1.0),
+    };
+    let origin = min.floor() - Vec2::splat(padding);
+    let width = ((max.x.ceil() - min.x.floor()) + padding * 2.0) as u32;
+    let height = ((max.y.ceil() - min.y.floor()) + padding * 2.0) as u32;
+
+    // Interior is on the left of segments when the outline's area is positive
+    let area: f32 = segments.iter().map(|s| s.a.perp_dot(s.b)).sum();
+    let orientation = if area >= 0.0 { 1.0 } else { -1.0 };
+
+    let channels = mode.channels();
+    let mut data = vec![0u8; (width * height * channels) as usize];
+    let encode =
+        |distance: f32| ((0.5 + distance / (2.0 * range)).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    for y in 0..height {
+        for x in 0..width {
+            let p = origin + Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let inside = is_inside(&segments, p);
+            let sign = if inside { 1.0 } else { -1.0 };
+            let index = ((y * width + x) * channels) as usize;
+
+            if mode != GlyphRasterMode::Msdf {
+                let distance = segments
+                    .iter()
+                    .map(|s| closest_point(s, p).distance(p))
+                    .fold(f32::MAX, f32::min);
+                data[index] = match mode {
+                    GlyphRasterMode::Coverage => {
+                        ((0.5 + sign * distance).clamp(0.0, 1.0) * 255.0).round() as u8
+                    }
+                    _ => encode(sign * distance),
+                };
+                continue;
+            }
+
+            // Per channel: signed distance to the nearest segment of that
+            // color, preferring the most perpendicular one at shared corners
+            let mut channel_distances = [0.0f32; 3];
+            let mut true_distance = f32::MAX;
+            for (channel, mask) in [RED, GREEN, BLUE].into_iter().enumerate() {
+                let mut best = (f32::MAX, 0.0f32, 0.0f32);
+                for segment in segments.iter().filter(|s| s.color & mask != 0) {
+                    let closest = closest_point(segment, p);
+                    let offset = p - closest;
+                    let distance = offset.length();
+                    let direction = (segment.b - segment.a).normalize_or_zero();
+                    let side = direction.perp_dot(offset);
+                    let orthogonality = if distance > 0.0 {
+                        (side / distance).abs()
+                    } else {
+                        1.0
+                    };
+                    if distance < best.0 - 1e-4
+                        || (distance < best.0 + 1e-4 && orthogonality > best.2)
+                    {
+                        best = (distance, side, orthogonality);
+                    }
+                }
+                true_distance = true_distance.min(best.0);
+                let channel_sign = if best.1 * orientation >= 0.0 {
+                    1.0
+                } else {
+                    -1.0
+                };
+                channel_distances[channel] = channel_sign * best.0;
+            }
+
+            // Where the channels disagree with the true inside test, fall
+            // back to the plain distance so no speckles appear
+            let median = median3(channel_distances);
+            if (median >= 0.0) != inside {
+                channel_distances = [sign * true_distance; 3];
+            }
+            for channel in 0..3 {
+                data[index + channel] = encode(channel_distances[channel]);
+            }
+            data[index + 3] = encode(sign * true_distance);
+        }
+    }
+
+    Some(GlyphBitmap {
+        width,
+        height,
+        offset: origin,
+        channels,
+        data,
+    })
+}
+
+fn median3([a, b, c]: [f32; 3]) -> f32 {
+    a.min(b).max(a.max(b).min(c))
+}
+
+/// Identifies a glyph raster in an atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: AssetId,
+    pub glyph: u16,
+    /// Raster size in pixels
+    pub size: u32,
+}
+
+/// Where a glyph lives in the atlas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasGlyph {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Top-left corner relative to the pen at the raster size
+    pub offset: Vec2,
+    /// Pixel size the glyph was rasterized at
+    pub raster_size: f32,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GlyphAtlasError {
+    #[error("Glyph atlas is full at {0}x{0}")]
+    Full(u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32,
+}
+
+/// Shelf-packed atlas of rasterized glyphs that grows up to `max_size`
+pub struct GlyphAtlas {
+    mode: GlyphRasterMode,
+    width: u32,
+    height: u32,
+    max_size: u32,
+    /// Raster size for the SDF modes, which scale to any display size
+    pub sdf_size: f32,
+    /// Distance in raster pixels covered on each side of an edge
+    pub sdf_range: f32,
+    pixels: Vec<u8>,
+    entries: HashMap<GlyphKey, Option<AtlasGlyph>>,
+    shelves: Vec<Shelf>,
+    generation: u32,
+    dirty: bool,
+    texture: Option<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+impl Resource for GlyphAtlas {}
+
+/// Empty pixels between packed glyphs so filtering does not bleed
+const GLYPH_PADDING: u32 = 1;
+
+impl GlyphAtlas {
+    pub fn new(mode: GlyphRasterMode) -> Self {
+        Self::with_size(mode, 256, 4096)
+    }
+
+    /// Square atlas of `size` pixels that doubles up to `max_size`
+    pub fn with_size(mode: GlyphRasterMode, size: u32, max_size: u32) -> Self {
+        Self {
+            mode,
+            width: size,
+            height: size,
+            max_size: max_size.max(size),
+            sdf_size: 32.0,
+            sdf_range: 4.0,
+            pixels: vec![0; (size * size * mode.channels()) as usize],
+            entries: HashMap::new(),
+            shelves: Vec::new(),
+            generation: 0,
+            dirty: true,
+            texture: None,
+        }
+    }
+
+    pub fn mode(&self) -> GlyphRasterMode {
+        self.mode
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Glyphs rasterized so far, including blank ones
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Changes whenever existing UVs become invalid (growth or clearing)
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Pixel size glyphs of `size` are rasterized at
+    pub fn raster_size(&self, size: f32) -> f32 {
+        match self.mode {
+            GlyphRasterMode::Coverage => size.round().max(1.0),
+            _ => self.sdf_size,
+        }
+    }
+
+    /// Drop every glyph, e.g. when the set of displayed sizes changed
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+        self.entries.clear();
+        self.shelves.clear();
+        self.generation += 1;
+        self.dirty = true;
+    }
+
+    /// Look up or rasterize `glyph` of `face` for display at `size`
+    /// pixels. Blank glyphs return `None`.
+    pub fn glyph(
+        &mut self,
+        font: AssetId,
+        face: &dyn FontFace,
+        glyph: u16,
+        size: f32,
+    ) -> Result<Option<AtlasGlyph>, GlyphAtlasError> {
+        let raster_size = self.raster_size(size);
+        let key = GlyphKey {
+            font,
+            glyph,
+            size: raster_size as u32,
+        };
+        if let Some(entry) = self.entries.get(&key) {
+            return Ok(*entry);
+        }
+
+        let scale = raster_size / face.units_per_em();
+        let Some(bitmap) =
+            rasterize_outline(&face.outline(glyph), scale, self.mode, self.sdf_range)
+        else {
+            self.entries.insert(key, None);
+            return Ok(None);
+        };
+
+        let (x, y) = self.allocate(bitmap.width, bitmap.height)?;
+        let channels = self.mode.channels() as usize;
+        let row = bitmap.width as usize * channels;
+        for line in 0..bitmap.height as usize {
+            let target = ((y as usize + line) * self.width as usize + x as usize) * channels;
+            self.pixels[target..target + row]
+                .copy_from_slice(&bitmap.data[line * row..(line + 1) * row]);
+        }
+        self.dirty = true;
+
+        let entry = AtlasGlyph {
+            x,
+            y,
+            width: bitmap.width,
+            height: bitmap.height,
+            offset: bitmap.offset,
+            raster_size,
+        };
+        self.entries.insert(key, Some(entry));
+        Ok(Some(entry))
+    }
+
+    /// Normalized texture coordinates of a glyph's corners
+    pub fn uv_rect(&self, glyph: &AtlasGlyph) -> (Vec2, Vec2) {
+        let size = Vec2::new(self.width as f32, self.height as f32);
+        let min = Vec2::new(glyph.x as f32, glyph.y as f32);
+        (
+            min / size,
+            (min + Vec2::new(glyph.width as f32, glyph.height as f32)) / size,
+        )
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Result<(u32, u32), GlyphAtlasError> {
+        let (padded_width, padded_height) = (width + GLYPH_PADDING, height + GLYPH_PADDING);
+        if padded_width > self.max_size {
+            return Err(GlyphAtlasError::Full(self.max_size));
+        }
+        loop {
+            // Best-fitting shelf that is not much taller than the glyph
+            let fit = self
+                .shelves
+                .iter_mut()
+                .filter(|shelf| {
+                    shelf.height >= padded_height
+                        && shelf.height <= padded_height + padded_height / 2 + 2
+                        && shelf.x + padded_width <= self.width
+                })
+                .min_by_key(|shelf| shelf.height);
+            if let Some(shelf) = fit {
+                let position = (shelf.x, shelf.y);
+                shelf.x += padded_width;
+                return Ok(position);
+            }
+
+            let top = self.shelves.last().map_or(0, |s| s.y + s.height);
+            if top + padded_height <= self.height && padded_width <= self.width {
+                self.shelves.push(Shelf {
+                    y: top,
+                    height: padded_height,
+                    x: padded_width,
+                });
+                return Ok((0, top));
+            }
+
+            self.grow()?;
+        }
+    }
+
+    /// Double the atlas, keeping existing pixels where they are
+    fn grow(&mut self) -> Result<(), GlyphAtlasError> {
+        if self.width >= self.max_size && self.height >= self.max_size {
+            return Err(GlyphAtlasError::Full(self.max_size));
+        }
+        let channels = self.mode.channels() as usize;
+        let (old_width, old_height) = (self.width as usize, self.height as usize);
+        if self.height <= self.width {
+            self.height = (self.height * 2).min(self.max_size);
+        } else {
+            self.width = (self.width * 2).min(self.max_size);
+        }
+
+        let mut pixels = vec![0; self.width as usize * self.height as usize * channels];
+        let row = old_width * channels;
+        for y in 0..old_height {
+            let target = y * self.width as usize * channels;
+            pixels[target..target + row].copy_from_slice(&self.pixels[y * row..(y + 1) * row]);
+        }
+        self.pixels = pixels;
+        self.generation += 1;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Mirror the atlas to its texture, recreating it after growth. Returns
+    /// `true` when the texture was recreated and bind groups must be too.
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let size = wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        };
+        let recreate = self
+            .texture
+            .as_ref()
+            .is_none_or(|(texture, _)| texture.size() != size);
+        if recreate {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Glyph Atlas"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.mode.texture_format(),
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.texture = Some((texture, view));
+            self.dirty = true;
+        }
+
+        if self.dirty {
+            if let Some((texture, _)) = &self.texture {
+                queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d::ZERO,
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    &self.pixels,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(self.width * self.mode.channels()),
+                        rows_per_image: Some(self.height),
+                    },
+                    size,
+                );
+            }
+            self.dirty = false;
+        }
+        recreate
+    }
+
+    pub fn texture_view(&self) -> Option<&wgpu::TextureView> {
+        self.texture.as_ref().map(|(_, view)| view)
+    }
+}
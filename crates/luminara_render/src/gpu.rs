@@ -1,4 +1,5 @@
 use luminara_window::Window;
+use std::sync::Arc;
 use wgpu;
 
 pub struct GpuContext {
@@ -6,8 +7,49 @@ pub struct GpuContext {
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
-    pub surface: wgpu::Surface<'static>,
+    /// Window surface; `None` for headless contexts
+    pub surface: Option<wgpu::Surface<'static>>,
+    /// Format and size of the frames, also for headless contexts
     pub surface_config: wgpu::SurfaceConfiguration,
+    /// Texture headless frames are rendered into
+    offscreen: Option<Arc<wgpu::Texture>>,
+}
+
+/// Settings of a [`GpuContext::new_headless`] context
+#[derive(Debug, Clone)]
+pub struct HeadlessOptions {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    /// Use the software adapter (e.g. llvmpipe or WARP) even when hardware
+    /// is available, for reproducible output
+    pub force_fallback_adapter: bool,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            force_fallback_adapter: false,
+        }
+    }
+}
+
+/// Texture a frame is rendered into, from [`GpuContext::begin_frame`]
+pub enum Frame {
+    Surface(wgpu::SurfaceTexture),
+    Offscreen(Arc<wgpu::Texture>),
+}
+
+impl Frame {
+    pub fn texture(&self) -> &wgpu::Texture {
+        match self {
+            Frame::Surface(frame) => &frame.texture,
+            Frame::Offscreen(texture) => texture,
+        }
+    }
 }
 
 use crate::error::RenderError;
@@ -88,8 +130,9 @@ impl GpuContext {
             adapter,
             device,
             queue,
-            surface,
+            surface: Some(surface),
             surface_config: config,
+            offscreen: None,
         })
     }
 
@@ -166,30 +209,119 @@ impl GpuContext {
             adapter,
             device,
             queue,
-            surface,
+            surface: Some(surface),
             surface_config: config,
+            offscreen: None,
         })
     }
 
+    /// A context without a window, rendering into an offscreen texture that
+    /// [`Self::capture_frame`] reads back
+    pub fn new_headless(options: HeadlessOptions) -> Result<Self, RenderError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        // Fall back to the software adapter when there is no hardware one
+        let request = |force_fallback_adapter| {
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            }))
+        };
+        let adapter = if options.force_fallback_adapter {
+            request(true)
+        } else {
+            request(false).or_else(|| request(true))
+        }
+        .ok_or(RenderError::AdapterRequestFailed)?;
+
+        // Software and GL adapters may not reach the default limits
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Luminara Headless Device"),
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+                memory_hints: Default::default(),
+            },
+            None,
+        ))
+        .map_err(|e: wgpu::RequestDeviceError| RenderError::DeviceRequestFailed(e.to_string()))?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: options.format,
+            width: options.width.max(1),
+            height: options.height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 1,
+        };
+        let offscreen = Self::create_offscreen(&device, &config);
+
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            surface: None,
+            surface_config: config,
+            offscreen: Some(Arc::new(offscreen)),
+        })
+    }
+
+    fn create_offscreen(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Frame"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.surface_config.width = width;
             self.surface_config.height = height;
-            self.surface.configure(&self.device, &self.surface_config);
+            match &self.surface {
+                Some(surface) => surface.configure(&self.device, &self.surface_config),
+                None => {
+                    let texture = Self::create_offscreen(&self.device, &self.surface_config);
+                    self.offscreen = Some(Arc::new(texture));
+                }
+            }
         }
     }
 
-    pub fn begin_frame(&mut self) -> Option<(wgpu::SurfaceTexture, wgpu::TextureView)> {
-        match self.surface.get_current_texture() {
-            Ok(frame) => {
-                let view = frame
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default());
-                Some((frame, view))
-            }
+    pub fn begin_frame(&self) -> Option<(Frame, wgpu::TextureView)> {
+        let Some(surface) = &self.surface else {
+            let texture = self.offscreen.clone()?;
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            return Some((Frame::Offscreen(texture), view));
+        };
+        let frame = match surface.get_current_texture() {
+            Ok(frame) => frame,
             Err(wgpu::SurfaceError::Timeout) => {
                 log::warn!("Surface texture checkout timeout — skipping frame");
-                None
+                return None;
             }
             Err(wgpu::SurfaceError::Outdated) => {
                 log::info!(
@@ -197,47 +329,120 @@ impl GpuContext {
                     self.surface_config.width,
                     self.surface_config.height
                 );
-                self.surface.configure(&self.device, &self.surface_config);
-                // Retry immediately instead of skipping the frame
-                match self.surface.get_current_texture() {
-                    Ok(frame) => {
-                        let view = frame
-                            .texture
-                            .create_view(&wgpu::TextureViewDescriptor::default());
-                        Some((frame, view))
-                    }
-                    Err(e) => {
-                        log::error!("Surface still unavailable after reconfigure: {:?}", e);
-                        None
-                    }
-                }
+                self.reacquire(surface)?
             }
             Err(wgpu::SurfaceError::Lost) => {
                 log::warn!("Surface lost, reconfiguring...");
-                self.surface.configure(&self.device, &self.surface_config);
-                // Retry immediately instead of skipping the frame
-                match self.surface.get_current_texture() {
-                    Ok(frame) => {
-                        let view = frame
-                            .texture
-                            .create_view(&wgpu::TextureViewDescriptor::default());
-                        Some((frame, view))
-                    }
-                    Err(e) => {
-                        log::error!("Surface still unavailable after reconfigure: {:?}", e);
-                        None
-                    }
-                }
+                self.reacquire(surface)?
             }
             Err(e) => {
                 log::error!("Surface error: {:?}", e);
+                return None;
+            }
+        };
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        Some((Frame::Surface(frame), view))
+    }
+
+    /// Reconfigure the surface and retry immediately instead of skipping the frame
+    fn reacquire(&self, surface: &wgpu::Surface<'static>) -> Option<wgpu::SurfaceTexture> {
+        surface.configure(&self.device, &self.surface_config);
+        match surface.get_current_texture() {
+            Ok(frame) => Some(frame),
+            Err(e) => {
+                log::error!("Surface still unavailable after reconfigure: {:?}", e);
                 None
             }
         }
     }
 
-    pub fn end_frame(&self, frame: wgpu::SurfaceTexture) {
-        frame.present();
+    pub fn end_frame(&self, frame: Frame) {
+        if let Frame::Surface(frame) = frame {
+            frame.present();
+        }
+    }
+
+    /// Read back the last frame of a headless context
+    pub fn capture_frame(&self) -> Result<image::RgbaImage, RenderError> {
+        let texture = self.offscreen.as_ref().ok_or_else(|| {
+            RenderError::Capture("window surfaces cannot be read back".to_string())
+        })?;
+        self.read_texture(texture)
+    }
+
+    /// Copy an 8-bit RGBA or BGRA texture with `COPY_SRC` usage into an image.
+    /// Blocks until the GPU has finished the copy.
+    pub fn read_texture(&self, texture: &wgpu::Texture) -> Result<image::RgbaImage, RenderError> {
+        use wgpu::TextureFormat as F;
+        let bgra = match texture.format() {
+            F::Rgba8Unorm | F::Rgba8UnormSrgb => false,
+            F::Bgra8Unorm | F::Bgra8UnormSrgb => true,
+            format => {
+                return Err(RenderError::Capture(format!(
+                    "unsupported format {:?}",
+                    format
+                )))
+            }
+        };
+        let (width, height) = (texture.width(), texture.height());
+        let row_bytes = width * 4;
+        let padded_bytes_per_row = row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Capture"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Frame Capture Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        match receiver.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(RenderError::Capture(e.to_string())),
+            Err(e) => return Err(RenderError::Capture(e.to_string())),
+        }
+
+        let mut data = Vec::with_capacity((row_bytes * height) as usize);
+        {
+            let mapped = slice.get_mapped_range();
+            for row in mapped.chunks(padded_bytes_per_row as usize) {
+                data.extend_from_slice(&row[..row_bytes as usize]);
+            }
+        }
+        buffer.unmap();
+        if bgra {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        image::RgbaImage::from_raw(width, height, data)
+            .ok_or_else(|| RenderError::Capture("frame size mismatch".to_string()))
     }
 }
 
@@ -0,0 +1,687 @@
+//! Image-based lighting: environment map lights, skyboxes and reflection
+//! probes.
+//!
+//! A camera with an [`EnvironmentMapLight`] lights the lit materials it
+//! draws with an [`EnvironmentMap`]: diffuse light from its spherical
+//! harmonics, reflections from its prefiltered specular levels and the
+//! split-sum [`BrdfLut`]. [`ReflectionProbe`]s replace that environment
+//! inside their boxes, fading out over their blend distance. A [`Skybox`]
+//! shows an environment behind the scene.
+//!
+//! Materials read the environment from bind group 3, declared by the
+//! material prelude.
+
+use crate::environment_map::{BrdfLut, Cubemap, EnvironmentMap, SphericalHarmonics};
+use crate::render_graph::slots;
+use crate::render_target::CameraView;
+use crate::render_world::{components_by_main_entity, MainEntity};
+use crate::Shader;
+use luminara_asset::{AssetId, AssetServer, Handle};
+use luminara_core::shared_types::{Component, Query, Resource, World};
+use luminara_core::Entity;
+use luminara_math::{Transform, Vec3};
+use luminara_reflect_derive::Reflect;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+/// Ambient light of materials no environment lights
+pub const DEFAULT_AMBIENT: Vec3 = Vec3::new(0.15, 0.15, 0.2);
+
+/// Reflection probes blended into the lighting of one draw at most
+pub const MAX_BLENDED_PROBES: usize = 2;
+
+/// Side of the BRDF lookup table texture
+const BRDF_LUT_SIZE: u32 = 64;
+const BRDF_LUT_SAMPLES: u32 = 256;
+
+/// Lights the lit materials a camera draws with an environment map, except
+/// inside [`ReflectionProbe`]s
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub struct EnvironmentMapLight {
+    pub environment: Handle<EnvironmentMap>,
+    /// Scale of the environment's radiance
+    pub intensity: f32,
+}
+
+impl EnvironmentMapLight {
+    pub fn new(environment: Handle<EnvironmentMap>) -> Self {
+        Self {
+            environment,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl Component for EnvironmentMapLight {
+    fn type_name() -> &'static str {
+        "EnvironmentMapLight"
+    }
+}
+
+/// Shows an environment map where a camera draws nothing else
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub struct Skybox {
+    pub environment: Handle<EnvironmentMap>,
+    pub brightness: f32,
+}
+
+impl Skybox {
+    pub fn new(environment: Handle<EnvironmentMap>) -> Self {
+        Self {
+            environment,
+            brightness: 1.0,
+        }
+    }
+}
+
+impl Component for Skybox {
+    fn type_name() -> &'static str {
+        "Skybox"
+    }
+}
+
+/// Environment captured around a place, lighting the draws inside a box
+/// centered on the entity's [`Transform`] and rotated with it. The probe's
+/// weight rises from 0 at the box faces to 1 at `blend_distance` inside
+/// them, so overlapping probes cross-fade.
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub struct ReflectionProbe {
+    pub environment: Handle<EnvironmentMap>,
+    /// Half size of the box in world units; the transform's scale is ignored
+    pub half_extents: Vec3,
+    pub blend_distance: f32,
+    pub intensity: f32,
+}
+
+impl ReflectionProbe {
+    pub fn new(environment: Handle<EnvironmentMap>, half_extents: Vec3) -> Self {
+        Self {
+            environment,
+            half_extents,
+            blend_distance: 1.0,
+            intensity: 1.0,
+        }
+    }
+
+    /// How much of the lighting at `point` comes from this probe, ignoring
+    /// other probes
+    pub fn weight(&self, transform: &Transform, point: Vec3) -> f32 {
+        let local = transform.rotation.inverse() * (point - transform.translation);
+        let inside = (self.half_extents - local.abs()).min_element();
+        if inside < 0.0 {
+            0.0
+        } else if self.blend_distance <= 0.0 {
+            1.0
+        } else {
+            (inside / self.blend_distance).min(1.0)
+        }
+    }
+
+    pub fn volume(&self) -> f32 {
+        (self.half_extents * 2.0)
+            .max(Vec3::ZERO)
+            .to_array()
+            .iter()
+            .product()
+    }
+}
+
+impl Component for ReflectionProbe {
+    fn type_name() -> &'static str {
+        "ReflectionProbe"
+    }
+}
+
+/// Probes lighting a point. Layers nest: the first takes its weight of the
+/// light, the second its weight of the rest, and the camera's environment
+/// what remains. Smaller probes come first, so a probe placed inside a
+/// larger one overrides it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProbeBlend {
+    /// Index into the probe list and weight, innermost first, at most
+    /// [`MAX_BLENDED_PROBES`]
+    pub layers: Vec<(usize, f32)>,
+}
+
+impl ProbeBlend {
+    /// Blend of `probes` at `point`. Probes of equal volume keep their
+    /// order in the list.
+    pub fn new(point: Vec3, probes: &[(ReflectionProbe, Transform)]) -> Self {
+        let mut layers: Vec<(usize, f32, f32)> = probes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (probe, transform))| {
+                let weight = probe.weight(transform, point);
+                (weight > 0.0).then(|| (index, weight, probe.volume()))
+            })
+            .collect();
+        layers.sort_by(|a, b| a.2.total_cmp(&b.2).then(a.0.cmp(&b.0)));
+        Self {
+            layers: layers
+                .into_iter()
+                .take(MAX_BLENDED_PROBES)
+                .map(|(index, weight, _)| (index, weight))
+                .collect(),
+        }
+    }
+
+    /// Share of the light each layer and the camera's environment
+    /// contribute, adding up to 1
+    pub fn contributions(&self) -> (Vec<f32>, f32) {
+        let mut remaining = 1.0;
+        let shares = self
+            .layers
+            .iter()
+            .map(|&(_, weight)| {
+                let share = remaining * weight;
+                remaining -= share;
+                share
+            })
+            .collect();
+        (shares, remaining)
+    }
+}
+
+/// Environment components of a render world, gathered once per frame
+pub(crate) struct SceneEnvironments {
+    /// By main world camera entity
+    lights: HashMap<Entity, EnvironmentMapLight>,
+    skyboxes: HashMap<Entity, Skybox>,
+    /// Sorted by entity, so probe order does not change between frames
+    probes: Vec<(ReflectionProbe, Transform)>,
+}
+
+impl SceneEnvironments {
+    pub(crate) fn collect(world: &World) -> Self {
+        let mut probes: Vec<_> = Query::<(Entity, &ReflectionProbe, &Transform)>::new(world)
+            .iter()
+            .map(|(entity, probe, transform)| {
+                let main = world
+                    .get_component::<MainEntity>(entity)
+                    .map_or(entity, |main| main.0);
+                (main.id(), (probe.clone(), *transform))
+            })
+            .collect();
+        probes.sort_by_key(|(id, _)| *id);
+        Self {
+            lights: components_by_main_entity(world),
+            skyboxes: components_by_main_entity(world),
+            probes: probes.into_iter().map(|(_, probe)| probe).collect(),
+        }
+    }
+
+    pub(crate) fn light(&self, camera: Entity) -> Option<&EnvironmentMapLight> {
+        self.lights.get(&camera)
+    }
+
+    pub(crate) fn skybox(&self, camera: Entity) -> Option<&Skybox> {
+        self.skyboxes.get(&camera)
+    }
+
+    pub(crate) fn probes(&self) -> &[(ReflectionProbe, Transform)] {
+        &self.probes
+    }
+
+    fn handles(&self) -> impl Iterator<Item = &Handle<EnvironmentMap>> {
+        self.lights
+            .values()
+            .map(|light| &light.environment)
+            .chain(self.skyboxes.values().map(|skybox| &skybox.environment))
+            .chain(self.probes.iter().map(|(probe, _)| &probe.environment))
+    }
+}
+
+/// Environment uniform of a draw, matching `EnvironmentUniform` in the
+/// material prelude
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct EnvironmentUniform {
+    /// Blended SH convolved for irradiance
+    irradiance: [[f32; 4]; 9],
+    /// Weights of the inner and outer probe, then 1 when an environment
+    /// lights the draw
+    weights: [f32; 4],
+    /// Specular intensity of the camera environment, inner and outer probe
+    intensities: [f32; 4],
+    /// Highest specular level of the same
+    levels: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyboxUniform {
+    inv_view_proj: [[f32; 4]; 4],
+    brightness: [f32; 4],
+}
+
+/// An environment map on the GPU
+struct GpuEnvironment {
+    _skybox: wgpu::Texture,
+    skybox_view: wgpu::TextureView,
+    _specular: wgpu::Texture,
+    specular_view: wgpu::TextureView,
+    levels: u32,
+    irradiance: SphericalHarmonics,
+}
+
+impl GpuEnvironment {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue, environment: &EnvironmentMap) -> Self {
+        let (skybox, skybox_view) = upload_cubemap(
+            device,
+            queue,
+            "Skybox Cubemap",
+            std::slice::from_ref(&environment.skybox),
+        );
+        let (specular, specular_view) = upload_cubemap(
+            device,
+            queue,
+            "Specular Environment Cubemap",
+            &environment.specular,
+        );
+        Self {
+            _skybox: skybox,
+            skybox_view,
+            _specular: specular,
+            specular_view,
+            levels: environment.specular.len() as u32,
+            irradiance: environment.irradiance,
+        }
+    }
+}
+
+/// Upload cubemaps of halving sizes as the mips of one cube texture
+fn upload_cubemap(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    levels: &[Cubemap],
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let size = levels[0].size;
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
+        },
+        mip_level_count: levels.len() as u32,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba16Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    for (level, cubemap) in levels.iter().enumerate() {
+        let texels: Vec<u16> = cubemap
+            .data
+            .iter()
+            .flat_map(|color| [color.x, color.y, color.z, 1.0])
+            .map(|value| half::f16::from_f32(value).to_bits())
+            .collect();
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: level as u32,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&texels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(cubemap.size * 8),
+                rows_per_image: Some(cubemap.size),
+            },
+            wgpu::Extent3d {
+                width: cubemap.size,
+                height: cubemap.size,
+                depth_or_array_layers: 6,
+            },
+        );
+    }
+    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::Cube),
+        ..Default::default()
+    });
+    (texture, view)
+}
+
+/// Layout of bind group 3 of material pipelines: the environment uniform,
+/// the specular cubemaps of the camera environment and of the inner and
+/// outer probe, their sampler and the BRDF lookup table
+pub(crate) fn environment_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let texture = |binding, view_dimension| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension,
+            multisampled: false,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Environment Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            texture(1, wgpu::TextureViewDimension::Cube),
+            texture(2, wgpu::TextureViewDimension::Cube),
+            texture(3, wgpu::TextureViewDimension::Cube),
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            texture(5, wgpu::TextureViewDimension::D2),
+        ],
+    })
+}
+
+/// GPU copies of environment maps, the BRDF lookup table and the skybox
+/// pipeline, created on first use
+#[derive(Default)]
+pub struct EnvironmentResources {
+    sampler: Option<wgpu::Sampler>,
+    brdf_lut: Option<(wgpu::Texture, wgpu::TextureView)>,
+    /// Uniform [`DEFAULT_AMBIENT`], standing in for missing environments
+    fallback: Option<GpuEnvironment>,
+    uploaded: HashMap<AssetId, (Arc<EnvironmentMap>, GpuEnvironment)>,
+    skybox_pipeline: Option<(wgpu::RenderPipeline, wgpu::BindGroupLayout)>,
+}
+
+impl Resource for EnvironmentResources {}
+
+impl EnvironmentResources {
+    /// Create the shared resources and upload every loaded environment the
+    /// scene uses. Reloaded assets are new `Arc`s, so they are uploaded
+    /// again.
+    pub(crate) fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        asset_server: &AssetServer,
+        scene: &SceneEnvironments,
+    ) {
+        self.sampler.get_or_insert_with(|| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Environment Sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            })
+        });
+        self.brdf_lut
+            .get_or_insert_with(|| upload_brdf_lut(device, queue));
+        self.fallback.get_or_insert_with(|| {
+            GpuEnvironment::new(device, queue, &EnvironmentMap::constant(DEFAULT_AMBIENT))
+        });
+        if self.skybox_pipeline.is_none() && !scene.skyboxes.is_empty() {
+            self.skybox_pipeline = Some(create_skybox_pipeline(device));
+        }
+
+        for handle in scene.handles() {
+            let Some(environment) = asset_server.get(handle) else {
+                continue;
+            };
+            let stale = self
+                .uploaded
+                .get(&handle.id())
+                .is_none_or(|(uploaded, _)| !Arc::ptr_eq(uploaded, &environment));
+            if stale {
+                let gpu = GpuEnvironment::new(device, queue, &environment);
+                self.uploaded.insert(handle.id(), (environment, gpu));
+            }
+        }
+    }
+
+    fn get(&self, handle: &Handle<EnvironmentMap>) -> Option<&GpuEnvironment> {
+        self.uploaded.get(&handle.id()).map(|(_, gpu)| gpu)
+    }
+
+    /// Bind group 3 of a draw lit by `camera`'s environment and the probes
+    /// of `blend`. `None` before [`Self::prepare`].
+    pub(crate) fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        camera: Option<&EnvironmentMapLight>,
+        blend: &ProbeBlend,
+        probes: &[(ReflectionProbe, Transform)],
+    ) -> Option<wgpu::BindGroup> {
+        let (Some(sampler), Some((_, brdf_lut)), Some(fallback)) =
+            (&self.sampler, &self.brdf_lut, &self.fallback)
+        else {
+            return None;
+        };
+
+        // Environments still loading light nothing
+        let camera =
+            camera.and_then(|light| Some((self.get(&light.environment)?, light.intensity)));
+        let layers: Vec<(&GpuEnvironment, f32, f32)> = blend
+            .layers
+            .iter()
+            .filter_map(|&(index, weight)| {
+                let (probe, _) = &probes[index];
+                Some((self.get(&probe.environment)?, weight, probe.intensity))
+            })
+            .collect();
+        let lit = camera.is_some() || !layers.is_empty();
+
+        let (camera, camera_intensity) = camera.unwrap_or((fallback, 1.0));
+        let layer = |index: usize| layers.get(index).copied().unwrap_or((fallback, 0.0, 0.0));
+        let (inner, inner_weight, inner_intensity) = layer(0);
+        let (outer, outer_weight, outer_intensity) = layer(1);
+
+        let irradiance = camera
+            .irradiance
+            .scale(camera_intensity)
+            .lerp(&outer.irradiance.scale(outer_intensity), outer_weight)
+            .lerp(&inner.irradiance.scale(inner_intensity), inner_weight)
+            .irradiance_coefficients();
+        let max_level = |environment: &GpuEnvironment| (environment.levels - 1) as f32;
+        let uniform = EnvironmentUniform {
+            irradiance: irradiance.coefficients.map(|c| c.extend(0.0).to_array()),
+            weights: [inner_weight, outer_weight, 0.0, if lit { 1.0 } else { 0.0 }],
+            intensities: [camera_intensity, inner_intensity, outer_intensity, 0.0],
+            levels: [max_level(camera), max_level(inner), max_level(outer), 0.0],
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Environment Buffer"),
+            contents: bytemuck::bytes_of(&uniform),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Environment Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&camera.specular_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&inner.specular_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&outer.specular_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(brdf_lut),
+                },
+            ],
+        }))
+    }
+
+    /// Pipeline and bind group drawing `skybox` behind `view`, once its
+    /// environment is loaded
+    pub(crate) fn skybox(
+        &self,
+        device: &wgpu::Device,
+        view: &CameraView,
+        skybox: &Skybox,
+    ) -> Option<(&wgpu::RenderPipeline, wgpu::BindGroup)> {
+        let (pipeline, layout) = self.skybox_pipeline.as_ref()?;
+        let (sampler, environment) = (self.sampler.as_ref()?, self.get(&skybox.environment)?);
+        let uniform = SkyboxUniform {
+            inv_view_proj: view.view_proj.inverse().to_cols_array_2d(),
+            brightness: [skybox.brightness, 0.0, 0.0, 0.0],
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Skybox Buffer"),
+            contents: bytemuck::bytes_of(&uniform),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Skybox Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&environment.skybox_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+        Some((pipeline, bind_group))
+    }
+}
+
+fn upload_brdf_lut(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let lut = BrdfLut::new(BRDF_LUT_SIZE, BRDF_LUT_SAMPLES);
+    let texels: Vec<u16> = lut
+        .data
+        .iter()
+        .flat_map(|texel| texel.to_array())
+        .map(|value| half::f16::from_f32(value).to_bits())
+        .collect();
+    let texture = device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some("BRDF LUT"),
+            size: wgpu::Extent3d {
+                width: lut.size,
+                height: lut.size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rg16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+        wgpu::util::TextureDataOrder::LayerMajor,
+        bytemuck::cast_slice(&texels),
+    );
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+/// Fullscreen pass on the far plane, drawn after opaque geometry so only
+/// uncovered pixels pass the depth test
+fn create_skybox_pipeline(device: &wgpu::Device) -> (wgpu::RenderPipeline, wgpu::BindGroupLayout) {
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Skybox Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::Cube,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Skybox Pipeline Layout"),
+        bind_group_layouts: &[&layout],
+        push_constant_ranges: &[],
+    });
+
+    let mut shader = Shader::from_wgsl(include_str!("../shaders/skybox.wgsl"));
+    let module = shader.compile(device);
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Skybox Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module,
+            entry_point: "vs_main",
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: slots::HDR_FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: slots::DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    });
+    (pipeline, layout)
+}
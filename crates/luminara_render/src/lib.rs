@@ -1,98 +1,223 @@
 pub mod ai_shader_pipeline;
 pub mod animation;
+pub mod animation_graph;
 pub mod animation_system;
 pub mod audio_debug_systems;
 pub mod buffer_pool;
 pub mod camera;
 pub mod camera_systems;
+pub mod clustered_lighting;
+pub mod color_lut;
 pub mod command;
 pub mod components;
 pub mod debug_rendering;
 pub mod draw_call_batcher;
+pub mod environment_map;
 pub mod error;
 pub mod fluid;
 pub mod fluid_systems;
+pub mod font;
 pub mod forward_plus;
 pub mod frustum_culling;
 pub mod gizmo;
 pub mod gizmo_system;
+pub mod gltf_scene;
+pub mod glyph_atlas;
 pub mod gpu;
 pub mod ik;
+pub mod image_based_lighting;
 pub mod instancing;
 pub mod lod_system;
 pub mod material;
 pub mod mesh;
+pub mod mesh_processing;
 pub mod occlusion_culling;
 pub mod mesh_loader;
 pub mod overlay;
+pub mod particle_effect;
 pub mod particles;
 pub mod pipeline;
 pub mod plugin;
+pub mod post_effects;
 pub mod post_process;
 pub mod render_graph;
+pub mod render_target;
+pub mod render_world;
 pub mod shader;
 pub mod shader_generator;
+pub mod shader_preprocessor;
 pub mod shadow;
+pub mod shadow_atlas;
+pub mod skinning;
+pub mod software_occlusion;
+pub mod software_renderer;
 pub mod sprite;
+pub mod sprite_animation;
 pub mod sprite_systems;
+pub mod terrain;
+pub mod text;
 pub mod texture;
+pub mod texture_atlas;
+pub mod tiled;
+pub mod tilemap;
+pub mod visibility;
 
 pub use ai_shader_pipeline::{
     AiShaderError, AiShaderPipeline, AiShaderResult, ExpressionGenerator, MockExpressionGenerator,
 };
 pub use animation::{AnimationClip, Bone, GltfLoader, GltfScene, Skeleton, SkinnedMesh};
+pub use animation_graph::{
+    animation_graph_system, apply_animation_pose_system, blend_weights_1d, blend_weights_2d,
+    AnimationEvent, AnimationEventKey, AnimationEvents, AnimationGraph, AnimationGraphLoader,
+    AnimationGraphPlayer, AnimationLayer, AnimationParameter, AnimationParameters, AnimationState,
+    AnimationTargets, AnimationTransition, BlendPoint1D, BlendPoint2D, BoneMask, BonePose,
+    LayerBlend, Motion, Pose, RootMotion, TransitionCondition,
+};
 pub use animation_system::{AnimationPlayer, AnimationPlugin, SampledBoneTransform};
 pub use audio_debug_systems::visualize_audio_sources_system;
-pub use camera::{Camera, Camera2d, Camera3d, Projection};
+pub use camera::{Camera, Camera2d, Camera3d, Projection, RenderLayers, RenderTarget, Viewport};
 pub use camera_systems::{camera_projection_system, camera_resize_system};
+pub use clustered_lighting::{
+    ClusterConfig, ClusterLight, ClusterLightShape, ClusterRange, LightClusters,
+};
+pub use color_lut::{ColorLut, ColorLutLoader, LutError};
 pub use command::{CommandBuffer, DrawCommand, GizmoType};
-pub use components::{DirectionalLight, Lod, MeshRenderer, PbrMaterial, PointLight};
+pub use components::{
+    DirectionalLight, Lod, MeshRenderer, MorphWeights, PbrMaterial, PointLight, SpotLight,
+};
 pub use debug_rendering::{DebugRenderMode, DebugRenderingResource};
 pub use draw_call_batcher::{
     BatchedDrawCall, DrawCallBatcher, DrawCallBatcherStats, DrawCallSortKey, MaterialKey,
 };
+pub use environment_map::{
+    prefilter_specular, specular_roughness, BrdfLut, Cubemap, EnvironmentMap,
+    EnvironmentMapError, EnvironmentMapLoader, EnvironmentMapSettings, SphericalHarmonics,
+};
 pub use error::RenderError;
 pub use fluid::{FluidRenderer, FluidSolverResource, FluidVisualizationMode};
 pub use fluid_systems::{
     cleanup_fluid_solvers_system, init_fluid_solvers_system, sync_fluid_textures_system,
     update_fluid_simulation_system,
 };
-pub use forward_plus::update_lights_system;
+pub use font::{Font, FontFace, FontLoader, OutlineCurve};
+pub use forward_plus::{update_lights_system, ForwardPlusNode};
 pub use frustum_culling::{Cullable, Frustum, FrustumCullingSystem, Plane};
 pub use gizmo::{GizmoCategories, Gizmos};
 pub use gizmo_system::{
     AudioVisualizationSettings, GizmoSystem, PhysicsVisualizationSettings,
     RenderingVisualizationSettings, TransformVisualizationSettings, VisualizationMode,
 };
-pub use gpu::GpuContext;
+pub use gltf_scene::{
+    import_gltf_scene, insert_gltf_sub_assets_system, register_gltf_components, spawn_gltf_scene,
+    GltfAssetLabel, GltfPlugin, GltfSceneLoader, GltfSkin, GltfSubAsset, GltfSubAssets,
+};
+pub use glyph_atlas::{
+    rasterize_outline, AtlasGlyph, GlyphAtlas, GlyphAtlasError, GlyphBitmap, GlyphKey,
+    GlyphRasterMode,
+};
+pub use gpu::{Frame, GpuContext, HeadlessOptions};
 pub use ik::{TwoBoneIK, TwoBoneIKSolver};
+pub use image_based_lighting::{
+    EnvironmentMapLight, EnvironmentResources, ProbeBlend, ReflectionProbe, Skybox,
+    DEFAULT_AMBIENT, MAX_BLENDED_PROBES,
+};
 pub use instancing::{InstanceBatcher, InstanceBatcherStats, InstanceData, InstanceGroup};
 pub use lod_system::{LodConfig, LodGenerator, LodState, LodStats};
-pub use material::Material;
-pub use mesh::{Mesh, Vertex, AABB};
+pub use material::{
+    load_material_textures_system, material_prelude, pbr_shader, register_builtin_shaders,
+    shader_handle, terrain_shader, unlit_shader, BlendMode, CullMode, Material, MaterialLoader,
+    MaterialPipelineKey, MaterialPlugin, MaterialRegistry, MaterialTextureQueue, MaterialTextures,
+    PreparedMaterialDraw, SelectMesh, ShaderMaterial, TextureBinding, UniformField, UniformLayout,
+    UniformType, UnlitMaterial,
+};
+pub use mesh::{Mesh, MorphTarget, SkinWeights, Vertex, AABB};
+pub use mesh_processing::{SimplifiedMesh, SimplifyOptions};
 pub use occlusion_culling::{
     Occludable, OcclusionCullingSystem, OcclusionQuery, OcclusionState, OcclusionStats,
     create_bbox_vertex_buffer, create_bbox_index_buffer,
 };
 pub use mesh_loader::MeshLoader;
-pub use overlay::{OverlayCommand, OverlayRenderer};
-pub use particles::{Particle, ParticleEmitter, ParticlePlugin, ParticleSystem};
+pub use overlay::{OverlayCommand, OverlayNode, OverlayRenderer};
+pub use particle_effect::{
+    particle_effect_system, Burst, Curve, EffectParticle, EmissionShape, EmitterState, Gradient,
+    ParticleCollider, ParticleColliders, ParticleCollision, ParticleEffect, ParticleEffectInstance,
+    ParticleEffectLoader, ParticleEmitterDesc, ParticleForce, ParticleRng,
+    ParticleSimulationContext, SimulationSpace, SubEmitter, SubEmitterTrigger, ValueRange,
+};
+pub use particles::{Particle, ParticleEmitter, ParticleInstance, ParticlePlugin, ParticleSystem};
 pub use pipeline::{CachedPipeline, PipelineCache, RenderPipelineDescriptor};
 pub use plugin::RenderPlugin;
-pub use post_process::{init_post_process_system, PostProcessResources};
-pub use shader::Shader;
+pub use post_effects::{
+    ev_from_luminance, exposure_from_ev, Antialiasing, AutoExposure, Exposure, PostEffect,
+    PostProcessSettings, Tonemapping, MID_GRAY,
+};
+pub use post_process::{init_post_process_system, PostProcessNode, PostProcessResources};
+pub use render_graph::{
+    CompiledRenderGraph, PassSlots, RenderContext, RenderFrame, RenderGraph, RenderGraphError,
+    RenderNode, ResourceDesc, SlotSize, TransientResource,
+};
+pub use render_target::{collect_camera_views, CameraView, RenderTargets, TargetKey};
+pub use render_world::{
+    extract_render_world_system, resize_surface_system, ExtractFn, ExtractedOverlay,
+    ExtractedWindow, MainEntity, RenderApp, RenderPipelining,
+};
+pub use shader::{Shader, ShaderLoader};
 pub use shader_generator::{CacheStats, ShaderGenerator};
-pub use shadow::{update_shadow_cascades_system, ShadowCascades, ShadowMapResources};
-pub use sprite::{Anchor, Rect, Sprite, SpriteBatcher, SpriteRenderResources, ZOrder};
-pub use sprite_systems::{init_sprite_system, prepare_sprite_batches, render_sprites};
-pub use texture::{Texture, TextureData, TextureFormat};
-
-use luminara_math::Color;
+pub use shader_preprocessor::{
+    import_path, ComposedShader, PipelineDependencies, ShaderComposer, ShaderDefs,
+    ShaderDependency, ShaderProcessError, ShaderSourceLocation,
+};
+pub use shadow::{
+    update_shadow_cascades_system, ShadowCascades, ShadowMapResources, ShadowPassNode,
+};
+pub use shadow_atlas::{
+    collect_shadow_requests, point_light_face, point_light_view_projs, screen_coverage,
+    spot_light_view_proj, update_shadow_atlas_system, ShadowAllocation, ShadowAtlas,
+    ShadowAtlasAllocator, ShadowAtlasNode, ShadowAtlasResources, ShadowFilter, ShadowLightKind,
+    ShadowRequest, ShadowSettings, ShadowTile, ShadowView, CUBE_FACES, MAX_SHADOW_LIGHTS,
+};
+pub use skinning::{
+    cpu_deformation_system, deform_mesh, joint_palette_system, morph_vertices, skin_vertex,
+    skin_vertices, DeformedMesh, GpuSkinJoint, JointPalette, SkinningMethod, SkinningPlugin,
+};
+pub use software_occlusion::{Occluder, OcclusionBuffer};
+pub use software_renderer::{mesh_thumbnail, SoftwareRenderer};
+pub use sprite::{
+    Anchor, BorderRect, NineSlice, NineSlicePiece, Rect, Sprite, SpriteBatch, SpriteBatcher,
+    SpriteInstance, SpriteRenderResources, ZOrder,
+};
+pub use sprite_animation::{
+    sprite_animation_system, SpriteAnimation, SpriteAnimationClip, SpriteAnimationEvent,
+    SpriteAnimationEvents, SpriteAnimationMode, SpriteFrame, SpriteFrameEvent,
+};
+pub use sprite_systems::{init_sprite_system, prepare_sprite_batches, render_sprites, SpritePlugin};
+pub use terrain::{
+    spawn_terrains_system, terrain_system, SculptMode, Terrain, TerrainChunk, TerrainChunkLod,
+    TerrainData, TerrainLoader, TerrainLodSettings, TerrainMaterial, TerrainNode, TerrainPlugin,
+    MAX_TERRAIN_LAYERS,
+};
+pub use text::{
+    asset_fonts, build_text_mesh, layout_text, text3d_layout_system, FontLookup, GlyphQuad,
+    LayoutFont, PositionedGlyph, Text3d, Text3dMesh, Text3dNode, Text3dRenderer, TextAlign,
+    TextLayout, TextLayoutOptions, TextLine, TextPlugin, TextSpan, TextStyle, TextVertex,
+};
+pub use texture::{SamplerSettings, Texture, TextureData, TextureFormat};
+pub use texture_atlas::{
+    atlas_sprite_system, AtlasSprite, PackedAtlas, TextureAtlas, TextureAtlasBuilder,
+    TextureAtlasError, TextureAtlasLoader,
+};
+pub use tiled::{
+    spawn_tiled_maps_system, TiledChunk, TiledLayer, TiledMap, TiledMapLoader, TiledTileset,
+};
+pub use tilemap::{
+    prepare_tilemap_batches, AutotileRule, Tile, TileCollider, TileLayer, Tilemap,
+    DEFAULT_CHUNK_SIZE,
+};
+pub use visibility::{visibility_system, VisibilityCulling, VisibilityStats, VisibleEntities};
 
 use luminara_asset::{AssetServer, Handle};
-use luminara_core::shared_types::{Query, Res, ResMut, Resource};
+use luminara_core::shared_types::{Query, Res, ResMut, Resource, World};
 use luminara_math::Transform;
-use wgpu::util::DeviceExt;
 
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
@@ -157,107 +282,6 @@ pub fn window_resize_system(
     }
 }
 
-const PBR_SHADER_SOURCE: &str = r#"
-struct CameraUniform {
-    view_proj: mat4x4<f32>,
-    camera_pos: vec3<f32>,
-    _padding: f32,
-};
-
-@group(0) @binding(0)
-var<uniform> camera: CameraUniform;
-
-struct ModelTransform {
-    model: mat4x4<f32>,
-};
-
-@group(1) @binding(0)
-var<uniform> model: ModelTransform;
-
-struct MaterialUniform {
-    albedo: vec4<f32>,
-    metallic: f32,
-    roughness: f32,
-    emissive: vec3<f32>,
-    has_albedo_texture: f32,
-};
-
-@group(2) @binding(0)
-var<uniform> material: MaterialUniform;
-
-@group(2) @binding(1)
-var albedo_texture: texture_2d<f32>;
-
-@group(2) @binding(2)
-var albedo_sampler: sampler;
-
-struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) normal: vec3<f32>,
-    @location(2) uv: vec2<f32>,
-    @location(3) tangent: vec4<f32>,
-};
-
-struct VertexOutput {
-    @builtin(position) position: vec4<f32>,
-    @location(0) normal: vec3<f32>,
-    @location(1) world_pos: vec3<f32>,
-    @location(2) uv: vec2<f32>,
-};
-
-@vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
-    var out: VertexOutput;
-    let world_pos = model.model * vec4<f32>(in.position, 1.0);
-    out.position = camera.view_proj * world_pos;
-    out.world_pos = world_pos.xyz;
-    // Use inverse transpose for correct normals under non-uniform scale
-    out.normal = normalize((model.model * vec4<f32>(in.normal, 0.0)).xyz);
-    out.uv = in.uv;
-    return out;
-}
-
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var albedo = material.albedo.rgb;
-    if (material.has_albedo_texture > 0.0) {
-        albedo = textureSample(albedo_texture, albedo_sampler, in.uv).rgb;
-    }
-    let metallic = material.metallic;
-    let roughness = material.roughness;
-
-    // Simple directional light (sun-like)
-    let light_dir = normalize(vec3<f32>(0.3, 0.7, 0.5));
-    let light_color = vec3<f32>(1.0, 0.95, 0.9);
-    let normal = normalize(in.normal);
-
-    // Diffuse (Lambertian)
-    let n_dot_l = max(dot(normal, light_dir), 0.0);
-    let diffuse = albedo * light_color * n_dot_l;
-
-    // Specular (Blinn-Phong approximation for PBR feel)
-    let view_dir = normalize(camera.camera_pos - in.world_pos);
-    let half_dir = normalize(light_dir + view_dir);
-    let spec_power = mix(16.0, 256.0, 1.0 - roughness);
-    let spec = pow(max(dot(normal, half_dir), 0.0), spec_power);
-    let fresnel = metallic + (1.0 - metallic) * pow(1.0 - max(dot(view_dir, half_dir), 0.0), 5.0);
-    let specular = light_color * spec * fresnel;
-
-    // Ambient
-    let ambient = albedo * vec3<f32>(0.15, 0.15, 0.2);
-
-    // Emissive
-    let emissive = material.emissive;
-
-    let final_color = ambient + diffuse + specular + emissive;
-
-    // Simple tone mapping
-    let mapped = final_color / (final_color + vec3<f32>(1.0));
-
-    return vec4<f32>(mapped, 1.0);
-}
-"#;
-
 /// PBR rendering system with Forward+ pipeline
 pub fn lod_update_system(
     mut lod_entities: Query<(&mut MeshRenderer, &Lod, &Transform)>,
@@ -266,11 +290,7 @@ pub fn lod_update_system(
     if let Some((_, cam_transform)) = cameras.iter().next() {
         for (renderer, lod, transform) in lod_entities.iter_mut() {
             let distance = (transform.translation - cam_transform.translation).length();
-            let lod_level = lod
-                .distances
-                .iter()
-                .position(|&d| distance < d)
-                .unwrap_or(lod.distances.len());
+            let lod_level = lod.level_at(distance);
             if lod_level < lod.meshes.len() {
                 renderer.mesh = lod.meshes[lod_level].clone();
             }
@@ -278,407 +298,82 @@ pub fn lod_update_system(
     }
 }
 
-/// PBR rendering system with Forward+ pipeline
-pub fn render_system(
-    gpu: ResMut<GpuContext>,
-    mut cache: ResMut<PipelineCache>,
-    _uniform_buffer: ResMut<CameraUniformBuffer>,
-    asset_server: Res<AssetServer>,
-    cameras: Query<(&Camera, &Transform)>,
-    meshes: Query<(&Handle<Mesh>, &Transform, &PbrMaterial)>,
-    _window: Res<luminara_window::Window>,
-    post_process: Res<PostProcessResources>,
-) {
-    let frame = match gpu.surface.get_current_texture() {
-        Ok(frame) => frame,
-        Err(e) => {
-            log::error!("Failed to get surface texture: {:?}", e);
-            return;
-        }
+/// Renders a frame by executing the [`RenderGraph`] into the surface texture,
+/// or the offscreen frame of a headless [`GpuContext`]
+pub fn render_system(world: &mut World) {
+    let world: &World = world;
+    let Some(gpu) = world.get_resource::<GpuContext>() else {
+        return;
     };
-    let view = frame
-        .texture
-        .create_view(&wgpu::TextureViewDescriptor::default());
-
-    let frame_size = frame.texture.size();
-    let render_width = frame_size.width.max(1);
-    let render_height = frame_size.height.max(1);
-
-    let clear_color = cameras
-        .iter()
-        .next()
-        .map(|(cam, _)| cam.clear_color)
-        .unwrap_or(Color::rgba(0.1, 0.1, 0.15, 1.0));
+    let Some((frame, view)) = gpu.begin_frame() else {
+        return;
+    };
+    let size = frame.texture().size();
+    render_graph_into(world, &gpu, &view, size.width, size.height);
+    gpu.end_frame(frame);
+}
 
-    let depth_texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
-        label: Some("Depth Texture"),
+/// Render the world into a new `width`×`height` texture in the frame format
+/// and read it back, e.g. for thumbnails or screenshots. Works with window
+/// and headless contexts alike.
+pub fn capture_world(
+    world: &World,
+    width: u32,
+    height: u32,
+) -> Result<image::RgbaImage, RenderError> {
+    let gpu = world
+        .get_resource::<GpuContext>()
+        .ok_or_else(|| RenderError::Capture("no GpuContext".to_string()))?;
+    let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Capture Target"),
         size: wgpu::Extent3d {
-            width: render_width,
-            height: render_height,
+            width: width.max(1),
+            height: height.max(1),
             depth_or_array_layers: 1,
         },
         mip_level_count: 1,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Depth32Float,
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: gpu.surface_config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
         view_formats: &[],
     });
-    let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    render_graph_into(world, &gpu, &view, width.max(1), height.max(1));
+    gpu.read_texture(&texture)
+}
 
+fn render_graph_into(
+    world: &World,
+    gpu: &GpuContext,
+    view: &wgpu::TextureView,
+    width: u32,
+    height: u32,
+) {
+    let Some(mut graph) = world.get_resource_mut::<RenderGraph>() else {
+        return;
+    };
     let mut encoder = gpu
         .device
         .create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
 
-    // PBR rendering
-    if let Some((camera, cam_transform)) = cameras.iter().next() {
-        if cache.get_pipeline("pbr_lite").is_none() {
-            // Create pipeline
-            let shader_module = gpu
-                .device
-                .create_shader_module(wgpu::ShaderModuleDescriptor {
-                    label: Some("PBR Shader"),
-                    source: wgpu::ShaderSource::Wgsl(PBR_SHADER_SOURCE.into()),
-                });
-
-            // Create bind group layouts
-            let camera_layout =
-                gpu.device
-                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                        label: Some("Camera Layout"),
-                        entries: &[wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }],
-                    });
-
-            let model_layout =
-                gpu.device
-                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                        label: Some("Model Layout"),
-                        entries: &[wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }],
-                    });
-
-            let material_layout =
-                gpu.device
-                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                        label: Some("Material Layout"),
-                        entries: &[
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 0,
-                                visibility: wgpu::ShaderStages::FRAGMENT,
-                                ty: wgpu::BindingType::Buffer {
-                                    ty: wgpu::BufferBindingType::Uniform,
-                                    has_dynamic_offset: false,
-                                    min_binding_size: None,
-                                },
-                                count: None,
-                            },
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 1,
-                                visibility: wgpu::ShaderStages::FRAGMENT,
-                                ty: wgpu::BindingType::Texture {
-                                    sample_type: wgpu::TextureSampleType::Float {
-                                        filterable: true,
-                                    },
-                                    view_dimension: wgpu::TextureViewDimension::D2,
-                                    multisampled: false,
-                                },
-                                count: None,
-                            },
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 2,
-                                visibility: wgpu::ShaderStages::FRAGMENT,
-                                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                                count: None,
-                            },
-                        ],
-                    });
-
-            let pipeline_layout =
-                gpu.device
-                    .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                        label: Some("PBR Pipeline Layout"),
-                        bind_group_layouts: &[&camera_layout, &model_layout, &material_layout],
-                        push_constant_ranges: &[],
-                    });
-
-            let pipeline = gpu
-                .device
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("PBR Pipeline"),
-                    layout: Some(&pipeline_layout),
-                    vertex: wgpu::VertexState {
-                        module: &shader_module,
-                        entry_point: "vs_main",
-                        buffers: &[Vertex::desc()],
-                        compilation_options: Default::default(),
-                    },
-                    fragment: Some(wgpu::FragmentState {
-                        module: &shader_module,
-                        entry_point: "fs_main",
-                        targets: &[Some(wgpu::ColorTargetState {
-                            format: gpu.surface_config.format,
-                            blend: None,
-                            write_mask: wgpu::ColorWrites::ALL,
-                        })],
-                        compilation_options: Default::default(),
-                    }),
-                    primitive: wgpu::PrimitiveState {
-                        topology: wgpu::PrimitiveTopology::TriangleList,
-                        strip_index_format: None,
-                        front_face: wgpu::FrontFace::Ccw,
-                        cull_mode: Some(wgpu::Face::Back),
-                        polygon_mode: wgpu::PolygonMode::Fill,
-                        unclipped_depth: false,
-                        conservative: false,
-                    },
-                    depth_stencil: Some(wgpu::DepthStencilState {
-                        format: wgpu::TextureFormat::Depth32Float,
-                        depth_write_enabled: true,
-                        depth_compare: wgpu::CompareFunction::Less,
-                        stencil: wgpu::StencilState::default(),
-                        bias: wgpu::DepthBiasState::default(),
-                    }),
-                    multisample: wgpu::MultisampleState::default(),
-                    multiview: None,
-                    cache: None,
-                });
-
-            cache.insert_pipeline(
-                "pbr_lite".to_string(),
-                pipeline,
-                vec![camera_layout, model_layout, material_layout],
-            );
-        }
-
-        let pbr_pipeline = cache.get_pipeline("pbr_lite").unwrap();
-
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Main Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: clear_color.r as f64,
-                        g: clear_color.g as f64,
-                        b: clear_color.b as f64,
-                        a: clear_color.a as f64,
-                    }),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &depth_view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
-                }),
-                stencil_ops: None,
-            }),
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        });
-
-        // Camera uniform - must match shader CameraUniform: mat4x4 + vec3 + padding = 80 bytes
-        let view_matrix = cam_transform.compute_matrix().inverse();
-        let aspect = render_width as f32 / render_height as f32;
-        let proj_matrix = camera.projection_matrix(aspect);
-        let _view_proj = proj_matrix * view_matrix;
-
-        // Serialize camera data: view_proj (64 bytes) + camera_pos (12 bytes) + padding (4 bytes) = 80 bytes
-        let vp_cols = _view_proj.to_cols_array();
-        let cam_pos = cam_transform.translation;
-        let mut camera_data = [0u8; 80];
-        camera_data[0..64].copy_from_slice(bytemuck::cast_slice(&vp_cols));
-        camera_data[64..76]
-            .copy_from_slice(bytemuck::cast_slice(&[cam_pos.x, cam_pos.y, cam_pos.z]));
-        // bytes 76..80 remain zero (padding)
-        let camera_buffer = gpu
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Camera Buffer"),
-                contents: &camera_data,
-                usage: wgpu::BufferUsages::UNIFORM,
-            });
-
-        let camera_bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Camera Bind Group"),
-            layout: &pbr_pipeline.bind_group_layouts[0],
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.as_entire_binding(),
-            }],
-        });
-
-        // Create default white texture
-        let default_texture_data = TextureData {
-            width: 1,
-            height: 1,
-            data: vec![255, 255, 255, 255],
-            format: TextureFormat::Rgba8,
-        };
-        let mut default_tex = Texture::new(default_texture_data);
-        default_tex.upload(&gpu.device, &gpu.queue);
-
-        render_pass.set_pipeline(&pbr_pipeline.pipeline);
-        render_pass.set_bind_group(0, &camera_bind_group, &[]);
-
-        // Render meshes
-        for (mesh_handle, mesh_transform, pbr_mat) in meshes.iter() {
-            if let Some(mesh) = asset_server.get(mesh_handle) {
-                let vb_guard = mesh.vertex_buffer.read().unwrap();
-                let ib_guard = mesh.index_buffer.read().unwrap();
-
-                if let (Some(vb), Some(ib)) = (vb_guard.as_ref(), ib_guard.as_ref()) {
-                    let _model_matrix = mesh_transform.compute_matrix();
-                    let model_cols = _model_matrix.to_cols_array();
-                    let model_data: &[u8] = bytemuck::cast_slice(&model_cols);
-                    let model_buffer =
-                        gpu.device
-                            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                                label: Some("Model Buffer"),
-                                contents: &model_data,
-                                usage: wgpu::BufferUsages::UNIFORM,
-                            });
-
-                    let model_bind_group =
-                        gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                            label: Some("Model Bind Group"),
-                            layout: &pbr_pipeline.bind_group_layouts[1],
-                            entries: &[wgpu::BindGroupEntry {
-                                binding: 0,
-                                resource: model_buffer.as_entire_binding(),
-                            }],
-                        });
-
-                    let has_texture = if let Some(texture_handle) = &pbr_mat.albedo_texture {
-                        asset_server.get(texture_handle).is_some()
-                    } else {
-                        false
-                    };
-                    let mat_uniform = MaterialUniformData {
-                        albedo: [
-                            pbr_mat.albedo.r,
-                            pbr_mat.albedo.g,
-                            pbr_mat.albedo.b,
-                            pbr_mat.albedo.a,
-                        ],
-                        metallic: pbr_mat.metallic,
-                        roughness: pbr_mat.roughness,
-                        _pad0: [0.0; 2],
-                        emissive: [pbr_mat.emissive.r, pbr_mat.emissive.g, pbr_mat.emissive.b],
-                        has_albedo_texture: if has_texture { 1.0 } else { 0.0 },
-                    };
-                    let mat_array = [mat_uniform];
-                    let mat_data = bytemuck::cast_slice(&mat_array);
-                    let mat_buffer =
-                        gpu.device
-                            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                                label: Some("Material Buffer"),
-                                contents: mat_data,
-                                usage: wgpu::BufferUsages::UNIFORM,
-                            });
-
-                    // Texture handling - use default texture for now
-                    let tex_view = default_tex.view.as_ref().unwrap();
-                    let tex_sampler = default_tex.sampler.as_ref().unwrap();
-
-                    let mat_bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                        label: Some("Material Bind Group"),
-                        layout: &pbr_pipeline.bind_group_layouts[2],
-                        entries: &[
-                            wgpu::BindGroupEntry {
-                                binding: 0,
-                                resource: mat_buffer.as_entire_binding(),
-                            },
-                            wgpu::BindGroupEntry {
-                                binding: 1,
-                                resource: wgpu::BindingResource::TextureView(tex_view),
-                            },
-                            wgpu::BindGroupEntry {
-                                binding: 2,
-                                resource: wgpu::BindingResource::Sampler(tex_sampler),
-                            },
-                        ],
-                    });
-
-                    render_pass.set_bind_group(1, &model_bind_group, &[]);
-                    render_pass.set_bind_group(2, &mat_bind_group, &[]);
-                    render_pass.set_vertex_buffer(0, vb.slice(..));
-                    render_pass.set_index_buffer(ib.slice(..), wgpu::IndexFormat::Uint32);
-                    render_pass.draw_indexed(0..mesh.indices.len() as u32, 0, 0..1);
-                }
-            }
-        }
-    }
-
-    // Post-processing (bloom)
-    if post_process.bloom_extract_pipeline.is_some() {
-        // Create intermediate textures for bloom
-        let bloom_texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Bloom Texture"),
-            size: wgpu::Extent3d {
-                width: render_width / 2,
-                height: render_height / 2,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba16Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-        let bloom_view = bloom_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        // Bloom extract pass
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Bloom Extract Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &bloom_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            if let Some(pipeline) = &post_process.bloom_extract_pipeline {
-                render_pass.set_pipeline(pipeline);
-                // Bind the main render target as input
-                // This would require bind group setup for bloom extract
-                // For now, skip detailed implementation
-            }
-        }
-
-        // Bloom blur and combine would follow...
-        // This is a placeholder for full bloom implementation
+    let render_frame = RenderFrame {
+        device: &gpu.device,
+        queue: &gpu.queue,
+        view,
+        format: gpu.surface_config.format,
+        width: width.max(1),
+        height: height.max(1),
+        world: Some(world),
+    };
+    if let Err(e) = graph.execute(&render_frame, &mut encoder) {
+        log::error!("Render graph failed: {}", e);
     }
 
     gpu.queue.submit(std::iter::once(encoder.finish()));
-    gpu.end_frame(frame);
+    if let Some(mut targets) = world.get_resource_mut::<RenderTargets>() {
+        targets.finish_readbacks(&gpu.device);
+    }
 }
@@ -8,8 +8,8 @@
 use luminara_asset::{AssetServer, Handle};
 use luminara_core::shared_types::{Query, Res, ResMut, Resource};
 use luminara_math::{Mat4, Transform, Vec3};
-use std::collections::HashMap;
 
+use crate::mesh_processing::{self, SimplifyOptions};
 use crate::{Camera, Mesh, AABB};
 
 /// LOD configuration resource
@@ -283,12 +283,18 @@ pub struct LodGenerator {
     /// Target reduction ratios for each LOD level
     /// Default: [1.0, 0.5, 0.25, 0.125, 0.0625] (50%, 25%, 12.5%, 6.25%)
     pub reduction_ratios: Vec<f32>,
+
+    /// Largest simplification error of a level, as a fraction of the mesh's
+    /// bounding box diagonal. Levels stop short of their ratio rather than
+    /// exceed it.
+    pub max_error: f32,
 }
 
 impl Default for LodGenerator {
     fn default() -> Self {
         Self {
             reduction_ratios: vec![1.0, 0.5, 0.25, 0.125, 0.0625],
+            max_error: 0.05,
         }
     }
 }
@@ -315,85 +321,23 @@ impl LodGenerator {
         lod_meshes
     }
     
-    /// Simplify a mesh using edge collapse algorithm
-    pub fn simplify_mesh(&self, source: &Mesh, target_ratio: f32) -> Mesh {
-        // Simple decimation algorithm: keep every Nth vertex
-        // In production, use proper mesh simplification (quadric error metrics)
-        
-        let target_vertex_count = (source.vertices.len() as f32 * target_ratio).max(3.0) as usize;
-        let target_triangle_count = (source.indices.len() as f32 / 3.0 * target_ratio).max(1.0) as usize * 3;
-        
-        if target_vertex_count >= source.vertices.len() {
-            return Mesh::new(source.vertices.clone(), source.indices.clone());
-        }
-        
-        // Simple uniform sampling for demonstration
-        // Real implementation would use quadric error metrics or similar
-        let step = source.vertices.len() / target_vertex_count;
-        let step = step.max(1);
-        
-        let mut new_vertices = Vec::new();
-        let mut vertex_map = HashMap::new();
-        
-        for (i, vertex) in source.vertices.iter().enumerate() {
-            if i % step == 0 || new_vertices.len() < 3 {
-                vertex_map.insert(i, new_vertices.len());
-                new_vertices.push(*vertex);
-            }
-        }
-        
-        // Remap indices
-        let mut new_indices = Vec::new();
-        for chunk in source.indices.chunks(3) {
-            if chunk.len() == 3 {
-                let i0 = chunk[0] as usize;
-                let i1 = chunk[1] as usize;
-                let i2 = chunk[2] as usize;
-                
-                // Find closest vertices in simplified mesh
-                let new_i0 = self.find_closest_vertex(i0, &vertex_map);
-                let new_i1 = self.find_closest_vertex(i1, &vertex_map);
-                let new_i2 = self.find_closest_vertex(i2, &vertex_map);
-                
-                // Skip degenerate triangles
-                if new_i0 != new_i1 && new_i1 != new_i2 && new_i0 != new_i2 {
-                    new_indices.push(new_i0 as u32);
-                    new_indices.push(new_i1 as u32);
-                    new_indices.push(new_i2 as u32);
-                    
-                    if new_indices.len() >= target_triangle_count {
-                        break;
-                    }
-                }
-            }
-        }
-        
-        // Ensure we have at least one triangle
-        if new_indices.len() < 3 && new_vertices.len() >= 3 {
-            new_indices = vec![0, 1, 2];
-        }
-        
-        Mesh::new(new_vertices, new_indices)
+    /// The coarsest level alone, to stand in for `source` as an
+    /// [`Occluder`](crate::Occluder)
+    pub fn occluder_mesh(&self, source: &Mesh) -> Mesh {
+        let ratio = self.reduction_ratios.iter().copied().fold(1.0, f32::min);
+        self.simplify_mesh(source, ratio)
     }
-    
-    fn find_closest_vertex(&self, original_index: usize, vertex_map: &HashMap<usize, usize>) -> usize {
-        // Find the closest vertex that exists in the simplified mesh
-        if let Some(&new_index) = vertex_map.get(&original_index) {
-            return new_index;
-        }
-        
-        // Search nearby vertices
-        for offset in 1..100 {
-            if let Some(&new_index) = vertex_map.get(&original_index.saturating_sub(offset)) {
-                return new_index;
-            }
-            if let Some(&new_index) = vertex_map.get(&(original_index + offset)) {
-                return new_index;
-            }
-        }
-        
-        // Fallback to first vertex
-        0
+
+    /// Simplify a mesh with quadric error metric edge collapses, keeping UV
+    /// seams and borders, then reorder it for the GPU
+    pub fn simplify_mesh(&self, source: &Mesh, target_ratio: f32) -> Mesh {
+        let options = SimplifyOptions {
+            max_error: self.max_error,
+            ..SimplifyOptions::ratio(source.indices.len(), target_ratio)
+        };
+        let simplified = mesh_processing::simplify(&source.vertices, &source.indices, &options);
+        let (vertices, indices) = mesh_processing::optimize(&source.vertices, &simplified.indices);
+        Mesh::new(vertices, indices)
     }
 }
 
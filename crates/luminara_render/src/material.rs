@@ -1,17 +1,1294 @@
+// Pluggable materials: uniform layouts derived through reflection, texture
+// bindings, pipeline keys and the `.material.ron` asset format
+use crate::draw_call_batcher::{DrawCallSortKey, MaterialKey};
+use crate::image_based_lighting::environment_layout;
+use crate::render_graph::slots;
+use crate::render_world::spawn_extracted;
 use crate::shader::Shader;
+use crate::shader_preprocessor::{ComposedShader, ShaderComposer, ShaderDefs, ShaderProcessError};
 use crate::texture::Texture;
-use luminara_core::Reflect;
-use luminara_math::Color;
-use std::sync::Arc;
+use crate::{
+    JointPalette, Mesh, PbrMaterial, PipelineCache, RenderLayers, SkinWeights, SkinnedMesh,
+};
+use luminara_asset::{Asset, AssetId, AssetLoadError, AssetLoader, AssetServer, Handle};
+use luminara_core::shared_types::{App, AppInterface, CoreStage, Plugin, Query, Resource, World};
+use luminara_core::system::ExclusiveMarker;
+use luminara_core::{Component, Entity, Reflect};
+use luminara_math::{Color, Quat, Transform, Vec3};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-pub struct Material {
+/// A surface description that can be drawn by the forward pass.
+///
+/// Materials are assets: entities reference them with a `Handle<M>`
+/// component, so many meshes share one material. The uniform block and the
+/// texture bindings are derived from the reflected fields by default:
+///
+/// - `f32`, `i32`, `u32`, `bool`, `Vec3`, `Quat` and `Color` fields become
+///   members of the `material` uniform, in declaration order
+/// - `Handle<Texture>` and `Option<Handle<Texture>>` fields become a texture
+///   and a sampler named `<field>` and `<field>_sampler`
+///
+/// The shader only contains the entry points `vs_main` and `fs_main`; the
+/// bind groups, the uniform struct and `VertexInput` are generated by
+/// [`material_prelude`] and prepended before compilation. Shaders go through
+/// the [`ShaderComposer`], so they may `#import` modules and branch on
+/// [`Material::shader_defs`]. Skinned meshes add `SKINNED`, for which
+/// `vs_main` also takes the `SkinInput` of `luminara::skinning`.
+pub trait Material: Asset + Reflect + Sized {
+    /// WGSL entry points of this material
+    fn shader(&self) -> Handle<Shader>;
+
+    /// Layout of the `material` uniform block
+    fn uniform_layout(&self) -> UniformLayout {
+        UniformLayout::from_reflect(self)
+    }
+
+    /// Contents of the `material` uniform block
+    fn uniform_data(&self) -> Vec<u8> {
+        self.uniform_layout().write(self)
+    }
+
+    /// Textures bound after the uniform block, in binding order
+    fn textures(&self) -> Vec<TextureBinding> {
+        TextureBinding::from_reflect(self)
+    }
+
+    /// Fixed-function state of the pipeline drawing this material
+    fn pipeline_key(&self) -> MaterialPipelineKey {
+        MaterialPipelineKey::default()
+    }
+
+    /// Shader permutation of this material, tested with `#ifdef` in its shader
+    fn shader_defs(&self) -> ShaderDefs {
+        ShaderDefs::default()
+    }
+
+    /// Quantized properties used to sort draws sharing a shader and textures
+    fn material_key(&self) -> MaterialKey {
+        MaterialKey::from_scalars(&self.uniform_layout().scalars(self))
+    }
+}
+
+/// Type of a member of a material uniform block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UniformType {
+    F32,
+    I32,
+    U32,
+    /// Stored as `u32`, since WGSL booleans are not host-shareable
+    Bool,
+    Vec3,
+    /// `Color` and `Quat`
+    Vec4,
+}
+
+impl UniformType {
+    /// Uniform type of a reflected field, if it can live in a uniform block
+    pub fn of(type_id: TypeId) -> Option<Self> {
+        if type_id == TypeId::of::<f32>() {
+            Some(Self::F32)
+        } else if type_id == TypeId::of::<i32>() {
+            Some(Self::I32)
+        } else if type_id == TypeId::of::<u32>() {
+            Some(Self::U32)
+        } else if type_id == TypeId::of::<bool>() {
+            Some(Self::Bool)
+        } else if type_id == TypeId::of::<Vec3>() {
+            Some(Self::Vec3)
+        } else if type_id == TypeId::of::<Color>() || type_id == TypeId::of::<Quat>() {
+            Some(Self::Vec4)
+        } else {
+            None
+        }
+    }
+
+    /// Alignment in the uniform address space
+    pub fn align(self) -> u32 {
+        match self {
+            Self::Vec3 | Self::Vec4 => 16,
+            _ => 4,
+        }
+    }
+
+    pub fn size(self) -> u32 {
+        match self {
+            Self::Vec3 => 12,
+            Self::Vec4 => 16,
+            _ => 4,
+        }
+    }
+
+    pub fn wgsl(self) -> &'static str {
+        match self {
+            Self::F32 => "f32",
+            Self::I32 => "i32",
+            Self::U32 | Self::Bool => "u32",
+            Self::Vec3 => "vec3<f32>",
+            Self::Vec4 => "vec4<f32>",
+        }
+    }
+
+    fn components(self) -> usize {
+        match self {
+            Self::Vec3 => 3,
+            Self::Vec4 => 4,
+            _ => 1,
+        }
+    }
+}
+
+/// A member of a material uniform block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformField {
+    pub name: String,
+    pub ty: UniformType,
+    /// Byte offset inside the block
+    pub offset: u32,
+}
+
+/// Memory layout of a material uniform block, following WGSL uniform
+/// alignment rules
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UniformLayout {
+    pub fields: Vec<UniformField>,
+    /// Size in bytes, a multiple of 16
+    pub size: u32,
+}
+
+impl UniformLayout {
+    /// Lay out every reflected field with a [`UniformType`]. Other fields,
+    /// such as texture handles, are skipped.
+    pub fn from_reflect(value: &dyn Reflect) -> Self {
+        let mut layout = Self::default();
+        for field in &value.type_info().fields {
+            if let Some(ty) = UniformType::of(field.type_id) {
+                layout.push(&field.name, ty);
+            }
+        }
+        layout
+    }
+
+    /// Append a member after the existing ones
+    pub fn push(&mut self, name: &str, ty: UniformType) -> &mut Self {
+        let end = self
+            .fields
+            .last()
+            .map_or(0, |field| field.offset + field.ty.size());
+        let offset = end.next_multiple_of(ty.align());
+        self.fields.push(UniformField {
+            name: name.to_string(),
+            ty,
+            offset,
+        });
+        self.size = (offset + ty.size()).next_multiple_of(16);
+        self
+    }
+
+    /// Pack the reflected fields of `value` into a buffer of `size` bytes
+    pub fn write(&self, value: &dyn Reflect) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.size.max(16) as usize];
+        for field in &self.fields {
+            let Some(reflected) = value.field(&field.name) else {
+                continue;
+            };
+            let mut offset = field.offset as usize;
+            for word in uniform_words(field.ty, reflected) {
+                bytes[offset..offset + 4].copy_from_slice(&word);
+                offset += 4;
+            }
+        }
+        bytes
+    }
+
+    /// Components of the member `name` of a block packed by [`Self::write`],
+    /// as `f32`
+    pub fn read(&self, bytes: &[u8], name: &str) -> Option<Vec<f32>> {
+        let field = self.fields.iter().find(|field| field.name == name)?;
+        let start = field.offset as usize;
+        let words = bytes.get(start..start + field.ty.size() as usize)?;
+        Some(
+            words
+                .chunks_exact(4)
+                .map(|word| {
+                    let word = [word[0], word[1], word[2], word[3]];
+                    match field.ty {
+                        UniformType::I32 => i32::from_le_bytes(word) as f32,
+                        UniformType::U32 | UniformType::Bool => u32::from_le_bytes(word) as f32,
+                        _ => f32::from_le_bytes(word),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Every component of the reflected fields of `value` as `f32`, in
+    /// layout order. Used to derive sort keys.
+    pub fn scalars(&self, value: &dyn Reflect) -> Vec<f32> {
+        let mut scalars = Vec::new();
+        for field in &self.fields {
+            match value.field(&field.name) {
+                Some(reflected) => scalars.extend(uniform_scalars(field.ty, reflected)),
+                None => scalars.extend(std::iter::repeat_n(0.0, field.ty.components())),
+            }
+        }
+        scalars
+    }
+
+    /// WGSL declaration of the uniform block
+    pub fn wgsl_struct(&self, name: &str) -> String {
+        let mut wgsl = format!("struct {} {{\n", name);
+        if self.fields.is_empty() {
+            // WGSL structs need at least one member
+            wgsl.push_str("    _unused: u32,\n");
+        }
+        for field in &self.fields {
+            wgsl.push_str(&format!("    {}: {},\n", field.name, field.ty.wgsl()));
+        }
+        wgsl.push_str("};\n");
+        wgsl
+    }
+}
+
+fn uniform_scalars(ty: UniformType, value: &dyn Reflect) -> Vec<f32> {
+    let any = value.as_any();
+    match ty {
+        UniformType::F32 => any.downcast_ref::<f32>().map(|v| vec![*v]),
+        UniformType::I32 => any.downcast_ref::<i32>().map(|v| vec![*v as f32]),
+        UniformType::U32 => any.downcast_ref::<u32>().map(|v| vec![*v as f32]),
+        UniformType::Bool => any
+            .downcast_ref::<bool>()
+            .map(|v| vec![if *v { 1.0 } else { 0.0 }]),
+        UniformType::Vec3 => any.downcast_ref::<Vec3>().map(|v| v.to_array().to_vec()),
+        UniformType::Vec4 => any
+            .downcast_ref::<Color>()
+            .map(|c| vec![c.r, c.g, c.b, c.a])
+            .or_else(|| any.downcast_ref::<Quat>().map(|q| q.to_array().to_vec())),
+    }
+    .unwrap_or_else(|| vec![0.0; ty.components()])
+}
+
+fn uniform_words(ty: UniformType, value: &dyn Reflect) -> Vec<[u8; 4]> {
+    let any = value.as_any();
+    match ty {
+        UniformType::I32 => vec![any
+            .downcast_ref::<i32>()
+            .copied()
+            .unwrap_or(0)
+            .to_le_bytes()],
+        UniformType::U32 => vec![any
+            .downcast_ref::<u32>()
+            .copied()
+            .unwrap_or(0)
+            .to_le_bytes()],
+        UniformType::Bool => {
+            vec![(any.downcast_ref::<bool>().copied().unwrap_or(false) as u32).to_le_bytes()]
+        }
+        _ => uniform_scalars(ty, value)
+            .into_iter()
+            .map(f32::to_le_bytes)
+            .collect(),
+    }
+}
+
+/// A texture slot of a material
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureBinding {
+    /// WGSL name of the texture; its sampler is `<name>_sampler`
+    pub name: String,
+    /// Unset slots bind a 1x1 white texture
+    pub texture: Option<Handle<Texture>>,
+}
+
+impl TextureBinding {
+    /// Collect every `Handle<Texture>` and `Option<Handle<Texture>>` field
+    pub fn from_reflect(value: &dyn Reflect) -> Vec<Self> {
+        value
+            .type_info()
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let reflected = value.field(&field.name)?.as_any();
+                let texture = if field.type_id == TypeId::of::<Option<Handle<Texture>>>() {
+                    reflected.downcast_ref::<Option<Handle<Texture>>>()?.clone()
+                } else if field.type_id == TypeId::of::<Handle<Texture>>() {
+                    Some(reflected.downcast_ref::<Handle<Texture>>()?.clone())
+                } else {
+                    return None;
+                };
+                Some(Self {
+                    name: field.name.clone(),
+                    texture,
+                })
+            })
+            .collect()
+    }
+
+    /// Point the texture slot `name` of `value` at `texture`
+    pub fn set(
+        value: &mut dyn Reflect,
+        name: &str,
+        texture: Handle<Texture>,
+    ) -> Result<(), AssetLoadError> {
+        let field = value
+            .field_mut(name)
+            .ok_or_else(|| AssetLoadError::Parse(format!("Unknown texture slot '{}'", name)))?
+            .as_any_mut();
+        if let Some(slot) = field.downcast_mut::<Option<Handle<Texture>>>() {
+            *slot = Some(texture);
+        } else if let Some(slot) = field.downcast_mut::<Handle<Texture>>() {
+            *slot = texture;
+        } else {
+            return Err(AssetLoadError::Parse(format!(
+                "Field '{}' is not a texture slot",
+                name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// How a material's color is combined with the target
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    luminara_reflect_derive::Reflect,
+)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    AlphaBlend,
+    Additive,
+    Premultiplied,
+}
+
+impl BlendMode {
+    pub fn blend_state(self) -> Option<wgpu::BlendState> {
+        match self {
+            Self::Opaque => None,
+            Self::AlphaBlend => Some(wgpu::BlendState::ALPHA_BLENDING),
+            Self::Premultiplied => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            Self::Additive => Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            }),
+        }
+    }
+}
+
+/// Which triangle faces are skipped
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    luminara_reflect_derive::Reflect,
+)]
+pub enum CullMode {
+    None,
+    Front,
+    #[default]
+    Back,
+}
+
+impl CullMode {
+    pub fn face(self) -> Option<wgpu::Face> {
+        match self {
+            Self::None => None,
+            Self::Front => Some(wgpu::Face::Front),
+            Self::Back => Some(wgpu::Face::Back),
+        }
+    }
+}
+
+/// Fixed-function state of a material pipeline. Materials with equal keys
+/// and shaders share a pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialPipelineKey {
+    pub blend_mode: BlendMode,
+    pub cull_mode: CullMode,
+    pub depth_write: bool,
+    /// Fragments with an alpha below `alpha_mask / 255` are discarded
+    pub alpha_mask: Option<u8>,
+}
+
+impl Default for MaterialPipelineKey {
+    fn default() -> Self {
+        Self {
+            blend_mode: BlendMode::Opaque,
+            cull_mode: CullMode::Back,
+            depth_write: true,
+            alpha_mask: None,
+        }
+    }
+}
+
+impl MaterialPipelineKey {
+    /// Alpha blended, double sided and without depth writes
+    pub fn transparent() -> Self {
+        Self {
+            blend_mode: BlendMode::AlphaBlend,
+            cull_mode: CullMode::None,
+            depth_write: false,
+            alpha_mask: None,
+        }
+    }
+
+    /// Transparent draws are sorted back to front after all opaque ones
+    pub fn is_transparent(&self) -> bool {
+        self.blend_mode != BlendMode::Opaque
+    }
+
+    pub fn alpha_cutoff(&self) -> Option<f32> {
+        self.alpha_mask.map(|mask| mask as f32 / 255.0)
+    }
+}
+
+/// WGSL declarations shared by every material shader:
+///
+/// - `camera` at group 0 and `model` at group 1
+/// - `material` at group 2, binding 0, followed by a texture and a sampler
+///   per entry of `textures`
+/// - `environment` at group 3, see [`crate::image_based_lighting`], and
+///   `environment_lighting(albedo, normal, view_dir, metallic, roughness)`,
+///   the image-based diffuse and specular light of a surface
+/// - `material_alpha_test(alpha)`, false below the key's alpha mask
+/// - `VertexInput` matching [`crate::Vertex`]
+pub fn material_prelude(
+    layout: &UniformLayout,
+    textures: &[String],
+    key: &MaterialPipelineKey,
+) -> String {
+    let mut wgsl = String::from(
+        "struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    camera_pos: vec3<f32>,
+    _padding: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct ModelTransform {
+    model: mat4x4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> model: ModelTransform;
+
+",
+    );
+    wgsl.push_str(&layout.wgsl_struct("MaterialUniform"));
+    wgsl.push_str("\n@group(2) @binding(0)\nvar<uniform> material: MaterialUniform;\n");
+    for (index, name) in textures.iter().enumerate() {
+        let binding = 1 + index * 2;
+        wgsl.push_str(&format!(
+            "\n@group(2) @binding({})\nvar {}: texture_2d<f32>;\n@group(2) @binding({})\nvar {}_sampler: sampler;\n",
+            binding,
+            name,
+            binding + 1,
+            name
+        ));
+    }
+    wgsl.push_str(ENVIRONMENT_PRELUDE);
+    wgsl.push_str(&format!(
+        "
+const MATERIAL_ALPHA_CUTOFF: f32 = {:?};
+
+fn material_alpha_test(alpha: f32) -> bool {{
+    return alpha >= MATERIAL_ALPHA_CUTOFF;
+}}
+
+struct VertexInput {{
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) tangent: vec4<f32>,
+}};
+
+",
+        key.alpha_cutoff().unwrap_or(0.0)
+    ));
+    wgsl
+}
+
+/// Environment bindings and image-based lighting of the material prelude.
+/// `irradiance` holds SH coefficients already convolved with the cosine
+/// lobe; without an environment the legacy constant ambient is used.
+const ENVIRONMENT_PRELUDE: &str = "
+struct EnvironmentUniform {
+    irradiance: array<vec4<f32>, 9>,
+    weights: vec4<f32>,
+    intensities: vec4<f32>,
+    levels: vec4<f32>,
+};
+
+@group(3) @binding(0)
+var<uniform> environment: EnvironmentUniform;
+@group(3) @binding(1)
+var environment_camera_map: texture_cube<f32>;
+@group(3) @binding(2)
+var environment_inner_map: texture_cube<f32>;
+@group(3) @binding(3)
+var environment_outer_map: texture_cube<f32>;
+@group(3) @binding(4)
+var environment_sampler: sampler;
+@group(3) @binding(5)
+var environment_brdf_lut: texture_2d<f32>;
+
+const DEFAULT_AMBIENT: vec3<f32> = vec3<f32>(0.15, 0.15, 0.2);
+
+fn environment_irradiance(n: vec3<f32>) -> vec3<f32> {
+    let c = environment.irradiance;
+    let irradiance = c[0].rgb * 0.282095
+        + c[1].rgb * (0.488603 * n.y)
+        + c[2].rgb * (0.488603 * n.z)
+        + c[3].rgb * (0.488603 * n.x)
+        + c[4].rgb * (1.092548 * n.x * n.y)
+        + c[5].rgb * (1.092548 * n.y * n.z)
+        + c[6].rgb * (0.315392 * (3.0 * n.z * n.z - 1.0))
+        + c[7].rgb * (1.092548 * n.x * n.z)
+        + c[8].rgb * (0.546274 * (n.x * n.x - n.y * n.y));
+    return max(irradiance, vec3<f32>(0.0));
+}
+
+fn environment_radiance(direction: vec3<f32>, roughness: f32) -> vec3<f32> {
+    let levels = environment.levels * roughness;
+    let camera_light = textureSampleLevel(environment_camera_map, environment_sampler, direction, levels.x).rgb;
+    let inner_light = textureSampleLevel(environment_inner_map, environment_sampler, direction, levels.y).rgb;
+    let outer_light = textureSampleLevel(environment_outer_map, environment_sampler, direction, levels.z).rgb;
+    let outer = mix(
+        camera_light * environment.intensities.x,
+        outer_light * environment.intensities.z,
+        environment.weights.y,
+    );
+    return mix(outer, inner_light * environment.intensities.y, environment.weights.x);
+}
+
+fn environment_lighting(
+    albedo: vec3<f32>,
+    normal: vec3<f32>,
+    view_dir: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+) -> vec3<f32> {
+    if (environment.weights.w == 0.0) {
+        return albedo * DEFAULT_AMBIENT;
+    }
+    let n_dot_v = max(dot(normal, view_dir), 0.0);
+    let brdf = textureSampleLevel(
+        environment_brdf_lut,
+        environment_sampler,
+        vec2<f32>(n_dot_v, roughness),
+        0.0,
+    ).rg;
+    let f0 = mix(vec3<f32>(0.04), albedo, metallic);
+    let specular = environment_radiance(reflect(-view_dir, normal), roughness) * (f0 * brdf.x + brdf.y);
+    let diffuse = albedo * (1.0 - metallic) * environment_irradiance(normal);
+    return diffuse + specular;
+}
+";
+
+/// Handle of the shader asset loaded from `path`
+pub fn shader_handle(path: &str) -> Handle<Shader> {
+    Handle::new(AssetId::from_path(path), 0)
+}
+
+/// Built-in shader of [`PbrMaterial`]
+pub fn pbr_shader() -> Handle<Shader> {
+    Handle::new(AssetId::from_u128(PBR_SHADER_ID), 0)
+}
+
+/// Built-in shader of [`UnlitMaterial`]
+pub fn unlit_shader() -> Handle<Shader> {
+    Handle::new(AssetId::from_u128(UNLIT_SHADER_ID), 0)
+}
+
+/// Built-in shader of [`TerrainMaterial`](crate::TerrainMaterial)
+pub fn terrain_shader() -> Handle<Shader> {
+    Handle::new(AssetId::from_u128(TERRAIN_SHADER_ID), 0)
+}
+
+const PBR_SHADER_ID: u128 = 0x4c75_6d69_6e61_7261_0001_0000_0000_0001;
+const UNLIT_SHADER_ID: u128 = 0x4c75_6d69_6e61_7261_0001_0000_0000_0002;
+const TERRAIN_SHADER_ID: u128 = 0x4c75_6d69_6e61_7261_0001_0000_0000_0003;
+
+/// Add the built-in material shaders to the asset server
+pub fn register_builtin_shaders(asset_server: &AssetServer) {
+    asset_server.insert(
+        pbr_shader().id(),
+        Shader::from_wgsl(include_str!("../shaders/materials/pbr.wgsl")),
+    );
+    asset_server.insert(
+        unlit_shader().id(),
+        Shader::from_wgsl(include_str!("../shaders/materials/unlit.wgsl")),
+    );
+    asset_server.insert(
+        terrain_shader().id(),
+        Shader::from_wgsl(include_str!("../shaders/materials/terrain.wgsl")),
+    );
+}
+
+impl Material for PbrMaterial {
+    fn shader(&self) -> Handle<Shader> {
+        pbr_shader()
+    }
+
+    fn pipeline_key(&self) -> MaterialPipelineKey {
+        if self.albedo.a < 1.0 {
+            MaterialPipelineKey {
+                blend_mode: BlendMode::AlphaBlend,
+                depth_write: false,
+                ..Default::default()
+            }
+        } else {
+            MaterialPipelineKey::default()
+        }
+    }
+
+    fn shader_defs(&self) -> ShaderDefs {
+        let mut defs = ShaderDefs::new();
+        if self.normal_texture.is_some() {
+            defs.set("NORMAL_MAP", "true");
+        }
+        defs
+    }
+
+    fn material_key(&self) -> MaterialKey {
+        MaterialKey::from_material(self)
+    }
+}
+
+/// Flat shaded material, unaffected by lights
+#[derive(Debug, Clone, Serialize, Deserialize, luminara_reflect_derive::Reflect)]
+pub struct UnlitMaterial {
+    pub color: Color,
+    #[serde(default)]
+    pub color_texture: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+}
+
+impl Default for UnlitMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            color_texture: None,
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+}
+
+impl Asset for UnlitMaterial {
+    fn type_name() -> &'static str {
+        "UnlitMaterial"
+    }
+}
+
+impl Material for UnlitMaterial {
+    fn shader(&self) -> Handle<Shader> {
+        unlit_shader()
+    }
+
+    fn pipeline_key(&self) -> MaterialPipelineKey {
+        MaterialPipelineKey {
+            blend_mode: self.blend_mode,
+            depth_write: self.blend_mode == BlendMode::Opaque,
+            ..Default::default()
+        }
+    }
+}
+
+/// On-disk form of a material: the material's own fields plus texture slots
+/// given as asset paths, e.g.
+///
+/// ```ron
+/// (
+///     material: (color: (r: 1.0, g: 0.5, b: 0.2, a: 1.0)),
+///     textures: { "color_texture": "textures/stone.png" },
+/// )
+/// ```
+#[derive(Deserialize)]
+struct MaterialFile<M> {
+    material: M,
+    #[serde(default)]
+    textures: HashMap<String, String>,
+}
+
+/// Texture paths referenced by loaded material, atlas and map files, waiting
+/// to be loaded by [`load_material_textures_system`]
+#[derive(Clone, Default)]
+pub struct MaterialTextureQueue(Arc<Mutex<Vec<String>>>);
+
+impl Resource for MaterialTextureQueue {}
+
+impl MaterialTextureQueue {
+    pub(crate) fn push(&self, path: String) {
+        self.0.lock().unwrap().push(path);
+    }
+
+    /// Remove and return every queued path
+    pub fn drain(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// Loads `.material.ron` files as `M` assets
+pub struct MaterialLoader<M> {
+    textures: MaterialTextureQueue,
+    _marker: PhantomData<fn() -> M>,
+}
+
+impl<M> MaterialLoader<M> {
+    pub fn new(textures: MaterialTextureQueue) -> Self {
+        Self {
+            textures,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<M: Material + DeserializeOwned> AssetLoader for MaterialLoader<M> {
+    type Asset = M;
+
+    fn extensions(&self) -> &[&str] {
+        &["material.ron"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let source = std::str::from_utf8(bytes)
+            .map_err(|e| AssetLoadError::Parse(format!("Material is not UTF-8: {}", e)))?;
+        let file: MaterialFile<M> =
+            ron::from_str(source).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+
+        let mut material = file.material;
+        for (slot, path) in file.textures {
+            // Same id as `AssetServer::load`, so the slot resolves once the
+            // queued load completes
+            TextureBinding::set(
+                &mut material,
+                &slot,
+                Handle::new(AssetId::from_path(&path), 0),
+            )?;
+            self.textures.push(path);
+        }
+        Ok(material)
+    }
+}
+
+/// Start loading the textures referenced by material files
+pub fn load_material_textures_system(world: &mut World) {
+    let Some(queue) = world
+        .get_resource::<MaterialTextureQueue>()
+        .map(|q| q.clone())
+    else {
+        return;
+    };
+    let paths = queue.drain();
+    if paths.is_empty() {
+        return;
+    }
+    if let Some(asset_server) = world.get_resource::<AssetServer>() {
+        for path in paths {
+            let _ = asset_server.load::<Texture>(&path);
+        }
+    }
+}
+
+/// A mesh ready to be drawn with its material, resolved on the CPU
+#[derive(Debug, Clone)]
+pub struct PreparedMaterialDraw {
+    pub entity: Entity,
+    pub mesh: Handle<Mesh>,
+    pub transform: Transform,
+    pub layers: RenderLayers,
+    pub sort_key: DrawCallSortKey,
+    /// [`Asset::type_name`] of the material; the bind group layout depends on it
+    pub material_type: &'static str,
+    pub shader: Handle<Shader>,
+    pub pipeline_key: MaterialPipelineKey,
+    pub shader_defs: ShaderDefs,
+    pub layout: UniformLayout,
+    pub uniform: Vec<u8>,
+    pub textures: Vec<TextureBinding>,
+    /// Joints of a skinned mesh; such draws use the `SKINNED` permutation
+    pub joint_palette: Option<JointPalette>,
+}
+
+impl PreparedMaterialDraw {
+    pub fn new<M: Material>(
+        entity: Entity,
+        mesh: &Handle<Mesh>,
+        transform: &Transform,
+        material: &M,
+        world: &World,
+        asset_server: &AssetServer,
+    ) -> Self {
+        let mut shader_defs = material.shader_defs();
+        // Only meshes with a weight per vertex can read the palette
+        let skin = world
+            .get_component::<SkinnedMesh>(entity)
+            .zip(world.get_component::<JointPalette>(entity))
+            .filter(|_| {
+                asset_server.get(mesh).is_some_and(|mesh| {
+                    !mesh.skin_weights.is_empty() && mesh.skin_weights.len() == mesh.vertices.len()
+                })
+            });
+        if let Some((skin, _)) = &skin {
+            shader_defs.set("SKINNED", "true");
+            if let Some(def) = skin.method.shader_def() {
+                shader_defs.set(def, "true");
+            }
+        }
+
+        Self {
+            entity,
+            mesh: mesh.clone(),
+            transform: *transform,
+            layers: world
+                .get_component::<RenderLayers>(entity)
+                .copied()
+                .unwrap_or_default(),
+            sort_key: DrawCallSortKey::for_material(material, asset_server),
+            material_type: <M as Asset>::type_name(),
+            shader: material.shader(),
+            pipeline_key: material.pipeline_key(),
+            shader_defs,
+            layout: material.uniform_layout(),
+            uniform: material.uniform_data(),
+            textures: material.textures(),
+            joint_palette: skin.map(|(_, palette)| palette.clone()),
+        }
+    }
+
+    /// Pipeline cache key; draws with equal ids share a pipeline
+    pub fn pipeline_id(&self) -> String {
+        let base = format!(
+            "material/{}/{:?}/{:?}",
+            self.material_type,
+            self.shader.id(),
+            self.pipeline_key
+        );
+        PipelineCache::permutation_label(&base, &self.shader_defs)
+    }
+
+    /// WGSL of the pipeline: the generated prelude followed by `shader`,
+    /// preprocessed for this draw's [`ShaderDefs`]
+    pub fn compose(
+        &self,
+        composer: &ShaderComposer,
+        shader: &str,
+    ) -> Result<ComposedShader, ShaderProcessError> {
+        let file = format!("{} shader", self.material_type);
+        let mut composed = composer.compose(&file, shader, &self.shader_defs)?;
+        let names: Vec<String> = self.textures.iter().map(|t| t.name.clone()).collect();
+        composed.prepend(
+            "material prelude",
+            &material_prelude(&self.layout, &names, &self.pipeline_key),
+        );
+        Ok(composed)
+    }
+}
+
+type CollectDraws = fn(&World, &AssetServer, &mut Vec<PreparedMaterialDraw>);
+/// Picks the mesh an extracted entity draws, `None` to leave it out
+pub type SelectMesh<'a> = &'a dyn Fn(Entity, &Handle<Mesh>, &Transform) -> Option<Handle<Mesh>>;
+/// Copies the entities of one material type that pass the visibility test
+/// into a render world
+type ExtractEntities = fn(&World, &mut World, SelectMesh);
+
+/// Material types known to the forward pass
+#[derive(Default)]
+pub struct MaterialRegistry {
+    collectors: Vec<(TypeId, CollectDraws, ExtractEntities)>,
+}
+
+impl Resource for MaterialRegistry {}
+
+impl MaterialRegistry {
+    /// Draw entities with a `Handle<Mesh>`, a `Transform` and a `Handle<M>`
+    pub fn register<M: Material>(&mut self) {
+        self.add(
+            TypeId::of::<Handle<M>>(),
+            collect_assets::<M>,
+            extract_assets::<M>,
+        );
+    }
+
+    /// Draw entities that carry the material itself as a component
+    pub fn register_component<M: Material + Component + Clone>(&mut self) {
+        self.add(
+            TypeId::of::<M>(),
+            collect_components::<M>,
+            extract_components::<M>,
+        );
+    }
+
+    fn add(&mut self, type_id: TypeId, collect: CollectDraws, extract: ExtractEntities) {
+        if !self.collectors.iter().any(|(id, _, _)| *id == type_id) {
+            self.collectors.push((type_id, collect, extract));
+        }
+    }
+
+    /// Every drawable mesh of every registered material type
+    pub fn collect(&self, world: &World, asset_server: &AssetServer) -> Vec<PreparedMaterialDraw> {
+        let mut draws = Vec::new();
+        for (_, collect, _) in &self.collectors {
+            collect(world, asset_server, &mut draws);
+        }
+        draws
+    }
+
+    /// Copy the drawable meshes of every registered material type from
+    /// `main` into the render world `render`, with the mesh `select` picks
+    pub fn extract(&self, main: &World, render: &mut World, select: SelectMesh) {
+        for (_, _, extract) in &self.collectors {
+            extract(main, render, select);
+        }
+    }
+}
+
+fn collect_assets<M: Material>(
+    world: &World,
+    asset_server: &AssetServer,
+    draws: &mut Vec<PreparedMaterialDraw>,
+) {
+    let query = Query::<(Entity, &Handle<Mesh>, &Transform, &Handle<M>)>::new(world);
+    for (entity, mesh, transform, handle) in query.iter() {
+        if let Some(material) = asset_server.get(handle) {
+            draws.push(PreparedMaterialDraw::new(
+                entity,
+                mesh,
+                transform,
+                &*material,
+                world,
+                asset_server,
+            ));
+        }
+    }
+}
+
+fn collect_components<M: Material + Component>(
+    world: &World,
+    asset_server: &AssetServer,
+    draws: &mut Vec<PreparedMaterialDraw>,
+) {
+    let query = Query::<(Entity, &Handle<Mesh>, &Transform, &M)>::new(world);
+    for (entity, mesh, transform, material) in query.iter() {
+        draws.push(PreparedMaterialDraw::new(
+            entity,
+            mesh,
+            transform,
+            material,
+            world,
+            asset_server,
+        ));
+    }
+}
+
+fn extract_assets<M: Material>(main: &World, render: &mut World, select: SelectMesh) {
+    let entities: Vec<_> = Query::<(Entity, &Handle<Mesh>, &Transform, &Handle<M>)>::new(main)
+        .iter()
+        .filter_map(|(entity, mesh, transform, material)| {
+            let mesh = select(entity, mesh, transform)?;
+            Some((entity, (mesh, *transform, material.clone())))
+        })
+        .collect();
+    for (entity, bundle) in entities {
+        spawn_extracted(main, render, entity, bundle);
+    }
+}
+
+fn extract_components<M: Material + Component + Clone>(
+    main: &World,
+    render: &mut World,
+    select: SelectMesh,
+) {
+    let entities: Vec<_> = Query::<(Entity, &Handle<Mesh>, &Transform, &M)>::new(main)
+        .iter()
+        .filter_map(|(entity, mesh, transform, material)| {
+            let mesh = select(entity, mesh, transform)?;
+            Some((entity, (mesh, *transform, material.clone())))
+        })
+        .collect();
+    for (entity, bundle) in entities {
+        spawn_extracted(main, render, entity, bundle);
+    }
+}
+
+/// Registers `M` with the forward pass and its `.material.ron` loader
+pub struct MaterialPlugin<M>(PhantomData<fn() -> M>);
+
+impl<M> Default for MaterialPlugin<M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<M: Material + DeserializeOwned> Plugin for MaterialPlugin<M> {
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn build(&self, app: &mut App) {
+        if app.world.get_resource::<MaterialRegistry>().is_none() {
+            app.insert_resource(MaterialRegistry::default());
+        }
+        // The queue is shared by every material type, so the first plugin
+        // also adds the one system draining it
+        if app.world.get_resource::<MaterialTextureQueue>().is_none() {
+            app.insert_resource(MaterialTextureQueue::default());
+            app.add_system::<ExclusiveMarker>(CoreStage::PreUpdate, load_material_textures_system);
+        }
+        if let Some(mut registry) = app.world.get_resource_mut::<MaterialRegistry>() {
+            registry.register::<M>();
+        }
+
+        app.add_system::<ExclusiveMarker>(CoreStage::Startup, register_material_loader::<M>);
+    }
+}
+
+fn register_material_loader<M: Material + DeserializeOwned>(world: &mut World) {
+    let Some(queue) = world
+        .get_resource::<MaterialTextureQueue>()
+        .map(|q| q.clone())
+    else {
+        return;
+    };
+    if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
+        asset_server.register_loader(MaterialLoader::<M>::new(queue));
+    }
+}
+
+/// GPU copies of material textures, uploaded on first use
+#[derive(Default)]
+pub struct MaterialTextures {
+    uploaded: HashMap<AssetId, (Arc<Texture>, Texture)>,
+    white: Option<Texture>,
+}
+
+impl Resource for MaterialTextures {}
+
+impl MaterialTextures {
+    /// Upload every loaded texture used by `draws`. Reloaded assets are new
+    /// `Arc`s, so they are uploaded again.
+    pub(crate) fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        asset_server: &AssetServer,
+        draws: &[PreparedMaterialDraw],
+    ) {
+        self.white.get_or_insert_with(|| {
+            let mut white = Texture::solid_color(1, 1, [255, 255, 255, 255]);
+            white.upload(device, queue);
+            white
+        });
+
+        for handle in draws
+            .iter()
+            .flat_map(|draw| &draw.textures)
+            .filter_map(|binding| binding.texture.as_ref())
+        {
+            let Some(source) = asset_server.get(handle) else {
+                continue;
+            };
+            // Float32 textures cannot use the filtering sampler
+            if source.data.format == crate::TextureFormat::Rgba32F {
+                continue;
+            }
+            let stale = self
+                .uploaded
+                .get(&handle.id())
+                .is_none_or(|(uploaded, _)| !Arc::ptr_eq(uploaded, &source));
+            if stale {
+                let mut texture = Texture::new(source.data.clone());
+                texture.sampler_settings = source.sampler_settings;
+                texture.upload(device, queue);
+                self.uploaded.insert(handle.id(), (source, texture));
+            }
+        }
+    }
+
+    /// View and sampler of a prepared texture, or of the white fallback
+    pub(crate) fn get(
+        &self,
+        handle: Option<&Handle<Texture>>,
+    ) -> Option<(&wgpu::TextureView, &wgpu::Sampler)> {
+        let texture = handle
+            .and_then(|handle| self.uploaded.get(&handle.id()))
+            .map(|(_, texture)| texture)
+            .or(self.white.as_ref())?;
+        Some((texture.view.as_ref()?, texture.sampler.as_ref()?))
+    }
+}
+
+/// Build the pipeline of a material draw from its composed and validated
+/// shader
+pub(crate) fn create_material_pipeline(
+    device: &wgpu::Device,
+    draw: &PreparedMaterialDraw,
+    shader: &ComposedShader,
+) -> (wgpu::RenderPipeline, Vec<wgpu::BindGroupLayout>) {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(draw.material_type),
+        source: wgpu::ShaderSource::Wgsl(shader.source.as_str().into()),
+    });
+
+    let uniform_entry = |binding, visibility| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Camera Layout"),
+        entries: &[uniform_entry(0, wgpu::ShaderStages::VERTEX_FRAGMENT)],
+    });
+    // Skinned permutations read the joint palette next to the model
+    // transform, and the skin weights from a second vertex buffer
+    let skinned = draw.shader_defs.contains("SKINNED");
+    let mut model_entries = vec![uniform_entry(0, wgpu::ShaderStages::VERTEX)];
+    let mut vertex_buffers = vec![crate::Vertex::desc()];
+    if skinned {
+        model_entries.push(JointPalette::bind_group_layout_entry());
+        vertex_buffers.push(SkinWeights::desc());
+    }
+    let model_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Model Layout"),
+        entries: &model_entries,
+    });
+
+    let mut material_entries = vec![uniform_entry(0, wgpu::ShaderStages::VERTEX_FRAGMENT)];
+    for index in 0..draw.textures.len() as u32 {
+        material_entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 1 + index * 2,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        });
+        material_entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 2 + index * 2,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        });
+    }
+    let material_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Material Layout"),
+        entries: &material_entries,
+    });
+
+    let environment_layout = environment_layout(device);
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Material Pipeline Layout"),
+        bind_group_layouts: &[
+            &camera_layout,
+            &model_layout,
+            &material_layout,
+            &environment_layout,
+        ],
+        push_constant_ranges: &[],
+    });
+
+    let key = draw.pipeline_key;
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(draw.material_type),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: "vs_main",
+            buffers: &vertex_buffers,
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: slots::HDR_FORMAT,
+                blend: key.blend_mode.blend_state(),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: key.cull_mode.face(),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: slots::DEPTH_FORMAT,
+            depth_write_enabled: key.depth_write,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    });
+
+    (
+        pipeline,
+        vec![
+            camera_layout,
+            model_layout,
+            material_layout,
+            environment_layout,
+        ],
+    )
+}
+
+/// Material with a runtime-compiled shader, produced by the AI shader
+/// pipeline. Unlike [`Material`] implementations it owns its shader directly.
+pub struct ShaderMaterial {
     pub name: String,
     pub shader: Arc<Shader>,
     pub base_color: Color,
     pub base_texture: Option<Arc<Texture>>,
 }
 
-impl Material {
+impl ShaderMaterial {
     pub fn new(name: &str, shader: Arc<Shader>) -> Self {
         Self {
             name: name.to_string(),
@@ -22,22 +1299,21 @@ impl Material {
     }
 }
 
-use luminara_core::shared_types::Component;
-impl Component for Material {
+impl Component for ShaderMaterial {
     fn type_name() -> &'static str {
-        "Material"
+        "ShaderMaterial"
     }
 }
 
-// Manual Reflect implementation for Material
+// Manual Reflect implementation for ShaderMaterial
 // We only reflect the name and base_color, not the shader/texture references
-impl Reflect for Material {
+impl Reflect for ShaderMaterial {
     fn type_info(&self) -> &luminara_core::TypeInfo {
         use std::sync::OnceLock;
         static INFO: OnceLock<luminara_core::TypeInfo> = OnceLock::new();
         INFO.get_or_init(|| luminara_core::TypeInfo {
-            type_name: "Material".to_string(),
-            type_id: std::any::TypeId::of::<Material>(),
+            type_name: "ShaderMaterial".to_string(),
+            type_id: std::any::TypeId::of::<ShaderMaterial>(),
             kind: luminara_core::TypeKind::Struct,
             fields: vec![
                 luminara_core::FieldInfo {
@@ -1,9 +1,11 @@
 use crate::command::DrawCommand;
+use crate::mesh_processing;
 use crate::PbrMaterial;
 use bytemuck::{Pod, Zeroable};
 use luminara_asset::{Asset, Handle, PlaceholderAsset};
 use luminara_core::Reflect;
-use luminara_math::{Mat4, Vec3};
+use luminara_math::glam::Affine2;
+use luminara_math::{Mat4, Vec2, Vec3};
 use wgpu;
 
 /// Axis-Aligned Bounding Box for mesh culling
@@ -93,12 +95,61 @@ impl Vertex {
 
 use std::sync::RwLock;
 
+/// Per-vertex offsets of one blend shape, weighted by
+/// [`MorphWeights`](crate::MorphWeights)
+#[derive(Debug, Clone, Default)]
+pub struct MorphTarget {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub tangents: Vec<[f32; 3]>,
+}
+
+/// Joints influencing one vertex of a skinned mesh, indexing the
+/// [`SkinnedMesh`](crate::SkinnedMesh) joint list
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+pub struct SkinWeights {
+    pub joints: [u16; 4],
+    pub weights: [f32; 4],
+}
+
+impl SkinWeights {
+    /// Layout of a second vertex buffer holding skin weights, read by the
+    /// `luminara::skinning` shader module
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SkinWeights>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                // Joint indices
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Uint16x4,
+                },
+                // Joint weights
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[u16; 4]>() as wgpu::BufferAddress,
+                    shader_location: 11,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
     pub aabb: AABB,
+    /// Blend shapes; each offset list is empty or parallel to `vertices`
+    pub morph_targets: Vec<MorphTarget>,
+    /// Skin weights; empty or parallel to `vertices`
+    pub skin_weights: Vec<SkinWeights>,
     pub vertex_buffer: RwLock<Option<wgpu::Buffer>>,
     pub index_buffer: RwLock<Option<wgpu::Buffer>>,
+    /// `skin_weights` as laid out by [`SkinWeights::desc`]
+    pub skin_buffer: RwLock<Option<wgpu::Buffer>>,
 }
 
 impl Mesh {
@@ -108,8 +159,11 @@ impl Mesh {
             vertices,
             indices,
             aabb,
+            morph_targets: Vec::new(),
+            skin_weights: Vec::new(),
             vertex_buffer: RwLock::new(None),
             index_buffer: RwLock::new(None),
+            skin_buffer: RwLock::new(None),
         }
     }
 
@@ -394,6 +448,19 @@ impl Mesh {
                 );
             }
         }
+
+        if !self.skin_weights.is_empty() {
+            let mut sb = self.skin_buffer.write().unwrap();
+            if sb.is_none() {
+                *sb = Some(
+                    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Skin Weight Buffer"),
+                        contents: bytemuck::cast_slice(&self.skin_weights),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                );
+            }
+        }
     }
 
     pub fn draw(mesh: Handle<Mesh>, material: Handle<PbrMaterial>, transform: Mat4) -> DrawCommand {
@@ -423,50 +490,7 @@ impl Mesh {
 
         for mesh in document.meshes() {
             for primitive in mesh.primitives() {
-                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-
-                // Read positions (required)
-                let positions: Vec<[f32; 3]> = reader
-                    .read_positions()
-                    .ok_or("Missing position attribute")?
-                    .collect();
-
-                // Read normals (required for PBR)
-                let normals: Vec<[f32; 3]> = reader
-                    .read_normals()
-                    .ok_or("Missing normal attribute")?
-                    .collect();
-
-                // Read UVs (optional, default to [0, 0])
-                let uvs: Vec<[f32; 2]> = reader
-                    .read_tex_coords(0)
-                    .map(|iter| iter.into_f32().collect())
-                    .unwrap_or_else(|| vec![[0.0, 0.0]; positions.len()]);
-
-                // Read tangents (optional, default to [1, 0, 0, 1])
-                let tangents: Vec<[f32; 4]> = reader
-                    .read_tangents()
-                    .map(|iter| iter.collect())
-                    .unwrap_or_else(|| vec![[1.0, 0.0, 0.0, 1.0]; positions.len()]);
-
-                // Build vertices
-                let mut vertices = Vec::new();
-                for i in 0..positions.len() {
-                    vertices.push(Vertex {
-                        position: positions[i],
-                        normal: normals[i],
-                        uv: uvs.get(i).copied().unwrap_or([0.0, 0.0]),
-                        tangent: tangents.get(i).copied().unwrap_or([1.0, 0.0, 0.0, 1.0]),
-                    });
-                }
-
-                // Read indices
-                let indices: Vec<u32> = reader
-                    .read_indices()
-                    .map(|iter| iter.into_u32().collect())
-                    .unwrap_or_else(|| (0..vertices.len() as u32).collect());
-
-                meshes.push(Mesh::new(vertices, indices));
+                meshes.push(Self::from_gltf_primitive(&primitive, &buffers, 0, None)?);
             }
         }
 
@@ -476,6 +500,117 @@ impl Mesh {
 
         Ok(meshes)
     }
+
+    /// Read one glTF primitive, taking UVs from `TEXCOORD_<uv_set>` and
+    /// applying `uv_transform` to them (see `KHR_texture_transform`)
+    pub(crate) fn from_gltf_primitive(
+        primitive: &gltf::Primitive,
+        buffers: &[gltf::buffer::Data],
+        uv_set: u32,
+        uv_transform: Option<Affine2>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+
+        // Read positions (required)
+        let positions: Vec<[f32; 3]> = reader
+            .read_positions()
+            .ok_or("Missing position attribute")?
+            .collect();
+
+        // Read normals (optional, computed below when missing)
+        let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(|iter| iter.collect());
+
+        // Read UVs (optional, default to [0, 0])
+        let uvs: Option<Vec<[f32; 2]>> = reader.read_tex_coords(uv_set).map(|iter| {
+            iter.into_f32()
+                .map(|uv| match uv_transform {
+                    Some(transform) => transform.transform_point2(Vec2::from(uv)).to_array(),
+                    None => uv,
+                })
+                .collect()
+        });
+
+        // Read tangents (optional, generated below when missing)
+        let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(|iter| iter.collect());
+
+        let morph_targets: Vec<MorphTarget> = reader
+            .read_morph_targets()
+            .map(|(positions, normals, tangents)| MorphTarget {
+                positions: positions.map(|iter| iter.collect()).unwrap_or_default(),
+                normals: normals.map(|iter| iter.collect()).unwrap_or_default(),
+                tangents: tangents.map(|iter| iter.collect()).unwrap_or_default(),
+            })
+            .collect();
+
+        let skin_weights: Vec<SkinWeights> = match (reader.read_joints(0), reader.read_weights(0)) {
+            (Some(joints), Some(weights)) => joints
+                .into_u16()
+                .zip(weights.into_f32())
+                .map(|(joints, weights)| SkinWeights { joints, weights })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Build vertices
+        let mut vertices = Vec::new();
+        for (i, &position) in positions.iter().enumerate() {
+            vertices.push(Vertex {
+                position,
+                normal: normals
+                    .as_ref()
+                    .and_then(|n| n.get(i).copied())
+                    .unwrap_or([0.0, 0.0, 1.0]),
+                uv: uvs
+                    .as_ref()
+                    .and_then(|uv| uv.get(i).copied())
+                    .unwrap_or([0.0, 0.0]),
+                tangent: tangents
+                    .as_ref()
+                    .and_then(|t| t.get(i).copied())
+                    .unwrap_or([1.0, 0.0, 0.0, 1.0]),
+            });
+        }
+
+        let indices: Option<Vec<u32>> = reader.read_indices().map(|iter| iter.into_u32().collect());
+
+        // Morph targets and skin weights address vertices by index, so their
+        // vertex order must be kept: no welding, flat normals or reordering
+        if !morph_targets.is_empty() || !skin_weights.is_empty() {
+            let indices = indices.unwrap_or_else(|| (0..vertices.len() as u32).collect());
+            if normals.is_none() {
+                mesh_processing::compute_normals(&mut vertices, &indices, std::f32::consts::PI);
+            }
+            if tangents.is_none() && uvs.is_some() {
+                mesh_processing::generate_tangents(&mut vertices, &indices);
+            }
+            let mut mesh = Mesh::new(vertices, indices);
+            mesh.morph_targets = morph_targets;
+            mesh.skin_weights = skin_weights;
+            return Ok(mesh);
+        }
+
+        // Read indices; unindexed primitives are welded into indexed ones
+        let (vertices, indices) = match indices {
+            Some(indices) => (vertices, indices),
+            None => {
+                let indices: Vec<u32> = (0..vertices.len() as u32).collect();
+                mesh_processing::weld_vertices(&vertices, &indices, 0.0)
+            }
+        };
+
+        // glTF asks for flat normals when none are given
+        let (mut vertices, indices) = if normals.is_none() {
+            mesh_processing::flat_normals(&vertices, &indices)
+        } else {
+            (vertices, indices)
+        };
+        if tangents.is_none() && uvs.is_some() {
+            mesh_processing::generate_tangents(&mut vertices, &indices);
+        }
+
+        let (vertices, indices) = mesh_processing::optimize(&vertices, &indices);
+        Ok(Mesh::new(vertices, indices))
+    }
 }
 
 #[cfg(test)]
@@ -590,8 +725,11 @@ impl Reflect for Mesh {
             vertices: self.vertices.clone(),
             indices: self.indices.clone(),
             aabb: self.aabb,
+            morph_targets: self.morph_targets.clone(),
+            skin_weights: self.skin_weights.clone(),
             vertex_buffer: RwLock::new(None),
             index_buffer: RwLock::new(None),
+            skin_buffer: RwLock::new(None),
         })
     }
 
@@ -0,0 +1,934 @@
+/// Mesh processing toolkit
+///
+/// CPU-side operations on indexed triangle lists: vertex welding, normal and
+/// tangent generation, quadric error metric simplification and reordering for
+/// the GPU vertex cache, overdraw and vertex fetch. Everything works on plain
+/// vertex and index slices so it runs at import time and in tests without a
+/// GPU.
+use crate::Vertex;
+use luminara_math::glam::DVec3;
+use luminara_math::{Vec2, Vec3};
+use std::collections::{HashMap, VecDeque};
+
+fn position(vertex: &Vertex) -> Vec3 {
+    Vec3::from_array(vertex.position)
+}
+
+fn triangles(indices: &[u32]) -> impl Iterator<Item = [usize; 3]> + '_ {
+    indices
+        .chunks_exact(3)
+        .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+}
+
+/// Angle of the triangle corner at `p0`
+fn corner_angle(p0: Vec3, p1: Vec3, p2: Vec3) -> f32 {
+    let e1 = (p1 - p0).normalize_or_zero();
+    let e2 = (p2 - p0).normalize_or_zero();
+    e1.dot(e2).clamp(-1.0, 1.0).acos()
+}
+
+/// Unit normal of a triangle, or zero when it is too thin to have one
+fn face_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    let normal = (b - a).cross(c - a);
+    let longest = (b - a)
+        .length_squared()
+        .max((c - b).length_squared())
+        .max((a - c).length_squared());
+    if normal.length() <= longest * 1e-5 {
+        return Vec3::ZERO;
+    }
+    normal.normalize()
+}
+
+/// Unit vector perpendicular to `n`
+fn perpendicular(n: Vec3) -> Vec3 {
+    let axis = if n.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+    (axis - n * n.dot(axis)).normalize_or_zero()
+}
+
+/// Merge vertices whose attributes all match within `tolerance` and remap the
+/// indices. A tolerance of zero merges exact duplicates only. Triangles that
+/// collapse to a line are dropped.
+pub fn weld_vertices(
+    vertices: &[Vertex],
+    indices: &[u32],
+    tolerance: f32,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let mut welded: Vec<Vertex> = Vec::new();
+    let mut remap = Vec::with_capacity(vertices.len());
+
+    if tolerance <= 0.0 {
+        let mut unique: HashMap<[u32; 12], u32> = HashMap::new();
+        for vertex in vertices {
+            let index = *unique.entry(exact_key(vertex)).or_insert_with(|| {
+                welded.push(*vertex);
+                welded.len() as u32 - 1
+            });
+            remap.push(index);
+        }
+    } else {
+        let cell = |p: [f32; 3]| p.map(|c| (c / tolerance).floor() as i64);
+        let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+        for vertex in vertices {
+            let [x, y, z] = cell(vertex.position);
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let Some(candidates) = grid.get(&[x + dx, y + dy, z + dz]) else {
+                            continue;
+                        };
+                        if let Some(&index) = candidates
+                            .iter()
+                            .find(|&&i| attributes_match(&welded[i as usize], vertex, tolerance))
+                        {
+                            found = Some(index);
+                            break 'search;
+                        }
+                    }
+                }
+            }
+            let index = found.unwrap_or_else(|| {
+                welded.push(*vertex);
+                let index = welded.len() as u32 - 1;
+                grid.entry([x, y, z]).or_default().push(index);
+                index
+            });
+            remap.push(index);
+        }
+    }
+
+    let mut welded_indices = Vec::with_capacity(indices.len());
+    for [a, b, c] in triangles(indices) {
+        let (a, b, c) = (remap[a], remap[b], remap[c]);
+        if a != b && b != c && a != c {
+            welded_indices.extend_from_slice(&[a, b, c]);
+        }
+    }
+    (welded, welded_indices)
+}
+
+/// Bits of every attribute, with -0.0 folded into 0.0
+fn exact_key(vertex: &Vertex) -> [u32; 12] {
+    let floats: &[f32; 12] = bytemuck::cast_ref(vertex);
+    floats.map(|c| (c + 0.0).to_bits())
+}
+
+fn attributes_match(a: &Vertex, b: &Vertex, tolerance: f32) -> bool {
+    let close = |x: &[f32], y: &[f32]| x.iter().zip(y).all(|(x, y)| (x - y).abs() <= tolerance);
+    close(&a.position, &b.position)
+        && close(&a.normal, &b.normal)
+        && close(&a.uv, &b.uv)
+        && close(&a.tangent, &b.tangent)
+}
+
+/// Recompute per-vertex normals from the triangles around each position,
+/// weighting faces by their corner angle. Faces are smoothed together when
+/// their normals are within `crease_angle` (radians) of a face using the
+/// vertex, so split vertices along UV seams stay smooth while hard edges stay
+/// hard.
+pub fn compute_normals(vertices: &mut [Vertex], indices: &[u32], crease_angle: f32) {
+    let remap = position_remap(vertices);
+    let face_normals: Vec<Vec3> = triangles(indices)
+        .map(|[a, b, c]| {
+            face_normal(
+                position(&vertices[a]),
+                position(&vertices[b]),
+                position(&vertices[c]),
+            )
+        })
+        .collect();
+
+    // Faces around each position, with the angle of their corner there
+    let mut corners: HashMap<u32, Vec<(usize, f32)>> = HashMap::new();
+    // Faces that reference each vertex directly
+    let mut own_faces: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+    for (face, [a, b, c]) in triangles(indices).enumerate() {
+        let p = [a, b, c].map(|v| position(&vertices[v]));
+        for (corner, &v) in [a, b, c].iter().enumerate() {
+            let angle = corner_angle(p[corner], p[(corner + 1) % 3], p[(corner + 2) % 3]);
+            corners.entry(remap[v]).or_default().push((face, angle));
+            if face_normals[face] != Vec3::ZERO {
+                own_faces[v].push(face);
+            }
+        }
+    }
+
+    let cos_crease = crease_angle.cos();
+    for (v, faces) in own_faces.iter().enumerate() {
+        let Some(around) = corners.get(&remap[v]) else {
+            continue;
+        };
+        let mut normal = Vec3::ZERO;
+        for &(face, angle) in around {
+            // Vertices only used by degenerate faces take every face around them
+            let smooth = faces.is_empty()
+                || faces
+                    .iter()
+                    .any(|&own| face_normals[own].dot(face_normals[face]) >= cos_crease - 1e-6);
+            if smooth {
+                normal += face_normals[face] * angle;
+            }
+        }
+        let normal = normal.normalize_or_zero();
+        if normal != Vec3::ZERO {
+            vertices[v].normal = normal.to_array();
+        }
+    }
+}
+
+/// Give every triangle its own vertices carrying the face normal, then merge
+/// exact duplicates again. This is what glTF asks for when a primitive has no
+/// normals.
+pub fn flat_normals(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut flat = Vec::with_capacity(indices.len());
+    for [a, b, c] in triangles(indices) {
+        let normal = face_normal(
+            position(&vertices[a]),
+            position(&vertices[b]),
+            position(&vertices[c]),
+        );
+        for v in [a, b, c] {
+            flat.push(Vertex {
+                normal: normal.to_array(),
+                ..vertices[v]
+            });
+        }
+    }
+    let flat_indices: Vec<u32> = (0..flat.len() as u32).collect();
+    weld_vertices(&flat, &flat_indices, 0.0)
+}
+
+/// Generate tangents following the MikkTSpace conventions used by glTF
+/// exporters: per-corner tangents from the UV gradient are projected onto the
+/// normal plane, weighted by corner angle and summed per vertex, with the
+/// bitangent sign in `w`. Vertices are not split, so weld duplicates first;
+/// a vertex shared by mirrored UV islands takes the dominant handedness.
+pub fn generate_tangents(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut tangents = vec![Vec3::ZERO; vertices.len()];
+    let mut handedness = vec![0.0f32; vertices.len()];
+
+    for [a, b, c] in triangles(indices) {
+        let p = [a, b, c].map(|v| position(&vertices[v]));
+        let uv = [a, b, c].map(|v| Vec2::from_array(vertices[v].uv));
+        let (e1, e2) = (p[1] - p[0], p[2] - p[0]);
+        let (d1, d2) = (uv[1] - uv[0], uv[2] - uv[0]);
+        let det = d1.x * d2.y - d2.x * d1.y;
+        if det.abs() < 1e-12 {
+            continue;
+        }
+        let s_dir = (e1 * d2.y - e2 * d1.y) / det;
+        let t_dir = (e2 * d1.x - e1 * d2.x) / det;
+
+        for (corner, &v) in [a, b, c].iter().enumerate() {
+            let n = Vec3::from_array(vertices[v].normal).normalize_or_zero();
+            let tangent = (s_dir - n * n.dot(s_dir)).normalize_or_zero();
+            let angle = corner_angle(p[corner], p[(corner + 1) % 3], p[(corner + 2) % 3]);
+            let sign = if n.cross(s_dir).dot(t_dir) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            tangents[v] += tangent * angle;
+            handedness[v] += sign * angle;
+        }
+    }
+
+    for (vertex, (tangent, sign)) in vertices.iter_mut().zip(tangents.iter().zip(&handedness)) {
+        let n = Vec3::from_array(vertex.normal).normalize_or_zero();
+        let mut t = (*tangent - n * n.dot(*tangent)).normalize_or_zero();
+        if t == Vec3::ZERO {
+            t = perpendicular(n);
+        }
+        let w = if *sign < 0.0 { -1.0 } else { 1.0 };
+        vertex.tangent = [t.x, t.y, t.z, w];
+    }
+}
+
+/// Options for [`simplify`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimplifyOptions {
+    /// Stop once the index count is at or below this
+    pub target_index_count: usize,
+    /// Largest error allowed, as a fraction of the bounding box diagonal
+    pub max_error: f32,
+    /// Keep vertices on open borders in place
+    pub lock_border: bool,
+}
+
+impl SimplifyOptions {
+    /// Aim for `ratio` of the triangles with no error limit
+    pub fn ratio(index_count: usize, ratio: f32) -> Self {
+        Self {
+            target_index_count: ((index_count / 3) as f32 * ratio.clamp(0.0, 1.0)) as usize * 3,
+            max_error: f32::MAX,
+            lock_border: false,
+        }
+    }
+}
+
+/// Result of [`simplify`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimplifiedMesh {
+    /// Triangles referencing the original vertex buffer
+    pub indices: Vec<u32>,
+    /// Largest collapse error, as a fraction of the bounding box diagonal
+    pub error: f32,
+}
+
+/// Error quadric of a set of planes, in double precision
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    /// Upper triangle of A: xx, xy, xz, yy, yz, zz
+    a: [f64; 6],
+    b: DVec3,
+    c: f64,
+    weight: f64,
+}
+
+impl Quadric {
+    /// Squared distance to the plane `n·p + d = 0`, scaled by `weight`
+    fn plane(n: DVec3, d: f64, weight: f64) -> Self {
+        Self {
+            a: [
+                n.x * n.x * weight,
+                n.x * n.y * weight,
+                n.x * n.z * weight,
+                n.y * n.y * weight,
+                n.y * n.z * weight,
+                n.z * n.z * weight,
+            ],
+            b: n * d * weight,
+            c: d * d * weight,
+            weight,
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.a.iter_mut().zip(other.a) {
+            *a += b;
+        }
+        self.b += other.b;
+        self.c += other.c;
+        self.weight += other.weight;
+    }
+
+    /// Weighted mean squared distance of `p` to the planes
+    fn error(&self, p: DVec3) -> f64 {
+        if self.weight <= 0.0 {
+            return 0.0;
+        }
+        let [xx, xy, xz, yy, yz, zz] = self.a;
+        let quadratic = xx * p.x * p.x
+            + yy * p.y * p.y
+            + zz * p.z * p.z
+            + 2.0 * (xy * p.x * p.y + xz * p.x * p.z + yz * p.y * p.z);
+        ((quadratic + 2.0 * self.b.dot(p) + self.c) / self.weight).max(0.0)
+    }
+}
+
+/// Extra weight of the constraint planes that hold borders and seams in place
+const BORDER_WEIGHT: f64 = 10.0;
+
+/// Index of the first vertex sharing each vertex's position
+fn position_remap(vertices: &[Vertex]) -> Vec<u32> {
+    let mut first: HashMap<[u32; 3], u32> = HashMap::new();
+    vertices
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            *first
+                .entry(v.position.map(f32::to_bits))
+                .or_insert(i as u32)
+        })
+        .collect()
+}
+
+/// Triangles around each position, in compressed row form
+struct Adjacency {
+    offsets: Vec<usize>,
+    triangles: Vec<usize>,
+}
+
+impl Adjacency {
+    fn new(indices: &[u32], remap: &[u32]) -> Self {
+        let mut offsets = vec![0; remap.len() + 1];
+        for &i in indices {
+            offsets[remap[i as usize] as usize + 1] += 1;
+        }
+        for i in 0..remap.len() {
+            offsets[i + 1] += offsets[i];
+        }
+        let mut fill = offsets.clone();
+        let mut triangles = vec![0; indices.len()];
+        for (t, corners) in indices.chunks_exact(3).enumerate() {
+            for &i in corners {
+                let slot = &mut fill[remap[i as usize] as usize];
+                triangles[*slot] = t;
+                *slot += 1;
+            }
+        }
+        Self { offsets, triangles }
+    }
+
+    fn around(&self, position: u32) -> &[usize] {
+        &self.triangles[self.offsets[position as usize]..self.offsets[position as usize + 1]]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VertexKind {
+    Manifold,
+    /// On an open edge; may only slide along it
+    Border,
+    Locked,
+}
+
+/// Simplify a mesh by collapsing edges in order of quadric error (Garland and
+/// Heckbert), moving a vertex onto a neighbour so attributes never need
+/// interpolating.
+///
+/// Vertices split by UV seams or hard normals collapse together with their
+/// twins and only along the seam, so texture islands keep their outline.
+/// Open borders only collapse along themselves, and plane constraints keep
+/// both from drifting. The returned indices reference `vertices`; use
+/// [`optimize_vertex_fetch`] to drop the vertices no longer used.
+pub fn simplify(vertices: &[Vertex], indices: &[u32], options: &SimplifyOptions) -> SimplifiedMesh {
+    let remap = position_remap(vertices);
+    let positions: Vec<DVec3> = vertices.iter().map(|v| position(v).as_dvec3()).collect();
+    let extent = crate::AABB::from_vertices(vertices);
+    let diagonal = (extent.max - extent.min).length() as f64;
+
+    // Zero-area triangles at the position level carry no surface
+    let mut indices: Vec<u32> = indices
+        .chunks_exact(3)
+        .filter(|t| {
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| remap[i as usize]);
+            a != b && b != c && a != c
+        })
+        .flatten()
+        .copied()
+        .collect();
+    if diagonal <= 0.0 || indices.len() <= options.target_index_count {
+        return SimplifiedMesh {
+            indices,
+            error: 0.0,
+        };
+    }
+
+    let (mut quadrics, kinds) = build_quadrics(&positions, &indices, &remap, options.lock_border);
+    let max_error = (options.max_error as f64 * diagonal).powi(2);
+    let mut error = 0.0f64;
+    let mut collapse: Vec<u32> = (0..vertices.len() as u32).collect();
+
+    while indices.len() > options.target_index_count {
+        let adjacency = Adjacency::new(&indices, &remap);
+
+        let mut candidates: Vec<(f64, u32, u32)> = Vec::new();
+        for corners in indices.chunks_exact(3) {
+            for k in 0..3 {
+                let from = remap[corners[k] as usize];
+                let to = remap[corners[(k + 1) % 3] as usize];
+                for (from, to) in [(from, to), (to, from)] {
+                    if kinds[from as usize] != VertexKind::Locked {
+                        let cost = quadrics[from as usize].error(positions[to as usize]);
+                        candidates.push((cost, from, to));
+                    }
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+        candidates.dedup_by_key(|c| (c.1, c.2));
+
+        let mut budget = (indices.len() - options.target_index_count) / 3;
+        let mut locked = vec![false; vertices.len()];
+        let mut collapsed = 0;
+        for (cost, from, to) in candidates {
+            if cost > max_error {
+                break;
+            }
+            if locked[from as usize] || locked[to as usize] {
+                continue;
+            }
+            if kinds[from as usize] == VertexKind::Border
+                && !is_border_edge(&indices, &adjacency, &remap, from, to)
+            {
+                continue;
+            }
+            let Some(wedges) = wedge_targets(&indices, &adjacency, &remap, from, to) else {
+                continue;
+            };
+            if flips(&indices, &adjacency, &remap, &positions, from, to) {
+                continue;
+            }
+
+            let mut removed = 0;
+            for &t in adjacency.around(from) {
+                let corners = &indices[t * 3..t * 3 + 3];
+                if corners.iter().any(|&i| remap[i as usize] == to) {
+                    removed += 1;
+                }
+                // Lock the one-ring so other collapses this pass see exact geometry
+                for &i in corners {
+                    locked[remap[i as usize] as usize] = true;
+                }
+            }
+            locked[to as usize] = true;
+            for (wedge, target) in wedges {
+                collapse[wedge as usize] = target;
+            }
+            let from_quadric = quadrics[from as usize];
+            quadrics[to as usize].add(&from_quadric);
+            error = error.max(cost);
+            collapsed += 1;
+
+            if removed >= budget {
+                break;
+            }
+            budget -= removed;
+        }
+        if collapsed == 0 {
+            break;
+        }
+
+        indices = indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]].map(|i| collapse[i as usize]))
+            .filter(|t| {
+                let [a, b, c] = t.map(|i| remap[i as usize]);
+                a != b && b != c && a != c
+            })
+            .flatten()
+            .collect();
+        for (i, target) in collapse.iter_mut().enumerate() {
+            *target = i as u32;
+        }
+    }
+
+    SimplifiedMesh {
+        indices,
+        error: (error.sqrt() / diagonal) as f32,
+    }
+}
+
+/// Face quadrics plus constraint planes along borders and seams, and the
+/// collapse rules of each position
+fn build_quadrics(
+    positions: &[DVec3],
+    indices: &[u32],
+    remap: &[u32],
+    lock_border: bool,
+) -> (Vec<Quadric>, Vec<VertexKind>) {
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut kinds = vec![VertexKind::Manifold; positions.len()];
+
+    let mut position_edges: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut attribute_edges: HashMap<(u32, u32), u32> = HashMap::new();
+    for corners in indices.chunks_exact(3) {
+        for k in 0..3 {
+            let (a, b) = (corners[k], corners[(k + 1) % 3]);
+            *attribute_edges.entry((a, b)).or_default() += 1;
+            *position_edges
+                .entry((remap[a as usize], remap[b as usize]))
+                .or_default() += 1;
+        }
+    }
+
+    for corners in indices.chunks_exact(3) {
+        let p = [corners[0], corners[1], corners[2]].map(|i| positions[i as usize]);
+        let normal = (p[1] - p[0]).cross(p[2] - p[0]);
+        let area = normal.length() * 0.5;
+        if area <= 0.0 {
+            continue;
+        }
+        let n = normal / (area * 2.0);
+        let face = Quadric::plane(n, -n.dot(p[0]), area);
+        for &i in corners {
+            quadrics[remap[i as usize] as usize].add(&face);
+        }
+
+        for k in 0..3 {
+            let (a, b) = (corners[k], corners[(k + 1) % 3]);
+            let (pa, pb) = (remap[a as usize], remap[b as usize]);
+            let border = !position_edges.contains_key(&(pb, pa));
+            let seam = !border && !attribute_edges.contains_key(&(b, a));
+            if position_edges[&(pa, pb)] > 1 {
+                // Non-manifold edge
+                kinds[pa as usize] = VertexKind::Locked;
+                kinds[pb as usize] = VertexKind::Locked;
+            }
+            if !(border || seam) {
+                continue;
+            }
+            if border {
+                for v in [pa, pb] {
+                    if lock_border {
+                        kinds[v as usize] = VertexKind::Locked;
+                    } else if kinds[v as usize] == VertexKind::Manifold {
+                        kinds[v as usize] = VertexKind::Border;
+                    }
+                }
+            }
+            // Plane through the edge, perpendicular to the face
+            let edge = p[(k + 1) % 3] - p[k];
+            let plane_normal = edge.cross(n).normalize_or_zero();
+            let mut constraint = Quadric::plane(
+                plane_normal,
+                -plane_normal.dot(p[k]),
+                edge.length_squared() * BORDER_WEIGHT,
+            );
+            // Constraints add error without diluting the average over faces
+            constraint.weight = 0.0;
+            quadrics[pa as usize].add(&constraint);
+            quadrics[pb as usize].add(&constraint);
+        }
+    }
+
+    // A border vertex with more than one outgoing border edge is a bow tie
+    let mut outgoing = vec![0u32; positions.len()];
+    for &(a, b) in position_edges.keys() {
+        if a != b && !position_edges.contains_key(&(b, a)) {
+            outgoing[a as usize] += 1;
+        }
+    }
+    for (kind, count) in kinds.iter_mut().zip(outgoing) {
+        if count > 1 {
+            *kind = VertexKind::Locked;
+        }
+    }
+    (quadrics, kinds)
+}
+
+fn is_border_edge(
+    indices: &[u32],
+    adjacency: &Adjacency,
+    remap: &[u32],
+    from: u32,
+    to: u32,
+) -> bool {
+    let mut forward = 0;
+    let mut backward = 0;
+    for &t in adjacency.around(from) {
+        let corners = &indices[t * 3..t * 3 + 3];
+        for k in 0..3 {
+            let (a, b) = (
+                remap[corners[k] as usize],
+                remap[corners[(k + 1) % 3] as usize],
+            );
+            forward += (a == from && b == to) as u32;
+            backward += (a == to && b == from) as u32;
+        }
+    }
+    (forward == 0) != (backward == 0)
+}
+
+/// For every vertex at position `from` still in use, a vertex at position `to`
+/// it shares a triangle with. `None` when one has no partner, which is what
+/// stops seams and hard edges from collapsing across themselves.
+fn wedge_targets(
+    indices: &[u32],
+    adjacency: &Adjacency,
+    remap: &[u32],
+    from: u32,
+    to: u32,
+) -> Option<Vec<(u32, u32)>> {
+    let mut wedges: Vec<(u32, Option<u32>)> = Vec::new();
+    for &t in adjacency.around(from) {
+        let corners = &indices[t * 3..t * 3 + 3];
+        let partner = corners.iter().copied().find(|&i| remap[i as usize] == to);
+        for &wedge in corners.iter().filter(|&&i| remap[i as usize] == from) {
+            match wedges.iter_mut().find(|(w, _)| *w == wedge) {
+                Some((_, target)) => {
+                    if target.is_none() {
+                        *target = partner;
+                    }
+                }
+                None => wedges.push((wedge, partner)),
+            }
+        }
+    }
+    wedges
+        .into_iter()
+        .map(|(wedge, target)| target.map(|target| (wedge, target)))
+        .collect()
+}
+
+/// Whether moving `from` onto `to` turns a remaining triangle over
+fn flips(
+    indices: &[u32],
+    adjacency: &Adjacency,
+    remap: &[u32],
+    positions: &[DVec3],
+    from: u32,
+    to: u32,
+) -> bool {
+    adjacency.around(from).iter().any(|&t| {
+        let corners = [0, 1, 2].map(|k| remap[indices[t * 3 + k] as usize]);
+        if corners.contains(&to) {
+            return false;
+        }
+        let before = corners.map(|i| positions[i as usize]);
+        let after = corners.map(|i| positions[if i == from { to } else { i } as usize]);
+        let n0 = (before[1] - before[0])
+            .cross(before[2] - before[0])
+            .normalize_or_zero();
+        let n1 = (after[1] - after[0])
+            .cross(after[2] - after[0])
+            .normalize_or_zero();
+        n0.dot(n1) < 0.25
+    })
+}
+
+/// Simulated post-transform cache size for [`optimize_vertex_cache`]
+const CACHE_SIZE: usize = 32;
+
+/// Forsyth's vertex score: recently used vertices and vertices with few
+/// remaining triangles score higher
+fn vertex_score(cache_position: Option<usize>, remaining: u32) -> f32 {
+    if remaining == 0 {
+        return -1.0;
+    }
+    let cache = match cache_position {
+        None => 0.0,
+        Some(p) if p < 3 => 0.75,
+        Some(p) => (1.0 - (p - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(1.5),
+    };
+    cache + 2.0 * (remaining as f32).powf(-0.5)
+}
+
+/// Reorder triangles for the post-transform vertex cache using Tom Forsyth's
+/// linear-speed algorithm
+pub fn optimize_vertex_cache(indices: &mut [u32], vertex_count: usize) {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return;
+    }
+
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for &i in indices.iter() {
+        offsets[i as usize + 1] += 1;
+    }
+    for i in 0..vertex_count {
+        offsets[i + 1] += offsets[i];
+    }
+    let mut fill = offsets.clone();
+    let mut vertex_triangles = vec![0usize; triangle_count * 3];
+    for (t, corners) in indices.chunks_exact(3).enumerate() {
+        for &i in corners {
+            vertex_triangles[fill[i as usize]] = t;
+            fill[i as usize] += 1;
+        }
+    }
+
+    let mut remaining: Vec<u32> = (0..vertex_count)
+        .map(|v| (offsets[v + 1] - offsets[v]) as u32)
+        .collect();
+    let mut cache_position: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut scores: Vec<f32> = (0..vertex_count)
+        .map(|v| vertex_score(None, remaining[v]))
+        .collect();
+    let triangle_score = |scores: &[f32], t: usize| -> f32 {
+        indices[t * 3..t * 3 + 3]
+            .iter()
+            .map(|&i| scores[i as usize])
+            .sum()
+    };
+    let mut added = vec![false; triangle_count];
+    let mut output = Vec::with_capacity(indices.len());
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut best = (0..triangle_count)
+        .max_by(|&a, &b| triangle_score(&scores, a).total_cmp(&triangle_score(&scores, b)));
+    let mut cursor = 0;
+
+    while let Some(t) = best {
+        added[t] = true;
+        let corners = [indices[t * 3], indices[t * 3 + 1], indices[t * 3 + 2]];
+        output.extend_from_slice(&corners);
+        for &v in &corners {
+            remaining[v as usize] -= 1;
+        }
+
+        let mut next_cache = corners.to_vec();
+        next_cache.extend(cache.iter().copied().filter(|v| !corners.contains(v)));
+        for &v in &next_cache[next_cache.len().min(CACHE_SIZE)..] {
+            cache_position[v as usize] = None;
+            scores[v as usize] = vertex_score(None, remaining[v as usize]);
+        }
+        next_cache.truncate(CACHE_SIZE);
+        for (p, &v) in next_cache.iter().enumerate() {
+            cache_position[v as usize] = Some(p);
+            scores[v as usize] = vertex_score(Some(p), remaining[v as usize]);
+        }
+        cache = next_cache;
+
+        best = None;
+        let mut best_score = f32::MIN;
+        for &v in &cache {
+            for &candidate in &vertex_triangles[offsets[v as usize]..offsets[v as usize + 1]] {
+                if added[candidate] {
+                    continue;
+                }
+                let score = triangle_score(&scores, candidate);
+                if score > best_score {
+                    best_score = score;
+                    best = Some(candidate);
+                }
+            }
+        }
+        if best.is_none() {
+            // Dead end: continue with the next triangle in input order
+            while cursor < triangle_count && added[cursor] {
+                cursor += 1;
+            }
+            best = (cursor < triangle_count).then_some(cursor);
+        }
+    }
+
+    indices.copy_from_slice(&output);
+}
+
+/// Vertex shader invocations per triangle with a FIFO post-transform cache of
+/// `cache_size` entries. 3.0 is the worst case; well-ordered meshes approach
+/// 0.5-0.7.
+pub fn average_cache_miss_ratio(indices: &[u32], cache_size: usize) -> f32 {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return 0.0;
+    }
+    cache_misses(indices, cache_size).iter().sum::<u32>() as f32 / triangle_count as f32
+}
+
+/// Misses of each triangle in a FIFO cache
+fn cache_misses(indices: &[u32], cache_size: usize) -> Vec<u32> {
+    let mut cache: VecDeque<u32> = VecDeque::with_capacity(cache_size);
+    indices
+        .chunks_exact(3)
+        .map(|corners| {
+            let mut misses = 0;
+            for &v in corners {
+                if !cache.contains(&v) {
+                    misses += 1;
+                    if cache.len() == cache_size {
+                        cache.pop_front();
+                    }
+                    cache.push_back(v);
+                }
+            }
+            misses
+        })
+        .collect()
+}
+
+/// FIFO cache size used to find cluster boundaries for overdraw ordering
+const OVERDRAW_CACHE_SIZE: usize = 16;
+
+/// Reorder clusters of triangles so surfaces facing away from the mesh centre
+/// draw first and occlude the ones behind them. Run after
+/// [`optimize_vertex_cache`]; clusters are split where the cache restarts
+/// anyway, and further only while their own miss ratio stays within
+/// `threshold` times that of the run they come from (e.g. 1.05).
+pub fn optimize_overdraw(indices: &mut [u32], vertices: &[Vertex], threshold: f32) {
+    let triangle_count = indices.len() / 3;
+    if triangle_count < 2 {
+        return;
+    }
+    let misses = cache_misses(indices, OVERDRAW_CACHE_SIZE);
+
+    // Hard boundaries: every vertex of the triangle missed
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for (t, &missed) in misses.iter().enumerate().skip(1) {
+        if missed == 3 {
+            runs.push(start..t);
+            start = t;
+        }
+    }
+    runs.push(start..triangle_count);
+
+    // Soft boundaries inside each run
+    let mut clusters = Vec::new();
+    for run in runs {
+        let run_misses: u32 = misses[run.clone()].iter().sum();
+        let run_ratio = run_misses as f32 / run.len() as f32;
+        let mut start = run.start;
+        let mut cluster_misses = 0;
+        for t in run.clone() {
+            cluster_misses += misses[t];
+            let len = t + 1 - start;
+            let ratio = cluster_misses as f32 / len as f32;
+            if t + 1 < run.end && misses[t + 1] >= 2 && ratio <= run_ratio * threshold {
+                clusters.push(start..t + 1);
+                start = t + 1;
+                cluster_misses = 0;
+            }
+        }
+        clusters.push(start..run.end);
+    }
+
+    let triangle = |t: usize| [0, 1, 2].map(|k| position(&vertices[indices[t * 3 + k] as usize]));
+    let mut centroid = Vec3::ZERO;
+    let mut total_area = 0.0;
+    for t in 0..triangle_count {
+        let [a, b, c] = triangle(t);
+        let area = (b - a).cross(c - a).length() * 0.5;
+        centroid += (a + b + c) / 3.0 * area;
+        total_area += area;
+    }
+    if total_area > 0.0 {
+        centroid /= total_area;
+    }
+
+    let mut keyed: Vec<(f32, std::ops::Range<usize>)> = clusters
+        .into_iter()
+        .map(|cluster| {
+            let mut center = Vec3::ZERO;
+            let mut normal = Vec3::ZERO;
+            let mut area = 0.0;
+            for t in cluster.clone() {
+                let [a, b, c] = triangle(t);
+                let n = (b - a).cross(c - a);
+                let triangle_area = n.length() * 0.5;
+                center += (a + b + c) / 3.0 * triangle_area;
+                normal += n;
+                area += triangle_area;
+            }
+            if area > 0.0 {
+                center /= area;
+            }
+            ((center - centroid).dot(normal.normalize_or_zero()), cluster)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut output = Vec::with_capacity(indices.len());
+    for (_, cluster) in keyed {
+        output.extend_from_slice(&indices[cluster.start * 3..cluster.end * 3]);
+    }
+    indices.copy_from_slice(&output);
+}
+
+/// Reorder vertices by first use and drop the unused ones
+pub fn optimize_vertex_fetch(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut remap = vec![u32::MAX; vertices.len()];
+    let mut ordered = Vec::new();
+    let indices = indices
+        .iter()
+        .map(|&i| {
+            let slot = &mut remap[i as usize];
+            if *slot == u32::MAX {
+                *slot = ordered.len() as u32;
+                ordered.push(vertices[i as usize]);
+            }
+            *slot
+        })
+        .collect();
+    (ordered, indices)
+}
+
+/// Reorder for the vertex cache, overdraw and vertex fetch, in that order
+pub fn optimize(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut indices = indices.to_vec();
+    optimize_vertex_cache(&mut indices, vertices.len());
+    optimize_overdraw(&mut indices, vertices, 1.05);
+    optimize_vertex_fetch(vertices, &indices)
+}
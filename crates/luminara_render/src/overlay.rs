@@ -2,8 +2,15 @@
 //!
 //! Minimal 2D overlay for rendering text and colored rectangles on top of the
 //! 3D scene. Uses an embedded 8×8 bitmap font (ASCII 32–126) and a single
-//! draw call per frame.
+//! draw call per frame, plus a second one for text laid out with
+//! [`crate::text::layout_text`], which draws from the overlay's own glyph
+//! atlas.
 
+use crate::glyph_atlas::{GlyphAtlas, GlyphAtlasError, GlyphRasterMode};
+use crate::render_graph::{slots, PassSlots, RenderContext, RenderNode};
+use crate::render_world::ExtractedOverlay;
+use crate::text::{GlyphQuad, TextLayout};
+use crate::RenderError;
 use luminara_core::shared_types::Resource;
 use luminara_diagnostic::profiler::OverlayRendererInterface;
 use wgpu::util::DeviceExt;
@@ -40,17 +47,29 @@ pub enum OverlayCommand {
         color: [f32; 4],
         scale: f32,
     },
+    /// Glyph quads of a laid-out text, drawn from the overlay's glyph atlas
+    /// after all other commands.
+    Glyphs {
+        x: f32,
+        y: f32,
+        quads: Vec<GlyphQuad>,
+    },
 }
 
-/// GPU-backed overlay renderer.  Insert as a resource; `render_system` will
-/// drain the command queue each frame.
+/// GPU-backed overlay renderer.  Insert as a resource; the render graph's
+/// [`OverlayNode`] drains the command queue each frame.
 pub struct OverlayRenderer {
     /// Draw commands to be rendered this frame (cleared after rendering).
     pub commands: Vec<OverlayCommand>,
+    /// Coverage atlas for [`OverlayCommand::Glyphs`].
+    pub text_atlas: GlyphAtlas,
     // -- lazy GPU state --
     initialized: bool,
     pipeline: Option<wgpu::RenderPipeline>,
     font_bind_group: Option<wgpu::BindGroup>,
+    bind_group_layout: Option<wgpu::BindGroupLayout>,
+    text_sampler: Option<wgpu::Sampler>,
+    text_bind_group: Option<wgpu::BindGroup>,
 }
 
 impl Resource for OverlayRenderer {}
@@ -59,9 +78,13 @@ impl Default for OverlayRenderer {
     fn default() -> Self {
         Self {
             commands: Vec::new(),
+            text_atlas: GlyphAtlas::new(GlyphRasterMode::Coverage),
             initialized: false,
             pipeline: None,
             font_bind_group: None,
+            bind_group_layout: None,
+            text_sampler: None,
+            text_bind_group: None,
         }
     }
 }
@@ -162,6 +185,19 @@ impl OverlayRenderer {
         });
     }
 
+    /// Queue a laid-out text with its top-left corner at the given pixel
+    /// position, rasterizing any new glyphs into [`Self::text_atlas`].
+    pub fn draw_text_layout(
+        &mut self,
+        x: f32,
+        y: f32,
+        layout: &TextLayout,
+    ) -> Result<(), GlyphAtlasError> {
+        let quads = layout.quads(&mut self.text_atlas)?;
+        self.commands.push(OverlayCommand::Glyphs { x, y, quads });
+        Ok(())
+    }
+
     /// Clear all queued commands without rendering.
     pub fn clear(&mut self) {
         self.commands.clear();
@@ -184,15 +220,19 @@ impl OverlayRenderer {
 
         self.ensure_initialized(device, queue, surface_format);
 
-        let vertices = self.generate_vertices(screen_width, screen_height);
-        if vertices.is_empty() {
+        let (vertices, glyph_vertices) = self.generate_vertices(screen_width, screen_height);
+        if vertices.is_empty() && glyph_vertices.is_empty() {
             self.commands.clear();
             return;
         }
 
+        if !glyph_vertices.is_empty() {
+            self.prepare_text_atlas(device, queue);
+        }
+
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Overlay VB"),
-            contents: bytemuck::cast_slice(&vertices),
+            contents: bytemuck::cast_slice(&[vertices.as_slice(), &glyph_vertices].concat()),
             usage: wgpu::BufferUsages::VERTEX,
         });
 
@@ -218,6 +258,14 @@ impl OverlayRenderer {
             // Disable backface culling implicitly by topology or ensure winding is correct.
             // Vertices are generated as CCW (TL->BL->TR, TR->BL->BR) which is standard.
             pass.draw(0..vertices.len() as u32, 0..1);
+
+            if let Some(text_bind_group) = &self.text_bind_group {
+                if !glyph_vertices.is_empty() {
+                    let start = vertices.len() as u32;
+                    pass.set_bind_group(0, text_bind_group, &[]);
+                    pass.draw(start..start + glyph_vertices.len() as u32, 0..1);
+                }
+            }
         }
 
         self.commands.clear();
@@ -227,6 +275,41 @@ impl OverlayRenderer {
     // Internals
     // --------------------------------------------------------------------
 
+    /// Upload the glyph atlas and rebuild its bind group when the texture
+    /// was recreated.
+    fn prepare_text_atlas(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let recreated = self.text_atlas.upload(device, queue);
+        if !recreated && self.text_bind_group.is_some() {
+            return;
+        }
+        let (Some(layout), Some(view)) = (&self.bind_group_layout, self.text_atlas.texture_view())
+        else {
+            return;
+        };
+        let sampler = self.text_sampler.get_or_insert_with(|| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Overlay Text Sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            })
+        });
+        self.text_bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Overlay Text BG"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        }));
+    }
+
     fn ensure_initialized(
         &mut self,
         device: &wgpu::Device,
@@ -355,12 +438,15 @@ impl OverlayRenderer {
 
         self.pipeline = Some(pipeline);
         self.font_bind_group = Some(bind_group);
+        self.bind_group_layout = Some(bgl);
         self.initialized = true;
         log::info!("Overlay renderer initialised (128×48 font atlas)");
     }
 
-    fn generate_vertices(&self, sw: u32, sh: u32) -> Vec<OverlayVertex> {
+    /// Vertices for the bitmap-font pass and for the glyph atlas pass.
+    fn generate_vertices(&self, sw: u32, sh: u32) -> (Vec<OverlayVertex>, Vec<OverlayVertex>) {
         let mut verts = Vec::with_capacity(self.commands.len() * 24);
+        let mut glyph_verts = Vec::new();
         let sw = sw as f32;
         let sh = sh as f32;
 
@@ -419,9 +505,27 @@ impl OverlayRenderer {
                         cx += cw;
                     }
                 }
+                OverlayCommand::Glyphs { x, y, quads } => {
+                    for quad in quads {
+                        let (x0, y0) = px_to_ndc(x + quad.min.x, y + quad.min.y, sw, sh);
+                        let (x1, y1) = px_to_ndc(x + quad.max.x, y + quad.max.y, sw, sh);
+                        push_quad(
+                            &mut glyph_verts,
+                            x0,
+                            y0,
+                            x1,
+                            y1,
+                            quad.uv_min.x,
+                            quad.uv_min.y,
+                            quad.uv_max.x,
+                            quad.uv_max.y,
+                            quad.color.into(),
+                        );
+                    }
+                }
             }
         }
-        verts
+        (verts, glyph_verts)
     }
 }
 
@@ -607,6 +711,7 @@ fn char_uv_by_index(idx: usize) -> (f32, f32, f32, f32) {
 }
 
 /// Expand the compact bit-per-pixel font into an R8 byte array for wgpu.
+/// Bit 0 of each row byte is the leftmost pixel.
 fn build_font_texture_data() -> Vec<u8> {
     let mut data = vec![0u8; FONT_TEX_W * FONT_TEX_H];
     for (idx, glyph) in FONT_DATA.iter().enumerate() {
@@ -615,7 +720,7 @@ fn build_font_texture_data() -> Vec<u8> {
         for y in 0..8 {
             let byte = glyph[y];
             for x in 0..8 {
-                let pixel = if byte & (0x80 >> x) != 0 { 255u8 } else { 0u8 };
+                let pixel = if byte & (1 << x) != 0 { 255u8 } else { 0u8 };
                 let tx = col * 8 + x;
                 let ty = row * 8 + y;
                 data[ty * FONT_TEX_W + tx] = pixel;
@@ -860,7 +965,6 @@ const FONT_DATA: [[u8; 8]; 96] = [
     [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
 ];
 
-
 // ============================================================================
 // Integration with luminara_diagnostic
 // ============================================================================
@@ -874,3 +978,55 @@ impl OverlayRendererInterface for OverlayRenderer {
         self.draw_text(x, y, text, color, scale);
     }
 }
+
+// ============================================================================
+// Render graph node
+// ============================================================================
+
+/// Draws the queued overlay commands on top of the presented frame
+pub struct OverlayNode;
+
+impl OverlayNode {
+    pub const NAME: &'static str = "overlay";
+}
+
+impl RenderNode for OverlayNode {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn declare(&self, slots: &mut PassSlots) {
+        slots.read(slots::SWAPCHAIN).write(slots::SWAPCHAIN);
+    }
+
+    fn run<'a>(&self, context: &mut RenderContext<'a>) -> Result<(), RenderError> {
+        let Some(mut overlay) = context
+            .world
+            .and_then(|world| world.get_resource_mut::<OverlayRenderer>())
+        else {
+            return Ok(());
+        };
+        // Render worlds carry the commands of their own frame; the ones on
+        // the shared renderer were queued since and wait for the next frame
+        let extracted = context
+            .world
+            .and_then(|world| world.get_resource_mut::<ExtractedOverlay>())
+            .map(|mut extracted| std::mem::take(&mut extracted.commands));
+        let queued = extracted.map(|commands| std::mem::replace(&mut overlay.commands, commands));
+
+        let (width, height) = context.target_size;
+        overlay.render(
+            context.device,
+            context.queue,
+            context.encoder,
+            context.view,
+            context.target_format,
+            width,
+            height,
+        );
+        if let Some(queued) = queued {
+            overlay.commands = queued;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1145 @@
+//! Data-driven particle effects.
+//!
+//! A [`ParticleEffect`] asset describes emitters: where particles spawn, how
+//! many, how they change over their lifetime, the forces acting on them and
+//! what they collide with. Each entity with a [`ParticleEffectInstance`]
+//! simulates its own pools from a seed, so a run can be replayed exactly.
+
+use crate::mesh::Mesh;
+use crate::particles::ParticleInstance;
+use crate::skinning::global_matrix;
+use luminara_asset::{Asset, AssetId, AssetLoadError, AssetLoader, AssetServer, Handle};
+use luminara_core::{Component, Entity, Query, Resource, World};
+use luminara_math::{Color, Mat4, Quat, Vec3};
+use serde::{Deserialize, Deserializer};
+use std::path::Path;
+use std::sync::Arc;
+
+// ── Randomness ──────────────────────────────────────────────────────────
+
+/// Small deterministic generator (SplitMix64) so effects replay exactly
+#[derive(Debug, Clone)]
+pub struct ParticleRng(u64);
+
+impl ParticleRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Uniform direction
+    pub fn unit_vector(&mut self) -> Vec3 {
+        let z = self.range(-1.0, 1.0);
+        let angle = self.range(0.0, std::f32::consts::TAU);
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        Vec3::new(r * angle.cos(), r * angle.sin(), z)
+    }
+}
+
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> f32 {
+    let mut h = (x as u32)
+        .wrapping_mul(0x8DA6_B343)
+        .wrapping_add((y as u32).wrapping_mul(0xD816_3841))
+        .wrapping_add((z as u32).wrapping_mul(0xCB1A_B31F))
+        .wrapping_add(seed.wrapping_mul(0x1656_67B1));
+    h = (h ^ (h >> 13)).wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    (h as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
+
+/// Smooth value noise in `[-1, 1]`
+fn value_noise(p: Vec3, seed: u32) -> f32 {
+    let cell = p.floor();
+    let f = p - cell;
+    let s = f * f * (Vec3::splat(3.0) - 2.0 * f);
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+
+    let corner = |dx: i32, dy: i32, dz: i32| hash(x + dx, y + dy, z + dz, seed);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), s.x);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), s.x);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), s.x);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), s.x);
+    lerp(lerp(x00, x10, s.y), lerp(x01, x11, s.y), s.z)
+}
+
+fn vector_noise(p: Vec3) -> Vec3 {
+    Vec3::new(value_noise(p, 1), value_noise(p, 2), value_noise(p, 3))
+}
+
+// ── Curves ──────────────────────────────────────────────────────────────
+
+/// Uniformly random value between `min` and `max`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ValueRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl ValueRange {
+    pub const fn constant(value: f32) -> Self {
+        Self {
+            min: value,
+            max: value,
+        }
+    }
+
+    pub const fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    pub fn sample(&self, rng: &mut ParticleRng) -> f32 {
+        rng.range(self.min, self.max)
+    }
+}
+
+/// Piecewise linear curve over a particle's normalized age, as
+/// `(time, value)` keys sorted by time
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Curve {
+    pub keys: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    pub fn constant(value: f32) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    pub fn linear(start: f32, end: f32) -> Self {
+        Self {
+            keys: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    pub fn sample(&self, t: f32) -> f32 {
+        sample_keys(&self.keys, t, |a, b, t| a + (b - a) * t).unwrap_or(1.0)
+    }
+}
+
+/// Colors over a particle's normalized age, as `(time, color)` keys sorted
+/// by time
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Gradient {
+    pub keys: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    pub fn linear(start: Color, end: Color) -> Self {
+        Self {
+            keys: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    pub fn sample(&self, t: f32) -> Color {
+        sample_keys(&self.keys, t, |a, b, t| {
+            Color::rgba(
+                a.r + (b.r - a.r) * t,
+                a.g + (b.g - a.g) * t,
+                a.b + (b.b - a.b) * t,
+                a.a + (b.a - a.a) * t,
+            )
+        })
+        .unwrap_or(Color::WHITE)
+    }
+}
+
+fn sample_keys<T: Copy>(keys: &[(f32, T)], t: f32, mix: impl Fn(T, T, f32) -> T) -> Option<T> {
+    let first = keys.first()?;
+    if t <= first.0 {
+        return Some(first.1);
+    }
+    for pair in keys.windows(2) {
+        let ((t0, a), (t1, b)) = (pair[0], pair[1]);
+        if t <= t1 {
+            let span = t1 - t0;
+            let f = if span > f32::EPSILON {
+                (t - t0) / span
+            } else {
+                1.0
+            };
+            return Some(mix(a, b, f));
+        }
+    }
+    keys.last().map(|(_, value)| *value)
+}
+
+// ── Effect description ──────────────────────────────────────────────────
+
+fn handle_from_path<'de, D, T>(deserializer: D) -> Result<Handle<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Asset,
+{
+    let path = String::deserialize(deserializer)?;
+    Ok(Handle::new(AssetId::from_path(&path), 0))
+}
+
+/// Where particles spawn, in the emitter's space, and their initial direction
+#[derive(Debug, Clone, Default, Deserialize)]
+pub enum EmissionShape {
+    /// At the origin, in any direction
+    #[default]
+    Point,
+    /// Inside the sphere, or on it with `surface_only`, moving outwards
+    Sphere {
+        radius: f32,
+        #[serde(default)]
+        surface_only: bool,
+    },
+    /// From a disc of `radius`, within `angle` degrees of +Y
+    Cone { angle: f32, radius: f32 },
+    /// Inside the box, moving along +Y
+    Box { half_extents: Vec3 },
+    /// Along the segment, moving along +Y
+    Edge { start: Vec3, end: Vec3 },
+    /// On the triangles of a mesh, weighted by area, along the surface normal
+    MeshSurface {
+        #[serde(deserialize_with = "handle_from_path")]
+        mesh: Handle<Mesh>,
+    },
+}
+
+/// Particles emitted at once
+#[derive(Debug, Clone, Deserialize)]
+pub struct Burst {
+    /// Seconds into each emitter cycle
+    pub time: f32,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum ParticleForce {
+    /// Constant acceleration in world space
+    Gravity(Vec3),
+    /// Fraction of velocity lost per second
+    Drag(f32),
+    /// Turbulence from smooth noise over the particle's position
+    Noise {
+        strength: f32,
+        frequency: f32,
+        /// How fast the noise field moves, in noise cells per second
+        #[serde(default)]
+        scroll: f32,
+    },
+    /// Pull towards a point of the emitter's space; a zero `radius` reaches
+    /// everywhere
+    Attractor {
+        position: Vec3,
+        strength: f32,
+        #[serde(default)]
+        radius: f32,
+    },
+}
+
+/// How particles react to [`ParticleColliders`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParticleCollision {
+    /// Fraction of the normal velocity kept when bouncing
+    #[serde(default)]
+    pub bounce: f32,
+    /// Fraction of the tangential velocity lost on contact
+    #[serde(default)]
+    pub friction: f32,
+    /// Particle radius as a fraction of its size
+    #[serde(default = "half")]
+    pub radius_scale: f32,
+    #[serde(default)]
+    pub kill: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum SubEmitterTrigger {
+    Birth,
+    Death,
+    Collision,
+}
+
+/// Particles spawned into another emitter of the same effect
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubEmitter {
+    pub trigger: SubEmitterTrigger,
+    /// Name of the target emitter
+    pub emitter: String,
+    pub count: u32,
+    /// Fraction of the parent's velocity added to the new particles
+    #[serde(default)]
+    pub inherit_velocity: f32,
+}
+
+/// Space particles are simulated in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum SimulationSpace {
+    /// Particles move with the emitter entity
+    Local,
+    /// Particles stay where they were emitted
+    #[default]
+    World,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParticleEmitterDesc {
+    pub name: String,
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// Particles per second
+    #[serde(default)]
+    pub rate: f32,
+    #[serde(default)]
+    pub bursts: Vec<Burst>,
+    /// Length of one emission cycle in seconds
+    #[serde(default = "default_duration")]
+    pub duration: f32,
+    #[serde(default = "yes")]
+    pub looping: bool,
+    #[serde(default)]
+    pub shape: EmissionShape,
+    pub lifetime: ValueRange,
+    pub speed: ValueRange,
+    pub size: ValueRange,
+    #[serde(default = "white")]
+    pub color: Color,
+    #[serde(default)]
+    pub size_over_lifetime: Option<Curve>,
+    #[serde(default)]
+    pub color_over_lifetime: Option<Gradient>,
+    /// Multiplies the particle's velocity
+    #[serde(default)]
+    pub speed_over_lifetime: Option<Curve>,
+    #[serde(default)]
+    pub forces: Vec<ParticleForce>,
+    #[serde(default)]
+    pub collision: Option<ParticleCollision>,
+    #[serde(default)]
+    pub sub_emitters: Vec<SubEmitter>,
+    #[serde(default)]
+    pub space: SimulationSpace,
+}
+
+impl ParticleEmitterDesc {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            capacity: default_capacity(),
+            rate: 0.0,
+            bursts: Vec::new(),
+            duration: default_duration(),
+            looping: true,
+            shape: EmissionShape::Point,
+            lifetime: ValueRange::constant(1.0),
+            speed: ValueRange::constant(1.0),
+            size: ValueRange::constant(0.1),
+            color: Color::WHITE,
+            size_over_lifetime: None,
+            color_over_lifetime: None,
+            speed_over_lifetime: None,
+            forces: Vec::new(),
+            collision: None,
+            sub_emitters: Vec::new(),
+            space: SimulationSpace::World,
+        }
+    }
+}
+
+fn default_capacity() -> usize {
+    1000
+}
+
+fn default_duration() -> f32 {
+    1.0
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn half() -> f32 {
+    0.5
+}
+
+fn white() -> Color {
+    Color::WHITE
+}
+
+/// A set of emitters played together
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ParticleEffect {
+    pub emitters: Vec<ParticleEmitterDesc>,
+}
+
+impl Asset for ParticleEffect {
+    fn type_name() -> &'static str {
+        "ParticleEffect"
+    }
+}
+
+impl ParticleEffect {
+    pub fn emitter_index(&self, name: &str) -> Option<usize> {
+        self.emitters.iter().position(|e| e.name == name)
+    }
+}
+
+/// Loads `.particles.ron` files
+pub struct ParticleEffectLoader;
+
+impl AssetLoader for ParticleEffectLoader {
+    type Asset = ParticleEffect;
+
+    fn extensions(&self) -> &[&str] {
+        &["particles.ron"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let source = std::str::from_utf8(bytes)
+            .map_err(|e| AssetLoadError::Parse(format!("Particle effect is not UTF-8: {}", e)))?;
+        let effect: ParticleEffect =
+            ron::from_str(source).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+
+        for emitter in &effect.emitters {
+            for sub in &emitter.sub_emitters {
+                if effect.emitter_index(&sub.emitter).is_none() {
+                    return Err(AssetLoadError::Parse(format!(
+                        "Emitter '{}' spawns into unknown emitter '{}'",
+                        emitter.name, sub.emitter
+                    )));
+                }
+            }
+        }
+        Ok(effect)
+    }
+}
+
+// ── Colliders ───────────────────────────────────────────────────────────
+
+/// World-space shape particles collide with
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParticleCollider {
+    /// Points with `dot(p, normal) < distance` are inside
+    Plane {
+        normal: Vec3,
+        distance: f32,
+    },
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    Box {
+        center: Vec3,
+        rotation: Quat,
+        half_extents: Vec3,
+    },
+    Capsule {
+        a: Vec3,
+        b: Vec3,
+        radius: f32,
+    },
+}
+
+impl ParticleCollider {
+    /// Signed distance from `point` to the surface and the outward normal
+    pub fn distance(&self, point: Vec3) -> (f32, Vec3) {
+        match *self {
+            ParticleCollider::Plane { normal, distance } => {
+                let normal = normal.normalize_or_zero();
+                (point.dot(normal) - distance, normal)
+            }
+            ParticleCollider::Sphere { center, radius } => {
+                let offset = point - center;
+                (
+                    offset.length() - radius,
+                    offset.try_normalize().unwrap_or(Vec3::Y),
+                )
+            }
+            ParticleCollider::Capsule { a, b, radius } => {
+                let axis = b - a;
+                let t = if axis.length_squared() > f32::EPSILON {
+                    ((point - a).dot(axis) / axis.length_squared()).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let offset = point - (a + axis * t);
+                (
+                    offset.length() - radius,
+                    offset.try_normalize().unwrap_or(Vec3::Y),
+                )
+            }
+            ParticleCollider::Box {
+                center,
+                rotation,
+                half_extents,
+            } => {
+                let local = rotation.inverse() * (point - center);
+                let q = local.abs() - half_extents;
+                let outside = q.max(Vec3::ZERO);
+                if outside.length_squared() > 0.0 {
+                    let normal = (outside * local.signum()).normalize();
+                    (outside.length(), rotation * normal)
+                } else {
+                    // Inside: leave through the nearest face
+                    let axis = if q.x >= q.y && q.x >= q.z {
+                        Vec3::X * local.x.signum()
+                    } else if q.y >= q.z {
+                        Vec3::Y * local.y.signum()
+                    } else {
+                        Vec3::Z * local.z.signum()
+                    };
+                    (q.max_element(), rotation * axis)
+                }
+            }
+        }
+    }
+}
+
+/// Shapes particles collide with this frame. The physics plugin fills it
+/// from its colliders; effects may add their own.
+#[derive(Debug, Clone, Default)]
+pub struct ParticleColliders(pub Vec<ParticleCollider>);
+
+impl Resource for ParticleColliders {}
+
+// ── Simulation ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EffectParticle {
+    /// In the emitter's simulation space
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub age: f32,
+    pub lifetime: f32,
+    pub start_size: f32,
+    pub start_color: Color,
+    /// Size after `size_over_lifetime`
+    pub size: f32,
+    /// Color after `color_over_lifetime`
+    pub color: Color,
+}
+
+impl EffectParticle {
+    /// Fraction of the lifetime elapsed
+    pub fn normalized_age(&self) -> f32 {
+        if self.lifetime > 0.0 {
+            (self.age / self.lifetime).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Live particles and emission state of one emitter
+#[derive(Debug, Clone)]
+pub struct EmitterState {
+    pub particles: Vec<EffectParticle>,
+    /// Seconds into the current cycle
+    pub time: f32,
+    pub finished: bool,
+    accumulator: f32,
+    rng: ParticleRng,
+}
+
+/// Particle lifecycle event that can start sub-emitters
+#[derive(Debug, Clone, Copy)]
+struct SpawnEvent {
+    emitter: usize,
+    trigger: SubEmitterTrigger,
+    position: Vec3,
+    velocity: Vec3,
+}
+
+/// What an effect simulation needs from the world
+pub struct ParticleSimulationContext<'a> {
+    /// World transform of the emitting entity
+    pub transform: Mat4,
+    pub colliders: &'a [ParticleCollider],
+    pub meshes: &'a dyn Fn(&Handle<Mesh>) -> Option<Arc<Mesh>>,
+}
+
+/// Sub-emitters spawning sub-emitters stop after this many generations in
+/// one frame
+const MAX_SUB_EMITTER_DEPTH: usize = 8;
+
+/// Plays a [`ParticleEffect`] on its entity
+pub struct ParticleEffectInstance {
+    pub effect: Handle<ParticleEffect>,
+    pub seed: u64,
+    pub playing: bool,
+    /// Per-emitter pools, in the order of [`ParticleEffect::emitters`]
+    pub emitters: Vec<EmitterState>,
+}
+
+impl Component for ParticleEffectInstance {
+    fn type_name() -> &'static str {
+        "ParticleEffectInstance"
+    }
+}
+
+impl ParticleEffectInstance {
+    pub fn new(effect: Handle<ParticleEffect>, seed: u64) -> Self {
+        Self {
+            effect,
+            seed,
+            playing: true,
+            emitters: Vec::new(),
+        }
+    }
+
+    /// Live particles across all emitters
+    pub fn particle_count(&self) -> usize {
+        self.emitters.iter().map(|e| e.particles.len()).sum()
+    }
+
+    /// Whether every emitter stopped emitting and its particles died
+    pub fn is_finished(&self) -> bool {
+        !self.emitters.is_empty()
+            && self
+                .emitters
+                .iter()
+                .all(|e| e.finished && e.particles.is_empty())
+    }
+
+    /// Start again from the seed
+    pub fn restart(&mut self) {
+        self.emitters.clear();
+        self.playing = true;
+    }
+
+    /// Advance the simulation by `dt` seconds
+    pub fn simulate(
+        &mut self,
+        effect: &ParticleEffect,
+        context: &ParticleSimulationContext,
+        dt: f32,
+    ) {
+        if self.emitters.len() != effect.emitters.len() {
+            self.emitters = (0..effect.emitters.len())
+                .map(|index| EmitterState {
+                    particles: Vec::new(),
+                    time: 0.0,
+                    finished: false,
+                    accumulator: 0.0,
+                    rng: ParticleRng::new(
+                        self.seed ^ (index as u64).wrapping_mul(0xA24B_AED4_963E_E407),
+                    ),
+                })
+                .collect();
+        }
+        if !self.playing {
+            return;
+        }
+
+        let mut events = Vec::new();
+        for (index, desc) in effect.emitters.iter().enumerate() {
+            let count = emission_count(&mut self.emitters[index], desc, dt);
+            for _ in 0..count {
+                spawn(
+                    &mut self.emitters[index],
+                    desc,
+                    index,
+                    context,
+                    None,
+                    &mut events,
+                );
+            }
+            update(
+                &mut self.emitters[index],
+                desc,
+                index,
+                context,
+                dt,
+                &mut events,
+            );
+        }
+
+        // Sub-emitters, which may in turn trigger more
+        for _ in 0..MAX_SUB_EMITTER_DEPTH {
+            if events.is_empty() {
+                break;
+            }
+            let mut next = Vec::new();
+            for event in events {
+                for sub in &effect.emitters[event.emitter].sub_emitters {
+                    if sub.trigger != event.trigger {
+                        continue;
+                    }
+                    let Some(target) = effect.emitter_index(&sub.emitter) else {
+                        continue;
+                    };
+                    let from = effect.emitters[event.emitter].space;
+                    let to = effect.emitters[target].space;
+                    let origin = (
+                        convert_point(event.position, from, to, context.transform),
+                        convert_vector(event.velocity, from, to, context.transform)
+                            * sub.inherit_velocity,
+                    );
+                    for _ in 0..sub.count {
+                        spawn(
+                            &mut self.emitters[target],
+                            &effect.emitters[target],
+                            target,
+                            context,
+                            Some(origin),
+                            &mut next,
+                        );
+                    }
+                }
+            }
+            events = next;
+        }
+    }
+
+    /// Instance data for drawing the particles with the shared particle
+    /// pipeline
+    pub fn render_instances(
+        &self,
+        effect: &ParticleEffect,
+        transform: Mat4,
+    ) -> Vec<ParticleInstance> {
+        let mut instances = Vec::with_capacity(self.particle_count());
+        for (state, desc) in self.emitters.iter().zip(&effect.emitters) {
+            for particle in &state.particles {
+                let position = match desc.space {
+                    SimulationSpace::Local => transform.transform_point3(particle.position),
+                    SimulationSpace::World => particle.position,
+                };
+                instances.push(ParticleInstance::new(
+                    position,
+                    particle.size,
+                    particle.color,
+                ));
+            }
+        }
+        instances
+    }
+}
+
+fn convert_point(point: Vec3, from: SimulationSpace, to: SimulationSpace, transform: Mat4) -> Vec3 {
+    match (from, to) {
+        (SimulationSpace::Local, SimulationSpace::World) => transform.transform_point3(point),
+        (SimulationSpace::World, SimulationSpace::Local) => {
+            transform.inverse().transform_point3(point)
+        }
+        _ => point,
+    }
+}
+
+fn convert_vector(
+    vector: Vec3,
+    from: SimulationSpace,
+    to: SimulationSpace,
+    transform: Mat4,
+) -> Vec3 {
+    match (from, to) {
+        (SimulationSpace::Local, SimulationSpace::World) => transform.transform_vector3(vector),
+        (SimulationSpace::World, SimulationSpace::Local) => {
+            transform.inverse().transform_vector3(vector)
+        }
+        _ => vector,
+    }
+}
+
+/// Particles due from the rate and bursts over the next `dt` seconds
+fn emission_count(state: &mut EmitterState, desc: &ParticleEmitterDesc, dt: f32) -> u32 {
+    if state.finished {
+        return 0;
+    }
+
+    let start = state.time;
+    let end = start + dt;
+    let mut count = 0;
+
+    for burst in &desc.bursts {
+        let crossed = if start == 0.0 {
+            burst.time >= start && burst.time < end
+        } else {
+            burst.time > start && burst.time <= end
+        };
+        if crossed {
+            count += burst.count;
+        }
+    }
+
+    state.accumulator += desc.rate * dt;
+    let due = state.accumulator.floor();
+    state.accumulator -= due;
+    count += due as u32;
+
+    if end >= desc.duration {
+        if desc.looping && desc.duration > 0.0 {
+            // Bursts at the start of the next cycle
+            let overflow = end - desc.duration;
+            for burst in &desc.bursts {
+                if burst.time < overflow.min(desc.duration) {
+                    count += burst.count;
+                }
+            }
+            state.time = overflow % desc.duration;
+        } else {
+            state.time = desc.duration;
+            state.finished = true;
+        }
+    } else {
+        state.time = end;
+    }
+    count
+}
+
+/// Initial position and direction on the emission shape
+fn sample_shape(
+    shape: &EmissionShape,
+    rng: &mut ParticleRng,
+    meshes: &dyn Fn(&Handle<Mesh>) -> Option<Arc<Mesh>>,
+) -> (Vec3, Vec3) {
+    match shape {
+        EmissionShape::Point => (Vec3::ZERO, rng.unit_vector()),
+        EmissionShape::Sphere {
+            radius,
+            surface_only,
+        } => {
+            let direction = rng.unit_vector();
+            let distance = if *surface_only {
+                *radius
+            } else {
+                // Uniform over the volume
+                radius * rng.next_f32().cbrt()
+            };
+            (direction * distance, direction)
+        }
+        EmissionShape::Cone { angle, radius } => {
+            let disc_angle = rng.range(0.0, std::f32::consts::TAU);
+            let disc_radius = radius * rng.next_f32().sqrt();
+            let position = Vec3::new(disc_angle.cos(), 0.0, disc_angle.sin()) * disc_radius;
+
+            // Uniform over the spherical cap
+            let min_cos = angle.to_radians().cos();
+            let cos = rng.range(min_cos, 1.0);
+            let sin = (1.0 - cos * cos).max(0.0).sqrt();
+            let around = rng.range(0.0, std::f32::consts::TAU);
+            let direction = Vec3::new(sin * around.cos(), cos, sin * around.sin());
+            (position, direction)
+        }
+        EmissionShape::Box { half_extents } => {
+            let position = Vec3::new(
+                rng.range(-half_extents.x, half_extents.x),
+                rng.range(-half_extents.y, half_extents.y),
+                rng.range(-half_extents.z, half_extents.z),
+            );
+            (position, Vec3::Y)
+        }
+        EmissionShape::Edge { start, end } => (start.lerp(*end, rng.next_f32()), Vec3::Y),
+        EmissionShape::MeshSurface { mesh } => {
+            let Some(mesh) = meshes(mesh) else {
+                return (Vec3::ZERO, Vec3::Y);
+            };
+            sample_mesh_surface(&mesh, rng).unwrap_or((Vec3::ZERO, Vec3::Y))
+        }
+    }
+}
+
+fn sample_mesh_surface(mesh: &Mesh, rng: &mut ParticleRng) -> Option<(Vec3, Vec3)> {
+    let triangle = |i: usize| {
+        let corner =
+            |k: usize| Vec3::from(mesh.vertices[mesh.indices[i * 3 + k] as usize].position);
+        (corner(0), corner(1), corner(2))
+    };
+    let triangles = mesh.indices.len() / 3;
+    let areas: Vec<f32> = (0..triangles)
+        .map(|i| {
+            let (a, b, c) = triangle(i);
+            (b - a).cross(c - a).length() * 0.5
+        })
+        .collect();
+    let total: f32 = areas.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut pick = rng.next_f32() * total;
+    let index = areas
+        .iter()
+        .position(|&area| {
+            pick -= area;
+            pick < 0.0
+        })
+        .unwrap_or(triangles - 1);
+
+    let (a, b, c) = triangle(index);
+    let (mut u, mut v) = (rng.next_f32(), rng.next_f32());
+    if u + v > 1.0 {
+        u = 1.0 - u;
+        v = 1.0 - v;
+    }
+    let position = a + (b - a) * u + (c - a) * v;
+    let normal = (b - a).cross(c - a).normalize_or_zero();
+    Some((position, normal))
+}
+
+/// Spawn one particle, from the shape or at `origin` (position, added
+/// velocity) for sub-emitters
+fn spawn(
+    state: &mut EmitterState,
+    desc: &ParticleEmitterDesc,
+    index: usize,
+    context: &ParticleSimulationContext,
+    origin: Option<(Vec3, Vec3)>,
+    events: &mut Vec<SpawnEvent>,
+) {
+    if state.particles.len() >= desc.capacity {
+        return;
+    }
+
+    let rng = &mut state.rng;
+    let (offset, direction) = sample_shape(&desc.shape, rng, context.meshes);
+    let speed = desc.speed.sample(rng);
+    let lifetime = desc.lifetime.sample(rng);
+    let size = desc.size.sample(rng);
+
+    let (position, velocity) = match origin {
+        Some((position, inherited)) => (position + offset, direction * speed + inherited),
+        None => {
+            let velocity = direction * speed;
+            match desc.space {
+                SimulationSpace::Local => (offset, velocity),
+                SimulationSpace::World => (
+                    context.transform.transform_point3(offset),
+                    context.transform.transform_vector3(velocity),
+                ),
+            }
+        }
+    };
+
+    let particle = EffectParticle {
+        position,
+        velocity,
+        age: 0.0,
+        lifetime,
+        start_size: size,
+        start_color: desc.color,
+        size: size
+            * desc
+                .size_over_lifetime
+                .as_ref()
+                .map_or(1.0, |c| c.sample(0.0)),
+        color: desc
+            .color_over_lifetime
+            .as_ref()
+            .map_or(desc.color, |g| multiply(desc.color, g.sample(0.0))),
+    };
+    state.particles.push(particle);
+    events.push(SpawnEvent {
+        emitter: index,
+        trigger: SubEmitterTrigger::Birth,
+        position,
+        velocity,
+    });
+}
+
+fn multiply(a: Color, b: Color) -> Color {
+    Color::rgba(a.r * b.r, a.g * b.g, a.b * b.b, a.a * b.a)
+}
+
+/// Age, move and collide the particles of one emitter
+fn update(
+    state: &mut EmitterState,
+    desc: &ParticleEmitterDesc,
+    index: usize,
+    context: &ParticleSimulationContext,
+    dt: f32,
+    events: &mut Vec<SpawnEvent>,
+) {
+    let local = desc.space == SimulationSpace::Local;
+    let to_world = context.transform;
+    let to_local = to_world.inverse();
+    let elapsed = state.time;
+
+    state.particles.retain_mut(|particle| {
+        particle.age += dt;
+        if particle.age >= particle.lifetime {
+            events.push(SpawnEvent {
+                emitter: index,
+                trigger: SubEmitterTrigger::Death,
+                position: particle.position,
+                velocity: particle.velocity,
+            });
+            return false;
+        }
+
+        for force in &desc.forces {
+            match *force {
+                ParticleForce::Gravity(acceleration) => {
+                    let acceleration = if local {
+                        to_local.transform_vector3(acceleration)
+                    } else {
+                        acceleration
+                    };
+                    particle.velocity += acceleration * dt;
+                }
+                ParticleForce::Drag(drag) => {
+                    particle.velocity *= (1.0 - drag * dt).max(0.0);
+                }
+                ParticleForce::Noise {
+                    strength,
+                    frequency,
+                    scroll,
+                } => {
+                    let sample = particle.position * frequency + Vec3::splat(elapsed * scroll);
+                    particle.velocity += vector_noise(sample) * strength * dt;
+                }
+                ParticleForce::Attractor {
+                    position,
+                    strength,
+                    radius,
+                } => {
+                    let target = if local {
+                        position
+                    } else {
+                        to_world.transform_point3(position)
+                    };
+                    let offset = target - particle.position;
+                    let distance = offset.length();
+                    if distance > f32::EPSILON && (radius <= 0.0 || distance < radius) {
+                        particle.velocity += offset / distance * strength * dt;
+                    }
+                }
+            }
+        }
+
+        let t = particle.normalized_age();
+        let speed_scale = desc
+            .speed_over_lifetime
+            .as_ref()
+            .map_or(1.0, |c| c.sample(t));
+        particle.position += particle.velocity * speed_scale * dt;
+        particle.size = particle.start_size
+            * desc
+                .size_over_lifetime
+                .as_ref()
+                .map_or(1.0, |c| c.sample(t));
+        particle.color = desc
+            .color_over_lifetime
+            .as_ref()
+            .map_or(particle.start_color, |g| {
+                multiply(particle.start_color, g.sample(t))
+            });
+
+        if let Some(collision) = &desc.collision {
+            let radius = particle.size * collision.radius_scale;
+            let mut world_position = if local {
+                to_world.transform_point3(particle.position)
+            } else {
+                particle.position
+            };
+            let mut world_velocity = if local {
+                to_world.transform_vector3(particle.velocity)
+            } else {
+                particle.velocity
+            };
+
+            let mut hit = false;
+            for collider in context.colliders {
+                let (distance, normal) = collider.distance(world_position);
+                if distance >= radius {
+                    continue;
+                }
+                hit = true;
+                world_position += normal * (radius - distance);
+                let normal_speed = world_velocity.dot(normal);
+                if normal_speed < 0.0 {
+                    let tangential = world_velocity - normal * normal_speed;
+                    world_velocity = tangential * (1.0 - collision.friction)
+                        - normal * normal_speed * collision.bounce;
+                }
+            }
+
+            if hit {
+                events.push(SpawnEvent {
+                    emitter: index,
+                    trigger: SubEmitterTrigger::Collision,
+                    position: if local {
+                        to_local.transform_point3(world_position)
+                    } else {
+                        world_position
+                    },
+                    velocity: particle.velocity,
+                });
+                if collision.kill {
+                    events.push(SpawnEvent {
+                        emitter: index,
+                        trigger: SubEmitterTrigger::Death,
+                        position: particle.position,
+                        velocity: particle.velocity,
+                    });
+                    return false;
+                }
+                if local {
+                    particle.position = to_local.transform_point3(world_position);
+                    particle.velocity = to_local.transform_vector3(world_velocity);
+                } else {
+                    particle.position = world_position;
+                    particle.velocity = world_velocity;
+                }
+            }
+        }
+
+        true
+    });
+}
+
+// ── Systems ─────────────────────────────────────────────────────────────
+
+/// Simulate every [`ParticleEffectInstance`] with the frame time
+pub fn particle_effect_system(world: &mut World) {
+    let dt = world
+        .get_resource::<luminara_core::Time>()
+        .map(|t| t.delta_seconds())
+        .unwrap_or(0.0);
+    if dt <= 0.0 {
+        return;
+    }
+
+    let entities: Vec<Entity> = Query::<(Entity, &ParticleEffectInstance)>::new(&*world)
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    let colliders = world
+        .get_resource::<ParticleColliders>()
+        .map(|c| c.0.clone())
+        .unwrap_or_default();
+    let Some(assets) = world.get_resource::<AssetServer>() else {
+        return;
+    };
+    let meshes = |handle: &Handle<Mesh>| assets.get(handle);
+
+    for entity in entities {
+        let transform = global_matrix(world, entity);
+        let Some(instance) = world.get_component_mut::<ParticleEffectInstance>(entity) else {
+            continue;
+        };
+        let Some(effect) = assets.get(&instance.effect) else {
+            continue;
+        };
+        let context = ParticleSimulationContext {
+            transform,
+            colliders: &colliders,
+            meshes: &meshes,
+        };
+        instance.simulate(&effect, &context, dt);
+    }
+}
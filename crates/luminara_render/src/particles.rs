@@ -1,6 +1,8 @@
+use crate::particle_effect::{particle_effect_system, ParticleColliders, ParticleEffectLoader};
 use crate::GpuContext;
-use luminara_core::system::FunctionMarker;
-use luminara_core::{App, AppInterface, Component, CoreStage, Plugin, Query, Res, ResMut};
+use luminara_asset::AssetServer;
+use luminara_core::system::{ExclusiveMarker, FunctionMarker};
+use luminara_core::{App, AppInterface, Component, CoreStage, Plugin, Query, Res, ResMut, World};
 use luminara_math::{Color, Mat4, Vec3};
 use wgpu::util::DeviceExt;
 
@@ -105,6 +107,16 @@ impl Plugin for ParticlePlugin {
             ResMut<'static, ParticleSystem>,
             Res<'static, luminara_core::Time>,
         )>(CoreStage::Update, particle_update_system);
+
+        app.insert_resource(ParticleColliders::default());
+        app.add_system::<ExclusiveMarker>(CoreStage::Startup, register_particle_effect_loader);
+        app.add_system::<ExclusiveMarker>(CoreStage::Update, particle_effect_system);
+    }
+}
+
+fn register_particle_effect_loader(world: &mut World) {
+    if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
+        asset_server.register_loader(ParticleEffectLoader);
     }
 }
 
@@ -167,8 +179,8 @@ pub fn particle_update_system(
 // For Phase 1, we can implement a basic `render_particles` system
 // that uploads instance data to a buffer.
 #[repr(C)]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-struct ParticleInstance {
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ParticleInstance {
     model_matrix_0: [f32; 4],
     model_matrix_1: [f32; 4],
     model_matrix_2: [f32; 4],
@@ -176,6 +188,37 @@ struct ParticleInstance {
     color: [f32; 4],
 }
 
+impl ParticleInstance {
+    /// Instance for a particle of `size` at `position`
+    pub fn new(position: Vec3, size: f32, color: Color) -> Self {
+        let transform = Mat4::from_scale_rotation_translation(
+            Vec3::splat(size),
+            luminara_math::Quat::IDENTITY,
+            position,
+        );
+        let cols = transform.to_cols_array_2d();
+        Self {
+            model_matrix_0: cols[0],
+            model_matrix_1: cols[1],
+            model_matrix_2: cols[2],
+            model_matrix_3: cols[3],
+            color: color.into(),
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        Vec3::new(
+            self.model_matrix_3[0],
+            self.model_matrix_3[1],
+            self.model_matrix_3[2],
+        )
+    }
+
+    pub fn color(&self) -> [f32; 4] {
+        self.color
+    }
+}
+
 pub fn particle_render_prepare_system(
     mut particle_system: ResMut<ParticleSystem>,
     gpu: Res<GpuContext>,
@@ -188,24 +231,9 @@ pub fn particle_render_prepare_system(
         .particles
         .iter()
         .map(|p| {
-            let transform = Mat4::from_scale_rotation_translation(
-                Vec3::splat(p.size),
-                luminara_math::Quat::IDENTITY,
-                p.position,
-            );
-            let cols = transform.to_cols_array_2d();
-            ParticleInstance {
-                model_matrix_0: cols[0],
-                model_matrix_1: cols[1],
-                model_matrix_2: cols[2],
-                model_matrix_3: cols[3],
-                color: [
-                    p.color.r,
-                    p.color.g,
-                    p.color.b,
-                    p.color.a * (p.lifetime / p.max_lifetime),
-                ],
-            }
+            let mut color = p.color;
+            color.a *= p.lifetime / p.max_lifetime;
+            ParticleInstance::new(p.position, p.size, color)
         })
         .collect();
 
@@ -1,5 +1,6 @@
 use crate::shader::Shader;
-use std::collections::HashMap;
+use crate::shader_preprocessor::{PipelineDependencies, ShaderDefs, ShaderDependency};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use wgpu;
 
@@ -20,6 +21,9 @@ pub struct RenderPipelineDescriptor {
 
 pub struct PipelineCache {
     pipelines: HashMap<String, CachedPipeline>,
+    dependencies: PipelineDependencies,
+    /// Labels whose shader failed to compose or validate
+    failed: HashSet<String>,
 }
 
 impl Default for PipelineCache {
@@ -32,6 +36,17 @@ impl PipelineCache {
     pub fn new() -> Self {
         Self {
             pipelines: HashMap::new(),
+            dependencies: PipelineDependencies::default(),
+            failed: HashSet::new(),
+        }
+    }
+
+    /// Label of one permutation of a pipeline, e.g. `pbr[NORMAL_MAP,SKINNED]`
+    pub fn permutation_label(base: &str, defs: &ShaderDefs) -> String {
+        if defs.is_empty() {
+            base.to_string()
+        } else {
+            format!("{}[{}]", base, defs.key())
         }
     }
 
@@ -54,6 +69,46 @@ impl PipelineCache {
         );
     }
 
+    /// Insert a pipeline built from composed shader code. It is dropped by
+    /// [`Self::invalidate`] when one of `dependencies` changes.
+    pub fn insert_pipeline_with_dependencies(
+        &mut self,
+        label: String,
+        pipeline: wgpu::RenderPipeline,
+        layouts: Vec<wgpu::BindGroupLayout>,
+        dependencies: impl IntoIterator<Item = ShaderDependency>,
+    ) {
+        self.dependencies.insert(&label, dependencies);
+        self.insert_pipeline(label, pipeline, layouts);
+    }
+
+    /// Remember that `label` could not be built, so it is not retried every
+    /// frame. A change to one of `dependencies` clears the failure.
+    pub fn mark_failed(
+        &mut self,
+        label: String,
+        dependencies: impl IntoIterator<Item = ShaderDependency>,
+    ) {
+        self.dependencies.insert(&label, dependencies);
+        self.failed.insert(label);
+    }
+
+    pub fn is_failed(&self, label: &str) -> bool {
+        self.failed.contains(label)
+    }
+
+    /// Drop every pipeline built from `dependency` so it is rebuilt on next
+    /// use. Returns the dropped labels.
+    pub fn invalidate(&mut self, dependency: &ShaderDependency) -> Vec<String> {
+        let labels = self.dependencies.affected(dependency);
+        for label in &labels {
+            self.pipelines.remove(label);
+            self.failed.remove(label);
+            self.dependencies.remove(label);
+        }
+        labels
+    }
+
     // Kept for backward compatibility if needed, but updated to use the new storage
     pub fn get_or_create(
         &mut self,
@@ -1,12 +1,21 @@
 use crate::camera::Camera;
+use crate::color_lut::ColorLutLoader;
 use crate::command::CommandBuffer;
+use crate::environment_map::EnvironmentMapLoader;
 use crate::forward_plus::{update_lights_system, ForwardPlusRenderer};
 use crate::gpu::GpuContext;
+use crate::material::{MaterialPlugin, MaterialRegistry, MaterialTextures, UnlitMaterial};
 use crate::mesh::Mesh;
 use crate::pipeline::PipelineCache;
 use crate::render_graph::RenderGraph;
+use crate::render_world::{
+    extract_render_world_system, resize_surface_system, RenderApp, RenderPipelining,
+};
+use crate::shader::ShaderLoader;
+use crate::terrain::TerrainMaterial;
 use crate::texture::TextureLoader;
-use crate::CameraUniformBuffer;
+use crate::visibility::{visibility_system, VisibilityCulling};
+use crate::{CameraUniformBuffer, PbrMaterial};
 use luminara_asset::{AssetServer, Handle};
 use luminara_core::shared_types::{
     App, AppInterface, CoreStage, Plugin, Query, Res, ResMut, World,
@@ -26,27 +35,41 @@ impl Plugin for RenderPlugin {
     fn build(&self, app: &mut App) {
         // Initialize resources
         app.insert_resource(PipelineCache::new());
-        app.insert_resource(RenderGraph::new());
+        app.insert_resource(RenderGraph::forward_3d());
+        app.insert_resource(crate::RenderTargets::default());
         app.insert_resource(CommandBuffer::default());
         app.insert_resource(ForwardPlusRenderer::new());
+        app.insert_resource(crate::LightClusters::default());
         app.insert_resource(crate::ShadowMapResources::default());
         app.insert_resource(crate::ShadowCascades::default());
+        app.insert_resource(crate::ShadowAtlas::default());
+        app.insert_resource(crate::ShadowAtlasResources::default());
         app.insert_resource(crate::PostProcessResources::default());
+        app.insert_resource(crate::EnvironmentResources::default());
         app.insert_resource(crate::overlay::OverlayRenderer::new());
+        app.insert_resource(crate::text::Text3dRenderer::default());
         app.insert_resource(crate::FluidSolverResource::new());
         app.insert_resource(crate::DebugRenderingResource::new());
+        app.insert_resource(MaterialTextures::default());
+        app.insert_resource(crate::ShaderComposer::new());
+
+        // Built-in materials. `PbrMaterial` is also drawn when used directly
+        // as a component, as it was before materials became assets.
+        let mut materials = MaterialRegistry::default();
+        materials.register_component::<PbrMaterial>();
+        app.insert_resource(materials);
+        app.add_plugins(MaterialPlugin::<PbrMaterial>::default());
+        app.add_plugins(MaterialPlugin::<UnlitMaterial>::default());
+        app.add_plugins(MaterialPlugin::<TerrainMaterial>::default());
+        app.add_plugins(crate::GltfPlugin);
+        app.add_plugins(crate::SkinningPlugin);
+        app.add_plugins(crate::TextPlugin);
+        app.add_plugins(crate::SpritePlugin);
+        app.add_plugins(crate::TerrainPlugin);
 
         // Register startup system to initialize GPU context once Window is available
         app.add_system::<ExclusiveMarker>(CoreStage::Startup, setup_gpu_context);
 
-        // Register window resize system
-        app.add_system::<(
-            FunctionMarker,
-            ResMut<'static, GpuContext>,
-            luminara_core::event::EventReader<'static, luminara_window::WindowEvent>,
-            Res<'static, luminara_window::Window>,
-        )>(CoreStage::PreUpdate, crate::window_resize_system);
-
         // Register camera resize system to update aspect ratio on window resize
         app.add_system::<(
             FunctionMarker,
@@ -62,14 +85,6 @@ impl Plugin for RenderPlugin {
             Res<'static, luminara_window::Window>,
         )>(CoreStage::PreRender, crate::camera_projection_system);
 
-        // Register mesh upload system
-        app.add_system::<(
-            FunctionMarker,
-            ResMut<'static, GpuContext>,
-            Res<'static, AssetServer>,
-            Query<'static, &Handle<Mesh>>,
-        )>(CoreStage::PreRender, crate::mesh_upload_system);
-
         // Register fluid systems
         app.add_system::<(
             FunctionMarker,
@@ -97,32 +112,6 @@ impl Plugin for RenderPlugin {
             ResMut<'static, crate::FluidSolverResource>,
         )>(CoreStage::PostUpdate, crate::cleanup_fluid_solvers_system);
 
-        // Register Forward+ light update system
-        app.add_system::<(
-            FunctionMarker,
-            ResMut<'static, ForwardPlusRenderer>,
-            Res<'static, GpuContext>,
-            Query<'static, (&crate::DirectionalLight, &Transform)>,
-            Query<'static, (&crate::PointLight, &Transform)>,
-        )>(CoreStage::PreRender, update_lights_system);
-
-        // Register shadow cascade update system
-        app.add_system::<(
-            FunctionMarker,
-            Res<'static, GpuContext>,
-            Res<'static, crate::ShadowCascades>,
-            ResMut<'static, crate::ShadowMapResources>,
-            Query<'static, (&Camera, &Transform)>,
-            Query<'static, (&crate::DirectionalLight, &Transform)>,
-        )>(CoreStage::PreRender, crate::update_shadow_cascades_system);
-
-        // Register post-process initialization system
-        app.add_system::<(
-            FunctionMarker,
-            ResMut<'static, crate::PostProcessResources>,
-            Res<'static, GpuContext>,
-        )>(CoreStage::PreRender, crate::init_post_process_system);
-
         // Register LOD update system
         app.add_system::<(
             FunctionMarker,
@@ -130,23 +119,76 @@ impl Plugin for RenderPlugin {
             Query<'static, (&Camera, &Transform)>,
         )>(CoreStage::PreRender, crate::lod_update_system);
 
-        // Register render_system to Render stage
-        app.add_system::<(
-            FunctionMarker,
-            ResMut<'static, GpuContext>,
-            ResMut<'static, PipelineCache>,
-            ResMut<'static, CameraUniformBuffer>,
-            Res<'static, AssetServer>,
-            Query<'static, (&Camera, &Transform)>,
-            Query<'static, (&Handle<Mesh>, &Transform, &crate::PbrMaterial)>,
-            Res<'static, luminara_window::Window>,
-            Res<'static, crate::PostProcessResources>,
-        )>(CoreStage::Render, crate::render_system);
+        // Frustum, LOD and occlusion culling before extraction
+        if app.world.get_resource::<VisibilityCulling>().is_none() {
+            app.insert_resource(VisibilityCulling::default());
+        }
+        app.add_system::<ExclusiveMarker>(CoreStage::PreRender, visibility_system);
+
+        // The main world only extracts; render systems run on render worlds
+        if app.world.get_resource::<RenderPipelining>().is_none() {
+            app.insert_resource(RenderPipelining::default());
+        }
+        app.insert_resource(render_app());
+        app.add_system::<ExclusiveMarker>(CoreStage::Render, extract_render_world_system);
     }
 }
 
-/// Startup system to initialize GpuContext and basic rendering resources
+/// The render app with the built-in render systems
+fn render_app() -> RenderApp {
+    let mut render_app = RenderApp::new();
+    render_app.add_system::<ExclusiveMarker>(CoreStage::PreRender, resize_surface_system);
+
+    render_app.add_system::<(
+        FunctionMarker,
+        ResMut<'static, GpuContext>,
+        Res<'static, AssetServer>,
+        Query<'static, &Handle<Mesh>>,
+    )>(CoreStage::PreRender, crate::mesh_upload_system);
+
+    render_app.add_system::<(
+        FunctionMarker,
+        ResMut<'static, ForwardPlusRenderer>,
+        Res<'static, GpuContext>,
+        ResMut<'static, crate::LightClusters>,
+        Query<'static, (&Camera, &Transform)>,
+        Query<'static, (&crate::DirectionalLight, &Transform)>,
+        Query<'static, (&crate::PointLight, &Transform)>,
+        Query<'static, (&crate::SpotLight, &Transform)>,
+    )>(CoreStage::PreRender, update_lights_system);
+
+    render_app.add_system::<(
+        FunctionMarker,
+        Res<'static, GpuContext>,
+        Res<'static, crate::ShadowCascades>,
+        ResMut<'static, crate::ShadowMapResources>,
+        Query<'static, (&Camera, &Transform)>,
+        Query<'static, (&crate::DirectionalLight, &Transform)>,
+    )>(CoreStage::PreRender, crate::update_shadow_cascades_system);
+
+    render_app
+        .add_system::<ExclusiveMarker>(CoreStage::PreRender, crate::update_shadow_atlas_system);
+
+    render_app.add_system::<(
+        FunctionMarker,
+        ResMut<'static, crate::PostProcessResources>,
+        Res<'static, GpuContext>,
+    )>(CoreStage::PreRender, crate::init_post_process_system);
+
+    render_app.add_system::<ExclusiveMarker>(CoreStage::Render, crate::render_system);
+    render_app
+}
+
+/// Startup system to initialize GpuContext and basic rendering resources.
+/// A context inserted before startup, such as a headless one, is used as is.
 pub fn setup_gpu_context(world: &mut World) {
+    // Borrowed in place rather than removed, since it may already be shared
+    // with the render world
+    if world.get_resource::<GpuContext>().is_some() {
+        world.resource_scope::<GpuContext, _, _>(|world, gpu| init_gpu_resources(world, gpu));
+        return;
+    }
+
     let gpu = {
         let window = world.get_resource::<Window>().expect("Window not found");
         match GpuContext::new(&window) {
@@ -157,10 +199,20 @@ pub fn setup_gpu_context(world: &mut World) {
             }
         }
     };
+    init_gpu_resources(world, &gpu);
+    world.insert_resource(gpu);
+}
 
-    // Register texture loader
+/// Register the render asset loaders and create the resources built from the
+/// GPU context
+fn init_gpu_resources(world: &mut World, gpu: &GpuContext) {
+    // Register texture and shader loaders
     if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
         asset_server.register_loader(TextureLoader);
+        asset_server.register_loader(ShaderLoader);
+        asset_server.register_loader(ColorLutLoader);
+        asset_server.register_loader(EnvironmentMapLoader::default());
+        crate::register_builtin_shaders(&asset_server);
     }
 
     // Create camera uniform buffer
@@ -211,10 +263,8 @@ pub fn setup_gpu_context(world: &mut World) {
 
     // Initialize debug rendering
     if let Some(mut debug_rendering) = world.get_resource_mut::<crate::DebugRenderingResource>() {
-        debug_rendering.initialize(&gpu);
+        debug_rendering.initialize(gpu);
     } else {
         log::error!("DebugRenderingResource not found during GPU setup");
     }
-
-    world.insert_resource(gpu);
 }
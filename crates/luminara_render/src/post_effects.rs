@@ -0,0 +1,287 @@
+//! Per-camera post-processing settings.
+//!
+//! A [`PostProcessSettings`] component on a camera lists the effects
+//! [`PostProcessNode`](crate::PostProcessNode) applies to its HDR image, in
+//! order, before the result is written to the camera's target. Cameras
+//! without one are tone mapped with ACES, as [`PostProcessSettings::default`].
+//!
+//! The tone curves and exposure math below are the CPU twins of the shader
+//! code, so they can be tested and used to preview a grade.
+
+use crate::color_lut::ColorLut;
+use luminara_asset::Handle;
+use luminara_core::shared_types::Component;
+use luminara_math::glam::Mat3;
+use luminara_math::Vec3;
+use luminara_reflect_derive::Reflect;
+use serde::{Deserialize, Serialize};
+
+/// Scene luminance auto exposure maps to mid gray
+pub const MID_GRAY: f32 = 0.18;
+
+/// Ordered post-processing stack of a camera
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub struct PostProcessSettings {
+    pub effects: Vec<PostEffect>,
+}
+
+impl PostProcessSettings {
+    /// A stack without any effect. Values above 1 are clipped on output.
+    pub fn empty() -> Self {
+        Self {
+            effects: Vec::new(),
+        }
+    }
+
+    /// Append `effect` to the end of the stack
+    pub fn with(mut self, effect: PostEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    /// Whether any effect reads the camera's depth buffer
+    pub fn uses_depth(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| matches!(effect, PostEffect::Ssao { .. }))
+    }
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self::empty().with(PostEffect::Tonemap(Tonemapping::Aces))
+    }
+}
+
+impl Component for PostProcessSettings {
+    fn type_name() -> &'static str {
+        "PostProcessSettings"
+    }
+}
+
+/// One step of a [`PostProcessSettings`] stack. Effects before the
+/// [`PostEffect::Tonemap`] see linear HDR color, effects after it see
+/// display-referred color in `0..=1`.
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub enum PostEffect {
+    /// Glow around pixels brighter than `threshold`
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+    },
+    Exposure(Exposure),
+    Tonemap(Tonemapping),
+    /// Grade through a 3D LUT applied to sRGB-encoded color, as `.cube`
+    /// files expect. `strength` blends between the input and the graded color.
+    ColorGrading {
+        lut: Handle<ColorLut>,
+        strength: f32,
+    },
+    /// Darkens the image further than `radius` from its center, reaching
+    /// full `intensity` `smoothness` later. Distances are 1 at the corners.
+    Vignette {
+        intensity: f32,
+        radius: f32,
+        smoothness: f32,
+    },
+    /// Splits red and blue apart towards the edges, by up to `intensity`
+    /// of the view size
+    ChromaticAberration {
+        intensity: f32,
+    },
+    Antialias(Antialiasing),
+    /// Screen space ambient occlusion from the depth buffer, sampling
+    /// `samples` points within `radius` world units
+    Ssao {
+        radius: f32,
+        intensity: f32,
+        samples: u32,
+    },
+}
+
+impl PostEffect {
+    pub fn bloom() -> Self {
+        Self::Bloom {
+            threshold: 1.0,
+            intensity: 0.3,
+        }
+    }
+
+    pub fn vignette() -> Self {
+        Self::Vignette {
+            intensity: 0.4,
+            radius: 0.5,
+            smoothness: 0.5,
+        }
+    }
+
+    pub fn chromatic_aberration() -> Self {
+        Self::ChromaticAberration { intensity: 0.005 }
+    }
+
+    pub fn ssao() -> Self {
+        Self::Ssao {
+            radius: 0.5,
+            intensity: 1.0,
+            samples: 16,
+        }
+    }
+
+    pub fn color_grading(lut: Handle<ColorLut>) -> Self {
+        Self::ColorGrading { lut, strength: 1.0 }
+    }
+}
+
+/// How bright the scene is made before tone mapping
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+pub enum Exposure {
+    /// Scale by `2^compensation`
+    Manual { compensation: f32 },
+    /// Follow the average scene luminance
+    Auto(AutoExposure),
+}
+
+/// Eye adaptation towards the exposure that maps the scene's average
+/// luminance to [`MID_GRAY`]. Exposure values are in stops relative to that
+/// average, so `0` is a scene averaging mid gray.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct AutoExposure {
+    /// Darkest scene exposure adapted to
+    pub min_ev: f32,
+    /// Brightest scene exposure adapted to
+    pub max_ev: f32,
+    /// Stops added after adaptation
+    pub compensation: f32,
+    /// Adaptation rate per second when the scene gets brighter
+    pub speed_up: f32,
+    /// Adaptation rate per second when the scene gets darker
+    pub speed_down: f32,
+}
+
+impl Default for AutoExposure {
+    fn default() -> Self {
+        Self {
+            min_ev: -8.0,
+            max_ev: 8.0,
+            compensation: 0.0,
+            speed_up: 3.0,
+            speed_down: 1.0,
+        }
+    }
+}
+
+impl AutoExposure {
+    /// Exposure value the eye adapts to for a scene of `average_luminance`
+    pub fn target_ev(&self, average_luminance: f32) -> f32 {
+        ev_from_luminance(average_luminance).clamp(self.min_ev, self.max_ev)
+    }
+
+    /// Move `current` towards `target` over `dt` seconds
+    pub fn adapt(&self, current: f32, target: f32, dt: f32) -> f32 {
+        let speed = if target > current {
+            self.speed_up
+        } else {
+            self.speed_down
+        };
+        current + (target - current) * (1.0 - (-dt.max(0.0) * speed).exp())
+    }
+
+    /// Factor applied to scene color once adapted to `ev`
+    pub fn exposure(&self, ev: f32) -> f32 {
+        exposure_from_ev(ev - self.compensation)
+    }
+}
+
+/// Scene exposure value of an average luminance, 0 at [`MID_GRAY`]
+pub fn ev_from_luminance(luminance: f32) -> f32 {
+    (luminance.max(1e-4) / MID_GRAY).log2()
+}
+
+/// Factor that brings a scene of exposure value `ev` to mid gray
+pub fn exposure_from_ev(ev: f32) -> f32 {
+    (-ev).exp2()
+}
+
+/// Rec. 709 luminance of linear color
+pub fn luminance(color: Vec3) -> f32 {
+    color.dot(Vec3::new(0.2126, 0.7152, 0.0722))
+}
+
+/// Tone curves mapping HDR color into `0..=1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+pub enum Tonemapping {
+    /// Narkowicz's fit of the ACES filmic curve
+    #[default]
+    Aces,
+    /// Sobotka's AgX, desaturating bright colors instead of skewing hues
+    AgX,
+    Reinhard,
+}
+
+impl Tonemapping {
+    /// Tone map linear HDR `color` to linear display color
+    pub fn apply(&self, color: Vec3) -> Vec3 {
+        let color = color.max(Vec3::ZERO);
+        match self {
+            Tonemapping::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                ((color * (a * color + b)) / (color * (c * color + d) + e))
+                    .clamp(Vec3::ZERO, Vec3::ONE)
+            }
+            Tonemapping::AgX => agx(color),
+            Tonemapping::Reinhard => color / (color + 1.0),
+        }
+    }
+}
+
+/// Minimal AgX with the default look, after Wrensch's polynomial fit
+fn agx(color: Vec3) -> Vec3 {
+    const MIN_EV: f32 = -12.47393;
+    const MAX_EV: f32 = 4.026069;
+    let inset = Mat3::from_cols_array(&[
+        0.8424791,
+        0.04232824,
+        0.04237565,
+        0.0784336,
+        0.8784686,
+        0.0784336,
+        0.07922375,
+        0.07916613,
+        0.879143,
+    ]);
+    let outset = Mat3::from_cols_array(&[
+        1.196879,
+        -0.05289685,
+        -0.05297164,
+        -0.09802088,
+        1.151903,
+        -0.09804345,
+        -0.09902974,
+        -0.09896118,
+        1.151074,
+    ]);
+
+    let log = (inset * color)
+        .max(Vec3::splat(1e-10))
+        .to_array()
+        .map(f32::log2);
+    let x = ((Vec3::from(log) - MIN_EV) / (MAX_EV - MIN_EV)).clamp(Vec3::ZERO, Vec3::ONE);
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    let curve =
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232;
+    // The curve's output is display encoded with a 2.2 gamma
+    let display = (outset * curve).clamp(Vec3::ZERO, Vec3::ONE);
+    Vec3::from(display.to_array().map(|c| c.powf(2.2)))
+}
+
+/// Edge antialiasing applied to the tone mapped image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum Antialiasing {
+    /// Single pass, blurs along luma edges
+    Fxaa,
+    /// Edge detection, blend weights from edge shapes, then neighborhood
+    /// blending, keeping texture detail sharper than FXAA
+    Smaa,
+}
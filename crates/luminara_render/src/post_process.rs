@@ -1,57 +1,368 @@
-// Post-processing implementation with tone mapping and gamma correction
-use crate::{GpuContext, Shader};
+// Post-processing: each camera's effect stack, then tone mapped output
+use crate::color_lut::ColorLut;
+use crate::post_effects::{Antialiasing, Exposure, PostEffect, PostProcessSettings, Tonemapping};
+use crate::render_graph::{slots, PassSlots, RenderContext, RenderNode};
+use crate::render_target::{
+    collect_camera_views, CameraView, RenderTargets, TargetKey, OFFSCREEN_FORMAT,
+};
+use crate::render_world::components_by_main_entity;
+use crate::{GpuContext, RenderError, Shader};
+use luminara_asset::{AssetId, AssetServer};
 use luminara_core::shared_types::{Res, ResMut, Resource};
+use luminara_core::{Entity, Time};
+use luminara_math::{Mat4, Vec3};
+use std::collections::{HashMap, HashSet};
+use wgpu::util::DeviceExt;
+
+/// Format of the textures effects render into
+const INTERMEDIATE_FORMAT: wgpu::TextureFormat = slots::HDR_FORMAT;
+
+/// Side of the texture the scene luminance is averaged from
+const LUMINANCE_SIZE: u32 = 64;
+
+/// Fragment entry points of `post_process.wgsl` rendering into
+/// [`INTERMEDIATE_FORMAT`]
+const EFFECT_ENTRIES: &[&str] = &[
+    "fs_bloom_prefilter",
+    "fs_blur_h",
+    "fs_blur_v",
+    "fs_bloom_composite",
+    "fs_luminance",
+    "fs_downsample",
+    "fs_adapt_exposure",
+    "fs_exposure",
+    "fs_tonemap_aces",
+    "fs_tonemap_agx",
+    "fs_tonemap_reinhard",
+    "fs_color_grading",
+    "fs_vignette",
+    "fs_chromatic_aberration",
+    "fs_fxaa",
+    "fs_smaa_edges",
+    "fs_smaa_weights",
+    "fs_smaa_blend",
+];
+
+/// Effect entry points built the first time a stack uses [`PostEffect::Ssao`]
+const SSAO_ENTRIES: &[&str] = &["fs_ssao", "fs_ssao_blur", "fs_ssao_apply"];
+
+/// `Params` of `post_process.wgsl`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostParams {
+    out_rect: [f32; 4],
+    in_rect: [f32; 4],
+    aux_rect: [f32; 4],
+    settings: [f32; 4],
+    extra: [f32; 4],
+    lut_min: [f32; 4],
+    lut_max: [f32; 4],
+    view_proj: [[f32; 4]; 4],
+    inv_view_proj: [[f32; 4]; 4],
+}
 
 /// Post-processing resources
+#[derive(Default)]
 pub struct PostProcessResources {
+    /// Output into the surface format
     pub pipeline: Option<wgpu::RenderPipeline>,
+    /// Output into [`OFFSCREEN_FORMAT`] for cameras with offscreen targets
+    pub offscreen_pipeline: Option<wgpu::RenderPipeline>,
     pub bind_group_layout: Option<wgpu::BindGroupLayout>,
     pub sampler: Option<wgpu::Sampler>,
-    // Bloom resources
-    pub bloom_extract_pipeline: Option<wgpu::RenderPipeline>,
-    pub bloom_blur_pipeline: Option<wgpu::RenderPipeline>,
-    pub bloom_combine_pipeline: Option<wgpu::RenderPipeline>,
-    pub bloom_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    depth_sampler: Option<wgpu::Sampler>,
+    shader: Option<Shader>,
+    pipeline_layout: Option<wgpu::PipelineLayout>,
+    effect_pipelines: HashMap<&'static str, wgpu::RenderPipeline>,
+    fallback: Option<Fallback>,
+    /// Effect textures by target size
+    intermediates: HashMap<(u32, u32), Intermediates>,
+    /// Halving chain from [`LUMINANCE_SIZE`] down to one texel
+    luminance: Vec<TextureTarget>,
+    luts: HashMap<AssetId, GpuLut>,
+    /// Adapted exposure of each auto exposed camera
+    exposure: HashMap<Entity, AdaptedExposure>,
 }
 
 impl Resource for PostProcessResources {}
 
-impl Default for PostProcessResources {
-    fn default() -> Self {
+struct TextureTarget {
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl TextureTarget {
+    fn new(device: &wgpu::Device, label: &str, size: (u32, u32)) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.0.max(1),
+                height: size.1.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: INTERMEDIATE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            _texture: texture,
+            view,
+        }
+    }
+
+    fn pair(device: &wgpu::Device, label: &str, size: (u32, u32)) -> [Self; 2] {
+        [
+            Self::new(device, label, size),
+            Self::new(device, label, size),
+        ]
+    }
+}
+
+/// Bound where a pass has no texture of its own
+struct Fallback {
+    color: TextureTarget,
+    lut: GpuLut,
+}
+
+struct Intermediates {
+    /// Ping-pong between effects
+    color: [TextureTarget; 2],
+    /// Quarter size, for bloom
+    bloom: Option<[TextureTarget; 2]>,
+    /// SSAO and SMAA working textures
+    scratch: Option<[TextureTarget; 2]>,
+}
+
+struct GpuLut {
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    size: u32,
+    domain_min: Vec3,
+    domain_max: Vec3,
+}
+
+impl GpuLut {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue, lut: &ColorLut) -> Self {
+        let extent = wgpu::Extent3d {
+            width: lut.size,
+            height: lut.size,
+            depth_or_array_layers: lut.size,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Color Grading LUT"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let texels: Vec<u16> = lut
+            .data
+            .iter()
+            .flat_map(|color| [color.x, color.y, color.z, 1.0])
+            .map(|value| half::f16::from_f32(value).to_bits())
+            .collect();
+        queue.write_texture(
+            texture.as_image_copy(),
+            bytemuck::cast_slice(&texels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(lut.size * 8),
+                rows_per_image: Some(lut.size),
+            },
+            extent,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         Self {
-            pipeline: None,
-            bind_group_layout: None,
-            sampler: None,
-            bloom_extract_pipeline: None,
-            bloom_blur_pipeline: None,
-            bloom_combine_pipeline: None,
-            bloom_bind_group_layout: None,
+            _texture: texture,
+            view,
+            size: lut.size,
+            domain_min: lut.domain_min,
+            domain_max: lut.domain_max,
         }
     }
 }
 
+/// Exposure value adapted over the previous frames, on the GPU
+struct AdaptedExposure {
+    states: [TextureTarget; 2],
+    /// Index of the state written last
+    current: usize,
+    /// Nothing adapted yet; jump straight to the scene's exposure
+    reset: bool,
+}
+
+/// What the cameras of one target need this frame
+#[derive(Default)]
+struct TargetNeeds {
+    bloom: bool,
+    scratch: bool,
+}
+
+/// The effect stack of one camera, or of the parts of a target no camera
+/// covers
+struct Job<'a> {
+    /// Pixel rectangle `(x, y, width, height)` inside the target
+    rect: (u32, u32, u32, u32),
+    view: Option<&'a CameraView>,
+    settings: &'a PostProcessSettings,
+}
+
+/// A target being post-processed and where its result goes
+struct TargetOutput<'a> {
+    size: (u32, u32),
+    hdr: &'a wgpu::TextureView,
+    depth: &'a wgpu::TextureView,
+    output: &'a wgpu::TextureView,
+    /// Output in [`OFFSCREEN_FORMAT`] rather than the surface format
+    offscreen: bool,
+    clear: wgpu::Color,
+}
+
+/// One fullscreen draw of an effect
+struct Pass<'a> {
+    pipeline: &'a wgpu::RenderPipeline,
+    input: &'a wgpu::TextureView,
+    /// Region read, in UV
+    in_rect: [f32; 4],
+    aux: Option<(&'a wgpu::TextureView, [f32; 4])>,
+    lut: Option<&'a GpuLut>,
+    output: &'a wgpu::TextureView,
+    /// Pixels written
+    out_rect: (u32, u32, u32, u32),
+    load: wgpu::LoadOp<wgpu::Color>,
+    settings: [f32; 4],
+    extra: [f32; 4],
+}
+
+impl<'a> Pass<'a> {
+    fn new(
+        pipeline: &'a wgpu::RenderPipeline,
+        input: &'a wgpu::TextureView,
+        in_rect: [f32; 4],
+        output: &'a wgpu::TextureView,
+        out_rect: (u32, u32, u32, u32),
+    ) -> Self {
+        Self {
+            pipeline,
+            input,
+            in_rect,
+            aux: None,
+            lut: None,
+            output,
+            out_rect,
+            load: wgpu::LoadOp::Load,
+            settings: [0.0; 4],
+            extra: [0.0; 4],
+        }
+    }
+
+    fn aux(mut self, view: &'a wgpu::TextureView, rect: [f32; 4]) -> Self {
+        self.aux = Some((view, rect));
+        self
+    }
+
+    fn settings(mut self, settings: [f32; 4]) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    fn extra(mut self, extra: [f32; 4]) -> Self {
+        self.extra = extra;
+        self
+    }
+}
+
+/// Frame state shared by the passes of every stack
+struct Frame<'a> {
+    device: &'a wgpu::Device,
+    encoder: &'a mut wgpu::CommandEncoder,
+    depth: &'a wgpu::TextureView,
+    view_proj: Mat4,
+    /// Seconds since the last frame, for eye adaptation
+    dt: f32,
+}
+
+/// UV rectangle of a pixel rectangle inside a texture of `size`
+fn uv_rect(rect: (u32, u32, u32, u32), size: (u32, u32)) -> [f32; 4] {
+    let (width, height) = (size.0 as f32, size.1 as f32);
+    [
+        rect.0 as f32 / width,
+        rect.1 as f32 / height,
+        (rect.0 + rect.2) as f32 / width,
+        (rect.1 + rect.3) as f32 / height,
+    ]
+}
+
+fn quarter_size(size: (u32, u32)) -> (u32, u32) {
+    (size.0.div_ceil(4), size.1.div_ceil(4))
+}
+
+/// The quarter size texels covering a pixel rectangle
+fn quarter_rect(rect: (u32, u32, u32, u32)) -> (u32, u32, u32, u32) {
+    let (x, y) = (rect.0 / 4, rect.1 / 4);
+    let right = (rect.0 + rect.2).div_ceil(4);
+    let bottom = (rect.1 + rect.3).div_ceil(4);
+    (x, y, (right - x).max(1), (bottom - y).max(1))
+}
+
 impl PostProcessResources {
     pub fn initialize(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
-        // Create bind group layout
+        let texture_entry = |binding, sample_type, view_dimension| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let color = wgpu::TextureSampleType::Float { filterable: true };
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Post Process Bind Group Layout"),
             entries: &[
                 // Input texture
+                texture_entry(0, color, wgpu::TextureViewDimension::D2),
+                // Sampler
                 wgpu::BindGroupLayoutEntry {
-                    binding: 0,
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                // Pass parameters
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
                     visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
                     },
                     count: None,
                 },
-                // Sampler
+                // Auxiliary texture: bloom, occlusion, adapted exposure
+                texture_entry(3, color, wgpu::TextureViewDimension::D2),
+                // Color grading LUT
+                texture_entry(4, color, wgpu::TextureViewDimension::D3),
+                // Scene depth
+                texture_entry(
+                    5,
+                    wgpu::TextureSampleType::Depth,
+                    wgpu::TextureViewDimension::D2,
+                ),
+                // Depth sampler
                 wgpu::BindGroupLayoutEntry {
-                    binding: 1,
+                    binding: 6,
                     visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
                     count: None,
                 },
             ],
@@ -68,81 +379,534 @@ impl PostProcessResources {
             mipmap_filter: wgpu::FilterMode::Nearest,
             ..Default::default()
         });
+        let depth_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Process Depth Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            ..Default::default()
+        });
 
-        // Create pipeline
+        // Create pipelines
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Post Process Pipeline Layout"),
             bind_group_layouts: &[&bind_group_layout],
             push_constant_ranges: &[],
         });
-
         let mut shader = Shader::from_wgsl(include_str!("../shaders/post_process.wgsl"));
-        let shader_module = shader.compile(device);
-
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Post Process Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vs_main",
-                buffers: &[],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
-        });
+        let module = shader.compile(device);
+        let pipeline = |entry_point, format| {
+            create_post_process_pipeline(device, module, &pipeline_layout, entry_point, format)
+        };
 
-        self.pipeline = Some(pipeline);
+        // sRGB render targets encode gamma in hardware
+        let output_entry = |format: wgpu::TextureFormat| {
+            if format.is_srgb() {
+                "fs_output_srgb"
+            } else {
+                "fs_output"
+            }
+        };
+        self.pipeline = Some(pipeline(output_entry(format), format));
+        self.offscreen_pipeline = Some(pipeline(output_entry(OFFSCREEN_FORMAT), OFFSCREEN_FORMAT));
+        self.effect_pipelines = EFFECT_ENTRIES
+            .iter()
+            .map(|&entry| (entry, pipeline(entry, INTERMEDIATE_FORMAT)))
+            .collect();
         self.bind_group_layout = Some(bind_group_layout);
         self.sampler = Some(sampler);
+        self.depth_sampler = Some(depth_sampler);
+        self.shader = Some(shader);
+        self.pipeline_layout = Some(pipeline_layout);
     }
 
-    /// Create a bind group for a specific input texture
-    pub fn create_bind_group(
-        &self,
+    /// Build the effect pipelines of `entries` that don't exist yet
+    fn build_effect_pipelines(&mut self, device: &wgpu::Device, entries: &[&'static str]) {
+        let (Some(shader), Some(pipeline_layout)) = (&mut self.shader, &self.pipeline_layout)
+        else {
+            return;
+        };
+        for &entry in entries {
+            if !self.effect_pipelines.contains_key(entry) {
+                let pipeline = create_post_process_pipeline(
+                    device,
+                    shader.compile(device),
+                    pipeline_layout,
+                    entry,
+                    INTERMEDIATE_FORMAT,
+                );
+                self.effect_pipelines.insert(entry, pipeline);
+            }
+        }
+    }
+
+    /// Allocate what this frame's stacks use and drop the rest
+    fn prepare(
+        &mut self,
         device: &wgpu::Device,
-        texture_view: &wgpu::TextureView,
-    ) -> wgpu::BindGroup {
-        device.create_bind_group(&wgpu::BindGroupDescriptor {
+        queue: &wgpu::Queue,
+        asset_server: Option<&AssetServer>,
+        targets: &HashMap<(u32, u32), TargetNeeds>,
+        stacks: &[&PostProcessSettings],
+        auto_exposed: &HashSet<Entity>,
+    ) {
+        if self.fallback.is_none() {
+            self.fallback = Some(Fallback {
+                color: TextureTarget::new(device, "Post Process Fallback", (1, 1)),
+                lut: GpuLut::new(device, queue, &ColorLut::identity(2)),
+            });
+        }
+
+        self.intermediates
+            .retain(|size, _| targets.contains_key(size));
+        for (&size, needs) in targets {
+            let intermediates = self
+                .intermediates
+                .entry(size)
+                .or_insert_with(|| Intermediates {
+                    color: TextureTarget::pair(device, "Post Process Color", size),
+                    bloom: None,
+                    scratch: None,
+                });
+            if needs.bloom && intermediates.bloom.is_none() {
+                intermediates.bloom = Some(TextureTarget::pair(
+                    device,
+                    "Post Process Bloom",
+                    quarter_size(size),
+                ));
+            }
+            if needs.scratch && intermediates.scratch.is_none() {
+                intermediates.scratch =
+                    Some(TextureTarget::pair(device, "Post Process Scratch", size));
+            }
+        }
+
+        let uses_ssao = stacks.iter().any(|settings| {
+            settings
+                .effects
+                .iter()
+                .any(|effect| matches!(effect, PostEffect::Ssao { .. }))
+        });
+        if uses_ssao {
+            self.build_effect_pipelines(device, SSAO_ENTRIES);
+        }
+
+        if !auto_exposed.is_empty() && self.luminance.is_empty() {
+            let mut size = LUMINANCE_SIZE;
+            loop {
+                self.luminance
+                    .push(TextureTarget::new(device, "Scene Luminance", (size, size)));
+                if size == 1 {
+                    break;
+                }
+                size /= 2;
+            }
+        }
+        self.exposure
+            .retain(|camera, _| auto_exposed.contains(camera));
+        for &camera in auto_exposed {
+            self.exposure
+                .entry(camera)
+                .or_insert_with(|| AdaptedExposure {
+                    states: TextureTarget::pair(device, "Adapted Exposure", (1, 1)),
+                    current: 0,
+                    reset: true,
+                });
+        }
+
+        let Some(asset_server) = asset_server else {
+            return;
+        };
+        for settings in stacks {
+            for effect in &settings.effects {
+                let PostEffect::ColorGrading { lut, .. } = effect else {
+                    continue;
+                };
+                if self.luts.contains_key(&lut.id()) {
+                    continue;
+                }
+                if let Some(loaded) = asset_server.get(lut) {
+                    self.luts
+                        .insert(lut.id(), GpuLut::new(device, queue, &loaded));
+                }
+            }
+        }
+    }
+
+    /// Record one fullscreen draw
+    fn draw(&self, frame: &mut Frame, pass: Pass) {
+        let (Some(layout), Some(sampler), Some(depth_sampler), Some(fallback)) = (
+            &self.bind_group_layout,
+            &self.sampler,
+            &self.depth_sampler,
+            &self.fallback,
+        ) else {
+            return;
+        };
+        let lut = pass.lut.unwrap_or(&fallback.lut);
+        let (aux, aux_rect) = pass
+            .aux
+            .unwrap_or((&fallback.color.view, [0.0, 0.0, 1.0, 1.0]));
+        let params = PostParams {
+            out_rect: [
+                pass.out_rect.0 as f32,
+                pass.out_rect.1 as f32,
+                pass.out_rect.2 as f32,
+                pass.out_rect.3 as f32,
+            ],
+            in_rect: pass.in_rect,
+            aux_rect,
+            settings: pass.settings,
+            extra: pass.extra,
+            lut_min: lut.domain_min.extend(0.0).to_array(),
+            lut_max: lut.domain_max.extend(0.0).to_array(),
+            view_proj: frame.view_proj.to_cols_array_2d(),
+            inv_view_proj: frame.view_proj.inverse().to_cols_array_2d(),
+        };
+        let buffer = frame
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Post Process Params"),
+                contents: bytemuck::bytes_of(&params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let bind_group = frame.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Post Process Bind Group"),
-            layout: self.bind_group_layout.as_ref().unwrap(),
+            layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(texture_view),
+                    resource: wgpu::BindingResource::TextureView(pass.input),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(self.sampler.as_ref().unwrap()),
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(aux),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&lut.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(frame.depth),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::Sampler(depth_sampler),
                 },
             ],
-        })
+        });
+
+        let mut render_pass = frame
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Process Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: pass.output,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: pass.load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+        let (x, y, width, height) = pass.out_rect;
+        render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+        render_pass.set_scissor_rect(x, y, width, height);
+        render_pass.set_pipeline(pass.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
     }
+
+    /// Run the effects of `job` on the target's HDR image and write the
+    /// result to the target. Returns the camera whose exposure adapted.
+    fn run_stack(
+        &self,
+        frame: &mut Frame,
+        target: &TargetOutput,
+        job: &Job,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> Option<Entity> {
+        let intermediates = self.intermediates.get(&target.size)?;
+        let effect = |entry: &str| &self.effect_pipelines[entry];
+        let rect = job.rect;
+        let uv = uv_rect(rect, target.size);
+        frame.view_proj = job.view.map_or(Mat4::IDENTITY, |view| view.view_proj);
+        let mut adapted = None;
+
+        let mut input = target.hdr;
+        let mut next = 0;
+        for step in &job.settings.effects {
+            let output = &intermediates.color[next].view;
+            let pass = |entry| Pass::new(effect(entry), input, uv, output, rect);
+            let applied = match step {
+                PostEffect::Bloom {
+                    threshold,
+                    intensity,
+                } => {
+                    let Some(bloom) = &intermediates.bloom else {
+                        continue;
+                    };
+                    let quarter = quarter_rect(rect);
+                    let quarter_uv = uv_rect(quarter, quarter_size(target.size));
+                    self.draw(
+                        frame,
+                        Pass::new(
+                            effect("fs_bloom_prefilter"),
+                            input,
+                            uv,
+                            &bloom[0].view,
+                            quarter,
+                        )
+                        .settings([*threshold, 0.0, 0.0, 0.0]),
+                    );
+                    // Two wider and wider blurs approximate a large kernel
+                    for spacing in [1.0, 2.0] {
+                        for (entry, from, to) in [("fs_blur_h", 0, 1), ("fs_blur_v", 1, 0)] {
+                            self.draw(
+                                frame,
+                                Pass::new(
+                                    effect(entry),
+                                    &bloom[from].view,
+                                    quarter_uv,
+                                    &bloom[to].view,
+                                    quarter,
+                                )
+                                .settings([spacing, 0.0, 0.0, 0.0]),
+                            );
+                        }
+                    }
+                    self.draw(
+                        frame,
+                        pass("fs_bloom_composite")
+                            .aux(&bloom[0].view, quarter_uv)
+                            .settings([*intensity, 0.0, 0.0, 0.0]),
+                    );
+                    true
+                }
+                PostEffect::Exposure(Exposure::Manual { compensation }) => {
+                    self.draw(
+                        frame,
+                        pass("fs_exposure").settings([*compensation, 0.0, 0.0, 0.0]),
+                    );
+                    true
+                }
+                PostEffect::Exposure(Exposure::Auto(auto)) => {
+                    let Some(state) = job.view.and_then(|view| self.exposure.get(&view.entity))
+                    else {
+                        continue;
+                    };
+                    let full = (0, 0, LUMINANCE_SIZE, LUMINANCE_SIZE);
+                    self.draw(
+                        frame,
+                        Pass::new(
+                            effect("fs_luminance"),
+                            input,
+                            uv,
+                            &self.luminance[0].view,
+                            full,
+                        ),
+                    );
+                    for level in 1..self.luminance.len() {
+                        let size = LUMINANCE_SIZE >> level;
+                        self.draw(
+                            frame,
+                            Pass::new(
+                                effect("fs_downsample"),
+                                &self.luminance[level - 1].view,
+                                [0.0, 0.0, 1.0, 1.0],
+                                &self.luminance[level].view,
+                                (0, 0, size, size),
+                            ),
+                        );
+                    }
+                    let (previous, current) = (
+                        &state.states[state.current],
+                        &state.states[1 - state.current],
+                    );
+                    self.draw(
+                        frame,
+                        Pass::new(
+                            effect("fs_adapt_exposure"),
+                            &self.luminance[self.luminance.len() - 1].view,
+                            [0.0, 0.0, 1.0, 1.0],
+                            &current.view,
+                            (0, 0, 1, 1),
+                        )
+                        .aux(&previous.view, [0.0, 0.0, 1.0, 1.0])
+                        .settings([auto.min_ev, auto.max_ev, auto.speed_up, auto.speed_down])
+                        .extra([
+                            frame.dt,
+                            if state.reset { 1.0 } else { 0.0 },
+                            0.0,
+                            0.0,
+                        ]),
+                    );
+                    self.draw(
+                        frame,
+                        pass("fs_exposure")
+                            .aux(&current.view, [0.0, 0.0, 1.0, 1.0])
+                            .settings([auto.compensation, 1.0, 0.0, 0.0]),
+                    );
+                    adapted = job.view.map(|view| view.entity);
+                    true
+                }
+                PostEffect::Tonemap(tonemapping) => {
+                    let entry = match tonemapping {
+                        Tonemapping::Aces => "fs_tonemap_aces",
+                        Tonemapping::AgX => "fs_tonemap_agx",
+                        Tonemapping::Reinhard => "fs_tonemap_reinhard",
+                    };
+                    self.draw(frame, pass(entry));
+                    true
+                }
+                PostEffect::ColorGrading { lut, strength } => {
+                    // Not loaded yet
+                    let Some(lut) = self.luts.get(&lut.id()) else {
+                        continue;
+                    };
+                    let mut grading =
+                        pass("fs_color_grading").settings([*strength, lut.size as f32, 0.0, 0.0]);
+                    grading.lut = Some(lut);
+                    self.draw(frame, grading);
+                    true
+                }
+                PostEffect::Vignette {
+                    intensity,
+                    radius,
+                    smoothness,
+                } => {
+                    self.draw(
+                        frame,
+                        pass("fs_vignette").settings([*intensity, *radius, *smoothness, 0.0]),
+                    );
+                    true
+                }
+                PostEffect::ChromaticAberration { intensity } => {
+                    self.draw(
+                        frame,
+                        pass("fs_chromatic_aberration").settings([*intensity, 0.0, 0.0, 0.0]),
+                    );
+                    true
+                }
+                PostEffect::Antialias(Antialiasing::Fxaa) => {
+                    self.draw(frame, pass("fs_fxaa"));
+                    true
+                }
+                PostEffect::Antialias(Antialiasing::Smaa) => {
+                    let Some(scratch) = &intermediates.scratch else {
+                        continue;
+                    };
+                    let (edges, weights) = (&scratch[0].view, &scratch[1].view);
+                    self.draw(
+                        frame,
+                        Pass::new(effect("fs_smaa_edges"), input, uv, edges, rect),
+                    );
+                    self.draw(
+                        frame,
+                        Pass::new(effect("fs_smaa_weights"), edges, uv, weights, rect),
+                    );
+                    self.draw(frame, pass("fs_smaa_blend").aux(weights, uv));
+                    true
+                }
+                PostEffect::Ssao {
+                    radius,
+                    intensity,
+                    samples,
+                } => {
+                    let (Some(scratch), Some(_)) = (&intermediates.scratch, job.view) else {
+                        continue;
+                    };
+                    let settings = [*radius, *intensity, *samples as f32, 0.0];
+                    let (occlusion, blurred) = (&scratch[0].view, &scratch[1].view);
+                    self.draw(
+                        frame,
+                        Pass::new(effect("fs_ssao"), input, uv, occlusion, rect).settings(settings),
+                    );
+                    self.draw(
+                        frame,
+                        Pass::new(effect("fs_ssao_blur"), occlusion, uv, blurred, rect),
+                    );
+                    self.draw(
+                        frame,
+                        pass("fs_ssao_apply").aux(blurred, uv).settings(settings),
+                    );
+                    true
+                }
+            };
+            if applied {
+                input = output;
+                next = 1 - next;
+            }
+        }
+
+        let pipeline = if target.offscreen {
+            &self.offscreen_pipeline
+        } else {
+            &self.pipeline
+        };
+        let mut output = Pass::new(pipeline.as_ref()?, input, uv, target.output, rect);
+        output.load = load;
+        self.draw(frame, output);
+        adapted
+    }
+}
+
+fn create_post_process_pipeline(
+    device: &wgpu::Device,
+    module: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+    entry_point: &str,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(entry_point),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module,
+            entry_point: "vs_main",
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module,
+            entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
 }
 
 /// System to initialize post-processing resources
@@ -151,3 +915,165 @@ pub fn init_post_process_system(mut resources: ResMut<PostProcessResources>, gpu
         resources.initialize(&gpu.device, gpu.surface_config.format);
     }
 }
+
+/// Runs the [`PostProcessSettings`] stack of every camera on the HDR scene
+/// color, then writes the result into the swapchain or the camera's
+/// offscreen target. Parts of a target no camera covers are tone mapped with
+/// the default stack.
+pub struct PostProcessNode;
+
+impl PostProcessNode {
+    pub const NAME: &'static str = "post_process";
+}
+
+impl RenderNode for PostProcessNode {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn declare(&self, slots: &mut PassSlots) {
+        slots
+            .read(slots::HDR)
+            .read(slots::DEPTH)
+            .write(slots::SWAPCHAIN);
+    }
+
+    fn run<'a>(&self, context: &mut RenderContext<'a>) -> Result<(), RenderError> {
+        let (Some(world), Some(hdr_view), Some(depth_view)) = (
+            context.world,
+            context.resources.texture_view(slots::HDR),
+            context.resources.texture_view(slots::DEPTH),
+        ) else {
+            return Ok(());
+        };
+        let Some(mut resources) = world.get_resource_mut::<PostProcessResources>() else {
+            return Ok(());
+        };
+        if resources.pipeline.is_none() {
+            return Ok(());
+        }
+
+        let views = collect_camera_views(world, context.target_size);
+        let settings = components_by_main_entity::<PostProcessSettings>(world);
+        let default_settings = PostProcessSettings::default();
+        let mut targets = world.get_resource_mut::<RenderTargets>();
+
+        let mut outputs = vec![(
+            TargetKey::Window,
+            TargetOutput {
+                size: context.target_size,
+                hdr: hdr_view,
+                depth: depth_view,
+                output: context.view,
+                offscreen: false,
+                clear: wgpu::Color::BLACK,
+            },
+        )];
+        if let Some(targets) = targets.as_deref() {
+            for (key, target) in targets.iter() {
+                outputs.push((
+                    *key,
+                    TargetOutput {
+                        size: target.size,
+                        hdr: &target.hdr_view,
+                        depth: &target.depth_view,
+                        output: &target.color_view,
+                        offscreen: true,
+                        clear: wgpu::Color::TRANSPARENT,
+                    },
+                ));
+            }
+        }
+
+        let mut jobs: Vec<Vec<Job>> = Vec::with_capacity(outputs.len());
+        let mut needs: HashMap<(u32, u32), TargetNeeds> = HashMap::new();
+        let mut auto_exposed = HashSet::new();
+        for (key, target) in &outputs {
+            let cameras: Vec<&CameraView> = views.iter().filter(|view| view.key == *key).collect();
+            let full = (0, 0, target.size.0, target.size.1);
+            let mut target_jobs = Vec::new();
+            // The HDR target is cleared where no camera draws
+            if !cameras.iter().any(|view| view.viewport == full) {
+                target_jobs.push(Job {
+                    rect: full,
+                    view: None,
+                    settings: &default_settings,
+                });
+            }
+            for view in cameras {
+                target_jobs.push(Job {
+                    rect: view.viewport,
+                    view: Some(view),
+                    settings: settings.get(&view.entity).unwrap_or(&default_settings),
+                });
+            }
+
+            let target_needs = needs.entry(target.size).or_default();
+            for job in &target_jobs {
+                for effect in &job.settings.effects {
+                    match effect {
+                        PostEffect::Bloom { .. } => target_needs.bloom = true,
+                        PostEffect::Antialias(Antialiasing::Smaa) | PostEffect::Ssao { .. } => {
+                            target_needs.scratch = true
+                        }
+                        PostEffect::Exposure(Exposure::Auto(_)) => {
+                            if let Some(view) = job.view {
+                                auto_exposed.insert(view.entity);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            jobs.push(target_jobs);
+        }
+
+        let stacks: Vec<&PostProcessSettings> =
+            jobs.iter().flatten().map(|job| job.settings).collect();
+        let asset_server = world.get_resource::<AssetServer>();
+        resources.prepare(
+            context.device,
+            context.queue,
+            asset_server.as_deref(),
+            &needs,
+            &stacks,
+            &auto_exposed,
+        );
+        drop(asset_server);
+
+        let dt = world
+            .get_resource::<Time>()
+            .map_or(1.0 / 60.0, |time| time.delta_seconds());
+        let mut adapted = Vec::new();
+        for ((_, target), target_jobs) in outputs.iter().zip(&jobs) {
+            let mut frame = Frame {
+                device: context.device,
+                encoder: &mut *context.encoder,
+                depth: target.depth,
+                view_proj: Mat4::IDENTITY,
+                dt,
+            };
+            for (index, job) in target_jobs.iter().enumerate() {
+                let load = if index == 0 {
+                    wgpu::LoadOp::Clear(target.clear)
+                } else {
+                    wgpu::LoadOp::Load
+                };
+                adapted.extend(resources.run_stack(&mut frame, target, job, load));
+            }
+        }
+        for camera in adapted {
+            if let Some(state) = resources.exposure.get_mut(&camera) {
+                state.current = 1 - state.current;
+                state.reset = false;
+            }
+        }
+
+        drop(jobs);
+        drop(outputs);
+        if let Some(targets) = targets.as_mut() {
+            targets.encode_readbacks(context.encoder);
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,416 @@
+//! Render graph for composing render passes
+//!
+//! Passes declare the named resource slots they create, read and write. The
+//! GPU-independent [`RenderGraph::compile`] step turns those declarations into an
+//! execution order, culls passes whose output is never observed, and plans the
+//! lifetimes of transient resources so that compatible ones can share memory.
+
+use crate::error::RenderError;
+use luminara_core::shared_types::{Resource, World};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use thiserror::Error;
+
+/// Well-known slot names used by the built-in passes
+pub mod slots {
+    /// The surface texture being presented this frame (imported)
+    pub const SWAPCHAIN: &str = "swapchain";
+    /// Cascaded shadow map array owned by `ShadowMapResources` (imported)
+    pub const SHADOW_MAP: &str = "shadow_map";
+    /// Point and spot light shadow atlas owned by `ShadowAtlasResources` (imported)
+    pub const SHADOW_ATLAS: &str = "shadow_atlas";
+    /// Linear HDR scene color written by the forward pass
+    pub const HDR: &str = "hdr";
+    /// Scene depth buffer written by the forward pass
+    pub const DEPTH: &str = "depth";
+
+    /// Format of the [`HDR`] slot
+    pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+    /// Format of the [`DEPTH`] slot
+    pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+}
+
+/// Size of a transient texture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotSize {
+    /// Scaled from the size of the frame's render target
+    Viewport { scale: f32 },
+    /// Fixed size in texels
+    Fixed { width: u32, height: u32 },
+}
+
+impl SlotSize {
+    pub fn resolve(&self, target_width: u32, target_height: u32) -> (u32, u32) {
+        match *self {
+            SlotSize::Viewport { scale } => (
+                ((target_width as f32 * scale) as u32).max(1),
+                ((target_height as f32 * scale) as u32).max(1),
+            ),
+            SlotSize::Fixed { width, height } => (width.max(1), height.max(1)),
+        }
+    }
+}
+
+/// Description of a transient resource created by a pass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResourceDesc {
+    Texture {
+        size: SlotSize,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+    },
+    Buffer {
+        size: u64,
+        usage: wgpu::BufferUsages,
+    },
+}
+
+impl ResourceDesc {
+    /// Render target matching the frame size that later passes can sample
+    pub fn viewport_texture(format: wgpu::TextureFormat) -> Self {
+        Self::Texture {
+            size: SlotSize::Viewport { scale: 1.0 },
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        }
+    }
+
+    pub fn texture(size: SlotSize, format: wgpu::TextureFormat) -> Self {
+        Self::Texture {
+            size,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        }
+    }
+
+    pub fn buffer(size: u64, usage: wgpu::BufferUsages) -> Self {
+        Self::Buffer { size, usage }
+    }
+
+    /// Whether two resources can share the same memory when their lifetimes
+    /// do not overlap. Usage flags are merged, everything else must match.
+    pub fn can_alias(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                ResourceDesc::Texture { size, format, .. },
+                ResourceDesc::Texture {
+                    size: other_size,
+                    format: other_format,
+                    ..
+                },
+            ) => size == other_size && format == other_format,
+            (ResourceDesc::Buffer { size, .. }, ResourceDesc::Buffer { size: other, .. }) => {
+                size == other
+            }
+            _ => false,
+        }
+    }
+
+    fn merged_with(&self, other: &Self) -> Self {
+        match (*self, *other) {
+            (
+                ResourceDesc::Texture {
+                    size,
+                    format,
+                    usage,
+                },
+                ResourceDesc::Texture {
+                    usage: other_usage, ..
+                },
+            ) => ResourceDesc::Texture {
+                size,
+                format,
+                usage: usage | other_usage,
+            },
+            (
+                ResourceDesc::Buffer { size, usage },
+                ResourceDesc::Buffer {
+                    usage: other_usage, ..
+                },
+            ) => ResourceDesc::Buffer {
+                size,
+                usage: usage | other_usage,
+            },
+            (desc, _) => desc,
+        }
+    }
+}
+
+/// Resource slots declared by a pass
+#[derive(Debug, Clone, Default)]
+pub struct PassSlots {
+    creates: Vec<(String, ResourceDesc)>,
+    reads: Vec<String>,
+    writes: Vec<String>,
+    side_effects: bool,
+}
+
+impl PassSlots {
+    /// Create a transient resource; creating counts as writing it
+    pub fn create(&mut self, name: impl Into<String>, desc: ResourceDesc) -> &mut Self {
+        self.creates.push((name.into(), desc));
+        self
+    }
+
+    pub fn read(&mut self, name: impl Into<String>) -> &mut Self {
+        self.reads.push(name.into());
+        self
+    }
+
+    pub fn write(&mut self, name: impl Into<String>) -> &mut Self {
+        self.writes.push(name.into());
+        self
+    }
+
+    /// Keep the pass even if nothing reads its outputs (readbacks, queries, ...)
+    pub fn side_effects(&mut self) -> &mut Self {
+        self.side_effects = true;
+        self
+    }
+
+    pub fn creates(&self) -> &[(String, ResourceDesc)] {
+        &self.creates
+    }
+
+    pub fn reads(&self) -> &[String] {
+        &self.reads
+    }
+
+    pub fn writes(&self) -> &[String] {
+        &self.writes
+    }
+
+    pub fn has_side_effects(&self) -> bool {
+        self.side_effects
+    }
+
+    fn produces(&self, name: &str) -> bool {
+        self.writes.iter().any(|w| w == name) || self.creates.iter().any(|(c, _)| c == name)
+    }
+}
+
 pub trait RenderNode: Send + Sync {
     fn name(&self) -> &str;
+
+    /// Declare the resources this pass touches. Nodes that declare nothing are
+    /// assumed to have side effects so that they are never culled.
+    fn declare(&self, slots: &mut PassSlots) {
+        slots.side_effects();
+    }
+
     fn run<'a>(&self, context: &mut RenderContext<'a>) -> Result<(), RenderError>;
 }
 
+/// Errors reported by [`RenderGraph::compile`]
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RenderGraphError {
+    #[error("Duplicate render pass '{0}'")]
+    DuplicatePass(String),
+    #[error("Resource '{resource}' is created by both '{first}' and '{second}'")]
+    DuplicateResource {
+        resource: String,
+        first: String,
+        second: String,
+    },
+    #[error("Pass '{pass}' reads '{resource}', which no pass produces")]
+    MissingInput { pass: String, resource: String },
+    #[error("Pass '{pass}' writes '{resource}', which is neither created nor imported")]
+    UndeclaredResource { pass: String, resource: String },
+    #[error("Edge references unknown pass '{0}'")]
+    UnknownPass(String),
+    #[error("Render graph contains a cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// Planned lifetime of a transient resource in a compiled graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransientResource {
+    pub name: String,
+    pub desc: ResourceDesc,
+    /// Index into [`CompiledRenderGraph::order`] of the first pass using it
+    pub first_use: usize,
+    /// Index into [`CompiledRenderGraph::order`] of the last pass using it
+    pub last_use: usize,
+    /// Physical allocation backing this resource
+    pub physical: usize,
+}
+
+/// Result of [`RenderGraph::compile`]
+#[derive(Debug, Clone, Default)]
+pub struct CompiledRenderGraph {
+    /// Names of the passes to run, in execution order
+    pub order: Vec<String>,
+    /// Passes whose outputs are never observed
+    pub culled: Vec<String>,
+    /// Transient resources used by the passes that run
+    pub resources: Vec<TransientResource>,
+    /// Allocations shared by aliased transient resources
+    pub physical: Vec<ResourceDesc>,
+    node_indices: Vec<usize>,
+}
+
+impl CompiledRenderGraph {
+    pub fn resource(&self, name: &str) -> Option<&TransientResource> {
+        self.resources.iter().find(|r| r.name == name)
+    }
+
+    /// Whether two transient resources share an allocation
+    pub fn is_aliased(&self, a: &str, b: &str) -> bool {
+        match (self.resource(a), self.resource(b)) {
+            (Some(a), Some(b)) => a.physical == b.physical,
+            _ => false,
+        }
+    }
+}
+
+enum PhysicalResource {
+    Texture {
+        desc: ResourceDesc,
+        extent: (u32, u32),
+        _texture: wgpu::Texture,
+        view: wgpu::TextureView,
+    },
+    Buffer {
+        desc: ResourceDesc,
+        buffer: wgpu::Buffer,
+    },
+}
+
+/// GPU allocations backing the transient resources of a compiled graph
+#[derive(Default)]
+pub struct TransientResources {
+    physical: Vec<Option<PhysicalResource>>,
+    by_name: HashMap<String, usize>,
+}
+
+impl TransientResources {
+    pub fn texture_view(&self, name: &str) -> Option<&wgpu::TextureView> {
+        match self.physical.get(*self.by_name.get(name)?)? {
+            Some(PhysicalResource::Texture { view, .. }) => Some(view),
+            _ => None,
+        }
+    }
+
+    pub fn buffer(&self, name: &str) -> Option<&wgpu::Buffer> {
+        match self.physical.get(*self.by_name.get(name)?)? {
+            Some(PhysicalResource::Buffer { buffer, .. }) => Some(buffer),
+            _ => None,
+        }
+    }
+
+    /// Create or reuse the allocations planned by `compiled`
+    fn prepare(&mut self, device: &wgpu::Device, compiled: &CompiledRenderGraph, size: (u32, u32)) {
+        self.physical.resize_with(compiled.physical.len(), || None);
+        self.physical.truncate(compiled.physical.len());
+
+        for (index, desc) in compiled.physical.iter().enumerate() {
+            let reusable = match (&self.physical[index], desc) {
+                (
+                    Some(PhysicalResource::Texture {
+                        desc: current,
+                        extent,
+                        ..
+                    }),
+                    ResourceDesc::Texture { size: slot, .. },
+                ) => current == desc && *extent == slot.resolve(size.0, size.1),
+                (Some(PhysicalResource::Buffer { desc: current, .. }), _) => current == desc,
+                _ => false,
+            };
+            if reusable {
+                continue;
+            }
+
+            let label = format!("Render Graph Transient {}", index);
+            self.physical[index] = Some(match *desc {
+                ResourceDesc::Texture {
+                    size: slot,
+                    format,
+                    usage,
+                } => {
+                    let extent = slot.resolve(size.0, size.1);
+                    let texture = device.create_texture(&wgpu::TextureDescriptor {
+                        label: Some(&label),
+                        size: wgpu::Extent3d {
+                            width: extent.0,
+                            height: extent.1,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format,
+                        usage,
+                        view_formats: &[],
+                    });
+                    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                    PhysicalResource::Texture {
+                        desc: *desc,
+                        extent,
+                        _texture: texture,
+                        view,
+                    }
+                }
+                ResourceDesc::Buffer { size, usage } => PhysicalResource::Buffer {
+                    desc: *desc,
+                    buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&label),
+                        size,
+                        usage,
+                        mapped_at_creation: false,
+                    }),
+                },
+            });
+        }
+
+        self.by_name = compiled
+            .resources
+            .iter()
+            .map(|r| (r.name.clone(), r.physical))
+            .collect();
+    }
+}
+
+/// Per-pass view of the frame being rendered
+pub struct RenderContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    /// The [`slots::SWAPCHAIN`] view
+    pub view: &'a wgpu::TextureView,
+    pub target_format: wgpu::TextureFormat,
+    pub target_size: (u32, u32),
+    pub world: Option<&'a World>,
+    pub resources: &'a TransientResources,
+}
+
+impl<'a> RenderContext<'a> {
+    /// View of a texture slot, including the swapchain
+    pub fn texture_view(&self, name: &str) -> Option<&wgpu::TextureView> {
+        if name == slots::SWAPCHAIN {
+            Some(self.view)
+        } else {
+            self.resources.texture_view(name)
+        }
+    }
+}
+
+/// Frame-level inputs to [`RenderGraph::execute`]
+pub struct RenderFrame<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub view: &'a wgpu::TextureView,
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    pub world: Option<&'a World>,
+}
+
 pub struct RenderGraph {
-    pub nodes: Vec<Box<dyn RenderNode>>,
+    nodes: Vec<Box<dyn RenderNode>>,
+    imports: BTreeSet<String>,
+    edges: Vec<(String, String)>,
+    transients: TransientResources,
+    /// Result of the last compile, cleared whenever nodes, edges or imports change
+    compiled: Option<CompiledRenderGraph>,
 }
 
 impl Default for RenderGraph {
@@ -14,32 +420,345 @@ impl Default for RenderGraph {
 }
 
 impl RenderGraph {
+    /// An empty graph with only the swapchain imported
     pub fn new() -> Self {
-        Self { nodes: Vec::new() }
+        let mut imports = BTreeSet::new();
+        imports.insert(slots::SWAPCHAIN.to_string());
+        Self {
+            nodes: Vec::new(),
+            imports,
+            edges: Vec::new(),
+            transients: TransientResources::default(),
+            compiled: None,
+        }
+    }
+
+    /// The built-in 3D pipeline: shadows, Forward+ into HDR, world-space text,
+    /// tone mapping, overlay
+    pub fn forward_3d() -> Self {
+        let mut graph = Self::new();
+        graph.import(slots::SHADOW_MAP);
+        graph.import(slots::SHADOW_ATLAS);
+        graph.add_node(crate::shadow::ShadowPassNode);
+        graph.add_node(crate::shadow_atlas::ShadowAtlasNode);
+        graph.add_node(crate::forward_plus::ForwardPlusNode);
+        graph.add_node(crate::text::Text3dNode);
+        graph.add_node(crate::post_process::PostProcessNode);
+        graph.add_node(crate::overlay::OverlayNode);
+        graph
+    }
+
+    /// Declare a resource owned outside the graph. Passes writing imported
+    /// resources are never culled.
+    pub fn import(&mut self, name: impl Into<String>) {
+        self.imports.insert(name.into());
+        self.compiled = None;
     }
 
     pub fn add_node(&mut self, node: impl RenderNode + 'static) {
         self.nodes.push(Box::new(node));
+        self.compiled = None;
     }
-}
 
-use crate::command::CommandBuffer;
-use luminara_asset::AssetServer;
+    /// Insert a node ahead of `before` in declaration order, so it writes
+    /// shared resources before that pass does. Returns false if `before` is unknown.
+    pub fn insert_node_before(&mut self, before: &str, node: impl RenderNode + 'static) -> bool {
+        match self.nodes.iter().position(|n| n.name() == before) {
+            Some(index) => {
+                self.nodes.insert(index, Box::new(node));
+                self.compiled = None;
+                true
+            }
+            None => false,
+        }
+    }
 
-// Placeholder types for RenderContext and RenderError
-pub struct RenderContext<'a> {
-    pub device: &'a wgpu::Device,
-    pub queue: &'a wgpu::Queue,
-    pub encoder: &'a mut wgpu::CommandEncoder,
-    pub view: &'a wgpu::TextureView,
-    pub command_buffer: Option<&'a CommandBuffer>,
-    pub asset_server: Option<&'a AssetServer>,
+    pub fn remove_node(&mut self, name: &str) -> Option<Box<dyn RenderNode>> {
+        let index = self.nodes.iter().position(|n| n.name() == name)?;
+        self.compiled = None;
+        Some(self.nodes.remove(index))
+    }
+
+    /// Require `before` to run before `after` regardless of resource usage
+    pub fn add_edge(&mut self, before: impl Into<String>, after: impl Into<String>) {
+        self.edges.push((before.into(), after.into()));
+        self.compiled = None;
+    }
+
+    pub fn node_names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(|n| n.name())
+    }
+
+    /// Order the passes, cull unused ones and plan transient resources.
+    ///
+    /// Declaration order decides how passes sharing a resource are ordered:
+    /// producers of the same resource keep their order, a read sees the latest
+    /// write declared before it, and an overwrite waits for the earlier readers.
+    pub fn compile(&self) -> Result<CompiledRenderGraph, RenderGraphError> {
+        let names: Vec<&str> = self.nodes.iter().map(|n| n.name()).collect();
+        let mut index_of = HashMap::new();
+        for (index, name) in names.iter().enumerate() {
+            if index_of.insert(*name, index).is_some() {
+                return Err(RenderGraphError::DuplicatePass(name.to_string()));
+            }
+        }
+
+        let declared: Vec<PassSlots> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut slots = PassSlots::default();
+                node.declare(&mut slots);
+                slots
+            })
+            .collect();
+
+        // Resource origins
+        let mut created: HashMap<&str, (usize, ResourceDesc)> = HashMap::new();
+        for (pass, slots) in declared.iter().enumerate() {
+            for (name, desc) in &slots.creates {
+                let previous = if self.imports.contains(name) {
+                    Some("imported")
+                } else {
+                    created.get(name.as_str()).map(|(first, _)| names[*first])
+                };
+                if let Some(first) = previous {
+                    return Err(RenderGraphError::DuplicateResource {
+                        resource: name.clone(),
+                        first: first.to_string(),
+                        second: names[pass].to_string(),
+                    });
+                }
+                created.insert(name.as_str(), (pass, *desc));
+            }
+        }
+
+        // Dependencies: dependencies[p] holds the passes whose results p uses;
+        // ordering[p] holds earlier readers of what p overwrites, which must run
+        // first but don't keep p's inputs alive
+        let mut dependencies: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); self.nodes.len()];
+        let mut ordering: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); self.nodes.len()];
+        let mut last_producer: HashMap<&str, usize> = HashMap::new();
+        let mut readers: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (pass, slots) in declared.iter().enumerate() {
+            for name in &slots.writes {
+                if !self.imports.contains(name) && !created.contains_key(name.as_str()) {
+                    return Err(RenderGraphError::UndeclaredResource {
+                        pass: names[pass].to_string(),
+                        resource: name.clone(),
+                    });
+                }
+            }
+            for name in &slots.reads {
+                match last_producer.get(name.as_str()) {
+                    Some(&producer) => {
+                        dependencies[pass].insert(producer);
+                    }
+                    None if self.imports.contains(name) || slots.produces(name) => {}
+                    None => {
+                        return Err(RenderGraphError::MissingInput {
+                            pass: names[pass].to_string(),
+                            resource: name.clone(),
+                        })
+                    }
+                }
+                readers.entry(name.as_str()).or_default().push(pass);
+            }
+            let produced = slots
+                .writes
+                .iter()
+                .chain(slots.creates.iter().map(|(name, _)| name));
+            for name in produced {
+                if let Some(&previous) = last_producer.get(name.as_str()) {
+                    if previous != pass {
+                        dependencies[pass].insert(previous);
+                    }
+                }
+                for reader in readers.remove(name.as_str()).unwrap_or_default() {
+                    if reader != pass {
+                        ordering[pass].insert(reader);
+                    }
+                }
+                last_producer.insert(name.as_str(), pass);
+            }
+        }
+        for (before, after) in &self.edges {
+            let lookup = |name: &String| {
+                index_of
+                    .get(name.as_str())
+                    .copied()
+                    .ok_or_else(|| RenderGraphError::UnknownPass(name.clone()))
+            };
+            let (before, after) = (lookup(before)?, lookup(after)?);
+            dependencies[after].insert(before);
+        }
+
+        let constraints: Vec<BTreeSet<usize>> = dependencies
+            .iter()
+            .zip(&ordering)
+            .map(|(dependencies, ordering)| dependencies | ordering)
+            .collect();
+        let sorted = topological_order(&constraints).map_err(|cycle| {
+            RenderGraphError::Cycle(cycle.iter().map(|&p| names[p].to_string()).collect())
+        })?;
+
+        // Culling: keep passes with observable output and everything they depend on
+        let mut kept = vec![false; self.nodes.len()];
+        let mut stack: Vec<usize> = declared
+            .iter()
+            .enumerate()
+            .filter(|(_, slots)| {
+                slots.side_effects || slots.writes.iter().any(|name| self.imports.contains(name))
+            })
+            .map(|(pass, _)| pass)
+            .collect();
+        while let Some(pass) = stack.pop() {
+            if !std::mem::replace(&mut kept[pass], true) {
+                stack.extend(dependencies[pass].iter().copied());
+            }
+        }
+
+        let node_indices: Vec<usize> = sorted.iter().copied().filter(|&p| kept[p]).collect();
+        let culled = (0..self.nodes.len())
+            .filter(|&p| !kept[p])
+            .map(|p| names[p].to_string())
+            .collect();
+
+        // Lifetimes of transient resources over the execution order
+        let mut lifetimes: HashMap<&str, (usize, usize)> = HashMap::new();
+        for (position, &pass) in node_indices.iter().enumerate() {
+            let slots = &declared[pass];
+            let touched = slots
+                .reads
+                .iter()
+                .chain(&slots.writes)
+                .chain(slots.creates.iter().map(|(name, _)| name));
+            for name in touched {
+                if created.contains_key(name.as_str()) {
+                    let entry = lifetimes
+                        .entry(name.as_str())
+                        .or_insert((position, position));
+                    entry.0 = entry.0.min(position);
+                    entry.1 = entry.1.max(position);
+                }
+            }
+        }
+        let mut resources: Vec<TransientResource> = lifetimes
+            .into_iter()
+            .map(|(name, (first_use, last_use))| TransientResource {
+                name: name.to_string(),
+                desc: created[name].1,
+                first_use,
+                last_use,
+                physical: 0,
+            })
+            .collect();
+        resources.sort_by(|a, b| a.first_use.cmp(&b.first_use).then(a.name.cmp(&b.name)));
+
+        // Aliasing: greedily reuse an allocation whose previous user has finished
+        let mut physical: Vec<ResourceDesc> = Vec::new();
+        let mut busy_until: Vec<usize> = Vec::new();
+        for resource in &mut resources {
+            let reusable = (0..physical.len()).find(|&slot| {
+                busy_until[slot] < resource.first_use && physical[slot].can_alias(&resource.desc)
+            });
+            match reusable {
+                Some(slot) => {
+                    physical[slot] = physical[slot].merged_with(&resource.desc);
+                    busy_until[slot] = resource.last_use;
+                    resource.physical = slot;
+                }
+                None => {
+                    resource.physical = physical.len();
+                    physical.push(resource.desc);
+                    busy_until.push(resource.last_use);
+                }
+            }
+        }
+
+        Ok(CompiledRenderGraph {
+            order: node_indices.iter().map(|&p| names[p].to_string()).collect(),
+            culled,
+            resources,
+            physical,
+            node_indices,
+        })
+    }
+
+    /// Compile the graph if it changed, allocate its transient resources and
+    /// record every pass
+    pub fn execute(
+        &mut self,
+        frame: &RenderFrame<'_>,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> Result<(), RenderError> {
+        let compiled = match self.compiled.take() {
+            Some(compiled) => compiled,
+            None => self.compile()?,
+        };
+        let compiled = &*self.compiled.insert(compiled);
+        self.transients
+            .prepare(frame.device, compiled, (frame.width, frame.height));
+
+        for &index in &compiled.node_indices {
+            let mut context = RenderContext {
+                device: frame.device,
+                queue: frame.queue,
+                encoder: &mut *encoder,
+                view: frame.view,
+                target_format: frame.format,
+                target_size: (frame.width, frame.height),
+                world: frame.world,
+                resources: &self.transients,
+            };
+            self.nodes[index].run(&mut context)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
-pub enum RenderError {
-    Other(String),
+/// Kahn's algorithm preferring declaration order; on failure returns one cycle
+fn topological_order(dependencies: &[BTreeSet<usize>]) -> Result<Vec<usize>, Vec<usize>> {
+    let count = dependencies.len();
+    let mut dependents = vec![Vec::new(); count];
+    let mut remaining: Vec<usize> = dependencies.iter().map(|d| d.len()).collect();
+    for (pass, deps) in dependencies.iter().enumerate() {
+        for &dep in deps {
+            dependents[dep].push(pass);
+        }
+    }
+
+    let mut ready: BTreeSet<usize> = (0..count).filter(|&p| remaining[p] == 0).collect();
+    let mut order = Vec::with_capacity(count);
+    while let Some(pass) = ready.pop_first() {
+        order.push(pass);
+        for &next in &dependents[pass] {
+            remaining[next] -= 1;
+            if remaining[next] == 0 {
+                ready.insert(next);
+            }
+        }
+    }
+    if order.len() == count {
+        return Ok(order);
+    }
+
+    // Every unsorted pass waits on another unsorted pass; walk back until a repeat
+    let unsorted: HashSet<usize> = (0..count).filter(|p| !order.contains(p)).collect();
+    let mut path = vec![*unsorted.iter().min().unwrap()];
+    loop {
+        let current = *path.last().unwrap();
+        let next = *dependencies[current]
+            .iter()
+            .find(|dep| unsorted.contains(dep))
+            .unwrap();
+        if let Some(start) = path.iter().position(|&p| p == next) {
+            let mut cycle = path[start..].to_vec();
+            cycle.reverse();
+            return Err(cycle);
+        }
+        path.push(next);
+    }
 }
 
-use luminara_core::shared_types::Resource;
 impl Resource for RenderGraph {}
@@ -0,0 +1,310 @@
+// Camera resolution and offscreen render targets
+use crate::camera::{Camera, RenderLayers, RenderTarget};
+use crate::render_graph::slots;
+use crate::render_world::MainEntity;
+use crate::{TextureData, TextureFormat};
+use luminara_asset::{AssetId, AssetServer};
+use luminara_core::shared_types::{Query, Resource, World};
+use luminara_core::Entity;
+use luminara_math::{Color, Mat4, Transform, Vec3};
+use std::collections::HashMap;
+
+/// Color format of offscreen targets after tone mapping
+pub const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Identifies the surface a camera draws into. Cameras sharing a key share
+/// color and depth, so the first of them clears and the rest draw on top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetKey {
+    Window,
+    Texture(AssetId),
+    /// Readback images belong to a single camera
+    Image(Entity),
+}
+
+/// An active camera resolved against its target for one frame
+#[derive(Debug, Clone)]
+pub struct CameraView {
+    pub entity: Entity,
+    pub order: i32,
+    pub key: TargetKey,
+    pub target_size: (u32, u32),
+    /// Pixel rectangle `(x, y, width, height)` inside the target
+    pub viewport: (u32, u32, u32, u32),
+    pub view_proj: Mat4,
+    pub position: Vec3,
+    pub clear_color: Color,
+    pub layers: RenderLayers,
+}
+
+impl CameraView {
+    /// Whether a renderable on `layers` is drawn by this camera
+    pub fn sees(&self, layers: &RenderLayers) -> bool {
+        self.layers.intersects(layers)
+    }
+}
+
+/// Collect every active camera in render order.
+///
+/// Ties in [`Camera::order`] are broken by entity id so the result is stable
+/// between frames. Texture targets whose asset is not loaded are skipped.
+pub fn collect_camera_views(world: &World, window_size: (u32, u32)) -> Vec<CameraView> {
+    let asset_server = world.get_resource::<AssetServer>();
+    let cameras = Query::<(Entity, &Camera, &Transform)>::new(world);
+
+    let mut views: Vec<CameraView> = cameras
+        .iter()
+        .filter(|(_, camera, _)| camera.is_active)
+        .filter_map(|(render_entity, camera, transform)| {
+            // Extracted cameras keep the identity of their main world entity,
+            // so per-camera state like readback targets survives extraction
+            let entity = world
+                .get_component::<MainEntity>(render_entity)
+                .map_or(render_entity, |main| main.0);
+            let (key, target_size) = match &camera.target {
+                RenderTarget::Window => (TargetKey::Window, window_size),
+                RenderTarget::Texture(handle) => {
+                    let texture = asset_server.as_ref()?.get(handle)?;
+                    (
+                        TargetKey::Texture(handle.id()),
+                        (texture.data.width, texture.data.height),
+                    )
+                }
+                RenderTarget::Image { width, height } => {
+                    (TargetKey::Image(entity), (*width, *height))
+                }
+            };
+            let target_size = (target_size.0.max(1), target_size.1.max(1));
+            let viewport = camera.viewport.to_pixels(target_size);
+            let aspect = viewport.2 as f32 / viewport.3 as f32;
+            let world_matrix = transform.compute_matrix();
+
+            Some(CameraView {
+                entity,
+                order: camera.order,
+                key,
+                target_size,
+                viewport,
+                view_proj: camera.projection_matrix(aspect) * camera.view_matrix(&world_matrix),
+                position: transform.translation,
+                clear_color: camera.clear_color,
+                layers: world
+                    .get_component::<RenderLayers>(render_entity)
+                    .copied()
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    views.sort_by_key(|view| (view.order, view.entity.id()));
+    views
+}
+
+pub(crate) struct OffscreenTarget {
+    pub size: (u32, u32),
+    pub color_view: wgpu::TextureView,
+    pub hdr_view: wgpu::TextureView,
+    pub depth_view: wgpu::TextureView,
+    color: wgpu::Texture,
+    readback: Option<Readback>,
+}
+
+struct Readback {
+    buffer: wgpu::Buffer,
+    padded_bytes_per_row: u32,
+    pending: bool,
+}
+
+/// GPU storage for cameras that render somewhere other than the window
+#[derive(Default)]
+pub struct RenderTargets {
+    targets: HashMap<TargetKey, OffscreenTarget>,
+    readbacks: HashMap<Entity, TextureData>,
+}
+
+impl Resource for RenderTargets {}
+
+impl RenderTargets {
+    /// Tone mapped color of a `RenderTarget::Texture`, ready for sampling
+    pub fn texture_view(
+        &self,
+        texture: &luminara_asset::Handle<crate::Texture>,
+    ) -> Option<&wgpu::TextureView> {
+        self.targets
+            .get(&TargetKey::Texture(texture.id()))
+            .map(|target| &target.color_view)
+    }
+
+    /// Last completed frame of a `RenderTarget::Image` camera as sRGB RGBA8
+    pub fn readback(&self, camera: Entity) -> Option<&TextureData> {
+        self.readbacks.get(&camera)
+    }
+
+    pub(crate) fn get(&self, key: &TargetKey) -> Option<&OffscreenTarget> {
+        self.targets.get(key)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&TargetKey, &OffscreenTarget)> {
+        self.targets.iter()
+    }
+
+    /// Allocate targets for this frame's cameras and drop unused ones
+    pub(crate) fn prepare(&mut self, device: &wgpu::Device, views: &[CameraView]) {
+        self.targets.retain(|key, target| {
+            views
+                .iter()
+                .any(|view| view.key == *key && view.target_size == target.size)
+        });
+        self.readbacks.retain(|camera, _| {
+            views
+                .iter()
+                .any(|view| view.key == TargetKey::Image(*camera))
+        });
+
+        for view in views {
+            if view.key == TargetKey::Window || self.targets.contains_key(&view.key) {
+                continue;
+            }
+            let readback = matches!(view.key, TargetKey::Image(_));
+            self.targets.insert(
+                view.key,
+                OffscreenTarget::new(device, view.target_size, readback),
+            );
+        }
+    }
+
+    /// Copy tone mapped readback targets into their staging buffers
+    pub(crate) fn encode_readbacks(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        for target in self.targets.values_mut() {
+            let Some(readback) = target.readback.as_mut() else {
+                continue;
+            };
+            encoder.copy_texture_to_buffer(
+                target.color.as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: &readback.buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(readback.padded_bytes_per_row),
+                        rows_per_image: Some(target.size.1),
+                    },
+                },
+                wgpu::Extent3d {
+                    width: target.size.0,
+                    height: target.size.1,
+                    depth_or_array_layers: 1,
+                },
+            );
+            readback.pending = true;
+        }
+    }
+
+    /// Map the staging buffers filled by the submitted frame. Blocks until
+    /// the GPU is done, so only cameras with readback targets pay for it.
+    pub fn finish_readbacks(&mut self, device: &wgpu::Device) {
+        for (key, target) in &mut self.targets {
+            let (TargetKey::Image(camera), Some(readback)) = (key, target.readback.as_mut()) else {
+                continue;
+            };
+            if !readback.pending {
+                continue;
+            }
+            readback.pending = false;
+
+            let slice = readback.buffer.slice(..);
+            let (sender, receiver) = std::sync::mpsc::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            device.poll(wgpu::Maintain::Wait);
+            if !matches!(receiver.recv(), Ok(Ok(()))) {
+                log::warn!("Failed to map readback buffer for camera {:?}", camera);
+                continue;
+            }
+
+            let (width, height) = target.size;
+            let row_bytes = (width * 4) as usize;
+            let mut data = Vec::with_capacity(row_bytes * height as usize);
+            {
+                let mapped = slice.get_mapped_range();
+                for row in mapped.chunks(readback.padded_bytes_per_row as usize) {
+                    data.extend_from_slice(&row[..row_bytes]);
+                }
+            }
+            readback.buffer.unmap();
+
+            self.readbacks.insert(
+                *camera,
+                TextureData {
+                    width,
+                    height,
+                    data,
+                    format: TextureFormat::Rgba8,
+                },
+            );
+        }
+    }
+}
+
+impl OffscreenTarget {
+    fn new(device: &wgpu::Device, size: (u32, u32), readback: bool) -> Self {
+        let texture = |label, format, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: size.0,
+                    height: size.1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let attachment = wgpu::TextureUsages::RENDER_ATTACHMENT;
+
+        let color = texture(
+            "Offscreen Color",
+            OFFSCREEN_FORMAT,
+            attachment | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
+        );
+        let hdr = texture(
+            "Offscreen HDR",
+            slots::HDR_FORMAT,
+            attachment | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+        // Sampled by post effects such as SSAO
+        let depth = texture(
+            "Offscreen Depth",
+            slots::DEPTH_FORMAT,
+            attachment | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+
+        let readback = readback.then(|| {
+            let padded_bytes_per_row = (size.0 * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+                * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+            Readback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Offscreen Readback"),
+                    size: padded_bytes_per_row as u64 * size.1 as u64,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                padded_bytes_per_row,
+                pending: false,
+            }
+        });
+
+        Self {
+            size,
+            color_view: color.create_view(&wgpu::TextureViewDescriptor::default()),
+            hdr_view: hdr.create_view(&wgpu::TextureViewDescriptor::default()),
+            depth_view: depth.create_view(&wgpu::TextureViewDescriptor::default()),
+            color,
+            readback,
+        }
+    }
+}
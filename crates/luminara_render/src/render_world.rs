@@ -0,0 +1,562 @@
+//! Render world extraction and pipelined rendering.
+//!
+//! At the end of every main frame, [`extract_render_world_system`] copies
+//! what the renderer needs into a fresh render [`World`]: the frame time,
+//! the active cameras with their post-processing settings, the lights, the
+//! renderables visible to some camera, world-space text meshes, gizmo
+//! commands and overlay commands.
+//! GPU resources are not copied but shared with the main world, see
+//! [`World::share_resource`]. The render systems of the [`RenderApp`] then
+//! draw that world, on a render thread when [`RenderPipelining::depth`]
+//! allows frames in flight, so the main schedule can simulate frame N+1
+//! while frame N renders.
+
+use crate::animation::SkinnedMesh;
+use crate::camera::{Camera, RenderLayers};
+use crate::clustered_lighting::LightClusters;
+use crate::command::CommandBuffer;
+use crate::components::{DirectionalLight, Lod, PointLight, SpotLight};
+use crate::forward_plus::ForwardPlusRenderer;
+use crate::frustum_culling::Frustum;
+use crate::glyph_atlas::GlyphAtlas;
+use crate::gpu::GpuContext;
+use crate::image_based_lighting::{
+    EnvironmentMapLight, EnvironmentResources, ReflectionProbe, Skybox,
+};
+use crate::material::{MaterialRegistry, MaterialTextures};
+use crate::mesh::Mesh;
+use crate::overlay::{OverlayCommand, OverlayRenderer};
+use crate::pipeline::PipelineCache;
+use crate::post_effects::PostProcessSettings;
+use crate::post_process::PostProcessResources;
+use crate::render_graph::RenderGraph;
+use crate::render_target::{collect_camera_views, RenderTargets};
+use crate::shader_preprocessor::ShaderComposer;
+use crate::shadow::{ShadowCascades, ShadowMapResources};
+use crate::shadow_atlas::{ShadowAtlas, ShadowAtlasResources, ShadowSettings};
+use crate::skinning::JointPalette;
+use crate::text::{Text3dMesh, Text3dRenderer};
+use crate::visibility::VisibleEntities;
+use crate::{CameraUniformBuffer, DebugRenderingResource};
+use luminara_asset::{AssetServer, Handle};
+use luminara_core::schedule::Schedule;
+use luminara_core::shared_types::{CoreStage, IntoSystem, Query, Resource, World};
+use luminara_core::{Bundle, Component, Entity, Time};
+use luminara_math::Transform;
+use luminara_scene::GlobalTransform;
+use luminara_window::Window;
+use std::collections::HashMap;
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::thread::JoinHandle;
+
+/// Entity of the main world an extracted entity was copied from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MainEntity(pub Entity);
+
+impl Component for MainEntity {
+    fn type_name() -> &'static str {
+        "MainEntity"
+    }
+}
+
+/// Window size when the frame was extracted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractedWindow {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Resource for ExtractedWindow {}
+
+/// Overlay commands queued by the main world during the extracted frame.
+/// [`crate::OverlayNode`] draws these instead of the commands queued on the
+/// shared [`OverlayRenderer`], which by then belong to the next frame.
+#[derive(Default)]
+pub struct ExtractedOverlay {
+    pub commands: Vec<OverlayCommand>,
+}
+
+impl Resource for ExtractedOverlay {}
+
+/// How far rendering may trail the simulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderPipelining {
+    /// Extracted frames that may be queued or rendering at once. The main
+    /// schedule blocks when that many are in flight. `1` renders each frame
+    /// on the main thread right after extraction.
+    pub depth: usize,
+}
+
+impl RenderPipelining {
+    /// Render every frame before the next one is simulated
+    pub const SYNCHRONOUS: Self = Self { depth: 1 };
+
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth: depth.max(1),
+        }
+    }
+}
+
+impl Default for RenderPipelining {
+    /// Frame N renders while frame N+1 is simulated
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl Resource for RenderPipelining {}
+
+/// Copies or shares part of the main world into a render world
+pub type ExtractFn = fn(&World, &mut World);
+
+/// The render side of the app: how frames are extracted, and the schedule
+/// that draws them
+pub struct RenderApp {
+    extractors: Vec<ExtractFn>,
+    /// `None` while the render thread owns it
+    schedule: Option<Schedule>,
+    thread: Option<RenderThread>,
+}
+
+impl Resource for RenderApp {}
+
+struct RenderThread {
+    depth: usize,
+    frames: SyncSender<World>,
+    /// Hands the schedule back once the channel closes
+    handle: JoinHandle<Schedule>,
+}
+
+impl Default for RenderApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderApp {
+    /// A render app sharing the built-in render resources and extracting
+    /// cameras, lights, reflection probes, renderables, gizmo and overlay
+    /// commands. Its schedule is empty; [`crate::RenderPlugin`] adds the
+    /// render systems.
+    pub fn new() -> Self {
+        let mut app = Self {
+            extractors: Vec::new(),
+            schedule: Some(Schedule::new()),
+            thread: None,
+        };
+        app.share_resource::<GpuContext>()
+            .share_resource::<CameraUniformBuffer>()
+            .share_resource::<PipelineCache>()
+            .share_resource::<RenderGraph>()
+            .share_resource::<RenderTargets>()
+            .share_resource::<ForwardPlusRenderer>()
+            .share_resource::<LightClusters>()
+            .share_resource::<ShadowMapResources>()
+            .share_resource::<ShadowCascades>()
+            .share_resource::<ShadowAtlas>()
+            .share_resource::<ShadowAtlasResources>()
+            .share_resource::<PostProcessResources>()
+            .share_resource::<EnvironmentResources>()
+            .share_resource::<OverlayRenderer>()
+            .share_resource::<GlyphAtlas>()
+            .share_resource::<Text3dRenderer>()
+            .share_resource::<DebugRenderingResource>()
+            .share_resource::<MaterialTextures>()
+            .share_resource::<ShaderComposer>()
+            .share_resource::<MaterialRegistry>()
+            .add_extract(extract_assets)
+            .add_extract(extract_window)
+            .add_extract(extract_time)
+            .add_extract(extract_cameras)
+            .add_extract(extract_lights)
+            .add_extract(extract_reflection_probes)
+            .add_extract(extract_renderables)
+            .add_extract(extract_text3d)
+            .add_extract(extract_commands)
+            .add_extract(extract_overlay);
+        app
+    }
+
+    /// Run `extract` for every frame, after the extractors added before it
+    pub fn add_extract(&mut self, extract: ExtractFn) -> &mut Self {
+        self.extractors.push(extract);
+        self
+    }
+
+    /// Give render worlds the main world's `R`, when it has one. The value
+    /// is shared, not copied, so the main world should not write it while
+    /// frames are in flight.
+    pub fn share_resource<R: Resource>(&mut self) -> &mut Self {
+        self.add_extract(|main, render| {
+            main.share_resource::<R>(render);
+        })
+    }
+
+    /// Add a system run on every render world. Waits for frames in flight.
+    pub fn add_system<Marker>(
+        &mut self,
+        stage: CoreStage,
+        system: impl IntoSystem<Marker>,
+    ) -> &mut Self {
+        self.finish();
+        if let Some(schedule) = self.schedule.as_mut() {
+            schedule.add_system(stage, system.into_system());
+        }
+        self
+    }
+
+    /// Build the render world of the current main frame
+    pub fn extract(&self, main: &World) -> World {
+        let mut render = World::new();
+        for extract in &self.extractors {
+            extract(main, &mut render);
+        }
+        render
+    }
+
+    /// Run the render schedule on `frame`. With a `depth` above one the
+    /// frame is queued for the render thread, blocking while `depth` frames
+    /// are already in flight.
+    pub fn render(&mut self, mut frame: World, depth: usize) {
+        if self
+            .thread
+            .as_ref()
+            .is_some_and(|thread| thread.depth != depth)
+        {
+            self.finish();
+        }
+        if depth <= 1 {
+            if let Some(schedule) = self.schedule.as_mut() {
+                schedule.run(&mut frame);
+            }
+            return;
+        }
+
+        if self.thread.is_none() {
+            let Some(schedule) = self.schedule.take() else {
+                return;
+            };
+            self.thread = Some(RenderThread::spawn(schedule, depth));
+        }
+        let Some(thread) = self.thread.as_ref() else {
+            return;
+        };
+        if thread.frames.send(frame).is_err() {
+            // The render thread panicked. Recover the schedule if possible
+            // and render on the main thread from now on.
+            log::error!("Render thread stopped, dropping frame");
+            self.finish();
+        }
+    }
+
+    /// Wait until every frame in flight has been rendered
+    pub fn finish(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        drop(thread.frames);
+        match thread.handle.join() {
+            Ok(schedule) => self.schedule = Some(schedule),
+            Err(_) => {
+                log::error!("Render thread panicked, its systems are lost");
+                self.schedule = Some(Schedule::new());
+            }
+        }
+    }
+
+    /// Whether frames are rendered on the render thread
+    pub fn is_pipelined(&self) -> bool {
+        self.thread.is_some()
+    }
+}
+
+impl Drop for RenderApp {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl RenderThread {
+    fn spawn(mut schedule: Schedule, depth: usize) -> Self {
+        // One frame renders while the others wait in the channel
+        let (frames, receiver) = sync_channel::<World>(depth - 1);
+        let handle = std::thread::Builder::new()
+            .name("render".to_string())
+            .spawn(move || {
+                while let Ok(mut frame) = receiver.recv() {
+                    schedule.run(&mut frame);
+                }
+                schedule
+            })
+            .expect("failed to spawn render thread");
+        Self {
+            depth,
+            frames,
+            handle,
+        }
+    }
+}
+
+/// Extracts the frame into a render world and renders it, on the render
+/// thread when [`RenderPipelining`] allows frames in flight
+pub fn extract_render_world_system(world: &mut World) {
+    let Some(mut render_app) = world.remove_resource::<RenderApp>() else {
+        return;
+    };
+    let depth = world
+        .get_resource::<RenderPipelining>()
+        .map_or(1, |pipelining| pipelining.depth);
+    let frame = render_app.extract(world);
+    render_app.render(frame, depth);
+    world.insert_resource(render_app);
+}
+
+/// Resize the surface to the extracted window size
+pub fn resize_surface_system(world: &mut World) {
+    let (Some(window), Some(mut gpu)) = (
+        world
+            .get_resource::<ExtractedWindow>()
+            .map(|window| *window),
+        world.get_resource_mut::<GpuContext>(),
+    ) else {
+        return;
+    };
+    if window.width > 0
+        && window.height > 0
+        && (gpu.surface_config.width != window.width || gpu.surface_config.height != window.height)
+    {
+        log::info!(
+            "resize_surface_system: resizing to {}x{}",
+            window.width,
+            window.height
+        );
+        gpu.resize(window.width, window.height);
+    }
+}
+
+/// Spawn `bundle` in `render` as the copy of `entity`, with its render layers
+/// and skin
+pub(crate) fn spawn_extracted<B: Bundle>(
+    main: &World,
+    render: &mut World,
+    entity: Entity,
+    bundle: B,
+) -> Option<Entity> {
+    let extracted = render.spawn_bundle(bundle).ok()?;
+    let _ = render.add_component(extracted, MainEntity(entity));
+    if let Some(layers) = main.get_component::<RenderLayers>(entity).copied() {
+        let _ = render.add_component(extracted, layers);
+    }
+    extract_optional::<SkinnedMesh>(main, render, entity, extracted);
+    extract_optional::<JointPalette>(main, render, entity, extracted);
+    Some(extracted)
+}
+
+/// Copy `entity`'s `C` from `main` onto its render world copy, if it has one
+fn extract_optional<C: Component + Clone>(
+    main: &World,
+    render: &mut World,
+    entity: Entity,
+    extracted: Entity,
+) {
+    if let Some(component) = main.get_component::<C>(entity).cloned() {
+        let _ = render.add_component(extracted, component);
+    }
+}
+
+/// Every `C` in `world` by the main world entity it was extracted from
+pub(crate) fn components_by_main_entity<C: Component + Clone>(world: &World) -> HashMap<Entity, C> {
+    Query::<(Entity, &C)>::new(world)
+        .iter()
+        .map(|(entity, component)| {
+            let main = world
+                .get_component::<MainEntity>(entity)
+                .map_or(entity, |main| main.0);
+            (main, component.clone())
+        })
+        .collect()
+}
+
+/// Size frusta are built for when culling renderables
+pub(crate) fn window_size(world: &World) -> (u32, u32) {
+    if let Some(window) = world.get_resource::<Window>() {
+        let (width, height) = window.inner_size();
+        if width > 0 && height > 0 {
+            return (width, height);
+        }
+    }
+    world.get_resource::<GpuContext>().map_or((1, 1), |gpu| {
+        (gpu.surface_config.width, gpu.surface_config.height)
+    })
+}
+
+/// Render worlds read assets through a clone sharing the main storage
+fn extract_assets(main: &World, render: &mut World) {
+    if let Some(asset_server) = main.get_resource::<AssetServer>() {
+        let asset_server = asset_server.clone();
+        render.insert_resource(asset_server);
+    }
+}
+
+fn extract_window(main: &World, render: &mut World) {
+    let Some(window) = main.get_resource::<Window>() else {
+        return;
+    };
+    let (width, height) = window.inner_size();
+    drop(window);
+    render.insert_resource(ExtractedWindow { width, height });
+}
+
+/// Frame timing, for effects that change over time such as eye adaptation
+fn extract_time(main: &World, render: &mut World) {
+    if let Some(time) = main.get_resource::<Time>().map(|time| time.clone()) {
+        render.insert_resource(time);
+    }
+}
+
+fn extract_cameras(main: &World, render: &mut World) {
+    let cameras: Vec<_> = Query::<(Entity, &Camera, &Transform)>::new(main)
+        .iter()
+        .filter(|(_, camera, _)| camera.is_active)
+        .map(|(entity, camera, transform)| (entity, (camera.clone(), *transform)))
+        .collect();
+    for (entity, bundle) in cameras {
+        let Some(extracted) = spawn_extracted(main, render, entity, bundle) else {
+            continue;
+        };
+        extract_optional::<PostProcessSettings>(main, render, entity, extracted);
+        extract_optional::<EnvironmentMapLight>(main, render, entity, extracted);
+        extract_optional::<Skybox>(main, render, entity, extracted);
+    }
+}
+
+fn extract_reflection_probes(main: &World, render: &mut World) {
+    let probes: Vec<_> = Query::<(Entity, &ReflectionProbe, &Transform)>::new(main)
+        .iter()
+        .map(|(entity, probe, transform)| (entity, (probe.clone(), *transform)))
+        .collect();
+    for (entity, bundle) in probes {
+        spawn_extracted(main, render, entity, bundle);
+    }
+}
+
+fn extract_lights(main: &World, render: &mut World) {
+    let directional: Vec<_> = Query::<(Entity, &DirectionalLight, &Transform)>::new(main)
+        .iter()
+        .map(|(entity, light, transform)| (entity, (light.clone(), *transform)))
+        .collect();
+    for (entity, bundle) in directional {
+        spawn_extracted(main, render, entity, bundle);
+    }
+
+    let point: Vec<_> = Query::<(Entity, &PointLight, &Transform)>::new(main)
+        .iter()
+        .map(|(entity, light, transform)| (entity, (light.clone(), *transform)))
+        .collect();
+    for (entity, bundle) in point {
+        if let Some(extracted) = spawn_extracted(main, render, entity, bundle) {
+            extract_optional::<ShadowSettings>(main, render, entity, extracted);
+        }
+    }
+
+    let spot: Vec<_> = Query::<(Entity, &SpotLight, &Transform)>::new(main)
+        .iter()
+        .map(|(entity, light, transform)| (entity, (light.clone(), *transform)))
+        .collect();
+    for (entity, bundle) in spot {
+        if let Some(extracted) = spawn_extracted(main, render, entity, bundle) {
+            extract_optional::<ShadowSettings>(main, render, entity, extracted);
+        }
+    }
+}
+
+/// Meshes of every registered material type inside some camera's frustum.
+/// After a [`visibility_system`](crate::visibility_system) pass its
+/// [`VisibleEntities`] decide instead, and pick the [`Lod`] mesh. Shadow-casting
+/// lights reach outside the views, so while one exists every renderable is
+/// extracted.
+fn extract_renderables(main: &World, render: &mut World) {
+    let (Some(registry), Some(asset_server)) = (
+        main.get_resource::<MaterialRegistry>(),
+        main.get_resource::<AssetServer>(),
+    ) else {
+        return;
+    };
+
+    let casts_shadows = Query::<&DirectionalLight>::new(main)
+        .iter()
+        .any(|light| light.cast_shadows)
+        || Query::<&PointLight>::new(main)
+            .iter()
+            .any(|light| light.cast_shadows)
+        || Query::<&SpotLight>::new(main)
+            .iter()
+            .any(|light| light.cast_shadows);
+    let visible_entities = main.get_resource::<VisibleEntities>();
+    let frusta: Vec<Frustum> = collect_camera_views(main, window_size(main))
+        .iter()
+        .map(|view| Frustum::from_view_projection(&view.view_proj))
+        .collect();
+    let select = |entity: Entity, mesh: &Handle<Mesh>, transform: &Transform| {
+        if let Some(visible_entities) = &visible_entities {
+            let level = visible_entities.lod_level(entity);
+            if level.is_none() && !casts_shadows {
+                return None;
+            }
+            let lod_mesh = level.and_then(|level| {
+                let lod = main.get_component::<Lod>(entity)?;
+                lod.meshes.get(level).or(lod.meshes.last()).cloned()
+            });
+            return Some(lod_mesh.unwrap_or_else(|| mesh.clone()));
+        }
+        if casts_shadows {
+            return Some(mesh.clone());
+        }
+        // Meshes still loading have no bounds yet
+        let Some(loaded) = asset_server.get(mesh) else {
+            return Some(mesh.clone());
+        };
+        let matrix = transform.compute_matrix();
+        frusta
+            .iter()
+            .any(|frustum| frustum.intersects_world_aabb(&loaded.aabb, &matrix))
+            .then(|| mesh.clone())
+    };
+    registry.extract(main, render, &select);
+}
+
+/// Text meshes with their world transform
+fn extract_text3d(main: &World, render: &mut World) {
+    let texts: Vec<(Entity, Text3dMesh, Transform)> = Query::<(Entity, &Text3dMesh)>::new(main)
+        .iter()
+        .map(|(entity, mesh)| {
+            let transform = main
+                .get_component::<GlobalTransform>(entity)
+                .map(|global| global.0)
+                .or_else(|| main.get_component::<Transform>(entity).copied())
+                .unwrap_or_default();
+            (entity, mesh.clone(), transform)
+        })
+        .collect();
+    for (entity, mesh, transform) in texts {
+        spawn_extracted(main, render, entity, (mesh, transform));
+    }
+}
+
+fn extract_commands(main: &World, render: &mut World) {
+    if let Some(commands) = main.get_resource::<CommandBuffer>() {
+        let commands = CommandBuffer {
+            commands: commands.commands.clone(),
+        };
+        render.insert_resource(commands);
+    }
+}
+
+fn extract_overlay(main: &World, render: &mut World) {
+    if let Some(mut overlay) = main.get_resource_mut::<OverlayRenderer>() {
+        let commands = std::mem::take(&mut overlay.commands);
+        drop(overlay);
+        render.insert_resource(ExtractedOverlay { commands });
+    }
+}
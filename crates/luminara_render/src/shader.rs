@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use luminara_asset::{Asset, AssetLoadError, AssetLoader};
+use std::path::{Path, PathBuf};
 use wgpu;
 
 pub struct Shader {
@@ -62,4 +63,35 @@ impl Shader {
         }
         self.module.as_ref().unwrap()
     }
+
+    /// WGSL text of this shader, reading it from disk for file sources
+    pub fn source_code(&self) -> std::io::Result<String> {
+        match &self.source {
+            ShaderSource::Wgsl(code) => Ok(code.clone()),
+            ShaderSource::WgslFile(path) => std::fs::read_to_string(path),
+        }
+    }
+}
+
+impl Asset for Shader {
+    fn type_name() -> &'static str {
+        "Shader"
+    }
+}
+
+/// Loads `.wgsl` files as [`Shader`] assets
+pub struct ShaderLoader;
+
+impl AssetLoader for ShaderLoader {
+    type Asset = Shader;
+
+    fn extensions(&self) -> &[&str] {
+        &["wgsl"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let source = std::str::from_utf8(bytes)
+            .map_err(|e| AssetLoadError::Parse(format!("Shader is not UTF-8: {}", e)))?;
+        Ok(Shader::from_wgsl(source))
+    }
 }
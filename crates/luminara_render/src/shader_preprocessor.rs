@@ -0,0 +1,499 @@
+// WGSL preprocessing: `#import`, `#ifdef`/`#ifndef`/`#else`/`#endif`,
+// `#define` and `#{NAME}` substitution, with a source map back to the
+// original files so naga errors point at the line that was written
+use crate::shader::Shader;
+use luminara_asset::{AssetId, AssetServer, Handle};
+use luminara_core::shared_types::Resource;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Shader definitions selecting a permutation, e.g. `SKINNED` or
+/// `MAX_LIGHTS=16`. Ordered, so equal sets always produce the same key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ShaderDefs(BTreeMap<String, String>);
+
+impl ShaderDefs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a flag tested with `#ifdef`
+    pub fn with(mut self, name: &str) -> Self {
+        self.set(name, "true");
+        self
+    }
+
+    /// Add a value substituted for `#{NAME}`
+    pub fn with_value(mut self, name: &str, value: impl fmt::Display) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set(&mut self, name: &str, value: impl fmt::Display) {
+        self.0.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.0.remove(name);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Stable permutation key, e.g. `MAX_LIGHTS=16,SKINNED`
+    pub fn key(&self) -> String {
+        self.0
+            .iter()
+            .map(|(name, value)| {
+                if value == "true" {
+                    name.clone()
+                } else {
+                    format!("{}={}", name, value)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Origin of a line of composed WGSL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderSourceLocation {
+    /// Module name or file the line was written in
+    pub file: String,
+    /// 1-based line number in that file
+    pub line: usize,
+}
+
+impl fmt::Display for ShaderSourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ShaderProcessError {
+    #[error("{location}: unknown shader module '{module}'")]
+    UnknownImport {
+        module: String,
+        location: ShaderSourceLocation,
+    },
+    #[error("import cycle: {}", .0.join(" -> "))]
+    ImportCycle(Vec<String>),
+    #[error("{location}: {message}")]
+    Directive {
+        message: String,
+        location: ShaderSourceLocation,
+    },
+    #[error("{location}: '{name}' is not defined")]
+    UndefinedValue {
+        name: String,
+        location: ShaderSourceLocation,
+    },
+    #[error("{file}: missing #endif")]
+    UnterminatedConditional { file: String },
+    /// WGSL that naga rejected; the location is mapped back to the source
+    #[error("{}: {message}", location.as_ref().map_or("<composed>".to_string(), |l| l.to_string()))]
+    Invalid {
+        message: String,
+        location: Option<ShaderSourceLocation>,
+    },
+}
+
+/// Something a composed shader was built from. Pipelines remember their
+/// dependencies so a reload only rebuilds the permutations that used it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShaderDependency {
+    /// A shader asset used as the entry point source
+    Asset(AssetId),
+    /// A named module pulled in with `#import`
+    Module(String),
+}
+
+/// Output of [`ShaderComposer::compose`]
+#[derive(Debug, Clone, Default)]
+pub struct ComposedShader {
+    pub source: String,
+    /// Origin of every line of `source`
+    pub source_map: Vec<ShaderSourceLocation>,
+    /// Modules imported while composing, in import order
+    pub imports: Vec<String>,
+}
+
+impl ComposedShader {
+    /// Insert generated code before the composed source
+    pub fn prepend(&mut self, file: &str, code: &str) {
+        let mut source = String::with_capacity(code.len() + self.source.len());
+        let mut source_map = Vec::with_capacity(self.source_map.len());
+        for (index, line) in code.lines().enumerate() {
+            source.push_str(line);
+            source.push('\n');
+            source_map.push(ShaderSourceLocation {
+                file: file.to_string(),
+                line: index + 1,
+            });
+        }
+        source.push_str(&self.source);
+        source_map.append(&mut self.source_map);
+        self.source = source;
+        self.source_map = source_map;
+    }
+
+    /// Origin of a 1-based line of [`Self::source`]
+    pub fn locate(&self, line: usize) -> Option<&ShaderSourceLocation> {
+        line.checked_sub(1)
+            .and_then(|index| self.source_map.get(index))
+    }
+
+    /// Parse and validate with naga, reporting errors at their source line
+    pub fn validate(&self) -> Result<naga::Module, ShaderProcessError> {
+        let module = naga::front::wgsl::parse_str(&self.source).map_err(|e| {
+            ShaderProcessError::Invalid {
+                message: e.message().to_string(),
+                location: e
+                    .location(&self.source)
+                    .and_then(|l| self.locate(l.line_number as usize))
+                    .cloned(),
+            }
+        })?;
+
+        let mut validator = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::default(),
+        );
+        validator.validate(&module).map_err(|e| {
+            let mut message = e.as_inner().to_string();
+            let mut source: Option<&dyn std::error::Error> =
+                std::error::Error::source(e.as_inner());
+            while let Some(cause) = source {
+                message.push_str(": ");
+                message.push_str(&cause.to_string());
+                source = cause.source();
+            }
+            ShaderProcessError::Invalid {
+                message,
+                location: e
+                    .location(&self.source)
+                    .and_then(|l| self.locate(l.line_number as usize))
+                    .cloned(),
+            }
+        })?;
+        Ok(module)
+    }
+}
+
+/// Named WGSL modules and the preprocessor that stitches them together.
+///
+/// Modules are registered by name, or come from shader assets whose first
+/// directive is `#define_import_path some::name`. [`Self::sync`] picks up
+/// reloaded shader assets and reports what changed.
+pub struct ShaderComposer {
+    modules: HashMap<String, String>,
+    watched: HashMap<AssetId, Arc<Shader>>,
+}
+
+impl Resource for ShaderComposer {}
+
+impl Default for ShaderComposer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShaderComposer {
+    /// Composer with the engine's built-in modules
+    pub fn new() -> Self {
+        let mut composer = Self::empty();
+        composer.add_module(
+            "luminara::lighting",
+            include_str!("../shaders/modules/lighting.wgsl"),
+        );
+        composer.add_module(
+            "luminara::skinning",
+            include_str!("../shaders/modules/skinning.wgsl"),
+        );
+        composer.add_module(
+            "luminara::shadows",
+            include_str!("../shaders/modules/shadows.wgsl"),
+        );
+        composer.add_module("luminara::text", include_str!("../shaders/modules/text.wgsl"));
+        composer
+    }
+
+    /// Composer without any modules
+    pub fn empty() -> Self {
+        Self {
+            modules: HashMap::new(),
+            watched: HashMap::new(),
+        }
+    }
+
+    /// Register or replace a module importable as `#import name`
+    pub fn add_module(&mut self, name: &str, source: &str) {
+        self.modules.insert(name.to_string(), source.to_string());
+    }
+
+    pub fn has_module(&self, name: &str) -> bool {
+        self.modules.contains_key(name)
+    }
+
+    /// Preprocess `source` for the permutation `defs`. `file` names the
+    /// source in error messages and the source map.
+    pub fn compose(
+        &self,
+        file: &str,
+        source: &str,
+        defs: &ShaderDefs,
+    ) -> Result<ComposedShader, ShaderProcessError> {
+        let mut state = ComposeState {
+            defs: defs.clone(),
+            output: ComposedShader::default(),
+            stack: vec![file.to_string()],
+        };
+        self.process(file, source, &mut state)?;
+        Ok(state.output)
+    }
+
+    fn process(
+        &self,
+        file: &str,
+        source: &str,
+        state: &mut ComposeState,
+    ) -> Result<(), ShaderProcessError> {
+        // Each entry is (emitting, a branch was already taken)
+        let mut conditionals: Vec<(bool, bool)> = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let location = || ShaderSourceLocation {
+                file: file.to_string(),
+                line: index + 1,
+            };
+            let active = conditionals.last().is_none_or(|(emit, _)| *emit);
+            let trimmed = line.trim();
+
+            let Some(directive) = trimmed.strip_prefix('#').filter(|d| !d.starts_with('{')) else {
+                if active {
+                    let line = substitute(line, &state.defs).map_err(|name| {
+                        ShaderProcessError::UndefinedValue {
+                            name,
+                            location: location(),
+                        }
+                    })?;
+                    state.output.source.push_str(&line);
+                    state.output.source.push('\n');
+                    state.output.source_map.push(location());
+                }
+                continue;
+            };
+
+            let mut parts = directive.split_whitespace();
+            let keyword = parts.next().unwrap_or_default();
+            let argument = parts.next();
+            let directive_error = |message: &str| ShaderProcessError::Directive {
+                message: message.to_string(),
+                location: location(),
+            };
+
+            match (keyword, argument) {
+                ("ifdef", Some(name)) | ("ifndef", Some(name)) => {
+                    let defined = state.defs.contains(name);
+                    let emit = active && (defined == (keyword == "ifdef"));
+                    conditionals.push((emit, emit || !active));
+                }
+                ("else", None) => {
+                    let parent_active =
+                        conditionals.len() < 2 || conditionals[conditionals.len() - 2].0;
+                    let Some((emit, taken)) = conditionals.last_mut() else {
+                        return Err(directive_error("#else without #ifdef"));
+                    };
+                    *emit = parent_active && !*taken;
+                    *taken = true;
+                }
+                ("endif", None) => {
+                    if conditionals.pop().is_none() {
+                        return Err(directive_error("#endif without #ifdef"));
+                    }
+                }
+                _ if !active => {}
+                ("define", Some(name)) => {
+                    let value = parts.collect::<Vec<_>>().join(" ");
+                    state
+                        .defs
+                        .set(name, if value.is_empty() { "true" } else { &value });
+                }
+                ("undef", Some(name)) => state.defs.remove(name),
+                ("import", Some(module)) => self.import(module, location(), state)?,
+                ("define_import_path", Some(_)) => {}
+                _ => {
+                    return Err(directive_error(&format!(
+                        "invalid directive '#{}'",
+                        directive
+                    )))
+                }
+            }
+        }
+
+        if !conditionals.is_empty() {
+            return Err(ShaderProcessError::UnterminatedConditional {
+                file: file.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn import(
+        &self,
+        module: &str,
+        location: ShaderSourceLocation,
+        state: &mut ComposeState,
+    ) -> Result<(), ShaderProcessError> {
+        if let Some(start) = state.stack.iter().position(|name| name == module) {
+            let mut cycle = state.stack[start..].to_vec();
+            cycle.push(module.to_string());
+            return Err(ShaderProcessError::ImportCycle(cycle));
+        }
+        // Each module is included once per composition
+        if state.output.imports.iter().any(|name| name == module) {
+            return Ok(());
+        }
+        let Some(source) = self.modules.get(module) else {
+            return Err(ShaderProcessError::UnknownImport {
+                module: module.to_string(),
+                location,
+            });
+        };
+
+        state.output.imports.push(module.to_string());
+        state.stack.push(module.to_string());
+        self.process(module, source, state)?;
+        state.stack.pop();
+        Ok(())
+    }
+
+    /// Remember the shader asset `handle` was compiled from, so [`Self::sync`]
+    /// notices when it is reloaded
+    pub fn watch(&mut self, asset_server: &AssetServer, handle: &Handle<Shader>) {
+        if self.watched.contains_key(&handle.id()) {
+            return;
+        }
+        if let Some(shader) = asset_server.get(handle) {
+            self.register_asset_module(&shader);
+            self.watched.insert(handle.id(), shader);
+        }
+    }
+
+    /// Check watched shader assets for reloads. Shaders declaring an import
+    /// path replace their module. Returns everything that changed, for
+    /// [`crate::PipelineCache::invalidate`].
+    pub fn sync(&mut self, asset_server: &AssetServer) -> Vec<ShaderDependency> {
+        let mut changed = Vec::new();
+        let mut reloaded = Vec::new();
+        for (id, seen) in &self.watched {
+            let Some(current) = asset_server.get(&Handle::<Shader>::new(*id, 0)) else {
+                continue;
+            };
+            if !Arc::ptr_eq(seen, &current) {
+                reloaded.push((*id, current));
+            }
+        }
+
+        for (id, shader) in reloaded {
+            changed.push(ShaderDependency::Asset(id));
+            if let Some(name) = self.register_asset_module(&shader) {
+                changed.push(ShaderDependency::Module(name));
+            }
+            self.watched.insert(id, shader);
+        }
+        changed
+    }
+
+    fn register_asset_module(&mut self, shader: &Shader) -> Option<String> {
+        let source = shader.source_code().ok()?;
+        let name = import_path(&source)?;
+        self.add_module(&name, &source);
+        Some(name)
+    }
+}
+
+struct ComposeState {
+    defs: ShaderDefs,
+    output: ComposedShader,
+    /// Files being processed, for cycle detection
+    stack: Vec<String>,
+}
+
+/// Name declared with `#define_import_path`, if any
+pub fn import_path(source: &str) -> Option<String> {
+    source.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("#define_import_path")
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+    })
+}
+
+/// Replace `#{NAME}` with the value of `NAME`
+fn substitute(line: &str, defs: &ShaderDefs) -> Result<String, String> {
+    if !line.contains("#{") {
+        return Ok(line.to_string());
+    }
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("#{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return Ok(output);
+        };
+        let name = after[..end].trim();
+        output.push_str(defs.get(name).ok_or_else(|| name.to_string())?);
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Shader dependencies of cached pipelines
+#[derive(Debug, Default)]
+pub struct PipelineDependencies {
+    dependencies: HashMap<String, HashSet<ShaderDependency>>,
+}
+
+impl PipelineDependencies {
+    pub fn insert(
+        &mut self,
+        label: &str,
+        dependencies: impl IntoIterator<Item = ShaderDependency>,
+    ) {
+        self.dependencies
+            .insert(label.to_string(), dependencies.into_iter().collect());
+    }
+
+    pub fn remove(&mut self, label: &str) {
+        self.dependencies.remove(label);
+    }
+
+    /// Labels of the pipelines built from `dependency`, sorted
+    pub fn affected(&self, dependency: &ShaderDependency) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .dependencies
+            .iter()
+            .filter(|(_, dependencies)| dependencies.contains(dependency))
+            .map(|(label, _)| label.clone())
+            .collect();
+        labels.sort();
+        labels
+    }
+}
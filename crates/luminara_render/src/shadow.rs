@@ -1,7 +1,11 @@
 // Cascaded shadow mapping implementation with smooth transitions
-use crate::{Camera, DirectionalLight, GpuContext};
-use luminara_core::shared_types::{Query, Res, ResMut, Resource};
+use crate::render_graph::{slots, PassSlots, RenderContext, RenderNode};
+use crate::{Camera, DirectionalLight, GpuContext, Mesh, RenderError, Shader};
+use luminara_asset::{AssetServer, Handle};
+use luminara_core::shared_types::{Query, Res, ResMut, Resource, World};
 use luminara_math::{Mat4, Transform, Vec3};
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
 
 /// Shadow cascade configuration
 pub struct ShadowCascades {
@@ -42,6 +46,11 @@ pub struct ShadowMapResources {
     pub cascade_buffer: Option<wgpu::Buffer>,
     pub bind_group: Option<wgpu::BindGroup>,
     pub bind_group_layout: Option<wgpu::BindGroupLayout>,
+    /// Single-layer depth views, one per cascade, used as render attachments
+    pub cascade_views: Vec<wgpu::TextureView>,
+    /// Depth-only pipeline rendering casters into a cascade
+    pub depth_pipeline: Option<wgpu::RenderPipeline>,
+    pub depth_layouts: Option<[wgpu::BindGroupLayout; 2]>,
 }
 
 impl Resource for ShadowMapResources {}
@@ -56,6 +65,9 @@ impl Default for ShadowMapResources {
             cascade_buffer: None,
             bind_group: None,
             bind_group_layout: None,
+            cascade_views: Vec::new(),
+            depth_pipeline: None,
+            depth_layouts: None,
         }
     }
 }
@@ -188,6 +200,23 @@ impl ShadowMapResources {
             ],
         });
 
+        let cascade_views = (0..config.cascade_count)
+            .map(|layer| {
+                shadow_texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Shadow Cascade View"),
+                    format: Some(wgpu::TextureFormat::Depth32Float),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    aspect: wgpu::TextureAspect::DepthOnly,
+                    base_mip_level: 0,
+                    mip_level_count: None,
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                })
+            })
+            .collect();
+
+        self.create_depth_pipeline(device, config);
+        self.cascade_views = cascade_views;
         self.shadow_texture = Some(shadow_texture);
         self.shadow_view = Some(shadow_view);
         self.shadow_sampler = Some(shadow_sampler);
@@ -196,6 +225,12 @@ impl ShadowMapResources {
         self.bind_group_layout = Some(bind_group_layout);
     }
 
+    fn create_depth_pipeline(&mut self, device: &wgpu::Device, config: &ShadowCascades) {
+        let (pipeline, layouts) = create_shadow_depth_pipeline(device, config.slope_bias);
+        self.depth_pipeline = Some(pipeline);
+        self.depth_layouts = Some(layouts);
+    }
+
     /// Update cascade uniform buffer on GPU
     pub fn update_cascade_buffer(&self, queue: &wgpu::Queue) {
         if let Some(buffer) = &self.cascade_buffer {
@@ -205,6 +240,77 @@ impl ShadowMapResources {
     }
 }
 
+/// Depth-only pipeline rendering shadow casters with `shaders/shadow.wgsl`,
+/// and its view and model bind group layouts
+pub(crate) fn create_shadow_depth_pipeline(
+    device: &wgpu::Device,
+    slope_bias: f32,
+) -> (wgpu::RenderPipeline, [wgpu::BindGroupLayout; 2]) {
+    let uniform_layout = |label| {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    };
+    let cascade_layout = uniform_layout("Shadow Cascade Layout");
+    let model_layout = uniform_layout("Shadow Model Layout");
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Shadow Pipeline Layout"),
+        bind_group_layouts: &[&cascade_layout, &model_layout],
+        push_constant_ranges: &[],
+    });
+
+    let mut shader = Shader::from_wgsl(include_str!("../shaders/shadow.wgsl"));
+    let shader_module = shader.compile(device);
+
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Shadow Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader_module,
+            entry_point: "vs_main",
+            buffers: &[crate::Vertex::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: None,
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: wgpu::TextureFormat::Depth32Float,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState {
+                constant: 0,
+                slope_scale: slope_bias,
+                clamp: 0.0,
+            },
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    });
+
+    (pipeline, [cascade_layout, model_layout])
+}
+
 /// Calculate cascade split depths using logarithmic distribution with lambda blending
 pub fn calculate_cascade_splits(near: f32, far: f32, cascade_count: u32, lambda: f32) -> Vec<f32> {
     let mut splits = Vec::with_capacity(cascade_count as usize);
@@ -361,8 +467,12 @@ pub fn update_shadow_cascades_system(
         shadow_resources.initialize(&gpu.device, &config);
     }
 
-    // Get active camera
-    let Some((camera, camera_transform)) = cameras.iter().next() else {
+    // Cascades follow the first active camera drawing to the window
+    let Some((camera, camera_transform)) = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active && camera.target == crate::RenderTarget::Window)
+        .min_by_key(|(camera, _)| camera.order)
+    else {
         return;
     };
 
@@ -412,3 +522,134 @@ pub fn update_shadow_cascades_system(
     // Update GPU buffer with new cascade data
     shadow_resources.update_cascade_buffer(&gpu.queue);
 }
+
+/// Renders shadow casters into every cascade of the shadow map
+pub struct ShadowPassNode;
+
+impl ShadowPassNode {
+    pub const NAME: &'static str = "shadow";
+}
+
+impl RenderNode for ShadowPassNode {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn declare(&self, slots: &mut PassSlots) {
+        slots.write(slots::SHADOW_MAP);
+    }
+
+    fn run<'a>(&self, context: &mut RenderContext<'a>) -> Result<(), RenderError> {
+        let Some(world) = context.world else {
+            return Ok(());
+        };
+        let (Some(shadows), Some(asset_server)) = (
+            world.get_resource::<ShadowMapResources>(),
+            world.get_resource::<AssetServer>(),
+        ) else {
+            return Ok(());
+        };
+        let (Some(pipeline), Some([cascade_layout, model_layout])) =
+            (&shadows.depth_pipeline, &shadows.depth_layouts)
+        else {
+            return Ok(());
+        };
+        let casts_shadows = Query::<&DirectionalLight>::new(world)
+            .iter()
+            .any(|light| light.cast_shadows);
+        if !casts_shadows {
+            return Ok(());
+        }
+        let device = context.device;
+
+        let casters = prepare_shadow_casters(world, &asset_server, device, model_layout);
+
+        for (cascade, view) in shadows.cascade_uniforms.iter().zip(&shadows.cascade_views) {
+            let cascade_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Shadow Cascade Uniform"),
+                contents: bytemuck::bytes_of(cascade),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let cascade_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Shadow Cascade Bind Group"),
+                layout: cascade_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: cascade_buffer.as_entire_binding(),
+                }],
+            });
+
+            let mut render_pass = context
+                .encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Shadow Cascade Pass"),
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &cascade_bind_group, &[]);
+
+            draw_shadow_casters(&mut render_pass, &casters);
+        }
+        Ok(())
+    }
+}
+
+/// Meshes of the render world with a model bind group for the shadow depth
+/// pipeline
+pub(crate) fn prepare_shadow_casters(
+    world: &World,
+    asset_server: &AssetServer,
+    device: &wgpu::Device,
+    model_layout: &wgpu::BindGroupLayout,
+) -> Vec<(Arc<Mesh>, wgpu::BindGroup)> {
+    let mut casters = Vec::new();
+    for (mesh_handle, transform) in Query::<(&Handle<Mesh>, &Transform)>::new(world).iter() {
+        let Some(mesh) = asset_server.get(mesh_handle) else {
+            continue;
+        };
+        let model = transform.compute_matrix().to_cols_array();
+        let model_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadow Model Buffer"),
+            contents: bytemuck::cast_slice(&model),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Model Bind Group"),
+            layout: model_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: model_buffer.as_entire_binding(),
+            }],
+        });
+        casters.push((mesh, bind_group));
+    }
+    casters
+}
+
+/// Draw casters from [`prepare_shadow_casters`], with the shadow view bound
+/// to group 0
+pub(crate) fn draw_shadow_casters(
+    render_pass: &mut wgpu::RenderPass,
+    casters: &[(Arc<Mesh>, wgpu::BindGroup)],
+) {
+    for (mesh, model_bind_group) in casters {
+        let vb_guard = mesh.vertex_buffer.read().unwrap();
+        let ib_guard = mesh.index_buffer.read().unwrap();
+        if let (Some(vb), Some(ib)) = (vb_guard.as_ref(), ib_guard.as_ref()) {
+            render_pass.set_bind_group(1, model_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, vb.slice(..));
+            render_pass.set_index_buffer(ib.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..mesh.indices.len() as u32, 0, 0..1);
+        }
+    }
+}
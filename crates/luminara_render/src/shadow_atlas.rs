@@ -0,0 +1,938 @@
+//! Shadow atlas for point and spot lights.
+//!
+//! Every frame the shadow-casting point and spot lights are ranked by how
+//! much of the screen their volume covers, and [`ShadowAtlas::allocate`]
+//! packs one square tile per shadow view into a single depth texture: six
+//! cube faces for a point light, one view for a spot light. More important
+//! lights get larger tiles; once the atlas is full, the remaining lights fall
+//! back to smaller tiles and finally go without shadows.
+//!
+//! Packing and the light view matrices are plain CPU code.
+//! [`ShadowAtlasNode`] renders the casters into the tiles, and the
+//! `luminara::shadows` shader module filters the atlas with the
+//! [`ShadowFilter`] and biases of each light's [`ShadowSettings`].
+
+use crate::render_graph::{slots, PassSlots, RenderContext, RenderNode};
+use crate::render_target::{collect_camera_views, CameraView};
+use crate::render_world::MainEntity;
+use crate::shadow::{
+    create_shadow_depth_pipeline, draw_shadow_casters, prepare_shadow_casters, CascadeUniform,
+};
+use crate::{Frustum, GpuContext, PointLight, RenderError, SpotLight};
+use luminara_asset::AssetServer;
+use luminara_core::shared_types::{Component, Query, Resource, World};
+use luminara_core::Entity;
+use luminara_math::{Mat4, Transform, Vec3, Vec4};
+use luminara_reflect_derive::Reflect;
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+
+/// Lights the atlas holds shadows for at most
+pub const MAX_SHADOW_LIGHTS: usize = 64;
+
+/// Shadow views of the atlas at most, enough for six faces per light
+pub const MAX_SHADOW_VIEWS: usize = MAX_SHADOW_LIGHTS * 6;
+
+/// How shadow map samples are filtered
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub enum ShadowFilter {
+    /// A single comparison sample
+    Hard,
+    /// Percentage-closer filtering over a `(2 * radius + 1)²` texel grid
+    Pcf { radius: u32 },
+    /// Percentage-closer soft shadows: blockers are searched for within
+    /// `search_radius` texels, and the penumbra of a light `light_size` world
+    /// units across is filtered with a `(2 * filter_radius + 1)²` sample grid
+    Pcss {
+        light_size: f32,
+        search_radius: u32,
+        filter_radius: u32,
+    },
+}
+
+impl ShadowFilter {
+    /// Filter kind as read by the shader: 0 hard, 1 PCF, 2 PCSS
+    pub fn kind(&self) -> u32 {
+        match self {
+            ShadowFilter::Hard => 0,
+            ShadowFilter::Pcf { .. } => 1,
+            ShadowFilter::Pcss { .. } => 2,
+        }
+    }
+}
+
+/// Shadow bias and filtering of a point or spot light. Lights without one
+/// use the defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct ShadowSettings {
+    /// World units a receiver moves towards the light before the depth
+    /// comparison
+    pub depth_bias: f32,
+    /// Shadow map texels a receiver moves along its normal before the depth
+    /// comparison, scaled down as the surface faces the light
+    pub normal_bias: f32,
+    /// Near plane of the shadow views
+    pub near: f32,
+    pub filter: ShadowFilter,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            depth_bias: 0.02,
+            normal_bias: 0.6,
+            near: 0.05,
+            filter: ShadowFilter::Pcf { radius: 1 },
+        }
+    }
+}
+
+impl Component for ShadowSettings {
+    fn type_name() -> &'static str {
+        "ShadowSettings"
+    }
+}
+
+/// Square region of the atlas, in texels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShadowTile {
+    pub x: u32,
+    pub y: u32,
+    pub size: u32,
+}
+
+impl ShadowTile {
+    /// `(min_u, min_v, max_u, max_v)` of the tile in an atlas `atlas_size`
+    /// texels wide
+    pub fn uv_rect(&self, atlas_size: u32) -> Vec4 {
+        let scale = 1.0 / atlas_size as f32;
+        Vec4::new(
+            self.x as f32,
+            self.y as f32,
+            (self.x + self.size) as f32,
+            (self.y + self.size) as f32,
+        ) * scale
+    }
+
+    pub fn overlaps(&self, other: &ShadowTile) -> bool {
+        self.x < other.x + other.size
+            && other.x < self.x + self.size
+            && self.y < other.y + other.size
+            && other.y < self.y + self.size
+    }
+}
+
+/// Quadtree allocator handing out power-of-two tiles of a square atlas.
+/// Free blocks are split into quarters on demand; freed tiles are not merged
+/// back, as the atlas is packed from scratch every frame.
+#[derive(Debug, Clone)]
+pub struct ShadowAtlasAllocator {
+    size: u32,
+    free: Vec<ShadowTile>,
+}
+
+impl ShadowAtlasAllocator {
+    /// Allocator over an atlas `size` texels wide, a power of two
+    pub fn new(size: u32) -> Self {
+        assert!(size.is_power_of_two(), "atlas size must be a power of two");
+        Self {
+            size,
+            free: vec![ShadowTile { x: 0, y: 0, size }],
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Texels not handed out
+    pub fn free_area(&self) -> u64 {
+        self.free
+            .iter()
+            .map(|tile| tile.size as u64 * tile.size as u64)
+            .sum()
+    }
+
+    /// A tile of at least `size` texels, rounded up to a power of two,
+    /// carved from the smallest free block that fits
+    pub fn allocate(&mut self, size: u32) -> Option<ShadowTile> {
+        let size = size.max(1).next_power_of_two();
+        let index = self
+            .free
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.size >= size)
+            .min_by_key(|(_, block)| (block.size, block.y, block.x))
+            .map(|(index, _)| index)?;
+        let mut block = self.free.swap_remove(index);
+        while block.size > size {
+            let half = block.size / 2;
+            for (dx, dy) in [(half, 0), (0, half), (half, half)] {
+                self.free.push(ShadowTile {
+                    x: block.x + dx,
+                    y: block.y + dy,
+                    size: half,
+                });
+            }
+            block.size = half;
+        }
+        Some(block)
+    }
+
+    /// Return a tile from [`allocate`](Self::allocate)
+    pub fn free(&mut self, tile: ShadowTile) {
+        self.free.push(tile);
+    }
+}
+
+/// Shape of a shadow-casting light
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShadowLightKind {
+    Point {
+        position: Vec3,
+        range: f32,
+    },
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        outer_angle: f32,
+        range: f32,
+    },
+}
+
+impl ShadowLightKind {
+    pub fn point(light: &PointLight, transform: &Transform) -> Self {
+        Self::Point {
+            position: transform.translation,
+            range: light.range,
+        }
+    }
+
+    pub fn spot(light: &SpotLight, transform: &Transform) -> Self {
+        Self::Spot {
+            position: transform.translation,
+            direction: transform.forward(),
+            outer_angle: light.outer_angle,
+            range: light.range,
+        }
+    }
+
+    /// Shadow views the light renders: six cube faces or a single view
+    pub fn view_count(&self) -> usize {
+        match self {
+            Self::Point { .. } => 6,
+            Self::Spot { .. } => 1,
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        match *self {
+            Self::Point { position, .. } | Self::Spot { position, .. } => position,
+        }
+    }
+
+    pub fn range(&self) -> f32 {
+        match *self {
+            Self::Point { range, .. } | Self::Spot { range, .. } => range,
+        }
+    }
+
+    /// Smallest sphere around the lit volume, as `(center, radius)`
+    pub fn bounding_sphere(&self) -> (Vec3, f32) {
+        match *self {
+            Self::Point { position, range } => (position, range),
+            Self::Spot {
+                position,
+                direction,
+                outer_angle,
+                range,
+            } => {
+                // A cone `range` long: wide cones are bounded by the circle
+                // at their base, narrow ones by the sphere through their tip
+                let angle = outer_angle.clamp(0.0, std::f32::consts::FRAC_PI_2);
+                if angle > std::f32::consts::FRAC_PI_4 {
+                    (
+                        position + direction * range * angle.cos(),
+                        range * angle.sin(),
+                    )
+                } else {
+                    let radius = range / (2.0 * angle.cos());
+                    (position + direction * radius, radius)
+                }
+            }
+        }
+    }
+
+    /// View-projection matrices of the light's shadow views
+    pub fn view_projs(&self, near: f32) -> Vec<Mat4> {
+        match *self {
+            Self::Point { position, range } => {
+                point_light_view_projs(position, near, range).to_vec()
+            }
+            Self::Spot {
+                position,
+                direction,
+                outer_angle,
+                range,
+            } => vec![spot_light_view_proj(
+                position,
+                direction,
+                outer_angle,
+                near,
+                range,
+            )],
+        }
+    }
+
+    /// Width of the shadow frustum one world unit in front of the light
+    pub fn frustum_width(&self) -> f32 {
+        match *self {
+            Self::Point { .. } => 2.0,
+            Self::Spot { outer_angle, .. } => 2.0 * spot_half_fov(outer_angle).tan(),
+        }
+    }
+
+    /// Largest fraction of a view the lit volume covers, the importance of
+    /// its shadows
+    pub fn importance(&self, views: &[CameraView]) -> f32 {
+        let (center, radius) = self.bounding_sphere();
+        views
+            .iter()
+            .map(|view| screen_coverage(&view.view_proj, view.position, center, radius))
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Projected radius of a sphere over half the height of a perspective view,
+/// clamped to 1. Spheres outside the view cover nothing, spheres around the
+/// camera the whole view.
+pub fn screen_coverage(view_proj: &Mat4, camera_position: Vec3, center: Vec3, radius: f32) -> f32 {
+    if !Frustum::from_view_projection(view_proj).intersects_sphere(center, radius) {
+        return 0.0;
+    }
+    let distance = camera_position.distance(center);
+    if distance <= radius {
+        return 1.0;
+    }
+    // The second row of the view-projection's rotation part has the length
+    // of the projection's vertical scale, `1 / tan(fov_y / 2)`
+    let scale = Vec3::new(view_proj.x_axis.y, view_proj.y_axis.y, view_proj.z_axis.y).length();
+    (radius * scale / distance).min(1.0)
+}
+
+/// Forward and up directions of the six cube faces, in the `+X, -X, +Y, -Y,
+/// +Z, -Z` order of cube map layers
+pub const CUBE_FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::NEG_Y),
+    (Vec3::NEG_X, Vec3::NEG_Y),
+    (Vec3::Y, Vec3::Z),
+    (Vec3::NEG_Y, Vec3::NEG_Z),
+    (Vec3::Z, Vec3::NEG_Y),
+    (Vec3::NEG_Z, Vec3::NEG_Y),
+];
+
+/// View-projections of the six 90° cube faces around a point light
+pub fn point_light_view_projs(position: Vec3, near: f32, far: f32) -> [Mat4; 6] {
+    let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, near, far);
+    CUBE_FACES.map(|(forward, up)| projection * Mat4::look_to_rh(position, forward, up))
+}
+
+/// Index into [`CUBE_FACES`] of the face `direction` points through
+pub fn point_light_face(direction: Vec3) -> usize {
+    let abs = direction.abs();
+    if abs.x >= abs.y && abs.x >= abs.z {
+        if direction.x >= 0.0 {
+            0
+        } else {
+            1
+        }
+    } else if abs.y >= abs.z {
+        if direction.y >= 0.0 {
+            2
+        } else {
+            3
+        }
+    } else if direction.z >= 0.0 {
+        4
+    } else {
+        5
+    }
+}
+
+/// View-projection of a spot light's cone
+pub fn spot_light_view_proj(
+    position: Vec3,
+    direction: Vec3,
+    outer_angle: f32,
+    near: f32,
+    far: f32,
+) -> Mat4 {
+    let direction = direction.normalize();
+    let up = if direction.y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    };
+    let projection = Mat4::perspective_rh(2.0 * spot_half_fov(outer_angle), 1.0, near, far);
+    projection * Mat4::look_to_rh(position, direction, up)
+}
+
+/// Cones wider than this are rendered into a view that just fits 170°
+fn spot_half_fov(outer_angle: f32) -> f32 {
+    outer_angle.clamp(0.01, 85f32.to_radians())
+}
+
+/// A light asking for shadows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowRequest {
+    pub light: Entity,
+    pub kind: ShadowLightKind,
+    pub settings: ShadowSettings,
+    pub importance: f32,
+}
+
+/// One view of a light's shadows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowView {
+    pub tile: ShadowTile,
+    pub view_proj: Mat4,
+}
+
+/// Atlas tiles of a light's shadows
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowAllocation {
+    pub light: Entity,
+    pub kind: ShadowLightKind,
+    pub settings: ShadowSettings,
+    pub importance: f32,
+    /// One per [`ShadowLightKind::view_count`]
+    pub views: Vec<ShadowView>,
+}
+
+impl ShadowAllocation {
+    /// Side of the light's tiles, in texels
+    pub fn tile_size(&self) -> u32 {
+        self.views.first().map_or(0, |view| view.tile.size)
+    }
+}
+
+/// Point and spot light shadow atlas configuration, and this frame's
+/// allocations
+pub struct ShadowAtlas {
+    /// Side of the atlas texture, a power of two
+    pub size: u32,
+    /// Tile side of a light covering the whole screen
+    pub max_tile_size: u32,
+    /// Smallest tile side; lights that do not fit at it get no shadows
+    pub min_tile_size: u32,
+    /// Lights with shadows this frame, most important first
+    pub allocations: Vec<ShadowAllocation>,
+}
+
+impl Default for ShadowAtlas {
+    fn default() -> Self {
+        Self {
+            size: 4096,
+            max_tile_size: 1024,
+            min_tile_size: 64,
+            allocations: Vec::new(),
+        }
+    }
+}
+
+impl Resource for ShadowAtlas {}
+
+impl ShadowAtlas {
+    /// Tile side for a light of `importance`: the largest power of two
+    /// within `max_tile_size * importance`, kept between the min and max
+    pub fn tile_size(&self, importance: f32) -> u32 {
+        let min = self.min_tile_size.max(1).next_power_of_two();
+        let max = prev_power_of_two(self.max_tile_size.min(self.size)).max(min);
+        let wanted = (self.max_tile_size as f32 * importance.clamp(0.0, 1.0)) as u32;
+        prev_power_of_two(wanted).clamp(min, max)
+    }
+
+    /// Pack the shadow views of `requests` into the atlas, most important
+    /// first. Lights that cover nothing get no shadows.
+    pub fn allocate(&mut self, requests: &[ShadowRequest]) {
+        let mut requests: Vec<&ShadowRequest> = requests
+            .iter()
+            .filter(|request| request.importance > 0.0)
+            .collect();
+        requests.sort_by(|a, b| b.importance.total_cmp(&a.importance));
+
+        let mut allocator = ShadowAtlasAllocator::new(self.size);
+        let min_size = self.tile_size(0.0);
+        self.allocations.clear();
+        for request in requests {
+            if self.allocations.len() == MAX_SHADOW_LIGHTS {
+                break;
+            }
+            let mut size = self.tile_size(request.importance);
+            let tiles = loop {
+                if let Some(tiles) = allocate_tiles(&mut allocator, size, request.kind.view_count())
+                {
+                    break Some(tiles);
+                }
+                if size <= min_size {
+                    break None;
+                }
+                size /= 2;
+            };
+            let Some(tiles) = tiles else {
+                continue;
+            };
+            let views = tiles
+                .into_iter()
+                .zip(request.kind.view_projs(request.settings.near))
+                .map(|(tile, view_proj)| ShadowView { tile, view_proj })
+                .collect();
+            self.allocations.push(ShadowAllocation {
+                light: request.light,
+                kind: request.kind,
+                settings: request.settings,
+                importance: request.importance,
+                views,
+            });
+        }
+    }
+
+    /// Index of `light`'s shadows in the atlas light buffer
+    pub fn light_index(&self, light: Entity) -> Option<u32> {
+        self.allocations
+            .iter()
+            .position(|allocation| allocation.light == light)
+            .map(|index| index as u32)
+    }
+
+    pub fn allocation(&self, light: Entity) -> Option<&ShadowAllocation> {
+        self.allocations
+            .iter()
+            .find(|allocation| allocation.light == light)
+    }
+
+    /// Every allocated view, in light order
+    pub fn views(&self) -> impl Iterator<Item = &ShadowView> {
+        self.allocations
+            .iter()
+            .flat_map(|allocation| &allocation.views)
+    }
+
+    /// Lights and views as laid out in the atlas storage buffers
+    pub fn gpu_data(&self) -> (Vec<GpuShadowLight>, Vec<GpuShadowView>) {
+        let mut lights = Vec::with_capacity(self.allocations.len());
+        let mut views = Vec::new();
+        for allocation in &self.allocations {
+            let settings = &allocation.settings;
+            let (filter_radius, search_radius, light_size) = match settings.filter {
+                ShadowFilter::Hard => (0, 0, 0.0),
+                ShadowFilter::Pcf { radius } => (radius, 0, 0.0),
+                ShadowFilter::Pcss {
+                    light_size,
+                    search_radius,
+                    filter_radius,
+                } => (filter_radius, search_radius, light_size),
+            };
+            let position = allocation.kind.position();
+            lights.push(GpuShadowLight {
+                position_near: [position.x, position.y, position.z, settings.near],
+                params: [
+                    allocation.kind.range(),
+                    settings.depth_bias,
+                    settings.normal_bias,
+                    light_size,
+                ],
+                first_view: views.len() as u32,
+                view_count: allocation.views.len() as u32,
+                filter_kind: settings.filter.kind(),
+                filter_radius,
+                search_radius,
+                frustum_width: allocation.kind.frustum_width(),
+                _padding: [0; 2],
+            });
+            views.extend(allocation.views.iter().map(|view| GpuShadowView {
+                view_proj: view.view_proj.to_cols_array_2d(),
+                uv_rect: view.tile.uv_rect(self.size).to_array(),
+            }));
+        }
+        (lights, views)
+    }
+}
+
+fn prev_power_of_two(value: u32) -> u32 {
+    if value == 0 {
+        0
+    } else {
+        1 << (31 - value.leading_zeros())
+    }
+}
+
+/// `count` tiles of `size`, or none if they do not all fit
+fn allocate_tiles(
+    allocator: &mut ShadowAtlasAllocator,
+    size: u32,
+    count: usize,
+) -> Option<Vec<ShadowTile>> {
+    let mut tiles = Vec::with_capacity(count);
+    for _ in 0..count {
+        match allocator.allocate(size) {
+            Some(tile) => tiles.push(tile),
+            None => {
+                for tile in tiles {
+                    allocator.free(tile);
+                }
+                return None;
+            }
+        }
+    }
+    Some(tiles)
+}
+
+/// A light of the atlas light buffer
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuShadowLight {
+    /// xyz: position, w: near plane
+    pub position_near: [f32; 4],
+    /// x: far plane, y: depth bias, z: normal bias, w: PCSS light size
+    pub params: [f32; 4],
+    pub first_view: u32,
+    pub view_count: u32,
+    /// [`ShadowFilter::kind`]
+    pub filter_kind: u32,
+    pub filter_radius: u32,
+    pub search_radius: u32,
+    /// [`ShadowLightKind::frustum_width`]
+    pub frustum_width: f32,
+    pub _padding: [u32; 2],
+}
+
+/// A view of the atlas view buffer
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuShadowView {
+    pub view_proj: [[f32; 4]; 4],
+    /// [`ShadowTile::uv_rect`]
+    pub uv_rect: [f32; 4],
+}
+
+/// Atlas texture, light and view buffers, and the bind group shaders sample
+/// them through: atlas, comparison sampler, lights, views
+#[derive(Default)]
+pub struct ShadowAtlasResources {
+    pub texture: Option<wgpu::Texture>,
+    pub view: Option<wgpu::TextureView>,
+    pub sampler: Option<wgpu::Sampler>,
+    pub light_buffer: Option<wgpu::Buffer>,
+    pub view_buffer: Option<wgpu::Buffer>,
+    pub bind_group: Option<wgpu::BindGroup>,
+    pub bind_group_layout: Option<wgpu::BindGroupLayout>,
+    /// Depth-only pipeline rendering casters into a tile
+    pub depth_pipeline: Option<wgpu::RenderPipeline>,
+    pub depth_layouts: Option<[wgpu::BindGroupLayout; 2]>,
+}
+
+impl Resource for ShadowAtlasResources {}
+
+impl ShadowAtlasResources {
+    /// Create the atlas texture, or recreate it after the atlas size changed
+    pub fn initialize(&mut self, device: &wgpu::Device, config: &ShadowAtlas) {
+        let size = self.texture.as_ref().map(|texture| texture.width());
+        if size == Some(config.size) {
+            return;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Atlas Texture"),
+            size: wgpu::Extent3d {
+                width: config.size,
+                height: config.size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = self.sampler.take().unwrap_or_else(|| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Shadow Atlas Sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                compare: Some(wgpu::CompareFunction::LessEqual),
+                ..Default::default()
+            })
+        });
+        let storage = |label, size: usize| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let light_buffer = self.light_buffer.take().unwrap_or_else(|| {
+            storage(
+                "Shadow Atlas Lights",
+                MAX_SHADOW_LIGHTS * std::mem::size_of::<GpuShadowLight>(),
+            )
+        });
+        let view_buffer = self.view_buffer.take().unwrap_or_else(|| {
+            storage(
+                "Shadow Atlas Views",
+                MAX_SHADOW_VIEWS * std::mem::size_of::<GpuShadowView>(),
+            )
+        });
+        let layout = self
+            .bind_group_layout
+            .take()
+            .unwrap_or_else(|| shadow_atlas_layout(device));
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Atlas Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: light_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: view_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        if self.depth_pipeline.is_none() {
+            let (pipeline, layouts) = create_shadow_depth_pipeline(device, 2.0);
+            self.depth_pipeline = Some(pipeline);
+            self.depth_layouts = Some(layouts);
+        }
+        self.texture = Some(texture);
+        self.view = Some(view);
+        self.sampler = Some(sampler);
+        self.light_buffer = Some(light_buffer);
+        self.view_buffer = Some(view_buffer);
+        self.bind_group = Some(bind_group);
+        self.bind_group_layout = Some(layout);
+    }
+
+    /// Upload the lights and views of `atlas`
+    pub fn update_buffers(&self, queue: &wgpu::Queue, atlas: &ShadowAtlas) {
+        let (Some(light_buffer), Some(view_buffer)) = (&self.light_buffer, &self.view_buffer)
+        else {
+            return;
+        };
+        let (lights, views) = atlas.gpu_data();
+        if !lights.is_empty() {
+            queue.write_buffer(light_buffer, 0, bytemuck::cast_slice(&lights));
+            queue.write_buffer(view_buffer, 0, bytemuck::cast_slice(&views));
+        }
+    }
+}
+
+/// Layout of the atlas bind group, as declared by `luminara::shadows` users
+fn shadow_atlas_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let storage = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Shadow Atlas Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None,
+            },
+            storage(2),
+            storage(3),
+        ],
+    })
+}
+
+/// Shadow requests of the shadow-casting point and spot lights in `world`,
+/// ranked against `views`. Requests name the main world lights.
+pub fn collect_shadow_requests(world: &World, views: &[CameraView]) -> Vec<ShadowRequest> {
+    let request = |entity: Entity, kind: ShadowLightKind| ShadowRequest {
+        light: world
+            .get_component::<MainEntity>(entity)
+            .map_or(entity, |main| main.0),
+        kind,
+        settings: world
+            .get_component::<ShadowSettings>(entity)
+            .copied()
+            .unwrap_or_default(),
+        importance: kind.importance(views),
+    };
+
+    let mut requests: Vec<ShadowRequest> = Query::<(Entity, &PointLight, &Transform)>::new(world)
+        .iter()
+        .filter(|(_, light, _)| light.cast_shadows)
+        .map(|(entity, light, transform)| request(entity, ShadowLightKind::point(light, transform)))
+        .collect();
+    requests.extend(
+        Query::<(Entity, &SpotLight, &Transform)>::new(world)
+            .iter()
+            .filter(|(_, light, _)| light.cast_shadows)
+            .map(|(entity, light, transform)| {
+                request(entity, ShadowLightKind::spot(light, transform))
+            }),
+    );
+    requests
+}
+
+/// Packs the atlas for this frame's lights and cameras and uploads its
+/// buffers
+pub fn update_shadow_atlas_system(world: &mut World) {
+    let views = {
+        let Some(gpu) = world.get_resource::<GpuContext>() else {
+            return;
+        };
+        collect_camera_views(world, (gpu.surface_config.width, gpu.surface_config.height))
+    };
+    let requests = collect_shadow_requests(world, &views);
+
+    let (Some(gpu), Some(mut atlas), Some(mut resources)) = (
+        world.get_resource::<GpuContext>(),
+        world.get_resource_mut::<ShadowAtlas>(),
+        world.get_resource_mut::<ShadowAtlasResources>(),
+    ) else {
+        return;
+    };
+    resources.initialize(&gpu.device, &atlas);
+    atlas.allocate(&requests);
+    resources.update_buffers(&gpu.queue, &atlas);
+}
+
+/// Renders shadow casters into every tile of the shadow atlas
+pub struct ShadowAtlasNode;
+
+impl ShadowAtlasNode {
+    pub const NAME: &'static str = "shadow_atlas";
+}
+
+impl RenderNode for ShadowAtlasNode {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn declare(&self, slots: &mut PassSlots) {
+        slots.write(slots::SHADOW_ATLAS);
+    }
+
+    fn run<'a>(&self, context: &mut RenderContext<'a>) -> Result<(), RenderError> {
+        let Some(world) = context.world else {
+            return Ok(());
+        };
+        let (Some(atlas), Some(resources), Some(asset_server)) = (
+            world.get_resource::<ShadowAtlas>(),
+            world.get_resource::<ShadowAtlasResources>(),
+            world.get_resource::<AssetServer>(),
+        ) else {
+            return Ok(());
+        };
+        let (Some(view), Some(pipeline), Some([view_layout, model_layout])) = (
+            &resources.view,
+            &resources.depth_pipeline,
+            &resources.depth_layouts,
+        ) else {
+            return Ok(());
+        };
+        if atlas.allocations.is_empty() {
+            return Ok(());
+        }
+        let device = context.device;
+
+        let casters = prepare_shadow_casters(world, &asset_server, device, model_layout);
+        let views: Vec<(ShadowTile, wgpu::BindGroup)> = atlas
+            .views()
+            .map(|shadow_view| {
+                let uniform = CascadeUniform {
+                    view_proj: shadow_view.view_proj.to_cols_array_2d(),
+                    split_depth: 0.0,
+                    blend_start: 0.0,
+                    blend_end: 0.0,
+                    _padding: 0.0,
+                };
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Shadow Atlas View Uniform"),
+                    contents: bytemuck::bytes_of(&uniform),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Shadow Atlas View Bind Group"),
+                    layout: view_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }],
+                });
+                (shadow_view.tile, bind_group)
+            })
+            .collect();
+
+        let mut render_pass = context
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Atlas Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+        render_pass.set_pipeline(pipeline);
+        for (tile, bind_group) in &views {
+            render_pass.set_viewport(
+                tile.x as f32,
+                tile.y as f32,
+                tile.size as f32,
+                tile.size as f32,
+                0.0,
+                1.0,
+            );
+            render_pass.set_scissor_rect(tile.x, tile.y, tile.size, tile.size);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            draw_shadow_casters(&mut render_pass, &casters);
+        }
+        Ok(())
+    }
+}
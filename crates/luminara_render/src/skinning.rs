@@ -0,0 +1,437 @@
+//! Joint palettes and mesh deformation.
+//!
+//! [`joint_palette_system`] turns the joints of every [`SkinnedMesh`] into a
+//! [`JointPalette`]. The GPU reads the same palette through the
+//! `luminara::skinning` shader module; [`deform_mesh`] applies it on the CPU,
+//! together with morph targets, for picking, physics and tests.
+
+use crate::animation::SkinnedMesh;
+use crate::components::MorphWeights;
+use crate::mesh::{Mesh, MorphTarget, SkinWeights, Vertex, AABB};
+use bytemuck::{Pod, Zeroable};
+use luminara_asset::{AssetServer, Handle};
+use luminara_core::shared_types::{App, AppInterface, CoreStage, Plugin, Query, World};
+use luminara_core::system::ExclusiveMarker;
+use luminara_core::{Component, Entity};
+use luminara_math::algebra::DualQuat;
+use luminara_math::glam::Mat3;
+use luminara_math::{Mat4, Quat, Transform, Vec3};
+use luminara_scene::GlobalTransform;
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+
+/// How joint influences are combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkinningMethod {
+    /// Blend joint matrices; cheap, but volume collapses at twisting joints
+    #[default]
+    Linear,
+    /// Blend rigid joint transforms as dual quaternions; keeps volume but
+    /// ignores joint scale
+    DualQuaternion,
+}
+
+impl SkinningMethod {
+    /// Shader definition selecting this method in `luminara::skinning`
+    pub fn shader_def(&self) -> Option<&'static str> {
+        match self {
+            SkinningMethod::Linear => None,
+            SkinningMethod::DualQuaternion => Some("DUAL_QUAT_SKINNING"),
+        }
+    }
+}
+
+/// One palette entry as laid out in the GPU storage buffer
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct GpuSkinJoint {
+    pub matrix: [[f32; 4]; 4],
+    pub real: [f32; 4],
+    pub dual: [f32; 4],
+}
+
+/// Per-joint skinning transforms of one skinned mesh, relative to the mesh
+/// entity, as both matrices and dual quaternions
+#[derive(Debug, Clone, Default)]
+pub struct JointPalette {
+    pub matrices: Vec<Mat4>,
+    pub dual_quats: Vec<DualQuat>,
+}
+
+impl Component for JointPalette {
+    fn type_name() -> &'static str {
+        "JointPalette"
+    }
+}
+
+impl JointPalette {
+    /// Palette for joints at `joint_globals` bound by `inverse_bind_matrices`,
+    /// for a mesh drawn with `mesh_global`. Joints without an inverse bind
+    /// matrix use the identity.
+    pub fn compute(
+        mesh_global: Mat4,
+        joint_globals: &[Mat4],
+        inverse_bind_matrices: &[Mat4],
+    ) -> Self {
+        let to_mesh = mesh_global.inverse();
+        let matrices: Vec<Mat4> = joint_globals
+            .iter()
+            .enumerate()
+            .map(|(i, joint)| {
+                let inverse_bind = inverse_bind_matrices
+                    .get(i)
+                    .copied()
+                    .unwrap_or(Mat4::IDENTITY);
+                to_mesh * *joint * inverse_bind
+            })
+            .collect();
+        let dual_quats = matrices
+            .iter()
+            .map(|matrix| {
+                let (_, rotation, translation) = matrix.to_scale_rotation_translation();
+                DualQuat::from_rotation_translation(rotation, translation)
+            })
+            .collect();
+        Self {
+            matrices,
+            dual_quats,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.matrices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matrices.is_empty()
+    }
+
+    /// Entries in the layout of the `joint_palette` shader binding
+    pub fn gpu_joints(&self) -> Vec<GpuSkinJoint> {
+        self.matrices
+            .iter()
+            .zip(&self.dual_quats)
+            .map(|(matrix, dq)| GpuSkinJoint {
+                matrix: matrix.to_cols_array_2d(),
+                real: dq.real.to_array(),
+                dual: dq.dual.to_array(),
+            })
+            .collect()
+    }
+
+    /// Storage buffer holding [`Self::gpu_joints`]
+    pub fn create_buffer(&self, device: &wgpu::Device) -> wgpu::Buffer {
+        // Storage buffers can't be empty
+        let mut joints = self.gpu_joints();
+        if joints.is_empty() {
+            joints.push(GpuSkinJoint::zeroed());
+        }
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Joint Palette"),
+            contents: bytemuck::cast_slice(&joints),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        })
+    }
+
+    /// Update a buffer from [`Self::create_buffer`] with the same joint count
+    pub fn write_buffer(&self, queue: &wgpu::Queue, buffer: &wgpu::Buffer) {
+        queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.gpu_joints()));
+    }
+
+    /// Layout entry of the `joint_palette` binding
+    pub fn bind_group_layout_entry() -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<GpuSkinJoint>() as u64),
+            },
+            count: None,
+        }
+    }
+
+    fn blend_matrix(&self, skin: &SkinWeights) -> Mat4 {
+        let mut matrix = Mat4::ZERO;
+        for (&joint, &weight) in skin.joints.iter().zip(&skin.weights) {
+            if weight > 0.0 {
+                let joint = self
+                    .matrices
+                    .get(joint as usize)
+                    .copied()
+                    .unwrap_or(Mat4::IDENTITY);
+                matrix += joint * weight;
+            }
+        }
+        matrix
+    }
+
+    fn blend_dual_quat(&self, skin: &SkinWeights) -> DualQuat {
+        let dual_quat = |joint: u16| {
+            self.dual_quats
+                .get(joint as usize)
+                .copied()
+                .unwrap_or(DualQuat::IDENTITY)
+        };
+        let pivot = dual_quat(skin.joints[0]).real;
+
+        let zero = Quat::from_xyzw(0.0, 0.0, 0.0, 0.0);
+        let mut blended = DualQuat::new(zero, zero);
+        for (&joint, &weight) in skin.joints.iter().zip(&skin.weights) {
+            if weight <= 0.0 {
+                continue;
+            }
+            let dq = dual_quat(joint);
+            // Stay in the pivot's hemisphere so rotations blend the short way
+            let weight = if dq.real.dot(pivot) < 0.0 {
+                -weight
+            } else {
+                weight
+            };
+            blended.real = blended.real + dq.real * weight;
+            blended.dual = blended.dual + dq.dual * weight;
+        }
+        blended.normalize()
+    }
+}
+
+/// Skin one vertex. Tangent handedness is kept.
+pub fn skin_vertex(
+    vertex: &Vertex,
+    skin: &SkinWeights,
+    palette: &JointPalette,
+    method: SkinningMethod,
+) -> Vertex {
+    let position = Vec3::from(vertex.position);
+    let normal = Vec3::from(vertex.normal);
+    let tangent = Vec3::from_slice(&vertex.tangent[..3]);
+
+    let (position, normal, tangent) = match method {
+        SkinningMethod::Linear => {
+            let matrix = palette.blend_matrix(skin);
+            (
+                matrix.transform_point3(position),
+                normal_matrix(matrix) * normal,
+                matrix.transform_vector3(tangent),
+            )
+        }
+        SkinningMethod::DualQuaternion => {
+            let dq = palette.blend_dual_quat(skin);
+            (
+                dq.transform_point(position),
+                dq.real * normal,
+                dq.real * tangent,
+            )
+        }
+    };
+
+    Vertex {
+        position: position.to_array(),
+        normal: normal.normalize_or_zero().to_array(),
+        uv: vertex.uv,
+        tangent: tangent
+            .normalize_or_zero()
+            .extend(vertex.tangent[3])
+            .to_array(),
+    }
+}
+
+/// Inverse-transpose of the linear part of `matrix` up to a positive factor,
+/// which keeps normals perpendicular under non-uniform scale
+fn normal_matrix(matrix: Mat4) -> Mat3 {
+    let (x, y, z) = (
+        matrix.x_axis.truncate(),
+        matrix.y_axis.truncate(),
+        matrix.z_axis.truncate(),
+    );
+    let cofactor = Mat3::from_cols(y.cross(z), z.cross(x), x.cross(y));
+    cofactor * x.dot(y.cross(z)).signum()
+}
+
+/// Skin `vertices` with their parallel `skin_weights`
+pub fn skin_vertices(
+    vertices: &[Vertex],
+    skin_weights: &[SkinWeights],
+    palette: &JointPalette,
+    method: SkinningMethod,
+) -> Vec<Vertex> {
+    vertices
+        .iter()
+        .zip(skin_weights)
+        .map(|(vertex, skin)| skin_vertex(vertex, skin, palette, method))
+        .collect()
+}
+
+/// Add the morph target offsets of `targets` scaled by `weights`
+pub fn morph_vertices(
+    vertices: &[Vertex],
+    targets: &[MorphTarget],
+    weights: &[f32],
+) -> Vec<Vertex> {
+    let mut vertices = vertices.to_vec();
+    let mut renormalize = false;
+    for (target, &weight) in targets.iter().zip(weights) {
+        if weight == 0.0 {
+            continue;
+        }
+        for (vertex, offset) in vertices.iter_mut().zip(&target.positions) {
+            add_scaled(&mut vertex.position, offset, weight);
+        }
+        for (vertex, offset) in vertices.iter_mut().zip(&target.normals) {
+            add_scaled(&mut vertex.normal, offset, weight);
+            renormalize = true;
+        }
+        for (vertex, offset) in vertices.iter_mut().zip(&target.tangents) {
+            let mut tangent = [vertex.tangent[0], vertex.tangent[1], vertex.tangent[2]];
+            add_scaled(&mut tangent, offset, weight);
+            vertex.tangent[..3].copy_from_slice(&tangent);
+            renormalize = true;
+        }
+    }
+
+    if renormalize {
+        for vertex in &mut vertices {
+            vertex.normal = Vec3::from(vertex.normal).normalize_or_zero().to_array();
+            let tangent = Vec3::from_slice(&vertex.tangent[..3]).normalize_or_zero();
+            vertex.tangent[..3].copy_from_slice(&tangent.to_array());
+        }
+    }
+    vertices
+}
+
+fn add_scaled(value: &mut [f32; 3], offset: &[f32; 3], weight: f32) {
+    for (v, o) in value.iter_mut().zip(offset) {
+        *v += o * weight;
+    }
+}
+
+/// Vertices of `mesh` after morph targets, then skinning when a palette is
+/// given, in the mesh entity's space
+pub fn deform_mesh(
+    mesh: &Mesh,
+    morph_weights: &[f32],
+    skin: Option<(&JointPalette, SkinningMethod)>,
+) -> Vec<Vertex> {
+    let vertices = morph_vertices(&mesh.vertices, &mesh.morph_targets, morph_weights);
+    match skin {
+        Some((palette, method)) if mesh.skin_weights.len() == vertices.len() => {
+            skin_vertices(&vertices, &mesh.skin_weights, palette, method)
+        }
+        _ => vertices,
+    }
+}
+
+/// CPU copy of an entity's deformed mesh. Add it to opt in;
+/// [`cpu_deformation_system`] keeps it up to date.
+#[derive(Debug, Clone)]
+pub struct DeformedMesh {
+    pub vertices: Vec<Vertex>,
+    pub aabb: AABB,
+}
+
+impl Default for DeformedMesh {
+    fn default() -> Self {
+        Self {
+            vertices: Vec::new(),
+            aabb: AABB::from_vertices(&[]),
+        }
+    }
+}
+
+impl Component for DeformedMesh {
+    fn type_name() -> &'static str {
+        "DeformedMesh"
+    }
+}
+
+pub(crate) fn global_matrix(world: &World, entity: Entity) -> Mat4 {
+    if let Some(global) = world.get_component::<GlobalTransform>(entity) {
+        global.matrix()
+    } else {
+        world
+            .get_component::<Transform>(entity)
+            .map(|t| t.to_matrix())
+            .unwrap_or(Mat4::IDENTITY)
+    }
+}
+
+/// Recompute the [`JointPalette`] of every [`SkinnedMesh`] from the global
+/// transforms of its joints
+pub fn joint_palette_system(world: &mut World) {
+    let skinned: Vec<Entity> = Query::<(Entity, &SkinnedMesh)>::new(&*world)
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in skinned {
+        let Some(skin) = world.get_component::<SkinnedMesh>(entity) else {
+            continue;
+        };
+        let joint_globals: Vec<Mat4> = skin
+            .joints
+            .iter()
+            .map(|&joint| global_matrix(world, joint))
+            .collect();
+        let palette = JointPalette::compute(
+            global_matrix(world, entity),
+            &joint_globals,
+            &skin.inverse_bind_matrices,
+        );
+
+        if let Some(existing) = world.get_component_mut::<JointPalette>(entity) {
+            *existing = palette;
+        } else {
+            let _ = world.add_component(entity, palette);
+        }
+    }
+}
+
+/// Fill the [`DeformedMesh`] of entities that have one
+pub fn cpu_deformation_system(world: &mut World) {
+    let entities: Vec<(Entity, Handle<Mesh>)> =
+        Query::<(Entity, &DeformedMesh, &Handle<Mesh>)>::new(&*world)
+            .iter()
+            .map(|(entity, _, mesh)| (entity, mesh.clone()))
+            .collect();
+
+    for (entity, handle) in entities {
+        let Some(mesh) = world
+            .get_resource::<AssetServer>()
+            .and_then(|asset_server| asset_server.get(&handle))
+        else {
+            continue;
+        };
+
+        let weights = world
+            .get_component::<MorphWeights>(entity)
+            .map(|m| m.weights.clone())
+            .unwrap_or_default();
+        let method = world
+            .get_component::<SkinnedMesh>(entity)
+            .map(|skin| skin.method);
+        let palette = world.get_component::<JointPalette>(entity);
+        let skin = palette.zip(method);
+
+        let vertices = deform_mesh(&mesh, &weights, skin);
+        if let Some(deformed) = world.get_component_mut::<DeformedMesh>(entity) {
+            deformed.aabb = AABB::from_vertices(&vertices);
+            deformed.vertices = vertices;
+        }
+    }
+}
+
+/// Computes joint palettes and CPU-deformed meshes once transforms have
+/// been propagated
+pub struct SkinningPlugin;
+
+impl Plugin for SkinningPlugin {
+    fn name(&self) -> &str {
+        "SkinningPlugin"
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_system::<ExclusiveMarker>(CoreStage::PreRender, joint_palette_system);
+        app.add_system::<ExclusiveMarker>(CoreStage::PreRender, cpu_deformation_system);
+    }
+}
@@ -0,0 +1,289 @@
+//! CPU occlusion culling against a hierarchical depth buffer.
+//!
+//! Occluders, usually a coarse LOD of large meshes, are rasterized four
+//! pixels at a time into a small depth buffer, which is then reduced into a
+//! Hi-Z pyramid whose texels keep the farthest depth beneath them. A bounding
+//! box is tested against the few texels of the level where its screen
+//! rectangle spans at most two of them: when its nearest point lies behind
+//! all of them, the occluders cover it. Unlike the GPU queries of
+//! [`OcclusionCullingSystem`](crate::OcclusionCullingSystem), results are
+//! ready in the frame they are asked for.
+
+use crate::mesh::{Mesh, AABB};
+use luminara_asset::Handle;
+use luminara_core::shared_types::Component;
+use luminara_math::{Mat4, Vec3, Vec4};
+use wide::{f32x4, CmpGe};
+
+/// Marks a mesh entity as hiding what is behind it
+#[derive(Debug, Clone, Default)]
+pub struct Occluder {
+    /// Stand-in rasterized instead of the entity's mesh. Without one, the
+    /// coarsest level of the entity's [`Lod`](crate::Lod) is used, or else
+    /// the mesh itself.
+    pub mesh: Option<Handle<Mesh>>,
+}
+
+impl Occluder {
+    pub fn with_mesh(mesh: Handle<Mesh>) -> Self {
+        Self { mesh: Some(mesh) }
+    }
+}
+
+impl Component for Occluder {
+    fn type_name() -> &'static str {
+        "Occluder"
+    }
+}
+
+/// Clip space `w` below which a point counts as on the camera plane
+const MIN_W: f32 = 1e-5;
+
+struct DepthLevel {
+    width: usize,
+    height: usize,
+    depth: Vec<f32>,
+}
+
+impl DepthLevel {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            depth: vec![1.0; width * height],
+        }
+    }
+}
+
+/// Depth buffer of occluders with its Hi-Z pyramid. Depths follow the
+/// camera's clip space, 0 at the near plane and 1 at the far plane.
+pub struct OcclusionBuffer {
+    /// Level 0 is the rasterized buffer; each next level halves the last
+    levels: Vec<DepthLevel>,
+    view_proj: Mat4,
+}
+
+impl OcclusionBuffer {
+    /// The width is rounded up to a multiple of four for the rasterizer
+    pub fn new(width: u32, height: u32) -> Self {
+        let width = (width.max(1) as usize).next_multiple_of(4);
+        let height = height.max(1) as usize;
+        let mut levels = vec![DepthLevel::new(width, height)];
+        loop {
+            let last = levels.last().unwrap();
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let (width, height) = (last.width.div_ceil(2), last.height.div_ceil(2));
+            levels.push(DepthLevel::new(width, height));
+        }
+        Self {
+            levels,
+            view_proj: Mat4::IDENTITY,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.levels[0].width as u32, self.levels[0].height as u32)
+    }
+
+    pub fn view_proj(&self) -> Mat4 {
+        self.view_proj
+    }
+
+    /// Reset every depth to the far plane for a view
+    pub fn clear(&mut self, view_proj: Mat4) {
+        self.view_proj = view_proj;
+        for level in &mut self.levels {
+            level.depth.fill(1.0);
+        }
+    }
+
+    /// Rasterized depth at pixel `(x, y)`, top-left origin
+    pub fn depth(&self, x: u32, y: u32) -> f32 {
+        let level = &self.levels[0];
+        level.depth[y as usize * level.width + x as usize]
+    }
+
+    /// Rasterize both faces of every triangle of `mesh` placed by `model`.
+    /// Returns how many triangles reached the near side of the camera.
+    pub fn rasterize_mesh(&mut self, mesh: &Mesh, model: &Mat4) -> usize {
+        let to_clip = self.view_proj * *model;
+        let clip: Vec<Vec4> = mesh
+            .vertices
+            .iter()
+            .map(|vertex| to_clip * Vec3::from(vertex.position).extend(1.0))
+            .collect();
+        mesh.indices
+            .chunks_exact(3)
+            .filter(|triangle| {
+                let corners = [
+                    clip[triangle[0] as usize],
+                    clip[triangle[1] as usize],
+                    clip[triangle[2] as usize],
+                ];
+                self.rasterize_clipped(corners)
+            })
+            .count()
+    }
+
+    /// Rebuild the pyramid levels from the rasterized depth
+    pub fn build_pyramid(&mut self) {
+        for i in 1..self.levels.len() {
+            let (finer, coarser) = self.levels.split_at_mut(i);
+            let src = &finer[i - 1];
+            let dst = &mut coarser[0];
+            for y in 0..dst.height {
+                let rows = [2 * y, (2 * y + 1).min(src.height - 1)];
+                for x in 0..dst.width {
+                    let columns = [2 * x, (2 * x + 1).min(src.width - 1)];
+                    let mut farthest = 0.0f32;
+                    for row in rows {
+                        for column in columns {
+                            farthest = farthest.max(src.depth[row * src.width + column]);
+                        }
+                    }
+                    dst.depth[y * dst.width + x] = farthest;
+                }
+            }
+        }
+    }
+
+    /// Whether `aabb` placed by `model` is hidden behind the occluders.
+    /// Boxes reaching the near plane or off screen are never occluded.
+    pub fn is_occluded(&self, aabb: &AABB, model: &Mat4) -> bool {
+        let to_clip = self.view_proj * *model;
+        let mut min = Vec3::splat(f32::MAX);
+        let mut max = Vec3::splat(f32::MIN);
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            );
+            let clip = to_clip * corner.extend(1.0);
+            if clip.z < 0.0 || clip.w <= MIN_W {
+                return false;
+            }
+            let screen = self.to_screen(clip);
+            min = min.min(screen);
+            max = max.max(screen);
+        }
+
+        let (width, height) = (self.levels[0].width, self.levels[0].height);
+        if max.x <= 0.0 || max.y <= 0.0 || min.x >= width as f32 || min.y >= height as f32 {
+            return false;
+        }
+        let x0 = min.x.max(0.0) as usize;
+        let y0 = min.y.max(0.0) as usize;
+        let x1 = (max.x as usize).min(width - 1);
+        let y1 = (max.y as usize).min(height - 1);
+
+        let mut level = 0;
+        while level + 1 < self.levels.len()
+            && ((x1 >> level) - (x0 >> level) > 1 || (y1 >> level) - (y0 >> level) > 1)
+        {
+            level += 1;
+        }
+        let texels = &self.levels[level];
+        let mut farthest = 0.0f32;
+        for y in (y0 >> level)..=(y1 >> level) {
+            for x in (x0 >> level)..=(x1 >> level) {
+                farthest = farthest.max(texels.depth[y * texels.width + x]);
+            }
+        }
+        min.z > farthest
+    }
+
+    /// Pixel coordinates and depth of a clip space point
+    fn to_screen(&self, clip: Vec4) -> Vec3 {
+        let ndc = clip.truncate() / clip.w;
+        Vec3::new(
+            (ndc.x * 0.5 + 0.5) * self.levels[0].width as f32,
+            (0.5 - ndc.y * 0.5) * self.levels[0].height as f32,
+            ndc.z,
+        )
+    }
+
+    /// Clip a triangle against the near plane and rasterize what is left
+    fn rasterize_clipped(&mut self, triangle: [Vec4; 3]) -> bool {
+        let mut polygon = [Vec4::ZERO; 4];
+        let mut count = 0;
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            if a.z >= 0.0 {
+                polygon[count] = a;
+                count += 1;
+            }
+            if (a.z >= 0.0) != (b.z >= 0.0) {
+                polygon[count] = a + (b - a) * (a.z / (a.z - b.z));
+                count += 1;
+            }
+        }
+        if count < 3 || polygon[..count].iter().any(|corner| corner.w <= MIN_W) {
+            return false;
+        }
+
+        let screen = polygon.map(|corner| self.to_screen(corner));
+        for i in 1..count - 1 {
+            self.rasterize_triangle(screen[0], screen[i], screen[i + 1]);
+        }
+        true
+    }
+
+    /// Keep the nearest depth at every pixel center inside the triangle
+    fn rasterize_triangle(&mut self, v0: Vec3, v1: Vec3, v2: Vec3) {
+        let area = (v1.x - v0.x) * (v2.y - v0.y) - (v1.y - v0.y) * (v2.x - v0.x);
+        if area.abs() <= f32::EPSILON {
+            return;
+        }
+        // Both faces are drawn; order the corners counter-clockwise
+        let (v1, v2) = if area < 0.0 { (v2, v1) } else { (v1, v2) };
+        let area = area.abs();
+
+        let level = &mut self.levels[0];
+        let min_x = v0.x.min(v1.x).min(v2.x).floor().max(0.0) as usize;
+        let min_y = v0.y.min(v1.y).min(v2.y).floor().max(0.0) as usize;
+        let max_x = (v0.x.max(v1.x).max(v2.x).ceil().max(0.0) as usize).min(level.width);
+        let max_y = (v0.y.max(v1.y).max(v2.y).ceil().max(0.0) as usize).min(level.height);
+        if min_x >= max_x || min_y >= max_y {
+            return;
+        }
+
+        // Edge functions `a * x + b * y + c`, positive inside; each one is
+        // also the weight of the opposite corner times the area
+        let edge = |from: Vec3, to: Vec3| {
+            let a = from.y - to.y;
+            let b = to.x - from.x;
+            (a, b, -(a * from.x + b * from.y))
+        };
+        let edges = [edge(v1, v2), edge(v2, v0), edge(v0, v1)];
+        let depths = [v0.z, v1.z, v2.z];
+        let (mut za, mut zb, mut zc) = (0.0, 0.0, 0.0);
+        for ((a, b, c), z) in edges.iter().zip(depths) {
+            za += a * z / area;
+            zb += b * z / area;
+            zc += c * z / area;
+        }
+
+        let zero = f32x4::splat(0.0);
+        let offsets = f32x4::new([0.5, 1.5, 2.5, 3.5]);
+        for y in min_y..max_y {
+            let py = y as f32 + 0.5;
+            let row = &mut level.depth[y * level.width..(y + 1) * level.width];
+            for x in (min_x & !3..max_x).step_by(4) {
+                let px = f32x4::splat(x as f32) + offsets;
+                let [e0, e1, e2] =
+                    edges.map(|(a, b, c)| (px * a + f32x4::splat(b * py + c)).cmp_ge(zero));
+                let inside = e0 & e1 & e2;
+                if inside.none() {
+                    continue;
+                }
+                let z = px * za + f32x4::splat(zb * py + zc);
+                let old = f32x4::new(row[x..x + 4].try_into().unwrap());
+                let new = inside.blend(z.min(old), old);
+                row[x..x + 4].copy_from_slice(&new.to_array());
+            }
+        }
+    }
+}
@@ -0,0 +1,1026 @@
+//! CPU reference renderer.
+//!
+//! [`SoftwareRenderer`] draws the same inputs as the GPU path — the material
+//! draws collected by the [`MaterialRegistry`], lights, window cameras, the
+//! [`SpriteBatcher`] and the gizmo commands of the [`CommandBuffer`] — with
+//! depth-buffered triangle rasterization on the CPU. Shading follows the
+//! built-in shaders: Lambert diffuse with Blinn-Phong specular, ACES tone
+//! mapping and gamma correction. Output is deterministic, so it can stand in
+//! for GPU captures in golden tests on machines without any adapter.
+//!
+//! [`mesh_thumbnail`] renders a single mesh, e.g. for asset previews.
+
+use crate::camera::{Camera, Projection, RenderLayers};
+use crate::command::{CommandBuffer, DrawCommand, GizmoType};
+use crate::components::{DirectionalLight, PbrMaterial, PointLight, SpotLight};
+use crate::material::{
+    BlendMode, CullMode, Material, MaterialPipelineKey, MaterialRegistry, TextureBinding,
+    UniformLayout, UnlitMaterial,
+};
+use crate::mesh::Mesh;
+use crate::render_target::{collect_camera_views, CameraView, TargetKey};
+use crate::sprite::{create_sprite_quad, SpriteBatcher};
+use crate::texture::{Texture, TextureFormat};
+use image::RgbaImage;
+use luminara_asset::{Asset, AssetServer};
+use luminara_core::shared_types::{Query, World};
+use luminara_math::{Color, Mat4, Transform, Vec2, Vec3, Vec4};
+use std::sync::Arc;
+
+/// HDR clear color when no camera draws to the window, as in the forward pass
+const DEFAULT_CLEAR: Vec4 = Vec4::new(0.1, 0.1, 0.15, 1.0);
+/// `default_light()` of the lighting shader module, used when the scene has
+/// no lights
+const DEFAULT_LIGHT_COLOR: Vec3 = Vec3::new(1.0, 0.95, 0.9);
+const AMBIENT: Vec3 = Vec3::new(0.15, 0.15, 0.2);
+/// Directional lights beyond this count are ignored, as in the light buffer
+const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+
+/// Renders worlds into images on the CPU
+pub struct SoftwareRenderer {
+    width: u32,
+    height: u32,
+    /// Linear scene color written by the material pass
+    hdr: Vec<Vec4>,
+    /// Tone-mapped color; sprites and gizmos are drawn on top of it
+    color: Vec<Vec4>,
+    depth: Vec<f32>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut renderer = Self {
+            width: 0,
+            height: 0,
+            hdr: Vec::new(),
+            color: Vec::new(),
+            depth: Vec::new(),
+        };
+        renderer.resize(width, height);
+        renderer
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        let pixels = (self.width * self.height) as usize;
+        self.hdr = vec![Vec4::ZERO; pixels];
+        self.color = vec![Vec4::ZERO; pixels];
+        self.depth = vec![1.0; pixels];
+    }
+
+    /// Render the cameras targeting the window, in camera order, followed by
+    /// sprites and gizmo lines seen through the last of them. Returns the
+    /// frame as it would be written to an sRGB target.
+    pub fn render(&mut self, world: &World) -> RgbaImage {
+        let views: Vec<CameraView> = collect_camera_views(world, (self.width, self.height))
+            .into_iter()
+            .filter(|view| view.key == TargetKey::Window)
+            .collect();
+        let asset_server = world.get_resource::<AssetServer>();
+        let lights = SceneLight::collect(world);
+
+        let mut surfaces = Vec::new();
+        if let Some(asset_server) = asset_server.as_deref() {
+            let draws = match world.get_resource::<MaterialRegistry>() {
+                Some(registry) => registry.collect(world, asset_server),
+                None => {
+                    let mut registry = MaterialRegistry::default();
+                    registry.register_component::<PbrMaterial>();
+                    registry.collect(world, asset_server)
+                }
+            };
+            for draw in draws {
+                let Some(mesh) = asset_server.get(&draw.mesh) else {
+                    continue;
+                };
+                let surface = Surface::new(
+                    draw.material_type,
+                    &draw.layout,
+                    &draw.uniform,
+                    &draw.textures,
+                    draw.pipeline_key,
+                    Some(asset_server),
+                );
+                surfaces.push(SurfaceDraw {
+                    mesh,
+                    transform: draw.transform,
+                    layers: draw.layers,
+                    surface,
+                });
+            }
+        }
+
+        self.hdr.fill(DEFAULT_CLEAR);
+        for (index, view) in views.iter().enumerate() {
+            // The first camera clears the target, later ones draw on top
+            if index == 0 {
+                let clear = view.clear_color;
+                self.hdr.fill(Vec4::new(clear.r, clear.g, clear.b, clear.a));
+            }
+            self.depth.fill(1.0);
+            let raster_view = RasterView {
+                view_proj: view.view_proj,
+                position: view.position,
+                viewport: view.viewport,
+            };
+
+            // Opaque draws first, then transparent ones back to front
+            let mut order: Vec<&SurfaceDraw> = surfaces
+                .iter()
+                .filter(|draw| view.sees(&draw.layers))
+                .collect();
+            let distance =
+                |draw: &SurfaceDraw| (draw.transform.translation - view.position).length_squared();
+            order.sort_by(|a, b| {
+                match (
+                    a.surface.pipeline_key.is_transparent(),
+                    b.surface.pipeline_key.is_transparent(),
+                ) {
+                    (true, true) => distance(b).total_cmp(&distance(a)),
+                    (ta, tb) => ta.cmp(&tb),
+                }
+            });
+            for draw in order {
+                self.draw_surface(
+                    &raster_view,
+                    &draw.mesh,
+                    &draw.transform,
+                    &draw.surface,
+                    &lights,
+                );
+            }
+        }
+        self.tone_map();
+
+        if let Some(view) = views.last() {
+            let raster_view = RasterView {
+                view_proj: view.view_proj,
+                position: view.position,
+                viewport: (0, 0, self.width, self.height),
+            };
+            if let (Some(batcher), Some(asset_server)) = (
+                world.get_resource::<SpriteBatcher>(),
+                asset_server.as_deref(),
+            ) {
+                self.draw_sprites(&raster_view, &batcher, asset_server);
+            }
+            if let Some(commands) = world.get_resource::<CommandBuffer>() {
+                self.draw_gizmos(&raster_view, &commands);
+            }
+        }
+
+        self.to_image()
+    }
+
+    /// Render `mesh` with `material` on a transparent background, seen from
+    /// above and in front of its bounds. Textures are sampled when an asset
+    /// server is given.
+    pub fn render_mesh(
+        &mut self,
+        mesh: &Mesh,
+        material: &PbrMaterial,
+        asset_server: Option<&AssetServer>,
+    ) -> RgbaImage {
+        let center = (mesh.aabb.min + mesh.aabb.max) * 0.5;
+        let radius = ((mesh.aabb.max - mesh.aabb.min).length() * 0.5).max(0.001);
+        let fov = 40.0_f32;
+        let distance = radius / (fov.to_radians() * 0.5).sin();
+        let eye = center + Vec3::new(0.6, 0.5, 1.0).normalize() * distance;
+        let camera = Camera {
+            projection: Projection::Perspective {
+                fov,
+                near: distance * 0.05,
+                far: distance * 4.0,
+            },
+            ..Default::default()
+        };
+        let aspect = self.width as f32 / self.height as f32;
+        let view = RasterView {
+            view_proj: camera.projection_matrix(aspect) * Mat4::look_at_rh(eye, center, Vec3::Y),
+            position: eye,
+            viewport: (0, 0, self.width, self.height),
+        };
+
+        let surface = Surface::new(
+            <PbrMaterial as Asset>::type_name(),
+            &material.uniform_layout(),
+            &material.uniform_data(),
+            &material.textures(),
+            material.pipeline_key(),
+            asset_server,
+        );
+        self.hdr.fill(Vec4::ZERO);
+        self.depth.fill(1.0);
+        self.draw_surface(&view, mesh, &Transform::IDENTITY, &surface, &[]);
+        self.tone_map();
+        self.to_image()
+    }
+
+    fn draw_surface(
+        &mut self,
+        view: &RasterView,
+        mesh: &Mesh,
+        transform: &Transform,
+        surface: &Surface,
+        lights: &[SceneLight],
+    ) {
+        let model = transform.compute_matrix();
+        let clip_from_model = view.view_proj * model;
+        let vertices: Vec<ClipVertex> = mesh
+            .vertices
+            .iter()
+            .map(|vertex| {
+                let position = Vec3::from(vertex.position);
+                ClipVertex {
+                    clip: clip_from_model * position.extend(1.0),
+                    varyings: Varyings {
+                        world: model.transform_point3(position),
+                        normal: model.transform_vector3(Vec3::from(vertex.normal)),
+                        uv: Vec2::from(vertex.uv),
+                    },
+                }
+            })
+            .collect();
+
+        let key = surface.pipeline_key;
+        let depth_mode = DepthMode {
+            test: true,
+            write: key.depth_write,
+        };
+        let (width, height) = (self.width, self.height);
+        let (hdr, depth) = (&mut self.hdr, &mut self.depth);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let Some(corners) = triangle
+                .iter()
+                .map(|&index| vertices.get(index as usize).copied())
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            rasterize_triangle(
+                (width, height),
+                depth,
+                view.viewport,
+                [corners[0], corners[1], corners[2]],
+                key.cull_mode,
+                depth_mode,
+                |pixel, varyings| {
+                    let Some(color) = surface.shade(varyings, view.position, lights) else {
+                        return false;
+                    };
+                    hdr[pixel] = blend(hdr[pixel], color, key.blend_mode);
+                    true
+                },
+            );
+        }
+    }
+
+    /// Tone map the HDR scene into the color target, as the post-process pass
+    fn tone_map(&mut self) {
+        for (color, hdr) in self.color.iter_mut().zip(&self.hdr) {
+            let mapped = aces_tonemap(hdr.truncate()).powf(1.0 / 2.2);
+            *color = mapped.extend(hdr.w.clamp(0.0, 1.0));
+        }
+    }
+
+    fn draw_sprites(&mut self, view: &RasterView, batcher: &SpriteBatcher, assets: &AssetServer) {
+        let (quad, indices) = create_sprite_quad();
+        let (width, height) = (self.width, self.height);
+        for batch in &batcher.batches {
+            let Some(texture) = assets.get(&batch.texture) else {
+                continue;
+            };
+            for instance in &batch.instances {
+                let model = Mat4::from_cols_array_2d(&instance.transform);
+                let tint = Vec4::from(instance.color);
+                let [u0, v0, u1, v1] = instance.uv_rect;
+                let corners: Vec<ClipVertex> = quad
+                    .iter()
+                    .map(|vertex| ClipVertex {
+                        clip: view.view_proj * model * Vec3::from(vertex.position).extend(1.0),
+                        varyings: Varyings {
+                            uv: Vec2::new(
+                                u0 + (u1 - u0) * vertex.uv[0],
+                                v0 + (v1 - v0) * vertex.uv[1],
+                            ),
+                            ..Default::default()
+                        },
+                    })
+                    .collect();
+                let (color, depth) = (&mut self.color, &mut self.depth);
+                for triangle in indices.chunks_exact(3) {
+                    rasterize_triangle(
+                        (width, height),
+                        depth,
+                        view.viewport,
+                        [
+                            corners[triangle[0] as usize],
+                            corners[triangle[1] as usize],
+                            corners[triangle[2] as usize],
+                        ],
+                        CullMode::None,
+                        DepthMode::DISABLED,
+                        |pixel, varyings| {
+                            let src = sample_texture(&texture, varyings.uv) * tint;
+                            color[pixel] =
+                                saturate(blend(color[pixel], src, BlendMode::AlphaBlend));
+                            true
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    fn draw_gizmos(&mut self, view: &RasterView, commands: &CommandBuffer) {
+        for command in &commands.commands {
+            let DrawCommand::DrawGizmo {
+                gizmo,
+                transform,
+                color,
+            } = command
+            else {
+                continue;
+            };
+            let color = Vec4::new(color.r, color.g, color.b, color.a);
+            for (start, end) in gizmo_lines(gizmo) {
+                self.draw_line(
+                    view,
+                    transform.transform_point3(start),
+                    transform.transform_point3(end),
+                    color,
+                );
+            }
+        }
+    }
+
+    /// Depth-tested line without depth writes, blended over the color target
+    fn draw_line(&mut self, view: &RasterView, start: Vec3, end: Vec3, color: Vec4) {
+        let mut a = view.view_proj * start.extend(1.0);
+        let mut b = view.view_proj * end.extend(1.0);
+        // Clip against the near plane
+        if a.z < 0.0 && b.z < 0.0 {
+            return;
+        }
+        if a.z < 0.0 {
+            a = a.lerp(b, a.z / (a.z - b.z));
+        } else if b.z < 0.0 {
+            b = b.lerp(a, b.z / (b.z - a.z));
+        }
+        let (a, b) = (view.to_screen(a), view.to_screen(b));
+        let steps = (b.x - a.x).abs().max((b.y - a.y).abs()).ceil().max(1.0) as u32;
+        for step in 0..=steps {
+            let point = a.lerp(b, step as f32 / steps as f32);
+            let (x, y) = (point.x.floor(), point.y.floor());
+            if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+                continue;
+            }
+            let pixel = y as usize * self.width as usize + x as usize;
+            if point.z > 1.0 || point.z > self.depth[pixel] + 1e-4 {
+                continue;
+            }
+            self.color[pixel] = saturate(blend(self.color[pixel], color, BlendMode::AlphaBlend));
+        }
+    }
+
+    fn to_image(&self) -> RgbaImage {
+        let mut data = Vec::with_capacity(self.color.len() * 4);
+        for color in &self.color {
+            data.extend_from_slice(&[
+                linear_to_srgb(color.x),
+                linear_to_srgb(color.y),
+                linear_to_srgb(color.z),
+                (color.w.clamp(0.0, 1.0) * 255.0).round() as u8,
+            ]);
+        }
+        RgbaImage::from_raw(self.width, self.height, data).expect("frame size mismatch")
+    }
+}
+
+/// Render `mesh` into a `size`×`size` image with a transparent background
+pub fn mesh_thumbnail(mesh: &Mesh, material: &PbrMaterial, size: u32) -> RgbaImage {
+    SoftwareRenderer::new(size, size).render_mesh(mesh, material, None)
+}
+
+struct SurfaceDraw {
+    mesh: Arc<Mesh>,
+    transform: Transform,
+    layers: RenderLayers,
+    surface: Surface,
+}
+
+/// Shading inputs decoded from a material's uniform block and textures
+struct Surface {
+    base_color: Vec4,
+    base_texture: Option<Arc<Texture>>,
+    metallic: f32,
+    roughness: f32,
+    metallic_roughness_texture: Option<Arc<Texture>>,
+    emissive: Vec3,
+    unlit: bool,
+    pipeline_key: MaterialPipelineKey,
+}
+
+impl Surface {
+    /// Materials are read by the field names of the built-in ones; custom
+    /// materials using other names are shaded as white PBR surfaces
+    fn new(
+        material_type: &str,
+        layout: &UniformLayout,
+        uniform: &[u8],
+        textures: &[TextureBinding],
+        pipeline_key: MaterialPipelineKey,
+        asset_server: Option<&AssetServer>,
+    ) -> Self {
+        let vec4 = |names: &[&str], default: Vec4| {
+            names
+                .iter()
+                .find_map(|name| layout.read(uniform, name))
+                .filter(|values| values.len() == 4)
+                .map_or(default, |values| Vec4::from_slice(&values))
+        };
+        let scalar = |name: &str, default: f32| {
+            layout
+                .read(uniform, name)
+                .and_then(|values| values.first().copied())
+                .unwrap_or(default)
+        };
+        let texture = |names: &[&str]| {
+            let asset_server = asset_server?;
+            textures
+                .iter()
+                .find(|binding| names.contains(&binding.name.as_str()))
+                .and_then(|binding| binding.texture.as_ref())
+                .and_then(|handle| asset_server.get(handle))
+        };
+
+        Self {
+            base_color: vec4(&["albedo", "color", "base_color"], Vec4::ONE),
+            base_texture: texture(&["albedo_texture", "color_texture", "base_color_texture"]),
+            metallic: scalar("metallic", 0.0),
+            roughness: scalar("roughness", 0.5),
+            metallic_roughness_texture: texture(&["metallic_roughness_texture"]),
+            emissive: vec4(&["emissive"], Vec4::ZERO).truncate(),
+            unlit: material_type == <UnlitMaterial as Asset>::type_name(),
+            pipeline_key,
+        }
+    }
+
+    /// Fragment color in linear HDR, or `None` when the alpha test discards it
+    fn shade(
+        &self,
+        varyings: &Varyings,
+        camera_position: Vec3,
+        lights: &[SceneLight],
+    ) -> Option<Vec4> {
+        let base = match &self.base_texture {
+            Some(texture) => self.base_color * sample_texture(texture, varyings.uv),
+            None => self.base_color,
+        };
+        if let Some(cutoff) = self.pipeline_key.alpha_cutoff() {
+            if base.w < cutoff {
+                return None;
+            }
+        }
+        if self.unlit {
+            return Some(base);
+        }
+
+        let metallic_roughness = match &self.metallic_roughness_texture {
+            Some(texture) => sample_texture(texture, varyings.uv),
+            None => Vec4::ONE,
+        };
+        let metallic = self.metallic * metallic_roughness.z;
+        let roughness = self.roughness * metallic_roughness.y;
+        let albedo = base.truncate();
+        let normal = varyings.normal.normalize_or_zero();
+        let view_dir = (camera_position - varyings.world).normalize_or_zero();
+
+        let mut lit = albedo * AMBIENT;
+        if lights.is_empty() {
+            let direction = Vec3::new(0.3, 0.7, 0.5).normalize();
+            lit += shade_light(
+                direction,
+                DEFAULT_LIGHT_COLOR,
+                albedo,
+                normal,
+                view_dir,
+                metallic,
+                roughness,
+            );
+        }
+        for light in lights {
+            if let Some((direction, radiance)) = light.incident(varyings.world) {
+                lit += shade_light(
+                    direction, radiance, albedo, normal, view_dir, metallic, roughness,
+                );
+            }
+        }
+        Some((lit + self.emissive).extend(base.w))
+    }
+}
+
+/// Diffuse and specular terms of `shade_direct` in the lighting module
+fn shade_light(
+    direction: Vec3,
+    radiance: Vec3,
+    albedo: Vec3,
+    normal: Vec3,
+    view_dir: Vec3,
+    metallic: f32,
+    roughness: f32,
+) -> Vec3 {
+    let n_dot_l = normal.dot(direction).max(0.0);
+    let diffuse = albedo * radiance * n_dot_l;
+
+    let half_dir = (direction + view_dir).normalize_or_zero();
+    let spec_power = 16.0 + (256.0 - 16.0) * (1.0 - roughness);
+    let spec = normal.dot(half_dir).max(0.0).powf(spec_power);
+    let fresnel = metallic + (1.0 - metallic) * (1.0 - view_dir.dot(half_dir).max(0.0)).powi(5);
+    diffuse + radiance * spec * fresnel
+}
+
+enum SceneLight {
+    Directional {
+        /// Towards the light
+        direction: Vec3,
+        radiance: Vec3,
+    },
+    Point {
+        position: Vec3,
+        radiance: Vec3,
+        range: f32,
+    },
+    Spot {
+        position: Vec3,
+        /// Along the cone axis, away from the light
+        axis: Vec3,
+        radiance: Vec3,
+        range: f32,
+        inner_cos: f32,
+        outer_cos: f32,
+    },
+}
+
+impl SceneLight {
+    fn collect(world: &World) -> Vec<Self> {
+        let radiance =
+            |color: Color, intensity: f32| Vec3::new(color.r, color.g, color.b) * intensity;
+        let mut lights: Vec<Self> = Query::<(&DirectionalLight, &Transform)>::new(world)
+            .iter()
+            .take(MAX_DIRECTIONAL_LIGHTS)
+            .map(|(light, transform)| Self::Directional {
+                direction: -transform.forward(),
+                radiance: radiance(light.color, light.intensity),
+            })
+            .collect();
+        lights.extend(Query::<(&PointLight, &Transform)>::new(world).iter().map(
+            |(light, transform)| Self::Point {
+                position: transform.translation,
+                radiance: radiance(light.color, light.intensity),
+                range: light.range,
+            },
+        ));
+        lights.extend(Query::<(&SpotLight, &Transform)>::new(world).iter().map(
+            |(light, transform)| Self::Spot {
+                position: transform.translation,
+                axis: transform.forward(),
+                radiance: radiance(light.color, light.intensity),
+                range: light.range,
+                inner_cos: light.inner_angle.cos(),
+                outer_cos: light.outer_angle.cos(),
+            },
+        ));
+        lights
+    }
+
+    /// Direction towards the light and the radiance arriving at `position`
+    fn incident(&self, position: Vec3) -> Option<(Vec3, Vec3)> {
+        match *self {
+            Self::Directional {
+                direction,
+                radiance,
+            } => Some((direction, radiance)),
+            Self::Point {
+                position: light_position,
+                radiance,
+                range,
+            } => {
+                let (direction, attenuation) = attenuate(light_position - position, range)?;
+                Some((direction, radiance * attenuation))
+            }
+            Self::Spot {
+                position: light_position,
+                axis,
+                radiance,
+                range,
+                inner_cos,
+                outer_cos,
+            } => {
+                let (direction, attenuation) = attenuate(light_position - position, range)?;
+                let cone = smoothstep(outer_cos, inner_cos, (-direction).dot(axis));
+                Some((direction, radiance * attenuation * cone))
+            }
+        }
+    }
+}
+
+/// Inverse-square falloff with a smooth cutoff at `range`, as in the
+/// clustered lighting shader
+fn attenuate(to_light: Vec3, range: f32) -> Option<(Vec3, f32)> {
+    let distance = to_light.length();
+    if distance > range {
+        return None;
+    }
+    let falloff = (1.0 - (distance / range).powi(4)).clamp(0.0, 1.0);
+    let attenuation = falloff * falloff / (distance * distance).max(0.0001);
+    Some((to_light / distance.max(0.0001), attenuation))
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Line segments outlining a gizmo in its local space
+fn gizmo_lines(gizmo: &GizmoType) -> Vec<(Vec3, Vec3)> {
+    const SEGMENTS: usize = 24;
+    let circle = |center: Vec3, u: Vec3, v: Vec3, radius: f32| {
+        (0..SEGMENTS)
+            .map(|i| {
+                let angle = |i: usize| i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+                let point = |a: f32| center + (u * a.cos() + v * a.sin()) * radius;
+                (point(angle(i)), point(angle(i + 1)))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    match *gizmo {
+        GizmoType::Line { start, end } => vec![(Vec3::from(start), Vec3::from(end))],
+        GizmoType::Arrow { start, end } => {
+            let (start, end) = (Vec3::from(start), Vec3::from(end));
+            let axis = end - start;
+            let length = axis.length();
+            if length <= f32::EPSILON {
+                return Vec::new();
+            }
+            let direction = axis / length;
+            let side = direction.any_orthonormal_vector() * length * 0.1;
+            let back = end - direction * length * 0.2;
+            vec![(start, end), (end, back + side), (end, back - side)]
+        }
+        GizmoType::Sphere { radius } => [
+            circle(Vec3::ZERO, Vec3::X, Vec3::Y, radius),
+            circle(Vec3::ZERO, Vec3::Y, Vec3::Z, radius),
+            circle(Vec3::ZERO, Vec3::Z, Vec3::X, radius),
+        ]
+        .concat(),
+        GizmoType::Box { half_extents } => {
+            let h = Vec3::from(half_extents);
+            let corner = |i: usize| {
+                Vec3::new(
+                    if i & 1 == 0 { -h.x } else { h.x },
+                    if i & 2 == 0 { -h.y } else { h.y },
+                    if i & 4 == 0 { -h.z } else { h.z },
+                )
+            };
+            // Corners differing in exactly one axis bit share an edge
+            (0..8)
+                .flat_map(|i| [1, 2, 4].map(|bit| (i, i | bit)))
+                .filter(|(i, j)| i != j)
+                .map(|(i, j)| (corner(i), corner(j)))
+                .collect()
+        }
+        GizmoType::Capsule { radius, height } => {
+            let half = Vec3::Y * (height * 0.5);
+            let mut lines = [
+                circle(half, Vec3::X, Vec3::Z, radius),
+                circle(-half, Vec3::X, Vec3::Z, radius),
+                circle(half, Vec3::X, Vec3::Y, radius),
+                circle(-half, Vec3::X, Vec3::Y, radius),
+            ]
+            .concat();
+            for side in [Vec3::X, -Vec3::X, Vec3::Z, -Vec3::Z] {
+                lines.push((half + side * radius, -half + side * radius));
+            }
+            lines
+        }
+    }
+}
+
+/// Camera transform and pixel rectangle of a draw
+struct RasterView {
+    view_proj: Mat4,
+    position: Vec3,
+    viewport: (u32, u32, u32, u32),
+}
+
+impl RasterView {
+    /// Pixel position and depth of a clip-space position
+    fn to_screen(&self, clip: Vec4) -> Vec3 {
+        let ndc = clip.truncate() / clip.w;
+        let (x, y, width, height) = self.viewport;
+        Vec3::new(
+            x as f32 + (ndc.x + 1.0) * 0.5 * width as f32,
+            y as f32 + (1.0 - ndc.y) * 0.5 * height as f32,
+            ndc.z,
+        )
+    }
+}
+
+/// Attributes interpolated across a triangle
+#[derive(Debug, Clone, Copy, Default)]
+struct Varyings {
+    world: Vec3,
+    normal: Vec3,
+    uv: Vec2,
+}
+
+impl Varyings {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            world: self.world.lerp(other.world, t),
+            normal: self.normal.lerp(other.normal, t),
+            uv: self.uv.lerp(other.uv, t),
+        }
+    }
+
+    fn weighted(corners: [&Self; 3], weights: [f32; 3]) -> Self {
+        let mut result = Self::default();
+        for (corner, weight) in corners.iter().zip(weights) {
+            result.world += corner.world * weight;
+            result.normal += corner.normal * weight;
+            result.uv += corner.uv * weight;
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClipVertex {
+    clip: Vec4,
+    varyings: Varyings,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DepthMode {
+    test: bool,
+    write: bool,
+}
+
+impl DepthMode {
+    const DISABLED: Self = Self {
+        test: false,
+        write: false,
+    };
+}
+
+/// Rasterize a clip-space triangle into `viewport`. `fragment` receives the
+/// pixel index and perspective-correct attributes of every covered pixel
+/// passing the depth test, and returns whether it wrote the pixel.
+fn rasterize_triangle(
+    (width, height): (u32, u32),
+    depth: &mut [f32],
+    viewport: (u32, u32, u32, u32),
+    triangle: [ClipVertex; 3],
+    cull_mode: CullMode,
+    depth_mode: DepthMode,
+    mut fragment: impl FnMut(usize, &Varyings) -> bool,
+) {
+    let polygon = clip_near(&triangle);
+    let view = RasterView {
+        view_proj: Mat4::IDENTITY,
+        position: Vec3::ZERO,
+        viewport,
+    };
+    let (vx, vy, vw, vh) = viewport;
+    let x_range = (vx.min(width), (vx + vw).min(width));
+    let y_range = (vy.min(height), (vy + vh).min(height));
+
+    for i in 1..polygon.len().saturating_sub(1) {
+        let corners = [polygon[0], polygon[i], polygon[i + 1]];
+        let mut screen = corners.map(|corner| view.to_screen(corner.clip));
+        let mut inv_w = corners.map(|corner| 1.0 / corner.clip.w);
+        let mut varyings = corners.map(|corner| corner.varyings);
+
+        let mut area = edge(screen[0], screen[1], screen[2]);
+        if area == 0.0 || !area.is_finite() {
+            continue;
+        }
+        // Screen y points down, so counter-clockwise (front) faces have a
+        // negative area
+        let front = area < 0.0;
+        match cull_mode {
+            CullMode::Back if !front => continue,
+            CullMode::Front if front => continue,
+            _ => {}
+        }
+        if area < 0.0 {
+            screen.swap(1, 2);
+            inv_w.swap(1, 2);
+            varyings.swap(1, 2);
+            area = -area;
+        }
+
+        let min = screen[0].min(screen[1]).min(screen[2]);
+        let max = screen[0].max(screen[1]).max(screen[2]);
+        let x0 = (min.x.floor().max(0.0) as u32).max(x_range.0);
+        let x1 = (max.x.ceil().max(0.0) as u32).min(x_range.1);
+        let y0 = (min.y.floor().max(0.0) as u32).max(y_range.0);
+        let y1 = (max.y.ceil().max(0.0) as u32).min(y_range.1);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let p = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+                let w0 = edge(screen[1], screen[2], p);
+                let w1 = edge(screen[2], screen[0], p);
+                let w2 = edge(screen[0], screen[1], p);
+                if !covers(w0, screen[1], screen[2])
+                    || !covers(w1, screen[2], screen[0])
+                    || !covers(w2, screen[0], screen[1])
+                {
+                    continue;
+                }
+                let barycentric = [w0 / area, w1 / area, w2 / area];
+                let z = barycentric[0] * screen[0].z
+                    + barycentric[1] * screen[1].z
+                    + barycentric[2] * screen[2].z;
+                if !(0.0..=1.0).contains(&z) {
+                    continue;
+                }
+                let pixel = y as usize * width as usize + x as usize;
+                if depth_mode.test && z >= depth[pixel] {
+                    continue;
+                }
+
+                let perspective = [
+                    barycentric[0] * inv_w[0],
+                    barycentric[1] * inv_w[1],
+                    barycentric[2] * inv_w[2],
+                ];
+                let sum = perspective[0] + perspective[1] + perspective[2];
+                let weights = perspective.map(|weight| weight / sum);
+                let attributes =
+                    Varyings::weighted([&varyings[0], &varyings[1], &varyings[2]], weights);
+                if fragment(pixel, &attributes) && depth_mode.write {
+                    depth[pixel] = z;
+                }
+            }
+        }
+    }
+}
+
+/// Clip a triangle against the near plane (`z >= 0` in wgpu clip space)
+fn clip_near(triangle: &[ClipVertex; 3]) -> Vec<ClipVertex> {
+    let mut polygon = Vec::with_capacity(4);
+    for i in 0..3 {
+        let current = triangle[i];
+        let next = triangle[(i + 1) % 3];
+        let (inside, next_inside) = (current.clip.z >= 0.0, next.clip.z >= 0.0);
+        if inside {
+            polygon.push(current);
+        }
+        if inside != next_inside {
+            let t = current.clip.z / (current.clip.z - next.clip.z);
+            polygon.push(ClipVertex {
+                clip: current.clip.lerp(next.clip, t),
+                varyings: current.varyings.lerp(&next.varyings, t),
+            });
+        }
+    }
+    polygon
+}
+
+/// Twice the signed area of `(a, b, p)`
+fn edge(a: Vec3, b: Vec3, p: Vec3) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Top-left fill rule, so pixels on edges shared by two triangles are drawn
+/// once
+fn covers(weight: f32, a: Vec3, b: Vec3) -> bool {
+    if weight != 0.0 {
+        return weight > 0.0;
+    }
+    let top = a.y == b.y && b.x > a.x;
+    let left = b.y < a.y;
+    top || left
+}
+
+/// Blend `src` over `dst` like the GPU blend state of `mode`
+fn blend(dst: Vec4, src: Vec4, mode: BlendMode) -> Vec4 {
+    let alpha = src.w;
+    let over = src.w + dst.w * (1.0 - alpha);
+    match mode {
+        BlendMode::Opaque => src,
+        BlendMode::AlphaBlend => {
+            (src.truncate() * alpha + dst.truncate() * (1.0 - alpha)).extend(over)
+        }
+        BlendMode::Premultiplied => (src.truncate() + dst.truncate() * (1.0 - alpha)).extend(over),
+        BlendMode::Additive => (src.truncate() * alpha + dst.truncate()).extend(src.w + dst.w),
+    }
+}
+
+/// Unorm targets clamp what is written to them
+fn saturate(color: Vec4) -> Vec4 {
+    color.clamp(Vec4::ZERO, Vec4::ONE)
+}
+
+fn aces_tonemap(color: Vec3) -> Vec3 {
+    let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+    ((color * (a * color + b)) / (color * (c * color + d) + e)).clamp(Vec3::ZERO, Vec3::ONE)
+}
+
+/// Bilinear sample honoring the texture's address modes and magnification
+/// filter. 8-bit textures are treated as sRGB, like their GPU copies.
+fn sample_texture(texture: &Texture, uv: Vec2) -> Vec4 {
+    let data = &texture.data;
+    if data.width == 0 || data.height == 0 {
+        return Vec4::ONE;
+    }
+    let settings = texture.sampler_settings;
+    let texel = |x: i64, y: i64| {
+        let x = wrap(x, data.width, settings.address_mode_u);
+        let y = wrap(y, data.height, settings.address_mode_v);
+        let index = (y * data.width + x) as usize;
+        match data.format {
+            TextureFormat::Rgba8 => data.data.get(index * 4..index * 4 + 4).map(|bytes| {
+                Vec4::new(
+                    srgb_to_linear(bytes[0]),
+                    srgb_to_linear(bytes[1]),
+                    srgb_to_linear(bytes[2]),
+                    bytes[3] as f32 / 255.0,
+                )
+            }),
+            TextureFormat::Rgba32F => data.data.get(index * 16..index * 16 + 16).map(|bytes| {
+                let channel = |i: usize| {
+                    f32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+                };
+                Vec4::new(channel(0), channel(4), channel(8), channel(12))
+            }),
+            TextureFormat::R8 => data
+                .data
+                .get(index)
+                .map(|&r| Vec4::new(r as f32 / 255.0, 0.0, 0.0, 1.0)),
+            TextureFormat::Rg8 | TextureFormat::Rgba16F => None,
+        }
+        .unwrap_or(Vec4::ONE)
+    };
+
+    let x = uv.x * data.width as f32 - 0.5;
+    let y = uv.y * data.height as f32 - 0.5;
+    if settings.mag_filter == wgpu::FilterMode::Nearest {
+        return texel(x.round() as i64, y.round() as i64);
+    }
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let top = texel(x0, y0).lerp(texel(x0 + 1, y0), fx);
+    let bottom = texel(x0, y0 + 1).lerp(texel(x0 + 1, y0 + 1), fx);
+    top.lerp(bottom, fy)
+}
+
+fn wrap(coordinate: i64, size: u32, mode: wgpu::AddressMode) -> u32 {
+    let size = size as i64;
+    let wrapped = match mode {
+        wgpu::AddressMode::Repeat => coordinate.rem_euclid(size),
+        wgpu::AddressMode::MirrorRepeat => {
+            let period = coordinate.rem_euclid(size * 2);
+            if period < size {
+                period
+            } else {
+                size * 2 - 1 - period
+            }
+        }
+        _ => coordinate.clamp(0, size - 1),
+    };
+    wrapped as u32
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let c = value.clamp(0.0, 1.0);
+    let encoded = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
@@ -14,6 +14,8 @@ pub struct Sprite {
     pub flip_x: bool,
     pub flip_y: bool,
     pub anchor: Anchor,
+    #[serde(default)]
+    pub nine_slice: Option<NineSlice>,
 }
 
 impl Component for Sprite {
@@ -31,6 +33,7 @@ impl Sprite {
             flip_x: false,
             flip_y: false,
             anchor: Anchor::Center,
+            nine_slice: None,
         }
     }
 
@@ -48,10 +51,15 @@ impl Sprite {
         self.anchor = anchor;
         self
     }
+
+    pub fn with_nine_slice(mut self, nine_slice: NineSlice) -> Self {
+        self.nine_slice = Some(nine_slice);
+        self
+    }
 }
 
 /// Texture coordinate rectangle for sprite atlases
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
     pub min: Vec2,
     pub max: Vec2,
@@ -101,6 +109,116 @@ impl Anchor {
     }
 }
 
+/// Widths of the four borders of a rectangle
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct BorderRect {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl BorderRect {
+    pub fn all(width: f32) -> Self {
+        Self {
+            left: width,
+            right: width,
+            top: width,
+            bottom: width,
+        }
+    }
+}
+
+/// 9-slice scaling: the corners of the sprite keep their size, the edges
+/// stretch along their side and the center stretches both ways
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct NineSlice {
+    /// Border widths in texels of the sprite's texture region
+    pub border: BorderRect,
+    /// Size of the sprite's texture region in texels
+    pub region_size: Vec2,
+    /// World units per border texel
+    pub scale: f32,
+}
+
+/// One of the nine quads of a sliced sprite, in the sprite's unit quad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NineSlicePiece {
+    pub offset: Vec2,
+    pub size: Vec2,
+    pub uv_rect: [f32; 4],
+}
+
+impl NineSlice {
+    pub fn new(border: BorderRect, region_size: Vec2) -> Self {
+        Self {
+            border,
+            region_size,
+            scale: 1.0,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Split a sprite of `size` world units showing `uv_rect` into its
+    /// pieces. Borders wider than the sprite shrink to fit; empty pieces are
+    /// left out.
+    pub fn pieces(&self, size: Vec2, uv_rect: [f32; 4]) -> Vec<NineSlicePiece> {
+        let size = size.max(Vec2::splat(f32::EPSILON));
+        let fit = |a: f32, b: f32, extent: f32| {
+            let (a, b) = (a * self.scale, b * self.scale);
+            let shrink = if a + b > extent {
+                extent / (a + b)
+            } else {
+                1.0
+            };
+            (a * shrink / extent, b * shrink / extent)
+        };
+        let (left, right) = fit(self.border.left, self.border.right, size.x);
+        let (top, bottom) = fit(self.border.top, self.border.bottom, size.y);
+
+        // Quad edges from left to right and top to bottom (+Y is up)
+        let xs = [-0.5, -0.5 + left, 0.5 - right, 0.5];
+        let ys = [0.5, 0.5 - top, -0.5 + bottom, -0.5];
+        let region = self.region_size.max(Vec2::ONE);
+        let (u0, v0, u3, v3) = (uv_rect[0], uv_rect[1], uv_rect[2], uv_rect[3]);
+        let us = [
+            u0,
+            u0 + (u3 - u0) * self.border.left / region.x,
+            u3 - (u3 - u0) * self.border.right / region.x,
+            u3,
+        ];
+        let vs = [
+            v0,
+            v0 + (v3 - v0) * self.border.top / region.y,
+            v3 - (v3 - v0) * self.border.bottom / region.y,
+            v3,
+        ];
+
+        let mut pieces = Vec::with_capacity(9);
+        for row in 0..3 {
+            for column in 0..3 {
+                let piece_size = Vec2::new(xs[column + 1] - xs[column], ys[row] - ys[row + 1]);
+                if piece_size.x <= 0.0 || piece_size.y <= 0.0 {
+                    continue;
+                }
+                pieces.push(NineSlicePiece {
+                    offset: Vec2::new(
+                        (xs[column] + xs[column + 1]) * 0.5,
+                        (ys[row] + ys[row + 1]) * 0.5,
+                    ),
+                    size: piece_size,
+                    uv_rect: [us[column], vs[row], us[column + 1], vs[row + 1]],
+                });
+            }
+        }
+        pieces
+    }
+}
+
 /// Z-order component for 2D sprite depth sorting
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub struct ZOrder(pub f32);
@@ -140,6 +258,8 @@ unsafe impl bytemuck::Zeroable for SpriteInstance {}
 pub struct SpriteBatch {
     pub texture: Handle<Texture>,
     pub instances: Vec<SpriteInstance>,
+    /// Z-order of the first sprite in the batch
+    pub z: f32,
 }
 
 impl SpriteBatch {
@@ -147,6 +267,7 @@ impl SpriteBatch {
         Self {
             texture,
             instances: Vec::new(),
+            z: 0.0,
         }
     }
 
@@ -213,28 +334,45 @@ impl SpriteBatcher {
         });
 
         // Build batches
-        for (sprite, transform, _) in sorted_sprites {
-            self.add_to_batch(sprite, transform);
+        for (sprite, transform, z) in sorted_sprites {
+            self.add_to_batch(sprite, transform, z.map(|z| z.0).unwrap_or(0.0));
         }
     }
 
-    /// Add a sprite to an appropriate batch
-    fn add_to_batch(&mut self, sprite: &Sprite, transform: &luminara_math::Mat4) {
-        // Find or create batch for this texture
-        let batch_index = self
+    /// Insert pre-built instances drawn with `texture` at z-order `z`, e.g.
+    /// a tilemap layer. They get batches of their own, placed after the
+    /// batches whose z-order is not above `z`.
+    pub fn insert_layer(
+        &mut self,
+        z: f32,
+        texture: &Handle<Texture>,
+        instances: impl IntoIterator<Item = SpriteInstance>,
+    ) {
+        let mut index = self
             .batches
             .iter()
-            .position(|b| {
-                b.texture.id() == sprite.texture.id() && !b.is_full(self.max_sprites_per_batch)
-            })
-            .unwrap_or_else(|| {
-                // Create new batch
-                self.batches.push(SpriteBatch::new(sprite.texture.clone()));
-                self.batches.len() - 1
+            .position(|b| b.z > z)
+            .unwrap_or(self.batches.len());
+        let max_size = self.max_sprites_per_batch.max(1);
+        let mut batch: Option<SpriteBatch> = None;
+        for instance in instances {
+            let current = batch.get_or_insert_with(|| SpriteBatch {
+                z,
+                ..SpriteBatch::new(texture.clone())
             });
+            current.add_instance(instance);
+            if current.is_full(max_size) {
+                self.batches.insert(index, batch.take().unwrap());
+                index += 1;
+            }
+        }
+        if let Some(batch) = batch {
+            self.batches.insert(index, batch);
+        }
+    }
 
-        let batch = &mut self.batches[batch_index];
-
+    /// Add a sprite to an appropriate batch
+    fn add_to_batch(&mut self, sprite: &Sprite, transform: &luminara_math::Mat4, z: f32) {
         // Create sprite instance
         let uv_rect = sprite
             .rect
@@ -255,18 +393,74 @@ impl SpriteBatcher {
             uv_rect
         };
 
-        let instance = SpriteInstance {
-            transform: transform.to_cols_array_2d(),
-            color: [
-                sprite.color.r,
-                sprite.color.g,
-                sprite.color.b,
-                sprite.color.a,
-            ],
-            uv_rect,
+        let color = [
+            sprite.color.r,
+            sprite.color.g,
+            sprite.color.b,
+            sprite.color.a,
+        ];
+
+        let Some(nine_slice) = sprite.nine_slice else {
+            let instance = SpriteInstance {
+                transform: transform.to_cols_array_2d(),
+                color,
+                uv_rect,
+            };
+            self.push_instance(&sprite.texture, z, instance);
+            return;
         };
 
-        batch.add_instance(instance);
+        // Slice the unflipped sprite, then mirror the pieces
+        let size = luminara_math::Vec2::new(
+            transform.x_axis.truncate().length(),
+            transform.y_axis.truncate().length(),
+        );
+        let mut unflipped = uv_rect;
+        if sprite.flip_x {
+            unflipped.swap(0, 2);
+        }
+        if sprite.flip_y {
+            unflipped.swap(1, 3);
+        }
+        for mut piece in nine_slice.pieces(size, unflipped) {
+            if sprite.flip_x {
+                piece.offset.x = -piece.offset.x;
+                piece.uv_rect.swap(0, 2);
+            }
+            if sprite.flip_y {
+                piece.offset.y = -piece.offset.y;
+                piece.uv_rect.swap(1, 3);
+            }
+            let local = luminara_math::Mat4::from_scale_rotation_translation(
+                piece.size.extend(1.0),
+                luminara_math::Quat::IDENTITY,
+                piece.offset.extend(0.0),
+            );
+            let instance = SpriteInstance {
+                transform: (*transform * local).to_cols_array_2d(),
+                color,
+                uv_rect: piece.uv_rect,
+            };
+            self.push_instance(&sprite.texture, z, instance);
+        }
+    }
+
+    fn push_instance(&mut self, texture: &Handle<Texture>, z: f32, instance: SpriteInstance) {
+        // Find or create batch for this texture
+        let batch_index = self
+            .batches
+            .iter()
+            .position(|b| b.texture.id() == texture.id() && !b.is_full(self.max_sprites_per_batch))
+            .unwrap_or_else(|| {
+                // Create new batch
+                self.batches.push(SpriteBatch {
+                    z,
+                    ..SpriteBatch::new(texture.clone())
+                });
+                self.batches.len() - 1
+            });
+
+        self.batches[batch_index].add_instance(instance);
     }
 
     /// Clear all batches
@@ -436,6 +630,52 @@ mod tests {
         let instance = &batcher.batches[0].instances[0];
         assert_eq!(instance.color, [0.5, 0.6, 0.7, 0.8]);
     }
+
+    #[test]
+    fn test_nine_slice_keeps_corner_size() {
+        let texture = Handle::<Texture>::new(AssetId::new(), 0);
+        let nine_slice = NineSlice::new(BorderRect::all(8.0), Vec2::new(32.0, 32.0));
+        let sprite = Sprite::new(texture).with_nine_slice(nine_slice);
+        let transform = Mat4::from_scale(luminara_math::Vec3::new(100.0, 50.0, 1.0));
+
+        let mut batcher = SpriteBatcher::new(1000);
+        batcher.prepare(vec![(&sprite, &transform, None)]);
+
+        let instances = &batcher.batches[0].instances;
+        assert_eq!(instances.len(), 9);
+        // Top-left corner: 8x8 world units showing the first quarter of the texture
+        let corner = Mat4::from_cols_array_2d(&instances[0].transform);
+        assert!((corner.x_axis.length() - 8.0).abs() < 1e-4);
+        assert!((corner.y_axis.length() - 8.0).abs() < 1e-4);
+        assert!((corner.w_axis.x - -46.0).abs() < 1e-4);
+        assert!((corner.w_axis.y - 21.0).abs() < 1e-4);
+        assert_eq!(instances[0].uv_rect, [0.0, 0.0, 0.25, 0.25]);
+        // Center stretches over the rest
+        let center = Mat4::from_cols_array_2d(&instances[4].transform);
+        assert!((center.x_axis.length() - 84.0).abs() < 1e-3);
+        assert!((center.y_axis.length() - 34.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_insert_layer_orders_by_z() {
+        let texture = Handle::<Texture>::new(AssetId::new(), 0);
+        let layer_texture = Handle::<Texture>::new(AssetId::new(), 0);
+        let sprite = Sprite::new(texture);
+        let z = ZOrder::new(1.0);
+
+        let mut batcher = SpriteBatcher::new(2);
+        batcher.prepare(vec![(&sprite, &Mat4::IDENTITY, Some(&z))]);
+        let instance = batcher.batches[0].instances[0];
+        batcher.insert_layer(-1.0, &layer_texture, vec![instance; 3]);
+        batcher.insert_layer(2.0, &layer_texture, vec![instance]);
+
+        let order: Vec<(bool, usize)> = batcher
+            .batches
+            .iter()
+            .map(|b| (b.texture.id() == layer_texture.id(), b.instances.len()))
+            .collect();
+        assert_eq!(order, vec![(true, 2), (true, 1), (false, 1), (true, 1)]);
+    }
 }
 
 /// Sprite rendering resources
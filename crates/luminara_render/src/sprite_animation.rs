@@ -0,0 +1,278 @@
+//! Frame-based sprite animation.
+//!
+//! A [`SpriteAnimation`] plays one of its [`SpriteAnimationClip`]s at a time
+//! by stepping the frame index of the entity's [`AtlasSprite`]. Clips loop,
+//! play once or ping-pong, and fire named events when playback enters a
+//! frame; [`sprite_animation_system`] collects those in
+//! [`SpriteAnimationEvents`].
+
+use crate::texture_atlas::AtlasSprite;
+use luminara_core::shared_types::{Component, Query, Resource, World};
+use luminara_core::Entity;
+use serde::{Deserialize, Serialize};
+
+/// What a clip does after its last frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SpriteAnimationMode {
+    /// Stop on the last frame
+    Once,
+    /// Start over from the first frame
+    #[default]
+    Loop,
+    /// Play backwards to the first frame, then forwards again
+    PingPong,
+}
+
+/// One frame of a clip: an atlas frame index shown for `duration` seconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpriteFrame {
+    pub index: usize,
+    pub duration: f32,
+}
+
+/// Named event fired when playback enters frame `frame` of a clip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpriteFrameEvent {
+    pub frame: usize,
+    pub name: String,
+}
+
+/// Sequence of atlas frames, e.g. "run" or "attack"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpriteAnimationClip {
+    pub name: String,
+    pub frames: Vec<SpriteFrame>,
+    #[serde(default)]
+    pub mode: SpriteAnimationMode,
+    #[serde(default)]
+    pub events: Vec<SpriteFrameEvent>,
+}
+
+impl SpriteAnimationClip {
+    /// Clip showing each atlas frame of `indices` for `1 / fps` seconds
+    pub fn from_indices(
+        name: impl Into<String>,
+        indices: impl IntoIterator<Item = usize>,
+        fps: f32,
+    ) -> Self {
+        let duration = 1.0 / fps.max(f32::EPSILON);
+        Self {
+            name: name.into(),
+            frames: indices
+                .into_iter()
+                .map(|index| SpriteFrame { index, duration })
+                .collect(),
+            mode: SpriteAnimationMode::Loop,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: SpriteAnimationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Fire `name` whenever playback enters frame `frame` of the clip
+    pub fn with_event(mut self, frame: usize, name: impl Into<String>) -> Self {
+        self.events.push(SpriteFrameEvent {
+            frame,
+            name: name.into(),
+        });
+        self
+    }
+
+    /// Length of one pass through the frames in seconds
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+}
+
+/// Sprite animation player. Drives the [`AtlasSprite`] of its entity.
+#[derive(Debug, Clone)]
+pub struct SpriteAnimation {
+    pub clips: Vec<SpriteAnimationClip>,
+    /// Playback rate, 1 for the authored frame durations
+    pub speed: f32,
+    pub playing: bool,
+    current: Option<usize>,
+    frame: usize,
+    elapsed: f32,
+    reverse: bool,
+    finished: bool,
+    entered: bool,
+}
+
+impl Component for SpriteAnimation {
+    fn type_name() -> &'static str {
+        "SpriteAnimation"
+    }
+}
+
+impl SpriteAnimation {
+    pub fn new(clips: Vec<SpriteAnimationClip>) -> Self {
+        Self {
+            clips,
+            speed: 1.0,
+            playing: true,
+            current: None,
+            frame: 0,
+            elapsed: 0.0,
+            reverse: false,
+            finished: false,
+            entered: false,
+        }
+    }
+
+    /// Start the clip called `name` from its first frame, unless it is
+    /// already playing. Returns `false` for an unknown clip.
+    pub fn play(&mut self, name: &str) -> bool {
+        let Some(clip) = self.clips.iter().position(|c| c.name == name) else {
+            return false;
+        };
+        if self.current != Some(clip) || self.finished {
+            self.current = Some(clip);
+            self.frame = 0;
+            self.elapsed = 0.0;
+            self.reverse = false;
+            self.finished = false;
+            self.entered = false;
+        }
+        self.playing = true;
+        true
+    }
+
+    pub fn current_clip(&self) -> Option<&SpriteAnimationClip> {
+        self.current.and_then(|i| self.clips.get(i))
+    }
+
+    /// Position in the current clip's frame list
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Atlas frame index currently shown
+    pub fn atlas_index(&self) -> Option<usize> {
+        self.current_clip()
+            .and_then(|clip| clip.frames.get(self.frame))
+            .map(|f| f.index)
+    }
+
+    /// Whether a [`SpriteAnimationMode::Once`] clip reached its end
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Move playback forward by `dt` seconds and return the events of the
+    /// frames entered, in order. Frames shorter than `dt` are not skipped.
+    pub fn advance(&mut self, dt: f32) -> Vec<String> {
+        let mut events = Vec::new();
+        let Some(clip) = self.current.and_then(|i| self.clips.get(i)) else {
+            return events;
+        };
+        if clip.frames.is_empty() {
+            return events;
+        }
+        let enter = |frame: usize, events: &mut Vec<String>| {
+            events.extend(
+                clip.events
+                    .iter()
+                    .filter(|e| e.frame == frame)
+                    .map(|e| e.name.clone()),
+            );
+        };
+        if !self.entered {
+            self.entered = true;
+            enter(self.frame, &mut events);
+        }
+        if !self.playing || self.finished {
+            return events;
+        }
+
+        self.elapsed += dt * self.speed;
+        let last = clip.frames.len() - 1;
+        loop {
+            let duration = clip.frames[self.frame].duration.max(f32::EPSILON);
+            if self.elapsed < duration {
+                break;
+            }
+            let next = match clip.mode {
+                SpriteAnimationMode::Once if self.frame == last => {
+                    self.finished = true;
+                    self.elapsed = 0.0;
+                    break;
+                }
+                SpriteAnimationMode::Loop if self.frame == last => 0,
+                SpriteAnimationMode::PingPong if last == 0 => 0,
+                SpriteAnimationMode::PingPong => {
+                    if (self.reverse && self.frame == 0) || (!self.reverse && self.frame == last) {
+                        self.reverse = !self.reverse;
+                    }
+                    if self.reverse {
+                        self.frame - 1
+                    } else {
+                        self.frame + 1
+                    }
+                }
+                _ => self.frame + 1,
+            };
+            self.elapsed -= duration;
+            self.frame = next;
+            enter(next, &mut events);
+        }
+        events
+    }
+}
+
+/// Event fired by a [`SpriteAnimation`] clip
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteAnimationEvent {
+    pub clip: String,
+    pub name: String,
+}
+
+/// Sprite animation events fired this frame, with the entity that fired them
+#[derive(Debug, Default)]
+pub struct SpriteAnimationEvents(pub Vec<(Entity, SpriteAnimationEvent)>);
+
+impl Resource for SpriteAnimationEvents {}
+
+/// Advance every [`SpriteAnimation`], show its frame on the entity's
+/// [`AtlasSprite`] and collect the events fired
+pub fn sprite_animation_system(world: &mut World) {
+    let dt = world
+        .get_resource::<luminara_core::Time>()
+        .map(|t| t.delta_seconds())
+        .unwrap_or(0.0);
+
+    let entities: Vec<Entity> = Query::<(Entity, &SpriteAnimation)>::new(world)
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+
+    let mut fired = Vec::new();
+    for entity in entities {
+        let Some(animation) = world.get_component_mut::<SpriteAnimation>(entity) else {
+            continue;
+        };
+        let events = animation.advance(dt);
+        let (Some(clip), Some(index)) = (animation.current_clip(), animation.atlas_index()) else {
+            continue;
+        };
+        fired.extend(events.into_iter().map(|name| {
+            (
+                entity,
+                SpriteAnimationEvent {
+                    clip: clip.name.clone(),
+                    name,
+                },
+            )
+        }));
+        if let Some(sprite) = world.get_component_mut::<AtlasSprite>(entity) {
+            sprite.index = index;
+        }
+    }
+
+    if let Some(mut events) = world.get_resource_mut::<SpriteAnimationEvents>() {
+        events.0 = fired;
+    }
+}
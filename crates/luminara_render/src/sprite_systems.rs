@@ -2,15 +2,71 @@ use crate::sprite::{
     create_sprite_quad, Sprite, SpriteBatcher, SpriteInstance, SpriteRenderResources, SpriteVertex,
     ZOrder,
 };
-use crate::GpuContext;
+use crate::sprite_animation::{sprite_animation_system, SpriteAnimationEvents};
+use crate::texture_atlas::{atlas_sprite_system, AtlasSprite, TextureAtlasLoader};
+use crate::tiled::{spawn_tiled_maps_system, TiledMapLoader};
+use crate::tilemap::prepare_tilemap_batches;
+use crate::{GpuContext, MaterialTextureQueue};
 use luminara_asset::AssetServer;
-use luminara_core::shared_types::{Query, Res, ResMut, Resource};
+use luminara_core::shared_types::{Query, Res, ResMut, Resource, World};
+use luminara_core::system::{ExclusiveMarker, FunctionMarker};
+use luminara_core::{App, AppInterface, CoreStage, Plugin};
 use luminara_math::Transform;
 use wgpu::util::DeviceExt;
 
 impl Resource for SpriteRenderResources {}
 impl Resource for SpriteBatcher {}
 
+/// 2D sprite support: texture atlas and Tiled map loading, sprite
+/// animation, and batching of sprites and tilemaps
+pub struct SpritePlugin;
+
+impl Plugin for SpritePlugin {
+    fn name(&self) -> &str {
+        "SpritePlugin"
+    }
+
+    fn build(&self, app: &mut App) {
+        if app.world.get_resource::<SpriteBatcher>().is_none() {
+            app.insert_resource(SpriteBatcher::new(1000));
+        }
+        if app.world.get_resource::<MaterialTextureQueue>().is_none() {
+            app.insert_resource(MaterialTextureQueue::default());
+        }
+        app.insert_resource(SpriteAnimationEvents::default());
+
+        app.add_system::<ExclusiveMarker>(CoreStage::Startup, register_sprite_loaders);
+        app.add_system::<ExclusiveMarker>(CoreStage::PreUpdate, spawn_tiled_maps_system);
+        app.add_system::<ExclusiveMarker>(CoreStage::Update, sprite_animation_system);
+        app.add_system::<(
+            FunctionMarker,
+            Query<'static, (&AtlasSprite, &mut Sprite)>,
+            Res<'static, AssetServer>,
+        )>(CoreStage::PostUpdate, atlas_sprite_system);
+        app.add_system::<(
+            FunctionMarker,
+            ResMut<'static, SpriteBatcher>,
+            Query<'static, (&Sprite, &Transform)>,
+            Query<'static, &ZOrder>,
+        )>(CoreStage::PreRender, prepare_sprite_batches);
+        app.add_system::<ExclusiveMarker>(CoreStage::PreRender, prepare_tilemap_batches);
+    }
+}
+
+fn register_sprite_loaders(world: &mut World) {
+    let Some(queue) = world
+        .get_resource::<MaterialTextureQueue>()
+        .map(|q| q.clone())
+    else {
+        return;
+    };
+    if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
+        let asset_dir = asset_server.asset_dir().to_path_buf();
+        asset_server.register_loader(TextureAtlasLoader::new(queue.clone()));
+        asset_server.register_loader(TiledMapLoader::new(asset_dir, queue));
+    }
+}
+
 /// Initialize sprite rendering resources
 pub fn init_sprite_system(
     mut resources: ResMut<SpriteRenderResources>,
@@ -188,7 +244,7 @@ pub fn prepare_sprite_batches(
 
 /// Render sprites using batched instancing
 pub fn render_sprites(
-    mut gpu: ResMut<GpuContext>,
+    gpu: Res<GpuContext>,
     resources: Res<SpriteRenderResources>,
     batcher: Res<SpriteBatcher>,
     camera_uniform: Res<crate::CameraUniformBuffer>,
@@ -206,15 +262,11 @@ pub fn render_sprites(
     let sampler = resources.sampler.as_ref().unwrap();
 
     // Get current frame
-    let frame = match gpu.begin_frame() {
-        Some((frame, _view)) => frame,
+    let (frame, view) = match gpu.begin_frame() {
+        Some(frame) => frame,
         None => return,
     };
 
-    let view = frame
-        .texture
-        .create_view(&wgpu::TextureViewDescriptor::default());
-
     let mut encoder = gpu
         .device
         .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -0,0 +1,1166 @@
+//! Heightmap terrain.
+//!
+//! [`TerrainData`] holds a 16-bit heightmap and per-sample splat weights for
+//! up to [`MAX_TERRAIN_LAYERS`] texture layers. A [`Terrain`] component draws
+//! it as square chunks picked from a CDLOD quadtree: chunks near the viewer
+//! use every sample, and each level further away halves the resolution.
+//! Chunk edges that border a coarser chunk are snapped onto the coarse
+//! chunk's vertices, so neighbouring levels meet without cracks.
+//!
+//! [`Terrain::sculpt`] and [`Terrain::paint`] edit the data at runtime; only
+//! chunks touching the edited samples are remeshed, and the splat maps of
+//! the [`TerrainMaterial`] are re-uploaded after painting. The 3D physics
+//! plugin builds a matching heightfield collider from
+//! [`TerrainData::to_height_map`].
+
+use crate::camera::Camera;
+use crate::material::{terrain_shader, Material, MaterialTextureQueue};
+use crate::shader::Shader;
+use crate::texture::{Texture, TextureData, TextureFormat};
+use crate::tiled::normalize;
+use crate::Mesh;
+use crate::Vertex;
+use luminara_asset::{Asset, AssetId, AssetLoadError, AssetLoader, AssetServer, Handle};
+use luminara_core::shared_types::{App, AppInterface, CoreStage, Plugin, Query, World};
+use luminara_core::system::ExclusiveMarker;
+use luminara_core::{Component, Entity};
+use luminara_math::geometry::reeb_graph::HeightMap;
+use luminara_math::{Transform, UVec2, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Texture layers a terrain can blend
+pub const MAX_TERRAIN_LAYERS: usize = 8;
+
+/// Quads per side of the tiles that cache height bounds for LOD selection
+const BOUNDS_TILE: u32 = 16;
+
+/// Heightmap and splat weights of a terrain
+#[derive(Debug, Clone)]
+pub struct TerrainData {
+    /// Samples along X and Z
+    pub size: UVec2,
+    /// Heights from 0 to `height_scale`, in rows along X
+    pub heights: Vec<u16>,
+    /// Distance between neighbouring samples
+    pub spacing: f32,
+    /// Height of the largest sample value
+    pub height_scale: f32,
+    /// Weight of every layer at each sample, summing to 255
+    pub splat: Vec<[u8; MAX_TERRAIN_LAYERS]>,
+    /// Texture of each layer
+    pub layers: Vec<Handle<Texture>>,
+}
+
+impl Asset for TerrainData {
+    fn type_name() -> &'static str {
+        "TerrainData"
+    }
+}
+
+impl TerrainData {
+    /// Flat terrain covered by the first layer
+    pub fn new(size: UVec2, spacing: f32, height_scale: f32) -> Self {
+        let samples = (size.x * size.y) as usize;
+        Self::from_heights(size, vec![0; samples], spacing, height_scale)
+    }
+
+    /// Terrain with the given heights, covered by the first layer
+    pub fn from_heights(size: UVec2, heights: Vec<u16>, spacing: f32, height_scale: f32) -> Self {
+        assert!(
+            size.x >= 2 && size.y >= 2,
+            "terrain needs at least 2x2 samples"
+        );
+        assert_eq!(heights.len(), (size.x * size.y) as usize);
+        let samples = heights.len();
+        Self {
+            size,
+            heights,
+            spacing,
+            height_scale,
+            splat: vec![single_layer(0); samples],
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn with_layers(mut self, layers: Vec<Handle<Texture>>) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    fn index(&self, x: u32, z: u32) -> usize {
+        (z.min(self.size.y - 1) * self.size.x + x.min(self.size.x - 1)) as usize
+    }
+
+    /// Quads along X and Z
+    pub fn quads(&self) -> UVec2 {
+        self.size - 1
+    }
+
+    /// Size of the terrain in local units
+    pub fn extent(&self) -> Vec2 {
+        self.quads().as_vec2() * self.spacing
+    }
+
+    /// Height of sample (`x`, `z`), clamped to the map
+    pub fn sample_height(&self, x: u32, z: u32) -> f32 {
+        self.heights[self.index(x, z)] as f32 / u16::MAX as f32 * self.height_scale
+    }
+
+    /// Height at a terrain-local XZ position, interpolated between samples
+    pub fn height_at(&self, position: Vec2) -> f32 {
+        let p = (position / self.spacing).clamp(Vec2::ZERO, self.quads().as_vec2());
+        let (x, z) = (p.x.floor() as u32, p.y.floor() as u32);
+        let (fx, fz) = (p.x - x as f32, p.y - z as f32);
+        let near = lerp(self.sample_height(x, z), self.sample_height(x + 1, z), fx);
+        let far = lerp(
+            self.sample_height(x, z + 1),
+            self.sample_height(x + 1, z + 1),
+            fx,
+        );
+        lerp(near, far, fz)
+    }
+
+    /// Surface normal at sample (`x`, `z`), from central differences
+    pub fn normal(&self, x: u32, z: u32) -> Vec3 {
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(self.size.x - 1));
+        let (z0, z1) = (z.saturating_sub(1), (z + 1).min(self.size.y - 1));
+        let dx = (self.sample_height(x1, z) - self.sample_height(x0, z))
+            / ((x1 - x0) as f32 * self.spacing);
+        let dz = (self.sample_height(x, z1) - self.sample_height(x, z0))
+            / ((z1 - z0) as f32 * self.spacing);
+        Vec3::new(-dx, 1.0, -dz).normalize()
+    }
+
+    /// Heights in local units, for pathfinding and the physics heightfield
+    pub fn to_height_map(&self) -> HeightMap {
+        let heights = self
+            .heights
+            .iter()
+            .map(|&h| h as f32 / u16::MAX as f32 * self.height_scale)
+            .collect();
+        HeightMap::new(
+            heights,
+            self.size.x as usize,
+            self.size.y as usize,
+            Vec3::new(self.spacing, 1.0, self.spacing),
+        )
+    }
+
+    /// Splat weights as two RGBA8 maps, holding layers 0-3 and 4-7
+    pub fn splat_textures(&self) -> [TextureData; 2] {
+        [0, 4].map(|first| TextureData {
+            width: self.size.x,
+            height: self.size.y,
+            data: self
+                .splat
+                .iter()
+                .flat_map(|weights| weights[first..first + 4].iter().copied())
+                .collect(),
+            format: TextureFormat::Rgba8,
+        })
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn single_layer(layer: usize) -> [u8; MAX_TERRAIN_LAYERS] {
+    let mut weights = [0; MAX_TERRAIN_LAYERS];
+    weights[layer] = 255;
+    weights
+}
+
+/// Scale weights to sum to 255, putting the rounding error on the heaviest
+/// layer; all-zero weights select the first layer
+fn quantize_weights(weights: [f32; MAX_TERRAIN_LAYERS]) -> [u8; MAX_TERRAIN_LAYERS] {
+    let total: f32 = weights.iter().map(|w| w.max(0.0)).sum();
+    if total <= 0.0 {
+        return single_layer(0);
+    }
+    let mut quantized = weights.map(|w| (w.max(0.0) * 255.0 / total).round() as i32);
+    let heaviest = (0..MAX_TERRAIN_LAYERS)
+        .max_by(|&a, &b| weights[a].total_cmp(&weights[b]))
+        .unwrap_or(0);
+    quantized[heaviest] += 255 - quantized.iter().sum::<i32>();
+    quantized.map(|w| w.clamp(0, 255) as u8)
+}
+
+/// Chunk size and LOD distances of a [`Terrain`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainLodSettings {
+    /// Quads per chunk side
+    pub chunk_size: u32,
+    /// Number of levels; level `n` uses every `2^n`th sample
+    pub lod_levels: u32,
+    /// Distance up to which level 0 is used; each further level doubles it
+    pub lod_distance: f32,
+}
+
+impl Default for TerrainLodSettings {
+    fn default() -> Self {
+        Self {
+            chunk_size: 32,
+            lod_levels: 4,
+            lod_distance: 64.0,
+        }
+    }
+}
+
+impl TerrainLodSettings {
+    /// Distance in local units up to which chunks of `level` are used
+    pub fn range(&self, level: u32) -> f32 {
+        self.lod_distance * (1u32 << level) as f32
+    }
+}
+
+/// Quadtree node of a terrain: chunk (`x`, `z`) of the grid of `level`,
+/// covering `chunk_size * 2^level` quads per side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TerrainNode {
+    pub level: u32,
+    pub x: u32,
+    pub z: u32,
+}
+
+impl TerrainNode {
+    /// Samples between neighbouring vertices of the node's mesh
+    pub fn step(&self) -> u32 {
+        1 << self.level
+    }
+
+    /// First sample covered by the node
+    pub fn origin(&self, chunk_size: u32) -> UVec2 {
+        UVec2::new(self.x, self.z) * (chunk_size << self.level)
+    }
+
+    fn children(&self) -> [TerrainNode; 4] {
+        let (level, x, z) = (self.level - 1, self.x * 2, self.z * 2);
+        [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dz)| TerrainNode {
+            level,
+            x: x + dx,
+            z: z + dz,
+        })
+    }
+}
+
+/// A node chosen by [`Terrain::select_lod`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerrainChunkLod {
+    pub node: TerrainNode,
+    /// Level of the chunks across the -X, +X, -Z and +Z edges, or the node's
+    /// own level where the neighbour is not coarser
+    pub neighbors: [u32; 4],
+}
+
+/// How [`Terrain::sculpt`] changes heights under the brush
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SculptMode {
+    /// Add `strength` at the brush center
+    Raise,
+    /// Subtract `strength` at the brush center
+    Lower,
+    /// Move towards the given height by `strength` (0-1)
+    Flatten(f32),
+    /// Move towards the average of the neighbouring samples by `strength` (0-1)
+    Smooth,
+}
+
+/// Mesh and neighbour levels of a spawned chunk
+#[derive(Debug)]
+struct ChunkState {
+    entity: Entity,
+    mesh: Handle<Mesh>,
+    neighbors: [u32; 4],
+}
+
+/// Marker on the chunk entities spawned for a [`Terrain`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerrainChunk {
+    pub terrain: Entity,
+    pub node: TerrainNode,
+}
+
+impl Component for TerrainChunk {
+    fn type_name() -> &'static str {
+        "TerrainChunk"
+    }
+}
+
+/// Heightmap terrain, drawn in chunks by [`terrain_system`]
+///
+/// Edit the data through [`Terrain::sculpt`] and [`Terrain::paint`] so the
+/// affected chunks, splat maps and colliders are rebuilt.
+#[derive(Debug)]
+pub struct Terrain {
+    data: TerrainData,
+    pub lod: TerrainLodSettings,
+    /// Material of the chunks; its splat maps are filled in from the data
+    pub material: Option<Handle<TerrainMaterial>>,
+    /// Lowest and highest sample of every bounds tile
+    bounds: Vec<(u16, u16)>,
+    height_revision: u64,
+    splat_revision: u64,
+    /// Samples edited since the chunks were last meshed, as min..max
+    dirty: Option<(UVec2, UVec2)>,
+    meshed_lod: Option<TerrainLodSettings>,
+    chunks: HashMap<TerrainNode, ChunkState>,
+    splat_maps: Option<[Handle<Texture>; 2]>,
+    uploaded_splat: Option<u64>,
+}
+
+impl Component for Terrain {
+    fn type_name() -> &'static str {
+        "Terrain"
+    }
+}
+
+impl Terrain {
+    pub fn new(data: TerrainData) -> Self {
+        let mut terrain = Self {
+            data,
+            lod: TerrainLodSettings::default(),
+            material: None,
+            bounds: Vec::new(),
+            height_revision: 0,
+            splat_revision: 0,
+            dirty: None,
+            meshed_lod: None,
+            chunks: HashMap::new(),
+            splat_maps: None,
+            uploaded_splat: None,
+        };
+        let tiles = terrain.bounds_tiles();
+        terrain.bounds = vec![(0, 0); (tiles.x * tiles.y) as usize];
+        terrain.update_bounds(UVec2::ZERO, terrain.data.size);
+        terrain
+    }
+
+    pub fn with_lod(mut self, lod: TerrainLodSettings) -> Self {
+        self.lod = lod;
+        self
+    }
+
+    pub fn with_material(mut self, material: Handle<TerrainMaterial>) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn data(&self) -> &TerrainData {
+        &self.data
+    }
+
+    /// Bumped by every height edit
+    pub fn height_revision(&self) -> u64 {
+        self.height_revision
+    }
+
+    /// Bumped by every splat edit
+    pub fn splat_revision(&self) -> u64 {
+        self.splat_revision
+    }
+
+    fn bounds_tiles(&self) -> UVec2 {
+        (self.data.quads() + BOUNDS_TILE - 1) / BOUNDS_TILE
+    }
+
+    /// Recompute the bounds tiles containing samples `min..max`
+    fn update_bounds(&mut self, min: UVec2, max: UVec2) {
+        let tiles = self.bounds_tiles();
+        // A sample on a tile border belongs to the tiles on both sides
+        let first = min.saturating_sub(UVec2::ONE) / BOUNDS_TILE;
+        let last = ((max.saturating_sub(UVec2::ONE)) / BOUNDS_TILE).min(tiles - 1);
+        for tz in first.y..=last.y {
+            for tx in first.x..=last.x {
+                let start = UVec2::new(tx, tz) * BOUNDS_TILE;
+                let end = (start + BOUNDS_TILE).min(self.data.quads());
+                let mut range = (u16::MAX, 0);
+                for z in start.y..=end.y {
+                    for x in start.x..=end.x {
+                        let h = self.data.heights[self.data.index(x, z)];
+                        range = (range.0.min(h), range.1.max(h));
+                    }
+                }
+                self.bounds[(tz * tiles.x + tx) as usize] = range;
+            }
+        }
+    }
+
+    /// First and last sample covered by `node`, clamped to the map
+    fn node_samples(&self, node: TerrainNode) -> (UVec2, UVec2) {
+        let origin = node.origin(self.lod.chunk_size);
+        let span = self.lod.chunk_size << node.level;
+        (origin, (origin + span).min(self.data.quads()))
+    }
+
+    /// Local bounding box of `node`
+    fn node_aabb(&self, node: TerrainNode) -> (Vec3, Vec3) {
+        let (start, end) = self.node_samples(node);
+        let tiles = self.bounds_tiles();
+        let first = start / BOUNDS_TILE;
+        let last = ((end - 1) / BOUNDS_TILE).min(tiles - 1);
+        let mut range = (u16::MAX, 0);
+        for tz in first.y..=last.y {
+            for tx in first.x..=last.x {
+                let (low, high) = self.bounds[(tz * tiles.x + tx) as usize];
+                range = (range.0.min(low), range.1.max(high));
+            }
+        }
+        let height = |h: u16| h as f32 / u16::MAX as f32 * self.data.height_scale;
+        let spacing = self.data.spacing;
+        (
+            Vec3::new(
+                start.x as f32 * spacing,
+                height(range.0),
+                start.y as f32 * spacing,
+            ),
+            Vec3::new(
+                end.x as f32 * spacing,
+                height(range.1),
+                end.y as f32 * spacing,
+            ),
+        )
+    }
+
+    fn top_level(&self) -> u32 {
+        self.lod.lod_levels.max(1) - 1
+    }
+
+    /// Pick the chunks to draw for a viewer at terrain-local `viewer`
+    ///
+    /// Starting from the coarsest level, a node is split while the viewer is
+    /// closer to its bounds than the range of the next finer level.
+    pub fn select_lod(&self, viewer: Vec3) -> Vec<TerrainChunkLod> {
+        let top = self.top_level();
+        let span = self.lod.chunk_size << top;
+        let roots = (self.data.quads() + span - 1) / span;
+        let mut nodes = Vec::new();
+        for z in 0..roots.y {
+            for x in 0..roots.x {
+                self.select_node(TerrainNode { level: top, x, z }, viewer, &mut nodes);
+            }
+        }
+
+        let selected: HashSet<TerrainNode> = nodes.iter().copied().collect();
+        nodes
+            .into_iter()
+            .map(|node| TerrainChunkLod {
+                node,
+                neighbors: [0, 1, 2, 3].map(|edge| self.neighbor_level(node, edge, &selected)),
+            })
+            .collect()
+    }
+
+    fn select_node(&self, node: TerrainNode, viewer: Vec3, nodes: &mut Vec<TerrainNode>) {
+        let (min, max) = self.node_aabb(node);
+        let distance = viewer.clamp(min, max).distance(viewer);
+        if node.level == 0 || distance >= self.lod.range(node.level - 1) {
+            nodes.push(node);
+            return;
+        }
+        for child in node.children() {
+            if child
+                .origin(self.lod.chunk_size)
+                .cmplt(self.data.quads())
+                .all()
+            {
+                self.select_node(child, viewer, nodes);
+            }
+        }
+    }
+
+    /// Level of the selected node across `edge` (-X, +X, -Z, +Z) if it is
+    /// coarser than `node`
+    fn neighbor_level(
+        &self,
+        node: TerrainNode,
+        edge: usize,
+        selected: &HashSet<TerrainNode>,
+    ) -> u32 {
+        // Neighbouring cell on the grid of level-0 chunks
+        let (x, z) = (node.x << node.level, node.z << node.level);
+        let cell = match edge {
+            0 => x.checked_sub(1).map(|x| (x, z)),
+            1 => Some((x + (1 << node.level), z)),
+            2 => z.checked_sub(1).map(|z| (x, z)),
+            _ => Some((x, z + (1 << node.level))),
+        };
+        let Some((x, z)) = cell else {
+            return node.level;
+        };
+        (node.level + 1..=self.top_level())
+            .find(|&level| {
+                selected.contains(&TerrainNode {
+                    level,
+                    x: x >> level,
+                    z: z >> level,
+                })
+            })
+            .unwrap_or(node.level)
+    }
+
+    /// Mesh of a selected chunk in terrain-local space
+    ///
+    /// Vertices sit on every `2^level`th sample; UVs span the whole terrain
+    /// from 0 to 1. Edges towards coarser neighbours follow the neighbour's
+    /// vertices.
+    pub fn chunk_mesh(&self, chunk: &TerrainChunkLod) -> Mesh {
+        let node = chunk.node;
+        let step = node.step();
+        let (start, end) = self.node_samples(node);
+        let columns = (end.x - start.x).div_ceil(step);
+        let rows = (end.y - start.y).div_ceil(step);
+        let quads = self.data.quads();
+        let spacing = self.data.spacing;
+
+        let coarse = |edge: usize| Some(chunk.neighbors[edge]).filter(|&level| level > node.level);
+        let mut vertices = Vec::with_capacity(((columns + 1) * (rows + 1)) as usize);
+        for j in 0..=rows {
+            for i in 0..=columns {
+                let x = (start.x + i * step).min(end.x);
+                let z = (start.y + j * step).min(end.y);
+                let height = if let Some(level) = coarse(0).filter(|_| i == 0) {
+                    self.snapped_height(x, z, level, false)
+                } else if let Some(level) = coarse(1).filter(|_| i == columns) {
+                    self.snapped_height(x, z, level, false)
+                } else if let Some(level) = coarse(2).filter(|_| j == 0) {
+                    self.snapped_height(x, z, level, true)
+                } else if let Some(level) = coarse(3).filter(|_| j == rows) {
+                    self.snapped_height(x, z, level, true)
+                } else {
+                    self.data.sample_height(x, z)
+                };
+                vertices.push(Vertex {
+                    position: [x as f32 * spacing, height, z as f32 * spacing],
+                    normal: self.data.normal(x, z).to_array(),
+                    uv: [x as f32 / quads.x as f32, z as f32 / quads.y as f32],
+                    tangent: [1.0, 0.0, 0.0, 1.0],
+                });
+            }
+        }
+
+        let mut indices = Vec::with_capacity((columns * rows * 6) as usize);
+        let row = columns + 1;
+        for j in 0..rows {
+            for i in 0..columns {
+                let a = j * row + i;
+                let (b, c) = (a + 1, a + row);
+                indices.extend_from_slice(&[a, c, b, b, c, c + 1]);
+            }
+        }
+        Mesh::new(vertices, indices)
+    }
+
+    /// Height at sample (`x`, `z`) on the edge of a chunk of `level`, running
+    /// along X if `along_x` and along Z otherwise
+    fn snapped_height(&self, x: u32, z: u32, level: u32, along_x: bool) -> f32 {
+        let step = 1 << level;
+        let (p, limit) = if along_x {
+            (x, self.data.quads().x)
+        } else {
+            (z, self.data.quads().y)
+        };
+        let p0 = p - p % step;
+        let p1 = (p0 + step).min(limit);
+        if p == p0 || p1 == p0 {
+            return self.data.sample_height(x, z);
+        }
+        let t = (p - p0) as f32 / (p1 - p0) as f32;
+        if along_x {
+            lerp(
+                self.data.sample_height(p0, z),
+                self.data.sample_height(p1, z),
+                t,
+            )
+        } else {
+            lerp(
+                self.data.sample_height(x, p0),
+                self.data.sample_height(x, p1),
+                t,
+            )
+        }
+    }
+
+    /// Samples within `radius` of `center`, as min..max
+    fn brush_region(&self, center: Vec2, radius: f32) -> Option<(UVec2, UVec2)> {
+        if radius <= 0.0 {
+            return None;
+        }
+        let size = self.data.size.as_vec2();
+        let min = ((center - radius) / self.data.spacing)
+            .floor()
+            .clamp(Vec2::ZERO, size);
+        let max = ((center + radius) / self.data.spacing + 1.0)
+            .floor()
+            .clamp(Vec2::ZERO, size);
+        let (min, max) = (min.as_uvec2(), max.as_uvec2());
+        (min.cmplt(max).all()).then_some((min, max))
+    }
+
+    /// Smooth falloff from 1 at `center` to 0 at `radius`
+    fn brush_weight(&self, center: Vec2, radius: f32, x: u32, z: u32) -> f32 {
+        let position = Vec2::new(x as f32, z as f32) * self.data.spacing;
+        let d = position.distance(center) / radius;
+        if d >= 1.0 {
+            0.0
+        } else {
+            (1.0 - d * d).powi(2)
+        }
+    }
+
+    fn mark_dirty(&mut self, min: UVec2, max: UVec2) {
+        self.dirty = Some(match self.dirty {
+            Some((a, b)) => (a.min(min), b.max(max)),
+            None => (min, max),
+        });
+    }
+
+    /// Change the heights within `radius` of the terrain-local XZ `center`
+    pub fn sculpt(&mut self, center: Vec2, radius: f32, strength: f32, mode: SculptMode) {
+        let Some((min, max)) = self.brush_region(center, radius) else {
+            return;
+        };
+        let to_units = u16::MAX as f32 / self.data.height_scale;
+        let source = matches!(mode, SculptMode::Smooth).then(|| self.data.clone());
+        let mut changed = false;
+        for z in min.y..max.y {
+            for x in min.x..max.x {
+                let weight = self.brush_weight(center, radius, x, z);
+                if weight <= 0.0 {
+                    continue;
+                }
+                let height = self.data.sample_height(x, z);
+                let target = match mode {
+                    SculptMode::Raise => height + strength * weight,
+                    SculptMode::Lower => height - strength * weight,
+                    SculptMode::Flatten(level) => {
+                        lerp(height, level, (strength * weight).clamp(0.0, 1.0))
+                    }
+                    SculptMode::Smooth => {
+                        let source = source.as_ref().unwrap();
+                        let average = [
+                            (x, z),
+                            (x.saturating_sub(1), z),
+                            (x + 1, z),
+                            (x, z.saturating_sub(1)),
+                            (x, z + 1),
+                        ]
+                        .iter()
+                        .map(|&(x, z)| source.sample_height(x, z))
+                        .sum::<f32>()
+                            / 5.0;
+                        lerp(height, average, (strength * weight).clamp(0.0, 1.0))
+                    }
+                };
+                let index = self.data.index(x, z);
+                let value = (target * to_units).round().clamp(0.0, u16::MAX as f32) as u16;
+                changed |= self.data.heights[index] != value;
+                self.data.heights[index] = value;
+            }
+        }
+        if changed {
+            self.update_bounds(min, max);
+            self.mark_dirty(min, max);
+            self.height_revision += 1;
+        }
+    }
+
+    /// Blend `layer` into the splat weights within `radius` of the
+    /// terrain-local XZ `center`; `strength` 1 fully covers the center
+    pub fn paint(&mut self, center: Vec2, radius: f32, layer: usize, strength: f32) {
+        if layer >= MAX_TERRAIN_LAYERS {
+            log::warn!("Terrain layer {} out of range", layer);
+            return;
+        }
+        let Some((min, max)) = self.brush_region(center, radius) else {
+            return;
+        };
+        let mut changed = false;
+        for z in min.y..max.y {
+            for x in min.x..max.x {
+                let amount = (strength * self.brush_weight(center, radius, x, z)).clamp(0.0, 1.0);
+                if amount <= 0.0 {
+                    continue;
+                }
+                let index = self.data.index(x, z);
+                let mut weights = self.data.splat[index].map(|w| w as f32 * (1.0 - amount));
+                weights[layer] += 255.0 * amount;
+                let weights = quantize_weights(weights);
+                changed |= self.data.splat[index] != weights;
+                self.data.splat[index] = weights;
+            }
+        }
+        if changed {
+            self.splat_revision += 1;
+        }
+    }
+
+    /// Upload the splat maps after painting and point the material at them
+    fn upload_splat_maps(&mut self, asset_server: &AssetServer) {
+        let Some(material) = self.material.clone() else {
+            return;
+        };
+        if self.uploaded_splat != Some(self.splat_revision) || self.splat_maps.is_none() {
+            let [low, high] = self.data.splat_textures().map(Texture::new);
+            self.splat_maps = Some(match &self.splat_maps {
+                Some([a, b]) => [
+                    asset_server.insert(a.id(), low),
+                    asset_server.insert(b.id(), high),
+                ],
+                None => [asset_server.add(low), asset_server.add(high)],
+            });
+            self.uploaded_splat = Some(self.splat_revision);
+        }
+
+        let (Some(current), Some([low, high])) = (asset_server.get(&material), &self.splat_maps)
+        else {
+            return;
+        };
+        if current.splat_0.as_ref() != Some(low) || current.splat_1.as_ref() != Some(high) {
+            let mut updated = (*current).clone();
+            updated.splat_0 = Some(low.clone());
+            updated.splat_1 = Some(high.clone());
+            asset_server.insert(material.id(), updated);
+        }
+    }
+}
+
+/// Material of terrain chunks: blends up to [`MAX_TERRAIN_LAYERS`] layer
+/// textures by the terrain's splat weights
+///
+/// `splat_0` and `splat_1` hold the weights of layers 0-3 and 4-7 and are
+/// set by [`terrain_system`].
+#[derive(Debug, Clone, Serialize, Deserialize, luminara_reflect_derive::Reflect)]
+pub struct TerrainMaterial {
+    /// Repeats of the layer textures across the terrain
+    pub tiling: f32,
+    pub roughness: f32,
+    #[serde(default)]
+    pub splat_0: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub splat_1: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub layer_0: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub layer_1: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub layer_2: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub layer_3: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub layer_4: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub layer_5: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub layer_6: Option<Handle<Texture>>,
+    #[serde(default)]
+    pub layer_7: Option<Handle<Texture>>,
+}
+
+impl Default for TerrainMaterial {
+    fn default() -> Self {
+        Self {
+            tiling: 64.0,
+            roughness: 0.9,
+            splat_0: None,
+            splat_1: None,
+            layer_0: None,
+            layer_1: None,
+            layer_2: None,
+            layer_3: None,
+            layer_4: None,
+            layer_5: None,
+            layer_6: None,
+            layer_7: None,
+        }
+    }
+}
+
+impl TerrainMaterial {
+    /// Material using `layers` as layer textures 0 and up
+    pub fn from_layers(layers: &[Handle<Texture>]) -> Self {
+        let mut material = Self::default();
+        let slots = [
+            &mut material.layer_0,
+            &mut material.layer_1,
+            &mut material.layer_2,
+            &mut material.layer_3,
+            &mut material.layer_4,
+            &mut material.layer_5,
+            &mut material.layer_6,
+            &mut material.layer_7,
+        ];
+        for (slot, layer) in slots.into_iter().zip(layers) {
+            *slot = Some(layer.clone());
+        }
+        material
+    }
+}
+
+impl Asset for TerrainMaterial {
+    fn type_name() -> &'static str {
+        "TerrainMaterial"
+    }
+}
+
+impl Material for TerrainMaterial {
+    fn shader(&self) -> Handle<Shader> {
+        terrain_shader()
+    }
+}
+
+/// On-disk form of a terrain, with paths relative to the file, e.g.
+///
+/// ```ron
+/// (
+///     heightmap: "island_height.png",
+///     spacing: 1.0,
+///     height_scale: 120.0,
+///     splat: ["island_splat_0.png", "island_splat_1.png"],
+///     layers: ["textures/grass.png", "textures/rock.png", "textures/sand.png"],
+/// )
+/// ```
+#[derive(Deserialize)]
+struct TerrainFile {
+    heightmap: String,
+    #[serde(default = "default_spacing")]
+    spacing: f32,
+    height_scale: f32,
+    #[serde(default)]
+    splat: Vec<String>,
+    #[serde(default)]
+    layers: Vec<String>,
+}
+
+fn default_spacing() -> f32 {
+    1.0
+}
+
+/// Loads `.terrain.ron` files
+///
+/// Heightmaps are 16-bit grayscale PNGs or square little-endian `.r16`
+/// files; splat maps are RGBA images holding four layer weights each.
+/// Layer textures are queued on the [`MaterialTextureQueue`].
+pub struct TerrainLoader {
+    asset_dir: PathBuf,
+    textures: MaterialTextureQueue,
+}
+
+impl TerrainLoader {
+    pub fn new(asset_dir: impl Into<PathBuf>, textures: MaterialTextureQueue) -> Self {
+        Self {
+            asset_dir: asset_dir.into(),
+            textures,
+        }
+    }
+
+    /// Asset path of the texture `file`, given relative to the file at `base`
+    fn layer_texture(&self, base: &Path, file: &str) -> Handle<Texture> {
+        let resolved = normalize(&base.parent().unwrap_or(Path::new("")).join(file));
+        let relative = resolved
+            .strip_prefix(normalize(&self.asset_dir))
+            .unwrap_or(&resolved);
+        let path = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.textures.push(path.clone());
+        Handle::new(AssetId::from_path(&path), 0)
+    }
+}
+
+fn read_relative(base: &Path, file: &str) -> Result<(PathBuf, Vec<u8>), AssetLoadError> {
+    let path = normalize(&base.parent().unwrap_or(Path::new("")).join(file));
+    let bytes = std::fs::read(&path)?;
+    Ok((path, bytes))
+}
+
+fn image_error(path: &Path, error: image::ImageError) -> AssetLoadError {
+    AssetLoadError::Parse(format!("{}: {}", path.display(), error))
+}
+
+/// Decode a 16-bit heightmap
+fn decode_heightmap(path: &Path, bytes: &[u8]) -> Result<(UVec2, Vec<u16>), AssetLoadError> {
+    if path.extension().and_then(|e| e.to_str()) == Some("r16") {
+        let samples = bytes.len() / 2;
+        let side = (samples as f64).sqrt() as u32;
+        if !bytes.len().is_multiple_of(2) || (side * side) as usize != samples {
+            return Err(AssetLoadError::Parse(format!(
+                "{}: raw heightmap is not square",
+                path.display()
+            )));
+        }
+        let heights = bytes
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        return Ok((UVec2::splat(side), heights));
+    }
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| image_error(path, e))?
+        .to_luma16();
+    Ok((UVec2::new(image.width(), image.height()), image.into_raw()))
+}
+
+impl AssetLoader for TerrainLoader {
+    type Asset = TerrainData;
+
+    fn extensions(&self) -> &[&str] {
+        &["terrain.ron"]
+    }
+
+    fn load(&self, bytes: &[u8], path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let source = std::str::from_utf8(bytes)
+            .map_err(|e| AssetLoadError::Parse(format!("Terrain is not UTF-8: {}", e)))?;
+        let file: TerrainFile =
+            ron::from_str(source).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+        if file.splat.len() > 2 || file.layers.len() > MAX_TERRAIN_LAYERS {
+            return Err(AssetLoadError::Parse(format!(
+                "Terrains have at most 2 splat maps and {} layers",
+                MAX_TERRAIN_LAYERS
+            )));
+        }
+
+        let (height_path, height_bytes) = read_relative(path, &file.heightmap)?;
+        let (size, heights) = decode_heightmap(&height_path, &height_bytes)?;
+        if size.x < 2 || size.y < 2 {
+            return Err(AssetLoadError::Parse(format!(
+                "{}: heightmap needs at least 2x2 samples",
+                height_path.display()
+            )));
+        }
+        let mut data = TerrainData::from_heights(size, heights, file.spacing, file.height_scale);
+
+        let mut weights = vec![[0.0; MAX_TERRAIN_LAYERS]; data.splat.len()];
+        for (map, splat) in file.splat.iter().enumerate() {
+            let (splat_path, splat_bytes) = read_relative(path, splat)?;
+            let image = image::load_from_memory(&splat_bytes)
+                .map_err(|e| image_error(&splat_path, e))?
+                .to_rgba8();
+            // Nearest sample when the splat map's size differs from the heightmap
+            for z in 0..size.y {
+                let v = z * image.height() / size.y;
+                for x in 0..size.x {
+                    let pixel = image.get_pixel(x * image.width() / size.x, v);
+                    let sample = &mut weights[(z * size.x + x) as usize];
+                    for channel in 0..4 {
+                        sample[map * 4 + channel] = pixel[channel] as f32;
+                    }
+                }
+            }
+        }
+        if !file.splat.is_empty() {
+            data.splat = weights.into_iter().map(quantize_weights).collect();
+        }
+
+        data.layers = file
+            .layers
+            .iter()
+            .map(|layer| self.layer_texture(path, layer))
+            .collect();
+        Ok(data)
+    }
+}
+
+/// Heightmap terrain: the `.terrain.ron` loader, the terrain material, and
+/// chunk LOD updates
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn name(&self) -> &str {
+        "TerrainPlugin"
+    }
+
+    fn build(&self, app: &mut App) {
+        if app.world.get_resource::<MaterialTextureQueue>().is_none() {
+            app.insert_resource(MaterialTextureQueue::default());
+        }
+        app.add_system::<ExclusiveMarker>(CoreStage::Startup, register_terrain_loader);
+        app.add_system::<ExclusiveMarker>(CoreStage::PreUpdate, spawn_terrains_system);
+        app.add_system::<ExclusiveMarker>(CoreStage::PostUpdate, terrain_system);
+    }
+}
+
+fn register_terrain_loader(world: &mut World) {
+    let Some(queue) = world
+        .get_resource::<MaterialTextureQueue>()
+        .map(|q| q.clone())
+    else {
+        return;
+    };
+    if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
+        let asset_dir = asset_server.asset_dir().to_path_buf();
+        asset_server.register_loader(TerrainLoader::new(asset_dir, queue));
+    }
+}
+
+/// Add a [`Terrain`] with a [`TerrainMaterial`] of its layers to entities
+/// holding a loaded `Handle<TerrainData>`
+pub fn spawn_terrains_system(world: &mut World) {
+    let pending: Vec<(Entity, Handle<TerrainData>)> =
+        Query::<(Entity, &Handle<TerrainData>)>::new(world)
+            .iter()
+            .map(|(entity, handle)| (entity, handle.clone()))
+            .collect();
+
+    let mut spawned = Vec::new();
+    {
+        let Some(asset_server) = world.get_resource::<AssetServer>() else {
+            return;
+        };
+        for (entity, handle) in pending {
+            if world.get_component::<Terrain>(entity).is_some() {
+                continue;
+            }
+            if let Some(data) = asset_server.get(&handle) {
+                let material = asset_server.add(TerrainMaterial::from_layers(&data.layers));
+                spawned.push((
+                    entity,
+                    Terrain::new((*data).clone()).with_material(material),
+                ));
+            }
+        }
+    }
+
+    for (entity, terrain) in spawned {
+        if let Err(e) = world.add_component(entity, terrain) {
+            log::error!("Failed to add a terrain: {:?}", e);
+        }
+    }
+}
+
+/// System that keeps the chunk entities of every [`Terrain`] up to date
+/// (Exclusive system — spawns and despawns chunk entities)
+///
+/// Chunks are selected around the first camera. Chunks that left the
+/// selection are despawned; chunks whose samples were edited or whose
+/// neighbours changed level are remeshed in place.
+pub fn terrain_system(world: &mut World) {
+    let Some(viewer) = Query::<(&Camera, &Transform)>::new(world)
+        .iter()
+        .next()
+        .map(|(_, transform)| transform.translation)
+    else {
+        return;
+    };
+    let terrains: Vec<(Entity, Transform)> = Query::<(Entity, &Terrain)>::new(world)
+        .iter()
+        .map(|(entity, _)| {
+            let transform = world.get_component::<Transform>(entity).copied();
+            (entity, transform.unwrap_or(Transform::IDENTITY))
+        })
+        .collect();
+
+    for (entity, transform) in terrains {
+        update_terrain_chunks(world, entity, transform, viewer);
+    }
+}
+
+fn update_terrain_chunks(world: &mut World, entity: Entity, transform: Transform, viewer: Vec3) {
+    let local_viewer = transform
+        .compute_matrix()
+        .inverse()
+        .transform_point3(viewer);
+    let mut despawned = Vec::new();
+    let mut created = Vec::new();
+    let material;
+    {
+        let Some(asset_server) = world.get_resource::<AssetServer>() else {
+            return;
+        };
+        let Some(terrain) = world.get_component_mut::<Terrain>(entity) else {
+            return;
+        };
+        terrain.upload_splat_maps(&asset_server);
+        material = terrain.material.clone();
+
+        let selected = terrain.select_lod(local_viewer);
+        let dirty = terrain.dirty.take();
+        let relod = terrain.meshed_lod != Some(terrain.lod);
+        terrain.meshed_lod = Some(terrain.lod);
+
+        let keep: HashSet<TerrainNode> = selected.iter().map(|chunk| chunk.node).collect();
+        terrain.chunks.retain(|node, chunk| {
+            let kept = !relod && keep.contains(node);
+            if !kept {
+                despawned.push(chunk.entity);
+            }
+            kept
+        });
+
+        for chunk in &selected {
+            // Normals reach one sample past an edit
+            let touched = dirty.is_some_and(|(min, max)| {
+                let (start, end) = terrain.node_samples(chunk.node);
+                start.cmple(max).all() && (end + 1).cmpge(min).all()
+            });
+            let stale = match terrain.chunks.get(&chunk.node) {
+                Some(state) => {
+                    (touched || state.neighbors != chunk.neighbors).then(|| state.mesh.clone())
+                }
+                None => {
+                    created.push((*chunk, asset_server.add(terrain.chunk_mesh(chunk))));
+                    continue;
+                }
+            };
+            if let Some(mesh) = stale {
+                asset_server.insert(mesh.id(), terrain.chunk_mesh(chunk));
+                if let Some(state) = terrain.chunks.get_mut(&chunk.node) {
+                    state.neighbors = chunk.neighbors;
+                }
+            }
+        }
+    }
+
+    for chunk in despawned {
+        world.despawn(chunk);
+    }
+    let mut spawned = Vec::new();
+    for (chunk, mesh) in created {
+        let chunk_entity = world.spawn();
+        let _ = world.add_component(chunk_entity, mesh.clone());
+        let _ = world.add_component(chunk_entity, transform);
+        let _ = world.add_component(
+            chunk_entity,
+            TerrainChunk {
+                terrain: entity,
+                node: chunk.node,
+            },
+        );
+        spawned.push((chunk, chunk_entity, mesh));
+    }
+
+    let chunk_entities: Vec<Entity> = {
+        let Some(terrain) = world.get_component_mut::<Terrain>(entity) else {
+            return;
+        };
+        for (chunk, chunk_entity, mesh) in spawned {
+            terrain.chunks.insert(
+                chunk.node,
+                ChunkState {
+                    entity: chunk_entity,
+                    mesh,
+                    neighbors: chunk.neighbors,
+                },
+            );
+        }
+        terrain.chunks.values().map(|state| state.entity).collect()
+    };
+
+    // Chunks follow the terrain's transform and material
+    for chunk in chunk_entities {
+        if let Some(chunk_transform) = world.get_component_mut::<Transform>(chunk) {
+            if *chunk_transform != transform {
+                *chunk_transform = transform;
+            }
+        }
+        if let Some(material) = &material {
+            if world.get_component::<Handle<TerrainMaterial>>(chunk) != Some(material) {
+                let _ = world.add_component(chunk, material.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,1085 @@
+//! Text layout and world-space text.
+//!
+//! [`layout_text`] turns styled [`TextSpan`]s into positioned glyphs: it maps
+//! grapheme clusters to glyphs with per-span font fallback, applies kerning,
+//! wraps lines at word boundaries and between CJK characters (with the usual
+//! kinsoku rules for punctuation) and aligns the lines. Ligatures and complex
+//! scripts that need contextual shaping are out of scope. The result is drawn
+//! through a [`GlyphAtlas`], either by the overlay or as a [`Text3d`] mesh,
+//! which the [`Text3dNode`] draws into the scene.
+
+use crate::camera::RenderLayers;
+use crate::font::{Font, FontFace, FontLoader};
+use crate::glyph_atlas::{GlyphAtlas, GlyphAtlasError, GlyphRasterMode};
+use crate::render_graph::{slots, PassSlots, RenderContext, RenderNode};
+use crate::render_target::{collect_camera_views, RenderTargets, TargetKey};
+use crate::shader_preprocessor::{ShaderComposer, ShaderDefs};
+use crate::skinning::global_matrix;
+use crate::RenderError;
+use luminara_asset::{AssetId, AssetServer, Handle};
+use luminara_core::system::ExclusiveMarker;
+use luminara_core::{
+    App, AppInterface, Component, CoreStage, Entity, Plugin, Query, Resource, World,
+};
+use luminara_math::{Color, Vec2, Vec3};
+use std::ops::Range;
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
+use wgpu::util::DeviceExt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    pub font: Handle<Font>,
+    /// Tried in order for characters `font` does not cover
+    pub fallbacks: Vec<Handle<Font>>,
+    /// Font size in pixels
+    pub size: f32,
+    pub color: Color,
+}
+
+impl TextStyle {
+    pub fn new(font: Handle<Font>, size: f32) -> Self {
+        Self {
+            font,
+            fallbacks: Vec::new(),
+            size,
+            color: Color::WHITE,
+        }
+    }
+}
+
+/// A run of text in one style
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+impl TextSpan {
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Self {
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretch wrapped lines to the full width; the last line stays left
+    Justify,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayoutOptions {
+    /// Wrap lines longer than this many pixels
+    pub max_width: Option<f32>,
+    pub align: TextAlign,
+    /// Multiplier on the font's line spacing
+    pub line_height: f32,
+}
+
+impl Default for TextLayoutOptions {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            align: TextAlign::Left,
+            line_height: 1.0,
+        }
+    }
+}
+
+/// Resolves a font handle to its face, e.g. [`asset_fonts`]
+pub type FontLookup<'a> = dyn Fn(&Handle<Font>) -> Option<Arc<dyn FontFace>> + 'a;
+
+/// A font used by a layout
+#[derive(Clone)]
+pub struct LayoutFont {
+    pub id: AssetId,
+    pub face: Arc<dyn FontFace>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    /// Index into [`TextLayout::fonts`]
+    pub font: usize,
+    pub glyph: u16,
+    pub size: f32,
+    /// Pen position on the baseline, in pixels from the top-left (Y down)
+    pub position: Vec2,
+    pub color: Color,
+    pub span: usize,
+    /// Byte offset of the glyph's cluster in its span's text
+    pub byte_index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    /// Range into [`TextLayout::glyphs`]
+    pub glyphs: Range<usize>,
+    /// Width without trailing whitespace
+    pub width: f32,
+    pub baseline: f32,
+    pub ascent: f32,
+    pub descent: f32,
+}
+
+#[derive(Clone, Default)]
+pub struct TextLayout {
+    pub fonts: Vec<LayoutFont>,
+    pub glyphs: Vec<PositionedGlyph>,
+    pub lines: Vec<TextLine>,
+    pub size: Vec2,
+}
+
+/// A textured quad for one glyph, in layout pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphQuad {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    pub color: Color,
+}
+
+impl TextLayout {
+    /// Rasterize the glyphs into `atlas` and build their quads
+    pub fn quads(&self, atlas: &mut GlyphAtlas) -> Result<Vec<GlyphQuad>, GlyphAtlasError> {
+        // Insert everything first: growing the atlas invalidates earlier UVs
+        let mut placed = Vec::with_capacity(self.glyphs.len());
+        for glyph in &self.glyphs {
+            let font = &self.fonts[glyph.font];
+            if let Some(entry) =
+                atlas.glyph(font.id, font.face.as_ref(), glyph.glyph, glyph.size)?
+            {
+                placed.push((glyph, entry));
+            }
+        }
+
+        Ok(placed
+            .into_iter()
+            .map(|(glyph, entry)| {
+                let scale = glyph.size / entry.raster_size;
+                let min = glyph.position + entry.offset * scale;
+                let (uv_min, uv_max) = atlas.uv_rect(&entry);
+                GlyphQuad {
+                    min,
+                    max: min + Vec2::new(entry.width as f32, entry.height as f32) * scale,
+                    uv_min,
+                    uv_max,
+                    color: glyph.color,
+                }
+            })
+            .collect())
+    }
+}
+
+// ── Line breaking ───────────────────────────────────────────────────────
+
+/// Characters that may not start a line (closing punctuation, small kana,
+/// prolonged sound mark)
+const NO_LINE_START: &str = "!%),.:;?]}¢°’”‰′″℃、。々〉》」』】〕〗〙〟ゝゞ゠ァィゥェォッャュョヮヵヶぁぃぅぇぉっゃゅょゎゕゖ・ーヽヾ！％），．：；？］｝｡｣､･ｧｨｩｪｫｬｭｮｯｰ…‥";
+
+/// Characters that may not end a line (opening punctuation)
+const NO_LINE_END: &str = "$([{£¥‘“〈《「『【〔〖〘〝＄（［｛｢￡￥";
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x2E80..=0x2FFF // radicals
+        | 0x3000..=0x303F // CJK punctuation
+        | 0x3040..=0x30FF // kana
+        | 0x31F0..=0x31FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xAC00..=0xD7AF // hangul syllables
+        | 0xF900..=0xFAFF
+        | 0xFF00..=0xFFEF // full and half width forms
+        | 0x20000..=0x3FFFF)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BreakClass {
+    Space,
+    Newline,
+    Other,
+}
+
+/// Whether a line may break between clusters starting with `before` and
+/// `after`
+fn can_break_between(before: char, before_class: BreakClass, after: char) -> bool {
+    if NO_LINE_START.contains(after) || NO_LINE_END.contains(before) {
+        return false;
+    }
+    if before_class == BreakClass::Space {
+        return !after.is_whitespace();
+    }
+    if after.is_whitespace() {
+        return false;
+    }
+    is_cjk(before) || is_cjk(after) || (before == '-' && after.is_alphanumeric())
+}
+
+// ── Layout ──────────────────────────────────────────────────────────────
+
+struct Cluster {
+    span: usize,
+    byte_index: usize,
+    first: char,
+    class: BreakClass,
+    /// (font, glyph) per character; marks follow their base
+    glyphs: Vec<(usize, u16)>,
+    advance: f32,
+    size: f32,
+    color: Color,
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+}
+
+/// Lay out `spans` with fonts resolved through `fonts`. Characters no
+/// available font covers are skipped.
+pub fn layout_text(
+    spans: &[TextSpan],
+    options: &TextLayoutOptions,
+    fonts: &FontLookup<'_>,
+) -> TextLayout {
+    let mut layout = TextLayout::default();
+    let clusters = shape(spans, fonts, &mut layout.fonts);
+    if clusters.is_empty() {
+        return layout;
+    }
+
+    // Break into lines as (cluster range, ended by a newline)
+    let mut lines: Vec<(Range<usize>, bool)> = Vec::new();
+    let mut start = 0;
+    let mut width = 0.0;
+    let mut last_break: Option<usize> = None;
+    let mut i = 0;
+    while i < clusters.len() {
+        let cluster = &clusters[i];
+        if cluster.class == BreakClass::Newline {
+            lines.push((start..i + 1, true));
+            start = i + 1;
+            width = 0.0;
+            last_break = None;
+            i += 1;
+            continue;
+        }
+        if i > start {
+            let previous = &clusters[i - 1];
+            if can_break_between(previous.first, previous.class, cluster.first) {
+                last_break = Some(i);
+            }
+        }
+
+        width += cluster.advance;
+        let overflows = options.max_width.is_some_and(|max| width > max + 1e-3);
+        if overflows && cluster.class != BreakClass::Space && i > start {
+            // Wrap at the last opportunity, or break the word if there is none
+            let at = last_break.filter(|&b| b > start).unwrap_or(i);
+            lines.push((start..at, false));
+            start = at;
+            width = clusters[start..=i].iter().map(|c| c.advance).sum();
+            last_break = None;
+        }
+        i += 1;
+    }
+    if start < clusters.len()
+        || clusters
+            .last()
+            .is_some_and(|c| c.class == BreakClass::Newline)
+    {
+        lines.push((start..clusters.len(), true));
+    }
+
+    let visible_width = |range: &Range<usize>| {
+        let end = clusters[range.clone()]
+            .iter()
+            .rposition(|c| c.class == BreakClass::Other)
+            .map_or(range.start, |p| range.start + p + 1);
+        clusters[range.start..end]
+            .iter()
+            .map(|c| c.advance)
+            .sum::<f32>()
+    };
+    let widest = lines
+        .iter()
+        .map(|(range, _)| visible_width(range))
+        .fold(0.0f32, f32::max);
+    let container = options.max_width.unwrap_or(widest);
+
+    let mut y = 0.0;
+    for (range, hard_end) in &lines {
+        let line_clusters = &clusters[range.clone()];
+        // Empty lines keep the height of their newline's font
+        let metrics = if line_clusters.is_empty() {
+            clusters
+                .get(range.start.saturating_sub(1))
+                .into_iter()
+                .collect::<Vec<_>>()
+        } else {
+            line_clusters.iter().collect()
+        };
+        let ascent = metrics.iter().map(|c| c.ascent).fold(0.0f32, f32::max);
+        let descent = metrics.iter().map(|c| c.descent).fold(0.0f32, f32::max);
+        let line_gap = metrics.iter().map(|c| c.line_gap).fold(0.0f32, f32::max);
+        let baseline = y + ascent;
+
+        let line_width = visible_width(range);
+        let visible_end = line_clusters
+            .iter()
+            .rposition(|c| c.class == BreakClass::Other)
+            .map_or(0, |p| p + 1);
+        let extra = (container - line_width).max(0.0);
+        let (mut x, gap_extra, space_extra) = match options.align {
+            TextAlign::Left => (0.0, 0.0, 0.0),
+            TextAlign::Center => (extra * 0.5, 0.0, 0.0),
+            TextAlign::Right => (extra, 0.0, 0.0),
+            TextAlign::Justify if *hard_end => (0.0, 0.0, 0.0),
+            TextAlign::Justify => {
+                let spaces = line_clusters[..visible_end]
+                    .iter()
+                    .filter(|c| c.class == BreakClass::Space)
+                    .count();
+                if spaces > 0 {
+                    (0.0, 0.0, extra / spaces as f32)
+                } else if visible_end > 1 {
+                    // Unspaced scripts spread the slack between characters
+                    (0.0, extra / (visible_end - 1) as f32, 0.0)
+                } else {
+                    (0.0, 0.0, 0.0)
+                }
+            }
+        };
+
+        let first_glyph = layout.glyphs.len();
+        for (index, cluster) in line_clusters.iter().enumerate() {
+            if cluster.class == BreakClass::Other {
+                for &(font, glyph) in &cluster.glyphs {
+                    layout.glyphs.push(PositionedGlyph {
+                        font,
+                        glyph,
+                        size: cluster.size,
+                        position: Vec2::new(x, baseline),
+                        color: cluster.color,
+                        span: cluster.span,
+                        byte_index: cluster.byte_index,
+                    });
+                }
+            }
+            x += cluster.advance;
+            if index + 1 < visible_end {
+                x += gap_extra;
+                if cluster.class == BreakClass::Space {
+                    x += space_extra;
+                }
+            }
+        }
+
+        let width = if space_extra > 0.0 || gap_extra > 0.0 {
+            container
+        } else {
+            line_width
+        };
+        layout.lines.push(TextLine {
+            glyphs: first_glyph..layout.glyphs.len(),
+            width,
+            baseline,
+            ascent,
+            descent,
+        });
+        y += (ascent + descent + line_gap) * options.line_height;
+    }
+
+    layout.size = Vec2::new(
+        layout.lines.iter().map(|l| l.width).fold(0.0f32, f32::max),
+        y,
+    );
+    layout
+}
+
+/// Map the spans to grapheme clusters with glyphs, advances and metrics
+fn shape(
+    spans: &[TextSpan],
+    fonts: &FontLookup<'_>,
+    layout_fonts: &mut Vec<LayoutFont>,
+) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for (span_index, span) in spans.iter().enumerate() {
+        let style = &span.style;
+        let chain: Vec<usize> = std::iter::once(&style.font)
+            .chain(&style.fallbacks)
+            .filter_map(|handle| resolve_font(handle, fonts, layout_fonts))
+            .collect();
+        let Some(&primary) = chain.first() else {
+            continue;
+        };
+
+        for (byte_index, grapheme) in span.text.grapheme_indices(true) {
+            let first = grapheme.chars().next().unwrap_or(' ');
+            let class = if grapheme.contains('\n') {
+                BreakClass::Newline
+            } else if first.is_whitespace() {
+                BreakClass::Space
+            } else {
+                BreakClass::Other
+            };
+
+            // The cluster uses the first font covering its base character
+            let found = chain.iter().find_map(|&font| {
+                layout_fonts[font]
+                    .face
+                    .glyph_id(first)
+                    .map(|glyph| (font, glyph))
+            });
+            let font = found.map_or(primary, |(font, _)| font);
+            let face = layout_fonts[font].face.clone();
+            let scale = style.size / face.units_per_em();
+
+            let mut glyphs = Vec::new();
+            let mut advance = 0.0;
+            if class == BreakClass::Other {
+                let Some((_, base)) = found else {
+                    continue;
+                };
+                glyphs.push((font, base));
+                advance = face.advance(base) * scale;
+                // Combining marks are drawn over the base without advancing
+                for mark in grapheme.chars().skip(1) {
+                    if let Some(glyph) = face.glyph_id(mark) {
+                        glyphs.push((font, glyph));
+                    }
+                }
+            } else if class == BreakClass::Space {
+                advance = face
+                    .glyph_id(first)
+                    .map_or(style.size * 0.25, |g| face.advance(g) * scale);
+            }
+
+            // Kerning against the previous glyph of the same font and size
+            if let (Some(previous), Some(&(_, glyph))) = (clusters.last_mut(), glyphs.first()) {
+                if let Some(&(previous_font, previous_glyph)) = previous.glyphs.first() {
+                    if previous_font == font && previous.size == style.size {
+                        previous.advance += face.kerning(previous_glyph, glyph) * scale;
+                    }
+                }
+            }
+
+            clusters.push(Cluster {
+                span: span_index,
+                byte_index,
+                first,
+                class,
+                glyphs,
+                advance,
+                size: style.size,
+                color: style.color,
+                ascent: face.ascent() * scale,
+                descent: face.descent() * scale,
+                line_gap: face.line_gap() * scale,
+            });
+        }
+    }
+    clusters
+}
+
+/// Index of `handle` in the layout's fonts, adding it on first use
+fn resolve_font(
+    handle: &Handle<Font>,
+    fonts: &FontLookup<'_>,
+    layout_fonts: &mut Vec<LayoutFont>,
+) -> Option<usize> {
+    if let Some(index) = layout_fonts.iter().position(|f| f.id == handle.id()) {
+        return Some(index);
+    }
+    let face = fonts(handle)?;
+    layout_fonts.push(LayoutFont {
+        id: handle.id(),
+        face,
+    });
+    Some(layout_fonts.len() - 1)
+}
+
+/// Resolve fonts from the asset server for [`layout_text`]
+pub fn asset_fonts(
+    assets: &AssetServer,
+) -> impl Fn(&Handle<Font>) -> Option<Arc<dyn FontFace>> + '_ {
+    move |handle| assets.get(handle).map(|font| font as Arc<dyn FontFace>)
+}
+
+// ── World-space text ────────────────────────────────────────────────────
+
+/// Text drawn in the world on the entity's XY plane, facing +Z
+#[derive(Debug, Clone)]
+pub struct Text3d {
+    pub spans: Vec<TextSpan>,
+    pub options: TextLayoutOptions,
+    /// Layout pixels per world unit
+    pub pixels_per_unit: f32,
+    /// Point of the text at the entity origin, from (0, 0) top-left to
+    /// (1, 1) bottom-right
+    pub anchor: Vec2,
+}
+
+impl Component for Text3d {
+    fn type_name() -> &'static str {
+        "Text3d"
+    }
+}
+
+impl Text3d {
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Self {
+            spans: vec![TextSpan::new(text, style)],
+            options: TextLayoutOptions::default(),
+            pixels_per_unit: 100.0,
+            anchor: Vec2::new(0.5, 0.5),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TextVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl TextVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+            wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32x4];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TextVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Geometry generated for a [`Text3d`] against the [`GlyphAtlas`] resource
+#[derive(Debug, Clone, Default)]
+pub struct Text3dMesh {
+    pub vertices: Vec<TextVertex>,
+    pub indices: Vec<u32>,
+    /// Layout size in world units
+    pub size: Vec2,
+    source: Option<(Vec<TextSpan>, TextLayoutOptions, f32, Vec2)>,
+    atlas_generation: u32,
+}
+
+impl Component for Text3dMesh {
+    fn type_name() -> &'static str {
+        "Text3dMesh"
+    }
+}
+
+impl Text3dMesh {
+    /// Mesh of `layout` with its glyphs rasterized into `atlas`, scaled to
+    /// `pixels_per_unit` with `anchor` at the origin
+    pub fn new(
+        layout: &TextLayout,
+        atlas: &mut GlyphAtlas,
+        pixels_per_unit: f32,
+        anchor: Vec2,
+    ) -> Result<Self, GlyphAtlasError> {
+        let quads = layout.quads(atlas)?;
+        let (vertices, indices) = build_text_mesh(&quads, layout.size, pixels_per_unit, anchor);
+        Ok(Self {
+            vertices,
+            indices,
+            size: layout.size / pixels_per_unit,
+            source: None,
+            atlas_generation: atlas.generation(),
+        })
+    }
+}
+
+/// Triangulate glyph quads into the local XY plane
+pub fn build_text_mesh(
+    quads: &[GlyphQuad],
+    layout_size: Vec2,
+    pixels_per_unit: f32,
+    anchor: Vec2,
+) -> (Vec<TextVertex>, Vec<u32>) {
+    let origin = layout_size * anchor;
+    let to_local = |p: Vec2| {
+        let p = (p - origin) / pixels_per_unit;
+        [p.x, -p.y, 0.0]
+    };
+
+    let mut vertices = Vec::with_capacity(quads.len() * 4);
+    let mut indices = Vec::with_capacity(quads.len() * 6);
+    for quad in quads {
+        let base = vertices.len() as u32;
+        let color: [f32; 4] = quad.color.into();
+        let corners = [
+            (
+                Vec2::new(quad.min.x, quad.max.y),
+                Vec2::new(quad.uv_min.x, quad.uv_max.y),
+            ),
+            (quad.max, quad.uv_max),
+            (
+                Vec2::new(quad.max.x, quad.min.y),
+                Vec2::new(quad.uv_max.x, quad.uv_min.y),
+            ),
+            (quad.min, quad.uv_min),
+        ];
+        for (position, uv) in corners {
+            vertices.push(TextVertex {
+                position: to_local(position),
+                uv: uv.to_array(),
+                color,
+            });
+        }
+        indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+    (vertices, indices)
+}
+
+/// Lay out changed [`Text3d`] components and rebuild their meshes
+pub fn text3d_layout_system(world: &mut World) {
+    let entities: Vec<Entity> = Query::<(Entity, &Text3d)>::new(&*world)
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    if entities.is_empty() {
+        return;
+    }
+    let mut updates = Vec::new();
+    {
+        let Some(assets) = world.get_resource::<AssetServer>() else {
+            return;
+        };
+        let Some(mut atlas) = world.get_resource_mut::<GlyphAtlas>() else {
+            return;
+        };
+        let fonts = asset_fonts(&assets);
+
+        for entity in entities {
+            let Some(text) = world.get_component::<Text3d>(entity) else {
+                continue;
+            };
+            let source = (
+                text.spans.clone(),
+                text.options.clone(),
+                text.pixels_per_unit,
+                text.anchor,
+            );
+            let up_to_date = world
+                .get_component::<Text3dMesh>(entity)
+                .is_some_and(|mesh| {
+                    mesh.source.as_ref() == Some(&source)
+                        && mesh.atlas_generation == atlas.generation()
+                        && !mesh.vertices.is_empty()
+                });
+            if up_to_date {
+                continue;
+            }
+
+            let layout = layout_text(&text.spans, &text.options, &fonts);
+            let mut mesh =
+                match Text3dMesh::new(&layout, &mut atlas, text.pixels_per_unit, text.anchor) {
+                    Ok(mesh) => mesh,
+                    Err(error) => {
+                        log::warn!("Text3d on {:?} not drawn: {}", entity, error);
+                        continue;
+                    }
+                };
+            mesh.source = Some(source);
+            updates.push((entity, mesh));
+        }
+    }
+
+    for (entity, mesh) in updates {
+        if let Some(existing) = world.get_component_mut::<Text3dMesh>(entity) {
+            *existing = mesh;
+        } else {
+            let _ = world.add_component(entity, mesh);
+        }
+    }
+}
+
+// ── Rendering ───────────────────────────────────────────────────────────
+
+/// Uniform of `shaders/text3d.wgsl`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Text3dUniform {
+    view_proj: [[f32; 4]; 4],
+    atlas_size: [f32; 2],
+    field_range: f32,
+    _padding: f32,
+}
+
+struct Text3dPipeline {
+    mode: GlyphRasterMode,
+    pipeline: wgpu::RenderPipeline,
+    camera_layout: wgpu::BindGroupLayout,
+    atlas_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+}
+
+impl Text3dPipeline {
+    /// Pipeline decoding atlases rasterized in `mode`
+    fn new(
+        device: &wgpu::Device,
+        composer: &ShaderComposer,
+        mode: GlyphRasterMode,
+    ) -> Option<Self> {
+        let mut defs = ShaderDefs::new();
+        if let Some(def) = mode.shader_def() {
+            defs = defs.with(def);
+        }
+        let composed = composer
+            .compose("text3d.wgsl", include_str!("../shaders/text3d.wgsl"), &defs)
+            .and_then(|composed| composed.validate().map(|_| composed));
+        let composed = match composed {
+            Ok(composed) => composed,
+            Err(e) => {
+                log::error!("Invalid text3d shader: {}", e);
+                return None;
+            }
+        };
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Text3d Shader"),
+            source: wgpu::ShaderSource::Wgsl(composed.source.as_str().into()),
+        });
+
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text3d Camera Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let atlas_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text3d Atlas Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text3d Pipeline Layout"),
+            bind_group_layouts: &[&camera_layout, &atlas_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text3d Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[TextVertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: slots::HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            // Text stays readable from behind, mirrored
+            primitive: wgpu::PrimitiveState {
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: slots::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Text3d Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Some(Self {
+            mode,
+            pipeline,
+            camera_layout,
+            atlas_layout,
+            sampler,
+        })
+    }
+}
+
+/// GPU state of the [`Text3dNode`], created on first use
+#[derive(Default)]
+pub struct Text3dRenderer {
+    pipeline: Option<Text3dPipeline>,
+    atlas_bind_group: Option<wgpu::BindGroup>,
+}
+
+impl Resource for Text3dRenderer {}
+
+impl Text3dRenderer {
+    /// Upload `atlas`, rebuilding the pipeline when its raster mode changed
+    /// and the bind group when its texture was recreated. False when the
+    /// shader can't be built.
+    fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        composer: &ShaderComposer,
+        atlas: &mut GlyphAtlas,
+    ) -> bool {
+        if self
+            .pipeline
+            .as_ref()
+            .is_none_or(|pipeline| pipeline.mode != atlas.mode())
+        {
+            self.pipeline = Text3dPipeline::new(device, composer, atlas.mode());
+            self.atlas_bind_group = None;
+        }
+        let recreated = atlas.upload(device, queue);
+        let (Some(pipeline), Some(view)) = (&self.pipeline, atlas.texture_view()) else {
+            return false;
+        };
+        if recreated || self.atlas_bind_group.is_none() {
+            self.atlas_bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Text3d Atlas Bind Group"),
+                layout: &pipeline.atlas_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&pipeline.sampler),
+                    },
+                ],
+            }));
+        }
+        true
+    }
+}
+
+/// Draws every [`Text3dMesh`] into the HDR target after the opaque and
+/// transparent geometry, depth tested against it without writing depth.
+/// Meshes built against an older [`GlyphAtlas::generation`] wait for
+/// [`text3d_layout_system`] to rebuild them.
+pub struct Text3dNode;
+
+impl Text3dNode {
+    pub const NAME: &'static str = "text3d";
+}
+
+impl RenderNode for Text3dNode {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn declare(&self, slots: &mut PassSlots) {
+        slots.read(slots::DEPTH).write(slots::HDR);
+    }
+
+    fn run<'a>(&self, context: &mut RenderContext<'a>) -> Result<(), RenderError> {
+        let resources = context.resources;
+        let (Some(world), Some(hdr_view), Some(depth_view)) = (
+            context.world,
+            resources.texture_view(slots::HDR),
+            resources.texture_view(slots::DEPTH),
+        ) else {
+            return Ok(());
+        };
+        let (Some(mut renderer), Some(mut atlas), Some(composer)) = (
+            world.get_resource_mut::<Text3dRenderer>(),
+            world.get_resource_mut::<GlyphAtlas>(),
+            world.get_resource::<ShaderComposer>(),
+        ) else {
+            return Ok(());
+        };
+
+        // Every text in world space, sharing one vertex and index buffer
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut texts = Vec::new();
+        for (entity, mesh) in Query::<(Entity, &Text3dMesh)>::new(world).iter() {
+            if mesh.indices.is_empty() || mesh.atlas_generation != atlas.generation() {
+                continue;
+            }
+            let matrix = global_matrix(world, entity);
+            let base = vertices.len() as u32;
+            vertices.extend(mesh.vertices.iter().map(|vertex| {
+                TextVertex {
+                    position: matrix
+                        .transform_point3(Vec3::from(vertex.position))
+                        .to_array(),
+                    ..*vertex
+                }
+            }));
+            let start = indices.len() as u32;
+            indices.extend(mesh.indices.iter().map(|index| index + base));
+            let layers = world
+                .get_component::<RenderLayers>(entity)
+                .copied()
+                .unwrap_or_default();
+            texts.push((start..indices.len() as u32, layers));
+        }
+        if texts.is_empty() {
+            return Ok(());
+        }
+
+        let device = context.device;
+        if !renderer.prepare(device, context.queue, &composer, &mut atlas) {
+            return Ok(());
+        }
+        let (Some(pipeline), Some(atlas_bind_group)) =
+            (&renderer.pipeline, &renderer.atlas_bind_group)
+        else {
+            return Ok(());
+        };
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Text3d Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Text3d Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let field_range = match atlas.mode() {
+            GlyphRasterMode::Coverage => 1.0,
+            _ => 2.0 * atlas.sdf_range,
+        };
+
+        let targets = world.get_resource::<RenderTargets>();
+        for view in collect_camera_views(world, context.target_size) {
+            let visible: Vec<&Range<u32>> = texts
+                .iter()
+                .filter(|(_, layers)| view.sees(layers))
+                .map(|(range, _)| range)
+                .collect();
+            if visible.is_empty() {
+                continue;
+            }
+            let (color_view, depth_view) = match view.key {
+                TargetKey::Window => (hdr_view, depth_view),
+                key => match targets.as_deref().and_then(|targets| targets.get(&key)) {
+                    Some(target) => (&target.hdr_view, &target.depth_view),
+                    None => continue,
+                },
+            };
+
+            let uniform = Text3dUniform {
+                view_proj: view.view_proj.to_cols_array_2d(),
+                atlas_size: [atlas.width() as f32, atlas.height() as f32],
+                field_range,
+                _padding: 0.0,
+            };
+            let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Text3d Uniform Buffer"),
+                contents: bytemuck::bytes_of(&uniform),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Text3d Camera Bind Group"),
+                layout: &pipeline.camera_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                }],
+            });
+
+            let mut render_pass = context
+                .encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Text3d Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: color_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+            let (x, y, width, height) = view.viewport;
+            render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+            render_pass.set_scissor_rect(x, y, width, height);
+            render_pass.set_pipeline(&pipeline.pipeline);
+            render_pass.set_bind_group(0, &camera_bind_group, &[]);
+            render_pass.set_bind_group(1, atlas_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            for range in visible {
+                render_pass.draw_indexed(range.clone(), 0, 0..1);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Font loading, the shared SDF glyph atlas and [`Text3d`] layout
+pub struct TextPlugin;
+
+impl Plugin for TextPlugin {
+    fn name(&self) -> &str {
+        "TextPlugin"
+    }
+
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GlyphAtlas::new(GlyphRasterMode::Sdf));
+        app.add_system::<ExclusiveMarker>(CoreStage::Startup, register_font_loader);
+        app.add_system::<ExclusiveMarker>(CoreStage::PreRender, text3d_layout_system);
+    }
+}
+
+fn register_font_loader(world: &mut World) {
+    if let Some(mut asset_server) = world.get_resource_mut::<AssetServer>() {
+        asset_server.register_loader(FontLoader);
+    }
+}
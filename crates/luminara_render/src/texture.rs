@@ -22,9 +22,32 @@ pub struct TextureData {
     pub format: TextureFormat,
 }
 
+/// How a texture is filtered and wrapped when sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplerSettings {
+    pub address_mode_u: wgpu::AddressMode,
+    pub address_mode_v: wgpu::AddressMode,
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        Self {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+        }
+    }
+}
+
 /// GPU texture resource
 pub struct Texture {
     pub data: TextureData,
+    pub sampler_settings: SamplerSettings,
     pub texture: Option<wgpu::Texture>,
     pub view: Option<wgpu::TextureView>,
     pub sampler: Option<wgpu::Sampler>,
@@ -68,6 +91,7 @@ impl Texture {
     pub fn new(data: TextureData) -> Self {
         Self {
             data,
+            sampler_settings: SamplerSettings::default(),
             texture: None,
             view: None,
             sampler: None,
@@ -179,13 +203,14 @@ impl Texture {
         );
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let settings = self.sampler_settings;
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_u: settings.address_mode_u,
+            address_mode_v: settings.address_mode_v,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            mag_filter: settings.mag_filter,
+            min_filter: settings.min_filter,
+            mipmap_filter: settings.mipmap_filter,
             ..Default::default()
         });
 
@@ -213,6 +238,12 @@ impl Texture {
         Self::new(texture_data)
     }
 
+    /// Create a texture to use as a camera's `RenderTarget::Texture`.
+    /// Only the size matters; cameras draw into a GPU copy owned by `RenderTargets`.
+    pub fn render_target(width: u32, height: u32) -> Self {
+        Self::solid_color(width, height, [0, 0, 0, 0])
+    }
+
     /// Create a checkerboard texture
     pub fn checkerboard(size: u32, color1: [u8; 4], color2: [u8; 4]) -> Self {
         let mut data = Vec::with_capacity((size * size * 4) as usize);
@@ -0,0 +1,442 @@
+//! Texture atlases for sprite sheets and tilesets.
+//!
+//! A [`TextureAtlas`] names rectangular frames of a single texture. Atlases
+//! are cut from a regular grid ([`TextureAtlas::from_grid`]), loaded from an
+//! `.atlas.json` sheet by [`TextureAtlasLoader`], or packed at runtime from
+//! loose images by [`TextureAtlasBuilder`]. [`AtlasSprite`] shows one frame
+//! of an atlas on the entity's [`Sprite`].
+
+use crate::material::MaterialTextureQueue;
+use crate::sprite::{Rect, Sprite};
+use crate::texture::{Texture, TextureData, TextureFormat};
+use luminara_asset::{Asset, AssetId, AssetLoadError, AssetLoader, AssetServer, Handle};
+use luminara_core::shared_types::{Component, Query, Res};
+use luminara_math::Vec2;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Frames of one texture, addressed by index or name
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    pub texture: Handle<Texture>,
+    /// Size of the texture in texels
+    pub size: Vec2,
+    /// Frame rectangles in texels, Y down
+    pub frames: Vec<Rect>,
+    names: HashMap<String, usize>,
+}
+
+impl Asset for TextureAtlas {
+    fn type_name() -> &'static str
+    where
+        Self: Sized,
+    {
+        "TextureAtlas"
+    }
+}
+
+impl TextureAtlas {
+    /// Empty atlas over a texture of `size` texels
+    pub fn new(texture: Handle<Texture>, size: Vec2) -> Self {
+        Self {
+            texture,
+            size,
+            frames: Vec::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Sprite sheet of `columns` x `rows` frames of `tile_size` texels, row
+    /// by row. `margin` texels surround the grid and `spacing` texels
+    /// separate the frames.
+    pub fn from_grid(
+        texture: Handle<Texture>,
+        size: Vec2,
+        tile_size: Vec2,
+        columns: u32,
+        rows: u32,
+        spacing: Vec2,
+        margin: Vec2,
+    ) -> Self {
+        let mut atlas = Self::new(texture, size);
+        for row in 0..rows {
+            for column in 0..columns {
+                let min = margin + Vec2::new(column as f32, row as f32) * (tile_size + spacing);
+                atlas.add_frame(Rect::new(min, min + tile_size));
+            }
+        }
+        atlas
+    }
+
+    /// Add a frame and return its index
+    pub fn add_frame(&mut self, rect: Rect) -> usize {
+        self.frames.push(rect);
+        self.frames.len() - 1
+    }
+
+    /// Add a frame that can be looked up by `name`
+    pub fn add_named_frame(&mut self, name: impl Into<String>, rect: Rect) -> usize {
+        let index = self.add_frame(rect);
+        self.names.insert(name.into(), index);
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Index of the frame called `name`
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// Size of frame `index` in texels
+    pub fn frame_size(&self, index: usize) -> Option<Vec2> {
+        self.frames.get(index).map(|r| r.max - r.min)
+    }
+
+    /// Normalized texture coordinates of frame `index`, as used by
+    /// [`Sprite::rect`]
+    pub fn uv_rect(&self, index: usize) -> Option<Rect> {
+        let size = self.size.max(Vec2::ONE);
+        self.frames
+            .get(index)
+            .map(|r| Rect::new(r.min / size, r.max / size))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TextureAtlasError {
+    #[error("Images do not fit in a {0}x{0} atlas")]
+    Full(u32),
+    #[error("Atlas image {0} is not RGBA8")]
+    UnsupportedFormat(usize),
+}
+
+/// Shelf packer that builds an atlas from loose images
+pub struct TextureAtlasBuilder {
+    /// Empty texels between packed images so filtering does not bleed
+    pub padding: u32,
+    /// Largest atlas side tried before giving up
+    pub max_size: u32,
+    images: Vec<(Option<String>, TextureData)>,
+}
+
+impl Default for TextureAtlasBuilder {
+    fn default() -> Self {
+        Self {
+            padding: 1,
+            max_size: 4096,
+            images: Vec::new(),
+        }
+    }
+}
+
+/// Output of [`TextureAtlasBuilder::build`]: the packed image and where
+/// each input image ended up, in insertion order
+#[derive(Debug, Clone)]
+pub struct PackedAtlas {
+    pub image: TextureData,
+    pub frames: Vec<Rect>,
+    pub names: HashMap<String, usize>,
+}
+
+impl PackedAtlas {
+    /// Store the packed texture and its atlas
+    pub fn add_to(self, asset_server: &AssetServer) -> Handle<TextureAtlas> {
+        let size = Vec2::new(self.image.width as f32, self.image.height as f32);
+        let texture = asset_server.add(Texture::new(self.image));
+        asset_server.add(TextureAtlas {
+            texture,
+            size,
+            frames: self.frames,
+            names: self.names,
+        })
+    }
+
+    /// Atlas over the packed image once it is stored as `texture`
+    pub fn atlas(&self, texture: Handle<Texture>) -> TextureAtlas {
+        let size = Vec2::new(self.image.width as f32, self.image.height as f32);
+        TextureAtlas {
+            texture,
+            size,
+            frames: self.frames.clone(),
+            names: self.names.clone(),
+        }
+    }
+}
+
+impl TextureAtlasBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Queue an image and return its frame index
+    pub fn add_image(&mut self, image: TextureData) -> usize {
+        self.images.push((None, image));
+        self.images.len() - 1
+    }
+
+    /// Queue an image whose frame can be looked up by `name`
+    pub fn add_named_image(&mut self, name: impl Into<String>, image: TextureData) -> usize {
+        self.images.push((Some(name.into()), image));
+        self.images.len() - 1
+    }
+
+    /// Pack the queued images, tallest first, into the smallest power of two
+    /// square that holds them
+    pub fn build(self) -> Result<PackedAtlas, TextureAtlasError> {
+        if let Some(index) = self
+            .images
+            .iter()
+            .position(|(_, image)| image.format != TextureFormat::Rgba8)
+        {
+            return Err(TextureAtlasError::UnsupportedFormat(index));
+        }
+
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&i| {
+            let image = &self.images[i].1;
+            (
+                std::cmp::Reverse(image.height),
+                std::cmp::Reverse(image.width),
+            )
+        });
+
+        let area: u64 = self
+            .images
+            .iter()
+            .map(|(_, i)| (i.width + self.padding) as u64 * (i.height + self.padding) as u64)
+            .sum();
+        let mut size = 1u32;
+        while (size as u64 * size as u64) < area {
+            size *= 2;
+        }
+        let positions = loop {
+            if size > self.max_size {
+                return Err(TextureAtlasError::Full(self.max_size));
+            }
+            if let Some(positions) = self.shelf_pack(&order, size) {
+                break positions;
+            }
+            size *= 2;
+        };
+
+        let mut pixels = vec![0u8; size as usize * size as usize * 4];
+        let mut frames = vec![Rect::new(Vec2::ZERO, Vec2::ZERO); self.images.len()];
+        let mut names = HashMap::new();
+        for (index, (name, image)) in self.images.into_iter().enumerate() {
+            let (x, y) = positions[index];
+            let row = image.width as usize * 4;
+            for line in 0..image.height as usize {
+                let target = ((y as usize + line) * size as usize + x as usize) * 4;
+                pixels[target..target + row].copy_from_slice(&image.data[line * row..][..row]);
+            }
+            let min = Vec2::new(x as f32, y as f32);
+            frames[index] = Rect::new(
+                min,
+                min + Vec2::new(image.width as f32, image.height as f32),
+            );
+            if let Some(name) = name {
+                names.insert(name, index);
+            }
+        }
+
+        Ok(PackedAtlas {
+            image: TextureData {
+                width: size,
+                height: size,
+                data: pixels,
+                format: TextureFormat::Rgba8,
+            },
+            frames,
+            names,
+        })
+    }
+
+    /// Top-left corners of the images on shelves of a `size` square, or
+    /// `None` when they do not fit
+    fn shelf_pack(&self, order: &[usize], size: u32) -> Option<Vec<(u32, u32)>> {
+        let mut positions = vec![(0, 0); self.images.len()];
+        let (mut x, mut y, mut shelf_height) = (0u32, 0u32, 0u32);
+        for &index in order {
+            let image = &self.images[index].1;
+            let (width, height) = (image.width + self.padding, image.height + self.padding);
+            if image.width > size || image.height > size {
+                return None;
+            }
+            if x + image.width > size {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+            if y + image.height > size {
+                return None;
+            }
+            positions[index] = (x, y);
+            x += width;
+            shelf_height = shelf_height.max(height);
+        }
+        Some(positions)
+    }
+}
+
+/// On-disk form of an atlas: a texture path, its size and either explicit
+/// frames or a grid, e.g.
+///
+/// ```json
+/// {
+///     "texture": "sprites/hero.png",
+///     "width": 256, "height": 64,
+///     "grid": { "tile_width": 32, "tile_height": 32, "columns": 8, "rows": 2 },
+///     "frames": [{ "name": "portrait", "x": 0, "y": 64, "w": 64, "h": 64 }]
+/// }
+/// ```
+///
+/// Grid frames come first, followed by the listed frames.
+#[derive(Deserialize)]
+struct AtlasFile {
+    texture: String,
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    grid: Option<AtlasFileGrid>,
+    #[serde(default)]
+    frames: Vec<AtlasFileFrame>,
+}
+
+#[derive(Deserialize)]
+struct AtlasFileGrid {
+    tile_width: u32,
+    tile_height: u32,
+    columns: u32,
+    rows: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    margin: u32,
+}
+
+#[derive(Deserialize)]
+struct AtlasFileFrame {
+    #[serde(default)]
+    name: Option<String>,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+/// Loads `.atlas.json` sprite sheets; their textures are queued on the
+/// [`MaterialTextureQueue`]
+pub struct TextureAtlasLoader {
+    textures: MaterialTextureQueue,
+}
+
+impl TextureAtlasLoader {
+    pub fn new(textures: MaterialTextureQueue) -> Self {
+        Self { textures }
+    }
+}
+
+impl AssetLoader for TextureAtlasLoader {
+    type Asset = TextureAtlas;
+
+    fn extensions(&self) -> &[&str] {
+        &["atlas.json"]
+    }
+
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let file: AtlasFile =
+            serde_json::from_slice(bytes).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+
+        let texture = Handle::new(AssetId::from_path(&file.texture), 0);
+        let size = Vec2::new(file.width as f32, file.height as f32);
+        let mut atlas = match file.grid {
+            Some(grid) => TextureAtlas::from_grid(
+                texture,
+                size,
+                Vec2::new(grid.tile_width as f32, grid.tile_height as f32),
+                grid.columns,
+                grid.rows,
+                Vec2::splat(grid.spacing as f32),
+                Vec2::splat(grid.margin as f32),
+            ),
+            None => TextureAtlas::new(texture, size),
+        };
+        for frame in file.frames {
+            let rect = Rect::from_coords(
+                frame.x as f32,
+                frame.y as f32,
+                frame.w as f32,
+                frame.h as f32,
+            );
+            match frame.name {
+                Some(name) => atlas.add_named_frame(name, rect),
+                None => atlas.add_frame(rect),
+            };
+        }
+        self.textures.push(file.texture);
+        Ok(atlas)
+    }
+}
+
+/// Shows frame `index` of `atlas` on the entity's [`Sprite`]
+#[derive(Debug, Clone)]
+pub struct AtlasSprite {
+    pub atlas: Handle<TextureAtlas>,
+    pub index: usize,
+}
+
+impl Component for AtlasSprite {
+    fn type_name() -> &'static str {
+        "AtlasSprite"
+    }
+}
+
+impl AtlasSprite {
+    pub fn new(atlas: Handle<TextureAtlas>, index: usize) -> Self {
+        Self { atlas, index }
+    }
+}
+
+/// Point each [`Sprite`] with an [`AtlasSprite`] at the texture and frame
+/// of its atlas. A 9-slice sprite's region follows the frame size.
+pub fn atlas_sprite_system(
+    mut sprites: Query<(&AtlasSprite, &mut Sprite)>,
+    asset_server: Res<AssetServer>,
+) {
+    for (atlas_sprite, sprite) in sprites.iter_mut() {
+        let Some(atlas) = asset_server.get(&atlas_sprite.atlas) else {
+            continue;
+        };
+        let (Some(rect), Some(frame_size)) = (
+            atlas.uv_rect(atlas_sprite.index),
+            atlas.frame_size(atlas_sprite.index),
+        ) else {
+            continue;
+        };
+        if sprite.texture.id() != atlas.texture.id() {
+            sprite.texture = atlas.texture.clone();
+        }
+        sprite.rect = Some(rect);
+        if let Some(nine_slice) = sprite.nine_slice.as_mut() {
+            nine_slice.region_size = frame_size;
+        }
+    }
+}
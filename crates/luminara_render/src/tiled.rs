@@ -0,0 +1,1025 @@
+//! Import of Tiled maps (`.tmx` and `.tmj`).
+//!
+//! [`TiledMapLoader`] reads orthogonal maps in the XML and JSON formats,
+//! including external `.tsx`/`.tsj` tilesets, CSV and uncompressed base64
+//! tile data and infinite maps. Tile collision objects are kept as their
+//! bounding rectangles. A [`TiledMap`] becomes a [`Tilemap`] with
+//! [`TiledMap::to_tilemap`]; [`spawn_tiled_maps_system`] does that for
+//! entities holding a `Handle<TiledMap>`.
+//!
+//! Diagonal (rotated) tile flips and compressed tile data are not
+//! supported.
+
+use crate::material::MaterialTextureQueue;
+use crate::sprite::Rect;
+use crate::texture::Texture;
+use crate::texture_atlas::TextureAtlas;
+use crate::tilemap::{Tile, TileCollider, TileLayer, Tilemap};
+use luminara_asset::{Asset, AssetId, AssetLoadError, AssetLoader, AssetServer, Handle};
+use luminara_core::shared_types::{Query, World};
+use luminara_core::Entity;
+use luminara_math::{Color, IVec2, Vec2};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Component as PathComponent, Path, PathBuf};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+/// Flip and rotation bits of a global tile id
+const GID_FLAGS: u32 = 0xF000_0000;
+
+/// Tileset of a Tiled map
+#[derive(Debug, Clone)]
+pub struct TiledTileset {
+    /// Global id of the tileset's first tile
+    pub first_gid: u32,
+    pub name: String,
+    /// Asset path of the tileset image
+    pub image: String,
+    pub texture: Handle<Texture>,
+    pub image_width: u32,
+    pub image_height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    /// Collision rectangle per local tile id, in tile pixels (Y down)
+    pub colliders: HashMap<u32, Rect>,
+}
+
+/// Rectangle of tile data; finite maps have a single one covering the map
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledChunk {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Global tile ids row by row, 0 for empty cells
+    pub gids: Vec<u32>,
+}
+
+/// Tile layer of a Tiled map; layers of groups are flattened
+#[derive(Debug, Clone)]
+pub struct TiledLayer {
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    /// The layer's `collision` bool property, `true` when missing
+    pub collision: bool,
+    pub chunks: Vec<TiledChunk>,
+}
+
+/// Orthogonal map loaded from a `.tmx` or `.tmj` file
+#[derive(Debug, Clone)]
+pub struct TiledMap {
+    pub width: u32,
+    pub height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<TiledTileset>,
+    pub layers: Vec<TiledLayer>,
+}
+
+impl Asset for TiledMap {
+    fn type_name() -> &'static str
+    where
+        Self: Sized,
+    {
+        "TiledMap"
+    }
+}
+
+impl TiledMap {
+    /// Tile of a global tile id, with its tileset as the index in
+    /// [`Self::tilesets`]
+    pub fn tile(&self, gid: u32) -> Option<Tile> {
+        let id = gid & !GID_FLAGS;
+        if id == 0 {
+            return None;
+        }
+        let tileset = self.tilesets.iter().rposition(|t| t.first_gid <= id)?;
+        Some(
+            Tile::new(tileset as u16, id - self.tilesets[tileset].first_gid).with_flip(
+                gid & FLIPPED_HORIZONTALLY != 0,
+                gid & FLIPPED_VERTICALLY != 0,
+            ),
+        )
+    }
+
+    /// Build a tilemap of the map, storing one atlas per tileset. Tiled rows
+    /// run down from the top of the map; tilemap rows run up, so row `r`
+    /// becomes cell row `height - 1 - r`.
+    pub fn to_tilemap(&self, asset_server: &AssetServer) -> Tilemap {
+        let tile_size = Vec2::new(self.tile_width as f32, self.tile_height as f32);
+        let mut tilemap = Tilemap::new(tile_size);
+        for tileset in &self.tilesets {
+            let columns = tileset.columns.max(1);
+            let tileset_tile = Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32);
+            let mut atlas = TextureAtlas::from_grid(
+                tileset.texture.clone(),
+                Vec2::new(tileset.image_width as f32, tileset.image_height as f32),
+                tileset_tile,
+                columns,
+                tileset.tile_count.div_ceil(columns),
+                Vec2::splat(tileset.spacing as f32),
+                Vec2::splat(tileset.margin as f32),
+            );
+            atlas.frames.truncate(tileset.tile_count as usize);
+            let index = tilemap.add_tileset(asset_server.add(atlas));
+
+            for (&id, rect) in &tileset.colliders {
+                let full = rect.min.x <= 0.0
+                    && rect.min.y <= 0.0
+                    && rect.max.x >= tileset_tile.x
+                    && rect.max.y >= tileset_tile.y;
+                let collider = if full {
+                    TileCollider::Full
+                } else {
+                    let tile = tileset_tile.max(Vec2::ONE);
+                    TileCollider::Rect(Rect::new(
+                        Vec2::new(rect.min.x / tile.x, 1.0 - rect.max.y / tile.y),
+                        Vec2::new(rect.max.x / tile.x, 1.0 - rect.min.y / tile.y),
+                    ))
+                };
+                tilemap.set_tile_collider(index, id, Some(collider));
+            }
+        }
+
+        for layer in &self.layers {
+            let mut tile_layer = TileLayer::new(layer.name.clone()).with_collision(layer.collision);
+            tile_layer.visible = layer.visible;
+            tile_layer.color = Color::rgba(1.0, 1.0, 1.0, layer.opacity);
+            let index = tilemap.add_layer(tile_layer);
+            for chunk in &layer.chunks {
+                let width = chunk.width.max(1) as i32;
+                for (i, &gid) in chunk.gids.iter().enumerate() {
+                    let Some(tile) = self.tile(gid) else {
+                        continue;
+                    };
+                    let row = chunk.y + i as i32 / width;
+                    let cell = IVec2::new(chunk.x + i as i32 % width, self.height as i32 - 1 - row);
+                    tilemap.set_tile(index, cell, Some(tile));
+                }
+            }
+        }
+        tilemap
+    }
+}
+
+/// Loads Tiled maps; tileset images are queued on the
+/// [`MaterialTextureQueue`]
+pub struct TiledMapLoader {
+    asset_dir: PathBuf,
+    textures: MaterialTextureQueue,
+}
+
+impl TiledMapLoader {
+    /// Loader for maps below `asset_dir`, which turns tileset image paths
+    /// into asset paths
+    pub fn new(asset_dir: impl Into<PathBuf>, textures: MaterialTextureQueue) -> Self {
+        Self {
+            asset_dir: asset_dir.into(),
+            textures,
+        }
+    }
+
+    /// Asset path of `file`, given relative to the file at `base`
+    fn asset_path(&self, base: &Path, file: &str) -> String {
+        let resolved = normalize(&base.parent().unwrap_or(Path::new("")).join(file));
+        let relative = match resolved.strip_prefix(normalize(&self.asset_dir)) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => resolved.clone(),
+        };
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn tileset_image(&self, base: &Path, image: &str) -> (String, Handle<Texture>) {
+        let path = self.asset_path(base, image);
+        self.textures.push(path.clone());
+        let handle = Handle::new(AssetId::from_path(&path), 0);
+        (path, handle)
+    }
+}
+
+impl AssetLoader for TiledMapLoader {
+    type Asset = TiledMap;
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx", "tmj"]
+    }
+
+    fn load(&self, bytes: &[u8], path: &Path) -> Result<Self::Asset, AssetLoadError> {
+        let source = std::str::from_utf8(bytes)
+            .map_err(|e| AssetLoadError::Parse(format!("Map is not UTF-8: {}", e)))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("tmj") | Some("json") => self.load_tmj(source, path),
+            _ => self.load_tmx(source, path),
+        }
+    }
+}
+
+/// Lexically resolve `.` and `..` components
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            PathComponent::CurDir => {}
+            PathComponent::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn parse_error(message: impl Into<String>) -> AssetLoadError {
+    AssetLoadError::Parse(message.into())
+}
+
+fn read_external(base: &Path, file: &str) -> Result<(PathBuf, String), AssetLoadError> {
+    let path = normalize(&base.parent().unwrap_or(Path::new("")).join(file));
+    let source = std::fs::read_to_string(&path)?;
+    Ok((path, source))
+}
+
+/// Global tile ids of a layer's data, as CSV or uncompressed base64
+fn decode_gids(
+    text: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, AssetLoadError> {
+    if let Some(compression) = compression.filter(|c| !c.is_empty()) {
+        return Err(AssetLoadError::UnsupportedFormat(format!(
+            "{} compressed tile data",
+            compression
+        )));
+    }
+    match encoding {
+        Some("csv") => text
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                v.parse::<u32>()
+                    .map_err(|_| parse_error(format!("Invalid tile id '{}'", v)))
+            })
+            .collect(),
+        Some("base64") => {
+            let bytes = decode_base64(text.trim())?;
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect())
+        }
+        other => Err(AssetLoadError::UnsupportedFormat(format!(
+            "tile data encoding {:?}",
+            other
+        ))),
+    }
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, AssetLoadError> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+    {
+        let v = value(c).ok_or_else(|| parse_error("Invalid base64 tile data"))?;
+        buffer = (buffer << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+// ---------------------------------------------------------------------------
+// TMX
+// ---------------------------------------------------------------------------
+
+/// Element of the small XML subset used by Tiled files
+#[derive(Debug, Default)]
+struct XmlElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlElement>,
+    text: String,
+}
+
+impl XmlElement {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn parse_attr<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, AssetLoadError> {
+        self.attr(name)
+            .map(|v| {
+                v.parse().map_err(|_| {
+                    parse_error(format!("Invalid {} '{}' on <{}>", name, v, self.name))
+                })
+            })
+            .transpose()
+    }
+
+    fn require<T: std::str::FromStr>(&self, name: &str) -> Result<T, AssetLoadError> {
+        self.parse_attr(name)?
+            .ok_or_else(|| parse_error(format!("<{}> has no {}", self.name, name)))
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Value of a bool `<property>`
+    fn bool_property(&self, name: &str) -> Option<bool> {
+        self.child("properties")?
+            .children_named("property")
+            .find(|p| p.attr("name") == Some(name))
+            .and_then(|p| p.attr("value"))
+            .map(|v| v == "true")
+    }
+}
+
+struct XmlParser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+fn parse_xml(source: &str) -> Result<XmlElement, AssetLoadError> {
+    let mut parser = XmlParser { source, pos: 0 };
+    parser.skip_misc()?;
+    parser.element()
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find(';') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let entity = &rest[start + 1..start + end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse()))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => out.push(c),
+            None => out.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+impl<'a> XmlParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), AssetLoadError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(parse_error(format!(
+                "Expected '{}' at byte {}",
+                token, self.pos
+            )))
+        }
+    }
+
+    fn skip_past(&mut self, token: &str) -> Result<(), AssetLoadError> {
+        let end = self
+            .rest()
+            .find(token)
+            .ok_or_else(|| parse_error(format!("Missing '{}'", token)))?;
+        self.pos += end + token.len();
+        Ok(())
+    }
+
+    /// Skip the XML declaration, comments and doctype
+    fn skip_misc(&mut self) -> Result<(), AssetLoadError> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.')))
+            .unwrap_or(rest.len());
+        self.pos += end;
+        rest[..end].to_string()
+    }
+
+    fn element(&mut self) -> Result<XmlElement, AssetLoadError> {
+        self.expect("<")?;
+        let mut element = XmlElement {
+            name: self.name(),
+            ..Default::default()
+        };
+        loop {
+            self.skip_whitespace();
+            if self.eat("/>") {
+                return Ok(element);
+            }
+            if self.eat(">") {
+                break;
+            }
+            let key = self.name();
+            if key.is_empty() {
+                return Err(parse_error(format!("Malformed tag <{}>", element.name)));
+            }
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = if self.eat("\"") {
+                '"'
+            } else {
+                self.expect("'")?;
+                '\''
+            };
+            let end = self
+                .rest()
+                .find(quote)
+                .ok_or_else(|| parse_error(format!("Unterminated {} attribute", key)))?;
+            let value = unescape(&self.rest()[..end]);
+            self.pos += end + 1;
+            element.attributes.push((key, value));
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(parse_error(format!("Unclosed <{}>", element.name)));
+            }
+            if self.eat("</") {
+                let name = self.name();
+                if name != element.name {
+                    return Err(parse_error(format!(
+                        "</{}> closes <{}>",
+                        name, element.name
+                    )));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(element);
+            }
+            if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.eat("<![CDATA[") {
+                let end = self
+                    .rest()
+                    .find("]]>")
+                    .ok_or_else(|| parse_error("Unterminated CDATA"))?;
+                element.text.push_str(&self.rest()[..end]);
+                self.pos += end + 3;
+            } else if rest.starts_with('<') {
+                element.children.push(self.element()?);
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                element.text.push_str(&unescape(&rest[..end]));
+                self.pos += end;
+            }
+        }
+    }
+}
+
+impl TiledMapLoader {
+    fn load_tmx(&self, source: &str, path: &Path) -> Result<TiledMap, AssetLoadError> {
+        let root = parse_xml(source)?;
+        if root.name != "map" {
+            return Err(parse_error(format!(
+                "Expected <map>, found <{}>",
+                root.name
+            )));
+        }
+        if let Some(orientation) = root.attr("orientation").filter(|o| *o != "orthogonal") {
+            return Err(AssetLoadError::UnsupportedFormat(format!(
+                "{} maps",
+                orientation
+            )));
+        }
+
+        let mut tilesets = Vec::new();
+        for element in root.children_named("tileset") {
+            let first_gid = element.require("firstgid")?;
+            let tileset = match element.attr("source") {
+                Some(file) => {
+                    let (tileset_path, source) = read_external(path, file)?;
+                    let external = parse_xml(&source)?;
+                    self.tmx_tileset(&external, first_gid, &tileset_path)?
+                }
+                None => self.tmx_tileset(element, first_gid, path)?,
+            };
+            tilesets.push(tileset);
+        }
+        tilesets.sort_by_key(|t| t.first_gid);
+
+        let mut layers = Vec::new();
+        tmx_layers(&root, true, 1.0, &mut layers)?;
+        Ok(TiledMap {
+            width: root.require("width")?,
+            height: root.require("height")?,
+            tile_width: root.require("tilewidth")?,
+            tile_height: root.require("tileheight")?,
+            tilesets,
+            layers,
+        })
+    }
+
+    fn tmx_tileset(
+        &self,
+        element: &XmlElement,
+        first_gid: u32,
+        path: &Path,
+    ) -> Result<TiledTileset, AssetLoadError> {
+        let image = element
+            .child("image")
+            .ok_or_else(|| AssetLoadError::UnsupportedFormat("image collection tilesets".into()))?;
+        let file = image
+            .attr("source")
+            .ok_or_else(|| parse_error("<image> has no source"))?;
+        let (image_path, texture) = self.tileset_image(path, file);
+        let tile_width = element.require("tilewidth")?;
+        let tile_height = element.require("tileheight")?;
+
+        let mut colliders = HashMap::new();
+        for tile in element.children_named("tile") {
+            let Some(group) = tile.child("objectgroup") else {
+                continue;
+            };
+            let rects: Vec<Rect> = group
+                .children_named("object")
+                .map(|object| {
+                    let x = object.parse_attr::<f32>("x")?.unwrap_or(0.0);
+                    let y = object.parse_attr::<f32>("y")?.unwrap_or(0.0);
+                    let width = object.parse_attr::<f32>("width")?.unwrap_or(0.0);
+                    let height = object.parse_attr::<f32>("height")?.unwrap_or(0.0);
+                    Ok(Rect::from_coords(x, y, width, height))
+                })
+                .collect::<Result<_, AssetLoadError>>()?;
+            if let Some(rect) = bounding_rect(&rects) {
+                colliders.insert(tile.require("id")?, rect);
+            }
+        }
+
+        Ok(TiledTileset {
+            first_gid,
+            name: element.attr("name").unwrap_or_default().to_string(),
+            image: image_path,
+            texture,
+            image_width: image.require("width")?,
+            image_height: image.require("height")?,
+            tile_width,
+            tile_height,
+            columns: element.require("columns")?,
+            tile_count: element.require("tilecount")?,
+            spacing: element.parse_attr("spacing")?.unwrap_or(0),
+            margin: element.parse_attr("margin")?.unwrap_or(0),
+            colliders,
+        })
+    }
+}
+
+/// Bounding rectangle of the non-empty rectangles
+fn bounding_rect(rects: &[Rect]) -> Option<Rect> {
+    rects
+        .iter()
+        .filter(|r| r.max.x > r.min.x && r.max.y > r.min.y)
+        .copied()
+        .reduce(|a, b| Rect::new(a.min.min(b.min), a.max.max(b.max)))
+}
+
+/// Collect the tile layers below `parent`, flattening groups
+fn tmx_layers(
+    parent: &XmlElement,
+    visible: bool,
+    opacity: f32,
+    layers: &mut Vec<TiledLayer>,
+) -> Result<(), AssetLoadError> {
+    for element in &parent.children {
+        let layer_visible = visible && element.attr("visible") != Some("0");
+        let layer_opacity = opacity * element.parse_attr("opacity")?.unwrap_or(1.0);
+        match element.name.as_str() {
+            "group" => tmx_layers(element, layer_visible, layer_opacity, layers)?,
+            "layer" => {
+                let data = element
+                    .child("data")
+                    .ok_or_else(|| parse_error("<layer> has no <data>"))?;
+                let (encoding, compression) = (data.attr("encoding"), data.attr("compression"));
+                // Chunks of infinite maps share the encoding of their <data>
+                let decode = |content: &XmlElement| -> Result<Vec<u32>, AssetLoadError> {
+                    match encoding {
+                        None => content
+                            .children_named("tile")
+                            .map(|tile| Ok(tile.parse_attr("gid")?.unwrap_or(0)))
+                            .collect(),
+                        _ => decode_gids(&content.text, encoding, compression),
+                    }
+                };
+                let chunks = if data.child("chunk").is_some() {
+                    data.children_named("chunk")
+                        .map(|chunk| {
+                            Ok(TiledChunk {
+                                x: chunk.require("x")?,
+                                y: chunk.require("y")?,
+                                width: chunk.require("width")?,
+                                height: chunk.require("height")?,
+                                gids: decode(chunk)?,
+                            })
+                        })
+                        .collect::<Result<_, AssetLoadError>>()?
+                } else {
+                    vec![TiledChunk {
+                        x: 0,
+                        y: 0,
+                        width: element.require("width")?,
+                        height: element.require("height")?,
+                        gids: decode(data)?,
+                    }]
+                };
+                layers.push(TiledLayer {
+                    name: element.attr("name").unwrap_or_default().to_string(),
+                    visible: layer_visible,
+                    opacity: layer_opacity,
+                    collision: element.bool_property("collision").unwrap_or(true),
+                    chunks,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// TMJ
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    orientation: Option<String>,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    #[serde(default)]
+    firstgid: u32,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    imagewidth: u32,
+    #[serde(default)]
+    imageheight: u32,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+#[derive(Deserialize)]
+struct JsonTile {
+    id: u32,
+    #[serde(default)]
+    objectgroup: Option<JsonObjectGroup>,
+}
+
+#[derive(Deserialize)]
+struct JsonObjectGroup {
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    #[serde(default = "yes")]
+    visible: bool,
+    #[serde(default = "one")]
+    opacity: f32,
+    #[serde(default)]
+    data: Option<JsonData>,
+    #[serde(default)]
+    chunks: Vec<JsonChunk>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    compression: Option<String>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonData {
+    Ids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Deserialize)]
+struct JsonChunk {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    data: JsonData,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+impl JsonLayer {
+    fn gids(&self, data: &JsonData) -> Result<Vec<u32>, AssetLoadError> {
+        match data {
+            JsonData::Ids(ids) => Ok(ids.clone()),
+            JsonData::Encoded(text) => decode_gids(
+                text,
+                Some(self.encoding.as_deref().unwrap_or("base64")),
+                self.compression.as_deref(),
+            ),
+        }
+    }
+}
+
+fn tmj_layers(
+    source: &[JsonLayer],
+    visible: bool,
+    opacity: f32,
+    layers: &mut Vec<TiledLayer>,
+) -> Result<(), AssetLoadError> {
+    for layer in source {
+        let layer_visible = visible && layer.visible;
+        let layer_opacity = opacity * layer.opacity;
+        match layer.kind.as_str() {
+            "group" => tmj_layers(&layer.layers, layer_visible, layer_opacity, layers)?,
+            "tilelayer" => {
+                let chunks = match &layer.data {
+                    Some(data) => vec![TiledChunk {
+                        x: 0,
+                        y: 0,
+                        width: layer.width,
+                        height: layer.height,
+                        gids: layer.gids(data)?,
+                    }],
+                    None => layer
+                        .chunks
+                        .iter()
+                        .map(|chunk| {
+                            Ok(TiledChunk {
+                                x: chunk.x,
+                                y: chunk.y,
+                                width: chunk.width,
+                                height: chunk.height,
+                                gids: layer.gids(&chunk.data)?,
+                            })
+                        })
+                        .collect::<Result<_, AssetLoadError>>()?,
+                };
+                let collision = layer
+                    .properties
+                    .iter()
+                    .find(|p| p.name == "collision")
+                    .and_then(|p| p.value.as_bool())
+                    .unwrap_or(true);
+                layers.push(TiledLayer {
+                    name: layer.name.clone(),
+                    visible: layer_visible,
+                    opacity: layer_opacity,
+                    collision,
+                    chunks,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+impl TiledMapLoader {
+    fn load_tmj(&self, source: &str, path: &Path) -> Result<TiledMap, AssetLoadError> {
+        let map: JsonMap =
+            serde_json::from_str(source).map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+        if let Some(orientation) = map.orientation.as_deref().filter(|o| *o != "orthogonal") {
+            return Err(AssetLoadError::UnsupportedFormat(format!(
+                "{} maps",
+                orientation
+            )));
+        }
+
+        let mut tilesets = Vec::new();
+        for tileset in &map.tilesets {
+            let first_gid = tileset.firstgid;
+            let tileset = match &tileset.source {
+                Some(file) => {
+                    let (tileset_path, source) = read_external(path, file)?;
+                    let external: JsonTileset = serde_json::from_str(&source)
+                        .map_err(|e| AssetLoadError::Parse(e.to_string()))?;
+                    self.tmj_tileset(&external, first_gid, &tileset_path)?
+                }
+                None => self.tmj_tileset(tileset, first_gid, path)?,
+            };
+            tilesets.push(tileset);
+        }
+        tilesets.sort_by_key(|t| t.first_gid);
+
+        let mut layers = Vec::new();
+        tmj_layers(&map.layers, true, 1.0, &mut layers)?;
+        Ok(TiledMap {
+            width: map.width,
+            height: map.height,
+            tile_width: map.tilewidth,
+            tile_height: map.tileheight,
+            tilesets,
+            layers,
+        })
+    }
+
+    fn tmj_tileset(
+        &self,
+        tileset: &JsonTileset,
+        first_gid: u32,
+        path: &Path,
+    ) -> Result<TiledTileset, AssetLoadError> {
+        let file = tileset
+            .image
+            .as_deref()
+            .ok_or_else(|| AssetLoadError::UnsupportedFormat("image collection tilesets".into()))?;
+        let (image, texture) = self.tileset_image(path, file);
+        let colliders = tileset
+            .tiles
+            .iter()
+            .filter_map(|tile| {
+                let group = tile.objectgroup.as_ref()?;
+                let rects: Vec<Rect> = group
+                    .objects
+                    .iter()
+                    .map(|o| Rect::from_coords(o.x, o.y, o.width, o.height))
+                    .collect();
+                bounding_rect(&rects).map(|rect| (tile.id, rect))
+            })
+            .collect();
+
+        Ok(TiledTileset {
+            first_gid,
+            name: tileset.name.clone(),
+            image,
+            texture,
+            image_width: tileset.imagewidth,
+            image_height: tileset.imageheight,
+            tile_width: tileset.tilewidth,
+            tile_height: tileset.tileheight,
+            columns: tileset.columns,
+            tile_count: tileset.tilecount,
+            spacing: tileset.spacing,
+            margin: tileset.margin,
+            colliders,
+        })
+    }
+}
+
+/// Give every entity with a loaded `Handle<TiledMap>` and no [`Tilemap`] the
+/// tilemap of its map
+pub fn spawn_tiled_maps_system(world: &mut World) {
+    let pending: Vec<(Entity, Handle<TiledMap>)> = Query::<(Entity, &Handle<TiledMap>)>::new(world)
+        .iter()
+        .map(|(entity, handle)| (entity, handle.clone()))
+        .collect();
+
+    let mut spawned = Vec::new();
+    {
+        let Some(asset_server) = world.get_resource::<AssetServer>() else {
+            return;
+        };
+        for (entity, handle) in pending {
+            if world.get_component::<Tilemap>(entity).is_some() {
+                continue;
+            }
+            if let Some(map) = asset_server.get(&handle) {
+                spawned.push((entity, map.to_tilemap(&asset_server)));
+            }
+        }
+    }
+
+    for (entity, tilemap) in spawned {
+        if let Err(e) = world.add_component(entity, tilemap) {
+            log::error!("Failed to add the tilemap of a Tiled map: {:?}", e);
+        }
+    }
+}
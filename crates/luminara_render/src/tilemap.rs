@@ -0,0 +1,564 @@
+//! Chunked 2D tilemaps.
+//!
+//! A [`Tilemap`] stores sparse [`TileLayer`]s of [`Tile`]s in square chunks.
+//! Each chunk caches its sprite instances and is only rebuilt after one of
+//! its tiles changed; [`prepare_tilemap_batches`] hands the cached instances
+//! of every visible layer to the [`SpriteBatcher`] at the layer's z-order.
+//!
+//! Tiles may carry a terrain, which [`AutotileRule`]s turn into the tile
+//! matching the neighbouring terrain. Colliders are set per tileset tile;
+//! [`Tilemap::collision_rects`] merges the solid cells of a map into as few
+//! rectangles as it can for the 2D physics plugin.
+
+use crate::sprite::{Rect, SpriteBatcher, SpriteInstance, ZOrder};
+use crate::texture_atlas::TextureAtlas;
+use luminara_asset::{AssetServer, Handle};
+use luminara_core::shared_types::{Component, Query, World};
+use luminara_core::Entity;
+use luminara_math::{Color, IVec2, Mat4, Quat, Transform, Vec2};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// Tiles per chunk side unless set with [`Tilemap::with_chunk_size`]
+pub const DEFAULT_CHUNK_SIZE: u32 = 32;
+
+/// A cell of a tile layer: frame `index` of tileset `tileset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub tileset: u16,
+    pub index: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Terrain painted on the cell; autotiling picks `index` from it
+    pub terrain: Option<u16>,
+}
+
+impl Tile {
+    pub fn new(tileset: u16, index: u32) -> Self {
+        Self {
+            tileset,
+            index,
+            flip_x: false,
+            flip_y: false,
+            terrain: None,
+        }
+    }
+
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+}
+
+/// Collision shape of a tileset tile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileCollider {
+    /// The whole cell; neighbouring full cells merge into larger rectangles
+    Full,
+    /// Part of the cell in tile units, from (0, 0) at the bottom left to (1, 1)
+    Rect(Rect),
+}
+
+#[derive(Debug, Clone)]
+struct TileChunk {
+    tiles: Vec<Option<Tile>>,
+    /// Instances in map space, grouped by tileset
+    instances: Vec<(u16, Vec<SpriteInstance>)>,
+    dirty: bool,
+}
+
+impl TileChunk {
+    fn new(chunk_size: u32) -> Self {
+        Self {
+            tiles: vec![None; (chunk_size * chunk_size) as usize],
+            instances: Vec::new(),
+            dirty: true,
+        }
+    }
+}
+
+/// One layer of a tilemap
+#[derive(Debug, Clone)]
+pub struct TileLayer {
+    pub name: String,
+    pub visible: bool,
+    /// Tint of every tile, e.g. for layer opacity
+    pub color: Color,
+    /// Z-order of the layer relative to the map's [`ZOrder`]. Layers of equal
+    /// z-order draw in layer order.
+    pub z: f32,
+    /// Whether the tiles of this layer collide
+    pub collision: bool,
+    chunks: BTreeMap<(i32, i32), TileChunk>,
+}
+
+impl TileLayer {
+    /// Visible, colliding layer below sprites of the default z-order
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            visible: true,
+            color: Color::WHITE,
+            z: -1.0,
+            collision: true,
+            chunks: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_z(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    pub fn with_collision(mut self, collision: bool) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+/// Picks the tile of a terrain cell from which of its neighbours share the
+/// terrain.
+///
+/// Edge masks (`corners == false`) have the bits N = 1, E = 2, S = 4, W = 8,
+/// enough for 16-tile sets. Blob masks (`corners == true`) add the corners,
+/// N = 1, NE = 2, E = 4, SE = 8, S = 16, SW = 32, W = 64, NW = 128, where a
+/// corner only counts when both of its edges do, for 47-tile sets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutotileRule {
+    pub terrain: u16,
+    pub tileset: u16,
+    pub tiles: HashMap<u8, u32>,
+    /// Tile for masks missing from `tiles`
+    pub fallback: u32,
+    pub corners: bool,
+}
+
+impl AutotileRule {
+    pub fn new(terrain: u16, tileset: u16, fallback: u32) -> Self {
+        Self {
+            terrain,
+            tileset,
+            tiles: HashMap::new(),
+            fallback,
+            corners: false,
+        }
+    }
+
+    pub fn with_corners(mut self, corners: bool) -> Self {
+        self.corners = corners;
+        self
+    }
+
+    pub fn with_tile(mut self, mask: u8, index: u32) -> Self {
+        self.tiles.insert(mask, index);
+        self
+    }
+
+    /// Neighbour mask of `cell`, where `same` tells whether a cell shares
+    /// the terrain
+    pub fn mask(&self, cell: IVec2, same: impl Fn(IVec2) -> bool) -> u8 {
+        let (n, e, s, w) = (
+            same(cell + IVec2::new(0, 1)),
+            same(cell + IVec2::new(1, 0)),
+            same(cell + IVec2::new(0, -1)),
+            same(cell + IVec2::new(-1, 0)),
+        );
+        if !self.corners {
+            return n as u8 | (e as u8) << 1 | (s as u8) << 2 | (w as u8) << 3;
+        }
+        let ne = n && e && same(cell + IVec2::new(1, 1));
+        let se = s && e && same(cell + IVec2::new(1, -1));
+        let sw = s && w && same(cell + IVec2::new(-1, -1));
+        let nw = n && w && same(cell + IVec2::new(-1, 1));
+        n as u8
+            | (ne as u8) << 1
+            | (e as u8) << 2
+            | (se as u8) << 3
+            | (s as u8) << 4
+            | (sw as u8) << 5
+            | (w as u8) << 6
+            | (nw as u8) << 7
+    }
+
+    /// Tile index for a neighbour mask
+    pub fn tile(&self, mask: u8) -> u32 {
+        self.tiles.get(&mask).copied().unwrap_or(self.fallback)
+    }
+}
+
+/// Layered, chunked grid of tiles. Cell (0, 0) has its bottom-left corner at
+/// the entity's origin; +Y is up.
+#[derive(Debug, Clone)]
+pub struct Tilemap {
+    /// Size of a cell in map units
+    pub tile_size: Vec2,
+    pub tilesets: Vec<Handle<TextureAtlas>>,
+    pub layers: Vec<TileLayer>,
+    pub autotile_rules: Vec<AutotileRule>,
+    chunk_size: u32,
+    colliders: HashMap<(u16, u32), TileCollider>,
+    collision_revision: u64,
+}
+
+impl Component for Tilemap {
+    fn type_name() -> &'static str {
+        "Tilemap"
+    }
+}
+
+impl Tilemap {
+    pub fn new(tile_size: Vec2) -> Self {
+        Self {
+            tile_size,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            autotile_rules: Vec::new(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            colliders: HashMap::new(),
+            collision_revision: 0,
+        }
+    }
+
+    /// Set the chunk side in tiles. Only takes effect before tiles are set.
+    pub fn with_chunk_size(mut self, chunk_size: u32) -> Self {
+        if self.layers.iter().all(|l| l.chunks.is_empty()) {
+            self.chunk_size = chunk_size.max(1);
+        }
+        self
+    }
+
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    /// Add a tileset and return its index for [`Tile::tileset`]
+    pub fn add_tileset(&mut self, atlas: Handle<TextureAtlas>) -> u16 {
+        self.tilesets.push(atlas);
+        (self.tilesets.len() - 1) as u16
+    }
+
+    /// Add a layer and return its index
+    pub fn add_layer(&mut self, layer: TileLayer) -> usize {
+        self.layers.push(layer);
+        self.collision_revision += 1;
+        self.layers.len() - 1
+    }
+
+    fn chunk_key(&self, cell: IVec2) -> ((i32, i32), usize) {
+        let size = self.chunk_size as i32;
+        let local = (cell.y.rem_euclid(size) * size + cell.x.rem_euclid(size)) as usize;
+        ((cell.x.div_euclid(size), cell.y.div_euclid(size)), local)
+    }
+
+    pub fn tile(&self, layer: usize, cell: IVec2) -> Option<Tile> {
+        let (key, local) = self.chunk_key(cell);
+        self.layers
+            .get(layer)?
+            .chunks
+            .get(&key)
+            .and_then(|chunk| chunk.tiles[local])
+    }
+
+    /// Set or clear a cell. Chunks are created on demand and dropped once
+    /// empty.
+    pub fn set_tile(&mut self, layer: usize, cell: IVec2, tile: Option<Tile>) {
+        let (key, local) = self.chunk_key(cell);
+        let chunk_size = self.chunk_size;
+        let Some(layer) = self.layers.get_mut(layer) else {
+            return;
+        };
+        match tile {
+            Some(tile) => {
+                let chunk = layer
+                    .chunks
+                    .entry(key)
+                    .or_insert_with(|| TileChunk::new(chunk_size));
+                chunk.tiles[local] = Some(tile);
+                chunk.dirty = true;
+            }
+            None => {
+                let Some(chunk) = layer.chunks.get_mut(&key) else {
+                    return;
+                };
+                chunk.tiles[local] = None;
+                chunk.dirty = true;
+                if chunk.tiles.iter().all(Option::is_none) {
+                    layer.chunks.remove(&key);
+                }
+            }
+        }
+        self.collision_revision += 1;
+    }
+
+    /// Every set cell of a layer
+    pub fn tiles(&self, layer: usize) -> impl Iterator<Item = (IVec2, Tile)> + '_ {
+        let size = self.chunk_size as i32;
+        self.layers.get(layer).into_iter().flat_map(move |layer| {
+            layer.chunks.iter().flat_map(move |(&(cx, cy), chunk)| {
+                chunk.tiles.iter().enumerate().filter_map(move |(i, tile)| {
+                    let cell = IVec2::new(cx * size + i as i32 % size, cy * size + i as i32 / size);
+                    tile.map(|tile| (cell, tile))
+                })
+            })
+        })
+    }
+
+    /// Paint `terrain` on a cell, or erase it, and re-pick the tiles of the
+    /// cell and its neighbours from the [`AutotileRule`]s
+    pub fn paint_terrain(&mut self, layer: usize, cell: IVec2, terrain: Option<u16>) {
+        match terrain {
+            Some(terrain) => {
+                let Some(rule) = self.autotile_rules.iter().find(|r| r.terrain == terrain) else {
+                    return;
+                };
+                let tile = Tile {
+                    terrain: Some(terrain),
+                    ..Tile::new(rule.tileset, rule.fallback)
+                };
+                self.set_tile(layer, cell, Some(tile));
+            }
+            None => self.set_tile(layer, cell, None),
+        }
+        for y in -1..=1 {
+            for x in -1..=1 {
+                self.resolve_autotile(layer, cell + IVec2::new(x, y));
+            }
+        }
+    }
+
+    /// Re-pick the tile of every terrain cell of a layer, e.g. after import
+    pub fn apply_autotiling(&mut self, layer: usize) {
+        let cells: Vec<IVec2> = self
+            .tiles(layer)
+            .filter(|(_, tile)| tile.terrain.is_some())
+            .map(|(cell, _)| cell)
+            .collect();
+        for cell in cells {
+            self.resolve_autotile(layer, cell);
+        }
+    }
+
+    fn resolve_autotile(&mut self, layer: usize, cell: IVec2) {
+        let Some(tile) = self.tile(layer, cell) else {
+            return;
+        };
+        let Some(terrain) = tile.terrain else {
+            return;
+        };
+        let Some(rule) = self.autotile_rules.iter().find(|r| r.terrain == terrain) else {
+            return;
+        };
+        let mask = rule.mask(cell, |n| {
+            self.tile(layer, n).and_then(|t| t.terrain) == Some(terrain)
+        });
+        let resolved = Tile {
+            tileset: rule.tileset,
+            index: rule.tile(mask),
+            ..tile
+        };
+        if resolved != tile {
+            self.set_tile(layer, cell, Some(resolved));
+        }
+    }
+
+    /// Set the collider of a tileset tile for every cell showing it
+    pub fn set_tile_collider(&mut self, tileset: u16, index: u32, collider: Option<TileCollider>) {
+        match collider {
+            Some(collider) => self.colliders.insert((tileset, index), collider),
+            None => self.colliders.remove(&(tileset, index)),
+        };
+        self.collision_revision += 1;
+    }
+
+    pub fn tile_collider(&self, tileset: u16, index: u32) -> Option<TileCollider> {
+        self.colliders.get(&(tileset, index)).copied()
+    }
+
+    /// Changes whenever the result of [`Self::collision_rects`] may change
+    pub fn collision_revision(&self) -> u64 {
+        self.collision_revision
+    }
+
+    /// Center of a cell in map space
+    pub fn cell_center(&self, cell: IVec2) -> Vec2 {
+        (cell.as_vec2() + Vec2::splat(0.5)) * self.tile_size
+    }
+
+    /// Cell containing a point in map space
+    pub fn cell_at(&self, position: Vec2) -> IVec2 {
+        (position / self.tile_size).floor().as_ivec2()
+    }
+
+    /// Collision rectangles of the colliding layers in map space. Full cells
+    /// are merged greedily: runs along +X first, then stacked along +Y.
+    pub fn collision_rects(&self) -> Vec<Rect> {
+        let mut solid = HashSet::new();
+        let mut rects = Vec::new();
+        for (index, layer) in self.layers.iter().enumerate() {
+            if !layer.collision {
+                continue;
+            }
+            for (cell, tile) in self.tiles(index) {
+                match self.colliders.get(&(tile.tileset, tile.index)) {
+                    Some(TileCollider::Full) => {
+                        solid.insert((cell.x, cell.y));
+                    }
+                    Some(TileCollider::Rect(part)) => {
+                        let origin = cell.as_vec2() * self.tile_size;
+                        rects.push(Rect::new(
+                            origin + part.min * self.tile_size,
+                            origin + part.max * self.tile_size,
+                        ));
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        let mut cells: Vec<(i32, i32)> = solid.iter().copied().collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        let mut used = HashSet::new();
+        for (x, y) in cells {
+            if used.contains(&(x, y)) {
+                continue;
+            }
+            let free = |cx: i32, cy: i32| solid.contains(&(cx, cy)) && !used.contains(&(cx, cy));
+            let mut width = 1;
+            while free(x + width, y) {
+                width += 1;
+            }
+            let mut height = 1;
+            while (0..width).all(|dx| free(x + dx, y + height)) {
+                height += 1;
+            }
+            for dy in 0..height {
+                for dx in 0..width {
+                    used.insert((x + dx, y + dy));
+                }
+            }
+            let min = Vec2::new(x as f32, y as f32) * self.tile_size;
+            rects.push(Rect::new(
+                min,
+                min + Vec2::new(width as f32, height as f32) * self.tile_size,
+            ));
+        }
+        rects
+    }
+
+    /// Rebuild the instances of changed chunks of a layer. Chunks using an
+    /// atlas that is not loaded yet stay dirty.
+    fn rebuild_chunks(&mut self, layer: usize, atlases: &[Option<Arc<TextureAtlas>>]) {
+        let size = self.chunk_size as i32;
+        let tile_size = self.tile_size;
+        let Some(layer) = self.layers.get_mut(layer) else {
+            return;
+        };
+        let color = [layer.color.r, layer.color.g, layer.color.b, layer.color.a];
+        for (&(cx, cy), chunk) in layer.chunks.iter_mut().filter(|(_, c)| c.dirty) {
+            let mut groups: BTreeMap<u16, Vec<SpriteInstance>> = BTreeMap::new();
+            let mut complete = true;
+            for (i, tile) in chunk.tiles.iter().enumerate() {
+                let Some(tile) = tile else {
+                    continue;
+                };
+                let Some(Some(atlas)) = atlases.get(tile.tileset as usize) else {
+                    complete = false;
+                    continue;
+                };
+                let Some(rect) = atlas.uv_rect(tile.index as usize) else {
+                    continue;
+                };
+                let mut uv_rect = [rect.min.x, rect.min.y, rect.max.x, rect.max.y];
+                if tile.flip_x {
+                    uv_rect.swap(0, 2);
+                }
+                if tile.flip_y {
+                    uv_rect.swap(1, 3);
+                }
+                let cell = IVec2::new(cx * size + i as i32 % size, cy * size + i as i32 / size);
+                let center = (cell.as_vec2() + Vec2::splat(0.5)) * tile_size;
+                let transform = Mat4::from_scale_rotation_translation(
+                    tile_size.extend(1.0),
+                    Quat::IDENTITY,
+                    center.extend(0.0),
+                );
+                groups
+                    .entry(tile.tileset)
+                    .or_default()
+                    .push(SpriteInstance {
+                        transform: transform.to_cols_array_2d(),
+                        color,
+                        uv_rect,
+                    });
+            }
+            chunk.instances = groups.into_iter().collect();
+            chunk.dirty = !complete;
+        }
+    }
+}
+
+/// Add the tiles of every visible [`Tilemap`] layer to the [`SpriteBatcher`].
+/// Runs after the sprites were batched, as `prepare` starts over.
+pub fn prepare_tilemap_batches(world: &mut World) {
+    let entities: Vec<Entity> = Query::<(Entity, &Tilemap)>::new(world)
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    if entities.is_empty() {
+        return;
+    }
+    let Some(asset_server) = world.get_resource::<AssetServer>() else {
+        return;
+    };
+    let Some(mut batcher) = world.get_resource_mut::<SpriteBatcher>() else {
+        return;
+    };
+
+    for entity in entities {
+        let matrix = world
+            .get_component::<Transform>(entity)
+            .map(|t| t.compute_matrix())
+            .unwrap_or(Mat4::IDENTITY);
+        let z = world.get_component::<ZOrder>(entity).map_or(0.0, |z| z.0);
+        let Some(tilemap) = world.get_component_mut::<Tilemap>(entity) else {
+            continue;
+        };
+        let atlases: Vec<Option<Arc<TextureAtlas>>> = tilemap
+            .tilesets
+            .iter()
+            .map(|handle| asset_server.get(handle))
+            .collect();
+
+        for index in 0..tilemap.layers.len() {
+            if !tilemap.layers[index].visible {
+                continue;
+            }
+            tilemap.rebuild_chunks(index, &atlases);
+            let layer = &tilemap.layers[index];
+            for (tileset, atlas) in atlases.iter().enumerate() {
+                let Some(atlas) = atlas else {
+                    continue;
+                };
+                let instances = layer
+                    .chunks
+                    .values()
+                    .flat_map(|chunk| chunk.instances.iter())
+                    .filter(|(set, _)| *set as usize == tileset)
+                    .flat_map(|(_, instances)| instances.iter())
+                    .map(|instance| {
+                        let local = Mat4::from_cols_array_2d(&instance.transform);
+                        SpriteInstance {
+                            transform: (matrix * local).to_cols_array_2d(),
+                            ..*instance
+                        }
+                    });
+                batcher.insert_layer(z + layer.z, &atlas.texture, instances);
+            }
+        }
+    }
+}
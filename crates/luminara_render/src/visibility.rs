@@ -0,0 +1,252 @@
+//! Per-frame visibility of mesh entities.
+//!
+//! [`visibility_system`] runs frustum culling, LOD selection and CPU
+//! occlusion culling as one pass over every active camera. Its
+//! [`VisibleEntities`] decide which renderables are extracted into the render
+//! world and with which [`Lod`] mesh.
+
+use crate::components::Lod;
+use crate::frustum_culling::{Frustum, FrustumCullingSystem};
+use crate::mesh::{Mesh, AABB};
+use crate::render_target::{collect_camera_views, CameraView};
+use crate::render_world::window_size;
+use crate::software_occlusion::{Occluder, OcclusionBuffer};
+use luminara_asset::{AssetServer, Handle};
+use luminara_core::shared_types::{Query, Resource, World};
+use luminara_core::Entity;
+use luminara_math::{Mat4, Transform};
+use std::collections::{HashMap, HashSet};
+
+/// Settings and scratch state of the visibility pass
+pub struct VisibilityCulling {
+    pub frustum_culling: bool,
+    pub occlusion_culling: bool,
+    frustum: FrustumCullingSystem,
+    occlusion: OcclusionBuffer,
+}
+
+impl VisibilityCulling {
+    /// Occluders are rasterized at `width` x `height` whatever the view size
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            frustum_culling: true,
+            occlusion_culling: true,
+            frustum: FrustumCullingSystem::new(),
+            occlusion: OcclusionBuffer::new(width, height),
+        }
+    }
+
+    /// Depth buffer of the last view culled
+    pub fn occlusion_buffer(&self) -> &OcclusionBuffer {
+        &self.occlusion
+    }
+
+    /// Cull the mesh entities of `world` against `views`
+    pub fn run(
+        &mut self,
+        world: &World,
+        views: &[CameraView],
+    ) -> (VisibleEntities, VisibilityStats) {
+        let mut visible = VisibleEntities::default();
+        let mut stats = VisibilityStats::default();
+        let Some(asset_server) = world.get_resource::<AssetServer>() else {
+            return (visible, stats);
+        };
+
+        let meshes = Query::<(Entity, &Handle<Mesh>, &Transform)>::new(world);
+        let mut candidates = Vec::new();
+        for (entity, mesh, transform) in meshes.iter() {
+            stats.total += 1;
+            match asset_server.get(mesh) {
+                Some(loaded) => candidates.push(Candidate {
+                    entity,
+                    mesh: mesh.clone(),
+                    aabb: loaded.aabb,
+                    matrix: transform.compute_matrix(),
+                }),
+                // Meshes still loading have no bounds yet
+                None => {
+                    visible.lod_levels.insert(entity, 0);
+                }
+            }
+        }
+        self.frustum.set_entities(
+            candidates
+                .iter()
+                .map(|candidate| (candidate.aabb, candidate.matrix)),
+        );
+        self.frustum.rebuild_bvh();
+
+        let mut in_frustum = vec![false; candidates.len()];
+        let mut occluders = HashSet::new();
+        for view in views {
+            let frustum = Frustum::from_view_projection(&view.view_proj);
+            let indices: Vec<usize> = if self.frustum_culling {
+                // BVH leaves are coarse; test their entities one by one
+                self.frustum
+                    .cull(&frustum)
+                    .into_iter()
+                    .filter(|&i| {
+                        frustum.intersects_world_aabb(&candidates[i].aabb, &candidates[i].matrix)
+                    })
+                    .collect()
+            } else {
+                (0..candidates.len()).collect()
+            };
+
+            if self.occlusion_culling {
+                self.occlusion.clear(view.view_proj);
+                for &i in &indices {
+                    let candidate = &candidates[i];
+                    let Some(occluder) = world.get_component::<Occluder>(candidate.entity) else {
+                        continue;
+                    };
+                    let handle = occluder
+                        .mesh
+                        .clone()
+                        .or_else(|| {
+                            let lod = world.get_component::<Lod>(candidate.entity)?;
+                            lod.meshes.last().cloned()
+                        })
+                        .unwrap_or_else(|| candidate.mesh.clone());
+                    if let Some(mesh) = asset_server.get(&handle) {
+                        stats.occluder_triangles +=
+                            self.occlusion.rasterize_mesh(&mesh, &candidate.matrix);
+                        occluders.insert(candidate.entity);
+                    }
+                }
+                self.occlusion.build_pyramid();
+            }
+
+            for i in indices {
+                in_frustum[i] = true;
+                let candidate = &candidates[i];
+                if self.occlusion_culling
+                    && self
+                        .occlusion
+                        .is_occluded(&candidate.aabb, &candidate.matrix)
+                {
+                    continue;
+                }
+                let distance = (candidate.matrix.w_axis.truncate() - view.position).length();
+                let level = world
+                    .get_component::<Lod>(candidate.entity)
+                    .map_or(0, |lod| lod.level_at(distance));
+                // The most detailed level any view asks for
+                visible
+                    .lod_levels
+                    .entry(candidate.entity)
+                    .and_modify(|current| *current = (*current).min(level))
+                    .or_insert(level);
+            }
+        }
+
+        for (candidate, in_frustum) in candidates.iter().zip(in_frustum) {
+            if !in_frustum {
+                stats.frustum_culled += 1;
+            } else if !visible.is_visible(candidate.entity) {
+                stats.occlusion_culled += 1;
+            }
+        }
+        stats.visible = visible.len();
+        stats.occluders = occluders.len();
+        for &level in visible.lod_levels.values() {
+            if stats.entities_per_lod.len() <= level {
+                stats.entities_per_lod.resize(level + 1, 0);
+            }
+            stats.entities_per_lod[level] += 1;
+        }
+        (visible, stats)
+    }
+}
+
+impl Default for VisibilityCulling {
+    fn default() -> Self {
+        Self::new(256, 128)
+    }
+}
+
+impl Resource for VisibilityCulling {}
+
+struct Candidate {
+    entity: Entity,
+    mesh: Handle<Mesh>,
+    aabb: AABB,
+    matrix: Mat4,
+}
+
+/// Mesh entities that passed the last visibility pass, with the LOD level
+/// picked for each
+#[derive(Debug, Clone, Default)]
+pub struct VisibleEntities {
+    lod_levels: HashMap<Entity, usize>,
+}
+
+impl VisibleEntities {
+    pub fn is_visible(&self, entity: Entity) -> bool {
+        self.lod_levels.contains_key(&entity)
+    }
+
+    /// `None` when `entity` is not visible
+    pub fn lod_level(&self, entity: Entity) -> Option<usize> {
+        self.lod_levels.get(&entity).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, usize)> + '_ {
+        self.lod_levels
+            .iter()
+            .map(|(entity, level)| (*entity, *level))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lod_levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lod_levels.is_empty()
+    }
+}
+
+impl Resource for VisibleEntities {}
+
+/// Counts from the last visibility pass
+#[derive(Debug, Clone, Default)]
+pub struct VisibilityStats {
+    /// Entities with a mesh
+    pub total: usize,
+    /// Outside every camera's frustum
+    pub frustum_culled: usize,
+    /// Inside some frustum but hidden behind occluders in all of them
+    pub occlusion_culled: usize,
+    pub visible: usize,
+    pub occluders: usize,
+    pub occluder_triangles: usize,
+    /// Visible entities per LOD level
+    pub entities_per_lod: Vec<usize>,
+}
+
+impl VisibilityStats {
+    /// Fraction of entities culled, 0 to 1
+    pub fn culled_fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.frustum_culled + self.occlusion_culled) as f32 / self.total as f32
+    }
+}
+
+impl Resource for VisibilityStats {}
+
+/// Culls mesh entities against the active cameras when a
+/// [`VisibilityCulling`] resource is present
+pub fn visibility_system(world: &mut World) {
+    let (visible, stats) = {
+        let Some(mut culling) = world.get_resource_mut::<VisibilityCulling>() else {
+            return;
+        };
+        let views = collect_camera_views(world, window_size(world));
+        culling.run(world, &views)
+    };
+    world.insert_resource(visible);
+    world.insert_resource(stats);
+}
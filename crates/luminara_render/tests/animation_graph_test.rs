@@ -0,0 +1,439 @@
+use luminara_asset::{AssetId, AssetLoader, Handle};
+use luminara_core::World;
+use luminara_math::{Quat, Transform, Vec2, Vec3};
+use luminara_render::animation::{AnimationChannel, AnimationClip, AnimationOutput, AnimationPath};
+use luminara_render::{
+    apply_animation_pose_system, blend_weights_1d, blend_weights_2d, AnimationEventKey,
+    AnimationGraph, AnimationGraphLoader, AnimationGraphPlayer, AnimationLayer, AnimationState,
+    AnimationTargets, AnimationTransition, BlendPoint1D, BoneMask, LayerBlend, Motion,
+    TransitionCondition,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const EPSILON: f32 = 1e-4;
+
+/// Clip moving `node` along X from `from` to `to` over one second
+fn slide(node: usize, from: f32, to: f32) -> AnimationClip {
+    AnimationClip {
+        name: format!("slide{}", node),
+        duration: 1.0,
+        channels: vec![AnimationChannel {
+            target_node_index: node,
+            target_path: AnimationPath::Translation,
+            inputs: vec![0.0, 1.0],
+            outputs: AnimationOutput::Vector3(vec![Vec3::X * from, Vec3::X * to]),
+        }],
+    }
+}
+
+fn handle(path: &str) -> Handle<AnimationClip> {
+    Handle::new(AssetId::from_path(path), 0)
+}
+
+struct Clips(HashMap<AssetId, Arc<AnimationClip>>);
+
+impl Clips {
+    fn new(clips: &[(&str, AnimationClip)]) -> Self {
+        Self(
+            clips
+                .iter()
+                .map(|(path, clip)| (AssetId::from_path(path), Arc::new(clip.clone())))
+                .collect(),
+        )
+    }
+
+    fn lookup(&self) -> impl Fn(&Handle<AnimationClip>) -> Option<Arc<AnimationClip>> + '_ {
+        |handle| self.0.get(&handle.id()).cloned()
+    }
+}
+
+fn x(player: &AnimationGraphPlayer, node: usize) -> f32 {
+    player.pose.get(node).unwrap().translation.unwrap().x
+}
+
+fn graph(layers: Vec<AnimationLayer>) -> AnimationGraph {
+    AnimationGraph {
+        layers,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_clip_sampling_interpolates_keyframes() {
+    let mut clip = slide(0, 0.0, 2.0);
+    clip.channels.push(AnimationChannel {
+        target_node_index: 0,
+        target_path: AnimationPath::Rotation,
+        inputs: vec![0.0, 1.0],
+        outputs: AnimationOutput::Rotation(vec![
+            Quat::IDENTITY,
+            Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+        ]),
+    });
+
+    let pose = clip.sample(0.5);
+    let bone = pose.get(0).unwrap();
+    assert!((bone.translation.unwrap().x - 1.0).abs() < EPSILON);
+    let expected = Quat::from_rotation_y(std::f32::consts::FRAC_PI_4);
+    assert!(bone.rotation.unwrap().angle_between(expected) < EPSILON);
+    assert!(bone.scale.is_none());
+
+    // Clamped past the last keyframe
+    let end = clip.sample(5.0);
+    assert!((end.get(0).unwrap().translation.unwrap().x - 2.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_blend_space_weights() {
+    let weights = blend_weights_1d(&[2.0, 0.0, 1.0], 0.25);
+    assert!((weights[1] - 0.75).abs() < EPSILON);
+    assert!((weights[2] - 0.25).abs() < EPSILON);
+    assert_eq!(weights[0], 0.0);
+    assert_eq!(blend_weights_1d(&[0.0, 1.0], 3.0), vec![0.0, 1.0]);
+
+    let points = [
+        Vec2::ZERO,
+        Vec2::new(0.0, 1.0),
+        Vec2::new(1.0, 0.0),
+        Vec2::new(-1.0, 0.0),
+    ];
+    let on_point = blend_weights_2d(&points, Vec2::new(0.0, 1.0));
+    assert!((on_point[1] - 1.0).abs() < EPSILON);
+
+    let between = blend_weights_2d(&points, Vec2::new(0.5, 0.0));
+    assert!((between.iter().sum::<f32>() - 1.0).abs() < EPSILON);
+    assert!((between[0] - 0.5).abs() < EPSILON);
+    assert!((between[2] - 0.5).abs() < EPSILON);
+    assert_eq!(between[3], 0.0);
+}
+
+#[test]
+fn test_blend_space_state_blends_clips() {
+    let clips = Clips::new(&[("walk", slide(0, 0.0, 0.0)), ("run", slide(0, 4.0, 4.0))]);
+    let motion = Motion::BlendSpace1D {
+        parameter: "speed".to_string(),
+        points: vec![
+            BlendPoint1D {
+                position: 0.0,
+                clip: handle("walk"),
+            },
+            BlendPoint1D {
+                position: 1.0,
+                clip: handle("run"),
+            },
+        ],
+    };
+    let graph = graph(vec![AnimationLayer::new(
+        "base",
+        vec![AnimationState::new("locomotion", motion)],
+    )]);
+
+    let mut player = AnimationGraphPlayer::new(Handle::default());
+    player.set_float("speed", 0.25);
+    player.update(&graph, &clips.lookup(), 0.1);
+    assert!((x(&player, 0) - 1.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_crossfade_blends_over_duration() {
+    let clips = Clips::new(&[("a", slide(0, 0.0, 0.0)), ("b", slide(0, 10.0, 10.0))]);
+    let graph = graph(vec![AnimationLayer::new(
+        "base",
+        vec![
+            AnimationState::new("a", Motion::Clip(handle("a"))),
+            AnimationState::new("b", Motion::Clip(handle("b"))),
+        ],
+    )]);
+
+    let mut player = AnimationGraphPlayer::new(Handle::default());
+    player.update(&graph, &clips.lookup(), 0.1);
+    assert_eq!(x(&player, 0), 0.0);
+
+    player.crossfade_to(0, "b", 0.4);
+    player.update(&graph, &clips.lookup(), 0.1);
+    assert!((x(&player, 0) - 2.5).abs() < EPSILON);
+    assert!(player.is_fading(0));
+    assert_eq!(player.current_state(&graph, 0), Some("b"));
+
+    player.update(&graph, &clips.lookup(), 0.3);
+    assert!((x(&player, 0) - 10.0).abs() < EPSILON);
+    assert!(!player.is_fading(0));
+}
+
+#[test]
+fn test_player_follows_a_changed_graph() {
+    let clips = Clips::new(&[("a", slide(0, 0.0, 0.0)), ("b", slide(0, 10.0, 10.0))]);
+    let states = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| AnimationState::new(*name, Motion::Clip(handle(name))))
+            .collect::<Vec<_>>()
+    };
+    let original = graph(vec![AnimationLayer::new("base", states(&["a", "b", "b"]))]);
+
+    let mut player = AnimationGraphPlayer::new(Handle::default());
+    player.update(&original, &clips.lookup(), 0.1);
+    player.crossfade_to(0, "b", 0.4);
+    player.update(&original, &clips.lookup(), 0.1);
+    player.crossfade_to(0, "a", 0.4);
+    player.update(&original, &clips.lookup(), 0.1);
+    assert!(player.is_fading(0));
+
+    // Reloaded in place with fewer states: the fade source is gone
+    let fewer = graph(vec![AnimationLayer::new("base", states(&["a"]))]);
+    player.update(&fewer, &clips.lookup(), 0.1);
+    assert_eq!(player.current_state(&fewer, 0), Some("a"));
+    assert!(!player.is_fading(0));
+
+    let mut reordered = graph(vec![AnimationLayer::new("base", states(&["b"]))]);
+    reordered
+        .layers
+        .push(AnimationLayer::new("upper", states(&["b", "a"])));
+    player.update(&reordered, &clips.lookup(), 0.1);
+    assert_eq!(player.current_state(&reordered, 1), Some("b"));
+    assert!((x(&player, 0) - 10.0).abs() < EPSILON);
+
+    // Pointing the player at another graph restarts its layers
+    player.crossfade_to(1, "a", 0.0);
+    player.update(&reordered, &clips.lookup(), 0.1);
+    assert_eq!(player.current_state(&reordered, 1), Some("a"));
+    player.graph = Handle::new(AssetId::from_path("other.animgraph.ron"), 0);
+    player.update(&reordered, &clips.lookup(), 0.1);
+    assert_eq!(player.current_state(&reordered, 1), Some("b"));
+}
+
+#[test]
+fn test_state_machine_transitions() {
+    let clips = Clips::new(&[("idle", slide(0, 0.0, 0.0)), ("jump", slide(0, 1.0, 1.0))]);
+    let mut jump = AnimationState::new("jump", Motion::Clip(handle("jump")));
+    jump.looping = false;
+    let mut layer = AnimationLayer::new(
+        "base",
+        vec![
+            AnimationState::new("idle", Motion::Clip(handle("idle"))),
+            jump,
+        ],
+    );
+    layer.transitions = vec![
+        AnimationTransition {
+            from: Some("idle".to_string()),
+            to: "jump".to_string(),
+            conditions: vec![
+                TransitionCondition::Trigger("jump".to_string()),
+                TransitionCondition::IsTrue("grounded".to_string()),
+            ],
+            duration: 0.0,
+            exit_time: None,
+        },
+        AnimationTransition {
+            from: Some("jump".to_string()),
+            to: "idle".to_string(),
+            conditions: Vec::new(),
+            duration: 0.0,
+            exit_time: Some(0.9),
+        },
+    ];
+    let graph = graph(vec![layer]);
+    let lookup = clips.lookup();
+
+    let mut player = AnimationGraphPlayer::new(Handle::default());
+    player.set_trigger("jump");
+    player.update(&graph, &lookup, 0.1);
+    assert_eq!(player.current_state(&graph, 0), Some("idle"));
+
+    player.set_bool("grounded", true);
+    player.update(&graph, &lookup, 0.1);
+    assert_eq!(player.current_state(&graph, 0), Some("jump"));
+    assert!(player.parameters.triggers.is_empty());
+
+    // Leaves only once the exit time is reached
+    player.update(&graph, &lookup, 0.5);
+    assert_eq!(player.current_state(&graph, 0), Some("jump"));
+    player.update(&graph, &lookup, 0.5);
+    assert_eq!(player.current_state(&graph, 0), Some("idle"));
+}
+
+#[test]
+fn test_layers_respect_masks() {
+    let mut base = slide(0, 1.0, 1.0);
+    base.channels.extend(slide(1, 1.0, 1.0).channels);
+    let mut wave = slide(1, 5.0, 5.0);
+    wave.channels.extend(slide(0, 5.0, 5.0).channels);
+    let clips = Clips::new(&[("base", base), ("wave", wave), ("nod", slide(1, 0.0, 2.0))]);
+
+    let mut upper = AnimationLayer::new(
+        "upper",
+        vec![AnimationState::new("wave", Motion::Clip(handle("wave")))],
+    );
+    upper.mask = BoneMask::new([1]);
+    upper.weight = 0.5;
+    let mut additive = AnimationLayer::new(
+        "additive",
+        vec![AnimationState::new("nod", Motion::Clip(handle("nod")))],
+    );
+    additive.blend = LayerBlend::Additive;
+
+    let graph = graph(vec![
+        AnimationLayer::new(
+            "base",
+            vec![AnimationState::new("base", Motion::Clip(handle("base")))],
+        ),
+        upper,
+        additive,
+    ]);
+
+    let mut player = AnimationGraphPlayer::new(Handle::default());
+    player.update(&graph, &clips.lookup(), 0.5);
+    assert!((x(&player, 0) - 1.0).abs() < EPSILON);
+    // Halfway to the override layer, plus the additive offset at t = 0.5
+    assert!((x(&player, 1) - (3.0 + 1.0)).abs() < EPSILON);
+
+    player.set_layer_weight(1, 0.0);
+    player.update(&graph, &clips.lookup(), 0.0);
+    assert!((x(&player, 1) - 2.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_events_fire_when_crossed() {
+    let clips = Clips::new(&[("walk", slide(0, 0.0, 1.0))]);
+    let mut walk = AnimationState::new("walk", Motion::Clip(handle("walk")));
+    walk.events = vec![
+        AnimationEventKey {
+            time: 0.25,
+            name: "left_foot".to_string(),
+        },
+        AnimationEventKey {
+            time: 0.75,
+            name: "right_foot".to_string(),
+        },
+    ];
+    let graph = graph(vec![AnimationLayer::new("base", vec![walk])]);
+    let lookup = clips.lookup();
+
+    let mut player = AnimationGraphPlayer::new(Handle::default());
+    let names = |events: Vec<luminara_render::AnimationEvent>| {
+        events.into_iter().map(|e| e.name).collect::<Vec<_>>()
+    };
+    assert!(player.update(&graph, &lookup, 0.2).is_empty());
+    assert_eq!(
+        names(player.update(&graph, &lookup, 0.1)),
+        vec!["left_foot"]
+    );
+    // Wraps around the loop point
+    assert_eq!(
+        names(player.update(&graph, &lookup, 1.0)),
+        vec!["left_foot", "right_foot"]
+    );
+}
+
+#[test]
+fn test_root_motion_is_extracted() {
+    let clips = Clips::new(&[("walk", slide(0, 0.0, 2.0))]);
+    let mut graph = graph(vec![AnimationLayer::new(
+        "base",
+        vec![AnimationState::new("walk", Motion::Clip(handle("walk")))],
+    )]);
+    graph.root_motion_node = Some(0);
+
+    let mut player = AnimationGraphPlayer::new(Handle::default());
+    player.update(&graph, &clips.lookup(), 0.25);
+    assert!((player.root_motion.translation.x - 0.5).abs() < EPSILON);
+    // The root stays at its first-frame position
+    assert_eq!(x(&player, 0), 0.0);
+
+    // Looping from 0.75 to 0.25 covers half the clip
+    player.update(&graph, &clips.lookup(), 0.5);
+    player.update(&graph, &clips.lookup(), 0.5);
+    assert!((player.root_motion.translation.x - 1.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_pose_is_applied_to_targets() {
+    let clips = Clips::new(&[("walk", slide(3, 0.0, 2.0))]);
+    let graph = graph(vec![AnimationLayer::new(
+        "base",
+        vec![AnimationState::new("walk", Motion::Clip(handle("walk")))],
+    )]);
+    let mut player = AnimationGraphPlayer::new(Handle::default());
+    player.update(&graph, &clips.lookup(), 0.5);
+
+    let mut world = World::new();
+    let joint = world.spawn();
+    world.add_component(joint, Transform::default()).unwrap();
+    let character = world.spawn();
+    world.add_component(character, player).unwrap();
+    world
+        .add_component(
+            character,
+            AnimationTargets::from_scene_ids(&HashMap::from([(3, joint)])),
+        )
+        .unwrap();
+
+    apply_animation_pose_system(&mut world);
+
+    let transform = world.get_component::<Transform>(joint).unwrap();
+    assert!((transform.translation.x - 1.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_graph_loader_parses_ron() {
+    let source = r#"(
+        parameters: { "speed": Float(0.0), "attack": Trigger },
+        root_motion_node: Some(0),
+        layers: [
+            (
+                name: "base",
+                states: [
+                    (
+                        name: "locomotion",
+                        motion: BlendSpace1D(
+                            parameter: "speed",
+                            points: [
+                                (position: 0.0, clip: "robot.glb#Animation0"),
+                                (position: 1.0, clip: "robot.glb#Animation1"),
+                            ],
+                        ),
+                        events: [(time: 0.5, name: "step")],
+                    ),
+                    (name: "attack", motion: Clip("robot.glb#Animation2"), looping: false),
+                ],
+                transitions: [
+                    (to: "attack", conditions: [Trigger("attack")], duration: 0.2),
+                    (from: Some("attack"), to: "locomotion", exit_time: Some(1.0), duration: 0.2),
+                ],
+            ),
+            (
+                name: "upper",
+                blend: Additive,
+                weight: 0.5,
+                mask: [2, 3],
+                states: [(name: "breathe", motion: Clip("robot.glb#Animation3"))],
+            ),
+        ],
+    )"#;
+    let graph = AnimationGraphLoader
+        .load(source.as_bytes(), Path::new("robot.animgraph.ron"))
+        .unwrap();
+
+    assert_eq!(graph.layers.len(), 2);
+    assert_eq!(graph.root_motion_node, Some(0));
+    let base = &graph.layers[0];
+    assert_eq!(base.transitions.len(), 2);
+    assert!(!base.states[1].looping);
+    match &base.states[0].motion {
+        Motion::BlendSpace1D { points, .. } => {
+            assert_eq!(points[1].clip, handle("robot.glb#Animation1"));
+        }
+        other => panic!("unexpected motion {:?}", other),
+    }
+    let upper = &graph.layers[1];
+    assert_eq!(upper.blend, LayerBlend::Additive);
+    assert!(upper.mask.contains(2) && !upper.mask.contains(0));
+
+    let broken = source.replace("to: \"attack\"", "to: \"missing\"");
+    assert!(AnimationGraphLoader
+        .load(broken.as_bytes(), Path::new("robot.animgraph.ron"))
+        .is_err());
+}
@@ -0,0 +1,188 @@
+use luminara_asset::AssetServer;
+use luminara_core::shared_types::World;
+use luminara_core::Entity;
+use luminara_math::{Transform, Vec3};
+use luminara_render::{
+    collect_camera_views, Camera, RenderLayers, RenderTarget, TargetKey, Texture, Viewport,
+};
+
+fn spawn_camera(world: &mut World, camera: Camera) -> Entity {
+    let entity = world.spawn();
+    world.add_component(entity, camera).unwrap();
+    world
+        .add_component(entity, Transform::from_xyz(0.0, 0.0, 5.0))
+        .unwrap();
+    entity
+}
+
+#[test]
+fn test_viewport_to_pixels() {
+    assert_eq!(Viewport::FULL.to_pixels((1920, 1080)), (0, 0, 1920, 1080));
+
+    // Right half for split-screen
+    let right = Viewport::new(0.5, 0.0, 0.5, 1.0);
+    assert_eq!(right.to_pixels((1920, 1080)), (960, 0, 960, 1080));
+
+    // Out of range rects are clamped and never empty
+    let outside = Viewport::new(0.9, 0.9, 0.5, 0.5);
+    assert_eq!(outside.to_pixels((100, 100)), (90, 90, 10, 10));
+    let degenerate = Viewport::new(1.0, 0.0, 0.0, 1.0);
+    assert_eq!(degenerate.to_pixels((100, 100)), (99, 0, 1, 100));
+}
+
+#[test]
+fn test_render_layers() {
+    let default = RenderLayers::default();
+    assert!(default.contains(0));
+    assert!(!default.contains(1));
+
+    let minimap = RenderLayers::layer(3);
+    assert!(!default.intersects(&minimap));
+    assert!(default.with(3).intersects(&minimap));
+    assert!(!default.with(3).without(3).intersects(&minimap));
+    assert!(RenderLayers::ALL.intersects(&minimap));
+    assert!(!RenderLayers::NONE.intersects(&RenderLayers::ALL));
+    assert!(RenderLayers::layer(31).contains(31));
+}
+
+#[test]
+#[should_panic(expected = "render layer out of range")]
+fn test_render_layer_out_of_range_panics() {
+    let _ = RenderLayers::layer(32);
+}
+
+#[test]
+fn test_cameras_are_sorted_by_order() {
+    let mut world = World::new();
+    let overlay = spawn_camera(
+        &mut world,
+        Camera {
+            order: 10,
+            ..Default::default()
+        },
+    );
+    let main = spawn_camera(&mut world, Camera::default());
+    let background = spawn_camera(
+        &mut world,
+        Camera {
+            order: -1,
+            ..Default::default()
+        },
+    );
+    spawn_camera(
+        &mut world,
+        Camera {
+            is_active: false,
+            ..Default::default()
+        },
+    );
+
+    let views = collect_camera_views(&world, (800, 600));
+    let entities: Vec<_> = views.iter().map(|view| view.entity).collect();
+    assert_eq!(entities, vec![background, main, overlay]);
+}
+
+#[test]
+fn test_split_screen_viewports() {
+    let mut world = World::new();
+    let left = spawn_camera(
+        &mut world,
+        Camera {
+            viewport: Viewport::new(0.0, 0.0, 0.5, 1.0),
+            ..Default::default()
+        },
+    );
+    let right = spawn_camera(
+        &mut world,
+        Camera {
+            order: 1,
+            viewport: Viewport::new(0.5, 0.0, 0.5, 1.0),
+            ..Default::default()
+        },
+    );
+    world.add_component(right, RenderLayers::layer(1)).unwrap();
+
+    let views = collect_camera_views(&world, (1280, 720));
+    assert_eq!(views.len(), 2);
+    assert_eq!(views[0].entity, left);
+    assert_eq!(views[0].viewport, (0, 0, 640, 720));
+    assert_eq!(views[1].viewport, (640, 0, 640, 720));
+    assert!(views.iter().all(|view| view.key == TargetKey::Window));
+
+    // The aspect ratio follows the viewport, not the window
+    let full = Camera::default().projection_matrix(1280.0 / 720.0);
+    let half = Camera::default().projection_matrix(640.0 / 720.0);
+    let view_matrix = Transform::from_xyz(0.0, 0.0, 5.0)
+        .compute_matrix()
+        .inverse();
+    assert_eq!(views[0].view_proj, half * view_matrix);
+    assert_ne!(views[0].view_proj, full * view_matrix);
+
+    // Renderables default to layer 0
+    assert!(views[0].sees(&RenderLayers::default()));
+    assert!(!views[1].sees(&RenderLayers::default()));
+    assert!(views[1].sees(&RenderLayers::layer(1)));
+    assert_eq!(views[1].position, Vec3::new(0.0, 0.0, 5.0));
+}
+
+#[test]
+fn test_offscreen_targets() {
+    let mut world = World::new();
+    let asset_server = AssetServer::new("assets");
+    let preview = asset_server.add(Texture::render_target(256, 128));
+    world.insert_resource(asset_server);
+
+    let security = spawn_camera(
+        &mut world,
+        Camera {
+            target: RenderTarget::Texture(preview.clone()),
+            viewport: Viewport::new(0.0, 0.0, 0.5, 0.5),
+            ..Default::default()
+        },
+    );
+    let capture = spawn_camera(
+        &mut world,
+        Camera {
+            target: RenderTarget::Image {
+                width: 64,
+                height: 32,
+            },
+            ..Default::default()
+        },
+    );
+    // Targets that are not loaded yet are skipped
+    spawn_camera(
+        &mut world,
+        Camera {
+            target: RenderTarget::Texture(Default::default()),
+            ..Default::default()
+        },
+    );
+
+    let views = collect_camera_views(&world, (1920, 1080));
+    assert_eq!(views.len(), 2);
+
+    let texture_view = views.iter().find(|view| view.entity == security).unwrap();
+    assert_eq!(texture_view.key, TargetKey::Texture(preview.id()));
+    assert_eq!(texture_view.target_size, (256, 128));
+    assert_eq!(texture_view.viewport, (0, 0, 128, 64));
+
+    let image_view = views.iter().find(|view| view.entity == capture).unwrap();
+    assert_eq!(image_view.key, TargetKey::Image(capture));
+    assert_eq!(image_view.target_size, (64, 32));
+}
+
+#[test]
+fn test_camera_deserializes_without_new_fields() {
+    // Scenes saved before cameras had targets
+    let camera: Camera = serde_json::from_value(serde_json::json!({
+        "projection": { "Perspective": { "fov": 45.0, "near": 0.1, "far": 100.0 } },
+        "clear_color": { "r": 0.0, "g": 0.0, "b": 0.0, "a": 1.0 },
+        "is_active": true,
+    }))
+    .unwrap();
+
+    assert_eq!(camera.order, 0);
+    assert_eq!(camera.target, RenderTarget::Window);
+    assert_eq!(camera.viewport, Viewport::FULL);
+}
@@ -89,6 +89,7 @@ fn test_cascade_view_proj_calculation() {
         },
         clear_color: luminara_math::Color::BLACK,
         is_active: true,
+        ..Default::default()
     };
 
     let near = 0.1;
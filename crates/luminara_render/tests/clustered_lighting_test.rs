@@ -0,0 +1,321 @@
+use luminara_math::{Color, Mat4, Quat, Transform, Vec2, Vec3};
+use luminara_render::{
+    Camera, ClusterConfig, ClusterLight, ClusterLightShape, LightClusters, PointLight, Projection,
+    ShaderComposer, ShaderDefs, SpotLight,
+};
+
+/// Deterministic xorshift so failures reproduce
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next()
+    }
+
+    fn direction(&mut self) -> Vec3 {
+        Vec3::new(
+            self.range(-1.0, 1.0),
+            self.range(-1.0, 1.0),
+            self.range(-1.0, 1.0),
+        )
+        .try_normalize()
+        .unwrap_or(Vec3::NEG_Z)
+    }
+}
+
+fn camera(projection: Projection) -> (Camera, Transform) {
+    let camera = Camera {
+        projection,
+        ..Default::default()
+    };
+    let transform = Transform {
+        translation: Vec3::new(2.0, 3.0, 10.0),
+        rotation: Quat::from_rotation_y(0.4) * Quat::from_rotation_x(-0.2),
+        ..Default::default()
+    };
+    (camera, transform)
+}
+
+fn perspective() -> (Camera, Transform) {
+    camera(Projection::Perspective {
+        fov: 60.0,
+        near: 0.1,
+        far: 100.0,
+    })
+}
+
+/// Mixed point and spot lights scattered around the camera
+fn random_lights(rng: &mut Rng, count: usize) -> Vec<ClusterLight> {
+    (0..count)
+        .map(|i| {
+            let transform = Transform::from_translation(Vec3::new(
+                rng.range(-40.0, 40.0),
+                rng.range(-20.0, 20.0),
+                rng.range(-90.0, 20.0),
+            ));
+            let range = rng.range(0.5, 12.0);
+            if i % 3 == 0 {
+                let direction = rng.direction();
+                let transform = Transform {
+                    rotation: Quat::from_rotation_arc(Vec3::NEG_Z, direction),
+                    ..transform
+                };
+                let outer_angle = rng.range(0.1, 1.2);
+                let spot = SpotLight {
+                    color: Color::WHITE,
+                    intensity: 1.0,
+                    range,
+                    inner_angle: outer_angle * 0.5,
+                    outer_angle,
+                    cast_shadows: false,
+                };
+                ClusterLight::from_spot(&spot, &transform)
+            } else {
+                let point = PointLight {
+                    color: Color::WHITE,
+                    intensity: 1.0,
+                    range,
+                    cast_shadows: false,
+                };
+                ClusterLight::from_point(&point, &transform)
+            }
+        })
+        .collect()
+}
+
+fn build(
+    config: ClusterConfig,
+    (camera, transform): &(Camera, Transform),
+    aspect: f32,
+    lights: Vec<ClusterLight>,
+) -> LightClusters {
+    let (Projection::Perspective { near, far, .. } | Projection::Orthographic { near, far, .. }) =
+        camera.projection;
+    let mut clusters = LightClusters::new(config);
+    clusters.update_frustum(camera.projection_matrix(aspect), near, far);
+    clusters.assign(camera.view_matrix(&transform.compute_matrix()), lights);
+    clusters
+}
+
+/// Test every light against every cluster
+fn assert_matches_brute_force(clusters: &LightClusters) {
+    let view = *clusters.view();
+    let local: Vec<ClusterLight> = clusters
+        .lights()
+        .iter()
+        .map(|light| light.transformed(&view))
+        .collect();
+    for cluster in 0..clusters.config().cluster_count() {
+        let (min, max) = clusters.cluster_bounds(cluster);
+        let expected: Vec<u32> = (0..local.len() as u32)
+            .filter(|&i| local[i as usize].intersects_aabb(min, max))
+            .collect();
+        assert_eq!(
+            clusters.cluster_lights(cluster),
+            expected.as_slice(),
+            "cluster {}",
+            cluster
+        );
+    }
+}
+
+#[test]
+fn test_binning_matches_brute_force_perspective() {
+    let mut rng = Rng(0x9e3779b97f4a7c15);
+    let clusters = build(
+        ClusterConfig {
+            max_lights_per_cluster: u32::MAX,
+            ..Default::default()
+        },
+        &perspective(),
+        16.0 / 9.0,
+        random_lights(&mut rng, 600),
+    );
+    assert!(!clusters.indices().is_empty());
+    assert_eq!(clusters.dropped(), 0);
+    assert_matches_brute_force(&clusters);
+}
+
+#[test]
+fn test_binning_matches_brute_force_orthographic() {
+    let mut rng = Rng(42);
+    let clusters = build(
+        ClusterConfig {
+            tiles_x: 8,
+            tiles_y: 8,
+            slices: 16,
+            max_lights_per_cluster: u32::MAX,
+        },
+        &camera(Projection::Orthographic {
+            size: 40.0,
+            near: 0.5,
+            far: 120.0,
+        }),
+        1.0,
+        random_lights(&mut rng, 400),
+    );
+    assert!(!clusters.indices().is_empty());
+    assert_matches_brute_force(&clusters);
+}
+
+#[test]
+fn test_lit_points_find_their_lights() {
+    let mut rng = Rng(7);
+    let view_camera = perspective();
+    let clusters = build(
+        ClusterConfig {
+            max_lights_per_cluster: u32::MAX,
+            ..Default::default()
+        },
+        &view_camera,
+        1.5,
+        random_lights(&mut rng, 300),
+    );
+    let projection = view_camera.0.projection_matrix(1.5);
+    let view = *clusters.view();
+
+    // Every light reaching a visible point is in that point's cluster
+    let mut checked = 0;
+    for _ in 0..3000 {
+        let depth = rng.range(0.2, 99.0);
+        let ndc = Vec2::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+        let view_point = projection.inverse().project_point3(ndc.extend(0.5));
+        let view_point = view_point * (depth / -view_point.z);
+        let cluster = clusters.cluster_at(ndc, depth).unwrap();
+        for (index, light) in clusters.lights().iter().enumerate() {
+            let light = light.transformed(&view);
+            let to_point = view_point - light.position;
+            if to_point.length() >= light.range * 0.999 {
+                continue;
+            }
+            if let ClusterLightShape::Spot {
+                direction,
+                outer_angle,
+                ..
+            } = light.shape
+            {
+                if to_point.normalize().dot(direction) <= outer_angle.cos() + 1e-3 {
+                    continue;
+                }
+            }
+            checked += 1;
+            assert!(
+                clusters.cluster_lights(cluster).contains(&(index as u32)),
+                "light {} missing from cluster {}",
+                index,
+                cluster
+            );
+        }
+    }
+    assert!(checked > 50, "only {} lit samples", checked);
+}
+
+#[test]
+fn test_scales_to_thousands_of_lights() {
+    let mut rng = Rng(1234);
+    let clusters = build(
+        ClusterConfig::default(),
+        &perspective(),
+        16.0 / 9.0,
+        random_lights(&mut rng, 5000),
+    );
+    assert_eq!(clusters.lights().len(), 5000);
+    let total: u32 = clusters.ranges().iter().map(|range| range.count).sum();
+    assert_eq!(total as usize, clusters.indices().len());
+    // Ranges are contiguous and in cluster order
+    let mut offset = 0;
+    for range in clusters.ranges() {
+        assert_eq!(range.offset, offset);
+        assert!(range.count <= ClusterConfig::default().max_lights_per_cluster);
+        offset += range.count;
+    }
+}
+
+#[test]
+fn test_cluster_cap_drops_extra_lights() {
+    let point = PointLight {
+        color: Color::WHITE,
+        intensity: 1.0,
+        range: 5.0,
+        cast_shadows: false,
+    };
+    let camera = perspective();
+    let target = camera.1.translation + camera.1.forward() * 10.0;
+    let lights = (0..20)
+        .map(|_| ClusterLight::from_point(&point, &Transform::from_translation(target)))
+        .collect();
+    let clusters = build(
+        ClusterConfig {
+            max_lights_per_cluster: 8,
+            ..Default::default()
+        },
+        &camera,
+        1.0,
+        lights,
+    );
+    let cluster = clusters.cluster_at(Vec2::ZERO, 10.0).unwrap();
+    assert_eq!(clusters.cluster_lights(cluster), &[0, 1, 2, 3, 4, 5, 6, 7]);
+    assert!(clusters.dropped() > 0);
+}
+
+#[test]
+fn test_depth_slices_are_exponential() {
+    let mut clusters = LightClusters::new(ClusterConfig {
+        slices: 4,
+        ..Default::default()
+    });
+    clusters.update_frustum(Mat4::perspective_rh(1.0, 1.0, 1.0, 10000.0), 1.0, 10000.0);
+    let depths: Vec<f32> = (0..=4).map(|slice| clusters.slice_depth(slice)).collect();
+    for (depth, expected) in depths.iter().zip([1.0, 10.0, 100.0, 1000.0, 10000.0]) {
+        assert!((depth - expected).abs() < expected * 1e-4, "{:?}", depths);
+    }
+    assert_eq!(clusters.slice_for_depth(0.5), 0);
+    assert_eq!(clusters.slice_for_depth(50.0), 1);
+    assert_eq!(clusters.slice_for_depth(5000.0), 3);
+    assert_eq!(clusters.cluster_at(Vec2::ZERO, 20000.0), None);
+
+    // The near slice of a centered tile straddles the view axis
+    let config = *clusters.config();
+    let cluster = clusters.cluster_index(config.tiles_x / 2, config.tiles_y / 2, 0);
+    let (min, max) = clusters.cluster_bounds(cluster);
+    assert!((max.z + 1.0).abs() < 1e-3 && (min.z + 10.0).abs() < 1e-2);
+}
+
+#[test]
+fn test_spot_cone_rejects_clusters_behind_it() {
+    let spot = SpotLight {
+        color: Color::WHITE,
+        intensity: 1.0,
+        range: 10.0,
+        inner_angle: 0.2,
+        outer_angle: 0.3,
+        cast_shadows: false,
+    };
+    // Pointing along -Z from the origin
+    let light = ClusterLight::from_spot(&spot, &Transform::default());
+    assert!(light.intersects_aabb(Vec3::new(-0.5, -0.5, -6.0), Vec3::new(0.5, 0.5, -5.0)));
+    assert!(!light.intersects_aabb(Vec3::new(-0.5, -0.5, 5.0), Vec3::new(0.5, 0.5, 6.0)));
+    assert!(!light.intersects_aabb(Vec3::new(4.0, -0.5, -3.0), Vec3::new(5.0, 0.5, -2.0)));
+    assert!(!light.intersects_aabb(Vec3::new(-0.5, -0.5, -12.0), Vec3::new(0.5, 0.5, -11.0)));
+}
+
+#[test]
+fn test_pbr_shader_validates() {
+    let composed = ShaderComposer::new()
+        .compose(
+            "pbr.wgsl",
+            include_str!("../shaders/pbr.wgsl"),
+            &ShaderDefs::new(),
+        )
+        .unwrap();
+    if let Err(error) = composed.validate() {
+        panic!("{}", error);
+    }
+}
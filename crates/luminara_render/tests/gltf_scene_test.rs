@@ -0,0 +1,313 @@
+use luminara_asset::{AssetLoader, AssetServer, Handle};
+use luminara_core::shared_types::World;
+use luminara_math::Transform;
+use luminara_render::{
+    register_gltf_components, spawn_gltf_scene, Camera, DirectionalLight, GltfAssetLabel,
+    GltfSceneLoader, GltfSkin, GltfSubAsset, GltfSubAssets, Mesh, PbrMaterial, Projection,
+    SkinnedMesh,
+};
+use luminara_scene::{Children, EntityData, Parent, Scene, TypeRegistry};
+use std::path::{Path, PathBuf};
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "extensionsUsed": [
+        "KHR_lights_punctual",
+        "KHR_materials_emissive_strength",
+        "KHR_texture_transform"
+    ],
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [
+        { "name": "Root", "translation": [0, 1, 0], "children": [1, 2, 4, 5] },
+        { "name": "Body", "mesh": 0, "skin": 0 },
+        { "name": "Hip", "translation": [0, 0.5, 0], "children": [3] },
+        { "name": "Knee" },
+        { "name": "Sun", "extensions": { "KHR_lights_punctual": { "light": 0 } } },
+        { "name": "View", "camera": 0 }
+    ],
+    "meshes": [{
+        "primitives": [
+            { "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "indices": 2, "material": 0 },
+            { "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "indices": 2 }
+        ]
+    }],
+    "skins": [{ "joints": [2, 3], "skeleton": 2 }],
+    "cameras": [{
+        "type": "perspective",
+        "perspective": { "yfov": 1.0, "znear": 0.1, "zfar": 50.0 }
+    }],
+    "extensions": {
+        "KHR_lights_punctual": {
+            "lights": [{ "type": "directional", "color": [1.0, 0.9, 0.8], "intensity": 3.0 }]
+        }
+    },
+    "materials": [{
+        "pbrMetallicRoughness": {
+            "baseColorFactor": [1.0, 0.5, 0.25, 1.0],
+            "metallicFactor": 0.2,
+            "roughnessFactor": 0.7,
+            "baseColorTexture": {
+                "index": 0,
+                "extensions": { "KHR_texture_transform": { "offset": [0.5, 0.25] } }
+            }
+        },
+        "emissiveFactor": [1.0, 0.5, 0.0],
+        "extensions": { "KHR_materials_emissive_strength": { "emissiveStrength": 4.0 } }
+    }],
+    "textures": [{ "sampler": 0, "source": 0 }],
+    "samplers": [{ "magFilter": 9728, "minFilter": 9728, "wrapS": 10497, "wrapT": 33648 }],
+    "images": [{ "uri": "albedo.png" }],
+    "animations": [{
+        "channels": [{ "sampler": 0, "target": { "node": 3, "path": "translation" } }],
+        "samplers": [{ "input": 3, "output": 4 }]
+    }],
+    "buffers": [{ "uri": "robot.bin", "byteLength": 100 }],
+    "bufferViews": [
+        { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+        { "buffer": 0, "byteOffset": 36, "byteLength": 24 },
+        { "buffer": 0, "byteOffset": 60, "byteLength": 6 },
+        { "buffer": 0, "byteOffset": 68, "byteLength": 8 },
+        { "buffer": 0, "byteOffset": 76, "byteLength": 24 }
+    ],
+    "accessors": [
+        { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+          "min": [0, 0, 0], "max": [1, 1, 0] },
+        { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2" },
+        { "bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR" },
+        { "bufferView": 3, "componentType": 5126, "count": 2, "type": "SCALAR",
+          "min": [0], "max": [1] },
+        { "bufferView": 4, "componentType": 5126, "count": 2, "type": "VEC3" }
+    ]
+}"#;
+
+/// Write the test model next to its buffer and image, returning its path
+fn write_model(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("luminara_gltf_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut bin: Vec<u8> = Vec::new();
+    let floats = |bin: &mut Vec<u8>, values: &[f32]| {
+        for value in values {
+            bin.extend_from_slice(&value.to_le_bytes());
+        }
+    };
+    floats(&mut bin, &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+    floats(&mut bin, &[0.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+    for index in [0u16, 1, 2, 0] {
+        bin.extend_from_slice(&index.to_le_bytes());
+    }
+    floats(&mut bin, &[0.0, 1.0]);
+    floats(&mut bin, &[0.0, 0.0, 0.0, 0.0, 2.0, 0.0]);
+    assert_eq!(bin.len(), 100);
+    std::fs::write(dir.join("robot.bin"), bin).unwrap();
+
+    image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]))
+        .save(dir.join("albedo.png"))
+        .unwrap();
+
+    let path = dir.join("robot.gltf");
+    std::fs::write(&path, GLTF).unwrap();
+    path
+}
+
+fn load(name: &str) -> (Scene, GltfSubAssets) {
+    let path = write_model(name);
+    let sub_assets = GltfSubAssets::default();
+    let loader = GltfSceneLoader::new(path.parent().unwrap(), sub_assets.clone());
+    let scene = loader.load(GLTF.as_bytes(), &path).unwrap();
+    (scene, sub_assets)
+}
+
+fn find<'a>(entities: &'a [EntityData], name: &str) -> Option<&'a EntityData> {
+    entities.iter().find_map(|entity| {
+        if entity.name == name {
+            Some(entity)
+        } else {
+            find(&entity.children, name)
+        }
+    })
+}
+
+fn component<T: luminara_core::Component + serde::de::DeserializeOwned>(
+    entity: &EntityData,
+) -> Option<T> {
+    let value = entity.components.get(T::type_name())?;
+    serde_json::from_value(value.clone()).ok()
+}
+
+#[test]
+fn test_nodes_become_scene_entities() {
+    let (scene, _) = load("nodes");
+
+    assert_eq!(scene.meta.name, "robot");
+    assert_eq!(scene.entities.len(), 1);
+    let root = &scene.entities[0];
+    assert_eq!(root.name, "Root");
+    assert_eq!(root.id, Some(0));
+    let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["Body", "Hip", "Sun", "View"]);
+
+    let hip = find(&scene.entities, "Hip").unwrap();
+    assert_eq!(hip.parent, Some(0));
+    let transform: Transform = component(hip).unwrap();
+    assert_eq!(transform.translation.y, 0.5);
+    assert_eq!(find(&scene.entities, "Knee").unwrap().parent, Some(2));
+}
+
+#[test]
+fn test_primitives_reference_labeled_sub_assets() {
+    let (scene, _) = load("primitives");
+    let body = find(&scene.entities, "Body").unwrap();
+
+    // Two primitives, so each gets its own child entity
+    assert_eq!(body.children.len(), 2);
+    let first = &body.children[0];
+    let second = &body.children[1];
+
+    let mesh: Handle<Mesh> = component(first).unwrap();
+    assert_eq!(
+        mesh,
+        GltfAssetLabel::Primitive {
+            mesh: 0,
+            primitive: 0
+        }
+        .handle("robot.gltf")
+    );
+    let material: Handle<PbrMaterial> = component(first).unwrap();
+    assert_eq!(material, GltfAssetLabel::Material(0).handle("robot.gltf"));
+    let default_material: Handle<PbrMaterial> = component(second).unwrap();
+    assert_eq!(
+        default_material,
+        GltfAssetLabel::DefaultMaterial.handle("robot.gltf")
+    );
+
+    let skin: GltfSkin = component(first).unwrap();
+    assert_eq!(skin.joints, vec![2, 3]);
+    assert_eq!(skin.skeleton, GltfAssetLabel::Skin(0).handle("robot.gltf"));
+}
+
+#[test]
+fn test_sub_assets_are_decoded() {
+    let (_, sub_assets) = load("sub_assets");
+    let assets = sub_assets.drain();
+    let get = |label: GltfAssetLabel| {
+        let id = label.id("robot.gltf");
+        assets
+            .iter()
+            .find(|(asset_id, _)| *asset_id == id)
+            .map(|(_, asset)| asset)
+    };
+
+    let Some(GltfSubAsset::Material(material)) = get(GltfAssetLabel::Material(0)) else {
+        panic!("Material0 missing");
+    };
+    assert_eq!(material.albedo.g, 0.5);
+    assert_eq!(material.metallic, 0.2);
+    assert_eq!(material.roughness, 0.7);
+    assert_eq!(
+        material.albedo_texture,
+        Some(GltfAssetLabel::Texture(0).handle("robot.gltf"))
+    );
+    // KHR_materials_emissive_strength scales the emissive factor
+    assert_eq!(material.emissive.r, 4.0);
+    assert_eq!(material.emissive.g, 2.0);
+
+    let Some(GltfSubAsset::Texture(texture)) = get(GltfAssetLabel::Texture(0)) else {
+        panic!("Texture0 missing");
+    };
+    assert_eq!((texture.data.width, texture.data.height), (2, 2));
+    assert_eq!(&texture.data.data[..4], &[255, 0, 0, 255]);
+    let sampler = texture.sampler_settings;
+    assert_eq!(sampler.address_mode_u, wgpu::AddressMode::Repeat);
+    assert_eq!(sampler.address_mode_v, wgpu::AddressMode::MirrorRepeat);
+    assert_eq!(sampler.mag_filter, wgpu::FilterMode::Nearest);
+
+    // KHR_texture_transform is baked into the UVs of the textured primitive
+    let Some(GltfSubAsset::Mesh(mesh)) = get(GltfAssetLabel::Primitive {
+        mesh: 0,
+        primitive: 0,
+    }) else {
+        panic!("Mesh0/Primitive0 missing");
+    };
+    assert!(mesh
+        .vertices
+        .iter()
+        .all(|v| v.uv[0] >= 0.5 && v.uv[1] >= 0.25));
+
+    assert!(matches!(
+        get(GltfAssetLabel::DefaultMaterial),
+        Some(GltfSubAsset::Material(_))
+    ));
+    let Some(GltfSubAsset::Skeleton(skeleton)) = get(GltfAssetLabel::Skin(0)) else {
+        panic!("Skin0 missing");
+    };
+    assert_eq!(skeleton.bones.len(), 2);
+    assert_eq!(skeleton.hierarchy, vec![None, Some(0)]);
+    let Some(GltfSubAsset::Animation(clip)) = get(GltfAssetLabel::Animation(0)) else {
+        panic!("Animation0 missing");
+    };
+    assert_eq!(clip.duration, 1.0);
+    assert_eq!(clip.channels[0].target_node_index, 3);
+}
+
+#[test]
+fn test_lights_and_cameras() {
+    let (scene, _) = load("lights");
+
+    let sun = find(&scene.entities, "Sun").unwrap();
+    let light: DirectionalLight = component(sun).unwrap();
+    assert_eq!(light.intensity, 3.0);
+    assert_eq!(light.color.g, 0.9);
+
+    let view = find(&scene.entities, "View").unwrap();
+    let camera: Camera = component(view).unwrap();
+    assert!(!camera.is_active);
+    match camera.projection {
+        Projection::Perspective { fov, near, far } => {
+            assert!((fov - 1.0f32.to_degrees()).abs() < 1e-4);
+            assert_eq!(near, 0.1);
+            assert_eq!(far, 50.0);
+        }
+        _ => panic!("expected a perspective camera"),
+    }
+}
+
+#[test]
+fn test_spawn_wires_skin_joints() {
+    let (scene, sub_assets) = load("spawn");
+
+    let mut world = World::new();
+    let mut registry = TypeRegistry::new();
+    register_gltf_components(&mut registry);
+    world.insert_resource(registry);
+    world.insert_resource(sub_assets);
+    world.insert_resource(AssetServer::new("assets"));
+
+    let nodes = spawn_gltf_scene(&mut world, &scene);
+    assert_eq!(nodes.len(), 6);
+    assert_eq!(
+        world.get_component::<Parent>(nodes[&2]).map(|p| p.0),
+        Some(nodes[&0])
+    );
+
+    let primitives = world.get_component::<Children>(nodes[&1]).unwrap().0.clone();
+    assert_eq!(primitives.len(), 2);
+    for primitive in primitives {
+        let skinned = world.get_component::<SkinnedMesh>(primitive).unwrap();
+        assert_eq!(skinned.joints, vec![nodes[&2], nodes[&3]]);
+        assert_eq!(skinned.inverse_bind_matrices.len(), 2);
+        assert!(skinned.skeleton.is_some());
+
+        // Sub-assets were stored before spawning
+        let mesh = world.get_component::<Handle<Mesh>>(primitive).unwrap();
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        assert!(asset_server.get(mesh).is_some());
+    }
+}
+
+#[test]
+fn test_invalid_gltf_fails() {
+    let loader = GltfSceneLoader::new("", GltfSubAssets::default());
+    assert!(loader.load(b"not gltf", Path::new("broken.glb")).is_err());
+}
@@ -0,0 +1,513 @@
+// Golden-image tests of the real render passes, drawn by a headless
+// `GpuContext` and read back with `capture_frame`.
+//
+// Tests are skipped when the machine has no adapter at all, not even a
+// software one. Software rasterizers differ slightly from GPUs, so frames are
+// compared with a small tolerance.
+//
+// ## Updating Reference Images
+//
+// 1. Review the visual changes to ensure they are correct
+// 2. Run: `UPDATE_GOLDEN_IMAGES=1 cargo test --test headless_capture_test`
+// 3. Commit the updated reference images in `tests/golden/headless/`
+
+use image::RgbaImage;
+use luminara_asset::{AssetId, AssetServer, Handle};
+use luminara_core::shared_types::{Res, ResMut, World};
+use luminara_core::system::SystemParam;
+use luminara_math::{Color, Mat4, Quat, Transform, Vec2, Vec3};
+use luminara_render::forward_plus::ForwardPlusRenderer;
+use luminara_render::plugin::setup_gpu_context;
+use luminara_render::render_graph::RenderGraph;
+use luminara_render::{
+    layout_text, render_sprites, Camera, CameraUniformBuffer, Font, FontFace, GlyphAtlas,
+    GlyphRasterMode, GpuContext, HeadlessOptions, JointPalette, Mesh, OutlineCurve, OverlayNode,
+    OverlayRenderer, PbrMaterial, PipelineCache, PostProcessResources, RenderTargets,
+    ShaderComposer, SkinWeights, SkinnedMesh, SkinningMethod, Sprite, SpriteBatcher,
+    SpriteRenderResources, Text3dMesh, Text3dRenderer, TextLayoutOptions, TextSpan, TextStyle,
+    Texture,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const SIZE: u32 = 128;
+/// Mean per-channel difference allowed between adapters
+const THRESHOLD: f32 = 0.02;
+
+fn headless_gpu() -> Option<GpuContext> {
+    match GpuContext::new_headless(HeadlessOptions {
+        width: SIZE,
+        height: SIZE,
+        ..Default::default()
+    }) {
+        Ok(gpu) => Some(gpu),
+        Err(e) => {
+            eprintln!("Skipping headless render test: {}", e);
+            None
+        }
+    }
+}
+
+/// A world with the resources `RenderPlugin` would insert, set up around a
+/// headless context
+fn render_world(gpu: GpuContext, graph: RenderGraph) -> World {
+    let mut world = World::new();
+    world.insert_resource(AssetServer::new("assets"));
+    world.insert_resource(PipelineCache::new());
+    world.insert_resource(graph);
+    world.insert_resource(RenderTargets::default());
+    world.insert_resource(ForwardPlusRenderer::new());
+    world.insert_resource(OverlayRenderer::new());
+    world.insert_resource(Text3dRenderer::default());
+    world.insert_resource(ShaderComposer::new());
+    world.insert_resource(luminara_render::DebugRenderingResource::new());
+    world.insert_resource(luminara_render::MaterialTextures::default());
+    world.insert_resource(luminara_render::EnvironmentResources::default());
+    let mut materials = luminara_render::MaterialRegistry::default();
+    materials.register_component::<PbrMaterial>();
+    world.insert_resource(materials);
+
+    let mut post_process = PostProcessResources::default();
+    post_process.initialize(&gpu.device, gpu.surface_config.format);
+    world.insert_resource(post_process);
+
+    world.insert_resource(gpu);
+    setup_gpu_context(&mut world);
+    world
+}
+
+fn capture(world: &World) -> RgbaImage {
+    let gpu = world.get_resource::<GpuContext>().unwrap();
+    gpu.capture_frame().expect("capture failed")
+}
+
+/// Mean absolute difference of all channels, in `0..=1`
+fn mean_difference(a: &RgbaImage, b: &RgbaImage) -> f32 {
+    let total: u64 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .map(|(x, y)| x.abs_diff(*y) as u64)
+        .sum();
+    total as f32 / (a.as_raw().len() as f32 * 255.0)
+}
+
+fn assert_golden(name: &str, frame: &RgbaImage) {
+    let golden_dir = PathBuf::from("tests/golden/headless");
+    let golden_path = golden_dir.join(format!("{}.png", name));
+
+    if std::env::var("UPDATE_GOLDEN_IMAGES").is_ok() {
+        std::fs::create_dir_all(&golden_dir).unwrap();
+        frame
+            .save(&golden_path)
+            .expect("Failed to save golden image");
+        println!("Updated golden image: {}", golden_path.display());
+        return;
+    }
+    assert!(
+        golden_path.exists(),
+        "Missing golden image {}; run with UPDATE_GOLDEN_IMAGES=1 to create it",
+        golden_path.display()
+    );
+
+    let golden = image::open(&golden_path)
+        .expect("Failed to load golden image")
+        .to_rgba8();
+    assert_eq!(golden.dimensions(), frame.dimensions());
+    let diff = mean_difference(frame, &golden);
+    if diff > THRESHOLD {
+        let diff_dir = PathBuf::from("tests/diffs");
+        std::fs::create_dir_all(&diff_dir).ok();
+        let actual_path = diff_dir.join(format!("{}_actual.png", name));
+        frame.save(&actual_path).ok();
+        panic!(
+            "Visual regression detected in '{}'!\n\
+             Difference: {:.4} (threshold: {:.4})\n\
+             Actual frame saved to: {}\n\
+             To update golden image, run: UPDATE_GOLDEN_IMAGES=1 cargo test",
+            name,
+            diff,
+            THRESHOLD,
+            actual_path.display()
+        );
+    }
+}
+
+#[test]
+fn test_headless_context() {
+    let Some(mut gpu) = headless_gpu() else {
+        return;
+    };
+    assert!(gpu.is_headless());
+    assert!(gpu.surface.is_none());
+
+    let frame = gpu.capture_frame().unwrap();
+    assert_eq!(frame.dimensions(), (SIZE, SIZE));
+
+    gpu.resize(64, 32);
+    let (frame, _view) = gpu.begin_frame().unwrap();
+    assert_eq!(frame.texture().width(), 64);
+    assert_eq!(frame.texture().height(), 32);
+    gpu.end_frame(frame);
+    assert_eq!(gpu.capture_frame().unwrap().dimensions(), (64, 32));
+}
+
+#[test]
+fn test_capture_clear_color() {
+    let Some(gpu) = headless_gpu() else {
+        return;
+    };
+    let (frame, view) = gpu.begin_frame().unwrap();
+    let mut encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::RED),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+    gpu.queue.submit(std::iter::once(encoder.finish()));
+    gpu.end_frame(frame);
+
+    let image = gpu.capture_frame().unwrap();
+    assert!(image.pixels().all(|pixel| pixel.0 == [255, 0, 0, 255]));
+}
+
+#[test]
+fn test_pbr_pass_golden() {
+    let Some(gpu) = headless_gpu() else {
+        return;
+    };
+    let mut world = render_world(gpu, RenderGraph::forward_3d());
+
+    let mesh = Mesh::sphere(1.0, 32);
+    mesh.upload(&world.get_resource::<GpuContext>().unwrap().device);
+    let mesh = world.get_resource::<AssetServer>().unwrap().add(mesh);
+
+    let camera = world.spawn();
+    world.add_component(camera, Camera::default()).unwrap();
+    world
+        .add_component(camera, Transform::from_xyz(0.0, 0.0, 3.0))
+        .unwrap();
+
+    for (x, metallic, roughness) in [(-1.2, 0.0, 0.3), (1.2, 1.0, 0.6)] {
+        let sphere = world.spawn();
+        world.add_component(sphere, mesh.clone()).unwrap();
+        world
+            .add_component(sphere, Transform::from_xyz(x, 0.0, 0.0))
+            .unwrap();
+        world
+            .add_component(
+                sphere,
+                PbrMaterial {
+                    albedo: Color::rgb(0.8, 0.3, 0.2),
+                    albedo_texture: None,
+                    normal_texture: None,
+                    metallic,
+                    roughness,
+                    metallic_roughness_texture: None,
+                    emissive: Color::BLACK,
+                },
+            )
+            .unwrap();
+    }
+
+    luminara_render::render_system(&mut world);
+    let frame = capture(&world);
+    // The spheres cover the center of the frame
+    let center = frame.get_pixel(SIZE / 2 - SIZE / 4, SIZE / 2).0;
+    let corner = frame.get_pixel(0, 0).0;
+    assert_ne!(center, corner);
+    assert_golden("pbr_pass", &frame);
+}
+
+#[test]
+fn test_skinned_material_pipeline() {
+    let Some(gpu) = headless_gpu() else {
+        return;
+    };
+    let mut world = render_world(gpu, RenderGraph::forward_3d());
+
+    // Bound at the origin, drawn where its only joint moved it
+    let mut mesh = Mesh::sphere(0.8, 16);
+    mesh.skin_weights = vec![
+        SkinWeights {
+            joints: [0; 4],
+            weights: [1.0, 0.0, 0.0, 0.0],
+        };
+        mesh.vertices.len()
+    ];
+    mesh.upload(&world.get_resource::<GpuContext>().unwrap().device);
+    let mesh = world.get_resource::<AssetServer>().unwrap().add(mesh);
+
+    let camera = world.spawn();
+    world.add_component(camera, Camera::default()).unwrap();
+    world
+        .add_component(camera, Transform::from_xyz(0.0, 0.0, 3.0))
+        .unwrap();
+
+    let skinned = world.spawn();
+    world.add_component(skinned, mesh.clone()).unwrap();
+    world.add_component(skinned, Transform::IDENTITY).unwrap();
+    world
+        .add_component(
+            skinned,
+            PbrMaterial {
+                albedo: Color::rgb(0.8, 0.3, 0.2),
+                albedo_texture: None,
+                normal_texture: None,
+                metallic: 0.0,
+                roughness: 0.5,
+                metallic_roughness_texture: None,
+                emissive: Color::BLACK,
+            },
+        )
+        .unwrap();
+    world
+        .add_component(
+            skinned,
+            SkinnedMesh {
+                mesh,
+                joints: Vec::new(),
+                inverse_bind_matrices: Vec::new(),
+                skeleton: None,
+                method: SkinningMethod::Linear,
+            },
+        )
+        .unwrap();
+    world
+        .add_component(
+            skinned,
+            JointPalette::compute(
+                Mat4::IDENTITY,
+                &[Mat4::from_translation(Vec3::new(-1.2, 0.0, 0.0))],
+                &[],
+            ),
+        )
+        .unwrap();
+
+    luminara_render::render_system(&mut world);
+
+    let draws = world
+        .get_resource::<luminara_render::MaterialRegistry>()
+        .unwrap()
+        .collect(&world, &world.get_resource::<AssetServer>().unwrap());
+    assert_eq!(draws.len(), 1);
+    assert!(draws[0].shader_defs.contains("SKINNED"));
+    let id = draws[0].pipeline_id();
+    let cache = world.get_resource::<PipelineCache>().unwrap();
+    assert!(!cache.is_failed(&id));
+    assert!(cache.get_pipeline(&id).is_some());
+    drop(cache);
+
+    let frame = capture(&world);
+    let corner = frame.get_pixel(0, 0).0;
+    assert_ne!(frame.get_pixel(SIZE / 2 - SIZE / 4, SIZE / 2).0, corner);
+    assert_eq!(frame.get_pixel(SIZE / 2, SIZE / 2).0, corner);
+}
+
+#[test]
+fn test_sprite_pass_golden() {
+    let Some(gpu) = headless_gpu() else {
+        return;
+    };
+    let mut world = render_world(gpu, RenderGraph::new());
+    world.insert_resource(SpriteRenderResources::default());
+
+    let texture = {
+        let gpu = world.get_resource::<GpuContext>().unwrap();
+        let mut texture = Texture::checkerboard(16, [255, 255, 255, 255], [40, 40, 200, 255]);
+        texture.upload(&gpu.device, &gpu.queue);
+
+        // Pixel-space orthographic camera with the origin in the center
+        let half = SIZE as f32 / 2.0;
+        let view_proj = Mat4::orthographic_rh(-half, half, -half, half, -1.0, 1.0);
+        let camera = world.get_resource::<CameraUniformBuffer>().unwrap();
+        gpu.queue.write_buffer(
+            &camera.buffer,
+            0,
+            bytemuck::cast_slice(&view_proj.to_cols_array()),
+        );
+        texture
+    };
+    let texture = world.get_resource::<AssetServer>().unwrap().add(texture);
+
+    luminara_render::init_sprite_system(
+        ResMut::<SpriteRenderResources>::get_param(&world),
+        Res::<GpuContext>::get_param(&world),
+        Res::<CameraUniformBuffer>::get_param(&world),
+    );
+
+    let sprites = [
+        (
+            Sprite::new(texture.clone()),
+            Transform {
+                translation: Vec3::new(-24.0, 0.0, 0.0),
+                rotation: Quat::IDENTITY,
+                scale: Vec3::splat(48.0),
+            },
+        ),
+        (
+            Sprite::new(texture).with_color(Color::rgb(1.0, 0.5, 0.0)),
+            Transform {
+                translation: Vec3::new(32.0, 24.0, 0.0),
+                rotation: Quat::from_rotation_z(0.5),
+                scale: Vec3::splat(32.0),
+            },
+        ),
+    ];
+    let matrices: Vec<Mat4> = sprites.iter().map(|(_, t)| t.compute_matrix()).collect();
+    let mut batcher = SpriteBatcher::new(64);
+    batcher.prepare(
+        sprites
+            .iter()
+            .zip(&matrices)
+            .map(|((sprite, _), matrix)| (sprite, matrix, None)),
+    );
+    assert_eq!(batcher.batches.len(), 1);
+    world.insert_resource(batcher);
+
+    render_sprites(
+        Res::<GpuContext>::get_param(&world),
+        Res::<SpriteRenderResources>::get_param(&world),
+        Res::<SpriteBatcher>::get_param(&world),
+        Res::<CameraUniformBuffer>::get_param(&world),
+        Res::<AssetServer>::get_param(&world),
+    );
+    let frame = capture(&world);
+    assert_ne!(frame.get_pixel(SIZE / 2 - 24, SIZE / 2).0, [0, 0, 0, 0]);
+    assert_golden("sprite_pass", &frame);
+}
+
+#[test]
+fn test_overlay_pass_golden() {
+    let Some(gpu) = headless_gpu() else {
+        return;
+    };
+    let mut graph = RenderGraph::new();
+    graph.add_node(OverlayNode);
+    let mut world = render_world(gpu, graph);
+
+    {
+        let mut overlay = world.get_resource_mut::<OverlayRenderer>().unwrap();
+        overlay.draw_rect(8.0, 8.0, 112.0, 40.0, [0.1, 0.1, 0.1, 0.8]);
+        overlay.draw_text(12.0, 12.0, "FPS 60", [0.2, 1.0, 0.2, 1.0], 2.0);
+        overlay.draw_rect(8.0, 96.0, 64.0, 8.0, [1.0, 0.3, 0.1, 1.0]);
+    }
+
+    luminara_render::render_system(&mut world);
+    let frame = capture(&world);
+    assert_eq!(frame.get_pixel(40, 100).0[3], 255);
+    assert_golden("overlay_pass", &frame);
+}
+
+/// Box glyphs 300 units wide and 700 tall on a 500 unit advance
+struct BoxFont;
+
+impl FontFace for BoxFont {
+    fn units_per_em(&self) -> f32 {
+        1000.0
+    }
+
+    fn ascent(&self) -> f32 {
+        800.0
+    }
+
+    fn descent(&self) -> f32 {
+        200.0
+    }
+
+    fn line_gap(&self) -> f32 {
+        0.0
+    }
+
+    fn glyph_id(&self, c: char) -> Option<u16> {
+        Some(c as u16)
+    }
+
+    fn advance(&self, _glyph: u16) -> f32 {
+        500.0
+    }
+
+    fn kerning(&self, _left: u16, _right: u16) -> f32 {
+        0.0
+    }
+
+    fn outline(&self, _glyph: u16) -> Vec<OutlineCurve> {
+        let corners = [
+            Vec2::new(100.0, 0.0),
+            Vec2::new(400.0, 0.0),
+            Vec2::new(400.0, 700.0),
+            Vec2::new(100.0, 700.0),
+        ];
+        (0..4)
+            .map(|i| OutlineCurve::Line(corners[i], corners[(i + 1) % 4]))
+            .collect()
+    }
+}
+
+#[test]
+fn test_text3d_pass_golden() {
+    let Some(gpu) = headless_gpu() else {
+        return;
+    };
+    let mut world = render_world(gpu, RenderGraph::forward_3d());
+    world.insert_resource(GlyphAtlas::new(GlyphRasterMode::Sdf));
+
+    let camera = world.spawn();
+    world.add_component(camera, Camera::default()).unwrap();
+    world
+        .add_component(camera, Transform::from_xyz(0.0, 0.0, 3.0))
+        .unwrap();
+
+    // Two glyphs either side of the center, one unit tall
+    let fonts = |_: &Handle<Font>| Some(Arc::new(BoxFont) as Arc<dyn FontFace>);
+    let mut style = TextStyle::new(Handle::new(AssetId::from_path("box.ttf"), 0), 64.0);
+    style.color = Color::rgb(1.0, 0.8, 0.2);
+    let layout = layout_text(
+        &[TextSpan::new("AB", style)],
+        &TextLayoutOptions::default(),
+        &fonts,
+    );
+    let mesh = {
+        let mut atlas = world.get_resource_mut::<GlyphAtlas>().unwrap();
+        Text3dMesh::new(&layout, &mut atlas, 64.0, Vec2::new(0.5, 0.5)).unwrap()
+    };
+    let text = world.spawn();
+    world.add_component(text, mesh).unwrap();
+    world.add_component(text, Transform::IDENTITY).unwrap();
+
+    luminara_render::render_system(&mut world);
+    let frame = capture(&world);
+    // Both glyphs are drawn, with the gap between them left clear
+    let corner = frame.get_pixel(0, 0).0;
+    let covered = |x_range: std::ops::Range<u32>| {
+        x_range
+            .flat_map(|x| (0..SIZE).map(move |y| (x, y)))
+            .filter(|&(x, y)| frame.get_pixel(x, y).0 != corner)
+            .count()
+    };
+    assert!(covered(0..SIZE / 2 - 2) > 0);
+    assert!(covered(SIZE / 2 + 2..SIZE) > 0);
+    assert_eq!(covered(SIZE / 2 - 1..SIZE / 2 + 1), 0);
+    assert_golden("text3d_pass", &frame);
+}
+
+#[test]
+fn test_capture_world_size() {
+    let Some(gpu) = headless_gpu() else {
+        return;
+    };
+    let world = render_world(gpu, RenderGraph::forward_3d());
+    let image = luminara_render::capture_world(&world, 40, 24).unwrap();
+    assert_eq!(image.dimensions(), (40, 24));
+}
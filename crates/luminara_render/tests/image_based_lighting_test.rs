@@ -0,0 +1,336 @@
+use luminara_asset::{AssetId, AssetLoader, Handle};
+use luminara_math::{Quat, Transform, Vec3};
+use luminara_render::{
+    prefilter_specular, BrdfLut, Cubemap, EnvironmentMap, EnvironmentMapError,
+    EnvironmentMapLoader, EnvironmentMapSettings, ProbeBlend, ReflectionProbe, ShaderComposer,
+    ShaderDefs, SphericalHarmonics, TextureData, TextureFormat,
+};
+use std::f32::consts::PI;
+use std::path::Path;
+
+fn assert_close(a: Vec3, b: Vec3, tolerance: f32) {
+    assert!(a.abs_diff_eq(b, tolerance), "{:?} != {:?}", a, b);
+}
+
+fn probe(half_extents: Vec3, blend_distance: f32) -> ReflectionProbe {
+    ReflectionProbe {
+        blend_distance,
+        ..ReflectionProbe::new(Handle::new(AssetId::new(), 0), half_extents)
+    }
+}
+
+/// A 2:1 Rgba32F image whose pixels are `color(u, v)` at their centers
+fn equirectangular(width: u32, color: impl Fn(f32, f32) -> Vec3) -> TextureData {
+    let height = width / 2;
+    let mut data = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let u = (x as f32 + 0.5) / width as f32;
+            let v = (y as f32 + 0.5) / height as f32;
+            let c = color(u, v);
+            for value in [c.x, c.y, c.z, 1.0] {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    TextureData {
+        width,
+        height,
+        data,
+        format: TextureFormat::Rgba32F,
+    }
+}
+
+#[test]
+fn test_cubemap_texel_solid_angles() {
+    for size in [1, 4, 16] {
+        let mut total = 0.0;
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    total += Cubemap::texel_solid_angle(size, x, y);
+                    let direction = Cubemap::texel_direction(size, face, x, y);
+                    assert!((direction.length() - 1.0).abs() < 1e-5);
+                }
+            }
+        }
+        assert!(
+            (total - 4.0 * PI).abs() < 1e-3,
+            "{} at size {}",
+            total,
+            size
+        );
+    }
+}
+
+#[test]
+fn test_cubemap_sampling() {
+    let cubemap = Cubemap::from_fn(8, |direction| direction);
+    for direction in [
+        Vec3::X,
+        -Vec3::Y,
+        Vec3::Z,
+        Vec3::new(0.3, -0.5, 0.8).normalize(),
+    ] {
+        assert_close(cubemap.sample(direction), direction, 0.15);
+    }
+    // Every face faces its own axis
+    assert!(cubemap.sample(Vec3::X).x > 0.9);
+    assert!(cubemap.sample(-Vec3::X).x < -0.9);
+    assert!(cubemap.sample(Vec3::Y).y > 0.9);
+    assert!(cubemap.sample(-Vec3::Z).z < -0.9);
+
+    let chain = cubemap.mip_chain();
+    assert_eq!(
+        chain.iter().map(|level| level.size).collect::<Vec<_>>(),
+        vec![8, 4, 2, 1]
+    );
+}
+
+#[test]
+fn test_cubemap_from_equirectangular() {
+    // Bright sky above the horizon, dark ground below it, and a red band
+    // around the image center
+    let image = equirectangular(64, |u, v| {
+        let red = if (u - 0.5).abs() < 0.1 { 1.0 } else { 0.0 };
+        Vec3::new(red, if v < 0.5 { 1.0 } else { 0.0 }, 0.0)
+    });
+    let cubemap = Cubemap::from_equirectangular(&image, 16).unwrap();
+    assert!(cubemap.sample(Vec3::Y).y > 0.9);
+    assert!(cubemap.sample(-Vec3::Y).y < 0.1);
+    // The image center looks down -Z
+    assert!(cubemap.sample(-Vec3::Z + Vec3::Y * 0.2).x > 0.9);
+    assert!(cubemap.sample(Vec3::Z + Vec3::Y * 0.2).x < 0.1);
+    assert!(cubemap.sample(Vec3::X + Vec3::Y * 0.2).x < 0.1);
+
+    let truncated = TextureData {
+        data: vec![0; 16],
+        ..image.clone()
+    };
+    assert!(matches!(
+        Cubemap::from_equirectangular(&truncated, 16),
+        Err(EnvironmentMapError::DataSize { .. })
+    ));
+    let single_channel = TextureData {
+        format: TextureFormat::R8,
+        ..image
+    };
+    assert_eq!(
+        Cubemap::from_equirectangular(&single_channel, 16),
+        Err(EnvironmentMapError::UnsupportedFormat(TextureFormat::R8))
+    );
+}
+
+#[test]
+fn test_sh_projection_of_constant_environment() {
+    let radiance = Vec3::new(0.5, 1.0, 2.0);
+    let sh = SphericalHarmonics::project(&Cubemap::from_fn(8, |_| radiance));
+    for direction in [
+        Vec3::X,
+        Vec3::Y,
+        -Vec3::Z,
+        Vec3::new(1.0, -2.0, 0.5).normalize(),
+    ] {
+        assert_close(sh.evaluate(direction), radiance, 1e-3);
+        // A uniform environment lights a white Lambertian surface with its
+        // own radiance
+        assert_close(sh.irradiance(direction), radiance, 1e-3);
+    }
+    assert_close(
+        SphericalHarmonics::constant(radiance).irradiance(Vec3::Y),
+        radiance,
+        1e-5,
+    );
+}
+
+#[test]
+fn test_sh_irradiance_of_directional_environment() {
+    // Light only from the upper hemisphere: a surface facing up receives
+    // all of it, one facing down none, and one on the side half
+    let sh = SphericalHarmonics::project(&Cubemap::from_fn(16, |direction| {
+        if direction.y > 0.0 {
+            Vec3::ONE
+        } else {
+            Vec3::ZERO
+        }
+    }));
+    assert!((sh.irradiance(Vec3::Y).x - 1.0).abs() < 0.1);
+    assert!(sh.irradiance(-Vec3::Y).x.abs() < 0.1);
+    assert!((sh.irradiance(Vec3::X).x - 0.5).abs() < 0.05);
+
+    // Clamped cosine radiance around +Z: irradiance / π at +Z is 2/3
+    let sh = SphericalHarmonics::project(&Cubemap::from_fn(16, |direction| {
+        Vec3::splat(direction.z.max(0.0))
+    }));
+    assert!((sh.irradiance(Vec3::Z).x - 2.0 / 3.0).abs() < 0.05);
+
+    let blended = SphericalHarmonics::constant(Vec3::ONE).lerp(&SphericalHarmonics::ZERO, 0.25);
+    assert_close(blended.irradiance(Vec3::X), Vec3::splat(0.75), 1e-5);
+}
+
+#[test]
+fn test_brdf_lut() {
+    let lut = BrdfLut::new(16, 128);
+    assert_eq!(lut.data.len(), 16 * 16);
+    for texel in &lut.data {
+        assert!(texel.min_element() >= 0.0);
+        assert!(texel.x + texel.y <= 1.0 + 1e-3, "{:?}", texel);
+    }
+    // A smooth surface seen head-on reflects close to `f0`
+    let smooth = BrdfLut::integrate(1.0, 0.05, 256);
+    assert!((smooth.x - 1.0).abs() < 0.05, "{:?}", smooth);
+    assert!(smooth.y < 0.05);
+    // Grazing angles add Fresnel reflection
+    assert!(BrdfLut::integrate(0.1, 0.2, 256).y > smooth.y);
+    // Rough surfaces lose energy to shadowing and masking
+    let white = |roughness| {
+        let sample = lut.sample(0.5, roughness);
+        sample.x + sample.y
+    };
+    assert!(white(0.1) > white(0.5));
+    assert!(white(0.5) > white(0.95));
+    assert!((lut.sample(0.5, 0.5) - BrdfLut::integrate(0.5, 0.5, 128)).length() < 0.05);
+}
+
+#[test]
+fn test_prefiltered_specular_levels() {
+    let radiance = Vec3::new(0.2, 0.4, 0.6);
+    let source = Cubemap::from_fn(32, |_| radiance);
+    let levels = prefilter_specular(&source, 16, 4, 32);
+    assert_eq!(
+        levels.iter().map(|level| level.size).collect::<Vec<_>>(),
+        vec![16, 8, 4, 2]
+    );
+    // Filtering a uniform environment keeps it uniform
+    for level in &levels {
+        for texel in &level.data {
+            assert_close(*texel, radiance, 1e-3);
+        }
+    }
+    // Levels are limited by the size
+    assert_eq!(prefilter_specular(&source, 4, 8, 8).len(), 3);
+
+    // Rough levels blur a bright spot into its surroundings
+    let spot = Cubemap::from_fn(32, |direction| {
+        Vec3::splat(if direction.y > 0.95 { 10.0 } else { 0.0 })
+    });
+    let levels = prefilter_specular(&spot, 16, 5, 64);
+    let near_spot = Vec3::new(0.6, 0.8, 0.0).normalize();
+    assert!(levels[0].sample(near_spot).x < 1e-3);
+    assert!(levels[4].sample(near_spot).x > levels[1].sample(near_spot).x);
+}
+
+#[test]
+fn test_environment_map_from_image() {
+    let settings = EnvironmentMapSettings {
+        skybox_size: 16,
+        specular_size: 8,
+        specular_levels: 3,
+        samples: 8,
+    };
+    let environment =
+        EnvironmentMap::from_equirectangular(&equirectangular(32, |_, _| Vec3::ONE), &settings)
+            .unwrap();
+    assert_eq!(environment.skybox.size, 16);
+    assert_eq!(environment.specular.len(), 3);
+    assert_eq!(environment.specular[0].size, 8);
+    assert_close(environment.irradiance.irradiance(Vec3::X), Vec3::ONE, 1e-2);
+
+    let constant = EnvironmentMap::constant(Vec3::splat(0.5));
+    assert_close(
+        constant.irradiance.irradiance(-Vec3::Y),
+        Vec3::splat(0.5),
+        1e-5,
+    );
+    assert_close(constant.specular[0].sample(Vec3::Z), Vec3::splat(0.5), 1e-5);
+}
+
+#[test]
+fn test_environment_map_loader() {
+    let loader = EnvironmentMapLoader::default();
+    assert_eq!(loader.extensions(), &["hdr"]);
+    assert!(loader.load(b"not an image", Path::new("sky.hdr")).is_err());
+}
+
+#[test]
+fn test_reflection_probe_weight() {
+    let probe = probe(Vec3::new(4.0, 2.0, 4.0), 1.0);
+    let transform = Transform::from_translation(Vec3::new(10.0, 0.0, 0.0));
+    assert_eq!(probe.weight(&transform, Vec3::new(10.0, 0.0, 0.0)), 1.0);
+    // Fading out over the blend distance inside the box
+    assert!((probe.weight(&transform, Vec3::new(13.5, 0.0, 0.0)) - 0.5).abs() < 1e-5);
+    assert!((probe.weight(&transform, Vec3::new(10.0, 1.75, 0.0)) - 0.25).abs() < 1e-5);
+    assert_eq!(probe.weight(&transform, Vec3::new(14.5, 0.0, 0.0)), 0.0);
+    assert_eq!(probe.weight(&transform, Vec3::ZERO), 0.0);
+    assert_eq!(probe.volume(), 8.0 * 4.0 * 8.0);
+
+    // The box turns with the transform
+    let rotated = Transform {
+        rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+        ..transform
+    };
+    assert_eq!(probe.weight(&rotated, Vec3::new(13.5, 0.0, 0.0)), 0.0);
+    assert_eq!(probe.weight(&rotated, Vec3::new(10.0, 2.5, 0.0)), 1.0);
+
+    // Without a blend distance the box has hard edges
+    let hard = ReflectionProbe {
+        blend_distance: 0.0,
+        ..probe
+    };
+    assert_eq!(hard.weight(&transform, Vec3::new(13.99, 0.0, 0.0)), 1.0);
+}
+
+#[test]
+fn test_probe_blending() {
+    let room = (probe(Vec3::splat(2.0), 1.0), Transform::IDENTITY);
+    let hall = (probe(Vec3::splat(10.0), 2.0), Transform::IDENTITY);
+    let closet = (probe(Vec3::splat(0.5), 0.0), Transform::IDENTITY);
+    let far = (
+        probe(Vec3::splat(1.0), 0.0),
+        Transform::from_translation(Vec3::splat(50.0)),
+    );
+    let probes = vec![hall, room, far.clone(), closet];
+
+    // Smaller probes are innermost, at most two blend
+    let blend = ProbeBlend::new(Vec3::ZERO, &probes);
+    assert_eq!(blend.layers, vec![(3, 1.0), (1, 1.0)]);
+    let (shares, camera) = blend.contributions();
+    assert_eq!(shares, vec![1.0, 0.0]);
+    assert_eq!(camera, 0.0);
+
+    // Between the room's blend zone and the hall
+    let blend = ProbeBlend::new(Vec3::new(1.5, 0.0, 0.0), &probes);
+    assert_eq!(blend.layers, vec![(1, 0.5), (0, 1.0)]);
+    let (shares, camera) = blend.contributions();
+    assert_eq!(shares, vec![0.5, 0.5]);
+    assert_eq!(camera, 0.0);
+
+    // At the edge of the hall, the camera's environment shows through
+    let blend = ProbeBlend::new(Vec3::new(9.0, 0.0, 0.0), &probes);
+    assert_eq!(blend.layers, vec![(0, 0.5)]);
+    let (shares, camera) = blend.contributions();
+    assert!((shares.iter().sum::<f32>() + camera - 1.0).abs() < 1e-6);
+    assert_eq!(camera, 0.5);
+
+    let outside = ProbeBlend::new(Vec3::splat(20.0), &probes);
+    assert!(outside.layers.is_empty());
+    assert_eq!(outside.contributions(), (vec![], 1.0));
+    assert_eq!(
+        ProbeBlend::new(Vec3::splat(50.0), &[far]).layers,
+        vec![(0, 1.0)]
+    );
+}
+
+#[test]
+fn test_skybox_shader_is_valid() {
+    let composed = ShaderComposer::new()
+        .compose(
+            "skybox.wgsl",
+            include_str!("../shaders/skybox.wgsl"),
+            &ShaderDefs::new(),
+        )
+        .unwrap();
+    if let Err(error) = composed.validate() {
+        panic!("{}", error);
+    }
+}
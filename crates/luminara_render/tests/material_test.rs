@@ -0,0 +1,314 @@
+use luminara_asset::{Asset, AssetId, AssetLoader, AssetServer, Handle};
+use luminara_core::shared_types::World;
+use luminara_math::{Color, Transform, Vec3};
+use luminara_reflect_derive::Reflect;
+use luminara_render::{
+    material_prelude, pbr_shader, shader_handle, BlendMode, CullMode, DrawCallSortKey, Material,
+    MaterialKey, MaterialLoader, MaterialPipelineKey, MaterialRegistry, MaterialTextureQueue, Mesh,
+    PbrMaterial, Shader, ShaderComposer, Texture, TextureBinding, UniformLayout, UniformType,
+    UnlitMaterial,
+};
+use std::path::Path;
+
+/// Project-defined material with its own shader
+#[derive(Debug, Clone, Reflect)]
+struct ToonMaterial {
+    steps: u32,
+    outline_color: Vec3,
+    outline_width: f32,
+    tint: Color,
+    lit: bool,
+    ramp: Option<Handle<Texture>>,
+}
+
+impl Asset for ToonMaterial {
+    fn type_name() -> &'static str {
+        "ToonMaterial"
+    }
+}
+
+impl Material for ToonMaterial {
+    fn shader(&self) -> Handle<Shader> {
+        shader_handle("shaders/toon.wgsl")
+    }
+
+    fn pipeline_key(&self) -> MaterialPipelineKey {
+        MaterialPipelineKey {
+            cull_mode: CullMode::Front,
+            alpha_mask: Some(128),
+            ..Default::default()
+        }
+    }
+}
+
+fn toon() -> ToonMaterial {
+    ToonMaterial {
+        steps: 3,
+        outline_color: Vec3::new(0.1, 0.2, 0.3),
+        outline_width: 2.0,
+        tint: Color::rgba(1.0, 0.5, 0.25, 1.0),
+        lit: true,
+        ramp: None,
+    }
+}
+
+fn pbr(albedo: Color) -> PbrMaterial {
+    PbrMaterial {
+        albedo,
+        albedo_texture: None,
+        normal_texture: None,
+        metallic: 0.8,
+        roughness: 0.3,
+        metallic_roughness_texture: None,
+        emissive: Color::rgba(0.5, 0.0, 0.0, 1.0),
+    }
+}
+
+fn offsets(layout: &UniformLayout) -> Vec<(&str, UniformType, u32)> {
+    layout
+        .fields
+        .iter()
+        .map(|field| (field.name.as_str(), field.ty, field.offset))
+        .collect()
+}
+
+#[test]
+fn test_uniform_layout_follows_wgsl_alignment() {
+    let layout = toon().uniform_layout();
+    assert_eq!(
+        offsets(&layout),
+        vec![
+            ("steps", UniformType::U32, 0),
+            // vec3 aligns to 16, the following f32 packs into its last lane
+            ("outline_color", UniformType::Vec3, 16),
+            ("outline_width", UniformType::F32, 28),
+            ("tint", UniformType::Vec4, 32),
+            ("lit", UniformType::Bool, 48),
+        ]
+    );
+    assert_eq!(layout.size, 64);
+
+    // Texture slots are not part of the uniform block
+    let layout = pbr(Color::WHITE).uniform_layout();
+    assert_eq!(
+        offsets(&layout),
+        vec![
+            ("albedo", UniformType::Vec4, 0),
+            ("metallic", UniformType::F32, 16),
+            ("roughness", UniformType::F32, 20),
+            ("emissive", UniformType::Vec4, 32),
+        ]
+    );
+    assert_eq!(layout.size, 48);
+}
+
+#[test]
+fn test_uniform_data() {
+    let bytes = toon().uniform_data();
+    assert_eq!(bytes.len(), 64);
+
+    let word = |offset: usize| <[u8; 4]>::try_from(&bytes[offset..offset + 4]).unwrap();
+    assert_eq!(u32::from_le_bytes(word(0)), 3);
+    assert_eq!(f32::from_le_bytes(word(16)), 0.1);
+    assert_eq!(f32::from_le_bytes(word(24)), 0.3);
+    assert_eq!(f32::from_le_bytes(word(28)), 2.0);
+    assert_eq!(f32::from_le_bytes(word(36)), 0.5);
+    assert_eq!(u32::from_le_bytes(word(48)), 1);
+}
+
+#[test]
+fn test_generated_wgsl() {
+    let material = toon();
+    let layout = material.uniform_layout();
+    assert_eq!(
+        layout.wgsl_struct("ToonUniform"),
+        "struct ToonUniform {\n    steps: u32,\n    outline_color: vec3<f32>,\n    outline_width: f32,\n    tint: vec4<f32>,\n    lit: u32,\n};\n"
+    );
+    assert!(UniformLayout::default()
+        .wgsl_struct("Empty")
+        .contains("_unused: u32"));
+
+    let textures = vec!["ramp".to_string(), "detail".to_string()];
+    let prelude = material_prelude(&layout, &textures, &material.pipeline_key());
+    assert!(prelude.contains("@group(2) @binding(0)\nvar<uniform> material: MaterialUniform;"));
+    assert!(prelude.contains("@group(2) @binding(1)\nvar ramp: texture_2d<f32>;"));
+    assert!(prelude.contains("@group(2) @binding(2)\nvar ramp_sampler: sampler;"));
+    assert!(prelude.contains("@group(2) @binding(3)\nvar detail: texture_2d<f32>;"));
+    assert!(prelude.contains("const MATERIAL_ALPHA_CUTOFF: f32 = 0.5019608;"));
+    assert!(
+        prelude.contains("@group(3) @binding(0)\nvar<uniform> environment: EnvironmentUniform;")
+    );
+    assert!(prelude.contains("fn environment_lighting("));
+    assert!(prelude.contains("struct VertexInput"));
+}
+
+#[test]
+fn test_texture_bindings_from_reflection() {
+    let mut material = pbr(Color::WHITE);
+    let albedo: Handle<Texture> = Handle::new(AssetId::from_path("textures/albedo.png"), 0);
+    material.albedo_texture = Some(albedo.clone());
+
+    let bindings = material.textures();
+    let names: Vec<_> = bindings.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "albedo_texture",
+            "normal_texture",
+            "metallic_roughness_texture"
+        ]
+    );
+    assert_eq!(bindings[0].texture, Some(albedo));
+    assert_eq!(bindings[1].texture, None);
+
+    let ramp: Handle<Texture> = Handle::new(AssetId::from_path("textures/ramp.png"), 0);
+    let mut material = toon();
+    TextureBinding::set(&mut material, "ramp", ramp.clone()).unwrap();
+    assert_eq!(material.ramp, Some(ramp.clone()));
+    assert!(TextureBinding::set(&mut material, "steps", ramp.clone()).is_err());
+    assert!(TextureBinding::set(&mut material, "missing", ramp).is_err());
+}
+
+#[test]
+fn test_pipeline_keys() {
+    let key = MaterialPipelineKey::default();
+    assert_eq!(key.blend_mode, BlendMode::Opaque);
+    assert_eq!(key.cull_mode, CullMode::Back);
+    assert!(key.depth_write);
+    assert_eq!(key.alpha_mask, None);
+    assert!(!key.is_transparent());
+    assert!(MaterialPipelineKey::transparent().is_transparent());
+
+    assert_eq!(pbr(Color::WHITE).pipeline_key(), key);
+    let glass = pbr(Color::rgba(1.0, 1.0, 1.0, 0.5)).pipeline_key();
+    assert_eq!(glass.blend_mode, BlendMode::AlphaBlend);
+    assert!(!glass.depth_write);
+
+    assert_eq!(toon().pipeline_key().alpha_cutoff(), Some(128.0 / 255.0));
+    assert_eq!(BlendMode::Opaque.blend_state(), None);
+    assert_eq!(CullMode::None.face(), None);
+}
+
+#[test]
+fn test_material_keys_and_sorting() {
+    let material = pbr(Color::rgba(1.0, 0.5, 0.25, 1.0));
+    let layout = material.uniform_layout();
+    // The reflected key matches the hand-written one for PBR
+    assert_eq!(
+        MaterialKey::from_scalars(&layout.scalars(&material)),
+        MaterialKey::from_material(&material)
+    );
+
+    let asset_server = AssetServer::new("assets");
+    let pbr_key = DrawCallSortKey::for_material(&material, &asset_server);
+    let toon_key = DrawCallSortKey::for_material(&toon(), &asset_server);
+    assert_ne!(pbr_key.shader_id, toon_key.shader_id);
+    // Scalars in layout order: steps, then outline_color
+    assert_eq!(toon_key.material_key.albedo[1], 25);
+    assert_eq!(
+        pbr_key,
+        DrawCallSortKey::for_material(&material.clone(), &asset_server)
+    );
+
+    // Only loaded textures take part in sorting
+    let mut textured = material.clone();
+    textured.albedo_texture = Some(Handle::new(AssetId::from_path("missing.png"), 0));
+    assert_eq!(
+        DrawCallSortKey::for_material(&textured, &asset_server).texture_id,
+        None
+    );
+    textured.albedo_texture = Some(asset_server.add(Texture::solid_color(1, 1, [255; 4])));
+    assert!(DrawCallSortKey::for_material(&textured, &asset_server)
+        .texture_id
+        .is_some());
+}
+
+#[test]
+fn test_material_file() {
+    let queue = MaterialTextureQueue::default();
+    let loader = MaterialLoader::<UnlitMaterial>::new(queue.clone());
+    assert_eq!(loader.extensions(), &["material.ron"]);
+
+    let source = r#"(
+        material: (
+            color: (r: 1.0, g: 0.5, b: 0.25, a: 0.5),
+            blend_mode: AlphaBlend,
+        ),
+        textures: { "color_texture": "textures/stone.png" },
+    )"#;
+    let material = loader
+        .load(source.as_bytes(), Path::new("stone.material.ron"))
+        .unwrap();
+
+    assert_eq!(material.color, Color::rgba(1.0, 0.5, 0.25, 0.5));
+    assert_eq!(material.blend_mode, BlendMode::AlphaBlend);
+    assert!(material.pipeline_key().is_transparent());
+    // Same id as `AssetServer::load("textures/stone.png")`
+    assert_eq!(
+        material.color_texture.map(|handle| handle.id()),
+        Some(AssetId::from_path("textures/stone.png"))
+    );
+    assert_eq!(queue.drain(), vec!["textures/stone.png".to_string()]);
+    assert!(queue.drain().is_empty());
+
+    let unknown_slot =
+        r#"(material: (color: (r: 1.0, g: 1.0, b: 1.0, a: 1.0)), textures: { "nope": "a.png" })"#;
+    assert!(loader
+        .load(unknown_slot.as_bytes(), Path::new("bad.material.ron"))
+        .is_err());
+}
+
+#[test]
+fn test_registry_collects_material_draws() {
+    let mut world = World::new();
+    let asset_server = AssetServer::new("assets");
+    let mesh: Handle<Mesh> = Handle::new(AssetId::from_path("meshes/cube.mesh"), 0);
+    let shared = asset_server.add(toon());
+
+    let spawn = |world: &mut World| {
+        let entity = world.spawn();
+        world.add_component(entity, mesh.clone()).unwrap();
+        world
+            .add_component(entity, Transform::from_xyz(0.0, 0.0, 0.0))
+            .unwrap();
+        entity
+    };
+    for _ in 0..2 {
+        let entity = spawn(&mut world);
+        world.add_component(entity, shared.clone()).unwrap();
+    }
+    let inline = spawn(&mut world);
+    world.add_component(inline, pbr(Color::WHITE)).unwrap();
+    // Handles to materials that are not loaded are skipped
+    let pending = spawn(&mut world);
+    world
+        .add_component(
+            pending,
+            Handle::<ToonMaterial>::new(AssetId::from_path("pending.material.ron"), 0),
+        )
+        .unwrap();
+
+    let mut registry = MaterialRegistry::default();
+    registry.register::<ToonMaterial>();
+    registry.register::<ToonMaterial>();
+    registry.register_component::<PbrMaterial>();
+
+    let draws = registry.collect(&world, &asset_server);
+    assert_eq!(draws.len(), 3);
+
+    let toon_draws: Vec<_> = draws
+        .iter()
+        .filter(|draw| draw.material_type == "ToonMaterial")
+        .collect();
+    assert_eq!(toon_draws.len(), 2);
+    assert_eq!(toon_draws[0].pipeline_id(), toon_draws[1].pipeline_id());
+    assert_eq!(toon_draws[0].uniform, toon().uniform_data());
+    let composed = toon_draws[0]
+        .compose(&ShaderComposer::new(), "@fragment")
+        .unwrap();
+    assert!(composed.source.ends_with("@fragment\n"));
+
+    let pbr_draw = draws.iter().find(|draw| draw.entity == inline).unwrap();
+    assert_eq!(pbr_draw.shader, pbr_shader());
+    assert_ne!(pbr_draw.pipeline_id(), toon_draws[0].pipeline_id());
+}
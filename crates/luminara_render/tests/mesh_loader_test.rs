@@ -53,6 +53,11 @@ fn test_mesh_loader_extensions() {
 
 /// Creates a minimal valid GLB file containing a single triangle
 fn create_minimal_glb_triangle() -> Vec<u8> {
+    create_glb_triangle(true)
+}
+
+/// Triangle GLB; without normals the normal data stays in the buffer unused
+fn create_glb_triangle(with_normals: bool) -> Vec<u8> {
     // GLB format:
     // Header (12 bytes): magic (4), version (4), length (4)
     // JSON chunk header (8 bytes): length (4), type (4)
@@ -103,6 +108,11 @@ fn create_minimal_glb_triangle() -> Vec<u8> {
         ],
         "buffers": [{"byteLength": 78}]
     }"#;
+    let json = if with_normals {
+        json.to_string()
+    } else {
+        json.replace(",\n                    \"NORMAL\": 1", "")
+    };
 
     // Binary data: positions (3 vec3), normals (3 vec3), indices (3 u16)
     let mut bin_data = Vec::new();
@@ -196,3 +206,18 @@ fn test_mesh_from_gltf_direct() {
     assert!(mesh.aabb.min.y <= mesh.aabb.max.y);
     assert!(mesh.aabb.min.z <= mesh.aabb.max.z);
 }
+
+#[test]
+fn test_mesh_from_gltf_without_normals() {
+    let glb_data = create_glb_triangle(false);
+    assert!(!String::from_utf8_lossy(&glb_data).contains("NORMAL"));
+    let meshes = Mesh::from_gltf(&glb_data).unwrap();
+    let mesh = &meshes[0];
+    assert_eq!(mesh.vertices.len(), 3);
+    assert_eq!(mesh.indices.len(), 3);
+
+    // Flat normals are computed from the counter-clockwise winding
+    for vertex in &mesh.vertices {
+        assert_eq!(vertex.normal, [0.0, 0.0, 1.0]);
+    }
+}
@@ -0,0 +1,498 @@
+use luminara_math::Vec3;
+use luminara_render::mesh_processing::{
+    average_cache_miss_ratio, compute_normals, flat_normals, generate_tangents, optimize,
+    optimize_overdraw, optimize_vertex_cache, optimize_vertex_fetch, simplify, weld_vertices,
+};
+use luminara_render::{LodGenerator, Mesh, SimplifyOptions, Vertex};
+
+fn vertex(position: [f32; 3], uv: [f32; 2]) -> Vertex {
+    Vertex {
+        position,
+        normal: [0.0, 1.0, 0.0],
+        uv,
+        tangent: [1.0, 0.0, 0.0, 1.0],
+    }
+}
+
+/// Flat `n`x`n` grid of quads in the XZ plane spanning [0, 1]
+fn grid(n: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    for z in 0..=n {
+        for x in 0..=n {
+            let (u, v) = (x as f32 / n as f32, z as f32 / n as f32);
+            vertices.push(vertex([u, 0.0, v], [u, v]));
+        }
+    }
+    let mut indices = Vec::new();
+    for z in 0..n {
+        for x in 0..n {
+            let i = z * (n + 1) + x;
+            indices.extend_from_slice(&[i, i + n + 1, i + 1, i + 1, i + n + 1, i + n + 2]);
+        }
+    }
+    (vertices, indices)
+}
+
+/// Grid whose left and right halves are separate UV islands sharing the
+/// middle column of positions
+fn seamed_grid(n: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let (mut vertices, mut indices) = grid(n);
+    let half = n / 2;
+    let mut twins = std::collections::HashMap::new();
+    for z in 0..=n {
+        let i = z * (n + 1) + half;
+        let mut twin = vertices[i as usize];
+        twin.uv[0] += 10.0;
+        twins.insert(i, vertices.len() as u32);
+        vertices.push(twin);
+    }
+    for t in indices.chunks_exact_mut(3) {
+        let right = t.iter().any(|&i| (i % (n + 1)) > half);
+        if right {
+            for i in t.iter_mut() {
+                if let Some(&twin) = twins.get(i) {
+                    *i = twin;
+                }
+            }
+        }
+    }
+    // Right island UVs are offset so the islands are told apart
+    for v in vertices.iter_mut() {
+        if v.position[0] > 0.5 {
+            v.uv[0] += 10.0;
+        }
+    }
+    (vertices, indices)
+}
+
+/// The built-in meshes wind triangles clockwise around their stored normals;
+/// flip them so normals derived from the winding point outwards
+fn counter_clockwise(mesh: Mesh) -> Mesh {
+    let mut indices = mesh.indices.clone();
+    for t in indices.chunks_exact_mut(3) {
+        t.swap(1, 2);
+    }
+    Mesh::new(mesh.vertices.clone(), indices)
+}
+
+fn area(vertices: &[Vertex], indices: &[u32]) -> f32 {
+    indices
+        .chunks_exact(3)
+        .map(|t| {
+            let [a, b, c] =
+                [t[0], t[1], t[2]].map(|i| Vec3::from_array(vertices[i as usize].position));
+            (b - a).cross(c - a).length() * 0.5
+        })
+        .sum()
+}
+
+/// Largest distance from the unit sphere over points spread across every
+/// triangle
+fn sphere_deviation(vertices: &[Vertex], indices: &[u32]) -> f32 {
+    let mut deviation = 0.0f32;
+    for t in indices.chunks_exact(3) {
+        let [a, b, c] = [t[0], t[1], t[2]].map(|i| Vec3::from_array(vertices[i as usize].position));
+        for i in 0..=4 {
+            for j in 0..=(4 - i) {
+                let (u, v) = (i as f32 / 4.0, j as f32 / 4.0);
+                let p = a + (b - a) * u + (c - a) * v;
+                deviation = deviation.max((p.length() - 1.0).abs());
+            }
+        }
+    }
+    deviation
+}
+
+fn assert_valid(vertex_count: usize, indices: &[u32]) {
+    assert_eq!(indices.len() % 3, 0);
+    assert!(indices.iter().all(|&i| (i as usize) < vertex_count));
+}
+
+#[test]
+fn test_simplify_sphere_geometric_error() {
+    let sphere = Mesh::sphere(1.0, 48);
+    let original = sphere_deviation(&sphere.vertices, &sphere.indices);
+
+    let mut previous_triangles = sphere.indices.len() / 3;
+    for ratio in [0.5, 0.25, 0.1] {
+        let options = SimplifyOptions::ratio(sphere.indices.len(), ratio);
+        let result = simplify(&sphere.vertices, &sphere.indices, &options);
+        assert_valid(sphere.vertices.len(), &result.indices);
+
+        let triangles = result.indices.len() / 3;
+        assert!(
+            triangles <= options.target_index_count / 3 + 8,
+            "{} triangles at {}",
+            triangles,
+            ratio
+        );
+        assert!(triangles < previous_triangles);
+        previous_triangles = triangles;
+
+        // The surface stays close to the sphere and the area is kept
+        let deviation = sphere_deviation(&sphere.vertices, &result.indices);
+        assert!(
+            deviation < original + 0.07,
+            "deviation {} at {}",
+            deviation,
+            ratio
+        );
+        let area = area(&sphere.vertices, &result.indices);
+        let sphere_area = 4.0 * std::f32::consts::PI;
+        assert!(
+            (area - sphere_area).abs() / sphere_area < 0.06,
+            "area {} at {}",
+            area,
+            ratio
+        );
+        assert!(result.error > 0.0 && result.error < 0.1);
+    }
+}
+
+#[test]
+fn test_simplify_respects_error_limit() {
+    let sphere = Mesh::sphere(1.0, 32);
+    let unlimited = simplify(
+        &sphere.vertices,
+        &sphere.indices,
+        &SimplifyOptions::ratio(sphere.indices.len(), 0.1),
+    );
+    let limited = simplify(
+        &sphere.vertices,
+        &sphere.indices,
+        &SimplifyOptions {
+            max_error: 0.001,
+            ..SimplifyOptions::ratio(sphere.indices.len(), 0.1)
+        },
+    );
+    assert!(limited.error <= 0.001);
+    assert!(limited.indices.len() > unlimited.indices.len());
+    assert!(
+        sphere_deviation(&sphere.vertices, &limited.indices)
+            < sphere_deviation(&sphere.vertices, &unlimited.indices)
+    );
+}
+
+#[test]
+fn test_simplify_flat_grid_keeps_borders() {
+    let (vertices, indices) = grid(16);
+    let result = simplify(
+        &vertices,
+        &indices,
+        &SimplifyOptions {
+            target_index_count: 6,
+            max_error: 1e-4,
+            lock_border: false,
+        },
+    );
+    // A plane collapses to almost nothing without leaving the plane or
+    // pulling the border in
+    assert!(
+        result.indices.len() <= 6 * 3,
+        "{} indices",
+        result.indices.len()
+    );
+    assert!((area(&vertices, &result.indices) - 1.0).abs() < 1e-4);
+    assert!(result.error < 1e-4);
+
+    // Locked borders keep every border vertex
+    let locked = simplify(
+        &vertices,
+        &indices,
+        &SimplifyOptions {
+            target_index_count: 6,
+            max_error: 1e-4,
+            lock_border: true,
+        },
+    );
+    let used: std::collections::HashSet<u32> = locked.indices.iter().copied().collect();
+    let on_border = |v: &Vertex| {
+        [v.position[0], v.position[2]]
+            .iter()
+            .any(|&c| c == 0.0 || c == 1.0)
+    };
+    for (i, v) in vertices.iter().enumerate() {
+        if on_border(v) {
+            assert!(used.contains(&(i as u32)), "border vertex {} removed", i);
+        }
+    }
+    assert!(locked.indices.len() < indices.len());
+}
+
+#[test]
+fn test_simplify_preserves_uv_seams() {
+    let (vertices, indices) = seamed_grid(16);
+    let result = simplify(
+        &vertices,
+        &indices,
+        &SimplifyOptions {
+            target_index_count: 12,
+            max_error: 1e-4,
+            lock_border: false,
+        },
+    );
+    assert!(result.indices.len() < indices.len() / 4);
+
+    // No triangle mixes the two UV islands
+    let island = |i: u32| vertices[i as usize].uv[0] >= 10.0;
+    for t in result.indices.chunks_exact(3) {
+        assert!(
+            t.iter().all(|&i| island(i) == island(t[0])),
+            "triangle {:?} spans the seam",
+            t
+        );
+    }
+    // Both islands still meet along the same seam positions
+    let seam_positions = |right: bool| {
+        let mut positions: Vec<[u32; 3]> = result
+            .indices
+            .iter()
+            .filter(|&&i| island(i) == right && vertices[i as usize].position[0] == 0.5)
+            .map(|&i| vertices[i as usize].position.map(f32::to_bits))
+            .collect();
+        positions.sort();
+        positions.dedup();
+        positions
+    };
+    assert_eq!(seam_positions(false), seam_positions(true));
+    assert!(seam_positions(false).len() >= 2);
+    assert!((area(&vertices, &result.indices) - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_weld_vertices() {
+    let cube = Mesh::cube(1.0);
+    // Unindexed copy of the cube
+    let unindexed: Vec<Vertex> = cube
+        .indices
+        .iter()
+        .map(|&i| cube.vertices[i as usize])
+        .collect();
+    let sequential: Vec<u32> = (0..unindexed.len() as u32).collect();
+
+    let (welded, indices) = weld_vertices(&unindexed, &sequential, 0.0);
+    assert_eq!(welded.len(), 24);
+    assert_eq!(indices.len(), 36);
+    for (t, original) in indices.chunks_exact(3).zip(cube.indices.chunks_exact(3)) {
+        for (&a, &b) in t.iter().zip(original) {
+            assert_eq!(
+                welded[a as usize].position,
+                cube.vertices[b as usize].position
+            );
+        }
+    }
+
+    // Nearly identical vertices merge within the tolerance
+    let mut noisy = unindexed.clone();
+    for (i, v) in noisy.iter_mut().enumerate() {
+        v.position[0] += (i % 3) as f32 * 1e-5;
+    }
+    assert_eq!(weld_vertices(&noisy, &sequential, 1e-3).0.len(), 24);
+    assert!(weld_vertices(&noisy, &sequential, 0.0).0.len() > 24);
+}
+
+#[test]
+fn test_normals() {
+    let mut sphere = counter_clockwise(Mesh::sphere(1.0, 24));
+    let analytic: Vec<[f32; 3]> = sphere.vertices.iter().map(|v| v.normal).collect();
+    for v in sphere.vertices.iter_mut() {
+        v.normal = [0.0, 0.0, 0.0];
+    }
+    compute_normals(&mut sphere.vertices, &sphere.indices, 60f32.to_radians());
+    // The bottom pole is a fan of zero-area triangles with nothing to go on
+    let bottom = |v: &Vertex| v.position[1] < -0.999;
+    for (v, expected) in sphere.vertices.iter().zip(&analytic) {
+        if bottom(v) {
+            continue;
+        }
+        let dot = Vec3::from_array(v.normal).dot(Vec3::from_array(*expected));
+        assert!(dot > 0.99, "normal at {:?} is off: {}", v.position, dot);
+    }
+
+    // Hard edges survive: every cube vertex keeps its face normal
+    let mut cube = counter_clockwise(Mesh::cube(1.0));
+    let faces: Vec<[f32; 3]> = cube.vertices.iter().map(|v| v.normal).collect();
+    compute_normals(&mut cube.vertices, &cube.indices, 60f32.to_radians());
+    for (v, expected) in cube.vertices.iter().zip(&faces) {
+        assert!(Vec3::from_array(v.normal).dot(Vec3::from_array(*expected)) > 0.999);
+    }
+
+    // Flat normals split shared vertices between faces that disagree
+    let (vertices, indices) = grid(2);
+    let (flat, flat_indices) = flat_normals(&vertices, &indices);
+    assert_eq!(flat.len(), vertices.len());
+    assert!(flat.iter().all(|v| v.normal[1] == 1.0));
+    assert_eq!(flat_indices.len(), indices.len());
+    let sphere = Mesh::sphere(1.0, 8);
+    let (flat, _) = flat_normals(&sphere.vertices, &sphere.indices);
+    assert!(flat.len() > sphere.vertices.len());
+}
+
+#[test]
+fn test_tangents() {
+    // u runs along +X on the grid
+    let (mut vertices, indices) = grid(4);
+    generate_tangents(&mut vertices, &indices);
+    for v in &vertices {
+        assert!((Vec3::new(v.tangent[0], v.tangent[1], v.tangent[2]) - Vec3::X).length() < 1e-5);
+    }
+    let handedness = vertices[0].tangent[3];
+
+    // Mirroring the UVs flips the bitangent sign
+    let mut mirrored = vertices.clone();
+    for v in mirrored.iter_mut() {
+        v.uv[0] = 1.0 - v.uv[0];
+    }
+    generate_tangents(&mut mirrored, &indices);
+    for v in &mirrored {
+        assert!((Vec3::new(v.tangent[0], v.tangent[1], v.tangent[2]) + Vec3::X).length() < 1e-5);
+        assert_eq!(v.tangent[3], -handedness);
+    }
+
+    // Sphere tangents follow the direction of increasing u and stay
+    // orthogonal to the normal
+    let mut sphere = Mesh::sphere(1.0, 24);
+    let expected: Vec<[f32; 4]> = sphere.vertices.iter().map(|v| v.tangent).collect();
+    generate_tangents(&mut sphere.vertices, &sphere.indices);
+    for (v, expected) in sphere.vertices.iter().zip(&expected) {
+        let t = Vec3::new(v.tangent[0], v.tangent[1], v.tangent[2]);
+        assert!((t.length() - 1.0).abs() < 1e-4);
+        assert!(t.dot(Vec3::from_array(v.normal)).abs() < 1e-4);
+        let pole = v.position[1].abs() > 0.999;
+        if !pole {
+            let e = Vec3::new(expected[0], expected[1], expected[2]);
+            assert!(
+                t.dot(e) > 0.95,
+                "tangent at {:?}: {:?} vs {:?}",
+                v.position,
+                t,
+                e
+            );
+        }
+    }
+}
+
+#[test]
+fn test_vertex_cache_optimization() {
+    let (vertices, indices) = grid(32);
+    // Scatter the triangles so the input order is cache hostile
+    let triangle_count = indices.len() / 3;
+    let mut shuffled = Vec::with_capacity(indices.len());
+    for k in 0..triangle_count {
+        let t = (k * 7919) % triangle_count;
+        shuffled.extend_from_slice(&indices[t * 3..t * 3 + 3]);
+    }
+    let before = average_cache_miss_ratio(&shuffled, 16);
+
+    let mut optimized = shuffled.clone();
+    optimize_vertex_cache(&mut optimized, vertices.len());
+    let after = average_cache_miss_ratio(&optimized, 16);
+    assert!(after < 0.8, "ACMR {} (was {})", after, before);
+    assert!(after < before * 0.5);
+
+    // Same triangles, same winding
+    let canonical = |indices: &[u32]| {
+        let mut triangles: Vec<[u32; 3]> = indices
+            .chunks_exact(3)
+            .map(|t| {
+                let r = (0..3).min_by_key(|&k| t[k]).unwrap();
+                [t[r], t[(r + 1) % 3], t[(r + 2) % 3]]
+            })
+            .collect();
+        triangles.sort();
+        triangles
+    };
+    assert_eq!(canonical(&optimized), canonical(&indices));
+
+    // Overdraw ordering keeps the cache efficiency within the threshold
+    let mut overdraw = optimized.clone();
+    optimize_overdraw(&mut overdraw, &vertices, 1.05);
+    assert_eq!(canonical(&overdraw), canonical(&indices));
+    assert!(average_cache_miss_ratio(&overdraw, 16) < after * 1.3);
+}
+
+#[test]
+fn test_overdraw_draws_outer_surfaces_first() {
+    // An inner sphere listed before an outer one
+    let inner = counter_clockwise(Mesh::sphere(0.5, 16));
+    let outer = counter_clockwise(Mesh::sphere(1.0, 16));
+    let mut vertices = inner.vertices.clone();
+    vertices.extend(outer.vertices.iter().copied());
+    let mut indices = inner.indices.clone();
+    indices.extend(
+        outer
+            .indices
+            .iter()
+            .map(|i| i + inner.vertices.len() as u32),
+    );
+
+    optimize_vertex_cache(&mut indices, vertices.len());
+    optimize_overdraw(&mut indices, &vertices, 1.05);
+
+    let is_outer = |t: &[u32]| t.iter().all(|&i| i as usize >= inner.vertices.len());
+    let triangles: Vec<&[u32]> = indices.chunks_exact(3).collect();
+    let outer_count = outer.indices.len() / 3;
+    let leading = triangles[..outer_count]
+        .iter()
+        .filter(|t| is_outer(t))
+        .count();
+    assert!(
+        leading as f32 > outer_count as f32 * 0.9,
+        "{} of the first {} triangles are on the outer sphere",
+        leading,
+        outer_count
+    );
+}
+
+#[test]
+fn test_vertex_fetch_and_full_optimization() {
+    let sphere = Mesh::sphere(1.0, 16);
+    let simplified = simplify(
+        &sphere.vertices,
+        &sphere.indices,
+        &SimplifyOptions::ratio(sphere.indices.len(), 0.25),
+    );
+    let (vertices, indices) = optimize_vertex_fetch(&sphere.vertices, &simplified.indices);
+    assert!(vertices.len() < sphere.vertices.len());
+    assert_valid(vertices.len(), &indices);
+    // Vertices appear in first-use order
+    let mut next = 0;
+    for &i in &indices {
+        assert!(i <= next);
+        if i == next {
+            next += 1;
+        }
+    }
+    assert_eq!(next as usize, vertices.len());
+
+    let (vertices, indices) = optimize(&sphere.vertices, &sphere.indices);
+    assert_valid(vertices.len(), &indices);
+    assert_eq!(indices.len(), sphere.indices.len());
+    assert!(average_cache_miss_ratio(&indices, 16) < average_cache_miss_ratio(&sphere.indices, 16));
+}
+
+#[test]
+fn test_lod_generator_levels() {
+    let generator = LodGenerator::default();
+    let sphere = Mesh::sphere(1.0, 48);
+    let levels = generator.generate_lod_meshes(&sphere);
+    assert_eq!(levels.len(), generator.reduction_ratios.len());
+
+    let mut previous = usize::MAX;
+    for (level, mesh) in levels.iter().enumerate() {
+        assert_valid(mesh.vertices.len(), &mesh.indices);
+        assert!(mesh.indices.len() <= previous);
+        previous = mesh.indices.len();
+        // Every level is still a closed sphere, not a scattering of triangles
+        let deviation = sphere_deviation(&mesh.vertices, &mesh.indices);
+        assert!(deviation < 0.12, "LOD {} deviates by {}", level, deviation);
+        let area = area(&mesh.vertices, &mesh.indices);
+        assert!(
+            area > 4.0 * std::f32::consts::PI * 0.85,
+            "LOD {} area {}",
+            level,
+            area
+        );
+    }
+    assert!(levels[2].indices.len() < levels[0].indices.len() / 3);
+}
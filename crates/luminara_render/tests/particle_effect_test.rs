@@ -0,0 +1,457 @@
+use luminara_asset::{AssetId, AssetLoader, AssetServer, Handle};
+use luminara_core::{Time, World};
+use luminara_math::{Color, Mat4, Vec3};
+use luminara_render::{
+    particle_effect_system, Burst, Curve, EmissionShape, Gradient, Mesh, ParticleCollider,
+    ParticleColliders, ParticleCollision, ParticleEffect, ParticleEffectInstance,
+    ParticleEffectLoader, ParticleEmitterDesc, ParticleForce, ParticleSimulationContext,
+    SimulationSpace, SubEmitter, SubEmitterTrigger, ValueRange, Vertex,
+};
+use std::path::Path;
+use std::sync::Arc;
+
+const DT: f32 = 1.0 / 60.0;
+
+fn no_meshes(_: &Handle<Mesh>) -> Option<Arc<Mesh>> {
+    None
+}
+
+fn context(transform: Mat4, colliders: &[ParticleCollider]) -> ParticleSimulationContext<'_> {
+    ParticleSimulationContext {
+        transform,
+        colliders,
+        meshes: &no_meshes,
+    }
+}
+
+fn run(effect: &ParticleEffect, seed: u64, steps: usize) -> ParticleEffectInstance {
+    let mut instance = ParticleEffectInstance::new(Handle::default(), seed);
+    for _ in 0..steps {
+        instance.simulate(effect, &context(Mat4::IDENTITY, &[]), DT);
+    }
+    instance
+}
+
+fn burst_emitter(name: &str, count: u32) -> ParticleEmitterDesc {
+    ParticleEmitterDesc {
+        bursts: vec![Burst { time: 0.0, count }],
+        looping: false,
+        ..ParticleEmitterDesc::new(name)
+    }
+}
+
+#[test]
+fn test_simulation_is_deterministic_per_seed() {
+    let effect = ParticleEffect {
+        emitters: vec![ParticleEmitterDesc {
+            rate: 120.0,
+            shape: EmissionShape::Sphere {
+                radius: 1.0,
+                surface_only: false,
+            },
+            lifetime: ValueRange::new(0.5, 1.5),
+            speed: ValueRange::new(1.0, 3.0),
+            forces: vec![ParticleForce::Noise {
+                strength: 4.0,
+                frequency: 2.0,
+                scroll: 0.5,
+            }],
+            ..ParticleEmitterDesc::new("sparks")
+        }],
+    };
+
+    let a = run(&effect, 7, 90);
+    let b = run(&effect, 7, 90);
+    let c = run(&effect, 8, 90);
+    assert!(a.particle_count() > 0);
+    assert_eq!(a.emitters[0].particles, b.emitters[0].particles);
+    assert_ne!(a.emitters[0].particles, c.emitters[0].particles);
+}
+
+#[test]
+fn test_rate_and_bursts_emit_expected_counts() {
+    let effect = ParticleEffect {
+        emitters: vec![ParticleEmitterDesc {
+            rate: 60.0,
+            bursts: vec![
+                Burst {
+                    time: 0.0,
+                    count: 10,
+                },
+                Burst {
+                    time: 0.5,
+                    count: 5,
+                },
+            ],
+            duration: 1.0,
+            looping: false,
+            lifetime: ValueRange::constant(10.0),
+            ..ParticleEmitterDesc::new("fountain")
+        }],
+    };
+
+    let instance = run(&effect, 1, 1);
+    assert_eq!(instance.particle_count(), 11);
+
+    // A full cycle: 60 from the rate, 15 from the bursts, then nothing more
+    let instance = run(&effect, 1, 120);
+    let count = instance.particle_count();
+    assert!((74..=76).contains(&count), "{}", count);
+    assert!(instance.emitters[0].finished);
+}
+
+#[test]
+fn test_capacity_limits_each_emitter() {
+    let effect = ParticleEffect {
+        emitters: vec![
+            ParticleEmitterDesc {
+                capacity: 16,
+                ..burst_emitter("small", 100)
+            },
+            burst_emitter("large", 100),
+        ],
+    };
+    let instance = run(&effect, 3, 1);
+    assert_eq!(instance.emitters[0].particles.len(), 16);
+    assert_eq!(instance.emitters[1].particles.len(), 100);
+}
+
+#[test]
+fn test_emission_shapes_bound_spawn_positions() {
+    let shapes = [
+        (
+            EmissionShape::Sphere {
+                radius: 2.0,
+                surface_only: true,
+            },
+            Box::new(|p: Vec3, _: Vec3| (p.length() - 2.0).abs() < 1e-4)
+                as Box<dyn Fn(Vec3, Vec3) -> bool>,
+        ),
+        (
+            EmissionShape::Cone {
+                angle: 30.0,
+                radius: 0.5,
+            },
+            Box::new(|p: Vec3, v: Vec3| {
+                p.y.abs() < 1e-4
+                    && p.length() <= 0.5 + 1e-4
+                    && v.normalize().dot(Vec3::Y) >= 30f32.to_radians().cos() - 1e-4
+            }),
+        ),
+        (
+            EmissionShape::Box {
+                half_extents: Vec3::new(1.0, 0.1, 2.0),
+            },
+            Box::new(|p: Vec3, _: Vec3| p.x.abs() <= 1.0 && p.y.abs() <= 0.1 && p.z.abs() <= 2.0),
+        ),
+        (
+            EmissionShape::Edge {
+                start: Vec3::ZERO,
+                end: Vec3::X * 4.0,
+            },
+            Box::new(|p: Vec3, _: Vec3| p.y == 0.0 && p.z == 0.0 && (0.0..=4.0).contains(&p.x)),
+        ),
+    ];
+
+    for (shape, inside) in shapes {
+        let effect = ParticleEffect {
+            emitters: vec![ParticleEmitterDesc {
+                shape: shape.clone(),
+                // Sample spawn positions without movement
+                speed_over_lifetime: Some(Curve::constant(0.0)),
+                ..burst_emitter("shape", 64)
+            }],
+        };
+        let mut instance = ParticleEffectInstance::new(Handle::default(), 11);
+        instance.simulate(&effect, &context(Mat4::IDENTITY, &[]), DT);
+        assert_eq!(instance.particle_count(), 64);
+        for particle in &instance.emitters[0].particles {
+            assert!(
+                inside(particle.position, particle.velocity),
+                "{:?}: {:?}",
+                shape,
+                particle
+            );
+        }
+    }
+}
+
+#[test]
+fn test_mesh_surface_emits_along_normals() {
+    let vertex = |position: [f32; 3]| Vertex {
+        position,
+        normal: [0.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
+    };
+    // Unit quad facing +Y at y = 2
+    let quad = Arc::new(Mesh::new(
+        vec![
+            vertex([0.0, 2.0, 0.0]),
+            vertex([0.0, 2.0, 1.0]),
+            vertex([1.0, 2.0, 1.0]),
+            vertex([1.0, 2.0, 0.0]),
+        ],
+        vec![0, 1, 2, 0, 2, 3],
+    ));
+    let handle: Handle<Mesh> = Handle::new(AssetId::from_path("quad.obj"), 0);
+    let meshes = move |_: &Handle<Mesh>| Some(quad.clone());
+
+    let effect = ParticleEffect {
+        emitters: vec![ParticleEmitterDesc {
+            shape: EmissionShape::MeshSurface { mesh: handle },
+            speed_over_lifetime: Some(Curve::constant(0.0)),
+            ..burst_emitter("surface", 32)
+        }],
+    };
+    let mut instance = ParticleEffectInstance::new(Handle::default(), 5);
+    let context = ParticleSimulationContext {
+        transform: Mat4::IDENTITY,
+        colliders: &[],
+        meshes: &meshes,
+    };
+    instance.simulate(&effect, &context, DT);
+
+    for particle in &instance.emitters[0].particles {
+        assert!((particle.position.y - 2.0).abs() < 1e-4);
+        assert!((0.0..=1.0).contains(&particle.position.x));
+        assert!((0.0..=1.0).contains(&particle.position.z));
+        assert!(particle.velocity.normalize().dot(Vec3::Y) > 0.999);
+    }
+}
+
+#[test]
+fn test_curves_and_gradients_follow_normalized_age() {
+    let curve = Curve {
+        keys: vec![(0.0, 0.0), (0.5, 2.0), (1.0, 1.0)],
+    };
+    assert_eq!(curve.sample(-1.0), 0.0);
+    assert_eq!(curve.sample(0.25), 1.0);
+    assert_eq!(curve.sample(0.75), 1.5);
+    assert_eq!(curve.sample(2.0), 1.0);
+
+    let gradient = Gradient::linear(Color::WHITE, Color::rgba(1.0, 0.0, 0.0, 0.0));
+    assert_eq!(gradient.sample(0.5), Color::rgba(1.0, 0.5, 0.5, 0.5));
+
+    let effect = ParticleEffect {
+        emitters: vec![ParticleEmitterDesc {
+            lifetime: ValueRange::constant(1.0),
+            size: ValueRange::constant(2.0),
+            size_over_lifetime: Some(Curve::linear(1.0, 0.0)),
+            color_over_lifetime: Some(gradient),
+            ..burst_emitter("fade", 1)
+        }],
+    };
+    let instance = run(&effect, 2, 30);
+    let particle = instance.emitters[0].particles[0];
+    assert!((particle.size - 1.0).abs() < 1e-3);
+    assert!((particle.color.a - 0.5).abs() < 1e-3);
+}
+
+#[test]
+fn test_gravity_drag_and_attractor_forces() {
+    let still = |forces: Vec<ParticleForce>| ParticleEffect {
+        emitters: vec![ParticleEmitterDesc {
+            speed: ValueRange::constant(0.0),
+            lifetime: ValueRange::constant(10.0),
+            forces,
+            ..burst_emitter("forces", 1)
+        }],
+    };
+
+    let falling = run(
+        &still(vec![ParticleForce::Gravity(Vec3::NEG_Y * 10.0)]),
+        1,
+        60,
+    );
+    let velocity = falling.emitters[0].particles[0].velocity;
+    assert!((velocity.y + 10.0).abs() < 1e-3);
+
+    let dragged = run(
+        &still(vec![
+            ParticleForce::Gravity(Vec3::NEG_Y * 10.0),
+            ParticleForce::Drag(5.0),
+        ]),
+        1,
+        60,
+    );
+    assert!(dragged.emitters[0].particles[0].velocity.y > velocity.y);
+
+    let attracted = run(
+        &still(vec![ParticleForce::Attractor {
+            position: Vec3::X * 5.0,
+            strength: 3.0,
+            radius: 0.0,
+        }]),
+        1,
+        30,
+    );
+    let particle = attracted.emitters[0].particles[0];
+    assert!(particle.velocity.x > 1.0);
+    assert!(particle.position.x > 0.0);
+}
+
+#[test]
+fn test_particles_bounce_off_colliders() {
+    let effect = ParticleEffect {
+        emitters: vec![ParticleEmitterDesc {
+            shape: EmissionShape::Point,
+            speed: ValueRange::constant(0.0),
+            size: ValueRange::constant(0.0),
+            lifetime: ValueRange::constant(10.0),
+            forces: vec![ParticleForce::Gravity(Vec3::NEG_Y * 10.0)],
+            collision: Some(ParticleCollision {
+                bounce: 0.5,
+                friction: 0.0,
+                radius_scale: 0.5,
+                kill: false,
+            }),
+            ..burst_emitter("rain", 1)
+        }],
+    };
+    let floor = [ParticleCollider::Plane {
+        normal: Vec3::Y,
+        distance: -1.0,
+    }];
+    let mut instance = ParticleEffectInstance::new(Handle::default(), 1);
+    let mut bounced = false;
+    for _ in 0..120 {
+        instance.simulate(&effect, &context(Mat4::IDENTITY, &floor), DT);
+        let particle = instance.emitters[0].particles[0];
+        assert!(particle.position.y >= -1.0 - 1e-4);
+        bounced |= particle.velocity.y > 0.0;
+    }
+    assert!(bounced);
+
+    // Box and sphere distances
+    let cube = ParticleCollider::Box {
+        center: Vec3::ZERO,
+        rotation: luminara_math::Quat::IDENTITY,
+        half_extents: Vec3::ONE,
+    };
+    assert_eq!(cube.distance(Vec3::new(3.0, 0.0, 0.0)), (2.0, Vec3::X));
+    assert_eq!(
+        cube.distance(Vec3::new(0.0, -0.75, 0.0)),
+        (-0.25, Vec3::NEG_Y)
+    );
+    let ball = ParticleCollider::Sphere {
+        center: Vec3::Y,
+        radius: 1.0,
+    };
+    assert_eq!(ball.distance(Vec3::Y * 3.0), (1.0, Vec3::Y));
+}
+
+#[test]
+fn test_sub_emitters_spawn_on_death() {
+    let effect = ParticleEffect {
+        emitters: vec![
+            ParticleEmitterDesc {
+                lifetime: ValueRange::constant(0.1),
+                sub_emitters: vec![SubEmitter {
+                    trigger: SubEmitterTrigger::Death,
+                    emitter: "debris".to_string(),
+                    count: 4,
+                    inherit_velocity: 0.0,
+                }],
+                ..burst_emitter("rocket", 2)
+            },
+            ParticleEmitterDesc {
+                lifetime: ValueRange::constant(5.0),
+                ..burst_emitter("debris", 0)
+            },
+        ],
+    };
+
+    let instance = run(&effect, 9, 3);
+    assert_eq!(instance.emitters[0].particles.len(), 2);
+    assert!(instance.emitters[1].particles.is_empty());
+
+    let instance = run(&effect, 9, 10);
+    assert!(instance.emitters[0].particles.is_empty());
+    assert_eq!(instance.emitters[1].particles.len(), 8);
+}
+
+#[test]
+fn test_local_space_particles_follow_the_emitter() {
+    let effect = |space| ParticleEffect {
+        emitters: vec![ParticleEmitterDesc {
+            speed: ValueRange::constant(0.0),
+            lifetime: ValueRange::constant(10.0),
+            space,
+            ..burst_emitter("trail", 1)
+        }],
+    };
+    let start = Mat4::IDENTITY;
+    let moved = Mat4::from_translation(Vec3::X * 10.0);
+
+    for space in [SimulationSpace::Local, SimulationSpace::World] {
+        let effect = effect(space);
+        let mut instance = ParticleEffectInstance::new(Handle::default(), 4);
+        instance.simulate(&effect, &context(start, &[]), DT);
+        instance.simulate(&effect, &context(moved, &[]), DT);
+
+        let rendered = instance.render_instances(&effect, moved)[0].position();
+        let expected = match space {
+            SimulationSpace::Local => Vec3::X * 10.0,
+            SimulationSpace::World => Vec3::ZERO,
+        };
+        assert!(rendered.distance(expected) < 1e-4, "{:?}", space);
+    }
+}
+
+#[test]
+fn test_effect_loader_and_system() {
+    let source = r#"(
+        emitters: [
+            (
+                name: "smoke",
+                rate: 30.0,
+                shape: Cone(angle: 15.0, radius: 0.2),
+                lifetime: (min: 1.0, max: 2.0),
+                speed: (min: 0.5, max: 1.0),
+                size: (min: 0.2, max: 0.4),
+                color_over_lifetime: Some([(0.0, (r: 1.0, g: 1.0, b: 1.0, a: 1.0)), (1.0, (r: 1.0, g: 1.0, b: 1.0, a: 0.0))]),
+                forces: [Gravity((0.0, 1.0, 0.0)), Drag(0.5)],
+                sub_emitters: [(trigger: Birth, emitter: "embers", count: 1)],
+                space: Local,
+            ),
+            (
+                name: "embers",
+                lifetime: (min: 0.5, max: 0.5),
+                speed: (min: 0.0, max: 0.0),
+                size: (min: 0.05, max: 0.05),
+            ),
+        ],
+    )"#;
+    let loader = ParticleEffectLoader;
+    let effect = loader
+        .load(source.as_bytes(), Path::new("fire.particles.ron"))
+        .unwrap();
+    assert_eq!(effect.emitters.len(), 2);
+    assert_eq!(effect.emitters[0].space, SimulationSpace::Local);
+    assert_eq!(effect.emitter_index("embers"), Some(1));
+
+    let broken = source.replace("emitter: \"embers\"", "emitter: \"ash\"");
+    assert!(loader
+        .load(broken.as_bytes(), Path::new("fire.particles.ron"))
+        .is_err());
+
+    let mut world = World::new();
+    let assets = AssetServer::new("assets");
+    let handle = assets.insert(AssetId::from_path("fire.particles.ron"), effect);
+    world.insert_resource(assets);
+    world.insert_resource(ParticleColliders::default());
+    let mut time = Time::new();
+    time.update_manual(0.5);
+    world.insert_resource(time);
+
+    let fire = world.spawn();
+    world
+        .add_component(fire, ParticleEffectInstance::new(handle, 42))
+        .unwrap();
+    particle_effect_system(&mut world);
+
+    let instance = world.get_component::<ParticleEffectInstance>(fire).unwrap();
+    assert_eq!(instance.emitters[0].particles.len(), 15);
+    assert_eq!(instance.emitters[1].particles.len(), 15);
+}
@@ -0,0 +1,228 @@
+use luminara_asset::AssetLoader;
+use luminara_math::Vec3;
+use luminara_render::{
+    ev_from_luminance, exposure_from_ev, AutoExposure, ColorLut, ColorLutLoader, LutError,
+    PostEffect, PostProcessSettings, ShaderComposer, ShaderDefs, Tonemapping, MID_GRAY,
+};
+use std::path::Path;
+
+/// A 2³ LUT that swaps red and blue
+const SWAP_CUBE: &str = "\
+# Created by hand
+TITLE \"swap\"
+LUT_3D_SIZE 2
+
+0 0 0
+0 0 1
+0 1 0
+0 1 1
+1 0 0
+1 0 1
+1 1 0
+1 1 1
+";
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!(a.abs_diff_eq(b, 1e-5), "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn test_parse_cube() {
+    let lut = ColorLut::parse_cube(SWAP_CUBE).unwrap();
+    assert_eq!(lut.size, 2);
+    assert_eq!(lut.data.len(), 8);
+    assert_eq!(lut.domain_min, Vec3::ZERO);
+    assert_eq!(lut.domain_max, Vec3::ONE);
+    assert_close(lut.entry(1, 0, 0), Vec3::new(0.0, 0.0, 1.0));
+    assert_close(
+        lut.sample(Vec3::new(0.8, 0.5, 0.1)),
+        Vec3::new(0.1, 0.5, 0.8),
+    );
+}
+
+#[test]
+fn test_parse_cube_domain() {
+    let source = SWAP_CUBE.replace(
+        "LUT_3D_SIZE 2",
+        "LUT_3D_SIZE 2\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 2 2 2",
+    );
+    let lut = ColorLut::parse_cube(&source).unwrap();
+    assert_eq!(lut.domain_max, Vec3::splat(2.0));
+    // Inputs are normalized to the domain before the lookup
+    assert_close(
+        lut.sample(Vec3::new(2.0, 1.0, 0.0)),
+        Vec3::new(0.0, 0.5, 1.0),
+    );
+    // and clamped to it
+    assert_close(
+        lut.sample(Vec3::new(4.0, -1.0, 0.0)),
+        Vec3::new(0.0, 0.0, 1.0),
+    );
+
+    let source = SWAP_CUBE.replace("LUT_3D_SIZE 2", "LUT_3D_SIZE 2\nLUT_3D_INPUT_RANGE 0 4");
+    let lut = ColorLut::parse_cube(&source).unwrap();
+    assert_eq!(lut.domain_max, Vec3::splat(4.0));
+
+    let source = SWAP_CUBE.replace(
+        "LUT_3D_SIZE 2",
+        "LUT_3D_SIZE 2\nDOMAIN_MIN 1 1 1\nDOMAIN_MAX 1 2 2",
+    );
+    assert_eq!(ColorLut::parse_cube(&source), Err(LutError::InvalidDomain));
+}
+
+#[test]
+fn test_parse_cube_errors() {
+    assert_eq!(ColorLut::parse_cube("0 0 0\n"), Err(LutError::MissingSize));
+    assert_eq!(
+        ColorLut::parse_cube("LUT_3D_SIZE 1\n0 0 0\n"),
+        Err(LutError::InvalidSize(1))
+    );
+    assert_eq!(
+        ColorLut::parse_cube("LUT_1D_SIZE 16\n"),
+        Err(LutError::Unsupported1D)
+    );
+
+    let truncated: String = SWAP_CUBE.lines().take(10).collect::<Vec<_>>().join("\n");
+    assert_eq!(
+        ColorLut::parse_cube(&truncated),
+        Err(LutError::EntryCount {
+            expected: 8,
+            found: 6
+        })
+    );
+
+    let late_keyword = format!("{}DOMAIN_MAX 1 1 1\n", SWAP_CUBE);
+    assert!(matches!(
+        ColorLut::parse_cube(&late_keyword),
+        Err(LutError::InvalidLine { line: 13, .. })
+    ));
+
+    let bad_entry = SWAP_CUBE.replace("0 1 1", "0 one 1");
+    assert!(matches!(
+        ColorLut::parse_cube(&bad_entry),
+        Err(LutError::InvalidLine { line: 8, .. })
+    ));
+}
+
+#[test]
+fn test_identity_lut_sampling() {
+    let lut = ColorLut::identity(17);
+    assert_eq!(lut.data.len(), 17 * 17 * 17);
+    for color in [
+        Vec3::ZERO,
+        Vec3::ONE,
+        Vec3::new(0.25, 0.5, 0.75),
+        Vec3::new(0.03, 0.97, 0.41),
+    ] {
+        assert_close(lut.sample(color), color);
+    }
+}
+
+#[test]
+fn test_color_lut_loader() {
+    let loader = ColorLutLoader;
+    assert_eq!(loader.extensions(), &["cube"]);
+    let lut = loader
+        .load(SWAP_CUBE.as_bytes(), Path::new("swap.cube"))
+        .unwrap();
+    assert_eq!(lut.size, 2);
+    assert!(loader
+        .load(b"LUT_3D_SIZE 2\n", Path::new("empty.cube"))
+        .is_err());
+}
+
+#[test]
+fn test_tone_curves() {
+    for curve in [Tonemapping::Aces, Tonemapping::AgX, Tonemapping::Reinhard] {
+        assert!(curve.apply(Vec3::ZERO).max_element() < 0.01, "{:?}", curve);
+
+        let mut previous = curve.apply(Vec3::ZERO);
+        for step in 1..=64 {
+            let value = 0.01 * 1.2f32.powi(step);
+            let mapped = curve.apply(Vec3::splat(value));
+            assert!(mapped.min_element() >= 0.0 && mapped.max_element() <= 1.0);
+            assert!(
+                mapped.x >= previous.x - 1e-6,
+                "{:?} decreases at {}",
+                curve,
+                value
+            );
+            previous = mapped;
+        }
+        // Bright highlights end up close to white
+        assert!(previous.min_element() > 0.8, "{:?}", curve);
+    }
+
+    assert_close(
+        Tonemapping::Reinhard.apply(Vec3::new(1.0, 3.0, 0.0)),
+        Vec3::new(0.5, 0.75, 0.0),
+    );
+}
+
+#[test]
+fn test_exposure_math() {
+    assert!(ev_from_luminance(MID_GRAY).abs() < 1e-6);
+    assert!((ev_from_luminance(MID_GRAY * 4.0) - 2.0).abs() < 1e-5);
+    assert!((exposure_from_ev(2.0) - 0.25).abs() < 1e-6);
+    // A scene exposed by its own exposure value averages mid gray
+    let scene = 2.7;
+    assert!((scene * exposure_from_ev(ev_from_luminance(scene)) - MID_GRAY).abs() < 1e-5);
+
+    let auto = AutoExposure::default();
+    assert_eq!(auto.target_ev(1e6), auto.max_ev);
+    assert_eq!(auto.target_ev(0.0), auto.min_ev);
+    let compensated = AutoExposure {
+        compensation: 1.0,
+        ..auto
+    };
+    assert!((compensated.exposure(0.0) - 2.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_exposure_adaptation() {
+    let auto = AutoExposure::default();
+    assert_eq!(auto.adapt(0.0, 4.0, 0.0), 0.0);
+
+    let mut ev = 0.0;
+    for _ in 0..120 {
+        let next = auto.adapt(ev, 4.0, 1.0 / 60.0);
+        assert!(next > ev && next < 4.0);
+        ev = next;
+    }
+    assert!((ev - 4.0).abs() < 0.5);
+
+    // Darkening adapts slower than brightening
+    let up = auto.adapt(0.0, 1.0, 0.1);
+    let down = 1.0 - auto.adapt(1.0, 0.0, 0.1);
+    assert!(up > down);
+}
+
+#[test]
+fn test_post_process_settings() {
+    let settings = PostProcessSettings::default();
+    assert!(matches!(
+        settings.effects[..],
+        [PostEffect::Tonemap(Tonemapping::Aces)]
+    ));
+    assert!(!settings.uses_depth());
+
+    let settings = PostProcessSettings::empty()
+        .with(PostEffect::ssao())
+        .with(PostEffect::bloom());
+    assert_eq!(settings.effects.len(), 2);
+    assert!(settings.uses_depth());
+}
+
+#[test]
+fn test_post_process_shader_is_valid() {
+    let composed = ShaderComposer::new()
+        .compose(
+            "post_process.wgsl",
+            include_str!("../shaders/post_process.wgsl"),
+            &ShaderDefs::new(),
+        )
+        .unwrap();
+    if let Err(error) = composed.validate() {
+        panic!("{}", error);
+    }
+}
@@ -112,6 +112,7 @@ proptest! {
             projection: Projection::Perspective { fov, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Generate projection matrix
@@ -176,6 +177,7 @@ proptest! {
             projection: Projection::Orthographic { size, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Generate projection matrix
@@ -253,6 +255,7 @@ proptest! {
             projection: Projection::Perspective { fov, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Create orthographic camera with same near/far
@@ -260,6 +263,7 @@ proptest! {
             projection: Projection::Orthographic { size, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Generate projection matrices
@@ -288,12 +292,14 @@ proptest! {
             projection: Projection::Perspective { fov: fov1, near: near1, far: far1 },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         let camera2 = Camera {
             projection: Projection::Perspective { fov: fov2, near: near1, far: far1 },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Generate projection matrices
@@ -336,6 +342,7 @@ proptest! {
             projection: Projection::Perspective { fov: fov1, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Get initial projection matrix
@@ -377,6 +384,7 @@ proptest! {
             projection: Projection::Perspective { fov, near: near1, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Get initial projection matrix
@@ -421,6 +429,7 @@ proptest! {
             projection: Projection::Perspective { fov, near, far: far1 },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Get initial projection matrix
@@ -465,6 +474,7 @@ proptest! {
             projection: Projection::Orthographic { size: size1, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Get initial projection matrix
@@ -508,6 +518,7 @@ proptest! {
             projection: Projection::Orthographic { size, near: near1, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Get initial projection matrix
@@ -552,6 +563,7 @@ proptest! {
             projection: Projection::Orthographic { size, near, far: far1 },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Get initial projection matrix
@@ -625,6 +637,7 @@ proptest! {
             projection: Projection::Perspective { fov, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Compute projection matrix with first aspect ratio (initial window size)
@@ -692,6 +705,7 @@ proptest! {
             projection: Projection::Orthographic { size, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Compute projection matrix with first aspect ratio (initial window size)
@@ -754,6 +768,7 @@ proptest! {
             projection: Projection::Perspective { fov, near, far },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Original aspect ratio
@@ -807,6 +822,7 @@ mod edge_cases {
             },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Test very wide aspect ratio
@@ -829,6 +845,7 @@ mod edge_cases {
             },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
         let matrix = narrow_fov_camera.projection_matrix(1.6);
         assert!(is_valid_projection_matrix(&matrix));
@@ -842,6 +859,7 @@ mod edge_cases {
             },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
         let matrix = wide_fov_camera.projection_matrix(1.6);
         assert!(is_valid_projection_matrix(&matrix));
@@ -858,6 +876,7 @@ mod edge_cases {
             },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
         let matrix = camera.projection_matrix(1.6);
         assert!(is_valid_projection_matrix(&matrix));
@@ -871,6 +890,7 @@ mod edge_cases {
             },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
         let matrix = camera.projection_matrix(1.6);
         assert!(is_valid_projection_matrix(&matrix));
@@ -886,6 +906,7 @@ mod edge_cases {
             },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Initial window size: 800x600 (aspect 1.333)
@@ -914,6 +935,7 @@ mod edge_cases {
             },
             clear_color: luminara_math::Color::BLACK,
             is_active: true,
+            ..Default::default()
         };
 
         // Square window (aspect 1.0)
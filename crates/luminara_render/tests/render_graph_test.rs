@@ -0,0 +1,328 @@
+use luminara_render::render_graph::slots;
+use luminara_render::{
+    PassSlots, RenderContext, RenderError, RenderGraph, RenderGraphError, RenderNode, ResourceDesc,
+    SlotSize,
+};
+
+/// Pass with fixed declarations that records nothing
+struct TestPass {
+    name: String,
+    slots: PassSlots,
+}
+
+impl RenderNode for TestPass {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn declare(&self, slots: &mut PassSlots) {
+        *slots = self.slots.clone();
+    }
+
+    fn run<'a>(&self, _context: &mut RenderContext<'a>) -> Result<(), RenderError> {
+        Ok(())
+    }
+}
+
+/// Node written before passes declared their slots
+struct LegacyPass;
+
+impl RenderNode for LegacyPass {
+    fn name(&self) -> &str {
+        "legacy"
+    }
+
+    fn run<'a>(&self, _context: &mut RenderContext<'a>) -> Result<(), RenderError> {
+        Ok(())
+    }
+}
+
+fn pass(name: &str, declare: impl FnOnce(&mut PassSlots)) -> TestPass {
+    let mut slots = PassSlots::default();
+    declare(&mut slots);
+    TestPass {
+        name: name.to_string(),
+        slots,
+    }
+}
+
+fn color() -> ResourceDesc {
+    ResourceDesc::viewport_texture(wgpu::TextureFormat::Rgba8Unorm)
+}
+
+#[test]
+fn test_builtin_graph_compiles() {
+    let compiled = RenderGraph::forward_3d().compile().unwrap();
+
+    assert_eq!(
+        compiled.order,
+        vec![
+            "shadow",
+            "shadow_atlas",
+            "forward",
+            "text3d",
+            "post_process",
+            "overlay"
+        ]
+    );
+    assert!(compiled.culled.is_empty());
+
+    // Both live from the forward pass through world-space text into
+    // post-processing, which reads depth for ambient occlusion
+    let lifetime = |name| {
+        let resource = compiled.resource(name).unwrap();
+        (
+            compiled.order[resource.first_use].as_str(),
+            compiled.order[resource.last_use].as_str(),
+        )
+    };
+    assert_eq!(lifetime(slots::HDR), ("forward", "post_process"));
+    assert_eq!(lifetime(slots::DEPTH), ("forward", "post_process"));
+    let depth = compiled.resource(slots::DEPTH).unwrap();
+    assert_eq!((depth.first_use, depth.last_use), (2, 4));
+    assert!(!compiled.is_aliased(slots::HDR, slots::DEPTH));
+    assert_eq!(compiled.physical.len(), 2);
+}
+
+#[test]
+fn test_custom_pass_is_ordered_by_resources() {
+    let mut graph = RenderGraph::forward_3d();
+    // Inserted before post-processing, which must then see its HDR output
+    assert!(graph.insert_node_before(
+        "post_process",
+        pass("outline", |s| {
+            s.read(slots::HDR).write(slots::HDR);
+        })
+    ));
+
+    let compiled = graph.compile().unwrap();
+    assert_eq!(
+        compiled.order,
+        vec![
+            "shadow",
+            "shadow_atlas",
+            "forward",
+            "text3d",
+            "outline",
+            "post_process",
+            "overlay"
+        ]
+    );
+
+    // Declared last, its output reaches nothing
+    let mut graph = RenderGraph::forward_3d();
+    graph.add_node(pass("outline", |s| {
+        s.read(slots::HDR).write(slots::HDR);
+    }));
+    let compiled = graph.compile().unwrap();
+    assert_eq!(compiled.culled, vec!["outline"]);
+}
+
+#[test]
+fn test_reads_follow_declaration_order() {
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("scene", |s| {
+        s.create("color", color());
+    }));
+    graph.add_node(pass("readback", |s| {
+        s.read("color").side_effects();
+    }));
+    // Overwrites what the readback saw, so it has to wait for it
+    graph.add_node(pass("grade", |s| {
+        s.read("color").write("color");
+    }));
+    graph.add_node(pass("present", |s| {
+        s.read("color").write(slots::SWAPCHAIN);
+    }));
+
+    let compiled = graph.compile().unwrap();
+    assert_eq!(compiled.order, vec!["scene", "readback", "grade", "present"]);
+
+    // Waiting for a reader doesn't keep that reader alive
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("scene", |s| {
+        s.create("color", color());
+    }));
+    graph.add_node(pass("unused_copy", |s| {
+        s.read("color").create("copy", color());
+    }));
+    graph.add_node(pass("grade", |s| {
+        s.read("color").write("color");
+    }));
+    graph.add_node(pass("present", |s| {
+        s.read("color").write(slots::SWAPCHAIN);
+    }));
+    let compiled = graph.compile().unwrap();
+    assert_eq!(compiled.order, vec!["scene", "grade", "present"]);
+    assert_eq!(compiled.culled, vec!["unused_copy"]);
+}
+
+#[test]
+fn test_insert_node_before() {
+    let mut graph = RenderGraph::forward_3d();
+    assert!(graph.insert_node_before(
+        "overlay",
+        pass("debug_text", |s| {
+            s.write(slots::SWAPCHAIN);
+        })
+    ));
+    assert!(!graph.insert_node_before("missing", pass("x", |_| {})));
+
+    let compiled = graph.compile().unwrap();
+    assert_eq!(
+        compiled.order,
+        vec![
+            "shadow",
+            "shadow_atlas",
+            "forward",
+            "text3d",
+            "post_process",
+            "debug_text",
+            "overlay"
+        ]
+    );
+}
+
+#[test]
+fn test_unused_passes_are_culled() {
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("scene", |s| {
+        s.create("scene_color", color()).write(slots::SWAPCHAIN);
+    }));
+    graph.add_node(pass("unused_blur", |s| {
+        s.read("scene_color").create("blurred", color());
+    }));
+    graph.add_node(pass("readback", |s| {
+        s.read("scene_color").side_effects();
+    }));
+    // Nodes without declarations keep running
+    graph.add_node(LegacyPass);
+
+    let compiled = graph.compile().unwrap();
+    assert_eq!(compiled.order, vec!["scene", "readback", "legacy"]);
+    assert_eq!(compiled.culled, vec!["unused_blur"]);
+    assert!(compiled.resource("blurred").is_none());
+}
+
+#[test]
+fn test_transient_lifetimes_and_aliasing() {
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("a", |s| {
+        s.create("first", color());
+    }));
+    graph.add_node(pass("b", |s| {
+        s.read("first").create("second", color());
+    }));
+    graph.add_node(pass("c", |s| {
+        s.read("second").create("third", color());
+    }));
+    graph.add_node(pass("d", |s| {
+        s.read("third")
+            .create(
+                "small",
+                ResourceDesc::texture(
+                    SlotSize::Viewport { scale: 0.5 },
+                    wgpu::TextureFormat::Rgba8Unorm,
+                ),
+            )
+            .write(slots::SWAPCHAIN);
+    }));
+
+    let compiled = graph.compile().unwrap();
+    assert_eq!(compiled.order, vec!["a", "b", "c", "d"]);
+
+    let first = compiled.resource("first").unwrap();
+    assert_eq!((first.first_use, first.last_use), (0, 1));
+    let third = compiled.resource("third").unwrap();
+    assert_eq!((third.first_use, third.last_use), (2, 3));
+
+    // "first" is dead by the time "third" is created; "second" overlaps both
+    assert!(compiled.is_aliased("first", "third"));
+    assert!(!compiled.is_aliased("first", "second"));
+    assert!(!compiled.is_aliased("second", "third"));
+    // Different size, different allocation
+    assert!(!compiled.is_aliased("first", "small"));
+    assert_eq!(compiled.physical.len(), 3);
+}
+
+#[test]
+fn test_compile_errors() {
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("tonemap", |s| {
+        s.read("hdr").write(slots::SWAPCHAIN);
+    }));
+    assert_eq!(
+        graph.compile().unwrap_err(),
+        RenderGraphError::MissingInput {
+            pass: "tonemap".to_string(),
+            resource: "hdr".to_string(),
+        }
+    );
+
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("scene", |s| {
+        s.write("hdr");
+    }));
+    assert!(matches!(
+        graph.compile(),
+        Err(RenderGraphError::UndeclaredResource { .. })
+    ));
+
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("a", |s| {
+        s.create("hdr", color());
+    }));
+    graph.add_node(pass("b", |s| {
+        s.create("hdr", color());
+    }));
+    assert_eq!(
+        graph.compile().unwrap_err(),
+        RenderGraphError::DuplicateResource {
+            resource: "hdr".to_string(),
+            first: "a".to_string(),
+            second: "b".to_string(),
+        }
+    );
+
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("a", |_| {}));
+    graph.add_node(pass("a", |_| {}));
+    assert_eq!(
+        graph.compile().unwrap_err(),
+        RenderGraphError::DuplicatePass("a".to_string())
+    );
+
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("a", |_| {}));
+    graph.add_edge("a", "b");
+    assert_eq!(
+        graph.compile().unwrap_err(),
+        RenderGraphError::UnknownPass("b".to_string())
+    );
+}
+
+#[test]
+fn test_cycles_are_reported() {
+    let mut graph = RenderGraph::forward_3d();
+    // Forward already precedes post-processing through the HDR slot
+    graph.add_edge("post_process", "forward");
+
+    match graph.compile() {
+        Err(RenderGraphError::Cycle(cycle)) => {
+            assert_eq!(cycle.len(), 2);
+            assert!(cycle.contains(&"forward".to_string()));
+            assert!(cycle.contains(&"post_process".to_string()));
+        }
+        other => panic!("expected a cycle, got {other:?}"),
+    }
+
+    let mut graph = RenderGraph::new();
+    graph.add_node(pass("a", |s| {
+        s.side_effects();
+    }));
+    graph.add_edge("a", "a");
+    assert_eq!(
+        graph.compile().unwrap_err(),
+        RenderGraphError::Cycle(vec!["a".to_string()])
+    );
+}
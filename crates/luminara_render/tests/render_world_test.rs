@@ -0,0 +1,517 @@
+use luminara_asset::{AssetId, AssetServer, Handle};
+use luminara_core::shared_types::{CoreStage, Query, Resource, World};
+use luminara_core::system::ExclusiveMarker;
+use luminara_core::Entity;
+use luminara_math::{Color, Mat4, Transform, Vec3};
+use luminara_render::command::{CommandBuffer, DrawCommand, GizmoType};
+use luminara_render::{
+    collect_camera_views, Camera, DirectionalLight, EnvironmentMapLight, ExtractedOverlay,
+    MainEntity, MaterialRegistry, Mesh, OverlayRenderer, PbrMaterial, PointLight, PostEffect,
+    PostProcessSettings, ReflectionProbe, RenderApp, RenderLayers, ShadowFilter, ShadowSettings,
+    Skybox, SpotLight, UnlitMaterial,
+};
+use std::sync::Mutex;
+use std::thread::ThreadId;
+
+fn pbr(albedo: Color) -> PbrMaterial {
+    PbrMaterial {
+        albedo,
+        albedo_texture: None,
+        normal_texture: None,
+        metallic: 0.0,
+        roughness: 0.5,
+        metallic_roughness_texture: None,
+        emissive: Color::BLACK,
+    }
+}
+
+/// A main world with a camera at z = 5 looking down -Z
+fn main_world() -> (World, Entity) {
+    let mut world = World::new();
+    world.insert_resource(AssetServer::new("assets"));
+    let mut materials = MaterialRegistry::default();
+    materials.register_component::<PbrMaterial>();
+    materials.register::<UnlitMaterial>();
+    world.insert_resource(materials);
+
+    let camera = world.spawn();
+    world
+        .add_component(
+            camera,
+            Camera {
+                clear_color: Color::rgb(0.2, 0.3, 0.4),
+                order: 3,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    world
+        .add_component(camera, Transform::from_xyz(0.0, 0.0, 5.0))
+        .unwrap();
+    world
+        .add_component(camera, RenderLayers::layer(0).with(2))
+        .unwrap();
+    (world, camera)
+}
+
+fn spawn_mesh(world: &mut World, transform: Transform) -> Entity {
+    let mesh = world
+        .get_resource::<AssetServer>()
+        .unwrap()
+        .add(Mesh::cube(1.0));
+    let entity = world.spawn();
+    world.add_component(entity, mesh).unwrap();
+    world.add_component(entity, transform).unwrap();
+    entity
+}
+
+/// Render entities copied from `entity`
+fn extracted(render: &World, entity: Entity) -> Vec<Entity> {
+    Query::<(Entity, &MainEntity)>::new(render)
+        .iter()
+        .filter(|(_, main)| main.0 == entity)
+        .map(|(extracted, _)| extracted)
+        .collect()
+}
+
+#[test]
+fn test_extracted_cameras_match_main_world() {
+    let (mut world, camera) = main_world();
+    let inactive = world.spawn();
+    world
+        .add_component(
+            inactive,
+            Camera {
+                is_active: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    world.add_component(inactive, Transform::IDENTITY).unwrap();
+    world
+        .add_component(
+            camera,
+            PostProcessSettings::empty().with(PostEffect::bloom()),
+        )
+        .unwrap();
+
+    let render = RenderApp::new().extract(&world);
+
+    let copies = extracted(&render, camera);
+    assert_eq!(copies.len(), 1);
+    let copy = render.get_component::<Camera>(copies[0]).unwrap();
+    assert_eq!(copy.clear_color, Color::rgb(0.2, 0.3, 0.4));
+    assert_eq!(copy.order, 3);
+    assert_eq!(
+        render.get_component::<Transform>(copies[0]),
+        world.get_component::<Transform>(camera)
+    );
+    assert_eq!(
+        render.get_component::<RenderLayers>(copies[0]),
+        Some(&RenderLayers::layer(0).with(2))
+    );
+    let settings = render
+        .get_component::<PostProcessSettings>(copies[0])
+        .unwrap();
+    assert!(matches!(settings.effects[..], [PostEffect::Bloom { .. }]));
+    assert!(extracted(&render, inactive).is_empty());
+
+    // Views of extracted cameras keep the main world identity
+    let views = collect_camera_views(&render, (640, 480));
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].entity, camera);
+    assert_eq!(
+        views[0].view_proj,
+        collect_camera_views(&world, (640, 480))[0].view_proj
+    );
+}
+
+#[test]
+fn test_extracted_lights_match_main_world() {
+    let (mut world, _) = main_world();
+    let directional = world.spawn();
+    world
+        .add_component(
+            directional,
+            DirectionalLight {
+                color: Color::rgb(1.0, 0.9, 0.8),
+                intensity: 2.0,
+                cast_shadows: false,
+                shadow_cascade_count: 4,
+            },
+        )
+        .unwrap();
+    world
+        .add_component(directional, Transform::IDENTITY)
+        .unwrap();
+
+    let point = world.spawn();
+    world
+        .add_component(
+            point,
+            PointLight {
+                color: Color::WHITE,
+                intensity: 5.0,
+                range: 10.0,
+                cast_shadows: false,
+            },
+        )
+        .unwrap();
+    world
+        .add_component(point, Transform::from_xyz(1.0, 2.0, 3.0))
+        .unwrap();
+
+    let spot = world.spawn();
+    world
+        .add_component(
+            spot,
+            SpotLight {
+                color: Color::WHITE,
+                intensity: 3.0,
+                range: 8.0,
+                inner_angle: 0.3,
+                outer_angle: 0.5,
+                cast_shadows: false,
+            },
+        )
+        .unwrap();
+    world
+        .add_component(spot, Transform::from_xyz(-1.0, 4.0, 0.0))
+        .unwrap();
+    let shadows = ShadowSettings {
+        filter: ShadowFilter::Hard,
+        ..Default::default()
+    };
+    world.add_component(spot, shadows).unwrap();
+
+    let render = RenderApp::new().extract(&world);
+
+    let copy = extracted(&render, directional)[0];
+    let light = render.get_component::<DirectionalLight>(copy).unwrap();
+    assert_eq!(light.color, Color::rgb(1.0, 0.9, 0.8));
+    assert_eq!(light.intensity, 2.0);
+
+    let copy = extracted(&render, point)[0];
+    assert_eq!(
+        render.get_component::<PointLight>(copy).unwrap().range,
+        10.0
+    );
+    assert_eq!(
+        render.get_component::<Transform>(copy),
+        Some(&Transform::from_xyz(1.0, 2.0, 3.0))
+    );
+
+    let copy = extracted(&render, spot)[0];
+    assert_eq!(
+        render.get_component::<SpotLight>(copy).unwrap().outer_angle,
+        0.5
+    );
+    assert_eq!(render.get_component::<ShadowSettings>(copy), Some(&shadows));
+    let copy = extracted(&render, point)[0];
+    assert!(render.get_component::<ShadowSettings>(copy).is_none());
+}
+
+#[test]
+fn test_extracted_environments_match_main_world() {
+    let (mut world, camera) = main_world();
+    let environment = Handle::new(AssetId::new(), 0);
+    world
+        .add_component(
+            camera,
+            EnvironmentMapLight {
+                intensity: 0.5,
+                ..EnvironmentMapLight::new(environment.clone())
+            },
+        )
+        .unwrap();
+    world
+        .add_component(camera, Skybox::new(environment.clone()))
+        .unwrap();
+    let probe = world.spawn();
+    world
+        .add_component(probe, ReflectionProbe::new(environment, Vec3::splat(3.0)))
+        .unwrap();
+    world
+        .add_component(probe, Transform::from_xyz(0.0, 1.0, 0.0))
+        .unwrap();
+
+    let render = RenderApp::new().extract(&world);
+
+    let copy = extracted(&render, camera)[0];
+    assert_eq!(
+        render
+            .get_component::<EnvironmentMapLight>(copy)
+            .unwrap()
+            .intensity,
+        0.5
+    );
+    assert!(render.get_component::<Skybox>(copy).is_some());
+
+    let copy = extracted(&render, probe)[0];
+    assert_eq!(
+        render
+            .get_component::<ReflectionProbe>(copy)
+            .unwrap()
+            .half_extents,
+        Vec3::splat(3.0)
+    );
+    assert_eq!(
+        render.get_component::<Transform>(copy),
+        Some(&Transform::from_xyz(0.0, 1.0, 0.0))
+    );
+}
+
+#[test]
+fn test_extracted_renderables_match_main_world() {
+    let (mut world, _) = main_world();
+    let component = spawn_mesh(&mut world, Transform::from_xyz(-1.0, 0.0, 0.0));
+    world
+        .add_component(component, pbr(Color::rgb(0.8, 0.1, 0.1)))
+        .unwrap();
+    world
+        .add_component(component, RenderLayers::layer(2))
+        .unwrap();
+
+    let asset = spawn_mesh(&mut world, Transform::from_xyz(1.0, 0.0, 0.0));
+    let material: Handle<UnlitMaterial> = world
+        .get_resource::<AssetServer>()
+        .unwrap()
+        .add(UnlitMaterial::default());
+    world.add_component(asset, material.clone()).unwrap();
+
+    let render = RenderApp::new().extract(&world);
+
+    let copy = extracted(&render, component)[0];
+    assert_eq!(
+        render.get_component::<Handle<Mesh>>(copy).unwrap().id(),
+        world.get_component::<Handle<Mesh>>(component).unwrap().id()
+    );
+    assert_eq!(
+        render.get_component::<PbrMaterial>(copy).unwrap().albedo,
+        Color::rgb(0.8, 0.1, 0.1)
+    );
+    assert_eq!(
+        render.get_component::<RenderLayers>(copy),
+        Some(&RenderLayers::layer(2))
+    );
+
+    let copy = extracted(&render, asset)[0];
+    assert_eq!(
+        render
+            .get_component::<Handle<UnlitMaterial>>(copy)
+            .unwrap()
+            .id(),
+        material.id()
+    );
+    assert_eq!(
+        render.get_component::<Transform>(copy),
+        Some(&Transform::from_xyz(1.0, 0.0, 0.0))
+    );
+
+    // The render world draws the same meshes as the main world
+    let registry = world.get_resource::<MaterialRegistry>().unwrap();
+    let asset_server = world.get_resource::<AssetServer>().unwrap();
+    let mut main_draws: Vec<_> = registry
+        .collect(&world, &asset_server)
+        .into_iter()
+        .map(|draw| {
+            (
+                format!("{:?}", draw.mesh.id()),
+                draw.pipeline_id(),
+                draw.uniform,
+            )
+        })
+        .collect();
+    let mut render_draws: Vec<_> = registry
+        .collect(&render, &asset_server)
+        .into_iter()
+        .map(|draw| {
+            (
+                format!("{:?}", draw.mesh.id()),
+                draw.pipeline_id(),
+                draw.uniform,
+            )
+        })
+        .collect();
+    main_draws.sort();
+    render_draws.sort();
+    assert_eq!(main_draws, render_draws);
+}
+
+#[test]
+fn test_extraction_skips_renderables_outside_views() {
+    let (mut world, _) = main_world();
+    let visible = spawn_mesh(&mut world, Transform::IDENTITY);
+    world.add_component(visible, pbr(Color::WHITE)).unwrap();
+    let behind = spawn_mesh(&mut world, Transform::from_xyz(0.0, 0.0, 50.0));
+    world.add_component(behind, pbr(Color::WHITE)).unwrap();
+
+    let render_app = RenderApp::new();
+    let render = render_app.extract(&world);
+    assert_eq!(extracted(&render, visible).len(), 1);
+    assert!(extracted(&render, behind).is_empty());
+
+    // Off-screen meshes may still cast shadows into the view
+    let sun = world.spawn();
+    world
+        .add_component(
+            sun,
+            DirectionalLight {
+                color: Color::WHITE,
+                intensity: 1.0,
+                cast_shadows: true,
+                shadow_cascade_count: 4,
+            },
+        )
+        .unwrap();
+    world.add_component(sun, Transform::IDENTITY).unwrap();
+    let render = render_app.extract(&world);
+    assert_eq!(extracted(&render, behind).len(), 1);
+}
+
+#[test]
+fn test_extracted_commands() {
+    let (world, _) = main_world();
+    let mut commands = CommandBuffer::default();
+    commands.push(DrawCommand::DrawGizmo {
+        gizmo: GizmoType::Sphere { radius: 2.0 },
+        transform: Mat4::IDENTITY,
+        color: Color::WHITE,
+    });
+    let mut world = world;
+    world.insert_resource(commands);
+    let mut overlay = OverlayRenderer::new();
+    overlay.draw_rect(0.0, 0.0, 10.0, 10.0, [1.0; 4]);
+    world.insert_resource(overlay);
+
+    let render = RenderApp::new().extract(&world);
+
+    let gizmos = render.get_resource::<CommandBuffer>().unwrap();
+    assert!(matches!(
+        gizmos.commands.as_slice(),
+        [DrawCommand::DrawGizmo {
+            gizmo: GizmoType::Sphere { radius },
+            ..
+        }] if *radius == 2.0
+    ));
+    // Overlay commands move to the frame they were queued for
+    assert_eq!(
+        render
+            .get_resource::<ExtractedOverlay>()
+            .unwrap()
+            .commands
+            .len(),
+        1
+    );
+    assert!(world
+        .get_resource::<OverlayRenderer>()
+        .unwrap()
+        .commands
+        .is_empty());
+}
+
+#[test]
+fn test_render_resources_are_shared() {
+    let (mut world, _) = main_world();
+    world.insert_resource(FrameNumber(1));
+    let mut render_app = RenderApp::new();
+    render_app.share_resource::<FrameNumber>();
+    let render = render_app.extract(&world);
+
+    render.get_resource_mut::<FrameNumber>().unwrap().0 = 2;
+    assert_eq!(world.get_resource::<FrameNumber>().unwrap().0, 2);
+    assert!(render.get_resource::<MaterialRegistry>().is_some());
+    assert!(render.get_resource::<AssetServer>().is_some());
+}
+
+struct FrameNumber(u64);
+
+impl Resource for FrameNumber {}
+
+/// Frames drawn by the render schedule, with the thread that drew them
+#[derive(Default)]
+struct Rendered(Mutex<Vec<(u64, ThreadId)>>);
+
+impl Resource for Rendered {}
+
+fn record_frame(world: &mut World) {
+    let frame = world.get_resource::<FrameNumber>().unwrap().0;
+    let rendered = world.get_resource::<Rendered>().unwrap();
+    rendered
+        .0
+        .lock()
+        .unwrap()
+        .push((frame, std::thread::current().id()));
+}
+
+fn extract_frame_number(main: &World, render: &mut World) {
+    let frame = main.get_resource::<FrameNumber>().unwrap().0;
+    render.insert_resource(FrameNumber(frame));
+}
+
+fn recording_app() -> RenderApp {
+    let mut render_app = RenderApp::new();
+    render_app
+        .share_resource::<Rendered>()
+        .add_extract(extract_frame_number)
+        .add_system::<ExclusiveMarker>(CoreStage::Render, record_frame);
+    render_app
+}
+
+fn rendered(world: &World) -> Vec<(u64, ThreadId)> {
+    world
+        .get_resource::<Rendered>()
+        .unwrap()
+        .0
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn test_synchronous_rendering() {
+    let (mut world, _) = main_world();
+    world.insert_resource(Rendered::default());
+    let mut render_app = recording_app();
+
+    for frame in 0..3 {
+        world.insert_resource(FrameNumber(frame));
+        let render = render_app.extract(&world);
+        render_app.render(render, 1);
+        // Each frame is drawn before the next one is simulated
+        assert_eq!(rendered(&world).len() as u64, frame + 1);
+    }
+    assert!(!render_app.is_pipelined());
+    let main_thread = std::thread::current().id();
+    assert!(rendered(&world)
+        .iter()
+        .all(|(_, thread)| *thread == main_thread));
+}
+
+#[test]
+fn test_pipelined_rendering() {
+    let (mut world, _) = main_world();
+    world.insert_resource(Rendered::default());
+    let mut render_app = recording_app();
+
+    for frame in 0..8 {
+        world.insert_resource(FrameNumber(frame));
+        let render = render_app.extract(&world);
+        render_app.render(render, 3);
+    }
+    assert!(render_app.is_pipelined());
+    render_app.finish();
+    assert!(!render_app.is_pipelined());
+
+    let frames = rendered(&world);
+    let order: Vec<u64> = frames.iter().map(|(frame, _)| *frame).collect();
+    assert_eq!(order, (0..8).collect::<Vec<_>>());
+    let main_thread = std::thread::current().id();
+    assert!(frames.iter().all(|(_, thread)| *thread != main_thread));
+
+    // Going back to synchronous rendering keeps the render systems
+    world.insert_resource(FrameNumber(8));
+    let render = render_app.extract(&world);
+    render_app.render(render, 1);
+    assert_eq!(rendered(&world).last().unwrap(), &(8, main_thread));
+}
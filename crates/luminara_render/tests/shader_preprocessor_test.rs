@@ -0,0 +1,336 @@
+use luminara_asset::{AssetId, AssetServer, Handle};
+use luminara_math::Color;
+use luminara_render::{
+    import_path, material_prelude, pbr_shader, register_builtin_shaders, unlit_shader, Material,
+    PbrMaterial, PipelineCache, PipelineDependencies, Shader, ShaderComposer, ShaderDefs,
+    ShaderDependency, ShaderProcessError, ShaderSourceLocation, TerrainMaterial, UnlitMaterial,
+};
+
+fn compose(source: &str, defs: &ShaderDefs) -> String {
+    ShaderComposer::empty()
+        .compose("test.wgsl", source, defs)
+        .unwrap()
+        .source
+}
+
+fn location(file: &str, line: usize) -> ShaderSourceLocation {
+    ShaderSourceLocation {
+        file: file.to_string(),
+        line,
+    }
+}
+
+fn location_of(line: usize) -> ShaderSourceLocation {
+    location("test.wgsl", line)
+}
+
+#[test]
+fn test_conditionals() {
+    let source = "a\n#ifdef SKINNED\nb\n#ifndef SHADOWS\nc\n#else\nd\n#endif\n#else\ne\n#endif\nf";
+
+    assert_eq!(compose(source, &ShaderDefs::new()), "a\ne\nf\n");
+    assert_eq!(
+        compose(source, &ShaderDefs::new().with("SKINNED")),
+        "a\nb\nc\nf\n"
+    );
+    assert_eq!(
+        compose(source, &ShaderDefs::new().with("SKINNED").with("SHADOWS")),
+        "a\nb\nd\nf\n"
+    );
+    // The nested #else stays inactive while its parent is skipped
+    assert_eq!(
+        compose(source, &ShaderDefs::new().with("SHADOWS")),
+        "a\ne\nf\n"
+    );
+
+    let composer = ShaderComposer::empty();
+    assert_eq!(
+        composer
+            .compose("test.wgsl", "#ifdef A\na", &ShaderDefs::new())
+            .unwrap_err(),
+        ShaderProcessError::UnterminatedConditional {
+            file: "test.wgsl".to_string()
+        }
+    );
+    assert!(matches!(
+        composer.compose("test.wgsl", "a\n#endif", &ShaderDefs::new()),
+        Err(ShaderProcessError::Directive { location, .. }) if location == location_of(2)
+    ));
+    assert!(composer
+        .compose("test.wgsl", "#pragma once", &ShaderDefs::new())
+        .is_err());
+}
+
+#[test]
+fn test_defines_and_substitution() {
+    let source = "#define MAX_LIGHTS 8\n#define SHADOWS\nconst N: u32 = #{MAX_LIGHTS}u;\n#ifdef SHADOWS\ns\n#endif\n#undef SHADOWS\n#ifdef SHADOWS\nt\n#endif";
+    assert_eq!(
+        compose(source, &ShaderDefs::new()),
+        "const N: u32 = 8u;\ns\n"
+    );
+
+    let defs = ShaderDefs::new().with_value("SAMPLES", 4);
+    assert_eq!(compose("let n = #{SAMPLES};", &defs), "let n = 4;\n");
+    // Defines in a skipped branch have no effect
+    assert_eq!(
+        compose("#ifdef NO\n#define SAMPLES 1\n#endif\n#{SAMPLES}", &defs),
+        "4\n"
+    );
+
+    assert_eq!(
+        ShaderComposer::empty()
+            .compose("test.wgsl", "a\nlet n = #{MISSING};", &defs)
+            .unwrap_err(),
+        ShaderProcessError::UndefinedValue {
+            name: "MISSING".to_string(),
+            location: location_of(2),
+        }
+    );
+}
+
+#[test]
+fn test_imports() {
+    let mut composer = ShaderComposer::empty();
+    composer.add_module(
+        "util::math",
+        "#define_import_path util::math\nfn square() {}",
+    );
+    composer.add_module(
+        "util::color",
+        "#define_import_path util::color\n#import util::math\nfn luminance() {}",
+    );
+
+    let composed = composer
+        .compose(
+            "main.wgsl",
+            "#import util::color\n#import util::math\nfn main() {}",
+            &ShaderDefs::new(),
+        )
+        .unwrap();
+    // util::math is included once, before the module that needs it
+    assert_eq!(
+        composed.source,
+        "fn square() {}\nfn luminance() {}\nfn main() {}\n"
+    );
+    assert_eq!(composed.imports, vec!["util::color", "util::math"]);
+    assert_eq!(
+        composed.source_map,
+        vec![
+            location("util::math", 2),
+            location("util::color", 3),
+            location("main.wgsl", 3),
+        ]
+    );
+
+    assert_eq!(
+        composer
+            .compose("main.wgsl", "\n#import util::missing", &ShaderDefs::new())
+            .unwrap_err(),
+        ShaderProcessError::UnknownImport {
+            module: "util::missing".to_string(),
+            location: location("main.wgsl", 2),
+        }
+    );
+    let error = composer
+        .compose("main.wgsl", "#import util::missing", &ShaderDefs::new())
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "main.wgsl:1: unknown shader module 'util::missing'"
+    );
+
+    composer.add_module("a", "#import b");
+    composer.add_module("b", "#import a");
+    let error = composer
+        .compose("main.wgsl", "#import a", &ShaderDefs::new())
+        .unwrap_err();
+    assert_eq!(
+        error,
+        ShaderProcessError::ImportCycle(vec!["a".into(), "b".into(), "a".into()])
+    );
+    assert_eq!(error.to_string(), "import cycle: a -> b -> a");
+
+    assert!(ShaderComposer::new().has_module("luminara::lighting"));
+    assert_eq!(
+        import_path("// lighting\n#define_import_path my::lighting\n"),
+        Some("my::lighting".to_string())
+    );
+    assert_eq!(import_path("fn main() {}"), None);
+}
+
+#[test]
+fn test_validation_errors_are_source_mapped() {
+    let mut composer = ShaderComposer::empty();
+    composer.add_module(
+        "broken",
+        "fn ok() -> f32 {\n    return 1.0;\n}\nfn broken() -> f32 {\n    return missing;\n}",
+    );
+    let mut composed = composer
+        .compose(
+            "main.wgsl",
+            "#import broken\nfn main() {}",
+            &ShaderDefs::new(),
+        )
+        .unwrap();
+    composed.prepend("prelude", "const A: f32 = 1.0;\nconst B: f32 = 2.0;");
+    assert_eq!(composed.locate(1), Some(&location("prelude", 1)));
+    assert_eq!(composed.locate(3), Some(&location("broken", 1)));
+    assert_eq!(composed.locate(0), None);
+
+    match composed.validate() {
+        Err(ShaderProcessError::Invalid { message, location }) => {
+            assert!(message.contains("missing"), "{}", message);
+            assert_eq!(location, Some(self::location("broken", 5)));
+        }
+        other => panic!("expected a validation error, got {:?}", other.map(|_| ())),
+    }
+
+    let type_error = ShaderComposer::empty()
+        .compose(
+            "main.wgsl",
+            "fn f() -> f32 {\n    return 1u;\n}",
+            &ShaderDefs::new(),
+        )
+        .unwrap();
+    assert!(type_error.validate().is_err());
+}
+
+#[test]
+fn test_permutation_labels() {
+    let defs = ShaderDefs::new()
+        .with("SKINNED")
+        .with_value("MAX_LIGHTS", 16)
+        .with("NORMAL_MAP");
+    assert_eq!(defs.key(), "MAX_LIGHTS=16,NORMAL_MAP,SKINNED");
+    assert_eq!(
+        PipelineCache::permutation_label("pbr", &defs),
+        "pbr[MAX_LIGHTS=16,NORMAL_MAP,SKINNED]"
+    );
+    assert_eq!(
+        PipelineCache::permutation_label("pbr", &ShaderDefs::new()),
+        "pbr"
+    );
+
+    let mut material = PbrMaterial {
+        albedo: Color::WHITE,
+        albedo_texture: None,
+        normal_texture: None,
+        metallic: 0.0,
+        roughness: 0.5,
+        metallic_roughness_texture: None,
+        emissive: Color::BLACK,
+    };
+    assert!(material.shader_defs().is_empty());
+    material.normal_texture = Some(Handle::new(AssetId::from_path("normal.png"), 0));
+    assert!(material.shader_defs().contains("NORMAL_MAP"));
+}
+
+#[test]
+fn test_invalidation_is_per_dependency() {
+    let pbr = ShaderDependency::Asset(pbr_shader().id());
+    let lighting = ShaderDependency::Module("luminara::lighting".to_string());
+
+    let mut dependencies = PipelineDependencies::default();
+    dependencies.insert("pbr", [pbr.clone(), lighting.clone()]);
+    dependencies.insert("pbr[NORMAL_MAP]", [pbr.clone(), lighting.clone()]);
+    dependencies.insert("unlit", [ShaderDependency::Asset(unlit_shader().id())]);
+    assert_eq!(
+        dependencies.affected(&lighting),
+        vec!["pbr", "pbr[NORMAL_MAP]"]
+    );
+    dependencies.remove("pbr");
+    assert_eq!(dependencies.affected(&pbr), vec!["pbr[NORMAL_MAP]"]);
+
+    let mut cache = PipelineCache::new();
+    cache.mark_failed(
+        "pbr[NORMAL_MAP]".to_string(),
+        [pbr.clone(), lighting.clone()],
+    );
+    cache.mark_failed(
+        "unlit".to_string(),
+        [ShaderDependency::Asset(unlit_shader().id())],
+    );
+    assert!(cache.is_failed("pbr[NORMAL_MAP]"));
+    assert_eq!(cache.invalidate(&lighting), vec!["pbr[NORMAL_MAP]"]);
+    assert!(!cache.is_failed("pbr[NORMAL_MAP]"));
+    assert!(cache.is_failed("unlit"));
+    assert!(cache.invalidate(&lighting).is_empty());
+}
+
+#[test]
+fn test_sync_reports_reloaded_shaders() {
+    let asset_server = AssetServer::new("assets");
+    let id = AssetId::from_path("shaders/noise.wgsl");
+    let handle: Handle<Shader> = asset_server.insert(
+        id,
+        Shader::from_wgsl("#define_import_path my::noise\nfn noise() -> f32 { return 0.0; }"),
+    );
+
+    let mut composer = ShaderComposer::new();
+    composer.watch(&asset_server, &handle);
+    assert!(composer.has_module("my::noise"));
+    assert!(composer.sync(&asset_server).is_empty());
+
+    asset_server.insert(
+        id,
+        Shader::from_wgsl("#define_import_path my::noise\nfn noise() -> f32 { return 1.0; }"),
+    );
+    assert_eq!(
+        composer.sync(&asset_server),
+        vec![
+            ShaderDependency::Asset(id),
+            ShaderDependency::Module("my::noise".to_string()),
+        ]
+    );
+    let composed = composer
+        .compose("main.wgsl", "#import my::noise", &ShaderDefs::new())
+        .unwrap();
+    assert!(composed.source.contains("return 1.0;"));
+    assert!(composer.sync(&asset_server).is_empty());
+}
+
+/// Compose and validate a material's shader the way the forward pass does
+fn validate_material<M: Material>(asset_server: &AssetServer, material: &M) {
+    let source = asset_server
+        .get(&material.shader())
+        .unwrap()
+        .source_code()
+        .unwrap();
+    let defs = material.shader_defs();
+    let mut composed = ShaderComposer::new()
+        .compose("material", &source, &defs)
+        .unwrap();
+    let textures: Vec<String> = material.textures().into_iter().map(|t| t.name).collect();
+    composed.prepend(
+        "material prelude",
+        &material_prelude(
+            &material.uniform_layout(),
+            &textures,
+            &material.pipeline_key(),
+        ),
+    );
+    if let Err(error) = composed.validate() {
+        panic!("{} with [{}]: {}", M::type_name(), defs.key(), error);
+    }
+}
+
+#[test]
+fn test_builtin_shaders_validate() {
+    let asset_server = AssetServer::new("assets");
+    register_builtin_shaders(&asset_server);
+
+    let mut pbr = PbrMaterial {
+        albedo: Color::WHITE,
+        albedo_texture: None,
+        normal_texture: None,
+        metallic: 0.0,
+        roughness: 0.5,
+        metallic_roughness_texture: None,
+        emissive: Color::BLACK,
+    };
+    validate_material(&asset_server, &pbr);
+    pbr.normal_texture = Some(Handle::new(AssetId::from_path("normal.png"), 0));
+    validate_material(&asset_server, &pbr);
+    validate_material(&asset_server, &UnlitMaterial::default());
+    validate_material(&asset_server, &TerrainMaterial::default());
+}
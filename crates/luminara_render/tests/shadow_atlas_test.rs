@@ -0,0 +1,361 @@
+use luminara_core::shared_types::World;
+use luminara_core::Entity;
+use luminara_math::{Color, Mat4, Quat, Transform, Vec3};
+use luminara_render::{
+    collect_shadow_requests, point_light_face, point_light_view_projs, screen_coverage,
+    spot_light_view_proj, PointLight, ShaderComposer, ShaderDefs, ShadowAtlas,
+    ShadowAtlasAllocator, ShadowFilter, ShadowLightKind, ShadowRequest, ShadowSettings, ShadowTile,
+    SpotLight, CUBE_FACES,
+};
+use std::f32::consts::FRAC_PI_4;
+
+fn entities(count: usize) -> Vec<Entity> {
+    let mut world = World::new();
+    (0..count).map(|_| world.spawn()).collect()
+}
+
+fn point(light: Entity, importance: f32) -> ShadowRequest {
+    ShadowRequest {
+        light,
+        kind: ShadowLightKind::Point {
+            position: Vec3::ZERO,
+            range: 10.0,
+        },
+        settings: ShadowSettings::default(),
+        importance,
+    }
+}
+
+fn spot(light: Entity, importance: f32) -> ShadowRequest {
+    ShadowRequest {
+        light,
+        kind: ShadowLightKind::Spot {
+            position: Vec3::ZERO,
+            direction: Vec3::NEG_Z,
+            outer_angle: FRAC_PI_4,
+            range: 10.0,
+        },
+        settings: ShadowSettings::default(),
+        importance,
+    }
+}
+
+fn assert_disjoint(tiles: &[ShadowTile], atlas_size: u32) {
+    for (i, a) in tiles.iter().enumerate() {
+        assert!(a.x + a.size <= atlas_size && a.y + a.size <= atlas_size);
+        for b in &tiles[i + 1..] {
+            assert!(!a.overlaps(b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+}
+
+/// Normalized device coordinates of `point`
+fn project(view_proj: &Mat4, point: Vec3) -> Vec3 {
+    view_proj.project_point3(point)
+}
+
+#[test]
+fn test_allocator_packs_power_of_two_tiles() {
+    let mut allocator = ShadowAtlasAllocator::new(1024);
+    let big = allocator.allocate(512).unwrap();
+    assert_eq!(
+        big,
+        ShadowTile {
+            x: 0,
+            y: 0,
+            size: 512
+        }
+    );
+    // Requests round up to a power of two
+    let small = allocator.allocate(100).unwrap();
+    assert_eq!(small.size, 128);
+
+    let mut tiles = vec![big, small];
+    while let Some(tile) = allocator.allocate(128) {
+        tiles.push(tile);
+    }
+    // 512² + 48 tiles of 128² fill the atlas exactly
+    assert_eq!(tiles.len(), 2 + 47);
+    assert_eq!(allocator.free_area(), 0);
+    assert_disjoint(&tiles, 1024);
+
+    allocator.free(small);
+    assert_eq!(allocator.allocate(128), Some(small));
+    assert_eq!(allocator.allocate(2048), None);
+}
+
+#[test]
+fn test_tile_size_follows_importance() {
+    let atlas = ShadowAtlas::default();
+    assert_eq!(atlas.tile_size(1.0), 1024);
+    assert_eq!(atlas.tile_size(0.5), 512);
+    assert_eq!(atlas.tile_size(0.3), 256);
+    assert_eq!(atlas.tile_size(0.0), 64);
+    assert_eq!(atlas.tile_size(4.0), 1024);
+}
+
+#[test]
+fn test_atlas_allocates_by_importance() {
+    let lights = entities(4);
+    let mut atlas = ShadowAtlas::default();
+    atlas.allocate(&[
+        spot(lights[0], 0.1),
+        point(lights[1], 1.0),
+        spot(lights[2], 0.0),
+        point(lights[3], 0.5),
+    ]);
+
+    // Lights off screen get no shadows, the others come most important first
+    assert_eq!(atlas.allocations.len(), 3);
+    assert_eq!(atlas.light_index(lights[1]), Some(0));
+    assert_eq!(atlas.light_index(lights[3]), Some(1));
+    assert_eq!(atlas.light_index(lights[0]), Some(2));
+    assert_eq!(atlas.light_index(lights[2]), None);
+
+    let near = atlas.allocation(lights[1]).unwrap();
+    assert_eq!(near.views.len(), 6);
+    assert!(near.views.iter().all(|view| view.tile.size == 1024));
+    assert_eq!(atlas.allocation(lights[3]).unwrap().tile_size(), 512);
+    let far = atlas.allocation(lights[0]).unwrap();
+    assert_eq!(far.views.len(), 1);
+    assert_eq!(far.tile_size(), 64);
+
+    let tiles: Vec<ShadowTile> = atlas.views().map(|view| view.tile).collect();
+    assert_eq!(tiles.len(), 13);
+    assert_disjoint(&tiles, atlas.size);
+}
+
+#[test]
+fn test_full_atlas_shrinks_then_drops_lights() {
+    let lights = entities(4);
+    let mut atlas = ShadowAtlas {
+        size: 1024,
+        max_tile_size: 512,
+        min_tile_size: 256,
+        ..Default::default()
+    };
+    // Six 512² faces do not fit in a 1024² atlas, so the first light falls
+    // back to 256², leaving room for ten more faces of that size
+    atlas.allocate(&[
+        point(lights[0], 1.0),
+        spot(lights[1], 0.9),
+        point(lights[2], 0.8),
+        point(lights[3], 0.7),
+    ]);
+    assert_eq!(atlas.allocation(lights[0]).unwrap().tile_size(), 256);
+    assert_eq!(atlas.allocation(lights[1]).unwrap().tile_size(), 256);
+    assert_eq!(atlas.allocation(lights[2]).unwrap().tile_size(), 256);
+    assert!(atlas.allocation(lights[3]).is_none());
+
+    let tiles: Vec<ShadowTile> = atlas.views().map(|view| view.tile).collect();
+    assert_disjoint(&tiles, atlas.size);
+}
+
+#[test]
+fn test_screen_coverage() {
+    let view = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+    let camera = Vec3::ZERO;
+
+    // tan(45°) = 1, so a sphere's radius over its distance
+    let coverage = screen_coverage(&view, camera, Vec3::new(0.0, 0.0, -20.0), 2.0);
+    assert!((coverage - 0.1).abs() < 1e-4, "{}", coverage);
+    let further = screen_coverage(&view, camera, Vec3::new(0.0, 0.0, -40.0), 2.0);
+    assert!(further < coverage);
+
+    assert_eq!(
+        screen_coverage(&view, camera, Vec3::new(0.0, 0.0, 20.0), 2.0),
+        0.0
+    );
+    assert_eq!(
+        screen_coverage(&view, camera, Vec3::new(1.0, 0.0, 0.0), 2.0),
+        1.0
+    );
+    let close = screen_coverage(&view, camera, Vec3::new(0.0, 0.0, -3.0), 2.5);
+    assert!((close - 2.5 / 3.0).abs() < 1e-4, "{}", close);
+}
+
+#[test]
+fn test_spot_light_bounds_enclose_cone() {
+    for angle in [0.2f32, FRAC_PI_4, 1.2] {
+        let kind = ShadowLightKind::Spot {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            direction: Vec3::NEG_Z,
+            outer_angle: angle,
+            range: 10.0,
+        };
+        let (center, radius) = kind.bounding_sphere();
+        let rim = Vec3::new(angle.sin(), 0.0, -angle.cos()) * 10.0;
+        for point in [Vec3::ZERO, Vec3::new(0.0, 0.0, -10.0), rim] {
+            let point = point + Vec3::new(1.0, 2.0, 3.0);
+            assert!(center.distance(point) <= radius + 1e-4, "{}", angle);
+        }
+        // Tighter than the sphere of the whole range
+        assert!(radius < 10.0);
+    }
+}
+
+#[test]
+fn test_point_light_faces() {
+    let position = Vec3::new(2.0, -1.0, 4.0);
+    let faces = point_light_view_projs(position, 0.1, 20.0);
+
+    for (index, (forward, _)) in CUBE_FACES.iter().enumerate() {
+        assert_eq!(point_light_face(*forward), index);
+        let center = project(&faces[index], position + *forward * 5.0);
+        assert!(center.truncate().length() < 1e-4, "{:?}", center);
+        assert!(center.z > 0.0 && center.z < 1.0);
+    }
+
+    // Every direction lands inside the face picked for it
+    for direction in [
+        Vec3::new(0.3, 0.9, -0.2),
+        Vec3::new(-0.7, 0.1, 0.69),
+        Vec3::new(0.5, -0.5, -0.51),
+        Vec3::new(1.0, 1.0, 1.0),
+    ] {
+        let face = point_light_face(direction);
+        let ndc = project(&faces[face], position + direction.normalize() * 10.0);
+        assert!(
+            ndc.x.abs() <= 1.0 + 1e-4 && ndc.y.abs() <= 1.0 + 1e-4,
+            "{:?}",
+            ndc
+        );
+    }
+}
+
+#[test]
+fn test_spot_light_view_proj() {
+    let position = Vec3::new(0.0, 5.0, 0.0);
+    let view_proj = spot_light_view_proj(position, Vec3::NEG_Y, 0.5, 0.1, 10.0);
+
+    let center = project(&view_proj, Vec3::ZERO);
+    assert!(center.truncate().length() < 1e-4);
+    // The rim of the cone is the edge of the view
+    let rim = project(&view_proj, Vec3::new(5.0 * 0.5f32.tan(), 0.0, 0.0));
+    assert!(
+        (rim.x.abs().max(rim.y.abs()) - 1.0).abs() < 1e-4,
+        "{:?}",
+        rim
+    );
+    let outside = project(&view_proj, Vec3::new(5.0, 4.0, 0.0));
+    assert!(outside.x.abs().max(outside.y.abs()) > 1.0);
+
+    let kind = ShadowLightKind::spot(
+        &SpotLight {
+            color: Color::WHITE,
+            intensity: 1.0,
+            range: 10.0,
+            inner_angle: 0.3,
+            outer_angle: 0.5,
+            cast_shadows: true,
+        },
+        &Transform {
+            rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
+            ..Transform::from_xyz(0.0, 5.0, 0.0)
+        },
+    );
+    let matrices = kind.view_projs(0.1);
+    assert_eq!(matrices.len(), 1);
+    assert!(matrices[0].abs_diff_eq(view_proj, 1e-4));
+}
+
+#[test]
+fn test_gpu_data_layout() {
+    let lights = entities(2);
+    let mut atlas = ShadowAtlas::default();
+    let mut soft = spot(lights[1], 0.5);
+    soft.settings.filter = ShadowFilter::Pcss {
+        light_size: 0.5,
+        search_radius: 3,
+        filter_radius: 2,
+    };
+    soft.settings.depth_bias = 0.1;
+    atlas.allocate(&[point(lights[0], 1.0), soft]);
+
+    let (gpu_lights, gpu_views) = atlas.gpu_data();
+    assert_eq!(gpu_lights.len(), 2);
+    assert_eq!(gpu_views.len(), 7);
+    assert_eq!((gpu_lights[0].first_view, gpu_lights[0].view_count), (0, 6));
+    assert_eq!((gpu_lights[1].first_view, gpu_lights[1].view_count), (6, 1));
+    assert_eq!(gpu_lights[0].filter_kind, 1);
+    assert_eq!(gpu_lights[0].frustum_width, 2.0);
+    assert_eq!(gpu_lights[1].filter_kind, 2);
+    assert_eq!(gpu_lights[1].params[1], 0.1);
+    assert_eq!(gpu_lights[1].params[3], 0.5);
+    assert_eq!(
+        (gpu_lights[1].search_radius, gpu_lights[1].filter_radius),
+        (3, 2)
+    );
+    assert_eq!(gpu_views[0].uv_rect, [0.0, 0.0, 0.25, 0.25]);
+}
+
+#[test]
+fn test_collect_shadow_requests() {
+    let mut world = World::new();
+    let camera = world.spawn();
+    world
+        .add_component(camera, luminara_render::Camera::default())
+        .unwrap();
+    world
+        .add_component(camera, Transform::from_xyz(0.0, 0.0, 10.0))
+        .unwrap();
+
+    let mut spawn_point = |cast_shadows, position: Vec3| {
+        let light = world.spawn();
+        world
+            .add_component(
+                light,
+                PointLight {
+                    color: Color::WHITE,
+                    intensity: 1.0,
+                    range: 2.0,
+                    cast_shadows,
+                },
+            )
+            .unwrap();
+        world
+            .add_component(light, Transform::from_translation(position))
+            .unwrap();
+        light
+    };
+    let near = spawn_point(true, Vec3::ZERO);
+    let far = spawn_point(true, Vec3::new(0.0, 0.0, -40.0));
+    let unshadowed = spawn_point(false, Vec3::ZERO);
+    let settings = ShadowSettings {
+        normal_bias: 2.0,
+        ..Default::default()
+    };
+    world.add_component(far, settings).unwrap();
+
+    let views = luminara_render::collect_camera_views(&world, (800, 600));
+    let requests = collect_shadow_requests(&world, &views);
+    assert_eq!(requests.len(), 2);
+    let request = |light| requests.iter().find(|r| r.light == light).unwrap();
+    assert!(request(near).importance > request(far).importance);
+    assert_eq!(request(near).settings, ShadowSettings::default());
+    assert_eq!(request(far).settings, settings);
+    assert!(requests.iter().all(|r| r.light != unshadowed));
+}
+
+#[test]
+fn test_shadows_shader_module_validates() {
+    let source = "#import luminara::shadows\n\
+        @group(0) @binding(0) var shadow_atlas: texture_depth_2d;\n\
+        @group(0) @binding(1) var shadow_sampler: sampler_comparison;\n\
+        @group(0) @binding(2) var<storage, read> shadow_lights: array<ShadowLight>;\n\
+        @group(0) @binding(3) var<storage, read> shadow_views: array<ShadowView>;\n\
+        @fragment\n\
+        fn fs_main(@location(0) position: vec3<f32>, @location(1) normal: vec3<f32>) \
+            -> @location(0) vec4<f32> {\n\
+            let light = shadow_lights[0];\n\
+            let view = shadow_views[shadow_view_index(light, position)];\n\
+            let lit = sample_shadow(shadow_atlas, shadow_sampler, light, view, position, normal);\n\
+            return vec4<f32>(vec3<f32>(lit), 1.0);\n\
+        }\n";
+    let composed = ShaderComposer::new()
+        .compose("shadowed.wgsl", source, &ShaderDefs::new())
+        .unwrap();
+    if let Err(error) = composed.validate() {
+        panic!("{}", error);
+    }
+}
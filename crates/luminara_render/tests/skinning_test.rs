@@ -0,0 +1,292 @@
+use luminara_asset::{AssetId, AssetServer, Handle};
+use luminara_core::World;
+use luminara_math::{Mat4, Quat, Transform, Vec3};
+use luminara_render::animation::{AnimationChannel, AnimationClip, AnimationOutput, AnimationPath};
+use luminara_render::{
+    apply_animation_pose_system, cpu_deformation_system, deform_mesh, joint_palette_system,
+    morph_vertices, skin_vertex, AnimationPlayer, AnimationTargets, DeformedMesh, GpuSkinJoint,
+    JointPalette, Mesh, MorphTarget, MorphWeights, ShaderComposer, ShaderDefs, SkinWeights,
+    SkinnedMesh, SkinningMethod, Vertex,
+};
+use luminara_scene::GlobalTransform;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+const EPSILON: f32 = 1e-4;
+
+fn vertex(position: [f32; 3]) -> Vertex {
+    Vertex {
+        position,
+        normal: [0.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
+    }
+}
+
+fn skin(joints: [u16; 2], weights: [f32; 2]) -> SkinWeights {
+    SkinWeights {
+        joints: [joints[0], joints[1], 0, 0],
+        weights: [weights[0], weights[1], 0.0, 0.0],
+    }
+}
+
+fn assert_close(actual: [f32; 3], expected: Vec3) {
+    assert!(
+        Vec3::from(actual).distance(expected) < EPSILON,
+        "{:?} != {:?}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn test_palette_is_relative_to_mesh_and_bind_pose() {
+    // Joint bound at y = 1, now moved to y = 3; mesh entity sits at x = 5
+    let bind = Mat4::from_translation(Vec3::Y);
+    let joint = Mat4::from_translation(Vec3::new(5.0, 3.0, 0.0));
+    let mesh = Mat4::from_translation(Vec3::X * 5.0);
+    let palette = JointPalette::compute(mesh, &[joint], &[bind.inverse()]);
+
+    let skinned = skin_vertex(
+        &vertex([0.0, 1.0, 0.0]),
+        &skin([0, 0], [1.0, 0.0]),
+        &palette,
+        SkinningMethod::Linear,
+    );
+    assert_close(skinned.position, Vec3::new(0.0, 3.0, 0.0));
+
+    assert_eq!(std::mem::size_of::<GpuSkinJoint>(), 96);
+    let gpu = palette.gpu_joints();
+    assert_eq!(gpu[0].matrix[3][1], 2.0);
+    assert_eq!(gpu[0].real, Quat::IDENTITY.to_array());
+}
+
+#[test]
+fn test_dual_quaternion_skinning_keeps_volume() {
+    // Half-way between an untwisted joint and one twisted half a turn
+    let palette = JointPalette::compute(
+        Mat4::IDENTITY,
+        &[Mat4::IDENTITY, Mat4::from_rotation_x(PI)],
+        &[],
+    );
+    let input = vertex([0.0, 1.0, 0.0]);
+    let weights = skin([0, 1], [0.5, 0.5]);
+
+    let linear = skin_vertex(&input, &weights, &palette, SkinningMethod::Linear);
+    assert!(Vec3::from(linear.position).length() < EPSILON);
+
+    let dual = skin_vertex(&input, &weights, &palette, SkinningMethod::DualQuaternion);
+    assert!((Vec3::from(dual.position).length() - 1.0).abs() < EPSILON);
+    assert!((Vec3::from(dual.normal).length() - 1.0).abs() < EPSILON);
+    assert_eq!(dual.tangent[3], 1.0);
+}
+
+#[test]
+fn test_linear_skinning_keeps_normals_perpendicular_under_scale() {
+    // A 45° slope stretched along X; its surface runs along (1, -1, 0)
+    let palette = JointPalette::compute(
+        Mat4::IDENTITY,
+        &[Mat4::from_scale(Vec3::new(2.0, 1.0, 1.0))],
+        &[],
+    );
+    let mut input = vertex([0.0, 0.0, 0.0]);
+    input.normal = Vec3::new(1.0, 1.0, 0.0).normalize().to_array();
+    input.tangent = [
+        std::f32::consts::FRAC_1_SQRT_2,
+        -std::f32::consts::FRAC_1_SQRT_2,
+        0.0,
+        1.0,
+    ];
+
+    let skinned = skin_vertex(
+        &input,
+        &skin([0, 0], [1.0, 0.0]),
+        &palette,
+        SkinningMethod::Linear,
+    );
+    let normal = Vec3::from(skinned.normal);
+    let tangent = Vec3::from_slice(&skinned.tangent[..3]);
+    assert_close(skinned.normal, Vec3::new(1.0, 2.0, 0.0).normalize());
+    assert_close(tangent.to_array(), Vec3::new(2.0, -1.0, 0.0).normalize());
+    assert!(normal.dot(tangent).abs() < EPSILON);
+
+    // Mirroring keeps the normal facing out of the mirrored surface
+    let mirror = JointPalette::compute(
+        Mat4::IDENTITY,
+        &[Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0))],
+        &[],
+    );
+    let mirrored = skin_vertex(
+        &input,
+        &skin([0, 0], [1.0, 0.0]),
+        &mirror,
+        SkinningMethod::Linear,
+    );
+    assert_close(mirrored.normal, Vec3::new(-1.0, 1.0, 0.0).normalize());
+}
+
+#[test]
+fn test_morph_targets_are_weighted() {
+    let vertices = vec![vertex([0.0, 0.0, 0.0]), vertex([1.0, 0.0, 0.0])];
+    let targets = vec![
+        MorphTarget {
+            positions: vec![[0.0, 2.0, 0.0], [0.0, 0.0, 0.0]],
+            normals: Vec::new(),
+            tangents: Vec::new(),
+        },
+        MorphTarget {
+            positions: vec![[1.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+            normals: vec![[0.0, -1.0, 1.0], [0.0, 0.0, 0.0]],
+            tangents: Vec::new(),
+        },
+    ];
+
+    let morphed = morph_vertices(&vertices, &targets, &[0.5, 1.0]);
+    assert_close(morphed[0].position, Vec3::new(1.0, 1.0, 0.0));
+    assert_close(morphed[1].position, Vec3::new(2.0, 0.0, 0.0));
+    assert_close(morphed[0].normal, Vec3::Z);
+    assert_close(morphed[1].normal, Vec3::Y);
+}
+
+#[test]
+fn test_deform_mesh_morphs_before_skinning() {
+    let mut mesh = Mesh::new(vec![vertex([0.0, 0.0, 0.0])], vec![0, 0, 0]);
+    mesh.morph_targets = vec![MorphTarget {
+        positions: vec![[1.0, 0.0, 0.0]],
+        ..Default::default()
+    }];
+    mesh.skin_weights = vec![skin([0, 0], [1.0, 0.0])];
+    let palette = JointPalette::compute(Mat4::IDENTITY, &[Mat4::from_rotation_y(PI / 2.0)], &[]);
+
+    let deformed = deform_mesh(&mesh, &[1.0], Some((&palette, SkinningMethod::Linear)));
+    assert_close(deformed[0].position, Vec3::new(0.0, 0.0, -1.0));
+
+    // Without a palette only the morph applies
+    let morphed = deform_mesh(&mesh, &[1.0], None);
+    assert_close(morphed[0].position, Vec3::X);
+}
+
+#[test]
+fn test_systems_skin_from_joint_transforms() {
+    let mut world = World::new();
+    let asset_server = AssetServer::new("assets");
+    let mut mesh = Mesh::new(
+        vec![vertex([0.0, 1.0, 0.0]), vertex([0.0, 2.0, 0.0])],
+        vec![0, 1, 0],
+    );
+    mesh.skin_weights = vec![skin([0, 1], [1.0, 0.0]), skin([0, 1], [0.0, 1.0])];
+    let mesh_handle: Handle<Mesh> = asset_server.insert(AssetId::from_path("arm#Mesh"), mesh);
+    world.insert_resource(asset_server);
+
+    let root = world.spawn();
+    world
+        .add_component(root, GlobalTransform(Transform::default()))
+        .unwrap();
+    let tip = world.spawn();
+    world
+        .add_component(
+            tip,
+            GlobalTransform(Transform::from_translation(Vec3::new(0.0, 1.0, 3.0))),
+        )
+        .unwrap();
+
+    let character = world.spawn();
+    world.add_component(character, mesh_handle.clone()).unwrap();
+    world
+        .add_component(
+            character,
+            SkinnedMesh {
+                mesh: mesh_handle,
+                joints: vec![root, tip],
+                // The tip was bound at y = 1
+                inverse_bind_matrices: vec![Mat4::IDENTITY, Mat4::from_translation(Vec3::NEG_Y)],
+                skeleton: None,
+                method: SkinningMethod::Linear,
+            },
+        )
+        .unwrap();
+    world
+        .add_component(character, DeformedMesh::default())
+        .unwrap();
+
+    joint_palette_system(&mut world);
+    cpu_deformation_system(&mut world);
+
+    let palette = world.get_component::<JointPalette>(character).unwrap();
+    assert_eq!(palette.len(), 2);
+    let deformed = world.get_component::<DeformedMesh>(character).unwrap();
+    assert_close(deformed.vertices[0].position, Vec3::Y);
+    assert_close(deformed.vertices[1].position, Vec3::new(0.0, 2.0, 3.0));
+    assert!((deformed.aabb.max.z - 3.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_weight_channels_drive_morph_weights() {
+    let clip = AnimationClip {
+        name: "blink".to_string(),
+        duration: 1.0,
+        channels: vec![AnimationChannel {
+            target_node_index: 4,
+            target_path: AnimationPath::Weights,
+            inputs: vec![0.0, 1.0],
+            // Two targets, two keyframes
+            outputs: AnimationOutput::Scalar(vec![0.0, 1.0, 1.0, 0.0]),
+        }],
+    };
+    let pose = clip.sample(0.25);
+    assert_eq!(pose.weights[&4], vec![0.25, 0.75]);
+    assert!(pose.bones.is_empty());
+
+    let mut world = World::new();
+    let face = world.spawn();
+    world
+        .add_component(
+            face,
+            MorphWeights {
+                weights: vec![0.0, 0.0],
+            },
+        )
+        .unwrap();
+    let character = world.spawn();
+    let player = AnimationPlayer {
+        sampled_weights: pose.weights.clone(),
+        ..Default::default()
+    };
+    world.add_component(character, player).unwrap();
+    world
+        .add_component(
+            character,
+            AnimationTargets::from_scene_ids(&HashMap::from([(4, face)])),
+        )
+        .unwrap();
+
+    apply_animation_pose_system(&mut world);
+    let weights = world.get_component::<MorphWeights>(face).unwrap();
+    assert_eq!(weights.weights, vec![0.25, 0.75]);
+}
+
+#[test]
+fn test_skinning_shader_module_validates() {
+    let source = "#import luminara::skinning\n\
+        @vertex\n\
+        fn vs_main(\n\
+            @location(0) position: vec3<f32>,\n\
+            @location(10) joints: vec4<u32>,\n\
+            @location(11) weights: vec4<f32>,\n\
+        ) -> @builtin(position) vec4<f32> {\n\
+            let skinned = skin_position(joints, weights, position);\n\
+            let normal = skin_normal(joints, weights, vec3<f32>(0.0, 1.0, 0.0));\n\
+            return vec4<f32>(skinned + normal * 0.0, 1.0);\n\
+        }\n";
+    let composer = ShaderComposer::new();
+    for method in [SkinningMethod::Linear, SkinningMethod::DualQuaternion] {
+        let mut defs = ShaderDefs::new();
+        if let Some(def) = method.shader_def() {
+            defs = defs.with(def);
+        }
+        let composed = composer.compose("skinned.wgsl", source, &defs).unwrap();
+        if let Err(error) = composed.validate() {
+            panic!("{:?}: {}", method, error);
+        }
+    }
+}
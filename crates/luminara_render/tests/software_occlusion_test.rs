@@ -0,0 +1,270 @@
+use luminara_asset::{AssetServer, Handle};
+use luminara_core::shared_types::{Query, World};
+use luminara_core::Entity;
+use luminara_math::{Color, Mat4, Quat, Transform, Vec3};
+use luminara_render::{
+    visibility_system, Camera, Lod, LodGenerator, MainEntity, MaterialRegistry, Mesh, Occluder,
+    OcclusionBuffer, PbrMaterial, RenderApp, VisibilityCulling, VisibilityStats, VisibleEntities,
+    AABB,
+};
+
+fn view_proj() -> Mat4 {
+    let camera = Camera::default();
+    camera.projection_matrix(2.0) * Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y)
+}
+
+fn unit_box() -> AABB {
+    AABB::new(Vec3::splat(-0.5), Vec3::splat(0.5))
+}
+
+/// A buffer with a 4x4 wall at the origin facing the camera
+fn wall_buffer() -> OcclusionBuffer {
+    let mut buffer = OcclusionBuffer::new(64, 32);
+    buffer.clear(view_proj());
+    let wall = Mat4::from_scale(Vec3::new(4.0, 4.0, 1.0));
+    assert_eq!(buffer.rasterize_mesh(&Mesh::quad(), &wall), 2);
+    buffer.build_pyramid();
+    buffer
+}
+
+#[test]
+fn test_occluder_depth() {
+    let buffer = wall_buffer();
+    assert_eq!(buffer.size(), (64, 32));
+    assert!(buffer.depth(32, 16) < 1.0);
+    assert_eq!(buffer.depth(0, 0), 1.0);
+
+    // Triangles facing away are drawn too
+    let mut buffer = OcclusionBuffer::new(64, 32);
+    buffer.clear(view_proj());
+    let back = Mat4::from_rotation_y(std::f32::consts::PI);
+    buffer.rasterize_mesh(&Mesh::quad(), &back);
+    assert!(buffer.depth(32, 16) < 1.0);
+}
+
+#[test]
+fn test_boxes_behind_occluders() {
+    let buffer = wall_buffer();
+    let at = |x: f32, z: f32| Mat4::from_translation(Vec3::new(x, 0.0, z));
+
+    assert!(buffer.is_occluded(&unit_box(), &at(0.0, -3.0)));
+    // In front of the wall, beside it, half hidden, crossing it
+    assert!(!buffer.is_occluded(&unit_box(), &at(0.0, 2.0)));
+    assert!(!buffer.is_occluded(&unit_box(), &at(6.0, -3.0)));
+    assert!(!buffer.is_occluded(&unit_box(), &at(1.8, -3.0)));
+    assert!(!buffer.is_occluded(&unit_box(), &at(0.0, -0.2)));
+    // Around the camera
+    assert!(!buffer.is_occluded(&unit_box(), &at(0.0, 5.0)));
+}
+
+#[test]
+fn test_occluders_clipped_by_near_plane() {
+    let mut buffer = OcclusionBuffer::new(64, 32);
+    buffer.clear(view_proj());
+    // A floor passing under the camera
+    let floor = Mat4::from_translation(Vec3::new(0.0, -1.0, 0.0))
+        * Mat4::from_rotation_x(-std::f32::consts::FRAC_PI_2)
+        * Mat4::from_scale(Vec3::splat(40.0));
+    assert_eq!(buffer.rasterize_mesh(&Mesh::quad(), &floor), 2);
+    assert!(buffer.depth(32, 31) < 1.0);
+    assert_eq!(buffer.depth(32, 0), 1.0);
+}
+
+fn scene() -> World {
+    let mut world = World::new();
+    world.insert_resource(AssetServer::new("assets"));
+    world.insert_resource(VisibilityCulling::new(128, 128));
+    let mut materials = MaterialRegistry::default();
+    materials.register_component::<PbrMaterial>();
+    world.insert_resource(materials);
+
+    let camera = world.spawn();
+    world.add_component(camera, Camera::default()).unwrap();
+    world
+        .add_component(camera, Transform::from_xyz(0.0, 0.0, 5.0))
+        .unwrap();
+    world
+}
+
+fn spawn_mesh(world: &mut World, mesh: Mesh, transform: Transform) -> Entity {
+    let mesh = world.get_resource::<AssetServer>().unwrap().add(mesh);
+    let entity = world.spawn();
+    world.add_component(entity, mesh).unwrap();
+    world.add_component(entity, transform).unwrap();
+    world
+        .add_component(
+            entity,
+            PbrMaterial {
+                albedo: Color::WHITE,
+                albedo_texture: None,
+                normal_texture: None,
+                metallic: 0.0,
+                roughness: 0.5,
+                metallic_roughness_texture: None,
+                emissive: Color::BLACK,
+            },
+        )
+        .unwrap();
+    entity
+}
+
+fn spawn_wall(world: &mut World) -> Entity {
+    let wall = spawn_mesh(
+        world,
+        Mesh::quad(),
+        Transform::from_scale(Vec3::new(4.0, 4.0, 1.0)),
+    );
+    world.add_component(wall, Occluder::default()).unwrap();
+    wall
+}
+
+#[test]
+fn test_visibility_pass() {
+    let mut world = scene();
+    let wall = spawn_wall(&mut world);
+    let hidden = spawn_mesh(
+        &mut world,
+        Mesh::cube(1.0),
+        Transform::from_xyz(0.0, 0.0, -3.0),
+    );
+    let beside = spawn_mesh(
+        &mut world,
+        Mesh::cube(1.0),
+        Transform::from_xyz(2.5, 0.0, 0.0),
+    );
+    let behind = spawn_mesh(
+        &mut world,
+        Mesh::cube(1.0),
+        Transform::from_xyz(0.0, 0.0, 10.0),
+    );
+
+    visibility_system(&mut world);
+
+    let visible = world.get_resource::<VisibleEntities>().unwrap();
+    assert!(visible.is_visible(wall));
+    assert!(visible.is_visible(beside));
+    assert!(!visible.is_visible(hidden));
+    assert!(!visible.is_visible(behind));
+
+    let stats = world.get_resource::<VisibilityStats>().unwrap();
+    assert_eq!(stats.total, 4);
+    assert_eq!(stats.frustum_culled, 1);
+    assert_eq!(stats.occlusion_culled, 1);
+    assert_eq!(stats.visible, 2);
+    assert_eq!(stats.occluders, 1);
+    assert_eq!(stats.occluder_triangles, 2);
+    assert_eq!(stats.entities_per_lod, vec![2]);
+    assert_eq!(stats.culled_fraction(), 0.5);
+}
+
+#[test]
+fn test_occlusion_culling_can_be_disabled() {
+    let mut world = scene();
+    spawn_wall(&mut world);
+    let hidden = spawn_mesh(
+        &mut world,
+        Mesh::cube(1.0),
+        Transform::from_xyz(0.0, 0.0, -3.0),
+    );
+    world
+        .get_resource_mut::<VisibilityCulling>()
+        .unwrap()
+        .occlusion_culling = false;
+
+    visibility_system(&mut world);
+    let visible = world.get_resource::<VisibleEntities>().unwrap();
+    assert!(visible.is_visible(hidden));
+    assert_eq!(
+        world.get_resource::<VisibilityStats>().unwrap().occluders,
+        0
+    );
+}
+
+#[test]
+fn test_occluders_use_the_coarsest_lod() {
+    let mut world = scene();
+    // The mesh itself is too small to hide anything; its last LOD is not
+    let upright = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+    let wall = spawn_mesh(
+        &mut world,
+        Mesh::cube(0.01),
+        Transform::from_rotation(upright),
+    );
+    let asset_server = world.get_resource::<AssetServer>().unwrap().clone();
+    let lod = Lod {
+        distances: vec![100.0],
+        meshes: vec![
+            world.get_component::<Handle<Mesh>>(wall).unwrap().clone(),
+            asset_server.add(Mesh::plane(8.0)),
+        ],
+    };
+    world.add_component(wall, lod).unwrap();
+    world.add_component(wall, Occluder::default()).unwrap();
+    let hidden = spawn_mesh(
+        &mut world,
+        Mesh::cube(1.0),
+        Transform::from_xyz(0.0, 0.0, -3.0),
+    );
+
+    visibility_system(&mut world);
+    assert!(!world
+        .get_resource::<VisibleEntities>()
+        .unwrap()
+        .is_visible(hidden));
+}
+
+#[test]
+fn test_extraction_uses_visibility_and_lod() {
+    let mut world = scene();
+    spawn_wall(&mut world);
+    let hidden = spawn_mesh(
+        &mut world,
+        Mesh::cube(1.0),
+        Transform::from_xyz(0.0, 0.0, -3.0),
+    );
+    let far = spawn_mesh(
+        &mut world,
+        Mesh::sphere(0.5, 16),
+        Transform::from_xyz(2.5, 0.0, 0.0),
+    );
+    let coarse = world
+        .get_resource::<AssetServer>()
+        .unwrap()
+        .add(Mesh::sphere(0.5, 4));
+    let detailed = world.get_component::<Handle<Mesh>>(far).unwrap().clone();
+    world
+        .add_component(
+            far,
+            Lod {
+                distances: vec![3.0],
+                meshes: vec![detailed, coarse.clone()],
+            },
+        )
+        .unwrap();
+
+    visibility_system(&mut world);
+    assert_eq!(
+        world
+            .get_resource::<VisibleEntities>()
+            .unwrap()
+            .lod_level(far),
+        Some(1)
+    );
+
+    let render = RenderApp::new().extract(&world);
+    let extracted: Vec<(Entity, Handle<Mesh>)> =
+        Query::<(&MainEntity, &Handle<Mesh>)>::new(&render)
+            .iter()
+            .map(|(main, mesh)| (main.0, mesh.clone()))
+            .collect();
+    assert!(extracted.iter().all(|(entity, _)| *entity != hidden));
+    let (_, mesh) = extracted.iter().find(|(entity, _)| *entity == far).unwrap();
+    assert_eq!(mesh.id(), coarse.id());
+}
+
+#[test]
+fn test_lod_generator_occluder_mesh() {
+    let source = Mesh::sphere(1.0, 32);
+    let occluder = LodGenerator::default().occluder_mesh(&source);
+    assert!(occluder.indices.len() < source.indices.len() / 4);
+}
@@ -0,0 +1,190 @@
+// Tests of the CPU reference renderer. It needs no adapter, so frames are
+// compared exactly.
+
+use luminara_asset::AssetServer;
+use luminara_core::shared_types::World;
+use luminara_math::{Color, Mat4, Quat, Transform, Vec3};
+use luminara_render::command::{CommandBuffer, DrawCommand, GizmoType};
+use luminara_render::{
+    mesh_thumbnail, Camera, Material, MaterialRegistry, Mesh, PbrMaterial, SoftwareRenderer,
+    UniformType,
+};
+
+const SIZE: u32 = 64;
+
+fn material(albedo: Color, emissive: Color) -> PbrMaterial {
+    PbrMaterial {
+        albedo,
+        albedo_texture: None,
+        normal_texture: None,
+        metallic: 0.0,
+        roughness: 0.5,
+        metallic_roughness_texture: None,
+        emissive,
+    }
+}
+
+fn scene(clear_color: Color) -> World {
+    let mut world = World::new();
+    world.insert_resource(AssetServer::new("assets"));
+    let mut materials = MaterialRegistry::default();
+    materials.register_component::<PbrMaterial>();
+    world.insert_resource(materials);
+
+    let camera = world.spawn();
+    world
+        .add_component(
+            camera,
+            Camera {
+                clear_color,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    world
+        .add_component(camera, Transform::from_xyz(0.0, 0.0, 3.0))
+        .unwrap();
+    world
+}
+
+fn spawn_mesh(world: &mut World, mesh: Mesh, transform: Transform, material: PbrMaterial) {
+    let mesh = world.get_resource::<AssetServer>().unwrap().add(mesh);
+    let entity = world.spawn();
+    world.add_component(entity, mesh).unwrap();
+    world.add_component(entity, transform).unwrap();
+    world.add_component(entity, material).unwrap();
+}
+
+#[test]
+fn test_clear_color() {
+    let world = scene(Color::rgb(1.0, 0.0, 0.0));
+    let frame = SoftwareRenderer::new(SIZE, SIZE).render(&world);
+    assert_eq!(frame.dimensions(), (SIZE, SIZE));
+
+    let first = frame.get_pixel(0, 0).0;
+    assert!(frame.pixels().all(|pixel| pixel.0 == first));
+    assert!(first[0] > 200 && first[1] == 0 && first[2] == 0);
+    assert_eq!(first[3], 255);
+}
+
+#[test]
+fn test_mesh_coverage() {
+    let mut world = scene(Color::BLACK);
+    spawn_mesh(
+        &mut world,
+        Mesh::sphere(1.0, 16),
+        Transform::IDENTITY,
+        material(Color::WHITE, Color::BLACK),
+    );
+    let frame = SoftwareRenderer::new(SIZE, SIZE).render(&world);
+
+    assert_ne!(frame.get_pixel(SIZE / 2, SIZE / 2).0, [0, 0, 0, 255]);
+    assert_eq!(frame.get_pixel(0, 0).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn test_depth_ordering() {
+    let mut world = scene(Color::BLACK);
+    // The far sphere is drawn last but hidden behind the near one
+    spawn_mesh(
+        &mut world,
+        Mesh::sphere(0.5, 16),
+        Transform::from_xyz(0.0, 0.0, 1.0),
+        material(Color::BLACK, Color::rgb(0.0, 1.0, 0.0)),
+    );
+    spawn_mesh(
+        &mut world,
+        Mesh::sphere(1.0, 16),
+        Transform::from_xyz(0.0, 0.0, -1.0),
+        material(Color::BLACK, Color::rgb(1.0, 0.0, 0.0)),
+    );
+    let frame = SoftwareRenderer::new(SIZE, SIZE).render(&world);
+
+    let center = frame.get_pixel(SIZE / 2, SIZE / 2).0;
+    assert!(center[1] > center[0], "near sphere hidden: {:?}", center);
+}
+
+#[test]
+fn test_back_faces_are_culled() {
+    let mut world = scene(Color::BLACK);
+    spawn_mesh(
+        &mut world,
+        Mesh::quad(),
+        Transform {
+            translation: Vec3::ZERO,
+            rotation: Quat::from_rotation_y(std::f32::consts::PI),
+            scale: Vec3::ONE,
+        },
+        material(Color::WHITE, Color::WHITE),
+    );
+    let frame = SoftwareRenderer::new(SIZE, SIZE).render(&world);
+    assert!(frame.pixels().all(|pixel| pixel.0 == [0, 0, 0, 255]));
+}
+
+#[test]
+fn test_gizmo_lines() {
+    let mut world = scene(Color::BLACK);
+    let mut commands = CommandBuffer::default();
+    commands.push(DrawCommand::DrawGizmo {
+        gizmo: GizmoType::Line {
+            start: [-2.0, 0.0, 0.0],
+            end: [2.0, 0.0, 0.0],
+        },
+        transform: Mat4::IDENTITY,
+        color: Color::WHITE,
+    });
+    world.insert_resource(commands);
+    let frame = SoftwareRenderer::new(SIZE, SIZE).render(&world);
+
+    let on_line = (0..SIZE).any(|y| frame.get_pixel(SIZE / 2, y).0 == [255, 255, 255, 255]);
+    assert!(on_line);
+    assert_eq!(frame.get_pixel(SIZE / 2, 0).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn test_deterministic() {
+    let mut world = scene(Color::rgb(0.1, 0.2, 0.3));
+    spawn_mesh(
+        &mut world,
+        Mesh::cube(1.0),
+        Transform {
+            translation: Vec3::ZERO,
+            rotation: Quat::from_rotation_y(0.6) * Quat::from_rotation_x(0.4),
+            scale: Vec3::ONE,
+        },
+        material(Color::rgb(0.8, 0.3, 0.2), Color::BLACK),
+    );
+    let mut renderer = SoftwareRenderer::new(SIZE, SIZE);
+    assert_eq!(renderer.render(&world), renderer.render(&world));
+
+    renderer.resize(32, 16);
+    assert_eq!(renderer.render(&world).dimensions(), (32, 16));
+}
+
+#[test]
+fn test_mesh_thumbnail() {
+    let thumbnail = mesh_thumbnail(
+        &Mesh::sphere(2.0, 16),
+        &material(Color::rgb(0.2, 0.6, 0.9), Color::BLACK),
+        SIZE,
+    );
+    assert_eq!(thumbnail.dimensions(), (SIZE, SIZE));
+    // The mesh is framed in the center on a transparent background
+    assert_eq!(thumbnail.get_pixel(0, 0).0[3], 0);
+    assert_eq!(thumbnail.get_pixel(SIZE / 2, SIZE / 2).0[3], 255);
+}
+
+#[test]
+fn test_uniform_layout_read() {
+    let material = material(Color::rgb(0.25, 0.5, 0.75), Color::BLACK);
+    let layout = material.uniform_layout();
+    let data = material.uniform_data();
+
+    assert_eq!(
+        layout.read(&data, "albedo"),
+        Some(vec![0.25, 0.5, 0.75, 1.0])
+    );
+    assert_eq!(layout.read(&data, "roughness"), Some(vec![0.5]));
+    assert_eq!(layout.read(&data, "missing"), None);
+    assert_eq!(layout.fields[0].ty, UniformType::Vec4);
+}
@@ -0,0 +1,116 @@
+use luminara_asset::Handle;
+use luminara_core::{Time, World};
+use luminara_render::{
+    sprite_animation_system, AtlasSprite, SpriteAnimation, SpriteAnimationClip,
+    SpriteAnimationEvent, SpriteAnimationEvents, SpriteAnimationMode,
+};
+
+const FRAME: f32 = 0.1;
+
+fn frames(animation: &mut SpriteAnimation, steps: usize) -> Vec<usize> {
+    (0..steps)
+        .map(|_| {
+            animation.advance(FRAME);
+            animation.atlas_index().unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_loop_once_and_ping_pong_playback() {
+    let mut animation = SpriteAnimation::new(vec![
+        SpriteAnimationClip::from_indices("run", [4, 5, 6], 10.0),
+        SpriteAnimationClip::from_indices("die", [7, 8], 10.0).with_mode(SpriteAnimationMode::Once),
+        SpriteAnimationClip::from_indices("idle", [0, 1, 2], 10.0)
+            .with_mode(SpriteAnimationMode::PingPong),
+    ]);
+    assert_eq!(animation.atlas_index(), None);
+
+    assert!(animation.play("run"));
+    assert_eq!(frames(&mut animation, 4), vec![5, 6, 4, 5]);
+
+    assert!(animation.play("die"));
+    assert_eq!(frames(&mut animation, 3), vec![8, 8, 8]);
+    assert!(animation.is_finished());
+
+    assert!(animation.play("idle"));
+    assert_eq!(frames(&mut animation, 6), vec![1, 2, 1, 0, 1, 2]);
+
+    assert!(!animation.play("fly"));
+    assert_eq!(animation.current_clip().unwrap().name, "idle");
+}
+
+#[test]
+fn test_play_keeps_running_clip_and_speed_scales_time() {
+    let mut animation =
+        SpriteAnimation::new(vec![SpriteAnimationClip::from_indices("run", 0..4, 10.0)]);
+    animation.play("run");
+    animation.advance(0.15);
+    assert_eq!(animation.frame(), 1);
+
+    // Playing the current clip again does not restart it
+    animation.play("run");
+    assert_eq!(animation.frame(), 1);
+
+    animation.speed = 2.0;
+    animation.advance(0.1);
+    assert_eq!(animation.frame(), 3);
+
+    animation.playing = false;
+    animation.advance(1.0);
+    assert_eq!(animation.frame(), 3);
+}
+
+#[test]
+fn test_frame_events_fire_on_entry() {
+    let clip = SpriteAnimationClip::from_indices("attack", [0, 1, 2], 10.0)
+        .with_event(0, "windup")
+        .with_event(2, "hit");
+    assert!((clip.duration() - 0.3).abs() < 1e-6);
+
+    let mut animation = SpriteAnimation::new(vec![clip]);
+    animation.play("attack");
+    assert_eq!(animation.advance(0.0), vec!["windup".to_string()]);
+    assert!(animation.advance(0.1).is_empty());
+    assert_eq!(animation.advance(0.1), vec!["hit".to_string()]);
+    // A long step enters every frame it passes
+    assert_eq!(
+        animation.advance(0.2),
+        vec!["windup".to_string()],
+        "looping back enters frame 0 again"
+    );
+}
+
+#[test]
+fn test_system_drives_atlas_sprite_and_collects_events() {
+    let mut world = World::new();
+    let mut time = Time::new();
+    time.update_manual(0.25);
+    world.insert_resource(time);
+    world.insert_resource(SpriteAnimationEvents::default());
+
+    let clip = SpriteAnimationClip::from_indices("walk", [3, 4, 5], 10.0).with_event(2, "step");
+    let mut animation = SpriteAnimation::new(vec![clip]);
+    animation.play("walk");
+
+    let entity = world.spawn();
+    world.add_component(entity, animation).unwrap();
+    world
+        .add_component(entity, AtlasSprite::new(Handle::default(), 0))
+        .unwrap();
+
+    sprite_animation_system(&mut world);
+
+    assert_eq!(world.get_component::<AtlasSprite>(entity).unwrap().index, 5);
+    let events = world.get_resource::<SpriteAnimationEvents>().unwrap();
+    assert_eq!(
+        events.0,
+        vec![(
+            entity,
+            SpriteAnimationEvent {
+                clip: "walk".to_string(),
+                name: "step".to_string(),
+            }
+        )]
+    );
+}
@@ -0,0 +1,491 @@
+use luminara_asset::{AssetId, AssetLoader, AssetServer, Handle};
+use luminara_core::{Entity, Query, World};
+use luminara_math::{Transform, UVec2, Vec2, Vec3};
+use luminara_render::{
+    terrain_system, Camera, MaterialTextureQueue, Mesh, SculptMode, Terrain, TerrainChunk,
+    TerrainChunkLod, TerrainData, TerrainLoader, TerrainLodSettings, TerrainMaterial, TerrainNode,
+};
+use std::path::PathBuf;
+
+fn lod(chunk_size: u32, lod_levels: u32, lod_distance: f32) -> TerrainLodSettings {
+    TerrainLodSettings {
+        chunk_size,
+        lod_levels,
+        lod_distance,
+    }
+}
+
+/// Bumpy terrain so that skipped samples differ from interpolated ones
+fn bumpy(side: u32) -> TerrainData {
+    let heights = (0..side * side)
+        .map(|i| ((i.wrapping_mul(2_654_435_761) >> 7) % 60_000) as u16)
+        .collect();
+    TerrainData::from_heights(UVec2::splat(side), heights, 1.0, 10.0)
+}
+
+fn node_area(terrain: &Terrain, node: TerrainNode) -> u32 {
+    let quads = terrain.data().quads();
+    let origin = node.origin(terrain.lod.chunk_size);
+    let end = (origin + (terrain.lod.chunk_size << node.level)).min(quads);
+    (end.x - origin.x) * (end.y - origin.y)
+}
+
+#[test]
+fn test_lod_selection_refines_towards_viewer() {
+    let terrain =
+        Terrain::new(TerrainData::new(UVec2::splat(257), 1.0, 10.0)).with_lod(lod(32, 4, 40.0));
+
+    // Far away the whole map is one coarsest chunk
+    let far = terrain.select_lod(Vec3::new(5000.0, 0.0, 5000.0));
+    assert_eq!(far.len(), 1);
+    assert_eq!(
+        far[0].node,
+        TerrainNode {
+            level: 3,
+            x: 0,
+            z: 0
+        }
+    );
+
+    let near = terrain.select_lod(Vec3::new(1.0, 2.0, 1.0));
+    let covered: u32 = near
+        .iter()
+        .map(|chunk| node_area(&terrain, chunk.node))
+        .sum();
+    assert_eq!(covered, 256 * 256, "selected chunks tile the map");
+    let level_at = |x: u32, z: u32| {
+        near.iter()
+            .find(|chunk| {
+                let origin = chunk.node.origin(32);
+                let span = 32 << chunk.node.level;
+                (origin.x..origin.x + span).contains(&x) && (origin.y..origin.y + span).contains(&z)
+            })
+            .unwrap()
+            .node
+            .level
+    };
+    assert_eq!(level_at(0, 0), 0);
+    assert!(level_at(250, 250) >= 2);
+    assert!(level_at(0, 0) < level_at(100, 0));
+
+    // Neighbour levels point at the coarser chunks across each edge
+    let origin = near
+        .iter()
+        .find(|chunk| {
+            chunk.node
+                == TerrainNode {
+                    level: 0,
+                    x: 0,
+                    z: 0,
+                }
+        })
+        .unwrap();
+    assert_eq!(origin.neighbors[0], 0, "no neighbour past the map border");
+    assert!(near
+        .iter()
+        .all(|chunk| chunk.neighbors.iter().all(|&l| l >= chunk.node.level)));
+}
+
+#[test]
+fn test_lod_selection_accounts_for_height() {
+    // A viewer high above the map sees coarser chunks than one on the ground
+    let terrain =
+        Terrain::new(TerrainData::new(UVec2::splat(129), 1.0, 10.0)).with_lod(lod(16, 3, 20.0));
+    let ground = terrain.select_lod(Vec3::new(64.0, 0.0, 64.0));
+    let above = terrain.select_lod(Vec3::new(64.0, 70.0, 64.0));
+    assert!(above.len() < ground.len());
+}
+
+#[test]
+fn test_chunk_mesh_layout() {
+    let terrain = Terrain::new(bumpy(41)).with_lod(lod(32, 2, 10.0));
+    let full = terrain.chunk_mesh(&TerrainChunkLod {
+        node: TerrainNode {
+            level: 0,
+            x: 0,
+            z: 0,
+        },
+        neighbors: [0; 4],
+    });
+    assert_eq!(full.vertices.len(), 33 * 33);
+    assert_eq!(full.indices.len(), 32 * 32 * 6);
+    let data = terrain.data();
+    for vertex in &full.vertices {
+        let [x, y, z] = vertex.position;
+        assert_eq!(y, data.sample_height(x as u32, z as u32));
+    }
+    assert_eq!(full.vertices[33].uv, [0.0, 1.0 / 40.0]);
+
+    // The chunk past the first is clamped to the map's last sample
+    let clamped = terrain.chunk_mesh(&TerrainChunkLod {
+        node: TerrainNode {
+            level: 0,
+            x: 1,
+            z: 0,
+        },
+        neighbors: [0; 4],
+    });
+    assert_eq!(clamped.vertices.len(), 9 * 33);
+    assert_eq!(clamped.vertices.last().unwrap().position[0], 40.0);
+
+    // Coarser levels skip samples but keep the map's edge
+    let coarse = terrain.chunk_mesh(&TerrainChunkLod {
+        node: TerrainNode {
+            level: 1,
+            x: 0,
+            z: 0,
+        },
+        neighbors: [1; 4],
+    });
+    assert_eq!(coarse.vertices.len(), 21 * 21);
+    assert_eq!(coarse.vertices[1].position[0], 2.0);
+    assert_eq!(coarse.vertices[20].position[0], 40.0);
+
+    // Counter-clockwise seen from above, so faces point up
+    let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(full.vertices[full.indices[i] as usize].position));
+    assert!((b - a).cross(c - a).y > 0.0);
+}
+
+/// Height of `mesh` at `point` on the line of vertices with the same X (or Z)
+fn edge_height(mesh: &Mesh, point: Vec2, along_z: bool) -> Option<f32> {
+    let mut line: Vec<(f32, f32)> = mesh
+        .vertices
+        .iter()
+        .filter(|v| {
+            if along_z {
+                v.position[0] == point.x
+            } else {
+                v.position[2] == point.y
+            }
+        })
+        .map(|v| {
+            (
+                if along_z {
+                    v.position[2]
+                } else {
+                    v.position[0]
+                },
+                v.position[1],
+            )
+        })
+        .collect();
+    line.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let t = if along_z { point.y } else { point.x };
+    line.windows(2)
+        .find(|w| w[0].0 <= t && t <= w[1].0)
+        .map(|w| {
+            let f = (t - w[0].0) / (w[1].0 - w[0].0);
+            w[0].1 + (w[1].1 - w[0].1) * f
+        })
+}
+
+#[test]
+fn test_chunk_edges_are_crack_free() {
+    let terrain = Terrain::new(bumpy(129)).with_lod(lod(8, 4, 12.0));
+    let chunks = terrain.select_lod(Vec3::new(20.0, 0.0, 30.0));
+    let meshes: Vec<Mesh> = chunks
+        .iter()
+        .map(|chunk| terrain.chunk_mesh(chunk))
+        .collect();
+    let bounds = |node: TerrainNode| {
+        let origin = node.origin(8).as_vec2();
+        (
+            origin,
+            (origin + (8u32 << node.level) as f32).min(Vec2::splat(128.0)),
+        )
+    };
+
+    let mut stitched = 0;
+    for (a, chunk_a) in chunks.iter().enumerate() {
+        for (b, chunk_b) in chunks.iter().enumerate() {
+            let (min_a, max_a) = bounds(chunk_a.node);
+            let (min_b, max_b) = bounds(chunk_b.node);
+            // Edges where `a`'s +X or +Z side meets `b`
+            let shared = if max_a.x == min_b.x && min_a.y < max_b.y && min_b.y < max_a.y {
+                Some(true)
+            } else if max_a.y == min_b.y && min_a.x < max_b.x && min_b.x < max_a.x {
+                Some(false)
+            } else {
+                None
+            };
+            let Some(along_z) = shared else {
+                continue;
+            };
+            if chunk_a.node.level != chunk_b.node.level {
+                stitched += 1;
+            }
+            for (mesh, other) in [(&meshes[a], &meshes[b]), (&meshes[b], &meshes[a])] {
+                for vertex in &mesh.vertices {
+                    let point = Vec2::new(vertex.position[0], vertex.position[2]);
+                    let on_edge = if along_z {
+                        point.x == max_a.x
+                    } else {
+                        point.y == max_a.y
+                    };
+                    let Some(height) = edge_height(other, point, along_z).filter(|_| on_edge)
+                    else {
+                        continue;
+                    };
+                    assert!(
+                        (height - vertex.position[1]).abs() < 1e-4,
+                        "crack between {:?} and {:?} at {}",
+                        chunk_a.node,
+                        chunk_b.node,
+                        point
+                    );
+                }
+            }
+        }
+    }
+    assert!(stitched > 0, "the selection mixes levels");
+}
+
+#[test]
+fn test_sculpt_and_paint() {
+    let mut terrain = Terrain::new(TerrainData::new(UVec2::splat(65), 1.0, 20.0));
+    terrain.sculpt(Vec2::new(32.0, 32.0), 8.0, 5.0, SculptMode::Raise);
+    assert_eq!(terrain.height_revision(), 1);
+    let data = terrain.data();
+    assert!((data.sample_height(32, 32) - 5.0).abs() < 1e-3);
+    assert!(data.sample_height(36, 32) > 0.0 && data.sample_height(36, 32) < 5.0);
+    assert_eq!(data.sample_height(41, 32), 0.0);
+    assert!((data.height_at(Vec2::new(32.0, 32.0)) - 5.0).abs() < 1e-3);
+    assert!(
+        data.normal(34, 32).x > 0.0,
+        "slope falls away from the peak"
+    );
+
+    terrain.sculpt(Vec2::new(32.0, 32.0), 8.0, 1.0, SculptMode::Smooth);
+    assert!(terrain.data().sample_height(32, 32) < 5.0);
+    terrain.sculpt(Vec2::new(32.0, 32.0), 8.0, 1.0, SculptMode::Flatten(2.0));
+    assert!((terrain.data().sample_height(32, 32) - 2.0).abs() < 1e-3);
+    terrain.sculpt(Vec2::new(32.0, 32.0), 8.0, 10.0, SculptMode::Lower);
+    assert_eq!(
+        terrain.data().sample_height(32, 32),
+        0.0,
+        "heights clamp at zero"
+    );
+    assert_eq!(terrain.height_revision(), 4);
+
+    // Off the map edits change nothing
+    terrain.sculpt(Vec2::new(-50.0, -50.0), 4.0, 1.0, SculptMode::Raise);
+    assert_eq!(terrain.height_revision(), 4);
+
+    terrain.paint(Vec2::new(10.0, 10.0), 4.0, 2, 1.0);
+    assert_eq!(terrain.splat_revision(), 1);
+    let splat = &terrain.data().splat;
+    assert_eq!(splat[10 * 65 + 10], [0, 0, 255, 0, 0, 0, 0, 0]);
+    let edge = splat[10 * 65 + 12];
+    assert!(edge[0] > 0 && edge[2] > 0);
+    assert_eq!(splat[0], [255, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(splat
+        .iter()
+        .all(|weights| weights.iter().map(|&w| w as u32).sum::<u32>() == 255));
+
+    let [low, high] = terrain.data().splat_textures();
+    assert_eq!(low.data.len(), 65 * 65 * 4);
+    assert_eq!(low.data[(10 * 65 + 10) * 4 + 2], 255);
+    assert!(high.data.iter().all(|&w| w == 0));
+}
+
+#[test]
+fn test_height_map_matches_samples() {
+    let data = bumpy(9);
+    let map = data.to_height_map();
+    assert_eq!((map.width, map.height), (9, 9));
+    assert_eq!(map.get(3, 5), data.sample_height(3, 5));
+    assert_eq!(
+        map.world_pos(3, 5),
+        Vec3::new(3.0, data.sample_height(3, 5), 5.0)
+    );
+}
+
+fn chunks_of(world: &World, terrain: Entity) -> Vec<(Entity, TerrainNode)> {
+    Query::<(Entity, &TerrainChunk)>::new(world)
+        .iter()
+        .filter(|(_, chunk)| chunk.terrain == terrain)
+        .map(|(entity, chunk)| (entity, chunk.node))
+        .collect()
+}
+
+#[test]
+fn test_system_spawns_and_remeshes_chunks() {
+    let mut world = World::new();
+    let asset_server = AssetServer::new("assets");
+    let material = asset_server.add(TerrainMaterial::default());
+    world.insert_resource(asset_server);
+
+    let camera = world.spawn();
+    world.add_component(camera, Camera::default()).unwrap();
+    world
+        .add_component(camera, Transform::from_xyz(105.0, 0.0, 5.0))
+        .unwrap();
+
+    let terrain = world.spawn();
+    let transform = Transform::from_xyz(100.0, 0.0, 0.0);
+    world
+        .add_component(
+            terrain,
+            Terrain::new(TerrainData::new(UVec2::splat(65), 1.0, 10.0))
+                .with_lod(lod(16, 3, 20.0))
+                .with_material(material.clone()),
+        )
+        .unwrap();
+    world.add_component(terrain, transform).unwrap();
+
+    terrain_system(&mut world);
+
+    let expected = world
+        .get_component::<Terrain>(terrain)
+        .unwrap()
+        .select_lod(Vec3::new(5.0, 0.0, 5.0));
+    let chunks = chunks_of(&world, terrain);
+    assert_eq!(chunks.len(), expected.len());
+    let (near_chunk, _) = *chunks
+        .iter()
+        .find(|(_, node)| {
+            *node
+                == TerrainNode {
+                    level: 0,
+                    x: 0,
+                    z: 0,
+                }
+        })
+        .unwrap();
+    assert_eq!(
+        world.get_component::<Transform>(near_chunk),
+        Some(&transform)
+    );
+    assert_eq!(
+        world.get_component::<Handle<TerrainMaterial>>(near_chunk),
+        Some(&material)
+    );
+    {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        let material = asset_server.get(&material).unwrap();
+        let splat = material.splat_0.as_ref().expect("splat map is assigned");
+        assert!(asset_server.get(splat).is_some());
+    }
+
+    // Sculpting remeshes the touched chunk in place
+    let mesh = world
+        .get_component::<Handle<Mesh>>(near_chunk)
+        .unwrap()
+        .clone();
+    world.get_component_mut::<Terrain>(terrain).unwrap().sculpt(
+        Vec2::new(4.0, 4.0),
+        3.0,
+        2.0,
+        SculptMode::Raise,
+    );
+    terrain_system(&mut world);
+    {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        let mesh = asset_server.get(&mesh).unwrap();
+        let peak = mesh
+            .vertices
+            .iter()
+            .find(|v| v.position[0] == 4.0 && v.position[2] == 4.0)
+            .unwrap();
+        assert!((peak.position[1] - 2.0).abs() < 1e-3);
+    }
+    assert!(world.get_component::<TerrainChunk>(near_chunk).is_some());
+
+    // Moving away coarsens the selection and despawns the old chunks
+    world
+        .get_component_mut::<Transform>(camera)
+        .unwrap()
+        .translation = Vec3::new(5000.0, 0.0, 0.0);
+    terrain_system(&mut world);
+    let chunks = chunks_of(&world, terrain);
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].1.level, 2);
+    assert!(world.get_component::<TerrainChunk>(near_chunk).is_none());
+}
+
+fn temp_assets(name: &str, files: &[(&str, Vec<u8>)]) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("luminara_terrain_{}_{}", name, std::process::id()));
+    for (path, bytes) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, bytes).unwrap();
+    }
+    dir
+}
+
+fn png(image: image::DynamicImage) -> Vec<u8> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+#[test]
+fn test_loader_reads_raw_heights_and_splat_maps() {
+    let heights: Vec<u8> = (0..9u16).flat_map(|h| (h * 1000).to_le_bytes()).collect();
+    // Half grass, half rock everywhere; the second map is empty
+    let splat = image::RgbaImage::from_pixel(3, 3, image::Rgba([100, 100, 0, 0]));
+    let dir = temp_assets(
+        "raw",
+        &[
+            ("terrain/height.r16", heights),
+            ("terrain/splat.png", png(splat.into())),
+        ],
+    );
+    let source = br#"(
+        heightmap: "height.r16",
+        spacing: 2.0,
+        height_scale: 65.535,
+        splat: ["splat.png"],
+        layers: ["../textures/grass.png", "../textures/rock.png"],
+    )"#;
+
+    let queue = MaterialTextureQueue::default();
+    let loader = TerrainLoader::new(&dir, queue.clone());
+    let data = loader
+        .load(source, &dir.join("terrain/island.terrain.ron"))
+        .unwrap();
+    assert_eq!(data.size, UVec2::splat(3));
+    assert_eq!(data.spacing, 2.0);
+    assert!((data.sample_height(2, 1) - 5.0).abs() < 1e-3);
+    assert_eq!(data.extent(), Vec2::splat(4.0));
+    let [grass, rock] = [data.splat[4][0], data.splat[4][1]];
+    assert_eq!(grass as u32 + rock as u32, 255);
+    assert!(grass.abs_diff(rock) <= 1);
+    assert_eq!(data.layers.len(), 2);
+    assert_eq!(data.layers[1].id(), AssetId::from_path("textures/rock.png"));
+    assert_eq!(
+        queue.drain(),
+        vec![
+            "textures/grass.png".to_string(),
+            "textures/rock.png".to_string()
+        ]
+    );
+
+    assert!(loader
+        .load(
+            b"(heightmap: \"missing.r16\", height_scale: 1.0)",
+            &dir.join("terrain/a.terrain.ron")
+        )
+        .is_err());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_loader_reads_16_bit_png() {
+    let image = image::ImageBuffer::<image::Luma<u16>, _>::from_fn(4, 2, |x, y| {
+        image::Luma([(x + y * 4) as u16 * 8000])
+    });
+    let dir = temp_assets("png", &[("height.png", png(image.into()))]);
+    let loader = TerrainLoader::new(&dir, MaterialTextureQueue::default());
+    let data = loader
+        .load(
+            b"(heightmap: \"height.png\", height_scale: 65.535)",
+            &dir.join("field.terrain.ron"),
+        )
+        .unwrap();
+    assert_eq!(data.size, UVec2::new(4, 2));
+    assert!((data.sample_height(3, 1) - 56.0).abs() < 1e-3);
+    assert_eq!(data.splat[0], [255, 0, 0, 0, 0, 0, 0, 0]);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,422 @@
+use luminara_asset::{AssetId, AssetLoader, Handle};
+use luminara_math::{Color, Vec2};
+use luminara_render::{
+    build_text_mesh, layout_text, rasterize_outline, Font, FontFace, FontLoader, GlyphAtlas,
+    GlyphAtlasError, GlyphRasterMode, OutlineCurve, ShaderComposer, ShaderDefs, TextAlign,
+    TextLayout, TextLayoutOptions, TextSpan, TextStyle,
+};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Box font: 1000 units per em, Latin half width, CJK full width
+struct BoxFont {
+    latin: bool,
+    cjk: bool,
+}
+
+impl FontFace for BoxFont {
+    fn units_per_em(&self) -> f32 {
+        1000.0
+    }
+
+    fn ascent(&self) -> f32 {
+        800.0
+    }
+
+    fn descent(&self) -> f32 {
+        200.0
+    }
+
+    fn line_gap(&self) -> f32 {
+        0.0
+    }
+
+    fn glyph_id(&self, c: char) -> Option<u16> {
+        let covered = if c.is_ascii() { self.latin } else { self.cjk };
+        covered.then_some(c as u16)
+    }
+
+    fn advance(&self, glyph: u16) -> f32 {
+        if glyph < 0x80 {
+            500.0
+        } else {
+            1000.0
+        }
+    }
+
+    fn kerning(&self, left: u16, right: u16) -> f32 {
+        if (left, right) == ('A' as u16, 'V' as u16) {
+            -100.0
+        } else {
+            0.0
+        }
+    }
+
+    fn outline(&self, glyph: u16) -> Vec<OutlineCurve> {
+        if glyph == ' ' as u16 {
+            return Vec::new();
+        }
+        let (x0, x1) = if glyph < 0x80 {
+            (100.0, 400.0)
+        } else {
+            (100.0, 900.0)
+        };
+        let corners = [
+            Vec2::new(x0, 0.0),
+            Vec2::new(x1, 0.0),
+            Vec2::new(x1, 700.0),
+            Vec2::new(x0, 700.0),
+        ];
+        (0..4)
+            .map(|i| OutlineCurve::Line(corners[i], corners[(i + 1) % 4]))
+            .collect()
+    }
+}
+
+fn handle(name: &str) -> Handle<Font> {
+    Handle::new(AssetId::from_path(name), 0)
+}
+
+fn fonts(handle: &Handle<Font>) -> Option<Arc<dyn FontFace>> {
+    let face: Arc<dyn FontFace> = if *handle == self::handle("latin.ttf") {
+        Arc::new(BoxFont {
+            latin: true,
+            cjk: false,
+        })
+    } else if *handle == self::handle("cjk.otf") {
+        Arc::new(BoxFont {
+            latin: false,
+            cjk: true,
+        })
+    } else {
+        Arc::new(BoxFont {
+            latin: true,
+            cjk: true,
+        })
+    };
+    Some(face)
+}
+
+fn style(size: f32) -> TextStyle {
+    TextStyle::new(handle("box.ttf"), size)
+}
+
+fn layout(text: &str, options: TextLayoutOptions) -> TextLayout {
+    layout_text(&[TextSpan::new(text, style(20.0))], &options, &fonts)
+}
+
+fn wrapped(max_width: f32) -> TextLayoutOptions {
+    TextLayoutOptions {
+        max_width: Some(max_width),
+        ..Default::default()
+    }
+}
+
+/// Text of each line, rebuilt from glyph ids
+fn line_texts(layout: &TextLayout) -> Vec<String> {
+    layout
+        .lines
+        .iter()
+        .map(|line| {
+            layout.glyphs[line.glyphs.clone()]
+                .iter()
+                .map(|g| char::from_u32(g.glyph as u32).unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_glyphs_advance_on_the_baseline_with_kerning() {
+    let text = layout("AB", TextLayoutOptions::default());
+    assert_eq!(text.glyphs.len(), 2);
+    assert_eq!(text.glyphs[0].position, Vec2::new(0.0, 16.0));
+    assert_eq!(text.glyphs[1].position, Vec2::new(10.0, 16.0));
+    assert_eq!(text.size, Vec2::new(20.0, 20.0));
+
+    let kerned = layout("AV", TextLayoutOptions::default());
+    assert_eq!(kerned.glyphs[1].position.x, 8.0);
+    assert_eq!(kerned.size.x, 18.0);
+}
+
+#[test]
+fn test_lines_wrap_at_spaces_and_break_long_words() {
+    let text = layout("aaa bbb ccc", wrapped(75.0));
+    assert_eq!(line_texts(&text), ["aaabbb", "ccc"]);
+    // Trailing spaces do not count towards the width
+    assert_eq!(text.lines[0].width, 70.0);
+    assert_eq!(text.lines[1].baseline, 36.0);
+    assert_eq!(text.glyphs[6].position.x, 0.0);
+
+    let long = layout("abcdefghij", wrapped(45.0));
+    assert_eq!(line_texts(&long), ["abcd", "efgh", "ij"]);
+
+    let explicit = layout("one\n\ntwo", TextLayoutOptions::default());
+    assert_eq!(line_texts(&explicit), ["one", "", "two"]);
+    assert_eq!(explicit.size.y, 60.0);
+}
+
+#[test]
+fn test_cjk_breaks_between_characters_with_kinsoku() {
+    let text = layout("今日は、いい天気です。「晴れ」", wrapped(60.0));
+    let lines = line_texts(&text);
+    assert_eq!(lines[0], "今日");
+    for (line, layout_line) in lines.iter().zip(&text.lines) {
+        assert!(layout_line.width <= 60.0, "{:?}", lines);
+        let first = line.chars().next().unwrap();
+        let last = line.chars().last().unwrap();
+        assert!(!"、。」".contains(first), "{:?}", lines);
+        assert!(last != '「', "{:?}", lines);
+    }
+    assert_eq!(lines.concat(), "今日は、いい天気です。「晴れ」");
+
+    // Latin words next to CJK still break between the scripts only
+    let mixed = layout("日本Rust", wrapped(70.0));
+    assert_eq!(line_texts(&mixed), ["日本", "Rust"]);
+}
+
+#[test]
+fn test_fallback_fonts_cover_missing_characters() {
+    let style = TextStyle {
+        fallbacks: vec![handle("cjk.otf")],
+        ..TextStyle::new(handle("latin.ttf"), 20.0)
+    };
+    let text = layout_text(
+        &[TextSpan::new("Hi日本", style.clone())],
+        &TextLayoutOptions::default(),
+        &fonts,
+    );
+    assert_eq!(text.fonts.len(), 2);
+    let used: Vec<usize> = text.glyphs.iter().map(|g| g.font).collect();
+    assert_eq!(used, [0, 0, 1, 1]);
+    assert_eq!(text.glyphs[3].position.x, 40.0);
+
+    // Without the fallback the uncovered characters are dropped
+    let latin_only = TextStyle {
+        fallbacks: Vec::new(),
+        ..style
+    };
+    let text = layout_text(
+        &[TextSpan::new("Hi日本", latin_only)],
+        &TextLayoutOptions::default(),
+        &fonts,
+    );
+    assert_eq!(text.glyphs.len(), 2);
+}
+
+#[test]
+fn test_alignment_and_justification() {
+    let aligned = |align| {
+        let options = TextLayoutOptions {
+            align,
+            ..wrapped(100.0)
+        };
+        layout("ab", options).glyphs[0].position.x
+    };
+    assert_eq!(aligned(TextAlign::Left), 0.0);
+    assert_eq!(aligned(TextAlign::Center), 40.0);
+    assert_eq!(aligned(TextAlign::Right), 80.0);
+
+    let options = TextLayoutOptions {
+        align: TextAlign::Justify,
+        ..wrapped(75.0)
+    };
+    let text = layout("aa bb cc dd", options.clone());
+    assert_eq!(line_texts(&text), ["aabb", "ccdd"]);
+    // The slack goes into the space; the last line stays left aligned
+    assert_eq!(text.glyphs[2].position.x, 55.0);
+    assert_eq!(text.lines[0].width, 75.0);
+    assert_eq!(text.glyphs[6].position.x, 30.0);
+
+    // Without spaces the slack is spread between characters
+    let cjk = layout(
+        "一二三四五",
+        TextLayoutOptions {
+            max_width: Some(70.0),
+            ..options
+        },
+    );
+    assert_eq!(line_texts(&cjk)[0], "一二三");
+    assert_eq!(cjk.glyphs[2].position.x, 50.0);
+}
+
+#[test]
+fn test_rich_spans_keep_their_style_and_line_metrics() {
+    let red = TextStyle {
+        color: Color::RED,
+        ..style(40.0)
+    };
+    let blue = TextStyle {
+        color: Color::BLUE,
+        ..style(20.0)
+    };
+    let text = layout_text(
+        &[
+            TextSpan::new("Big", red),
+            TextSpan::new(" small\nnext", blue),
+        ],
+        &TextLayoutOptions {
+            line_height: 1.5,
+            ..Default::default()
+        },
+        &fonts,
+    );
+    assert_eq!(line_texts(&text), ["Bigsmall", "next"]);
+    assert_eq!(text.glyphs[0].color, Color::RED);
+    assert_eq!(text.glyphs[3].color, Color::BLUE);
+    assert_eq!(text.glyphs[3].span, 1);
+    assert_eq!(text.glyphs[3].byte_index, 1);
+    assert_eq!(text.glyphs[3].position.x, 60.0 + 10.0);
+
+    // The tallest span sets the first line; line height scales the spacing
+    assert_eq!(text.lines[0].baseline, 32.0);
+    assert_eq!(text.lines[1].baseline, 60.0 + 16.0);
+}
+
+#[test]
+fn test_rasterized_box_glyph() {
+    let square = BoxFont {
+        latin: true,
+        cjk: true,
+    }
+    .outline('A' as u16);
+    let scale = 0.02;
+
+    let coverage = rasterize_outline(&square, scale, GlyphRasterMode::Coverage, 4.0).unwrap();
+    assert_eq!((coverage.width, coverage.height), (8, 16));
+    assert_eq!(coverage.offset, Vec2::new(1.0, -15.0));
+    let at = |bitmap: &luminara_render::GlyphBitmap, x: u32, y: u32, c: u32| {
+        bitmap.data[((y * bitmap.width + x) * bitmap.channels + c) as usize]
+    };
+    assert_eq!(at(&coverage, 4, 8, 0), 255);
+    assert_eq!(at(&coverage, 0, 8, 0), 0);
+
+    let sdf = rasterize_outline(&square, scale, GlyphRasterMode::Sdf, 4.0).unwrap();
+    assert_eq!(sdf.offset, Vec2::new(-2.0, -18.0));
+    let center = at(&sdf, sdf.width / 2, sdf.height / 2, 0);
+    let outside = at(&sdf, 0, 0, 0);
+    assert!(center > 160 && outside < 40, "{} {}", center, outside);
+
+    let msdf = rasterize_outline(&square, scale, GlyphRasterMode::Msdf, 4.0).unwrap();
+    assert_eq!(msdf.channels, 4);
+    for (x, y) in [(msdf.width / 2, msdf.height / 2), (7, 5)] {
+        let mut channels = [0, 1, 2].map(|c| at(&msdf, x, y, c));
+        channels.sort();
+        assert!(channels[1] > 128, "{:?}", channels);
+    }
+    let mut corner = [0, 1, 2].map(|c| at(&msdf, 0, 0, c));
+    corner.sort();
+    assert!(corner[1] < 128);
+
+    assert!(rasterize_outline(&[], scale, GlyphRasterMode::Sdf, 4.0).is_none());
+}
+
+#[test]
+fn test_atlas_packs_without_overlap_and_grows() {
+    let face = BoxFont {
+        latin: true,
+        cjk: true,
+    };
+    let font = AssetId::from_path("box.ttf");
+    let mut atlas = GlyphAtlas::with_size(GlyphRasterMode::Coverage, 64, 128);
+
+    let mut placed = Vec::new();
+    for c in 'a'..='z' {
+        let glyph = atlas.glyph(font, &face, c as u16, 20.0).unwrap().unwrap();
+        placed.push(glyph);
+    }
+    assert_eq!(atlas.len(), 26);
+    assert!(atlas.height() > 64);
+    assert!(atlas.generation() > 0);
+    for (i, a) in placed.iter().enumerate() {
+        assert!(a.x + a.width <= atlas.width() && a.y + a.height <= atlas.height());
+        for b in &placed[i + 1..] {
+            let apart = a.x + a.width <= b.x
+                || b.x + b.width <= a.x
+                || a.y + a.height <= b.y
+                || b.y + b.height <= a.y;
+            assert!(apart, "{:?} overlaps {:?}", a, b);
+        }
+    }
+
+    // Cached, and blank glyphs take no space
+    let again = atlas.glyph(font, &face, 'a' as u16, 20.0).unwrap();
+    assert_eq!(again, Some(placed[0]));
+    assert_eq!(atlas.glyph(font, &face, ' ' as u16, 20.0), Ok(None));
+
+    let error = (0..200)
+        .map(|size| atlas.glyph(font, &face, 'A' as u16, 20.0 + size as f32))
+        .find_map(Result::err);
+    assert_eq!(error, Some(GlyphAtlasError::Full(128)));
+}
+
+#[test]
+fn test_sdf_atlas_shares_one_raster_size() {
+    let face = BoxFont {
+        latin: true,
+        cjk: true,
+    };
+    let font = AssetId::from_path("box.ttf");
+    let mut atlas = GlyphAtlas::new(GlyphRasterMode::Msdf);
+    let small = atlas.glyph(font, &face, 'x' as u16, 12.0).unwrap();
+    let large = atlas.glyph(font, &face, 'x' as u16, 96.0).unwrap();
+    assert_eq!(small, large);
+    assert_eq!(small.unwrap().raster_size, atlas.sdf_size);
+    assert_eq!(
+        atlas.pixels().len() as u32,
+        atlas.width() * atlas.height() * 4
+    );
+}
+
+#[test]
+fn test_quads_and_world_space_mesh() {
+    let text = layout("AB", TextLayoutOptions::default());
+    let mut atlas = GlyphAtlas::new(GlyphRasterMode::Coverage);
+    let quads = text.quads(&mut atlas).unwrap();
+    assert_eq!(quads.len(), 2);
+    assert_eq!(quads[0].min, Vec2::new(1.0, 1.0));
+    assert_eq!(quads[0].max, Vec2::new(9.0, 17.0));
+    assert_eq!(quads[1].min.x, 11.0);
+    assert!(quads[0].uv_max.x <= 1.0 && quads[0].uv_min.x >= 0.0);
+
+    // Centered on the origin at 10 pixels per unit, Y up
+    let (vertices, indices) = build_text_mesh(&quads, text.size, 10.0, Vec2::splat(0.5));
+    assert_eq!(vertices.len(), 8);
+    assert_eq!(indices.len(), 12);
+    assert_eq!(vertices[3].position, [-0.9, 0.9, 0.0]);
+    assert_eq!(vertices[1].position, [-0.1, -0.7, 0.0]);
+    assert_eq!(vertices[3].uv, quads[0].uv_min.to_array());
+}
+
+#[test]
+fn test_font_loader_rejects_invalid_data() {
+    let loader = FontLoader;
+    assert!(loader.extensions().contains(&"ttf"));
+    assert!(loader
+        .load(b"definitely not a font", Path::new("broken.ttf"))
+        .is_err());
+}
+
+#[test]
+fn test_text_shader_module_validates() {
+    let source = "#import luminara::text\n\
+        @fragment\n\
+        fn fs_main(@location(0) uv: vec2<f32>, @location(1) color: vec4<f32>) -> @location(0) vec4<f32> {\n\
+            return vec4<f32>(color.rgb, color.a * glyph_alpha(uv, 8.0));\n\
+        }\n";
+    let composer = ShaderComposer::new();
+    for mode in [
+        GlyphRasterMode::Coverage,
+        GlyphRasterMode::Sdf,
+        GlyphRasterMode::Msdf,
+    ] {
+        let mut defs = ShaderDefs::new();
+        if let Some(def) = mode.shader_def() {
+            defs = defs.with(def);
+        }
+        let composed = composer.compose("text.wgsl", source, &defs).unwrap();
+        if let Err(error) = composed.validate() {
+            panic!("{:?}: {}", mode, error);
+        }
+    }
+}
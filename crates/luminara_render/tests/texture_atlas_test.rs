@@ -0,0 +1,126 @@
+use luminara_asset::{AssetId, AssetLoader, Handle};
+use luminara_math::Vec2;
+use luminara_render::{
+    MaterialTextureQueue, Rect, TextureAtlas, TextureAtlasBuilder, TextureAtlasError,
+    TextureAtlasLoader, TextureData, TextureFormat,
+};
+use std::path::Path;
+
+fn solid(width: u32, height: u32, value: u8) -> TextureData {
+    TextureData {
+        width,
+        height,
+        data: vec![value; (width * height * 4) as usize],
+        format: TextureFormat::Rgba8,
+    }
+}
+
+fn overlaps(a: &Rect, b: &Rect) -> bool {
+    a.min.x < b.max.x && b.min.x < a.max.x && a.min.y < b.max.y && b.min.y < a.max.y
+}
+
+#[test]
+fn test_packer_places_images_without_overlap() {
+    let mut builder = TextureAtlasBuilder::new().with_padding(1);
+    builder.add_named_image("wide", solid(20, 8, 10));
+    builder.add_image(solid(8, 30, 20));
+    builder.add_named_image("small", solid(5, 5, 30));
+    let packed = builder.build().unwrap();
+
+    assert_eq!(packed.frames.len(), 3);
+    assert_eq!(packed.image.width, packed.image.height);
+    assert!(packed.image.width.is_power_of_two());
+    assert_eq!(
+        packed.frames[0].max - packed.frames[0].min,
+        Vec2::new(20.0, 8.0)
+    );
+    assert_eq!(
+        packed.frames[1].max - packed.frames[1].min,
+        Vec2::new(8.0, 30.0)
+    );
+    for (i, a) in packed.frames.iter().enumerate() {
+        assert!(a.max.x <= packed.image.width as f32 && a.max.y <= packed.image.height as f32);
+        for b in &packed.frames[i + 1..] {
+            assert!(!overlaps(a, b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+
+    // Pixels are copied into their frames
+    for (index, value) in [(0, 10), (1, 20), (2, 30)] {
+        let frame = packed.frames[index];
+        let offset = ((frame.min.y as u32 * packed.image.width + frame.min.x as u32) * 4) as usize;
+        assert_eq!(packed.image.data[offset], value);
+    }
+
+    let atlas = packed.atlas(Handle::default());
+    assert_eq!(atlas.index_of("small"), Some(2));
+    assert_eq!(atlas.index_of("missing"), None);
+}
+
+#[test]
+fn test_packer_rejects_oversized_and_unsupported_images() {
+    let mut builder = TextureAtlasBuilder::new().with_max_size(32);
+    builder.add_image(solid(40, 4, 0));
+    assert!(matches!(builder.build(), Err(TextureAtlasError::Full(32))));
+
+    let mut builder = TextureAtlasBuilder::new();
+    builder.add_image(solid(4, 4, 0));
+    builder.add_image(TextureData {
+        width: 4,
+        height: 4,
+        data: vec![0; 16],
+        format: TextureFormat::R8,
+    });
+    assert!(matches!(
+        builder.build(),
+        Err(TextureAtlasError::UnsupportedFormat(1))
+    ));
+}
+
+#[test]
+fn test_grid_atlas_frames_and_uvs() {
+    let atlas = TextureAtlas::from_grid(
+        Handle::default(),
+        Vec2::new(70.0, 36.0),
+        Vec2::new(16.0, 16.0),
+        4,
+        2,
+        Vec2::splat(2.0),
+        Vec2::splat(1.0),
+    );
+
+    assert_eq!(atlas.len(), 8);
+    let frame = atlas.frames[5];
+    assert_eq!(frame.min, Vec2::new(19.0, 19.0));
+    assert_eq!(frame.max, Vec2::new(35.0, 35.0));
+    assert_eq!(atlas.frame_size(5), Some(Vec2::new(16.0, 16.0)));
+
+    let uv = atlas.uv_rect(5).unwrap();
+    assert!((uv.min.x - 19.0 / 70.0).abs() < 1e-6);
+    assert!((uv.max.y - 35.0 / 36.0).abs() < 1e-6);
+    assert!(atlas.uv_rect(8).is_none());
+}
+
+#[test]
+fn test_atlas_loader_reads_grid_and_named_frames() {
+    let queue = MaterialTextureQueue::default();
+    let loader = TextureAtlasLoader::new(queue.clone());
+    let json = br#"{
+        "texture": "sprites/hero.png",
+        "width": 128, "height": 96,
+        "grid": { "tile_width": 32, "tile_height": 32, "columns": 4, "rows": 2 },
+        "frames": [{ "name": "portrait", "x": 0, "y": 64, "w": 64, "h": 32 }]
+    }"#;
+
+    let atlas = loader
+        .load(json, Path::new("assets/sprites/hero.atlas.json"))
+        .unwrap();
+    assert_eq!(atlas.len(), 9);
+    assert_eq!(atlas.index_of("portrait"), Some(8));
+    assert_eq!(atlas.frame_size(8), Some(Vec2::new(64.0, 32.0)));
+    assert_eq!(atlas.frames[5].min, Vec2::new(32.0, 32.0));
+    assert_eq!(atlas.texture.id(), AssetId::from_path("sprites/hero.png"));
+    assert_eq!(queue.drain(), vec!["sprites/hero.png".to_string()]);
+
+    assert!(loader.load(b"{}", Path::new("broken.atlas.json")).is_err());
+}
@@ -0,0 +1,350 @@
+use luminara_asset::{AssetId, AssetLoader, AssetServer, Handle};
+use luminara_core::World;
+use luminara_math::{IVec2, Transform, Vec2, Vec3};
+use luminara_render::{
+    prepare_tilemap_batches, AutotileRule, MaterialTextureQueue, Rect, Sprite, SpriteBatcher,
+    TextureAtlas, Tile, TileCollider, TileLayer, TiledMap, TiledMapLoader, Tilemap,
+};
+use std::path::{Path, PathBuf};
+
+fn grass(index: u32) -> Option<Tile> {
+    Some(Tile::new(0, index))
+}
+
+#[test]
+fn test_tiles_are_stored_in_chunks() {
+    let mut tilemap = Tilemap::new(Vec2::splat(16.0)).with_chunk_size(8);
+    let layer = tilemap.add_layer(TileLayer::new("ground"));
+
+    tilemap.set_tile(layer, IVec2::new(0, 0), grass(1));
+    tilemap.set_tile(layer, IVec2::new(7, 7), grass(2));
+    tilemap.set_tile(layer, IVec2::new(-1, 0), grass(3));
+    tilemap.set_tile(layer, IVec2::new(20, -9), grass(4));
+
+    assert_eq!(tilemap.layers[layer].chunk_count(), 3);
+    assert_eq!(tilemap.tile(layer, IVec2::new(-1, 0)), grass(3));
+    assert_eq!(tilemap.tile(layer, IVec2::new(20, -9)), grass(4));
+    assert_eq!(tilemap.tile(layer, IVec2::new(1, 0)), None);
+    assert_eq!(tilemap.tiles(layer).count(), 4);
+
+    // Chunks are dropped once empty
+    tilemap.set_tile(layer, IVec2::new(-1, 0), None);
+    assert_eq!(tilemap.layers[layer].chunk_count(), 2);
+
+    assert_eq!(tilemap.cell_at(Vec2::new(-0.5, 33.0)), IVec2::new(-1, 2));
+    assert_eq!(tilemap.cell_center(IVec2::new(2, 1)), Vec2::new(40.0, 24.0));
+}
+
+#[test]
+fn test_autotiling_picks_tiles_from_neighbour_masks() {
+    let mut tilemap = Tilemap::new(Vec2::splat(16.0));
+    let layer = tilemap.add_layer(TileLayer::new("ground"));
+    tilemap.autotile_rules.push(
+        AutotileRule::new(1, 0, 99)
+            .with_tile(0b0010, 10)
+            .with_tile(0b1010, 11)
+            .with_tile(0b1000, 12)
+            .with_tile(0b1110, 13)
+            .with_tile(0b0001, 14),
+    );
+
+    for x in 0..3 {
+        tilemap.paint_terrain(layer, IVec2::new(x, 0), Some(1));
+    }
+    let row: Vec<u32> = (0..3)
+        .map(|x| tilemap.tile(layer, IVec2::new(x, 0)).unwrap().index)
+        .collect();
+    assert_eq!(row, vec![10, 11, 12]);
+
+    // Painting below the middle cell updates it and its neighbours
+    tilemap.paint_terrain(layer, IVec2::new(1, -1), Some(1));
+    assert_eq!(tilemap.tile(layer, IVec2::new(1, 0)).unwrap().index, 13);
+    assert_eq!(tilemap.tile(layer, IVec2::new(1, -1)).unwrap().index, 14);
+
+    tilemap.paint_terrain(layer, IVec2::new(1, -1), None);
+    assert_eq!(tilemap.tile(layer, IVec2::new(1, 0)).unwrap().index, 11);
+}
+
+#[test]
+fn test_blob_masks_only_count_corners_with_both_edges() {
+    let rule = AutotileRule::new(1, 0, 0).with_corners(true);
+    let filled = [IVec2::new(0, 1), IVec2::new(1, 1), IVec2::new(1, -1)];
+    let mask = rule.mask(IVec2::ZERO, |cell| filled.contains(&cell));
+    // N and NE; SE is ignored because neither S nor E is set
+    assert_eq!(mask, 0b0000_0001);
+
+    let filled = [IVec2::new(0, 1), IVec2::new(1, 0), IVec2::new(1, 1)];
+    let mask = rule.mask(IVec2::ZERO, |cell| filled.contains(&cell));
+    assert_eq!(mask, 0b0000_0111);
+}
+
+#[test]
+fn test_collision_rects_merge_full_tiles() {
+    let mut tilemap = Tilemap::new(Vec2::new(16.0, 8.0));
+    let ground = tilemap.add_layer(TileLayer::new("ground"));
+    let decor = tilemap.add_layer(TileLayer::new("decor").with_collision(false));
+    tilemap.set_tile_collider(0, 1, Some(TileCollider::Full));
+    tilemap.set_tile_collider(
+        0,
+        2,
+        Some(TileCollider::Rect(Rect::new(
+            Vec2::ZERO,
+            Vec2::new(1.0, 0.5),
+        ))),
+    );
+
+    // A 3x2 block, a lone cell and a half-height slab
+    for x in 0..3 {
+        for y in 0..2 {
+            tilemap.set_tile(ground, IVec2::new(x, y), grass(1));
+        }
+    }
+    tilemap.set_tile(ground, IVec2::new(5, 0), grass(1));
+    tilemap.set_tile(ground, IVec2::new(7, 3), grass(2));
+    tilemap.set_tile(decor, IVec2::new(10, 10), grass(1));
+
+    let revision = tilemap.collision_revision();
+    let mut rects = tilemap.collision_rects();
+    rects.sort_by(|a, b| a.min.x.total_cmp(&b.min.x));
+    assert_eq!(
+        rects,
+        vec![
+            Rect::new(Vec2::ZERO, Vec2::new(48.0, 16.0)),
+            Rect::new(Vec2::new(80.0, 0.0), Vec2::new(96.0, 8.0)),
+            Rect::new(Vec2::new(112.0, 24.0), Vec2::new(128.0, 28.0)),
+        ]
+    );
+
+    tilemap.set_tile(ground, IVec2::new(5, 0), None);
+    assert_ne!(tilemap.collision_revision(), revision);
+    assert_eq!(tilemap.collision_rects().len(), 2);
+}
+
+#[test]
+fn test_tile_layers_are_batched_below_sprites() {
+    let mut world = World::new();
+    let assets = AssetServer::new("assets");
+    let tiles_texture = Handle::new(AssetId::from_path("tiles.png"), 0);
+    let atlas = assets.add(TextureAtlas::from_grid(
+        tiles_texture.clone(),
+        Vec2::splat(32.0),
+        Vec2::splat(16.0),
+        2,
+        2,
+        Vec2::ZERO,
+        Vec2::ZERO,
+    ));
+    world.insert_resource(assets);
+
+    let sprite = Sprite::new(Handle::new(AssetId::from_path("hero.png"), 0));
+    let mut batcher = SpriteBatcher::new(100);
+    batcher.prepare([(&sprite, &Transform::IDENTITY.compute_matrix(), None)]);
+    world.insert_resource(batcher);
+
+    let mut tilemap = Tilemap::new(Vec2::splat(16.0));
+    tilemap.add_tileset(atlas);
+    let ground = tilemap.add_layer(TileLayer::new("ground"));
+    let hidden = tilemap.add_layer(TileLayer::new("hidden"));
+    tilemap.layers[hidden].visible = false;
+    tilemap.set_tile(
+        ground,
+        IVec2::new(2, 1),
+        Some(Tile::new(0, 3).with_flip(true, false)),
+    );
+    tilemap.set_tile(hidden, IVec2::new(0, 0), grass(0));
+
+    let entity = world.spawn();
+    world.add_component(entity, tilemap).unwrap();
+    world
+        .add_component(
+            entity,
+            Transform::from_translation(Vec3::new(100.0, 0.0, 0.0)),
+        )
+        .unwrap();
+
+    prepare_tilemap_batches(&mut world);
+
+    let batcher = world.get_resource::<SpriteBatcher>().unwrap();
+    assert_eq!(batcher.batch_count(), 2);
+    let tiles = &batcher.batches[0];
+    assert_eq!(tiles.texture.id(), tiles_texture.id());
+    assert_eq!(tiles.z, -1.0);
+    assert_eq!(tiles.instances.len(), 1);
+    let instance = tiles.instances[0];
+    assert_eq!(&instance.transform[3][..3], &[140.0, 24.0, 0.0]);
+    // Flipped horizontally: the u coordinates are swapped
+    assert_eq!(instance.uv_rect, [1.0, 0.5, 0.5, 1.0]);
+}
+
+fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("luminara_tiled_{}_{}", name, std::process::id()));
+    for (file, contents) in files {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+fn load_map(dir: &Path, file: &str, queue: &MaterialTextureQueue) -> TiledMap {
+    let loader = TiledMapLoader::new(dir, queue.clone());
+    let path = dir.join(file);
+    loader.load(&std::fs::read(&path).unwrap(), &path).unwrap()
+}
+
+const TSX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="terrain" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+ <image source="../textures/terrain.png" width="32" height="32"/>
+ <tile id="1">
+  <objectgroup>
+   <object id="1" x="0" y="0" width="16" height="16"/>
+  </objectgroup>
+ </tile>
+ <tile id="2">
+  <objectgroup>
+   <object id="1" x="0" y="8" width="16" height="8"/>
+  </objectgroup>
+ </tile>
+</tileset>
+"#;
+
+const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <!-- tilesets -->
+ <tileset firstgid="1" source="../tilesets/terrain.tsx"/>
+ <layer id="1" name="ground" width="3" height="2">
+  <data encoding="csv">
+2,0,3,
+2,2,2147483650
+</data>
+ </layer>
+ <group id="3" name="details" opacity="0.5">
+  <layer id="2" name="grass" width="3" height="2" visible="0">
+   <properties>
+    <property name="collision" type="bool" value="false"/>
+   </properties>
+   <data>
+    <tile gid="1"/><tile/><tile/>
+    <tile/><tile/><tile gid="4"/>
+   </data>
+  </layer>
+ </group>
+</map>
+"#;
+
+#[test]
+fn test_tmx_map_with_external_tileset() {
+    let dir = write_files(
+        "tmx",
+        &[("maps/level.tmx", TMX), ("tilesets/terrain.tsx", TSX)],
+    );
+    let queue = MaterialTextureQueue::default();
+    let map = load_map(&dir, "maps/level.tmx", &queue);
+
+    assert_eq!((map.width, map.height), (3, 2));
+    assert_eq!(map.tilesets.len(), 1);
+    let tileset = &map.tilesets[0];
+    assert_eq!(tileset.image, "textures/terrain.png");
+    assert_eq!(
+        tileset.texture.id(),
+        AssetId::from_path("textures/terrain.png")
+    );
+    assert_eq!(tileset.colliders.len(), 2);
+    assert_eq!(queue.drain(), vec!["textures/terrain.png".to_string()]);
+
+    assert_eq!(map.layers.len(), 2);
+    assert_eq!(
+        map.layers[0].chunks[0].gids,
+        vec![2, 0, 3, 2, 2, 0x8000_0002]
+    );
+    let grass = &map.layers[1];
+    assert_eq!(grass.name, "grass");
+    assert!(!grass.visible);
+    assert!(!grass.collision);
+    assert!((grass.opacity - 0.5).abs() < 1e-6);
+    assert_eq!(grass.chunks[0].gids, vec![1, 0, 0, 0, 0, 4]);
+
+    let assets = AssetServer::new(&dir);
+    let tilemap = map.to_tilemap(&assets);
+    assert_eq!(tilemap.tile_size, Vec2::splat(16.0));
+    assert_eq!(tilemap.tilesets.len(), 1);
+    // The top row of the map is the upper cell row
+    assert_eq!(tilemap.tile(0, IVec2::new(0, 1)), Some(Tile::new(0, 1)));
+    assert_eq!(tilemap.tile(0, IVec2::new(2, 1)), Some(Tile::new(0, 2)));
+    assert_eq!(
+        tilemap.tile(0, IVec2::new(2, 0)),
+        Some(Tile::new(0, 1).with_flip(true, false))
+    );
+    assert_eq!(tilemap.tile_collider(0, 1), Some(TileCollider::Full));
+    assert_eq!(
+        tilemap.tile_collider(0, 2),
+        Some(TileCollider::Rect(Rect::new(
+            Vec2::ZERO,
+            Vec2::new(1.0, 0.5)
+        )))
+    );
+    assert!(!tilemap.layers[1].collision);
+
+    // Row 0 and the left cell of row 1 are solid; the half tile adds a slab
+    let rects = tilemap.collision_rects();
+    assert_eq!(rects.len(), 3);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_tmj_map_with_base64_chunks() {
+    // Little-endian gids 1, 0, 0, 2 as base64
+    let tmj = r#"{
+        "width": 4, "height": 4, "tilewidth": 8, "tileheight": 8,
+        "orientation": "orthogonal", "infinite": true,
+        "tilesets": [{
+            "firstgid": 1, "name": "cave", "image": "cave.png",
+            "imagewidth": 16, "imageheight": 8, "tilewidth": 8, "tileheight": 8,
+            "columns": 2, "tilecount": 2
+        }],
+        "layers": [{
+            "type": "tilelayer", "name": "walls", "encoding": "base64",
+            "chunks": [{ "x": -2, "y": 0, "width": 2, "height": 2, "data": "AQAAAAAAAAAAAAAAAgAAAA==" }],
+            "properties": [{ "name": "collision", "type": "bool", "value": false }]
+        }, {
+            "type": "objectgroup", "name": "spawns", "objects": []
+        }]
+    }"#;
+    let dir = write_files("tmj", &[("cave.tmj", tmj)]);
+    let queue = MaterialTextureQueue::default();
+    let map = load_map(&dir, "cave.tmj", &queue);
+
+    assert_eq!(map.layers.len(), 1);
+    let chunk = &map.layers[0].chunks[0];
+    assert_eq!((chunk.x, chunk.y), (-2, 0));
+    assert_eq!(chunk.gids, vec![1, 0, 0, 2]);
+    assert!(!map.layers[0].collision);
+    assert_eq!(queue.drain(), vec!["cave.png".to_string()]);
+
+    let tilemap = map.to_tilemap(&AssetServer::new(&dir));
+    assert_eq!(tilemap.tile(0, IVec2::new(-2, 3)), Some(Tile::new(0, 0)));
+    assert_eq!(tilemap.tile(0, IVec2::new(-1, 2)), Some(Tile::new(0, 1)));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_unsupported_maps_are_rejected() {
+    let loader = TiledMapLoader::new("assets", MaterialTextureQueue::default());
+    let isometric =
+        br#"<map orientation="isometric" width="1" height="1" tilewidth="8" tileheight="8"></map>"#;
+    assert!(loader.load(isometric, Path::new("assets/iso.tmx")).is_err());
+
+    let compressed = br#"{
+        "width": 1, "height": 1, "tilewidth": 8, "tileheight": 8,
+        "layers": [{ "type": "tilelayer", "width": 1, "height": 1,
+            "encoding": "base64", "compression": "zlib", "data": "eJxjZGAAAAAGAAI=" }]
+    }"#;
+    assert!(loader
+        .load(compressed, Path::new("assets/zip.tmj"))
+        .is_err());
+    assert!(loader
+        .load(b"<map", Path::new("assets/broken.tmx"))
+        .is_err());
+}
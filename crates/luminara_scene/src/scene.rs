@@ -137,6 +137,12 @@ impl luminara_core::Component for Tag {
     }
 }
 
+impl luminara_asset::Asset for Scene {
+    fn type_name() -> &'static str {
+        "Scene"
+    }
+}
+
 impl Scene {
     pub fn load_from_file(path: &Path) -> Result<Self, SceneError> {
         crate::serialization::load_from_file(path)
@@ -323,6 +329,16 @@ impl Scene {
     }
 
     pub fn spawn_into(&self, world: &mut World) -> Vec<Entity> {
+        self.spawn_with_id_map(world).0
+    }
+
+    /// Spawn the scene and return the entity spawned for each [`EntityData::id`],
+    /// so references between entities (e.g. skin joints) can be resolved
+    pub fn spawn_into_with_ids(&self, world: &mut World) -> HashMap<u64, Entity> {
+        self.spawn_with_id_map(world).1
+    }
+
+    fn spawn_with_id_map(&self, world: &mut World) -> (Vec<Entity>, HashMap<u64, Entity>) {
         // Attempt to extract TypeRegistry from world to use it for deserialization
         let registry = world.remove_resource::<TypeRegistry>();
 
@@ -345,7 +361,7 @@ impl Scene {
             world.insert_resource(reg);
         }
 
-        spawned_entities
+        (spawned_entities, id_map)
     }
 
     pub(crate) fn spawn_entity_recursive(
@@ -87,8 +87,8 @@ impl ApplicationHandler for LuminaraWinitHandler {
                     if let Some(mut window) = self.app.world.get_resource_mut::<window::Window>() {
                         window.resize(size.width, size.height);
                     }
-                    // Run a full update cycle so window_resize_system picks up
-                    // the new stored size, reconfigures the GPU surface, and
+                    // Run a full update cycle so the new size is extracted,
+                    // resize_surface_system reconfigures the GPU surface, and
                     // render_system draws a frame at the correct dimensions.
                     self.app.update();
                     // (2) Request redraw immediately so the compositor gets a fresh
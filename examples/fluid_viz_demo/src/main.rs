@@ -122,6 +122,7 @@ fn setup_fluid_scene(world: &mut World) {
             },
             clear_color: Color::rgb(0.05, 0.05, 0.1),
             is_active: true,
+            ..Default::default()
         },
     );
     world.add_component(camera, Camera3d);
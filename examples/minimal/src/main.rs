@@ -26,6 +26,7 @@ fn setup(world: &mut World) {
             },
             clear_color: Color::BLACK,
             is_active: true,
+            ..Default::default()
         },
     );
     world.add_component(cam, Camera3d);
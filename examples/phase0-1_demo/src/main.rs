@@ -339,6 +339,7 @@ fn setup_scene(world: &mut World) {
                     },
                     clear_color: Color::rgba(0.02, 0.03, 0.06, 1.0),
                     is_active: true,
+                    ..Default::default()
                 },
             );
             world.add_component(camera, Camera3d);
@@ -269,6 +269,7 @@ fn create_fallback_scene(world: &mut World, asset_server: &AssetServer) {
             },
             clear_color: Color::rgb(0.1, 0.1, 0.15),
             is_active: true,
+            ..Default::default()
         },
     );
     world.add_component(camera, Camera3d);
@@ -282,6 +282,7 @@ fn test_rendering_pipeline_initializes() {
             },
             clear_color: Color::rgb(0.1, 0.1, 0.15),
             is_active: true,
+            ..Default::default()
         },
     );
     world.add_component(camera, Camera3d);
@@ -476,6 +477,7 @@ fn test_demo_runs_multiple_frames() {
             },
             clear_color: Color::rgb(0.1, 0.1, 0.15),
             is_active: true,
+            ..Default::default()
         },
     );
 